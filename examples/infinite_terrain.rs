@@ -0,0 +1,332 @@
+//! Streaming "infinite" terrain painted with triplanar materials.
+//!
+//! Chunks are generated around a camera flying over procedural hills:
+//! - Density comes from a height function, sampled per chunk
+//! - `MaterialGeneration` fills each new chunk's materials in the
+//!   background from height layers plus a steepness rule for cliffs
+//! - `auto_remesh` blends material attributes into freshly meshed chunks,
+//!   with `RemeshScheduling` budgeting them nearest-first, and chunks that
+//!   fall out of range are despawned
+//! - `PaletteStreaming` keeps the palette array at its small mips until a
+//!   visible chunk uses one of its layers
+//!
+//! Run with: `cargo run --example infinite_terrain`
+
+use bevy::asset::RenderAssetUsages;
+use bevy::pbr::ExtendedMaterial;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_painter::material_field::{
+    FIELD_SIZE, MaterialGeneration, OnChunkMaterialReady, RemeshScheduling,
+};
+use bevy_painter::palette::{PaletteBuilder, PaletteMaterial, TexturePalette};
+use bevy_painter::prelude::*;
+use bevy_sculpter::field::Field;
+use bevy_sculpter::prelude::*;
+use chunky_bevy::prelude::*;
+
+/// World units per chunk; one unit per voxel.
+const CHUNK_SIZE: f32 = FIELD_SIZE.x as f32;
+
+/// Chunks are kept within this many chunks of the camera.
+const VIEW_RADIUS: i32 = 5;
+
+const SAND: u8 = 0;
+const GRASS: u8 = 1;
+const ROCK: u8 = 2;
+const SNOW: u8 = 3;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(ChunkyPlugin::default())
+        .add_plugins(SurfaceNetsPlugin)
+        .add_plugins(TriplanarVoxelPlugin {
+            auto_remesh: true,
+            ..default()
+        })
+        .insert_resource(DensityFieldMeshSize(Vec3::splat(CHUNK_SIZE)))
+        .insert_resource(MaterialGeneration::new(chunk_material).with_max_spawns_per_frame(4))
+        .insert_resource(RemeshScheduling::default().with_max_chunks_per_frame(4))
+        .insert_resource(PaletteStreaming {
+            enabled: true,
+            resident_mip: 3,
+        })
+        .init_resource::<LoadedChunks>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, (fly_camera, stream_chunks).chain())
+        .add_observer(apply_triplanar_material)
+        .run();
+}
+
+/// Chunk entities by column position.
+#[derive(Resource, Default)]
+struct LoadedChunks(HashMap<IVec2, Entity>);
+
+#[derive(Resource)]
+struct TerrainMaterial(Handle<TriplanarVoxelMaterial>);
+
+/// Keeps the palette asset alive so streaming keeps managing its array.
+#[derive(Resource)]
+struct TerrainPalette(#[allow(dead_code)] Handle<TexturePalette>);
+
+#[derive(Component)]
+struct FlyCamera {
+    speed: f32,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut palettes: ResMut<Assets<TexturePalette>>,
+    mut materials: ResMut<Assets<TriplanarVoxelMaterial>>,
+) {
+    let albedo = create_palette(&mut images);
+    // Streaming only manages arrays that belong to a palette asset
+    let palette = PaletteBuilder::new()
+        .with_albedo(albedo.clone())
+        .add_material(PaletteMaterial::new("sand"))
+        .add_material(PaletteMaterial::new("grass"))
+        .add_material(PaletteMaterial::new("rock"))
+        .add_material(PaletteMaterial::new("snow"))
+        .build();
+    commands.insert_resource(TerrainPalette(palettes.add(palette)));
+    let material = materials.add(ExtendedMaterial {
+        base: StandardMaterial {
+            perceptual_roughness: 0.9,
+            ..default()
+        },
+        extension: TriplanarExtension::new(albedo)
+            .with_texture_scale(0.25)
+            .with_materials(4),
+    });
+    commands.insert_resource(TerrainMaterial(material));
+
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 40.0, 0.0).looking_to(Vec3::new(1.0, -0.35, 0.3), Vec3::Y),
+        FlyCamera { speed: 12.0 },
+        DistanceFog {
+            color: Color::srgb(0.7, 0.8, 0.9),
+            falloff: FogFalloff::Linear {
+                start: CHUNK_SIZE * (VIEW_RADIUS as f32 - 2.0),
+                end: CHUNK_SIZE * VIEW_RADIUS as f32,
+            },
+            ..default()
+        },
+    ));
+    commands.insert_resource(ClearColor(Color::srgb(0.7, 0.8, 0.9)));
+
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 10000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(10.0, 20.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 300.0,
+        ..default()
+    });
+}
+
+/// Terrain surface height at a world XZ position.
+fn height(x: f32, z: f32) -> f32 {
+    14.0 + 7.0 * (x * 0.045).sin() * (z * 0.035).cos() + 3.0 * (x * 0.13 + z * 0.11).sin()
+}
+
+/// Steepness of the surface at a world XZ position; 0 is flat.
+fn steepness(x: f32, z: f32) -> f32 {
+    let e = 0.5;
+    let dx = (height(x + e, z) - height(x - e, z)) / (2.0 * e);
+    let dz = (height(x, z + e) - height(x, z - e)) / (2.0 * e);
+    Vec2::new(dx, dz).length()
+}
+
+/// Generates the density field of the chunk at `column`.
+fn chunk_density(column: IVec2) -> DensityField {
+    let origin = column.as_vec2() * CHUNK_SIZE;
+    let mut density = DensityField::new();
+    for z in 0..FIELD_SIZE.z {
+        for x in 0..FIELD_SIZE.x {
+            let surface = height(origin.x + x as f32, origin.y + z as f32);
+            for y in 0..FIELD_SIZE.y {
+                // Negative below the surface, clamped to keep gradients smooth
+                density.set(x, y, z, (y as f32 - surface).clamp(-1.0, 1.0));
+            }
+        }
+    }
+    density
+}
+
+/// [`MaterialGeneration`] generator: rock on steep slopes, height layers
+/// elsewhere.
+fn chunk_material(chunk_pos: IVec3, local: UVec3) -> u8 {
+    let x = chunk_pos.x as f32 * CHUNK_SIZE + local.x as f32;
+    let z = chunk_pos.z as f32 * CHUNK_SIZE + local.z as f32;
+    if steepness(x, z) > 0.9 {
+        return ROCK;
+    }
+    match local.y {
+        0..10 => SAND,
+        10..19 => GRASS,
+        _ => SNOW,
+    }
+}
+
+fn fly_camera(time: Res<Time>, mut cameras: Query<(&mut Transform, &FlyCamera)>) {
+    for (mut transform, camera) in &mut cameras {
+        let forward = Vec3::new(1.0, 0.0, 0.3).normalize();
+        transform.translation += forward * camera.speed * time.delta_secs();
+        let ground = height(transform.translation.x, transform.translation.z);
+        transform.translation.y = transform.translation.y.lerp(ground + 24.0, 0.02);
+    }
+}
+
+/// Spawns missing chunks around the camera and despawns chunks out of
+/// range.
+///
+/// Chunks spawn without a `MaterialField`; `MaterialGeneration` fills it in
+/// and the plugin remeshes them once it lands.
+fn stream_chunks(
+    mut commands: Commands,
+    mut loaded: ResMut<LoadedChunks>,
+    cameras: Query<&Transform, With<FlyCamera>>,
+) {
+    let Ok(camera) = cameras.single() else {
+        return;
+    };
+    let center = (camera.translation.xz() / CHUNK_SIZE).floor().as_ivec2();
+
+    loaded.0.retain(|column, entity| {
+        let keep = (*column - center).abs().max_element() <= VIEW_RADIUS + 1;
+        if !keep {
+            commands.entity(*entity).despawn();
+        }
+        keep
+    });
+
+    for z in -VIEW_RADIUS..=VIEW_RADIUS {
+        for x in -VIEW_RADIUS..=VIEW_RADIUS {
+            let column = center + IVec2::new(x, z);
+            if loaded.0.contains_key(&column) {
+                continue;
+            }
+            let entity = commands
+                .spawn((
+                    Chunk,
+                    ChunkPos(IVec3::new(column.x, 0, column.y)),
+                    chunk_density(column),
+                    DensityFieldDirty,
+                    Transform::from_xyz(
+                        column.x as f32 * CHUNK_SIZE,
+                        0.0,
+                        column.y as f32 * CHUNK_SIZE,
+                    ),
+                ))
+                .id();
+            loaded.0.insert(column, entity);
+        }
+    }
+}
+
+/// Swaps in the triplanar material once the plugin has blended a chunk's
+/// material attributes, and records which palette layers it uses for
+/// streaming.
+fn apply_triplanar_material(
+    ready: On<OnChunkMaterialReady>,
+    mut commands: Commands,
+    chunks: Query<&Mesh3d>,
+    meshes: Res<Assets<Mesh>>,
+    material: Res<TerrainMaterial>,
+) {
+    let Some(mesh) = chunks
+        .get(ready.entity)
+        .ok()
+        .and_then(|mesh| meshes.get(&mesh.0))
+    else {
+        return;
+    };
+    commands
+        .entity(ready.entity)
+        .remove::<MeshMaterial3d<StandardMaterial>>()
+        .insert((
+            MeshMaterial3d(material.0.clone()),
+            MaterialUsage::from_mesh(mesh),
+        ));
+}
+
+/// A 4-layer palette with a full mip chain, so streaming has small mips to
+/// keep resident.
+fn create_palette(images: &mut Assets<Image>) -> Handle<Image> {
+    let size = 64u32;
+    let mip_count = size.ilog2() + 1;
+    let colors: [([u8; 3], [u8; 3]); 4] = [
+        ([220, 200, 140], [200, 180, 120]), // Sand
+        ([70, 140, 50], [50, 110, 40]),     // Grass
+        ([120, 115, 110], [90, 85, 80]),    // Rock
+        ([240, 245, 250], [215, 225, 235]), // Snow
+    ];
+
+    let mut data = Vec::new();
+    for (light, dark) in colors {
+        // Layer-major: every mip of one layer before the next layer
+        let mut mip: Vec<[u8; 3]> = (0..size * size)
+            .map(|i| {
+                let (x, y) = (i % size, i / size);
+                if (x / 8 + y / 8) % 2 == 0 {
+                    light
+                } else {
+                    dark
+                }
+            })
+            .collect();
+        let mut mip_size = size;
+        loop {
+            for texel in &mip {
+                data.extend_from_slice(&[texel[0], texel[1], texel[2], 255]);
+            }
+            if mip_size == 1 {
+                break;
+            }
+            mip = downsample(&mip, mip_size);
+            mip_size /= 2;
+        }
+    }
+
+    // `Image::new` only accepts mip 0
+    let mut image = Image::new_uninit(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: colors.len() as u32,
+        },
+        TextureDimension::D2,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.mip_level_count = mip_count;
+    image.data = Some(data);
+    images.add(image)
+}
+
+/// Box-filters a square mip to half size.
+fn downsample(mip: &[[u8; 3]], size: u32) -> Vec<[u8; 3]> {
+    let half = size / 2;
+    (0..half * half)
+        .map(|i| {
+            let (x, y) = (i % half * 2, i / half * 2);
+            let mut sum = [0u32; 3];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let texel = mip[((y + dy) * size + x + dx) as usize];
+                for c in 0..3 {
+                    sum[c] += texel[c] as u32;
+                }
+            }
+            sum.map(|c| (c / 4) as u8)
+        })
+        .collect()
+}
@@ -11,7 +11,7 @@ use bevy::pbr::ExtendedMaterial;
 use bevy::prelude::*;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use bevy_painter::material_field::{
-    MaterialBlendSettings, MaterialField, NeighborMaterialFields, compute_vertex_materials,
+    MaterialBlendSettings, MaterialField, NeighborMaterialFields, VertexMaterialComputer,
 };
 use bevy_painter::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS};
 use bevy_painter::prelude::*;
@@ -23,7 +23,7 @@ fn main() {
         .add_plugins(DefaultPlugins)
         .add_plugins(ChunkyPlugin::default())
         .add_plugins(SurfaceNetsPlugin)
-        .add_plugins(TriplanarVoxelPlugin)
+        .add_plugins(TriplanarVoxelPlugin::default())
         .insert_resource(DensityFieldMeshSize(Vec3::splat(10.0)))
         .init_resource::<MaterialBlendSettings>()
         .add_systems(Startup, setup)
@@ -230,25 +230,10 @@ fn apply_triplanar_materials(
         let normals = normals.clone();
 
         // Compute material data for each vertex
-        let mut material_ids: Vec<u32> = Vec::with_capacity(positions.len());
-        let mut material_weights: Vec<u32> = Vec::with_capacity(positions.len());
-
-        for pos in positions.iter() {
-            let world_pos = Vec3::from_array(*pos);
-
-            let vertex_data = compute_vertex_materials(
-                world_pos,
-                mesh_size.0,
-                density,
-                materials,
-                neighbor_density,
-                neighbor_materials,
-                &blend_settings,
-            );
-
-            material_ids.push(vertex_data.pack_ids());
-            material_weights.push(vertex_data.pack_weights());
-        }
+        let (material_ids, material_weights) =
+            VertexMaterialComputer::new(density, materials, mesh_size.0, &blend_settings)
+                .with_neighbors(neighbor_density, neighbor_materials)
+                .compute_packed(&positions);
 
         // Create a new mesh with all required attributes
         let mut new_mesh = Mesh::new(
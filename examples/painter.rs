@@ -22,7 +22,7 @@ use bevy::{
 use bevy_painter::{
     material_field::{
         MaterialBlendSettings, MaterialField, MaterialSlice, MaterialSliceExt,
-        NeighborMaterialFields, compute_vertex_materials,
+        NeighborMaterialFields, VertexMaterialComputer, brush,
     },
     mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS},
     prelude::*,
@@ -35,7 +35,7 @@ fn main() {
         .add_plugins(DefaultPlugins)
         .add_plugins(ChunkyPlugin::default())
         .add_plugins(SurfaceNetsPlugin)
-        .add_plugins(TriplanarVoxelPlugin)
+        .add_plugins(TriplanarVoxelPlugin::default())
         .insert_resource(DensityFieldMeshSize(Vec3::splat(10.0)))
         .init_resource::<MaterialBlendSettings>()
         .init_resource::<PaintBrush>()
@@ -374,8 +374,10 @@ fn paint_materials(
     mesh_size: Res<DensityFieldMeshSize>,
     brush: Res<PaintBrush>,
     chunk_manager: Res<ChunkManager>,
+    mut last_hit: Local<Option<Vec3>>,
 ) {
     if !mouse_buttons.pressed(MouseButton::Left) {
+        *last_hit = None;
         return;
     }
 
@@ -386,6 +388,9 @@ fn paint_materials(
 
     let Some(hit_point) = raycast_terrain(&chunks, &mesh_size, ray) else { return };
 
+    // Sweep from last frame's hit so fast strokes don't leave gaps
+    let stroke_start = last_hit.replace(hit_point).unwrap_or(hit_point);
+
     let chunk_world_size = mesh_size.0;
     let world_brush_radius = brush.radius;
 
@@ -399,11 +404,12 @@ fn paint_materials(
 
         let scale = Vec3::splat(32.0) / chunk_world_size;
         let grid_center = local_hit * scale;
+        let grid_start = (stroke_start - chunk_world_origin) * scale;
         let grid_radius = world_brush_radius * scale.x;
 
         // AABB check
-        let brush_min = grid_center - Vec3::splat(grid_radius);
-        let brush_max = grid_center + Vec3::splat(grid_radius);
+        let brush_min = grid_start.min(grid_center) - Vec3::splat(grid_radius);
+        let brush_max = grid_start.max(grid_center) + Vec3::splat(grid_radius);
 
         if brush_max.x < 0.0 || brush_min.x > 32.0
             || brush_max.y < 0.0 || brush_min.y > 32.0
@@ -412,23 +418,15 @@ fn paint_materials(
             continue;
         }
 
-        // Paint sphere
-        let grid_radius_sq = grid_radius * grid_radius;
-        let min = brush_min.max(Vec3::ZERO).as_ivec3();
-        let max = brush_max.min(Vec3::splat(31.0)).as_ivec3();
-
-        let mut painted = false;
-        for z in min.z..=max.z {
-            for y in min.y..=max.y {
-                for x in min.x..=max.x {
-                    let pos = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5);
-                    if pos.distance_squared(grid_center) <= grid_radius_sq {
-                        material_field.set(x as u32, y as u32, z as u32, brush.current_material);
-                        painted = true;
-                    }
-                }
-            }
-        }
+        // Paint a capsule between voxel centers
+        let painted = brush::paint_capsule(
+            &mut *material_field,
+            grid_start - 0.5,
+            grid_center - 0.5,
+            grid_radius,
+            brush.current_material,
+            None,
+        );
 
         if painted {
             commands.entity(entity).insert(MaterialMeshDirty);
@@ -564,23 +562,10 @@ fn rebuild_material_meshes(
         let normals = normals.clone();
 
         // Compute material data
-        let mut material_ids: Vec<u32> = Vec::with_capacity(positions.len());
-        let mut material_weights: Vec<u32> = Vec::with_capacity(positions.len());
-
-        for pos in positions.iter() {
-            let world_pos = Vec3::from_array(*pos);
-            let vertex_data = compute_vertex_materials(
-                world_pos,
-                mesh_size.0,
-                density,
-                materials,
-                neighbor_density,
-                neighbor_materials,
-                &blend_settings,
-            );
-            material_ids.push(vertex_data.pack_ids());
-            material_weights.push(vertex_data.pack_weights());
-        }
+        let (material_ids, material_weights) =
+            VertexMaterialComputer::new(density, materials, mesh_size.0, &blend_settings)
+                .with_neighbors(neighbor_density, neighbor_materials)
+                .compute_packed(&positions);
 
         // Create new mesh
         let mut new_mesh = Mesh::new(
@@ -11,7 +11,7 @@ use bevy_painter::prelude::*;
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugins(TriplanarVoxelPlugin)
+        .add_plugins(TriplanarVoxelPlugin::default())
         .add_systems(Startup, setup)
         .add_systems(Update, rotate_camera)
         .run();
@@ -0,0 +1,334 @@
+//! Two players painting the same terrain in split screen.
+//!
+//! Each half of the window is its own camera orbiting a different corner
+//! of the terrain:
+//! - Left click paints under the cursor in whichever half it is in, through
+//!   that half's camera with `CameraVoxelRaycast`
+//! - Each player paints their own material
+//! - `RemeshScheduling` remeshes the chunks nearest either camera first, so
+//!   both players see their strokes land at the same rate
+//!
+//! Run with: `cargo run --example split_screen`
+
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::Viewport;
+use bevy::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::pbr::ExtendedMaterial;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::{PrimaryWindow, WindowResized};
+use bevy_painter::material_field::{
+    CameraVoxelRaycast, FIELD_SIZE, MaterialBlendSettings, MaterialField, NeighborMaterialFields,
+    RemeshScheduling, VertexMaterialComputer,
+};
+use bevy_painter::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS};
+use bevy_painter::prelude::*;
+use bevy_sculpter::field::Field;
+use bevy_sculpter::prelude::*;
+use chunky_bevy::prelude::*;
+
+/// World units per chunk; one unit per voxel.
+const CHUNK_SIZE: f32 = FIELD_SIZE.x as f32;
+
+/// Chunks along each horizontal axis.
+const GRID: i32 = 3;
+
+const GROUND: u8 = 0;
+
+/// Material each player paints, by player index.
+const PLAYER_MATERIALS: [u8; 2] = [1, 2];
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(ChunkyPlugin::default())
+        .add_plugins(SurfaceNetsPlugin)
+        .add_plugins(TriplanarVoxelPlugin {
+            auto_remesh: true,
+            ..default()
+        })
+        .insert_resource(DensityFieldMeshSize(Vec3::splat(CHUNK_SIZE)))
+        .insert_resource(RemeshScheduling::default().with_max_chunks_per_frame(2))
+        .add_systems(Startup, setup)
+        .add_systems(
+            Update,
+            (set_camera_viewports, orbit_cameras, paint_under_cursor),
+        )
+        .add_systems(PostUpdate, apply_triplanar_materials)
+        .run();
+}
+
+#[derive(Resource)]
+struct TerrainMaterial(Handle<TriplanarVoxelMaterial>);
+
+/// Chunks whose fresh surface-nets mesh still needs material attributes.
+#[derive(Component)]
+struct PendingTriplanarMaterial;
+
+/// One player's half of the window.
+#[derive(Component)]
+struct PlayerView {
+    index: usize,
+    /// Point the camera orbits around.
+    focus: Vec3,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<TriplanarVoxelMaterial>>,
+) {
+    let albedo = create_palette(&mut images);
+    let material = materials.add(ExtendedMaterial {
+        base: StandardMaterial {
+            perceptual_roughness: 0.9,
+            ..default()
+        },
+        extension: TriplanarExtension::new(albedo)
+            .with_texture_scale(0.25)
+            .with_materials(3),
+    });
+    commands.insert_resource(TerrainMaterial(material));
+
+    for z in 0..GRID {
+        for x in 0..GRID {
+            let (density, materials) = generate_chunk(IVec2::new(x, z));
+            commands.spawn((
+                Chunk,
+                ChunkPos(IVec3::new(x, 0, z)),
+                density,
+                materials,
+                DensityFieldDirty,
+                PendingTriplanarMaterial,
+                Transform::from_xyz(x as f32 * CHUNK_SIZE, 0.0, z as f32 * CHUNK_SIZE),
+            ));
+        }
+    }
+
+    // Each player watches a different corner of the terrain
+    let extent = GRID as f32 * CHUNK_SIZE;
+    let focuses = [
+        Vec3::new(extent * 0.25, 12.0, extent * 0.25),
+        Vec3::new(extent * 0.75, 12.0, extent * 0.75),
+    ];
+    for (index, focus) in focuses.into_iter().enumerate() {
+        let camera = commands
+            .spawn((
+                Camera3d::default(),
+                Camera {
+                    order: index as isize,
+                    ..default()
+                },
+                Transform::from_translation(focus + Vec3::new(0.0, 25.0, 30.0))
+                    .looking_at(focus, Vec3::Y),
+                PlayerView { index, focus },
+            ))
+            .id();
+
+        commands.spawn((
+            Text::new(format!(
+                "Player {}\nLeft click to paint material {}",
+                index + 1,
+                PLAYER_MATERIALS[index]
+            )),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            UiTargetCamera(camera),
+        ));
+    }
+
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 10000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(10.0, 20.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 300.0,
+        ..default()
+    });
+}
+
+/// Terrain surface height at a world XZ position.
+fn height(x: f32, z: f32) -> f32 {
+    12.0 + 4.0 * (x * 0.08).sin() * (z * 0.06).cos()
+}
+
+/// Generates the density and material fields of the chunk at `column`.
+fn generate_chunk(column: IVec2) -> (DensityField, MaterialField) {
+    let origin = column.as_vec2() * CHUNK_SIZE;
+    let mut density = DensityField::new();
+    for z in 0..FIELD_SIZE.z {
+        for x in 0..FIELD_SIZE.x {
+            let surface = height(origin.x + x as f32, origin.y + z as f32);
+            for y in 0..FIELD_SIZE.y {
+                density.set(x, y, z, (y as f32 - surface).clamp(-1.0, 1.0));
+            }
+        }
+    }
+    (density, MaterialField::filled(GROUND))
+}
+
+/// Splits the window into a left and a right half, one per player.
+fn set_camera_viewports(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut resized: MessageReader<WindowResized>,
+    mut cameras: Query<(&mut Camera, &PlayerView)>,
+    mut initialized: Local<bool>,
+) {
+    // Viewports must be set once before the first resize event
+    if resized.read().count() == 0 && *initialized {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    *initialized = true;
+    let size = window.physical_size() / UVec2::new(2, 1);
+    for (mut camera, view) in &mut cameras {
+        camera.viewport = Some(Viewport {
+            physical_position: UVec2::new(size.x * view.index as u32, 0),
+            physical_size: size,
+            ..default()
+        });
+    }
+}
+
+fn orbit_cameras(time: Res<Time>, mut cameras: Query<(&mut Transform, &PlayerView)>) {
+    for (mut transform, view) in &mut cameras {
+        // Players orbit in opposite directions
+        let direction = if view.index == 0 { 1.0 } else { -1.0 };
+        let angle = time.elapsed_secs() * 0.15 * direction;
+        let offset = Quat::from_rotation_y(angle) * Vec3::new(0.0, 25.0, 30.0);
+        *transform =
+            Transform::from_translation(view.focus + offset).looking_at(view.focus, Vec3::Y);
+    }
+}
+
+/// Paints the player's material under the cursor through the camera of
+/// the half it is in.
+fn paint_under_cursor(
+    mouse: Res<ButtonInput<MouseButton>>,
+    views: Query<(Entity, &PlayerView)>,
+    raycast: CameraVoxelRaycast,
+    mut paint: MessageWriter<PaintCommand>,
+) {
+    if !mouse.pressed(MouseButton::Left) {
+        return;
+    }
+    for (camera, view) in &views {
+        if let Some(hit) = raycast.under_cursor(camera, 200.0) {
+            paint.write(PaintCommand::new(
+                BrushShape::Sphere {
+                    center: hit.position,
+                    radius: 2.5,
+                },
+                PLAYER_MATERIALS[view.index],
+            ));
+        }
+    }
+}
+
+/// Adds material attributes to freshly meshed chunks and swaps in the
+/// triplanar material. Later repaints are remeshed by the plugin.
+fn apply_triplanar_materials(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunks: Query<
+        (
+            Entity,
+            &Mesh3d,
+            &DensityField,
+            &MaterialField,
+            Option<&NeighborDensityFields>,
+            Option<&NeighborMaterialFields>,
+        ),
+        With<PendingTriplanarMaterial>,
+    >,
+    mesh_size: Res<DensityFieldMeshSize>,
+    blend_settings: Res<MaterialBlendSettings>,
+    material: Res<TerrainMaterial>,
+) {
+    for (entity, mesh, density, materials, neighbor_density, neighbor_materials) in &chunks {
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+        )
+        else {
+            continue;
+        };
+
+        let (material_ids, material_weights) =
+            VertexMaterialComputer::new(density, materials, mesh_size.0, &blend_settings)
+                .with_neighbors(neighbor_density, neighbor_materials)
+                .compute_packed(positions);
+
+        let mut painted = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+        );
+        painted.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
+        painted.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.clone());
+        painted.insert_attribute(ATTRIBUTE_MATERIAL_IDS, material_ids);
+        painted.insert_attribute(ATTRIBUTE_MATERIAL_WEIGHTS, material_weights);
+        if let Some(indices) = mesh.indices() {
+            painted.insert_indices(Indices::U32(indices.iter().map(|i| i as u32).collect()));
+        }
+
+        commands
+            .entity(entity)
+            .remove::<(PendingTriplanarMaterial, MeshMaterial3d<StandardMaterial>)>()
+            .insert((
+                Mesh3d(meshes.add(painted)),
+                MeshMaterial3d(material.0.clone()),
+            ));
+    }
+}
+
+/// A 3-layer checker palette: ground and one material per player.
+fn create_palette(images: &mut Assets<Image>) -> Handle<Image> {
+    let size = 64u32;
+    let colors: [([u8; 3], [u8; 3]); 3] = [
+        ([120, 150, 90], [100, 130, 75]), // Ground
+        ([220, 70, 60], [180, 50, 45]),   // Player 1
+        ([60, 110, 220], [45, 85, 180]),  // Player 2
+    ];
+
+    let mut data = Vec::new();
+    for (light, dark) in colors {
+        for i in 0..size * size {
+            let (x, y) = (i % size, i / size);
+            let texel = if (x / 8 + y / 8) % 2 == 0 {
+                light
+            } else {
+                dark
+            };
+            data.extend_from_slice(&[texel[0], texel[1], texel[2], 255]);
+        }
+    }
+
+    images.add(Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: colors.len() as u32,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
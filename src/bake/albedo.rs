@@ -0,0 +1,546 @@
+//! CPU bake of blended triplanar albedo into a per-chunk 2D texture.
+//!
+//! The atlas has six regions, one per box face (+X, +Y, +Z on the top row,
+//! -X, -Y, -Z below). Each triangle is projected onto the face its normal
+//! points at most, the same way the shader picks its dominant projection.
+//! Overhangs that project onto the same face region overlap; the last
+//! triangle drawn wins.
+
+use std::ops::Range;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::math::Affine3A;
+use bevy::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use thiserror::Error;
+
+use crate::material::TriplanarExtension;
+use crate::mesh::{VertexMaterialData, material_attributes, triangles, unpack_vertex};
+use crate::palette::layer_mip_range;
+
+/// Errors that can occur when baking a chunk.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BakeError {
+    #[error("Albedo texture not loaded")]
+    AlbedoNotLoaded,
+
+    #[error("Albedo texture has no CPU-side data; keep RenderAssetUsages::MAIN_WORLD to bake")]
+    AlbedoNoCpuData,
+
+    #[error("Albedo format {found:?} can't be baked; expected uncompressed 8-bit RGBA or BGRA")]
+    UnsupportedAlbedoFormat { found: TextureFormat },
+
+    #[error("Mesh has no Float32x3 position attribute")]
+    MissingPositions,
+
+    #[error("Mesh has no triplanar material attributes")]
+    MissingMaterialData,
+}
+
+/// Settings for [`bake_albedo`].
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct BakeSettings {
+    /// Texels per side of each of the six face regions.
+    ///
+    /// Default: 128
+    pub resolution: u32,
+    /// Texels of colour bleeding around each region's triangles, so bilinear
+    /// filtering and mip maps don't pull in empty texels.
+    ///
+    /// Default: 2
+    pub padding: u32,
+}
+
+impl Default for BakeSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 128,
+            padding: 2,
+        }
+    }
+}
+
+/// Result of [`bake_albedo`].
+#[derive(Clone, Debug)]
+pub struct BakedAlbedo {
+    /// Mesh with `ATTRIBUTE_UV_0` into `albedo`. Vertices are unshared per
+    /// triangle so faces in different regions can have different UVs.
+    pub mesh: Mesh,
+    /// sRGB atlas of the blended albedo.
+    pub albedo: Image,
+}
+
+/// Bakes the blended albedo of `mesh` rendered with `material` into a 2D
+/// texture and a matching UV-mapped mesh.
+///
+/// `world_from_local` must be the chunk's transform: triplanar UVs are
+/// computed in world space, so the same mesh bakes differently at different
+/// positions. Only albedo is baked; normal maps, ARM, tint masks and texture
+/// bombing are ignored.
+pub fn bake_albedo(
+    mesh: &Mesh,
+    world_from_local: Affine3A,
+    material: &TriplanarExtension,
+    images: &Assets<Image>,
+    settings: &BakeSettings,
+) -> Result<BakedAlbedo, BakeError> {
+    let image = images
+        .get(&material.albedo)
+        .ok_or(BakeError::AlbedoNotLoaded)?;
+    let sampler = AlbedoSampler::new(image)?;
+
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return Err(BakeError::MissingPositions);
+    };
+    let (ids, weights) = material_attributes(mesh).ok_or(BakeError::MissingMaterialData)?;
+    // Smooth shading carries over; meshes without normals get face normals
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) => Some(normals),
+        _ => None,
+    };
+
+    let scales: Vec<f32> = if material.extended_properties.is_empty() {
+        material
+            .material_properties
+            .iter()
+            .map(|p| p.texture_scale)
+            .collect()
+    } else {
+        material
+            .extended_properties
+            .iter()
+            .map(|p| p.base.texture_scale)
+            .collect()
+    };
+    let texture_scale =
+        |id: u8| material.texture_scale * scales.get(id as usize).copied().unwrap_or(1.0);
+
+    let resolution = settings.resolution.max(1);
+    let padding = settings.padding.min(resolution / 4);
+    let atlas = UVec2::new(resolution * 3, resolution * 2);
+    let mut texels = vec![None::<Vec3>; (atlas.x * atlas.y) as usize];
+
+    let (min, max) = positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), p| (min.min(Vec3::from(*p)), max.max(Vec3::from(*p))),
+    );
+    let extent = (max - min).max(Vec3::splat(1e-6));
+    let usable = (resolution - 2 * padding) as f32;
+
+    let local_from_world = world_from_local.inverse();
+    let mut out_positions = Vec::new();
+    let mut out_normals = Vec::new();
+    let mut out_uvs = Vec::new();
+
+    for [a, b, c] in triangles(mesh) {
+        let local = [a, b, c].map(|i| Vec3::from(positions[i]));
+        let world = local.map(|p| world_from_local.transform_point3(p));
+        let normal = (world[1] - world[0]).cross(world[2] - world[0]);
+        let Some(face) = Face::dominant(normal) else {
+            continue;
+        };
+        let corners = [a, b, c].map(|i| unpack_vertex(ids[i], weights[i]));
+
+        // Region-local texel coordinates of each corner
+        let origin = face.region_origin(resolution);
+        let texel = local.map(|p| {
+            let t = face.project((p - min) / extent);
+            Vec2::splat(padding as f32) + t * usable
+        });
+
+        rasterize(texel, |bary, pixel| {
+            let world_pos = world[0] * bary.x + world[1] * bary.y + world[2] * bary.z;
+            let color = blend(&corners, bary, |id| {
+                sampler.sample(id, face.project(world_pos) * texture_scale(id))
+            });
+            let index = (origin.y + pixel.y) * atlas.x + origin.x + pixel.x;
+            texels[index as usize] = Some(color);
+        });
+
+        let face_normal = local_from_world
+            .transform_vector3(normal)
+            .normalize_or_zero()
+            .to_array();
+        for ((corner, t), i) in local.iter().zip(texel).zip([a, b, c]) {
+            out_positions.push(corner.to_array());
+            out_normals.push(normals.map_or(face_normal, |normals| normals[i]));
+            out_uvs.push(((origin.as_vec2() + t) / atlas.as_vec2()).to_array());
+        }
+    }
+
+    dilate(&mut texels, atlas, padding);
+
+    let data = texels
+        .iter()
+        .flat_map(|texel| {
+            let color = texel.unwrap_or(Vec3::ZERO);
+            let srgb = Color::linear_rgb(color.x, color.y, color.z).to_srgba();
+            srgb.to_u8_array()
+        })
+        .collect();
+    let albedo = Image::new(
+        Extent3d {
+            width: atlas.x,
+            height: atlas.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    let vertex_count = out_positions.len() as u32;
+    let mut baked = Mesh::new(PrimitiveTopology::TriangleList, mesh.asset_usage);
+    baked.insert_attribute(Mesh::ATTRIBUTE_POSITION, out_positions);
+    baked.insert_attribute(Mesh::ATTRIBUTE_NORMAL, out_normals);
+    baked.insert_attribute(Mesh::ATTRIBUTE_UV_0, out_uvs);
+    baked.insert_indices(Indices::U32((0..vertex_count).collect()));
+
+    Ok(BakedAlbedo {
+        mesh: baked,
+        albedo,
+    })
+}
+
+/// One of the six box projection faces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Face {
+    /// 0 = X, 1 = Y, 2 = Z
+    axis: usize,
+    negative: bool,
+}
+
+impl Face {
+    fn dominant(normal: Vec3) -> Option<Self> {
+        let abs = normal.abs();
+        if abs.max_element() <= f32::EPSILON {
+            return None;
+        }
+        let axis = if abs.x >= abs.y && abs.x >= abs.z {
+            0
+        } else if abs.y >= abs.z {
+            1
+        } else {
+            2
+        };
+        Some(Self {
+            axis,
+            negative: normal[axis] < 0.0,
+        })
+    }
+
+    /// Planar coordinates matching the shader's `uv_x`, `uv_y` and `uv_z`.
+    fn project(self, p: Vec3) -> Vec2 {
+        match self.axis {
+            0 => p.yz(),
+            1 => p.xz(),
+            _ => p.xy(),
+        }
+    }
+
+    fn region_origin(self, resolution: u32) -> UVec2 {
+        UVec2::new(self.axis as u32, self.negative as u32) * resolution
+    }
+}
+
+/// Calls `shade` with barycentric coordinates for every texel centre covered
+/// by a triangle given in texel coordinates.
+fn rasterize(corners: [Vec2; 3], mut shade: impl FnMut(Vec3, UVec2)) {
+    let [a, b, c] = corners;
+    let area = (b - a).perp_dot(c - a);
+    if area.abs() <= f32::EPSILON {
+        return;
+    }
+
+    let min = a.min(b).min(c).floor().max(Vec2::ZERO).as_uvec2();
+    let max = a.max(b).max(c).ceil().max(Vec2::ZERO).as_uvec2();
+    for y in min.y..max.y {
+        for x in min.x..max.x {
+            let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let w0 = (b - p).perp_dot(c - p) / area;
+            let w1 = (c - p).perp_dot(a - p) / area;
+            let w2 = 1.0 - w0 - w1;
+            if w0 >= -1e-4 && w1 >= -1e-4 && w2 >= -1e-4 {
+                shade(Vec3::new(w0, w1, w2), UVec2::new(x, y));
+            }
+        }
+    }
+}
+
+/// Blends material colours at a point inside a triangle.
+fn blend(corners: &[VertexMaterialData; 3], bary: Vec3, sample: impl Fn(u8) -> Vec3) -> Vec3 {
+    let mut materials: Vec<(u8, f32)> = Vec::with_capacity(12);
+    for (corner, weight) in corners.iter().zip(bary.to_array()) {
+        for (&id, w) in corner.ids.iter().zip(corner.normalized_weights()) {
+            let w = weight * w;
+            if w <= 0.0 {
+                continue;
+            }
+            match materials.iter_mut().find(|(m, _)| *m == id) {
+                Some((_, total)) => *total += w,
+                None => materials.push((id, w)),
+            }
+        }
+    }
+
+    let total: f32 = materials.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 {
+        return sample(corners[0].ids[0]);
+    }
+    materials
+        .iter()
+        .map(|&(id, w)| sample(id) * (w / total))
+        .sum()
+}
+
+/// Grows filled texels into empty neighbours, `passes` times.
+fn dilate(texels: &mut [Option<Vec3>], size: UVec2, passes: u32) {
+    for _ in 0..passes {
+        let source = texels.to_vec();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let index = (y * size.x + x) as usize;
+                if source[index].is_some() {
+                    continue;
+                }
+                let neighbors = [(-1i32, 0i32), (1, 0), (0, -1), (0, 1)];
+                let mut sum = Vec3::ZERO;
+                let mut count = 0;
+                for (dx, dy) in neighbors {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    if nx < 0 || ny < 0 || nx >= size.x as i32 || ny >= size.y as i32 {
+                        continue;
+                    }
+                    if let Some(color) = source[(ny as u32 * size.x + nx as u32) as usize] {
+                        sum += color;
+                        count += 1;
+                    }
+                }
+                if count > 0 {
+                    texels[index] = Some(sum / count as f32);
+                }
+            }
+        }
+    }
+}
+
+/// Bilinear, wrapping reads from the top mip of an albedo array.
+struct AlbedoSampler<'a> {
+    data: &'a [u8],
+    size: UVec2,
+    layers: Vec<Range<usize>>,
+    bgra: bool,
+    /// sRGB byte to linear lookup
+    to_linear: [f32; 256],
+}
+
+impl<'a> AlbedoSampler<'a> {
+    fn new(image: &'a Image) -> Result<Self, BakeError> {
+        let descriptor = &image.texture_descriptor;
+        let bgra = match descriptor.format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            found => return Err(BakeError::UnsupportedAlbedoFormat { found }),
+        };
+        let data = image.data.as_deref().ok_or(BakeError::AlbedoNoCpuData)?;
+
+        let layers = (0..descriptor.size.depth_or_array_layers)
+            .map(|layer| {
+                layer_mip_range(
+                    descriptor.size,
+                    descriptor.format,
+                    descriptor.mip_level_count,
+                    image.data_order,
+                    layer,
+                    0,
+                )
+            })
+            .collect::<Vec<_>>();
+        if layers.last().is_some_and(|range| range.end > data.len()) {
+            return Err(BakeError::AlbedoNoCpuData);
+        }
+
+        Ok(Self {
+            data,
+            size: UVec2::new(descriptor.size.width, descriptor.size.height),
+            layers,
+            bgra,
+            to_linear: std::array::from_fn(|i| LinearRgba::from(Color::srgb_u8(i as u8, 0, 0)).red),
+        })
+    }
+
+    fn texel(&self, layer: &Range<usize>, x: u32, y: u32) -> Vec3 {
+        let offset = layer.start + ((y * self.size.x + x) * 4) as usize;
+        let t = &self.data[offset..offset + 4];
+        let (r, b) = if self.bgra {
+            (t[2], t[0])
+        } else {
+            (t[0], t[2])
+        };
+        Vec3::new(
+            self.to_linear[r as usize],
+            self.to_linear[t[1] as usize],
+            self.to_linear[b as usize],
+        )
+    }
+
+    fn sample(&self, id: u8, uv: Vec2) -> Vec3 {
+        let Some(layer) = self.layers.get(id as usize).or_else(|| self.layers.last()) else {
+            return Vec3::ZERO;
+        };
+
+        let p = uv.fract_gl() * self.size.as_vec2() - 0.5;
+        let base = p.floor();
+        let f = p - base;
+        let wrap = |v: f32, size: u32| (v as i32).rem_euclid(size as i32) as u32;
+        let (x0, y0) = (wrap(base.x, self.size.x), wrap(base.y, self.size.y));
+        let (x1, y1) = ((x0 + 1) % self.size.x, (y0 + 1) % self.size.y);
+
+        let top = self
+            .texel(layer, x0, y0)
+            .lerp(self.texel(layer, x1, y0), f.x);
+        let bottom = self
+            .texel(layer, x0, y1)
+            .lerp(self.texel(layer, x1, y1), f.x);
+        top.lerp(bottom, f.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::MeshTriplanarExt;
+
+    fn two_layer_albedo() -> Image {
+        let red = [255u8, 0, 0, 255];
+        let blue = [0u8, 0, 255, 255];
+        let data = [red; 4].into_iter().chain([blue; 4]).flatten().collect();
+        Image::new(
+            Extent3d {
+                width: 2,
+                height: 2,
+                depth_or_array_layers: 2,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    fn quad(material: u8) -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0f32, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, 1.0],
+                [0.0, 0.0, 1.0],
+            ],
+        );
+        mesh.insert_indices(Indices::U32(vec![0, 2, 1, 0, 3, 2]));
+        mesh.with_uniform_material(material)
+    }
+
+    #[test]
+    fn test_dominant_face() {
+        assert_eq!(
+            Face::dominant(Vec3::new(0.1, -2.0, 0.3)),
+            Some(Face {
+                axis: 1,
+                negative: true
+            })
+        );
+        assert_eq!(Face::dominant(Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn test_bake_flat_quad() {
+        let mut images = Assets::<Image>::default();
+        let albedo = images.add(two_layer_albedo());
+        let material = TriplanarExtension::new(albedo).with_materials(2);
+        let settings = BakeSettings {
+            resolution: 8,
+            padding: 1,
+        };
+
+        let baked =
+            bake_albedo(&quad(1), Affine3A::IDENTITY, &material, &images, &settings).unwrap();
+
+        assert_eq!(baked.albedo.width(), 24);
+        assert_eq!(baked.albedo.height(), 16);
+        assert_eq!(baked.mesh.count_vertices(), 6);
+
+        // Upward face lands in the +Y region (column 1, top row)
+        let data = baked.albedo.data.as_ref().unwrap();
+        let texel = |x: u32, y: u32| &data[((y * 24 + x) * 4) as usize..][..4];
+        assert_eq!(texel(12, 4), &[0, 0, 255, 255]);
+        // Unused regions stay black
+        assert_eq!(texel(2, 12), &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_bake_keeps_vertex_normals() {
+        let mut images = Assets::<Image>::default();
+        let material = TriplanarExtension::new(images.add(two_layer_albedo())).with_materials(2);
+        let tilted = Vec3::new(0.6, 0.8, 0.0).to_array();
+        let mut mesh = quad(0);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![tilted; 4]);
+
+        let baked = bake_albedo(
+            &mesh,
+            Affine3A::IDENTITY,
+            &material,
+            &images,
+            &BakeSettings::default(),
+        )
+        .unwrap();
+
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            baked.mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("baked mesh has no normals");
+        };
+        assert!(normals.iter().all(|normal| *normal == tilted));
+    }
+
+    #[test]
+    fn test_compressed_albedo_rejected() {
+        let mut image = two_layer_albedo();
+        image.texture_descriptor.format = TextureFormat::Bc7RgbaUnormSrgb;
+        let mut images = Assets::<Image>::default();
+        let material = TriplanarExtension::new(images.add(image));
+
+        assert!(matches!(
+            bake_albedo(
+                &quad(0),
+                Affine3A::IDENTITY,
+                &material,
+                &images,
+                &BakeSettings::default()
+            ),
+            Err(BakeError::UnsupportedAlbedoFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn test_blend_weights() {
+        let corners = [
+            VertexMaterialData::single(0),
+            VertexMaterialData::single(1),
+            VertexMaterialData::single(1),
+        ];
+        let color = blend(&corners, Vec3::new(0.5, 0.25, 0.25), |id| {
+            if id == 0 { Vec3::X } else { Vec3::Z }
+        });
+        assert!((color - Vec3::new(0.5, 0.0, 0.5)).length() < 1e-5);
+    }
+}
@@ -0,0 +1,16 @@
+//! Baking triplanar chunks to a single texture.
+//!
+//! This module provides:
+//! - [`bake_albedo`]: CPU box-projection bake of the blended albedo into a
+//!   per-chunk 2D texture plus a UV-mapped mesh
+//! - [`BakeToStandardMaterial`]: Component that bakes a chunk and swaps it to
+//!   a plain `StandardMaterial`, e.g. for low-end fallback or distant
+//!   static chunks
+//!
+//! The albedo palette must be an uncompressed 8-bit array with CPU data.
+
+mod albedo;
+mod swap;
+
+pub use albedo::{BakeError, BakeSettings, BakedAlbedo, bake_albedo};
+pub use swap::{BakeToStandardMaterial, BakedTriplanar, bake_marked_chunks};
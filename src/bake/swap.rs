@@ -0,0 +1,100 @@
+//! Swapping chunks between the triplanar material and a baked
+//! `StandardMaterial`.
+
+use bevy::prelude::*;
+
+use super::albedo::{BakeError, BakeSettings, bake_albedo};
+use crate::material::TriplanarVoxelMaterial;
+
+/// Request to bake this chunk and render it with a plain [`StandardMaterial`].
+///
+/// Useful as an ultra-low-end fallback or for static background chunks far
+/// from the player. The entity needs `Mesh3d`,
+/// `MeshMaterial3d<TriplanarVoxelMaterial>` and a `GlobalTransform`. The bake
+/// waits until the mesh, material and albedo are loaded, then replaces the
+/// mesh and material and inserts [`BakedTriplanar`] with the originals.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct BakeToStandardMaterial {
+    pub settings: BakeSettings,
+}
+
+/// The triplanar mesh and material a baked chunk was rendered with.
+#[derive(Component, Clone, Debug)]
+pub struct BakedTriplanar {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<TriplanarVoxelMaterial>,
+}
+
+impl BakedTriplanar {
+    /// Switch the entity back to its triplanar mesh and material.
+    pub fn restore(&self, entity: &mut EntityCommands) {
+        entity
+            .insert((
+                Mesh3d(self.mesh.clone()),
+                MeshMaterial3d(self.material.clone()),
+            ))
+            .remove::<(MeshMaterial3d<StandardMaterial>, BakedTriplanar)>();
+    }
+}
+
+/// Bakes chunks marked with [`BakeToStandardMaterial`].
+pub fn bake_marked_chunks(
+    mut commands: Commands,
+    chunks: Query<(
+        Entity,
+        &Mesh3d,
+        &MeshMaterial3d<TriplanarVoxelMaterial>,
+        &GlobalTransform,
+        &BakeToStandardMaterial,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    materials: Res<Assets<TriplanarVoxelMaterial>>,
+) {
+    for (entity, mesh, material, transform, bake) in &chunks {
+        let (Some(source_mesh), Some(source_material)) =
+            (meshes.get(&mesh.0), materials.get(&material.0))
+        else {
+            continue;
+        };
+
+        let baked = match bake_albedo(
+            source_mesh,
+            transform.affine(),
+            &source_material.extension,
+            &images,
+            &bake.settings,
+        ) {
+            Ok(baked) => baked,
+            // Try again next frame
+            Err(BakeError::AlbedoNotLoaded) => continue,
+            Err(error) => {
+                warn!("Failed to bake chunk {}: {}", entity, error);
+                commands.entity(entity).remove::<BakeToStandardMaterial>();
+                continue;
+            }
+        };
+
+        let standard = StandardMaterial {
+            base_color_texture: Some(images.add(baked.albedo)),
+            perceptual_roughness: 0.9,
+            ..default()
+        };
+        commands
+            .entity(entity)
+            .insert((
+                BakedTriplanar {
+                    mesh: mesh.0.clone(),
+                    material: material.0.clone(),
+                },
+                Mesh3d(meshes.add(baked.mesh)),
+                MeshMaterial3d(standard_materials.add(standard)),
+            ))
+            .remove::<(
+                MeshMaterial3d<TriplanarVoxelMaterial>,
+                BakeToStandardMaterial,
+            )>();
+    }
+}
@@ -0,0 +1,347 @@
+//! Minimal glTF 2.0 writer for painted chunk meshes.
+//!
+//! The output has one node, one mesh and one material. Positions, normals and
+//! indices are copied from the Bevy mesh; the per-vertex material blend is
+//! baked into `COLOR_0` using each material's average albedo colour.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::mesh::{material_attributes, triangles, unpack_vertex};
+use crate::palette::TexturePalette;
+
+/// Errors that can occur when exporting a mesh to glTF.
+#[derive(Error, Debug)]
+pub enum GltfExportError {
+    #[error("Failed to write glTF file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Mesh has no Float32x3 position attribute")]
+    MissingPositions,
+
+    #[error("Mesh has no triplanar material attributes")]
+    MissingMaterialData,
+
+    #[error("Palette colours unavailable: albedo not loaded or not an uncompressed RGBA format")]
+    PaletteColorsUnavailable,
+}
+
+/// Exports `mesh` to `path` with blended materials baked into vertex colours.
+///
+/// Material colours are the average albedo of each palette layer, so the
+/// albedo array must still have CPU data (see
+/// [`TexturePalette::average_colors`]). Paths ending in `.glb` are written as
+/// binary glTF, anything else as `.gltf` JSON with an embedded buffer.
+pub fn export_gltf(
+    mesh: &Mesh,
+    palette: &TexturePalette,
+    images: &Assets<Image>,
+    path: impl AsRef<Path>,
+) -> Result<(), GltfExportError> {
+    let colors = palette
+        .average_colors(images)
+        .ok_or(GltfExportError::PaletteColorsUnavailable)?;
+    export_gltf_with_colors(mesh, &colors, path)
+}
+
+/// Exports `mesh` to `path` using explicit per-material colours.
+///
+/// `material_colors[i]` is the colour of material `i`.
+pub fn export_gltf_with_colors(
+    mesh: &Mesh,
+    material_colors: &[Color],
+    path: impl AsRef<Path>,
+) -> Result<(), GltfExportError> {
+    let path = path.as_ref();
+    let binary = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("glb"));
+    let bytes = if binary {
+        encode_glb(mesh, material_colors)?
+    } else {
+        encode_gltf_embedded(mesh, material_colors)?.into_bytes()
+    };
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Blends material colours per vertex by the vertex's material weights.
+///
+/// Returns linear RGBA. Material IDs without a colour bake as white.
+pub fn bake_vertex_colors(
+    mesh: &Mesh,
+    material_colors: &[Color],
+) -> Result<Vec<[f32; 4]>, GltfExportError> {
+    let (ids, weights) = material_attributes(mesh).ok_or(GltfExportError::MissingMaterialData)?;
+    let linear: Vec<Vec4> = material_colors
+        .iter()
+        .map(|c| c.to_linear().to_vec4())
+        .collect();
+    let color_of = |id: u8| linear.get(id as usize).copied().unwrap_or(Vec4::ONE);
+
+    Ok(ids
+        .iter()
+        .zip(weights)
+        .map(|(&ids, &weights)| {
+            let data = unpack_vertex(ids, weights);
+            let mut color = Vec4::ZERO;
+            for (id, weight) in data.ids.iter().zip(data.normalized_weights()) {
+                color += color_of(*id) * weight;
+            }
+            color.to_array()
+        })
+        .collect())
+}
+
+/// Encodes `mesh` as a binary glTF (`.glb`) file.
+pub fn encode_glb(mesh: &Mesh, material_colors: &[Color]) -> Result<Vec<u8>, GltfExportError> {
+    let (mut bin, json) = build(mesh, material_colors, |_| None)?;
+    let mut json = json.into_bytes();
+    // Chunks are 4-byte aligned: JSON pads with spaces, BIN with zeros
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let total = 12 + 8 + json.len() + 8 + bin.len();
+    let mut out = Vec::with_capacity(total);
+    out.extend(b"glTF");
+    out.extend(2u32.to_le_bytes());
+    out.extend((total as u32).to_le_bytes());
+    out.extend((json.len() as u32).to_le_bytes());
+    out.extend(b"JSON");
+    out.extend(json);
+    out.extend((bin.len() as u32).to_le_bytes());
+    out.extend(b"BIN\0");
+    out.extend(bin);
+    Ok(out)
+}
+
+/// Encodes `mesh` as glTF JSON with the buffer embedded as a data URI.
+pub fn encode_gltf_embedded(
+    mesh: &Mesh,
+    material_colors: &[Color],
+) -> Result<String, GltfExportError> {
+    let (_, json) = build(mesh, material_colors, |bin| {
+        Some(format!(
+            "data:application/octet-stream;base64,{}",
+            base64(bin)
+        ))
+    })?;
+    Ok(json)
+}
+
+/// Accessor and buffer view bookkeeping while filling the binary buffer.
+#[derive(Default)]
+struct Writer {
+    bin: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl Writer {
+    /// Appends `bytes` as a buffer view and an accessor over it, returning the accessor index.
+    fn push(&mut self, bytes: &[u8], target: u32, accessor: String) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(bytes);
+        self.views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{offset},"byteLength":{},"target":{target}}}"#,
+            bytes.len()
+        ));
+        let view = self.views.len() - 1;
+        self.accessors
+            .push(accessor.replacen('{', &format!(r#"{{"bufferView":{view},"#), 1));
+        self.accessors.len() - 1
+    }
+}
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Builds the binary buffer and glTF JSON. `buffer_uri` returns the buffer
+/// URI for the finished buffer, or `None` for GLB where it is the BIN chunk.
+fn build(
+    mesh: &Mesh,
+    material_colors: &[Color],
+    buffer_uri: impl FnOnce(&[u8]) -> Option<String>,
+) -> Result<(Vec<u8>, String), GltfExportError> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return Err(GltfExportError::MissingPositions);
+    };
+    let colors = bake_vertex_colors(mesh, material_colors)?;
+    let indices: Vec<u32> = triangles(mesh)
+        .into_iter()
+        .flatten()
+        .map(|i| i as u32)
+        .collect();
+
+    let mut writer = Writer::default();
+    let count = positions.len();
+
+    let (min, max) = positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), p| (min.min(Vec3::from(*p)), max.max(Vec3::from(*p))),
+    );
+    let position = writer.push(
+        bytemuck::cast_slice(positions),
+        ARRAY_BUFFER,
+        format!(
+            r#"{{"componentType":{FLOAT},"count":{count},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            min.x, min.y, min.z, max.x, max.y, max.z
+        ),
+    );
+
+    let mut attributes = format!(r#""POSITION":{position}"#);
+    if let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+    {
+        let normal = writer.push(
+            bytemuck::cast_slice(normals),
+            ARRAY_BUFFER,
+            format!(r#"{{"componentType":{FLOAT},"count":{count},"type":"VEC3"}}"#),
+        );
+        let _ = write!(attributes, r#","NORMAL":{normal}"#);
+    }
+    let color = writer.push(
+        bytemuck::cast_slice(&colors),
+        ARRAY_BUFFER,
+        format!(r#"{{"componentType":{FLOAT},"count":{count},"type":"VEC4"}}"#),
+    );
+    let _ = write!(attributes, r#","COLOR_0":{color}"#);
+
+    let index = writer.push(
+        bytemuck::cast_slice(&indices),
+        ELEMENT_ARRAY_BUFFER,
+        format!(
+            r#"{{"componentType":{UNSIGNED_INT},"count":{},"type":"SCALAR"}}"#,
+            indices.len()
+        ),
+    );
+
+    let buffer = match buffer_uri(&writer.bin) {
+        Some(uri) => format!(r#"{{"byteLength":{},"uri":"{uri}"}}"#, writer.bin.len()),
+        None => format!(r#"{{"byteLength":{}}}"#, writer.bin.len()),
+    };
+    let json = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"bevy-painter"}},"#,
+            r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+            r#""meshes":[{{"primitives":[{{"attributes":{{{attributes}}},"indices":{index},"material":0}}]}}],"#,
+            r#""materials":[{{"pbrMetallicRoughness":{{"baseColorFactor":[1,1,1,1],"metallicFactor":0,"roughnessFactor":1}}}}],"#,
+            r#""buffers":[{buffer}],"bufferViews":[{views}],"accessors":[{accessors}]}}"#
+        ),
+        attributes = attributes,
+        index = index,
+        buffer = buffer,
+        views = writer.views.join(","),
+        accessors = writer.accessors.join(","),
+    );
+
+    Ok((writer.bin, json))
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{MeshTriplanarExt, VertexMaterialData};
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::PrimitiveTopology;
+
+    fn painted_triangle() -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        );
+        mesh.with_triplanar_materials(&[
+            VertexMaterialData::single(0),
+            VertexMaterialData::single(1),
+            VertexMaterialData::blend2(0, 1, 0.5),
+        ])
+    }
+
+    #[test]
+    fn test_bake_vertex_colors() {
+        let colors = [
+            Color::linear_rgb(1.0, 0.0, 0.0),
+            Color::linear_rgb(0.0, 0.0, 1.0),
+        ];
+        let baked = bake_vertex_colors(&painted_triangle(), &colors).unwrap();
+
+        assert_eq!(baked[0], [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(baked[1], [0.0, 0.0, 1.0, 1.0]);
+        assert!((baked[2][0] - 0.5).abs() < 0.01 && (baked[2][2] - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_glb_layout() {
+        let glb = encode_glb(&painted_triangle(), &[Color::WHITE]).unwrap();
+
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(
+            u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+            glb.len()
+        );
+        assert_eq!(&glb[16..20], b"JSON");
+        assert_eq!(glb.len() % 4, 0);
+    }
+
+    #[test]
+    fn test_embedded_json() {
+        let json = encode_gltf_embedded(&painted_triangle(), &[Color::WHITE]).unwrap();
+
+        assert!(json.contains(r#""COLOR_0":1"#));
+        assert!(json.contains("data:application/octet-stream;base64,"));
+        assert!(json.contains(r#""min":[0,0,0],"max":[1,1,0]"#));
+    }
+
+    #[test]
+    fn test_missing_material_data() {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; 3]);
+        assert!(matches!(
+            encode_glb(&mesh, &[]),
+            Err(GltfExportError::MissingMaterialData)
+        ));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+}
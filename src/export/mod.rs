@@ -0,0 +1,17 @@
+//! Exporting painted meshes to other tools.
+//!
+//! This module provides:
+//! - [`export_gltf`]: Writes a chunk mesh to glTF with its blended materials
+//!   baked into vertex colours, for renders in Blender and similar tools
+//! - [`bake_vertex_colors`]: The colour bake on its own
+//! - [`export_nav_costs`]: Per-triangle traversal costs by painted material,
+//!   for navigation meshes
+
+mod gltf;
+mod navmesh;
+
+pub use gltf::{
+    GltfExportError, bake_vertex_colors, encode_glb, encode_gltf_embedded, export_gltf,
+    export_gltf_with_colors,
+};
+pub use navmesh::{DEFAULT_NAV_COST, export_nav_costs};
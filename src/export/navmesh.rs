@@ -0,0 +1,67 @@
+//! Per-triangle traversal costs for navigation meshes.
+//!
+//! Pathfinding crates take an area cost per polygon; painting lava or water
+//! at runtime should change where agents walk. [`export_nav_costs`] turns each
+//! triangle's dominant material into such a cost.
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::mesh::{dominant_triangle_material, material_attributes, triangles};
+
+/// Cost of triangles whose material isn't in the cost map, or that have no
+/// material data.
+pub const DEFAULT_NAV_COST: f32 = 1.0;
+
+/// Traversal cost of every triangle of `mesh`, in index order.
+///
+/// Each triangle costs `cost_map[material]` for its dominant material by
+/// summed vertex weight, or [`DEFAULT_NAV_COST`] if the material is unmapped.
+/// Use [`f32::INFINITY`] to mark materials as impassable.
+///
+/// # Example
+/// ```ignore
+/// let costs = HashMap::from([(LAVA, f32::INFINITY), (WATER, 4.0), (ROAD, 0.5)]);
+/// let triangle_costs = export_nav_costs(&mesh, &costs);
+/// navmesh.set_area_costs(chunk, &triangle_costs);
+/// ```
+pub fn export_nav_costs(mesh: &Mesh, cost_map: &HashMap<u8, f32>) -> Vec<f32> {
+    let triangles = triangles(mesh);
+    let Some((ids, weights)) = material_attributes(mesh) else {
+        return vec![DEFAULT_NAV_COST; triangles.len()];
+    };
+    triangles
+        .into_iter()
+        .map(|triangle| {
+            dominant_triangle_material(triangle, ids, weights)
+                .and_then(|material| cost_map.get(&material).copied())
+                .unwrap_or(DEFAULT_NAV_COST)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::TriplanarMeshBuilder;
+
+    #[test]
+    fn test_nav_costs_by_dominant_material() {
+        let up = [0.0, 1.0, 0.0];
+        let mesh = TriplanarMeshBuilder::new()
+            .with_vertex_single([0.0, 0.0, 0.0], up, 1)
+            .with_vertex_single([1.0, 0.0, 0.0], up, 1)
+            .with_vertex_single([0.0, 0.0, 1.0], up, 2)
+            .with_vertex_single([1.0, 0.0, 1.0], up, 2)
+            .with_vertex_single([2.0, 0.0, 0.0], up, 3)
+            .with_vertex_single([2.0, 0.0, 1.0], up, 3)
+            .with_indices(vec![0, 1, 2, 1, 3, 2, 1, 4, 5])
+            .build_unwrap();
+        let costs: HashMap<u8, f32> = [(1, 0.5), (2, f32::INFINITY)].into_iter().collect();
+
+        assert_eq!(
+            export_nav_costs(&mesh, &costs),
+            vec![0.5, f32::INFINITY, DEFAULT_NAV_COST]
+        );
+    }
+}
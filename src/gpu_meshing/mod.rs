@@ -0,0 +1,795 @@
+//! Fully GPU-driven surface nets meshing with material blending.
+//!
+//! For very large worlds, CPU meshing and attribute generation become the
+//! bottleneck. With the `gpu_meshing` feature, chunks marked with
+//! [`GpuMeshedChunk`] get a GPU-only [`Mesh3d`], and whenever their
+//! [`DensityField`], [`MaterialField`] or neighbor data change, two compute
+//! passes rebuild it:
+//! - a vertex buffer laid out like a mesh with position, normal, packed
+//!   material IDs and packed material weights
+//! - an index buffer
+//!
+//! The output is copied straight into the mesh's slot in Bevy's mesh
+//! buffers, so the chunk draws, casts shadows and runs prepasses like any
+//! other triplanar mesh. Nothing is read back to the CPU.
+//!
+//! Each chunk is meshed with one sample of its neighbors' data on every
+//! side, taken from [`NeighborDensityFields`] and [`NeighborMaterialFields`],
+//! so chunk borders are stitched. Vertex materials follow the same
+//! [`MaterialBlendSettings`] as the CPU path, including blend priorities,
+//! hard edges and group rules.
+//!
+//! Limitations: a chunk holds at most [`MAX_QUADS`] quads and as many
+//! vertices; surfaces beyond that are dropped. Samples diagonal to the
+//! chunk, which face neighbor data doesn't cover, repeat the nearest chunk
+//! sample.
+
+use bevy::asset::{RenderAssetUsages, embedded_asset, load_embedded_asset};
+use bevy::camera::primitives::Aabb;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::render::{
+    Extract, ExtractSchedule, Render, RenderApp, RenderStartup, RenderSystems,
+    mesh::allocator::MeshAllocator,
+    render_graph::{self, RenderGraph, RenderLabel},
+    render_resource::{
+        BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, Buffer,
+        BufferDescriptor, BufferInitDescriptor, BufferUsages, CachedComputePipelineId,
+        ComputePassDescriptor, ComputePipelineDescriptor, PipelineCache, ShaderStages,
+        binding_types::{
+            storage_buffer_read_only_sized, storage_buffer_sized, uniform_buffer_sized,
+        },
+    },
+    renderer::{RenderContext, RenderDevice},
+};
+use bevy_sculpter::prelude::{DensityField, NeighborDensityFields};
+use bytemuck::{Pod, Zeroable};
+
+use crate::material_field::{
+    DensitySource, FIELD_SIZE, MaterialBlendSettings, MaterialField, MaterialStorage,
+    NeighborMaterialFields, sample_voxel,
+};
+use crate::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS};
+
+/// Samples along each axis: the chunk's own plus one neighbor sample on
+/// either side.
+const PADDED_SIZE: UVec3 = uvec3(FIELD_SIZE.x + 2, FIELD_SIZE.y + 2, FIELD_SIZE.z + 2);
+
+/// Total number of samples in the padded grid.
+const PADDED_VOLUME: usize = (PADDED_SIZE.x * PADDED_SIZE.y * PADDED_SIZE.z) as usize;
+
+/// Number of cells (one less than samples) along each axis.
+const CELL_COUNT: UVec3 = uvec3(PADDED_SIZE.x - 1, PADDED_SIZE.y - 1, PADDED_SIZE.z - 1);
+
+/// Total number of cells in the padded grid.
+const CELL_VOLUME: usize = (CELL_COUNT.x * CELL_COUNT.y * CELL_COUNT.z) as usize;
+
+/// Most quads a GPU meshed chunk holds. Also the vertex capacity.
+pub const MAX_QUADS: usize = 16384;
+
+/// Index capacity, two triangles per quad.
+const MAX_INDICES: usize = MAX_QUADS * 6;
+
+/// Size of the index and vertex counters, `Counters` in the shader.
+const COUNTERS_SIZE: usize = 2 * size_of::<u32>();
+
+/// Compute workgroup size along each axis (must match the shader).
+const WORKGROUP_SIZE: u32 = 4;
+
+/// Material IDs the blend rule tables cover.
+const MATERIAL_COUNT: usize = 256;
+
+/// Marker for chunks that should be meshed on the GPU.
+///
+/// The entity must also have a [`DensityField`] and a [`MaterialField`],
+/// and a [`MeshMaterial3d`] to draw with. [`GpuMeshingPlugin`] gives it
+/// its [`Mesh3d`], so don't also mesh it on the CPU. Meshing is re-run
+/// whenever either field or the neighbor data changes.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct GpuMeshedChunk {
+    /// World-space size of the chunk mesh (matches `DensityFieldMeshSize`).
+    pub mesh_size: Vec3,
+}
+
+impl Default for GpuMeshedChunk {
+    fn default() -> Self {
+        Self {
+            mesh_size: FIELD_SIZE.as_vec3(),
+        }
+    }
+}
+
+/// Uniform parameters for the meshing shader.
+///
+/// Must match `Params` in `surface_nets.wgsl`.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct GpuMeshingParams {
+    pub field_size: [u32; 3],
+    pub density_influence: f32,
+    pub mesh_scale: [f32; 3],
+    pub weight_threshold: f32,
+    pub max_materials: u32,
+    pub max_vertices: u32,
+    pub max_indices: u32,
+    pub _padding: u32,
+}
+
+/// A single vertex written by the meshing shader, in the interleaved
+/// layout of a mesh with position, normal, material IDs and weights.
+///
+/// Must match `GpuVertex` in `surface_nets.wgsl`.
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct GpuVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub material_ids: u32,
+    pub material_weights: u32,
+}
+
+/// Blending behavior of one material.
+///
+/// Must match `BlendMaterial` in `surface_nets.wgsl`.
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct GpuBlendMaterial {
+    pub priority_scale: f32,
+    pub hard_edges: u32,
+}
+
+/// [`MaterialBlendSettings`] as shader tables.
+struct GpuBlendRules {
+    materials: Vec<GpuBlendMaterial>,
+    /// One bit per ordered material pair that may blend, stronger first.
+    groups: Vec<u32>,
+}
+
+impl GpuBlendRules {
+    fn new(settings: &MaterialBlendSettings) -> Self {
+        let materials = (0..MATERIAL_COUNT)
+            .map(|id| {
+                let id = id as u8;
+                GpuBlendMaterial {
+                    priority_scale: settings.priority_scale(id),
+                    hard_edges: settings.material(id).hard_edges as u32,
+                }
+            })
+            .collect();
+
+        let mut groups = vec![0u32; MATERIAL_COUNT * MATERIAL_COUNT / 32];
+        for stronger in 0..MATERIAL_COUNT {
+            let stronger_group = settings.material(stronger as u8).group;
+            for weaker in 0..MATERIAL_COUNT {
+                let group = settings.material(weaker as u8).group;
+                if settings.group_rules.allows(stronger_group, group) {
+                    let bit = stronger * MATERIAL_COUNT + weaker;
+                    groups[bit / 32] |= 1 << (bit % 32);
+                }
+            }
+        }
+
+        Self { materials, groups }
+    }
+}
+
+/// Mesh a [`GpuMeshedChunk`] draws, sized for the meshing output.
+///
+/// Only exists in the render world; its vertices are overwritten by the
+/// compute passes, apart from two corners that give it chunk-sized bounds.
+fn gpu_chunk_mesh(mesh_size: Vec3) -> Mesh {
+    let mut positions = vec![[0.0f32; 3]; MAX_QUADS];
+    let (min, max) = gpu_chunk_bounds(mesh_size);
+    positions[0] = min.to_array();
+    positions[1] = max.to_array();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0f32, 1.0, 0.0]; MAX_QUADS])
+    .with_inserted_attribute(ATTRIBUTE_MATERIAL_IDS, vec![0u32; MAX_QUADS])
+    .with_inserted_attribute(ATTRIBUTE_MATERIAL_WEIGHTS, vec![0u32; MAX_QUADS])
+    .with_inserted_indices(Indices::U32(vec![0; MAX_INDICES]))
+}
+
+/// Bounds of a chunk's GPU mesh, including the stitched border cells.
+fn gpu_chunk_bounds(mesh_size: Vec3) -> (Vec3, Vec3) {
+    let cell = mesh_size / FIELD_SIZE.as_vec3();
+    (-cell, mesh_size + cell)
+}
+
+/// A chunk extracted for meshing this frame.
+struct GpuMeshingJob {
+    entity: Entity,
+    mesh: AssetId<Mesh>,
+    params: GpuMeshingParams,
+    density: Vec<f32>,
+    materials: Vec<u32>,
+}
+
+/// Chunks extracted from the main world this frame.
+#[derive(Resource, Default)]
+struct ExtractedGpuMeshingJobs {
+    jobs: Vec<GpuMeshingJob>,
+    alive: HashSet<Entity>,
+    /// Blend rules, when the settings changed.
+    rules: Option<GpuBlendRules>,
+}
+
+/// A chunk whose inputs are uploaded, waiting for its mesh to be
+/// allocated and the pipelines to compile.
+struct PreparedGpuMeshingJob {
+    mesh: AssetId<Mesh>,
+    bind_group: BindGroup,
+}
+
+/// Render-world meshing state.
+#[derive(Resource, Default)]
+struct GpuMeshingQueue {
+    pending: HashMap<Entity, PreparedGpuMeshingJob>,
+    /// Jobs the node runs this frame.
+    ready: Vec<PreparedGpuMeshingJob>,
+}
+
+/// Render-world pipelines, layout and shared buffers for GPU meshing.
+#[derive(Resource)]
+struct GpuMeshingPipelines {
+    layout: BindGroupLayout,
+    place_vertices: CachedComputePipelineId,
+    emit_quads: CachedComputePipelineId,
+    blend_materials: Buffer,
+    blend_groups: Buffer,
+    /// Scratch output shared by every job, copied into the chunk's mesh
+    /// after each one.
+    cell_buffer: Buffer,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    counter_buffer: Buffer,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GpuMeshingLabel;
+
+/// Plugin enabling GPU surface nets meshing for [`GpuMeshedChunk`] entities.
+pub struct GpuMeshingPlugin;
+
+impl Plugin for GpuMeshingPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/surface_nets.wgsl");
+
+        app.add_systems(PostUpdate, attach_gpu_chunk_meshes);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<GpuMeshingQueue>()
+            .init_resource::<ExtractedGpuMeshingJobs>()
+            .add_systems(RenderStartup, init_gpu_meshing_pipelines)
+            .add_systems(ExtractSchedule, extract_gpu_meshing_jobs)
+            .add_systems(
+                Render,
+                prepare_gpu_meshing_jobs.in_set(RenderSystems::PrepareBindGroups),
+            );
+
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        render_graph.add_node(GpuMeshingLabel, GpuMeshingNode);
+        render_graph.add_node_edge(GpuMeshingLabel, bevy::render::graph::CameraDriverLabel);
+    }
+}
+
+/// System giving new [`GpuMeshedChunk`]s the mesh the GPU writes into.
+pub fn attach_gpu_chunk_meshes(
+    mut commands: Commands,
+    chunks: Query<(Entity, &GpuMeshedChunk), Added<GpuMeshedChunk>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (entity, chunk) in &chunks {
+        let (min, max) = gpu_chunk_bounds(chunk.mesh_size);
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(gpu_chunk_mesh(chunk.mesh_size))),
+            Aabb::from_min_max(min, max),
+        ));
+    }
+}
+
+/// Packs `u8` material IDs four per `u32` (little-endian), matching the shader.
+fn pack_materials(materials: &[u8]) -> Vec<u32> {
+    materials
+        .chunks(4)
+        .map(|c| {
+            let mut bytes = [0u8; 4];
+            bytes[..c.len()].copy_from_slice(c);
+            u32::from_le_bytes(bytes)
+        })
+        .collect()
+}
+
+/// Density and material IDs of the padded grid around a chunk, starting
+/// one sample before its origin.
+///
+/// Samples past the chunk come from neighbor data where both density and
+/// material are known, like the CPU blend; others repeat the nearest
+/// chunk sample.
+fn padded_samples(
+    density: &DensityField,
+    materials: &MaterialField,
+    neighbor_densities: Option<&NeighborDensityFields>,
+    neighbor_materials: Option<&NeighborMaterialFields>,
+) -> (Vec<f32>, Vec<u8>) {
+    let last = FIELD_SIZE.as_ivec3() - IVec3::ONE;
+    let mut densities = Vec::with_capacity(PADDED_VOLUME);
+    let mut ids = Vec::with_capacity(PADDED_VOLUME);
+    for z in -1..=FIELD_SIZE.z as i32 {
+        for y in -1..=FIELD_SIZE.y as i32 {
+            for x in -1..=FIELD_SIZE.x as i32 {
+                let voxel = ivec3(x, y, z);
+                let (d, m) = sample_voxel(
+                    voxel,
+                    density,
+                    materials,
+                    neighbor_densities,
+                    neighbor_materials,
+                )
+                .unwrap_or_else(|| {
+                    let nearest = voxel.clamp(IVec3::ZERO, last);
+                    (
+                        DensitySource::density(density, nearest).unwrap_or(0.0),
+                        MaterialStorage::get(materials, nearest.as_uvec3()),
+                    )
+                });
+                densities.push(d);
+                ids.push(m);
+            }
+        }
+    }
+    (densities, ids)
+}
+
+type GpuMeshedChunkQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Ref<'static, GpuMeshedChunk>,
+        Ref<'static, Mesh3d>,
+        Ref<'static, DensityField>,
+        Ref<'static, MaterialField>,
+        Option<Ref<'static, NeighborDensityFields>>,
+        Option<Ref<'static, NeighborMaterialFields>>,
+    ),
+>;
+
+fn extract_gpu_meshing_jobs(
+    mut extracted: ResMut<ExtractedGpuMeshingJobs>,
+    chunks: Extract<GpuMeshedChunkQuery>,
+    blend_settings: Extract<Option<Res<MaterialBlendSettings>>>,
+) {
+    let default_settings = MaterialBlendSettings::default();
+    let settings = blend_settings.as_deref().unwrap_or(&default_settings);
+
+    extracted.jobs.clear();
+    extracted.alive.clear();
+    extracted.rules = blend_settings
+        .as_ref()
+        .filter(|settings| settings.is_changed())
+        .map(|settings| GpuBlendRules::new(settings));
+
+    for (entity, chunk, mesh, density, materials, neighbor_densities, neighbor_materials) in &chunks
+    {
+        extracted.alive.insert(entity);
+
+        let neighbors_changed = neighbor_densities.as_ref().is_some_and(Ref::is_changed)
+            || neighbor_materials.as_ref().is_some_and(Ref::is_changed);
+        if !(chunk.is_changed()
+            || mesh.is_changed()
+            || density.is_changed()
+            || materials.is_changed()
+            || neighbors_changed)
+        {
+            continue;
+        }
+
+        let (density, ids) = padded_samples(
+            &density,
+            &materials,
+            neighbor_densities.as_deref(),
+            neighbor_materials.as_deref(),
+        );
+        let mesh_scale = chunk.mesh_size / FIELD_SIZE.as_vec3();
+        extracted.jobs.push(GpuMeshingJob {
+            entity,
+            mesh: mesh.0.id(),
+            params: GpuMeshingParams {
+                field_size: PADDED_SIZE.to_array(),
+                density_influence: settings.density_influence,
+                mesh_scale: mesh_scale.to_array(),
+                weight_threshold: settings.weight_threshold,
+                max_materials: settings.max_materials.clamp(1, 4) as u32,
+                max_vertices: MAX_QUADS as u32,
+                max_indices: MAX_INDICES as u32,
+                _padding: 0,
+            },
+            density,
+            materials: pack_materials(&ids),
+        });
+    }
+}
+
+/// Creates the blend rule buffers.
+fn blend_rule_buffers(render_device: &RenderDevice, rules: &GpuBlendRules) -> (Buffer, Buffer) {
+    let materials = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("gpu_meshing_blend_materials"),
+        contents: bytemuck::cast_slice(&rules.materials),
+        usage: BufferUsages::STORAGE,
+    });
+    let groups = render_device.create_buffer_with_data(&BufferInitDescriptor {
+        label: Some("gpu_meshing_blend_groups"),
+        contents: bytemuck::cast_slice(&rules.groups),
+        usage: BufferUsages::STORAGE,
+    });
+    (materials, groups)
+}
+
+fn init_gpu_meshing_pipelines(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    pipeline_cache: Res<PipelineCache>,
+    asset_server: Res<AssetServer>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "gpu_meshing_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::COMPUTE,
+            (
+                uniform_buffer_sized(false, None),
+                storage_buffer_read_only_sized(false, None),
+                storage_buffer_read_only_sized(false, None),
+                storage_buffer_read_only_sized(false, None),
+                storage_buffer_read_only_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+            ),
+        ),
+    );
+
+    let shader = load_embedded_asset!(asset_server.as_ref(), "shaders/surface_nets.wgsl");
+
+    let place_vertices = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some("gpu_meshing_place_vertices".into()),
+        layout: vec![layout.clone()],
+        push_constant_ranges: vec![],
+        shader: shader.clone(),
+        shader_defs: vec![],
+        entry_point: Some("place_vertices".into()),
+        zero_initialize_workgroup_memory: false,
+    });
+
+    let emit_quads = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some("gpu_meshing_emit_quads".into()),
+        layout: vec![layout.clone()],
+        push_constant_ranges: vec![],
+        shader,
+        shader_defs: vec![],
+        entry_point: Some("emit_quads".into()),
+        zero_initialize_workgroup_memory: false,
+    });
+
+    let buffer = |label: &'static str, size: usize, usage: BufferUsages| {
+        render_device.create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size: size as u64,
+            usage,
+            mapped_at_creation: false,
+        })
+    };
+    let (blend_materials, blend_groups) = blend_rule_buffers(
+        &render_device,
+        &GpuBlendRules::new(&MaterialBlendSettings::default()),
+    );
+
+    commands.insert_resource(GpuMeshingPipelines {
+        layout,
+        place_vertices,
+        emit_quads,
+        blend_materials,
+        blend_groups,
+        cell_buffer: buffer(
+            "gpu_meshing_cells",
+            CELL_VOLUME * size_of::<i32>(),
+            BufferUsages::STORAGE,
+        ),
+        vertex_buffer: buffer(
+            "gpu_meshing_vertices",
+            MAX_QUADS * size_of::<GpuVertex>(),
+            BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        ),
+        index_buffer: buffer(
+            "gpu_meshing_indices",
+            MAX_INDICES * size_of::<u32>(),
+            BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+        ),
+        counter_buffer: buffer(
+            "gpu_meshing_counters",
+            COUNTERS_SIZE,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        ),
+    });
+}
+
+/// System uploading extracted jobs and queueing the ones whose mesh is
+/// allocated for [`GpuMeshingNode`].
+fn prepare_gpu_meshing_jobs(
+    mut queue: ResMut<GpuMeshingQueue>,
+    mut extracted: ResMut<ExtractedGpuMeshingJobs>,
+    pipelines: Option<ResMut<GpuMeshingPipelines>>,
+    pipeline_cache: Res<PipelineCache>,
+    mesh_allocator: Res<MeshAllocator>,
+    render_device: Res<RenderDevice>,
+) {
+    let Some(mut pipelines) = pipelines else {
+        return;
+    };
+
+    queue.ready.clear();
+    let alive = std::mem::take(&mut extracted.alive);
+    queue.pending.retain(|entity, _| alive.contains(entity));
+    extracted.alive = alive;
+
+    if let Some(rules) = extracted.rules.take() {
+        let (materials, groups) = blend_rule_buffers(&render_device, &rules);
+        pipelines.blend_materials = materials;
+        pipelines.blend_groups = groups;
+    }
+
+    for job in extracted.jobs.drain(..) {
+        let input = |label: &'static str, contents: &[u8], usage: BufferUsages| {
+            render_device.create_buffer_with_data(&BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage,
+            })
+        };
+        let params = input(
+            "gpu_meshing_params",
+            bytemuck::bytes_of(&job.params),
+            BufferUsages::UNIFORM,
+        );
+        let density = input(
+            "gpu_meshing_density",
+            bytemuck::cast_slice(&job.density),
+            BufferUsages::STORAGE,
+        );
+        let materials = input(
+            "gpu_meshing_materials",
+            bytemuck::cast_slice(&job.materials),
+            BufferUsages::STORAGE,
+        );
+        let bind_group = render_device.create_bind_group(
+            "gpu_meshing_bind_group",
+            &pipelines.layout,
+            &BindGroupEntries::sequential((
+                params.as_entire_binding(),
+                density.as_entire_binding(),
+                materials.as_entire_binding(),
+                pipelines.blend_materials.as_entire_binding(),
+                pipelines.blend_groups.as_entire_binding(),
+                pipelines.cell_buffer.as_entire_binding(),
+                pipelines.vertex_buffer.as_entire_binding(),
+                pipelines.index_buffer.as_entire_binding(),
+                pipelines.counter_buffer.as_entire_binding(),
+            )),
+        );
+        // A newer job replaces one still waiting for the same chunk
+        queue.pending.insert(
+            job.entity,
+            PreparedGpuMeshingJob {
+                mesh: job.mesh,
+                bind_group,
+            },
+        );
+    }
+
+    if pipeline_cache
+        .get_compute_pipeline(pipelines.place_vertices)
+        .is_none()
+        || pipeline_cache
+            .get_compute_pipeline(pipelines.emit_quads)
+            .is_none()
+    {
+        return;
+    }
+    let allocated: Vec<Entity> = queue
+        .pending
+        .iter()
+        .filter(|(_, job)| {
+            mesh_allocator.mesh_vertex_slice(&job.mesh).is_some()
+                && mesh_allocator.mesh_index_slice(&job.mesh).is_some()
+        })
+        .map(|(&entity, _)| entity)
+        .collect();
+    for entity in allocated {
+        if let Some(job) = queue.pending.remove(&entity) {
+            queue.ready.push(job);
+        }
+    }
+}
+
+struct GpuMeshingNode;
+
+impl render_graph::Node for GpuMeshingNode {
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let queue = world.resource::<GpuMeshingQueue>();
+        if queue.ready.is_empty() {
+            return Ok(());
+        }
+
+        let Some(pipelines) = world.get_resource::<GpuMeshingPipelines>() else {
+            return Ok(());
+        };
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let (Some(place_vertices), Some(emit_quads)) = (
+            pipeline_cache.get_compute_pipeline(pipelines.place_vertices),
+            pipeline_cache.get_compute_pipeline(pipelines.emit_quads),
+        ) else {
+            return Ok(());
+        };
+        let mesh_allocator = world.resource::<MeshAllocator>();
+
+        let cell_groups = CELL_COUNT.map(|c| c.div_ceil(WORKGROUP_SIZE));
+        let edge_groups = FIELD_SIZE.map(|c| c.div_ceil(WORKGROUP_SIZE));
+
+        for job in &queue.ready {
+            let (Some(vertices), Some(indices)) = (
+                mesh_allocator.mesh_vertex_slice(&job.mesh),
+                mesh_allocator.mesh_index_slice(&job.mesh),
+            ) else {
+                continue;
+            };
+
+            let encoder = render_context.command_encoder();
+            encoder.clear_buffer(&pipelines.counter_buffer, 0, None);
+            // Unused indices stay 0, drawing degenerate triangles
+            encoder.clear_buffer(&pipelines.index_buffer, 0, None);
+
+            // Separate passes so every cell vertex exists before quads reference it
+            {
+                let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("gpu_meshing_place_vertices"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(place_vertices);
+                pass.set_bind_group(0, &job.bind_group, &[]);
+                pass.dispatch_workgroups(cell_groups.x, cell_groups.y, cell_groups.z);
+            }
+            {
+                let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("gpu_meshing_emit_quads"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(emit_quads);
+                pass.set_bind_group(0, &job.bind_group, &[]);
+                pass.dispatch_workgroups(edge_groups.x, edge_groups.y, edge_groups.z);
+            }
+
+            encoder.copy_buffer_to_buffer(
+                &pipelines.vertex_buffer,
+                0,
+                vertices.buffer,
+                vertices.range.start as u64 * size_of::<GpuVertex>() as u64,
+                pipelines.vertex_buffer.size(),
+            );
+            encoder.copy_buffer_to_buffer(
+                &pipelines.index_buffer,
+                0,
+                indices.buffer,
+                indices.range.start as u64 * size_of::<u32>() as u64,
+                pipelines.index_buffer.size(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::mesh::VertexAttributeValues;
+    use bevy_sculpter::field::Field;
+
+    use super::*;
+    use crate::material_field::MaterialBlendInfo;
+    use crate::palette::MaterialGroup;
+
+    #[test]
+    fn test_pack_materials() {
+        let packed = pack_materials(&[1, 2, 3, 4, 5]);
+        assert_eq!(packed, vec![0x04030201, 0x00000005]);
+    }
+
+    #[test]
+    fn test_gpu_struct_sizes() {
+        // Must match WGSL layouts
+        assert_eq!(size_of::<GpuMeshingParams>(), 48);
+        assert_eq!(size_of::<GpuVertex>(), 32);
+        assert_eq!(size_of::<GpuBlendMaterial>(), 8);
+    }
+
+    #[test]
+    fn test_chunk_mesh_matches_vertex_layout() {
+        let mesh = gpu_chunk_mesh(Vec3::splat(10.0));
+        let layout = mesh.get_mesh_vertex_buffer_layout(&mut Default::default());
+        assert_eq!(
+            layout.0.layout().array_stride,
+            size_of::<GpuVertex>() as u64
+        );
+        assert_eq!(mesh.count_vertices(), MAX_QUADS);
+        assert_eq!(mesh.indices().unwrap().len(), MAX_INDICES);
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("missing positions");
+        };
+        assert_eq!(positions[1], [10.3125; 3]);
+    }
+
+    #[test]
+    fn test_blend_rules_follow_settings() {
+        let settings = MaterialBlendSettings {
+            materials: vec![
+                MaterialBlendInfo {
+                    group: MaterialGroup::Liquid,
+                    ..default()
+                },
+                MaterialBlendInfo {
+                    blend_priority: 1.0,
+                    hard_edges: true,
+                    ..default()
+                },
+            ],
+            ..default()
+        };
+        let rules = GpuBlendRules::new(&settings);
+        let allows = |stronger: usize, weaker: usize| {
+            let bit = stronger * MATERIAL_COUNT + weaker;
+            rules.groups[bit / 32] & (1 << (bit % 32)) != 0
+        };
+
+        assert_eq!(rules.materials[1].priority_scale, 2.0);
+        assert_eq!(rules.materials[1].hard_edges, 1);
+        assert_eq!(rules.materials[2].priority_scale, 1.0);
+        assert!(!allows(0, 1));
+        assert!(!allows(1, 0));
+        assert!(allows(0, 0));
+        assert!(allows(1, 2));
+    }
+
+    #[test]
+    fn test_padding_repeats_border_without_neighbors() {
+        let mut density = DensityField::new();
+        let mut materials = MaterialField::filled(1);
+        density.set(0, 5, 7, -3.0);
+        materials.set(0, 5, 7, 4);
+
+        let (densities, ids) = padded_samples(&density, &materials, None, None);
+        assert_eq!(densities.len(), PADDED_VOLUME);
+        let index = |p: IVec3| {
+            let p = (p + IVec3::ONE).as_uvec3();
+            (p.x + p.y * PADDED_SIZE.x + p.z * PADDED_SIZE.x * PADDED_SIZE.y) as usize
+        };
+        assert_eq!(densities[index(ivec3(0, 5, 7))], -3.0);
+        assert_eq!(densities[index(ivec3(-1, 5, 7))], -3.0);
+        assert_eq!(ids[index(ivec3(-1, 5, 7))], 4);
+        assert_eq!(ids[index(ivec3(32, 32, 32))], 1);
+    }
+}
@@ -0,0 +1,324 @@
+// GPU surface nets with per-vertex material blending.
+//
+// Two passes over one chunk's padded 34^3 sample grid, which holds the
+// chunk's 32^3 samples plus one neighbor sample on every side:
+// 1. place_vertices: one vertex per surface cell, with normal and blended materials
+// 2. emit_quads: one quad per sign-changing grid edge starting on a chunk sample
+//
+// Vertices in the padding cells match the ones the neighbor places itself,
+// so quads on the chunk border close the seam. Output vertices use the
+// interleaved layout of a Bevy mesh with position, normal, material IDs and
+// material weights, so they can be copied straight into the chunk's mesh.
+
+// Must match GpuMeshingParams in gpu_meshing/mod.rs
+struct Params {
+    field_size: vec3<u32>,
+    density_influence: f32,
+    mesh_scale: vec3<f32>,
+    weight_threshold: f32,
+    max_materials: u32,
+    max_vertices: u32,
+    max_indices: u32,
+}
+
+// Must match GpuBlendMaterial in gpu_meshing/mod.rs
+struct BlendMaterial {
+    priority_scale: f32,
+    hard_edges: u32,
+}
+
+// Must match GpuVertex in gpu_meshing/mod.rs
+struct GpuVertex {
+    position_x: f32,
+    position_y: f32,
+    position_z: f32,
+    normal_x: f32,
+    normal_y: f32,
+    normal_z: f32,
+    material_ids: u32,
+    material_weights: u32,
+}
+
+struct Counters {
+    index_count: atomic<u32>,
+    vertex_count: atomic<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> density: array<f32>;
+@group(0) @binding(2) var<storage, read> materials: array<u32>;
+@group(0) @binding(3) var<storage, read> blend_materials: array<BlendMaterial>;
+// One bit per (stronger, weaker) material pair that may blend
+@group(0) @binding(4) var<storage, read> blend_groups: array<u32>;
+@group(0) @binding(5) var<storage, read_write> cell_vertices: array<i32>;
+@group(0) @binding(6) var<storage, read_write> vertices: array<GpuVertex>;
+@group(0) @binding(7) var<storage, read_write> indices: array<u32>;
+@group(0) @binding(8) var<storage, read_write> counters: Counters;
+
+const CORNERS: array<vec3<u32>, 8> = array<vec3<u32>, 8>(
+    vec3<u32>(0u, 0u, 0u),
+    vec3<u32>(1u, 0u, 0u),
+    vec3<u32>(0u, 1u, 0u),
+    vec3<u32>(1u, 1u, 0u),
+    vec3<u32>(0u, 0u, 1u),
+    vec3<u32>(1u, 0u, 1u),
+    vec3<u32>(0u, 1u, 1u),
+    vec3<u32>(1u, 1u, 1u),
+);
+
+// Corner index pairs for the 12 cube edges
+const EDGES: array<vec2<u32>, 12> = array<vec2<u32>, 12>(
+    vec2<u32>(0u, 1u), vec2<u32>(2u, 3u), vec2<u32>(4u, 5u), vec2<u32>(6u, 7u),
+    vec2<u32>(0u, 2u), vec2<u32>(1u, 3u), vec2<u32>(4u, 6u), vec2<u32>(5u, 7u),
+    vec2<u32>(0u, 4u), vec2<u32>(1u, 5u), vec2<u32>(2u, 6u), vec2<u32>(3u, 7u),
+);
+
+fn sample_index(p: vec3<u32>) -> u32 {
+    return p.x + p.y * params.field_size.x + p.z * params.field_size.x * params.field_size.y;
+}
+
+fn cell_index(c: vec3<u32>) -> u32 {
+    let cells = params.field_size - vec3<u32>(1u);
+    return c.x + c.y * cells.x + c.z * cells.x * cells.y;
+}
+
+fn sample_material(p: vec3<u32>) -> u32 {
+    let i = sample_index(p);
+    return (materials[i / 4u] >> ((i % 4u) * 8u)) & 0xFFu;
+}
+
+fn allows_blend(stronger: u32, weaker: u32) -> bool {
+    let bit = stronger * 256u + weaker;
+    return ((blend_groups[bit / 32u] >> (bit % 32u)) & 1u) != 0u;
+}
+
+// Mirrors VertexMaterialComputer::blend_voxels in material_field/blending.rs:
+// interior corners contribute by depth scaled by blend priority, duplicates
+// merge, group rules drop materials that may not blend with a stronger one,
+// hard-edged materials snap to the dominant one, and the top max_materials
+// are kept.
+fn blend_cell(cell: vec3<u32>, corner_density: array<f32, 8>) -> vec2<u32> {
+    var ids: array<u32, 8>;
+    var weights: array<f32, 8>;
+    var count = 0u;
+
+    for (var i = 0u; i < 8u; i++) {
+        let d = corner_density[i];
+        if d >= 0.0 {
+            continue;
+        }
+        let w = clamp(-d * params.density_influence, 0.0, 1.0);
+        if w <= params.weight_threshold {
+            continue;
+        }
+        let id = sample_material(cell + CORNERS[i]);
+        let scaled = w * blend_materials[id].priority_scale;
+        var merged = false;
+        for (var j = 0u; j < count; j++) {
+            if ids[j] == id {
+                weights[j] += scaled;
+                merged = true;
+                break;
+            }
+        }
+        if !merged {
+            ids[count] = id;
+            weights[count] = scaled;
+            count++;
+        }
+    }
+
+    if count == 0u {
+        return vec2<u32>(sample_material(cell), 255u);
+    }
+
+    // Sort by weight descending
+    for (var i = 0u; i + 1u < count; i++) {
+        for (var j = i + 1u; j < count; j++) {
+            if weights[j] > weights[i] {
+                let id = ids[i];
+                ids[i] = ids[j];
+                ids[j] = id;
+                let w = weights[i];
+                weights[i] = weights[j];
+                weights[j] = w;
+            }
+        }
+    }
+
+    // Group rules, strongest first
+    var kept = 0u;
+    var hard_edges = false;
+    for (var i = 0u; i < count; i++) {
+        var allowed = true;
+        for (var j = 0u; j < kept; j++) {
+            if !allows_blend(ids[j], ids[i]) {
+                allowed = false;
+                break;
+            }
+        }
+        if allowed {
+            ids[kept] = ids[i];
+            weights[kept] = weights[i];
+            hard_edges = hard_edges || blend_materials[ids[i]].hard_edges != 0u;
+            kept++;
+        }
+    }
+
+    var max_materials = clamp(params.max_materials, 1u, 4u);
+    if hard_edges {
+        max_materials = 1u;
+    }
+    let taken = min(kept, max_materials);
+
+    var top_ids = vec4<u32>(0u);
+    var top_weights = vec4<f32>(0.0);
+    for (var k = 0u; k < taken; k++) {
+        top_ids[k] = ids[k];
+        top_weights[k] = weights[k];
+    }
+
+    // Quantize to u8 weights summing to 255; last slot absorbs rounding error
+    let sum = top_weights.x + top_weights.y + top_weights.z + top_weights.w;
+    var quantized = vec4<u32>(0u);
+    var running = 0u;
+    for (var k = 0u; k + 1u < taken; k++) {
+        quantized[k] = min(u32(round(top_weights[k] / sum * 255.0)), 255u - running);
+        running += quantized[k];
+    }
+    quantized[taken - 1u] = 255u - running;
+
+    let packed_ids = top_ids.x | (top_ids.y << 8u) | (top_ids.z << 16u) | (top_ids.w << 24u);
+    let packed_weights = quantized.x | (quantized.y << 8u) | (quantized.z << 16u) | (quantized.w << 24u);
+    return vec2<u32>(packed_ids, packed_weights);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn place_vertices(@builtin(global_invocation_id) cell: vec3<u32>) {
+    let cells = params.field_size - vec3<u32>(1u);
+    if any(cell >= cells) {
+        return;
+    }
+
+    var corner_density: array<f32, 8>;
+    var inside_mask = 0u;
+    for (var i = 0u; i < 8u; i++) {
+        corner_density[i] = density[sample_index(cell + CORNERS[i])];
+        if corner_density[i] < 0.0 {
+            inside_mask |= 1u << i;
+        }
+    }
+
+    if inside_mask == 0u || inside_mask == 0xFFu {
+        cell_vertices[cell_index(cell)] = -1;
+        return;
+    }
+
+    // Average of edge crossings
+    var sum = vec3<f32>(0.0);
+    var crossings = 0.0;
+    for (var e = 0u; e < 12u; e++) {
+        let a = EDGES[e].x;
+        let b = EDGES[e].y;
+        let da = corner_density[a];
+        let db = corner_density[b];
+        if (da < 0.0) == (db < 0.0) {
+            continue;
+        }
+        let t = da / (da - db);
+        sum += mix(vec3<f32>(CORNERS[a]), vec3<f32>(CORNERS[b]), t);
+        crossings += 1.0;
+    }
+    let local = sum / crossings;
+
+    // Density gradient across the cell points outward (density is positive outside)
+    let gradient = vec3<f32>(
+        (corner_density[1] + corner_density[3] + corner_density[5] + corner_density[7])
+            - (corner_density[0] + corner_density[2] + corner_density[4] + corner_density[6]),
+        (corner_density[2] + corner_density[3] + corner_density[6] + corner_density[7])
+            - (corner_density[0] + corner_density[1] + corner_density[4] + corner_density[5]),
+        (corner_density[4] + corner_density[5] + corner_density[6] + corner_density[7])
+            - (corner_density[0] + corner_density[1] + corner_density[2] + corner_density[3]),
+    );
+    var normal = vec3<f32>(0.0, 1.0, 0.0);
+    if dot(gradient, gradient) > 1e-12 {
+        normal = normalize(gradient);
+    }
+
+    let vertex_index = atomicAdd(&counters.vertex_count, 1u);
+    if vertex_index >= params.max_vertices {
+        cell_vertices[cell_index(cell)] = -1;
+        return;
+    }
+
+    let material = blend_cell(cell, corner_density);
+    // The padding layer sits one cell before the chunk origin
+    let position = (vec3<f32>(cell) + local - vec3<f32>(1.0)) * params.mesh_scale;
+
+    vertices[vertex_index] = GpuVertex(
+        position.x, position.y, position.z,
+        normal.x, normal.y, normal.z,
+        material.x,
+        material.y,
+    );
+    cell_vertices[cell_index(cell)] = i32(vertex_index);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn emit_quads(@builtin(global_invocation_id) id: vec3<u32>) {
+    // Only edges starting on one of the chunk's own samples, so every edge
+    // on a border belongs to exactly one chunk
+    let owned = params.field_size - vec3<u32>(2u);
+    if any(id >= owned) {
+        return;
+    }
+    let p = id + vec3<u32>(1u);
+
+    let d0 = density[sample_index(p)];
+
+    for (var axis = 0u; axis < 3u; axis++) {
+        let b = (axis + 1u) % 3u;
+        let c = (axis + 2u) % 3u;
+
+        var step = vec3<u32>(0u);
+        step[axis] = 1u;
+        let d1 = density[sample_index(p + step)];
+        if (d0 < 0.0) == (d1 < 0.0) {
+            continue;
+        }
+
+        var off_b = vec3<u32>(0u);
+        off_b[b] = 1u;
+        var off_c = vec3<u32>(0u);
+        off_c[c] = 1u;
+
+        let v0 = cell_vertices[cell_index(p)];
+        let v1 = cell_vertices[cell_index(p - off_b)];
+        let v2 = cell_vertices[cell_index(p - off_b - off_c)];
+        let v3 = cell_vertices[cell_index(p - off_c)];
+        if v0 < 0 || v1 < 0 || v2 < 0 || v3 < 0 {
+            continue;
+        }
+
+        let base = atomicAdd(&counters.index_count, 6u);
+        if base + 6u > params.max_indices {
+            return;
+        }
+        // Face outward: flip winding depending on which side is solid
+        if d0 < 0.0 {
+            indices[base + 0u] = u32(v0);
+            indices[base + 1u] = u32(v1);
+            indices[base + 2u] = u32(v2);
+            indices[base + 3u] = u32(v0);
+            indices[base + 4u] = u32(v2);
+            indices[base + 5u] = u32(v3);
+        } else {
+            indices[base + 0u] = u32(v0);
+            indices[base + 1u] = u32(v2);
+            indices[base + 2u] = u32(v1);
+            indices[base + 3u] = u32(v0);
+            indices[base + 4u] = u32(v3);
+            indices[base + 5u] = u32(v2);
+        }
+    }
+}
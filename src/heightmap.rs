@@ -0,0 +1,276 @@
+//! Material layers for heightmap terrain.
+//!
+//! [`HeightmapMaterialLayer`] stores one painted material per heightmap
+//! cell, plus [`Stratum`] rules that expose banded rock on steep slopes.
+//! It computes the same per-vertex material attributes as voxel chunks, so
+//! heightfield meshes render with [`TriplanarVoxelMaterial`] and the same
+//! palette. The layer is a [`MaterialStorage`] one voxel tall, so the
+//! [`brush`](crate::material_field::brush) functions paint it directly:
+//!
+//! ```
+//! use bevy::prelude::*;
+//! use bevy_painter::heightmap::HeightmapMaterialLayer;
+//! use bevy_painter::material_field::brush;
+//!
+//! const GRASS: u8 = 0;
+//! const PATH: u8 = 3;
+//!
+//! let mut layer = HeightmapMaterialLayer::new(uvec2(64, 64), GRASS);
+//! // Cells are (x, 0, z) in brush space
+//! brush::paint_capsule(&mut layer, vec3(4.0, 0.0, 4.0), vec3(60.0, 0.0, 30.0), 2.0, PATH, None);
+//! assert_eq!(layer.get(uvec2(4, 4)), PATH);
+//! ```
+//!
+//! [`TriplanarVoxelMaterial`]: crate::material::TriplanarVoxelMaterial
+
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+
+use crate::material_field::MaterialStorage;
+use crate::mesh::{MeshTriplanarExt, VertexMaterialData};
+
+/// A band of rock exposed on steep slopes within a height range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stratum {
+    /// Lowest world-space height of the band.
+    pub min_height: f32,
+    /// Highest world-space height of the band.
+    pub max_height: f32,
+    /// Minimum surface slope in degrees for the band to show.
+    pub min_slope_degrees: f32,
+    pub material_id: u8,
+}
+
+impl Stratum {
+    /// Whether a vertex at `height` with up-component `normal_y` shows the band.
+    fn covers(&self, height: f32, normal_y: f32) -> bool {
+        (self.min_height..=self.max_height).contains(&height)
+            && normal_y <= self.min_slope_degrees.to_radians().cos()
+    }
+}
+
+/// Painted surface materials for a heightmap terrain.
+///
+/// One cell per heightmap sample, in X-Z order (X varies fastest). Vertex
+/// materials blend the four cells around each vertex bilinearly; the first
+/// [`Stratum`] covering a vertex overrides the painted material.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct HeightmapMaterialLayer {
+    size: UVec2,
+    materials: Vec<u8>,
+    /// Slope bands tried in order.
+    pub strata: Vec<Stratum>,
+}
+
+impl HeightmapMaterialLayer {
+    /// Creates a `size.x` by `size.y` layer painted with `material_id`.
+    pub fn new(size: UVec2, material_id: u8) -> Self {
+        Self {
+            size,
+            materials: vec![material_id; (size.x * size.y) as usize],
+            strata: Vec::new(),
+        }
+    }
+
+    /// Appends a stratum.
+    pub fn with_stratum(mut self, stratum: Stratum) -> Self {
+        self.strata.push(stratum);
+        self
+    }
+
+    /// Cells along X and Z.
+    pub fn cells(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Painted material of a cell, or 0 out of bounds.
+    pub fn get(&self, cell: UVec2) -> u8 {
+        self.index(cell).map_or(0, |index| self.materials[index])
+    }
+
+    /// Paints a cell; out-of-bounds cells are ignored.
+    pub fn set(&mut self, cell: UVec2, material_id: u8) {
+        if let Some(index) = self.index(cell) {
+            self.materials[index] = material_id;
+        }
+    }
+
+    fn index(&self, cell: UVec2) -> Option<usize> {
+        cell.cmplt(self.size)
+            .all()
+            .then(|| (cell.x + cell.y * self.size.x) as usize)
+    }
+
+    /// Material data for a vertex at `cell_pos` (fractional cell
+    /// coordinates), world-space `height` and surface `normal`.
+    pub fn vertex_material(&self, cell_pos: Vec2, height: f32, normal: Vec3) -> VertexMaterialData {
+        let normal_y = normal.normalize_or(Vec3::Y).y;
+        if let Some(stratum) = self
+            .strata
+            .iter()
+            .find(|stratum| stratum.covers(height, normal_y))
+        {
+            return VertexMaterialData::single(stratum.material_id);
+        }
+
+        let max = self.size.as_vec2() - Vec2::ONE;
+        let pos = cell_pos.clamp(Vec2::ZERO, max.max(Vec2::ZERO));
+        let base = pos.floor();
+        let t = pos - base;
+        let base = base.as_uvec2();
+        let next = (base + UVec2::ONE).min(self.size.saturating_sub(UVec2::ONE));
+
+        // Bilinear weights, merged per material
+        let corners = [
+            (self.get(base), (1.0 - t.x) * (1.0 - t.y)),
+            (self.get(uvec2(next.x, base.y)), t.x * (1.0 - t.y)),
+            (self.get(uvec2(base.x, next.y)), (1.0 - t.x) * t.y),
+            (self.get(next), t.x * t.y),
+        ];
+        let mut merged: Vec<(u8, f32)> = Vec::with_capacity(4);
+        for (material, weight) in corners.into_iter().filter(|&(_, weight)| weight > 0.0) {
+            match merged.iter_mut().find(|(id, _)| *id == material) {
+                Some((_, total)) => *total += weight,
+                None => merged.push((material, weight)),
+            }
+        }
+        merged.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let mut ids = [0; 4];
+        let mut weights = [0.0; 4];
+        for (slot, (material, weight)) in merged.into_iter().enumerate() {
+            ids[slot] = material;
+            weights[slot] = weight;
+        }
+        VertexMaterialData::blend4(ids, weights)
+    }
+
+    /// Material data for every vertex of a heightfield mesh.
+    ///
+    /// Mesh positions are local to the layer, with cell `(x, z)` at
+    /// `(x * cell_size.x, _, z * cell_size.y)`. Returns `None` if the mesh
+    /// lacks float positions.
+    pub fn compute_mesh_materials(
+        &self,
+        mesh: &Mesh,
+        cell_size: Vec2,
+    ) -> Option<Vec<VertexMaterialData>> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => Some(normals),
+            _ => None,
+        };
+        Some(
+            positions
+                .iter()
+                .enumerate()
+                .map(|(i, &[x, y, z])| {
+                    let normal = normals.map_or(Vec3::Y, |normals| Vec3::from_array(normals[i]));
+                    self.vertex_material(vec2(x, z) / cell_size, y, normal)
+                })
+                .collect(),
+        )
+    }
+
+    /// Adds material attributes to a heightfield mesh.
+    ///
+    /// See [`compute_mesh_materials`](Self::compute_mesh_materials). Meshes
+    /// without float positions are returned unchanged.
+    pub fn apply_to_mesh(&self, mesh: Mesh, cell_size: Vec2) -> Mesh {
+        match self.compute_mesh_materials(&mesh, cell_size) {
+            Some(materials) => mesh.with_triplanar_materials(&materials),
+            None => mesh,
+        }
+    }
+}
+
+/// Cells map to `(x, 0, z)`; other heights are out of bounds.
+impl MaterialStorage for HeightmapMaterialLayer {
+    fn size(&self) -> UVec3 {
+        uvec3(self.size.x, 1, self.size.y)
+    }
+
+    fn get(&self, pos: UVec3) -> u8 {
+        if pos.y != 0 {
+            return 0;
+        }
+        HeightmapMaterialLayer::get(self, pos.xz())
+    }
+
+    fn set(&mut self, pos: UVec3, material_id: u8) {
+        if pos.y == 0 {
+            HeightmapMaterialLayer::set(self, pos.xz(), material_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::PrimitiveTopology;
+
+    use super::*;
+    use crate::mesh::ATTRIBUTE_MATERIAL_IDS;
+
+    const GRASS: u8 = 1;
+    const SAND: u8 = 2;
+    const CLIFF: u8 = 5;
+
+    #[test]
+    fn test_vertex_blends_neighboring_cells() {
+        let mut layer = HeightmapMaterialLayer::new(uvec2(4, 4), GRASS);
+        layer.set(uvec2(1, 0), SAND);
+
+        assert_eq!(
+            layer.vertex_material(vec2(0.0, 0.0), 0.0, Vec3::Y),
+            VertexMaterialData::single(GRASS)
+        );
+        let halfway = layer.vertex_material(vec2(0.5, 0.0), 0.0, Vec3::Y);
+        assert!(halfway.ids[..2].contains(&SAND));
+        assert!(halfway.weights[0].abs_diff(halfway.weights[1]) <= 1);
+        // Past the edge clamps to the last cell
+        assert_eq!(
+            layer.vertex_material(vec2(9.0, 9.0), 0.0, Vec3::Y).ids[0],
+            GRASS
+        );
+    }
+
+    #[test]
+    fn test_strata_show_on_steep_slopes() {
+        let layer = HeightmapMaterialLayer::new(uvec2(4, 4), GRASS).with_stratum(Stratum {
+            min_height: 10.0,
+            max_height: 20.0,
+            min_slope_degrees: 45.0,
+            material_id: CLIFF,
+        });
+        let steep = vec3(1.0, 0.3, 0.0);
+        assert_eq!(layer.vertex_material(Vec2::ONE, 15.0, steep).ids[0], CLIFF);
+        assert_eq!(
+            layer.vertex_material(Vec2::ONE, 15.0, Vec3::Y).ids[0],
+            GRASS
+        );
+        assert_eq!(layer.vertex_material(Vec2::ONE, 25.0, steep).ids[0], GRASS);
+    }
+
+    #[test]
+    fn test_apply_to_mesh() {
+        let mut layer = HeightmapMaterialLayer::new(uvec2(2, 2), GRASS);
+        layer.set(uvec2(1, 1), SAND);
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [4.0, 0.0, 4.0], [4.0, 0.0, 0.0]],
+        );
+
+        let mesh = layer.apply_to_mesh(mesh, Vec2::splat(4.0));
+        let Some(VertexAttributeValues::Uint32(ids)) = mesh.attribute(ATTRIBUTE_MATERIAL_IDS)
+        else {
+            panic!("missing material ids");
+        };
+        assert_eq!(ids[0] & 0xFF, GRASS as u32);
+        assert_eq!(ids[1] & 0xFF, SAND as u32);
+    }
+}
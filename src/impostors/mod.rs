@@ -0,0 +1,596 @@
+//! Billboard impostors for extremely distant chunk clusters.
+//!
+//! With the `impostors` feature, [`ImpostorPlugin`] replaces far
+//! [`ImpostorCluster`]s with a single camera-facing quad:
+//! - a cluster beyond [`ImpostorBaker::distance`] from every
+//!   [`ImpostorViewer`] camera is rendered once, with its own triplanar
+//!   materials, into a texture by an orthographic bake camera
+//! - the bake camera only sees [`ImpostorProxy`] copies of the cluster's
+//!   chunks, drawn on a render layer no other camera uses
+//! - once baked, the chunks are hidden and an [`ImpostorBillboard`] quad
+//!   textured with the bake turns to face the viewer every frame
+//! - when the direction to the viewer drifts more than
+//!   [`ImpostorBaker::rebake_angle`] from the baked one, the cluster is
+//!   baked again from the new direction
+//! - when the viewer comes closer, the billboard is removed and the chunks
+//!   are shown again
+//!
+//! Bakes are lit by the lights that include [`ImpostorBaker::render_layer`]
+//! in their `RenderLayers`, so add it to the sun. Only chunks with a
+//! [`TriplanarVoxelMaterial`] are baked.
+
+use bevy::camera::primitives::Aabb;
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::{RenderTarget, ScalingMode};
+use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
+use bevy::light::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use bevy::transform::TransformSystems;
+
+use crate::material::TriplanarVoxelMaterial;
+
+/// Marks a camera impostors are placed and baked for.
+///
+/// With several viewers, such as split-screen players, each cluster
+/// follows its nearest one: it turns into an impostor once every viewer is
+/// far away, and its billboard faces the nearest of them.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ImpostorViewer;
+
+/// Chunks drawn as one billboard while far from the [`ImpostorViewer`].
+///
+/// Put on any entity, e.g. a region of 4×4×4 chunks. Chunks need a
+/// `Mesh3d`, a `MeshMaterial3d<TriplanarVoxelMaterial>` and their `Aabb`.
+///
+/// # Example
+/// ```ignore
+/// commands.spawn((Name::new("Far ridge"), ImpostorCluster::new(chunks)));
+/// ```
+#[derive(Component, Clone, Debug, Default)]
+pub struct ImpostorCluster {
+    /// Chunk entities in the cluster.
+    pub chunks: Vec<Entity>,
+}
+
+impl ImpostorCluster {
+    pub fn new(chunks: Vec<Entity>) -> Self {
+        Self { chunks }
+    }
+}
+
+/// Impostor state of a cluster, inserted on the [`ImpostorCluster`] entity
+/// when it first bakes and removed when it is restored.
+#[derive(Component, Debug)]
+pub struct Impostor {
+    image: Handle<Image>,
+    billboard: Entity,
+    /// Direction from the viewer to the cluster of the last finished bake.
+    baked_direction: Option<Dir3>,
+    bake: Option<ImpostorBake>,
+    /// Chunks hidden behind the billboard and their previous visibility.
+    hidden: Vec<(Entity, Visibility)>,
+}
+
+impl Impostor {
+    /// The baked texture.
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+
+    /// The billboard entity.
+    pub fn billboard(&self) -> Entity {
+        self.billboard
+    }
+
+    /// View direction of the last finished bake, `None` until the first
+    /// bake finishes.
+    pub fn baked_direction(&self) -> Option<Dir3> {
+        self.baked_direction
+    }
+
+    /// Whether a bake is rendering.
+    pub fn is_baking(&self) -> bool {
+        self.bake.is_some()
+    }
+}
+
+/// A bake in flight.
+#[derive(Debug)]
+struct ImpostorBake {
+    camera: Entity,
+    proxies: Vec<Entity>,
+    direction: Dir3,
+    frames_left: u32,
+}
+
+/// Child drawing its parent chunk for an impostor bake camera.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ImpostorProxy;
+
+/// Camera-facing quad showing a baked [`Impostor`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ImpostorBillboard {
+    /// The [`ImpostorCluster`] entity.
+    pub cluster: Entity,
+}
+
+/// Settings of impostor baking.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct ImpostorBaker {
+    /// Viewer distance to a cluster's center beyond which it becomes an
+    /// impostor. Clusters are restored within 90% of it.
+    /// Default: 512.0
+    pub distance: f32,
+    /// Degrees the direction to the viewer may drift from the baked one
+    /// before the cluster is baked again.
+    /// Default: 10.0
+    pub rebake_angle: f32,
+    /// Width and height of each cluster's texture.
+    /// Default: 256
+    pub resolution: u32,
+    /// Bakes rendering at the same time.
+    /// Default: 2
+    pub max_concurrent_bakes: usize,
+    /// Frames a bake camera renders before the result is shown, leaving
+    /// time for the bake view's pipelines to compile.
+    /// Default: 3
+    pub bake_frames: u32,
+    /// Render layer of the bake proxies and cameras.
+    /// Default: 30
+    pub render_layer: usize,
+}
+
+impl Default for ImpostorBaker {
+    fn default() -> Self {
+        Self {
+            distance: 512.0,
+            rebake_angle: 10.0,
+            resolution: 256,
+            max_concurrent_bakes: 2,
+            bake_frames: 3,
+            render_layer: 30,
+        }
+    }
+}
+
+impl ImpostorBaker {
+    pub fn with_distance(mut self, distance: f32) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    pub fn with_rebake_angle(mut self, degrees: f32) -> Self {
+        self.rebake_angle = degrees;
+        self
+    }
+
+    pub fn with_resolution(mut self, resolution: u32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Whether a cluster baked from `baked` needs a new bake seen from
+    /// `current`.
+    fn needs_rebake(&self, baked: Dir3, current: Dir3) -> bool {
+        baked.angle_between(*current) > self.rebake_angle.to_radians()
+    }
+}
+
+/// Plugin adding impostor baking.
+///
+/// Insert an [`ImpostorBaker`] before adding it to change the defaults.
+pub struct ImpostorPlugin;
+
+impl Plugin for ImpostorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImpostorBaker>()
+            .register_type::<ImpostorBaker>()
+            .register_type::<ImpostorViewer>()
+            .add_systems(
+                PostUpdate,
+                (update_impostors, face_impostor_billboards)
+                    .chain()
+                    .after(TransformSystems::Propagate),
+            );
+    }
+}
+
+/// World-space bounding sphere of `chunks`, from their `Aabb`s.
+fn cluster_bounds(chunks: impl Iterator<Item = (GlobalTransform, Aabb)>) -> Option<(Vec3, f32)> {
+    let mut min = Vec3::INFINITY;
+    let mut max = Vec3::NEG_INFINITY;
+    for (transform, aabb) in chunks {
+        let (center, half) = (Vec3::from(aabb.center), Vec3::from(aabb.half_extents));
+        for i in 0..8 {
+            let sign = BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0);
+            let corner = center + half * Vec3::select(sign, Vec3::ONE, Vec3::NEG_ONE);
+            let corner = transform.transform_point(corner);
+            min = min.min(corner);
+            max = max.max(corner);
+        }
+    }
+    min.cmple(max)
+        .all()
+        .then(|| ((min + max) * 0.5, (max - min).length() * 0.5))
+}
+
+/// Transform looking along `direction` from `position`, with a fallback up
+/// axis for vertical views.
+fn looking_along(position: Vec3, direction: Dir3) -> Transform {
+    let up = if direction.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    };
+    Transform::from_translation(position).looking_to(direction, up)
+}
+
+/// System starting, finishing and undoing impostor bakes.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn update_impostors(
+    mut commands: Commands,
+    baker: Res<ImpostorBaker>,
+    viewers: Query<&GlobalTransform, With<ImpostorViewer>>,
+    mut clusters: Query<(Entity, &ImpostorCluster, Option<&mut Impostor>)>,
+    chunks: Query<(
+        &GlobalTransform,
+        &Aabb,
+        &Mesh3d,
+        &MeshMaterial3d<TriplanarVoxelMaterial>,
+        Option<&Visibility>,
+    )>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let viewers: Vec<Vec3> = viewers.iter().map(|v| v.translation()).collect();
+    if viewers.is_empty() {
+        return;
+    }
+    let mut bakes = clusters
+        .iter()
+        .filter(|(.., impostor)| impostor.as_ref().is_some_and(|i| i.is_baking()))
+        .count();
+
+    for (entity, cluster, impostor) in &mut clusters {
+        let Some((center, radius)) = cluster_bounds(
+            cluster
+                .chunks
+                .iter()
+                .filter_map(|&chunk| chunks.get(chunk).ok())
+                .map(|(transform, aabb, ..)| (*transform, *aabb)),
+        ) else {
+            continue;
+        };
+        let viewer = nearest_viewer(&viewers, center);
+        let distance = viewer.distance(center);
+        let Ok(direction) = Dir3::new(center - viewer) else {
+            continue;
+        };
+
+        let Some(mut impostor) = impostor else {
+            if distance > baker.distance && bakes < baker.max_concurrent_bakes {
+                let image = images.add(Image::new_target_texture(
+                    baker.resolution,
+                    baker.resolution,
+                    TextureFormat::Rgba8UnormSrgb,
+                ));
+                let material = materials.add(StandardMaterial {
+                    base_color_texture: Some(image.clone()),
+                    unlit: true,
+                    alpha_mode: AlphaMode::Mask(0.5),
+                    ..default()
+                });
+                let billboard = commands
+                    .spawn((
+                        Name::new("Impostor billboard"),
+                        Mesh3d(meshes.add(Rectangle::from_length(radius * 2.0))),
+                        MeshMaterial3d(material),
+                        looking_along(center, direction),
+                        Visibility::Hidden,
+                        NotShadowCaster,
+                        ImpostorBillboard { cluster: entity },
+                    ))
+                    .id();
+                let mut impostor = Impostor {
+                    image,
+                    billboard,
+                    baked_direction: None,
+                    bake: None,
+                    hidden: Vec::new(),
+                };
+                start_bake(
+                    &mut commands,
+                    &baker,
+                    &mut impostor,
+                    cluster,
+                    &chunks,
+                    (center, radius),
+                    direction,
+                );
+                commands.entity(entity).insert(impostor);
+                bakes += 1;
+            }
+            continue;
+        };
+
+        if distance < baker.distance * 0.9 {
+            // Viewer came back; show the chunks again
+            if let Some(bake) = impostor.bake.take() {
+                despawn_bake(&mut commands, bake);
+                bakes -= 1;
+            }
+            for &(chunk, visibility) in &impostor.hidden {
+                if let Ok(mut chunk) = commands.get_entity(chunk) {
+                    chunk.insert(visibility);
+                }
+            }
+            commands.entity(impostor.billboard).try_despawn();
+            commands.entity(entity).remove::<Impostor>();
+            continue;
+        }
+
+        if let Some(bake) = &mut impostor.bake
+            && bake.frames_left > 0
+        {
+            bake.frames_left -= 1;
+        } else if let Some(bake) = impostor.bake.take() {
+            impostor.baked_direction = Some(bake.direction);
+            despawn_bake(&mut commands, bake);
+            bakes -= 1;
+            // First bake: swap the chunks for the billboard
+            if impostor.hidden.is_empty() {
+                impostor.hidden = cluster
+                    .chunks
+                    .iter()
+                    .filter_map(|&chunk| {
+                        let (.., visibility) = chunks.get(chunk).ok()?;
+                        Some((chunk, visibility.copied().unwrap_or_default()))
+                    })
+                    .collect();
+                for &(chunk, _) in &impostor.hidden {
+                    commands.entity(chunk).insert(Visibility::Hidden);
+                }
+                commands
+                    .entity(impostor.billboard)
+                    .insert(Visibility::Visible);
+            }
+        } else if bakes < baker.max_concurrent_bakes
+            && impostor
+                .baked_direction
+                .is_some_and(|baked| baker.needs_rebake(baked, direction))
+        {
+            start_bake(
+                &mut commands,
+                &baker,
+                &mut impostor,
+                cluster,
+                &chunks,
+                (center, radius),
+                direction,
+            );
+            bakes += 1;
+        }
+    }
+}
+
+/// Spawn the bake camera and chunk proxies rendering `cluster` into the
+/// impostor's image, seen along `direction`.
+#[allow(clippy::type_complexity)]
+fn start_bake(
+    commands: &mut Commands,
+    baker: &ImpostorBaker,
+    impostor: &mut Impostor,
+    cluster: &ImpostorCluster,
+    chunks: &Query<(
+        &GlobalTransform,
+        &Aabb,
+        &Mesh3d,
+        &MeshMaterial3d<TriplanarVoxelMaterial>,
+        Option<&Visibility>,
+    )>,
+    (center, radius): (Vec3, f32),
+    direction: Dir3,
+) {
+    let layer = RenderLayers::layer(baker.render_layer);
+    let proxies = cluster
+        .chunks
+        .iter()
+        .filter_map(|&chunk| {
+            let (_, _, mesh, material, _) = chunks.get(chunk).ok()?;
+            let proxy = commands
+                .spawn((
+                    Mesh3d(mesh.0.clone()),
+                    MeshMaterial3d(material.0.clone()),
+                    // Shown even while the chunk itself is hidden
+                    Visibility::Visible,
+                    NotShadowCaster,
+                    layer.clone(),
+                    ImpostorProxy,
+                    ChildOf(chunk),
+                ))
+                .id();
+            Some(proxy)
+        })
+        .collect();
+
+    let camera = commands
+        .spawn((
+            Name::new("Impostor bake camera"),
+            Camera3d::default(),
+            Camera {
+                order: -2,
+                target: RenderTarget::Image(impostor.image.clone().into()),
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+                ..default()
+            },
+            Projection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: radius * 2.0,
+                    height: radius * 2.0,
+                },
+                near: 0.0,
+                far: radius * 4.0,
+                ..OrthographicProjection::default_3d()
+            }),
+            looking_along(center - direction * (radius * 2.0), direction),
+            Msaa::Off,
+            // The billboard is tonemapped with the rest of the scene
+            Tonemapping::None,
+            DebandDither::Disabled,
+            layer,
+        ))
+        .id();
+
+    impostor.bake = Some(ImpostorBake {
+        camera,
+        proxies,
+        direction,
+        frames_left: baker.bake_frames,
+    });
+}
+
+fn despawn_bake(commands: &mut Commands, bake: ImpostorBake) {
+    commands.entity(bake.camera).try_despawn();
+    for proxy in bake.proxies {
+        commands.entity(proxy).try_despawn();
+    }
+}
+
+/// Position of the viewer in `viewers` nearest `point`.
+fn nearest_viewer(viewers: &[Vec3], point: Vec3) -> Vec3 {
+    viewers
+        .iter()
+        .copied()
+        .min_by(|a, b| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        })
+        .unwrap_or(point)
+}
+
+/// System turning [`ImpostorBillboard`]s towards their nearest
+/// [`ImpostorViewer`].
+pub fn face_impostor_billboards(
+    viewers: Query<&GlobalTransform, With<ImpostorViewer>>,
+    mut billboards: Query<&mut Transform, With<ImpostorBillboard>>,
+) {
+    let viewers: Vec<Vec3> = viewers.iter().map(|v| v.translation()).collect();
+    if viewers.is_empty() {
+        return;
+    }
+    for mut transform in &mut billboards {
+        let viewer = nearest_viewer(&viewers, transform.translation);
+        if let Ok(direction) = Dir3::new(transform.translation - viewer) {
+            *transform = looking_along(transform.translation, direction);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_bounds_and_rebake_angle() {
+        let aabb = Aabb::from_min_max(Vec3::ZERO, Vec3::splat(32.0));
+        let (center, radius) = cluster_bounds(
+            [
+                (GlobalTransform::IDENTITY, aabb),
+                (
+                    GlobalTransform::from_translation(vec3(32.0, 0.0, 0.0)),
+                    aabb,
+                ),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        assert!(center.abs_diff_eq(vec3(32.0, 16.0, 16.0), 1e-4));
+        assert!((radius - vec3(64.0, 32.0, 32.0).length() * 0.5).abs() < 1e-4);
+        assert_eq!(cluster_bounds(std::iter::empty()), None);
+
+        let baker = ImpostorBaker::default();
+        let rotated = Dir3::new(Quat::from_rotation_y(0.1) * Vec3::X).unwrap();
+        assert!(!baker.needs_rebake(Dir3::X, rotated));
+        assert!(baker.needs_rebake(Dir3::X, Dir3::Z));
+    }
+
+    #[test]
+    fn test_clusters_follow_nearest_viewer() {
+        let viewers = [vec3(-500.0, 0.0, 0.0), vec3(400.0, 0.0, 0.0)];
+        assert_eq!(nearest_viewer(&viewers, vec3(1000.0, 0.0, 0.0)), viewers[1]);
+        assert_eq!(nearest_viewer(&viewers, vec3(-60.0, 0.0, 0.0)), viewers[0]);
+    }
+
+    #[test]
+    fn test_far_cluster_bakes_then_restores() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .init_asset::<TriplanarVoxelMaterial>()
+            .insert_resource(ImpostorBaker {
+                bake_frames: 1,
+                ..default()
+            })
+            .add_systems(Update, update_impostors);
+
+        let viewer = app
+            .world_mut()
+            .spawn((ImpostorViewer, GlobalTransform::IDENTITY))
+            .id();
+        let chunk = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(1000.0, 0.0, 0.0)),
+                Aabb::from_min_max(Vec3::splat(-16.0), Vec3::splat(16.0)),
+                Mesh3d::default(),
+                MeshMaterial3d::<TriplanarVoxelMaterial>::default(),
+            ))
+            .id();
+        let cluster = app
+            .world_mut()
+            .spawn(ImpostorCluster::new(vec![chunk]))
+            .id();
+
+        app.update();
+        let impostor = app.world().get::<Impostor>(cluster).unwrap();
+        assert!(impostor.is_baking());
+        assert_eq!(impostor.baked_direction(), None);
+        let mut proxies = app
+            .world_mut()
+            .query_filtered::<Entity, With<ImpostorProxy>>();
+        assert_eq!(proxies.iter(app.world()).count(), 1);
+
+        // One frame rendering, then the billboard replaces the chunk
+        app.update();
+        app.update();
+        let impostor = app.world().get::<Impostor>(cluster).unwrap();
+        assert!(!impostor.is_baking());
+        assert_eq!(impostor.baked_direction(), Some(Dir3::X));
+        let billboard = impostor.billboard();
+        assert_eq!(proxies.iter(app.world()).count(), 0);
+        assert_eq!(
+            app.world().get::<Visibility>(chunk),
+            Some(&Visibility::Hidden)
+        );
+        assert_eq!(
+            app.world().get::<Visibility>(billboard),
+            Some(&Visibility::Visible)
+        );
+
+        *app.world_mut().get_mut::<GlobalTransform>(viewer).unwrap() =
+            GlobalTransform::from_translation(vec3(900.0, 0.0, 0.0));
+        app.update();
+        assert!(app.world().get::<Impostor>(cluster).is_none());
+        assert!(app.world().get_entity(billboard).is_err());
+        assert_eq!(
+            app.world().get::<Visibility>(chunk),
+            Some(&Visibility::Inherited)
+        );
+    }
+}
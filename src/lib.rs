@@ -10,26 +10,82 @@
 //! - **Texture arrays**: Efficient GPU texture atlas for material palettes
 //! - **PBR support**: Optional normal and ARM (AO/Roughness/Metallic) maps
 //! - **Per-material properties**: Individual texture scale and blend sharpness
+//! - **Global overrides**: Runtime texture scale, sharpness and debug view tweaks for all materials
+//! - **Quality tiers**: One setting to scale shader cost via pipeline specialization
+//! - **Terrain color grading**: Optional 3D LUT graded onto terrain only
+//! - **Toon shading**: Banded lighting and rim light as an alternative to PBR
+//! - **Grass shells**: Shell-textured fur over grass-flagged materials, drawn instanced
+//! - **Unlit variant**: A cheap unlit material for stylized games and far LODs, selectable per entity
+//! - **Baked lightmaps**: Bevy's `Lightmap` on terrain meshes with a second UV set
+//! - **Instanced props**: Per-instance material overrides without breaking batching
+//! - **Profiling**: Tracing spans around painter work, and remesh cost as Bevy `Diagnostic`s
+//! - **GPU meshing** (`gpu_meshing` feature): Surface nets and material blending in compute shaders
+//! - **Baked fallback**: Chunks baked to a single texture on a plain `StandardMaterial`
+//! - **MagicaVoxel import** (`vox` feature): `.vox` models as density and material fields
+//! - **glTF export** (`export` feature): Painted meshes with materials baked into vertex colors, and navmesh costs by material
+//! - **Terrain aging** (`simulation` feature): Rules that spread, melt and grow materials over time
+//! - **Seamless noise** (`noise` feature): World-space noise, strata, patches, scattering and ore veins without chunk seams
+//! - **Heightmap terrain** (`heightmap` feature): Painted material layers and strata for heightfield meshes
+//! - **Detail scattering** (`scatter` feature): Grass and props placed by painted material and slope
+//! - **Impostors** (`impostors` feature): Far chunk clusters baked to camera-facing billboards
+//! - **Material picking** (`picking` feature): Exact per-pixel material and chunk under the cursor, read back from the GPU, and a `bevy_picking` backend for chunks
 
+pub mod bake;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "gpu_meshing")]
+pub mod gpu_meshing;
+#[cfg(feature = "heightmap")]
+pub mod heightmap;
+#[cfg(feature = "impostors")]
+pub mod impostors;
 pub mod material;
 #[cfg(feature = "material_field")]
 pub mod material_field;
 pub mod mesh;
+#[cfg(feature = "noise")]
+pub mod noise;
 pub mod palette;
+#[cfg(feature = "picking")]
+pub mod picking;
 mod plugin;
+#[cfg(feature = "scatter")]
+pub mod scatter;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+#[cfg(feature = "vox")]
+pub mod vox;
 
 pub use plugin::TriplanarVoxelPlugin;
 
 /// Prelude module with commonly used types.
 pub mod prelude {
     pub use crate::TriplanarVoxelPlugin;
-    pub use crate::material::{TriplanarExtension, TriplanarSettings, TriplanarVoxelMaterial};
+    #[cfg(feature = "impostors")]
+    pub use crate::impostors::{ImpostorBaker, ImpostorCluster, ImpostorPlugin, ImpostorViewer};
+    pub use crate::material::{
+        DisableNormalMaps, DissolveStyle, ForceBiplanar, GlobalTriplanarOverrides, GrassShells,
+        InstanceMaterialOverride, MaterialReveal, RenderGrassShells, RenderUnlit, ShadingMode,
+        SimplifiedMaterials, TriplanarDebugMode, TriplanarExtension, TriplanarQualitySettings,
+        TriplanarQualityTier, TriplanarSettings, TriplanarUnlitMaterial, TriplanarVoxelMaterial,
+    };
     pub use crate::mesh::{
-        ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS, MeshTriplanarExt, TriplanarMeshBuilder,
-        VertexMaterialData,
+        ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_PARAMS, ATTRIBUTE_MATERIAL_WEIGHTS,
+        DoubleBufferedMesh, MaterialParam, MaterialUsage, MeshMaterialQueryExt, MeshTriplanarExt,
+        PendingMesh, TriplanarMeshBuilder, VertexMaterialData,
+    };
+    #[cfg(feature = "material_field")]
+    pub use crate::material_field::{
+        AreaEffects, BrushFilter, BrushShape, MaterialMask, MaterialParamsField, PaintCommand,
+        PaintStroke, PainterDiagnosticsPlugin, ParamPaintCommand, ScorchConfig, SplineConfig,
     };
-    pub use crate::palette::{MAX_MATERIALS, MaterialPropertiesGpu};
+    pub use crate::palette::{MAX_MATERIALS, MaterialPropertiesGpu, PaletteStreaming};
+    #[cfg(feature = "picking")]
+    pub use crate::picking::{MaterialPicker, MaterialPickingPlugin, MaterialPickingSource};
+    #[cfg(all(feature = "picking", feature = "material_field"))]
+    pub use crate::picking::{ChunkPickingPlugin, ChunkPointerHits};
+    #[cfg(feature = "scatter")]
+    pub use crate::scatter::{ScatterRule, ScatterSurface, SurfaceScatter, SurfaceScatterPlugin};
+    #[cfg(feature = "simulation")]
+    pub use crate::simulation::{MaterialSimulation, MaterialSimulationPlugin};
 }
-/// Shader asset path (embedded).
-const TRIPLANAR_SHADER_PATH: &str =
-    "embedded://bevy-painter/material/shaders/triplanar_extension.wgsl";
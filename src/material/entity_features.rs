@@ -0,0 +1,408 @@
+//! Per-entity opt-outs of expensive shader features.
+//!
+//! Components like [`DisableNormalMaps`] and [`ForceBiplanar`] turn off
+//! features for a single mesh, [`SimplifiedMaterials`] drops its texture
+//! detail, and [`MaterialReveal`] dissolves it in or
+//! out, without the user creating extra material assets. [`MeshTag`](bevy::mesh::MeshTag) already carries
+//! [`InstanceMaterialOverride`](super::InstanceMaterialOverride), so the
+//! flags can't travel per instance. Instead the plugin keeps one shared
+//! variant of each material per feature set and points the entity at it;
+//! entities with the same material and components still batch together.
+//!
+//! Variants follow edits to their base material. Removing the components
+//! switches the entity back to the base material.
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use super::extension::TriplanarVoxelMaterial;
+
+/// Disable triplanar normal mapping on this entity.
+///
+/// Saves three normal map samples per material per pixel, e.g. for distant
+/// chunks or small props.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct DisableNormalMaps;
+
+/// Sample albedo with the two dominant projections only on this entity.
+///
+/// Has no effect if the material already uses biplanar albedo.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ForceBiplanar;
+
+/// Render this entity with the cheap far-terrain material path.
+///
+/// Samples blurrier mips through
+/// [`TriplanarExtension::simplified_mip_bias`](super::TriplanarExtension::simplified_mip_bias),
+/// saving texture bandwidth. With the `material_field` feature, remeshed
+/// chunks also take one dominant material per vertex instead of a blend,
+/// which is much cheaper to compute and makes the shader sample a single
+/// material. [`MaterialSimplification`](crate::material_field::MaterialSimplification)
+/// adds and removes it by camera distance.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct SimplifiedMaterials;
+
+/// Dissolve this entity in or out, from 0 (hidden) to 1 (fully shown).
+///
+/// Drives [`TriplanarExtension::reveal`](super::TriplanarExtension::reveal)
+/// with a world-space noise dissolve and a glowing front, styled by the
+/// material's [`DissolveStyle`](super::DissolveStyle). Animate it when
+/// chunks stream in, or towards 0 before despawning destroyed terrain.
+/// Values are quantized to 64 steps, each sharing one material variant.
+///
+/// The depth prepass and shadow maps use the full surface, so dissolving
+/// chunks still cast shadows.
+///
+/// # Example
+/// ```ignore
+/// fn fade_in(time: Res<Time>, mut chunks: Query<&mut MaterialReveal>) {
+///     for mut reveal in &mut chunks {
+///         if reveal.0 < 1.0 {
+///             reveal.0 = (reveal.0 + time.delta_secs()).min(1.0);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct MaterialReveal(pub f32);
+
+impl Default for MaterialReveal {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl MaterialReveal {
+    /// Fully dissolved, e.g. for freshly spawned chunks.
+    pub const HIDDEN: Self = Self(0.0);
+
+    /// Quantized reveal step; [`REVEAL_STEPS`] is fully shown.
+    fn step(self) -> u8 {
+        (self.0.clamp(0.0, 1.0) * REVEAL_STEPS as f32).round() as u8
+    }
+}
+
+/// Number of distinct [`MaterialReveal`] levels.
+const REVEAL_STEPS: u8 = 64;
+
+/// Feature bits derived from the per-entity components.
+const DISABLE_NORMAL_MAPS: u8 = 1 << 0;
+const FORCE_BIPLANAR: u8 = 1 << 1;
+const SIMPLIFIED: u8 = 1 << 2;
+
+/// The base material of an entity currently using a feature variant.
+#[derive(Component, Clone, Debug)]
+pub struct TriplanarMaterialVariant {
+    /// Material the entity was given before variants were applied.
+    pub base: Handle<TriplanarVoxelMaterial>,
+    variant: Handle<TriplanarVoxelMaterial>,
+}
+
+/// Base material, feature bits and reveal step.
+type VariantKey = (AssetId<TriplanarVoxelMaterial>, u8, u8);
+
+/// Shared feature variants, keyed by base material, feature bits and
+/// reveal step.
+#[derive(Resource, Default)]
+pub struct TriplanarMaterialVariants {
+    variants: HashMap<VariantKey, Handle<TriplanarVoxelMaterial>>,
+}
+
+impl TriplanarMaterialVariants {
+    /// Number of variant materials currently alive.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Whether no variants exist.
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+}
+
+/// Apply feature bits and a reveal step to a copy of the base material.
+fn make_variant(
+    base: &TriplanarVoxelMaterial,
+    features: u8,
+    reveal_step: u8,
+) -> TriplanarVoxelMaterial {
+    let mut variant = base.clone();
+    if features & DISABLE_NORMAL_MAPS != 0 {
+        variant.extension.enable_normal_maps = false;
+    }
+    if features & FORCE_BIPLANAR != 0 {
+        variant.extension.use_biplanar_color = true;
+    }
+    if features & SIMPLIFIED != 0 {
+        variant.extension.mip_bias += base.extension.simplified_mip_bias;
+    }
+    if reveal_step < REVEAL_STEPS {
+        variant.extension.reveal = base
+            .extension
+            .reveal
+            .min(reveal_step as f32 / REVEAL_STEPS as f32);
+    }
+    variant
+}
+
+/// System that points entities with feature components at shared material variants.
+#[allow(clippy::type_complexity)]
+pub fn apply_entity_feature_overrides(
+    mut commands: Commands,
+    mut queries: ParamSet<(
+        Query<
+            Entity,
+            Or<(
+                Changed<MeshMaterial3d<TriplanarVoxelMaterial>>,
+                Added<DisableNormalMaps>,
+                Added<ForceBiplanar>,
+                Added<SimplifiedMaterials>,
+                Changed<MaterialReveal>,
+            )>,
+        >,
+        Query<(
+            Entity,
+            &mut MeshMaterial3d<TriplanarVoxelMaterial>,
+            Has<DisableNormalMaps>,
+            Has<ForceBiplanar>,
+            Has<SimplifiedMaterials>,
+            Option<&MaterialReveal>,
+            Option<&TriplanarMaterialVariant>,
+        )>,
+    )>,
+    mut removed_normals: RemovedComponents<DisableNormalMaps>,
+    mut removed_biplanar: RemovedComponents<ForceBiplanar>,
+    mut removed_simplified: RemovedComponents<SimplifiedMaterials>,
+    mut removed_reveal: RemovedComponents<MaterialReveal>,
+    mut materials: ResMut<Assets<TriplanarVoxelMaterial>>,
+    mut variants: ResMut<TriplanarMaterialVariants>,
+) {
+    let mut dirty: Vec<Entity> = queries.p0().iter().collect();
+    dirty.extend(removed_normals.read());
+    dirty.extend(removed_biplanar.read());
+    dirty.extend(removed_simplified.read());
+    dirty.extend(removed_reveal.read());
+
+    let mut entities = queries.p1();
+    for entity in dirty {
+        let Ok((entity, mut material, no_normals, biplanar, simplified, reveal, current)) =
+            entities.get_mut(entity)
+        else {
+            continue;
+        };
+
+        // A handle we didn't set means the user assigned a new base material
+        let base = match current {
+            Some(current) if current.variant == material.0 => current.base.clone(),
+            _ => material.0.clone(),
+        };
+
+        let features = (no_normals as u8 * DISABLE_NORMAL_MAPS)
+            | (biplanar as u8 * FORCE_BIPLANAR)
+            | (simplified as u8 * SIMPLIFIED);
+        let reveal_step = reveal.map_or(REVEAL_STEPS, |reveal| reveal.step());
+        if features == 0 && reveal_step == REVEAL_STEPS {
+            if current.is_some() {
+                if material.0 != base {
+                    material.0 = base;
+                }
+                commands.entity(entity).remove::<TriplanarMaterialVariant>();
+            }
+            continue;
+        }
+
+        let key = (base.id(), features, reveal_step);
+        let variant = match variants.variants.get(&key) {
+            Some(variant) => variant.clone(),
+            None => {
+                let Some(base_material) = materials.get(&base) else {
+                    continue;
+                };
+                let variant = make_variant(base_material, features, reveal_step);
+                let variant = materials.add(variant);
+                variants.variants.insert(key, variant.clone());
+                variant
+            }
+        };
+
+        if material.0 != variant {
+            material.0 = variant.clone();
+        }
+        commands
+            .entity(entity)
+            .insert(TriplanarMaterialVariant { base, variant });
+    }
+}
+
+/// System that keeps variants in sync with their base materials.
+pub fn sync_material_variants(
+    mut events: MessageReader<AssetEvent<TriplanarVoxelMaterial>>,
+    mut materials: ResMut<Assets<TriplanarVoxelMaterial>>,
+    mut variants: ResMut<TriplanarMaterialVariants>,
+) {
+    for event in events.read() {
+        match *event {
+            AssetEvent::Modified { id } => {
+                let Some(base) = materials.get(id).cloned() else {
+                    continue;
+                };
+                for (&(base_id, features, reveal_step), variant) in &variants.variants {
+                    if base_id == id
+                        && let Some(target) = materials.get_mut(variant)
+                    {
+                        *target = make_variant(&base, features, reveal_step);
+                    }
+                }
+            }
+            AssetEvent::Unused { id } | AssetEvent::Removed { id } => {
+                variants.variants.retain(|&(base_id, ..), _| base_id != id);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::TriplanarExtension;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .init_asset::<TriplanarVoxelMaterial>()
+            .init_resource::<TriplanarMaterialVariants>()
+            .add_systems(Update, apply_entity_feature_overrides);
+        app
+    }
+
+    fn material() -> TriplanarVoxelMaterial {
+        TriplanarVoxelMaterial {
+            base: StandardMaterial::default(),
+            extension: TriplanarExtension::default().with_biplanar_color(false),
+        }
+    }
+
+    #[test]
+    fn test_variant_applied_and_shared() {
+        let mut app = app();
+        let base = app
+            .world_mut()
+            .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+            .add(material());
+        let a = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), DisableNormalMaps))
+            .id();
+        let b = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), DisableNormalMaps))
+            .id();
+        app.update();
+
+        let handle_a = app
+            .world()
+            .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(a)
+            .unwrap()
+            .0
+            .clone();
+        let handle_b = app
+            .world()
+            .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(b)
+            .unwrap()
+            .0
+            .clone();
+        assert_ne!(handle_a, base);
+        assert_eq!(handle_a, handle_b);
+        assert_eq!(app.world().resource::<TriplanarMaterialVariants>().len(), 1);
+
+        let materials = app.world().resource::<Assets<TriplanarVoxelMaterial>>();
+        let variant = materials.get(&handle_a).unwrap();
+        assert!(!variant.extension.enable_normal_maps);
+        assert!(!variant.extension.use_biplanar_color);
+    }
+
+    #[test]
+    fn test_removal_restores_base() {
+        let mut app = app();
+        let base = app
+            .world_mut()
+            .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+            .add(material());
+        let entity = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), ForceBiplanar))
+            .id();
+        app.update();
+        app.world_mut().entity_mut(entity).remove::<ForceBiplanar>();
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world
+                .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(entity)
+                .unwrap()
+                .0,
+            base
+        );
+        assert!(world.get::<TriplanarMaterialVariant>(entity).is_none());
+    }
+
+    #[test]
+    fn test_simplified_variant_biases_mips() {
+        let mut base = material();
+        base.extension = base.extension.with_mip_bias(0.5);
+        let variant = make_variant(&base, SIMPLIFIED, REVEAL_STEPS);
+        assert_eq!(variant.extension.mip_bias, 1.5);
+        assert_eq!(variant.extension.build_settings().lod_params.x, 1.5);
+    }
+
+    #[test]
+    fn test_reveal_variants_quantized() {
+        let mut app = app();
+        let base = app
+            .world_mut()
+            .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+            .add(material());
+        let a = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), MaterialReveal(0.5)))
+            .id();
+        let b = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), MaterialReveal(0.501)))
+            .id();
+        app.update();
+
+        let world = app.world();
+        let handle = &world
+            .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(a)
+            .unwrap()
+            .0;
+        assert_eq!(
+            handle,
+            &world
+                .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(b)
+                .unwrap()
+                .0
+        );
+        let materials = world.resource::<Assets<TriplanarVoxelMaterial>>();
+        assert_eq!(materials.get(handle).unwrap().extension.reveal, 0.5);
+
+        // Fully revealed needs no variant
+        app.world_mut().get_mut::<MaterialReveal>(a).unwrap().0 = 1.0;
+        app.update();
+        assert_eq!(
+            app.world()
+                .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(a)
+                .unwrap()
+                .0,
+            base
+        );
+    }
+}
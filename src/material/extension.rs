@@ -1,35 +1,48 @@
 //! Material extension for triplanar voxel rendering.
 
 use bevy::ecs::system::{SystemParamItem, lifetimeless::SRes};
+use bevy::log::info_span;
 use bevy::mesh::MeshVertexBufferLayoutRef;
 use bevy::pbr::{
     ExtendedMaterial, MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline,
-    StandardMaterial,
+    MeshPipelineKey, StandardMaterial,
 };
 use bevy::prelude::*;
 use bevy::render::{
     render_asset::RenderAssets,
     render_resource::{
         AsBindGroup, AsBindGroupError, BindGroupLayout, BindGroupLayoutEntries,
-        BindGroupLayoutEntry, BindingResources, BufferInitDescriptor, BufferUsages,
+        BindGroupLayoutEntry, BindingResources, BufferInitDescriptor, BufferUsages, FragmentState,
         OwnedBindingResource, RenderPipelineDescriptor, SamplerBindingType, ShaderStages,
         ShaderType, SpecializedMeshPipelineError, TextureSampleType, TextureViewDimension,
         UnpreparedBindGroup,
-        binding_types::{sampler, storage_buffer_read_only, texture_2d_array, uniform_buffer},
+        binding_types::{
+            sampler, storage_buffer_read_only, texture_2d, texture_2d_array, texture_3d,
+            uniform_buffer,
+        },
     },
     renderer::RenderDevice,
     texture::{FallbackImage, GpuImage},
 };
-use bevy::shader::ShaderRef;
+use bevy::shader::{ShaderDefVal, ShaderRef};
 use bytemuck::{Pod, Zeroable};
 
-use crate::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS};
-use crate::palette::{MAX_MATERIALS, MaterialPropertiesGpu};
+use super::overrides::GlobalTriplanarOverrides;
+use super::quality::{TriplanarQualityKey, TriplanarQualitySettings};
+use crate::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_PARAMS, ATTRIBUTE_MATERIAL_WEIGHTS};
+use crate::palette::{MAX_MATERIALS, MaterialPropertiesExtendedGpu, MaterialPropertiesGpu};
 
 /// Shader asset path (embedded).
-const TRIPLANAR_SHADER_PATH: &str =
+///
+/// With [`TriplanarVoxelPlugin::shader_override`](crate::TriplanarVoxelPlugin::shader_override)
+/// set, this asset holds a copy of the override instead.
+pub(super) const TRIPLANAR_SHADER_PATH: &str =
     "embedded://bevy_painter/material/shaders/triplanar_extension.wgsl";
 
+/// Prepass and shadow shader path (embedded).
+pub(super) const TRIPLANAR_PREPASS_SHADER_PATH: &str =
+    "embedded://bevy_painter/material/shaders/triplanar_prepass.wgsl";
+
 /// Convenience type alias for the complete triplanar voxel material.
 pub type TriplanarVoxelMaterial = ExtendedMaterial<StandardMaterial, TriplanarExtension>;
 
@@ -41,12 +54,168 @@ pub struct TriplanarSettings {
     pub blend_sharpness: f32,
     pub flags: u32,
     pub material_count: u32,
+    /// Biome tint mask placement: world XZ minimum, then 1 / extent.
+    pub tint_mask_rect: Vec4,
+    /// Blended albedo alpha below which cutout pixels are dropped.
+    pub alpha_cutoff: f32,
+    /// World-space voxel size for the grid overlay debug view.
+    pub debug_grid_cell_size: f32,
+    /// World-space chunk size for the chunk boundary debug view.
+    pub debug_grid_chunk_size: f32,
+    /// Blend between the ungraded and `color_lut` graded color.
+    pub color_lut_strength: f32,
+    /// Toon shading: band count, rim strength, then unused.
+    pub toon_params: Vec4,
+    /// Grass shells: layer count, height, blades per world unit, then unused.
+    pub shell_params: Vec4,
+    /// Dissolve: reveal, edge width, noise frequency, then unused.
+    pub reveal_params: Vec4,
+    /// Dissolve edge glow, linear RGB times intensity, then unused.
+    pub reveal_edge_color: Vec4,
+    /// Texture mip bias, then unused.
+    pub lod_params: Vec4,
 }
 
 impl TriplanarSettings {
     pub const FLAG_USE_BIPLANAR: u32 = 1 << 0;
     pub const FLAG_ENABLE_NORMALS: u32 = 1 << 1;
     pub const FLAG_HAS_ARM: u32 = 1 << 2;
+    /// Dither projection weights to hide banding at projection transitions.
+    pub const FLAG_SEAM_DITHER: u32 = 1 << 3;
+    /// A height array is bound; vertices are displaced.
+    pub const FLAG_HAS_HEIGHT: u32 = 1 << 4;
+    /// A biome tint mask is bound.
+    pub const FLAG_HAS_TINT_MASK: u32 = 1 << 5;
+    /// Drop pixels whose blended albedo alpha is below `alpha_cutoff`.
+    pub const FLAG_ALPHA_CUTOUT: u32 = 1 << 6;
+    /// Divide vertex weights by their actual sum instead of 255.
+    pub const FLAG_NORMALIZE_WEIGHTS: u32 = 1 << 8;
+    /// Light with [`ShadingMode::Toon`] instead of PBR.
+    pub const FLAG_TOON_SHADING: u32 = 1 << 9;
+    /// A color grading LUT is bound.
+    pub const FLAG_HAS_COLOR_LUT: u32 = 1 << 10;
+    /// Dissolve pixels by noise against `reveal_params.x`.
+    pub const FLAG_DISSOLVE: u32 = 1 << 11;
+
+    /// Debug: color by primary material ID.
+    pub const FLAG_DEBUG_MATERIAL_IDS: u32 = 1 << 16;
+    /// Debug: show the first three material weights as RGB.
+    pub const FLAG_DEBUG_MATERIAL_WEIGHTS: u32 = 1 << 17;
+    /// Debug: show triplanar projection weights as RGB.
+    pub const FLAG_DEBUG_TRIPLANAR_WEIGHTS: u32 = 1 << 18;
+    /// Debug: show world normals.
+    pub const FLAG_DEBUG_NORMALS: u32 = 1 << 19;
+    /// Debug overlay: draw voxel grid lines over the lit surface.
+    pub const FLAG_DEBUG_VOXEL_GRID: u32 = 1 << 20;
+    /// Debug overlay: highlight chunk boundaries over the lit surface.
+    pub const FLAG_DEBUG_CHUNK_BOUNDS: u32 = 1 << 21;
+    /// All debug flag bits.
+    pub const DEBUG_FLAGS_MASK: u32 = 0xFFFF_0000;
+    /// Debug bits that replace the shaded color with an unlit view. The
+    /// remaining debug bits are overlays drawn on top of normal shading.
+    pub const DEBUG_VIEW_MASK: u32 = Self::FLAG_DEBUG_MATERIAL_IDS
+        | Self::FLAG_DEBUG_MATERIAL_WEIGHTS
+        | Self::FLAG_DEBUG_TRIPLANAR_WEIGHTS
+        | Self::FLAG_DEBUG_NORMALS;
+}
+
+/// How [`TriplanarExtension`] lights the blended surface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum ShadingMode {
+    /// Bevy's PBR lighting.
+    #[default]
+    Pbr,
+    /// Banded diffuse lighting with a hard rim light, for stylized looks.
+    ///
+    /// Lit by ambient and directional lights only, with directional
+    /// shadows. Falls back to PBR under deferred rendering.
+    Toon {
+        /// Number of light bands, at least 1.
+        steps: u32,
+        /// Rim light strength, relative to the directional light. 0 disables it.
+        rim: f32,
+    },
+}
+
+/// Shell-textured grass drawn over materials with
+/// [`PaletteMaterial::grass_shells`](crate::palette::PaletteMaterial::grass_shells).
+///
+/// Shells are extra copies of the chunk mesh pushed out along the normal,
+/// each cutting away more of the grass by noise so blades taper towards
+/// the top. See [`RenderGrassShells`](super::RenderGrassShells).
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct GrassShells {
+    /// Number of shell layers, each one extra draw of the mesh.
+    /// Default: 8
+    pub layers: u32,
+    /// World-space height of the top shell.
+    /// Default: 0.15
+    pub height: f32,
+    /// Blades per world unit along each axis.
+    /// Default: 40.0
+    pub blade_density: f32,
+}
+
+/// Look of the noise dissolve driven by [`TriplanarExtension::reveal`].
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct DissolveStyle {
+    /// Width of the glowing band at the dissolve front, in noise units
+    /// (0-1). 0 disables the glow.
+    /// Default: 0.08
+    pub edge_width: f32,
+    /// Noise frequency per world unit; lower values dissolve in larger
+    /// patches.
+    /// Default: 0.5
+    pub noise_scale: f32,
+    /// Emissive color of the band at the dissolve front.
+    /// Default: LinearRgba::rgb(4.0, 1.6, 0.4)
+    pub edge_color: LinearRgba,
+}
+
+impl Default for DissolveStyle {
+    fn default() -> Self {
+        Self {
+            edge_width: 0.08,
+            noise_scale: 0.5,
+            edge_color: LinearRgba::rgb(4.0, 1.6, 0.4),
+        }
+    }
+}
+
+impl Default for GrassShells {
+    fn default() -> Self {
+        Self {
+            layers: 8,
+            height: 0.15,
+            blade_density: 40.0,
+        }
+    }
+}
+
+/// Pipeline key for [`TriplanarExtension`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TriplanarExtensionKey {
+    pub quality: TriplanarQualityKey,
+    /// Material properties use [`MaterialPropertiesExtendedGpu`].
+    pub extended_properties: bool,
+    /// Keep displacement in directional light shadow maps.
+    pub displacement_in_shadows: bool,
+    /// Run the alpha cutout in depth-only prepasses and shadow maps.
+    pub alpha_cutout: bool,
+    /// Enable alpha-to-coverage for cutout edges under MSAA.
+    pub alpha_to_coverage: bool,
+}
+
+impl TriplanarExtensionKey {
+    /// Shader defs for this key.
+    pub fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        let mut defs = self.quality.shader_defs();
+        if self.extended_properties {
+            defs.push("EXTENDED_MATERIAL_PROPERTIES".into());
+            defs.push("STANDARD_MATERIAL_CLEARCOAT".into());
+        }
+        defs
+    }
 }
 
 /// Material extension that adds triplanar mapping and multi-material blending.
@@ -55,11 +224,76 @@ pub struct TriplanarExtension {
     pub albedo: Handle<Image>,
     pub normal: Option<Handle<Image>>,
     pub arm: Option<Handle<Image>>,
+    /// Height array driving per-material vertex displacement.
+    pub height: Option<Handle<Image>>,
+    /// World-space 2D mask tinting materials with `hue_variation`.
+    /// R shifts hue and G scales saturation; 0.5 is neutral for both.
+    pub tint_mask: Option<Handle<Image>>,
+    /// World XZ area covered by `tint_mask`. The mask is clamped outside it.
+    pub tint_mask_rect: Rect,
     pub material_properties: Vec<MaterialPropertiesGpu>,
+    /// Properties with specular and clearcoat terms.
+    /// When non-empty, replaces `material_properties`.
+    pub extended_properties: Vec<MaterialPropertiesExtendedGpu>,
     pub texture_scale: f32,
     pub blend_sharpness: f32,
     pub use_biplanar_color: bool,
     pub enable_normal_maps: bool,
+    /// Break up visible bands at projection transitions with per-pixel noise.
+    pub seam_dither: bool,
+    /// Displace vertices in directional light shadow maps too.
+    ///
+    /// Off by default: shadows are cast by the undisplaced surface, which
+    /// avoids peter-panning on displaced materials. Point and spot light
+    /// shadows can't be told apart from a camera depth prepass, so they
+    /// always use the displaced surface.
+    pub displacement_in_shadows: bool,
+    /// Treat palette albedo alpha as a cutout mask with this threshold,
+    /// e.g. for foliage or grates.
+    ///
+    /// Pixels stay in the opaque pass. The prepass and shadow maps discard
+    /// the same pixels, so cut areas let light through and don't occlude.
+    pub alpha_cutoff: Option<f32>,
+    /// Antialias cutout edges with alpha-to-coverage when the view uses MSAA.
+    ///
+    /// Falls back to a hard cutout without MSAA. Has no effect unless
+    /// `alpha_cutoff` is set.
+    pub alpha_to_coverage: bool,
+    /// Normalize vertex weights by their sum, so weights that don't add up
+    /// to exactly 255 don't brighten or darken the blend.
+    ///
+    /// On by default. Turning it off uses the encoded weights as-is.
+    pub normalize_weights: bool,
+    /// Lighting model for the forward pass.
+    pub shading_mode: ShadingMode,
+    /// 3D color grading LUT applied to the final terrain color only.
+    ///
+    /// Indexed with linear color before tonemapping, clamped to 0-1; the
+    /// part above 1 passes through ungraded.
+    pub color_lut: Option<Handle<Image>>,
+    /// How much of `color_lut` to apply, from 0 to 1.
+    pub color_lut_strength: f32,
+    /// Grass shell settings for [`RenderGrassShells`](super::RenderGrassShells)
+    /// entities. `None` draws no shells.
+    pub grass_shells: Option<GrassShells>,
+    /// How much of the surface is shown, from 0 (dissolved away) to 1
+    /// (fully shown), through a world-space noise dissolve.
+    ///
+    /// Usually set per entity with [`MaterialReveal`](super::MaterialReveal)
+    /// rather than here. Unlike `alpha_cutoff`, the depth prepass and shadow
+    /// maps use the full surface.
+    pub reveal: f32,
+    /// Look of the dissolve while `reveal` is below 1.
+    pub dissolve: DissolveStyle,
+    /// Mip level offset for every texture sample; positive values sample
+    /// smaller, blurrier mips.
+    pub mip_bias: f32,
+    /// Mip bias added for [`SimplifiedMaterials`](super::SimplifiedMaterials)
+    /// entities, usually far chunks.
+    pub simplified_mip_bias: f32,
+    /// Shader quality toggles. Kept in sync with the
+    /// [`TriplanarQualitySettings`] resource by the plugin.
+    pub quality: TriplanarQualitySettings,
 }
 
 impl Default for TriplanarExtension {
@@ -68,11 +302,29 @@ impl Default for TriplanarExtension {
             albedo: Handle::default(),
             normal: None,
             arm: None,
+            height: None,
+            tint_mask: None,
+            tint_mask_rect: Rect::new(-512.0, -512.0, 512.0, 512.0),
             material_properties: Vec::new(),
+            extended_properties: Vec::new(),
             texture_scale: 1.0,
             blend_sharpness: 4.0,
             use_biplanar_color: true,
             enable_normal_maps: true,
+            seam_dither: false,
+            displacement_in_shadows: false,
+            alpha_cutoff: None,
+            alpha_to_coverage: false,
+            normalize_weights: true,
+            shading_mode: ShadingMode::Pbr,
+            color_lut: None,
+            color_lut_strength: 1.0,
+            grass_shells: None,
+            reveal: 1.0,
+            dissolve: DissolveStyle::default(),
+            mip_bias: 0.0,
+            simplified_mip_bias: 1.0,
+            quality: TriplanarQualitySettings::default(),
         }
     }
 }
@@ -95,11 +347,30 @@ impl TriplanarExtension {
         self
     }
 
+    pub fn with_height(mut self, height: Handle<Image>) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    pub fn with_tint_mask(mut self, mask: Handle<Image>, world_rect: Rect) -> Self {
+        self.tint_mask = Some(mask);
+        self.tint_mask_rect = world_rect;
+        self
+    }
+
     pub fn with_material_properties(mut self, properties: Vec<MaterialPropertiesGpu>) -> Self {
         self.material_properties = properties;
         self
     }
 
+    pub fn with_extended_material_properties(
+        mut self,
+        properties: Vec<MaterialPropertiesExtendedGpu>,
+    ) -> Self {
+        self.extended_properties = properties;
+        self
+    }
+
     pub fn with_material(mut self) -> Self {
         self.material_properties
             .push(MaterialPropertiesGpu::default());
@@ -134,6 +405,75 @@ impl TriplanarExtension {
         self
     }
 
+    pub fn with_seam_dither(mut self, enable: bool) -> Self {
+        self.seam_dither = enable;
+        self
+    }
+
+    pub fn with_displacement_in_shadows(mut self, enable: bool) -> Self {
+        self.displacement_in_shadows = enable;
+        self
+    }
+
+    pub fn with_alpha_cutoff(mut self, cutoff: f32) -> Self {
+        self.alpha_cutoff = Some(cutoff);
+        self
+    }
+
+    pub fn with_alpha_to_coverage(mut self, enable: bool) -> Self {
+        self.alpha_to_coverage = enable;
+        self
+    }
+
+    pub fn with_normalize_weights(mut self, enable: bool) -> Self {
+        self.normalize_weights = enable;
+        self
+    }
+
+    pub fn with_shading_mode(mut self, mode: ShadingMode) -> Self {
+        self.shading_mode = mode;
+        self
+    }
+
+    pub fn with_color_lut(mut self, lut: Handle<Image>, strength: f32) -> Self {
+        self.color_lut = Some(lut);
+        self.color_lut_strength = strength;
+        self
+    }
+
+    pub fn with_grass_shells(mut self, shells: GrassShells) -> Self {
+        self.grass_shells = Some(shells);
+        self
+    }
+
+    pub fn with_reveal(mut self, reveal: f32) -> Self {
+        self.reveal = reveal;
+        self
+    }
+
+    pub fn with_dissolve(mut self, dissolve: DissolveStyle) -> Self {
+        self.dissolve = dissolve;
+        self
+    }
+
+    pub fn with_mip_bias(mut self, mip_bias: f32) -> Self {
+        self.mip_bias = mip_bias;
+        self
+    }
+
+    pub fn with_simplified_mip_bias(mut self, mip_bias: f32) -> Self {
+        self.simplified_mip_bias = mip_bias;
+        self
+    }
+
+    /// Build settings with [`GlobalTriplanarOverrides`] folded in.
+    pub fn build_settings_with_overrides(
+        &self,
+        overrides: &GlobalTriplanarOverrides,
+    ) -> TriplanarSettings {
+        overrides.apply(self.build_settings())
+    }
+
     pub fn build_settings(&self) -> TriplanarSettings {
         let mut flags = 0u32;
 
@@ -149,28 +489,131 @@ impl TriplanarExtension {
             flags |= TriplanarSettings::FLAG_HAS_ARM;
         }
 
+        if self.seam_dither {
+            flags |= TriplanarSettings::FLAG_SEAM_DITHER;
+        }
+
+        if self.height.is_some() {
+            flags |= TriplanarSettings::FLAG_HAS_HEIGHT;
+        }
+
+        if self.tint_mask.is_some() {
+            flags |= TriplanarSettings::FLAG_HAS_TINT_MASK;
+        }
+
+        if self.normalize_weights {
+            flags |= TriplanarSettings::FLAG_NORMALIZE_WEIGHTS;
+        }
+
+        if self.color_lut.is_some() {
+            flags |= TriplanarSettings::FLAG_HAS_COLOR_LUT;
+        }
+
+        if self.alpha_cutoff.is_some() {
+            flags |= TriplanarSettings::FLAG_ALPHA_CUTOUT;
+        }
+
+        let toon_params = match self.shading_mode {
+            ShadingMode::Pbr => Vec4::ZERO,
+            ShadingMode::Toon { steps, rim } => {
+                flags |= TriplanarSettings::FLAG_TOON_SHADING;
+                Vec4::new(steps.max(1) as f32, rim.max(0.0), 0.0, 0.0)
+            }
+        };
+
+        let reveal = self.reveal.clamp(0.0, 1.0);
+        if reveal < 1.0 {
+            flags |= TriplanarSettings::FLAG_DISSOLVE;
+        }
+
+        let mask_size = self.tint_mask_rect.size().max(Vec2::splat(f32::EPSILON));
+
         TriplanarSettings {
             texture_scale: self.texture_scale,
             blend_sharpness: self.blend_sharpness,
             flags,
-            material_count: self.material_properties.len().max(1) as u32,
+            material_count: self
+                .material_properties
+                .len()
+                .max(self.extended_properties.len())
+                .max(1) as u32,
+            tint_mask_rect: Vec4::new(
+                self.tint_mask_rect.min.x,
+                self.tint_mask_rect.min.y,
+                1.0 / mask_size.x,
+                1.0 / mask_size.y,
+            ),
+            alpha_cutoff: self.alpha_cutoff.unwrap_or(0.0),
+            debug_grid_cell_size: 0.0,
+            debug_grid_chunk_size: 0.0,
+            color_lut_strength: self.color_lut_strength.clamp(0.0, 1.0),
+            toon_params,
+            shell_params: self.grass_shells.map_or(Vec4::ZERO, |shells| {
+                Vec4::new(
+                    shells.layers as f32,
+                    shells.height.max(0.0),
+                    shells.blade_density.max(0.0),
+                    0.0,
+                )
+            }),
+            reveal_params: Vec4::new(
+                reveal,
+                self.dissolve.edge_width.max(0.0),
+                self.dissolve.noise_scale.max(0.0),
+                0.0,
+            ),
+            reveal_edge_color: self.dissolve.edge_color.to_vec4().with_w(0.0),
+            lod_params: Vec4::new(self.mip_bias, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl TriplanarExtension {
+    /// Material properties padded to [`MAX_MATERIALS`], in whichever
+    /// layout the pipeline key selects.
+    fn material_props_bytes(&self) -> Vec<u8> {
+        fn padded<T: Pod + Default>(props: &[T]) -> Vec<u8> {
+            let mut padded = vec![T::default(); MAX_MATERIALS];
+            for (slot, props) in padded.iter_mut().zip(props) {
+                *slot = *props;
+            }
+            bytemuck::cast_slice(&padded).to_vec()
+        }
+
+        if self.extended_properties.is_empty() {
+            padded(&self.material_properties)
+        } else {
+            padded(&self.extended_properties)
         }
     }
 }
 
 impl AsBindGroup for TriplanarExtension {
-    type Data = ();
-    type Param = (SRes<RenderAssets<GpuImage>>, SRes<FallbackImage>);
+    type Data = TriplanarExtensionKey;
+    type Param = (
+        SRes<RenderAssets<GpuImage>>,
+        SRes<FallbackImage>,
+        Option<SRes<GlobalTriplanarOverrides>>,
+    );
 
-    fn bind_group_data(&self) -> Self::Data {}
+    fn bind_group_data(&self) -> Self::Data {
+        TriplanarExtensionKey {
+            quality: self.quality.key(),
+            extended_properties: !self.extended_properties.is_empty(),
+            displacement_in_shadows: self.displacement_in_shadows,
+            alpha_cutout: self.alpha_cutoff.is_some(),
+            alpha_to_coverage: self.alpha_cutoff.is_some() && self.alpha_to_coverage,
+        }
+    }
 
     fn unprepared_bind_group(
         &self,
         _layout: &BindGroupLayout,
         render_device: &RenderDevice,
-        (gpu_images, fallback_image): &mut SystemParamItem<'_, '_, Self::Param>,
+        (gpu_images, fallback_image, overrides): &mut SystemParamItem<'_, '_, Self::Param>,
         _force_no_bindless: bool,
     ) -> Result<UnpreparedBindGroup, AsBindGroupError> {
+        let _span = info_span!("triplanar_bind_group").entered();
         let albedo_image = gpu_images
             .get(&self.albedo)
             .ok_or(AsBindGroupError::RetryNextUpdate)?;
@@ -179,30 +622,23 @@ impl AsBindGroup for TriplanarExtension {
 
         let normal_image = self.normal.as_ref().and_then(|h| gpu_images.get(h));
         let arm_image = self.arm.as_ref().and_then(|h| gpu_images.get(h));
+        let height_image = self.height.as_ref().and_then(|h| gpu_images.get(h));
+        let tint_mask_image = self.tint_mask.as_ref().and_then(|h| gpu_images.get(h));
+        let color_lut_image = self.color_lut.as_ref().and_then(|h| gpu_images.get(h));
 
-        let settings = self.build_settings();
+        let settings = match overrides {
+            Some(overrides) => self.build_settings_with_overrides(overrides),
+            None => self.build_settings(),
+        };
         let settings_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
             label: Some("triplanar_settings"),
             contents: bytemuck::bytes_of(&settings),
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
-        let mut material_props = [MaterialPropertiesGpu::default(); MAX_MATERIALS];
-        for (i, props) in self
-            .material_properties
-            .iter()
-            .enumerate()
-            .take(MAX_MATERIALS)
-        {
-            material_props[i] = *props;
-        }
-        if self.material_properties.is_empty() {
-            material_props[0] = MaterialPropertiesGpu::default();
-        }
-
         let props_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
             label: Some("triplanar_material_props"),
-            contents: bytemuck::cast_slice(&material_props),
+            contents: &self.material_props_bytes(),
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
         });
 
@@ -260,6 +696,60 @@ impl AsBindGroup for TriplanarExtension {
                             .unwrap_or_else(|| fallback.sampler.clone()),
                     ),
                 ),
+                (
+                    108,
+                    OwnedBindingResource::TextureView(
+                        TextureViewDimension::D2Array,
+                        height_image
+                            .map(|i| i.texture_view.clone())
+                            .unwrap_or_else(|| fallback.texture_view.clone()),
+                    ),
+                ),
+                (
+                    109,
+                    OwnedBindingResource::Sampler(
+                        SamplerBindingType::Filtering,
+                        height_image
+                            .map(|i| i.sampler.clone())
+                            .unwrap_or_else(|| fallback.sampler.clone()),
+                    ),
+                ),
+                (
+                    110,
+                    OwnedBindingResource::TextureView(
+                        TextureViewDimension::D2,
+                        tint_mask_image
+                            .map(|i| i.texture_view.clone())
+                            .unwrap_or_else(|| fallback_image.d2.texture_view.clone()),
+                    ),
+                ),
+                (
+                    111,
+                    OwnedBindingResource::Sampler(
+                        SamplerBindingType::Filtering,
+                        tint_mask_image
+                            .map(|i| i.sampler.clone())
+                            .unwrap_or_else(|| fallback_image.d2.sampler.clone()),
+                    ),
+                ),
+                (
+                    112,
+                    OwnedBindingResource::TextureView(
+                        TextureViewDimension::D3,
+                        color_lut_image
+                            .map(|i| i.texture_view.clone())
+                            .unwrap_or_else(|| fallback_image.d3.texture_view.clone()),
+                    ),
+                ),
+                (
+                    113,
+                    OwnedBindingResource::Sampler(
+                        SamplerBindingType::Filtering,
+                        color_lut_image
+                            .map(|i| i.sampler.clone())
+                            .unwrap_or_else(|| fallback_image.d3.sampler.clone()),
+                    ),
+                ),
             ]),
         })
     }
@@ -294,6 +784,21 @@ impl AsBindGroup for TriplanarExtension {
                     texture_2d_array(TextureSampleType::Float { filterable: true }),
                 ),
                 (107, sampler(SamplerBindingType::Filtering)),
+                (
+                    108,
+                    texture_2d_array(TextureSampleType::Float { filterable: true }),
+                ),
+                (109, sampler(SamplerBindingType::Filtering)),
+                (
+                    110,
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                ),
+                (111, sampler(SamplerBindingType::Filtering)),
+                (
+                    112,
+                    texture_3d(TextureSampleType::Float { filterable: true }),
+                ),
+                (113, sampler(SamplerBindingType::Filtering)),
             ),
         )
         .to_vec()
@@ -313,6 +818,14 @@ impl MaterialExtension for TriplanarExtension {
         TRIPLANAR_SHADER_PATH.into()
     }
 
+    fn prepass_vertex_shader() -> ShaderRef {
+        TRIPLANAR_PREPASS_SHADER_PATH.into()
+    }
+
+    fn prepass_fragment_shader() -> ShaderRef {
+        TRIPLANAR_PREPASS_SHADER_PATH.into()
+    }
+
     fn deferred_vertex_shader() -> ShaderRef {
         TRIPLANAR_SHADER_PATH.into()
     }
@@ -325,22 +838,151 @@ impl MaterialExtension for TriplanarExtension {
         _pipeline: &MaterialExtensionPipeline,
         descriptor: &mut RenderPipelineDescriptor,
         layout: &MeshVertexBufferLayoutRef,
-        _key: MaterialExtensionKey<Self>,
+        key: MaterialExtensionKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
-        // Custom vertex layout with our material attributes
-        let vertex_layout = layout.0.get_layout(&[
-            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
-            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
-            ATTRIBUTE_MATERIAL_IDS.at_shader_location(2),
-            ATTRIBUTE_MATERIAL_WEIGHTS.at_shader_location(3),
-        ])?;
+        specialize_triplanar(descriptor, layout, key.mesh_key, key.bind_group_data)
+    }
+}
+
+/// Pipeline setup shared by every material using the triplanar bindings:
+/// quality defs, shadow displacement, alpha cutout, alpha-to-coverage and
+/// the custom vertex layout, including lightmap UVs for lightmapped meshes.
+pub(super) fn specialize_triplanar(
+    descriptor: &mut RenderPipelineDescriptor,
+    layout: &MeshVertexBufferLayoutRef,
+    mesh_key: MeshPipelineKey,
+    data: TriplanarExtensionKey,
+) -> Result<(), SpecializedMeshPipelineError> {
+    let shader_defs = data.shader_defs();
+    descriptor
+        .vertex
+        .shader_defs
+        .extend(shader_defs.iter().cloned());
+
+    // Directional shadow maps are the only shadow views with their own key bit
+    if mesh_key.contains(MeshPipelineKey::UNCLIPPED_DEPTH_ORTHO) && !data.displacement_in_shadows {
+        descriptor
+            .vertex
+            .shader_defs
+            .push("TRIPLANAR_NO_DISPLACEMENT".into());
+    }
+    if let Some(fragment) = descriptor.fragment.as_mut() {
+        fragment.shader_defs.extend(shader_defs);
+    }
+
+    // Depth-only prepasses and shadow maps have no fragment stage for opaque
+    // materials; cutouts need one to discard. The prepass shader holds both
+    // stages.
+    if data.alpha_cutout && descriptor.fragment.is_none() {
+        descriptor.fragment = Some(FragmentState {
+            shader: descriptor.vertex.shader.clone(),
+            shader_defs: descriptor.vertex.shader_defs.clone(),
+            entry_point: Some("fragment".into()),
+            targets: Vec::new(),
+        });
+    }
 
-        descriptor.vertex.buffers = vec![vertex_layout];
+    // Coverage comes from the fragment alpha; without MSAA the shader
+    // falls back to discarding
+    if data.alpha_to_coverage && mesh_key.msaa_samples() > 1 {
+        descriptor.multisample.alpha_to_coverage_enabled = true;
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment
+                .shader_defs
+                .push("TRIPLANAR_ALPHA_TO_COVERAGE".into());
+        }
+    }
 
-        Ok(())
+    // Custom vertex layout with our material attributes
+    let mut attributes = vec![
+        Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+        Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+        ATTRIBUTE_MATERIAL_IDS.at_shader_location(2),
+        ATTRIBUTE_MATERIAL_WEIGHTS.at_shader_location(3),
+    ];
+    if layout.0.contains(ATTRIBUTE_MATERIAL_PARAMS) {
+        attributes.push(ATTRIBUTE_MATERIAL_PARAMS.at_shader_location(4));
+        descriptor
+            .vertex
+            .shader_defs
+            .push("VERTEX_MATERIAL_PARAMS".into());
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.push("VERTEX_MATERIAL_PARAMS".into());
+        }
+    }
+    // Bevy's `Lightmap` component sets the key; the mesh must carry UV_1
+    if mesh_key.contains(MeshPipelineKey::LIGHTMAPPED) {
+        attributes.push(Mesh::ATTRIBUTE_UV_1.at_shader_location(5));
     }
+    let vertex_layout = layout.0.get_layout(&attributes)?;
+
+    descriptor.vertex.buffers = vec![vertex_layout];
+
+    Ok(())
 }
 
+/// Implements [`AsBindGroup`] for a material whose only field is
+/// `triplanar: TriplanarExtension` and which shades blended albedo alone,
+/// so normal and ARM maps are left out of its pipeline key.
+macro_rules! impl_albedo_only_bind_group {
+    ($material:ty, $label:literal) => {
+        impl ::bevy::render::render_resource::AsBindGroup for $material {
+            type Data = $crate::material::TriplanarExtensionKey;
+            type Param =
+                <$crate::material::TriplanarExtension as ::bevy::render::render_resource::AsBindGroup>::Param;
+
+            fn bind_group_data(&self) -> Self::Data {
+                let mut quality = self.triplanar.quality;
+                quality.normal_maps = false;
+                quality.arm_maps = false;
+                $crate::material::TriplanarExtensionKey {
+                    quality: quality.key(),
+                    ..::bevy::render::render_resource::AsBindGroup::bind_group_data(
+                        &self.triplanar,
+                    )
+                }
+            }
+
+            fn unprepared_bind_group(
+                &self,
+                layout: &::bevy::render::render_resource::BindGroupLayout,
+                render_device: &::bevy::render::renderer::RenderDevice,
+                param: &mut ::bevy::ecs::system::SystemParamItem<'_, '_, Self::Param>,
+                force_no_bindless: bool,
+            ) -> Result<
+                ::bevy::render::render_resource::UnpreparedBindGroup,
+                ::bevy::render::render_resource::AsBindGroupError,
+            > {
+                ::bevy::render::render_resource::AsBindGroup::unprepared_bind_group(
+                    &self.triplanar,
+                    layout,
+                    render_device,
+                    param,
+                    force_no_bindless,
+                )
+            }
+
+            fn bind_group_layout_entries(
+                render_device: &::bevy::render::renderer::RenderDevice,
+                force_no_bindless: bool,
+            ) -> Vec<::bevy::render::render_resource::BindGroupLayoutEntry>
+            where
+                Self: Sized,
+            {
+                <$crate::material::TriplanarExtension as ::bevy::render::render_resource::AsBindGroup>::bind_group_layout_entries(
+                    render_device,
+                    force_no_bindless,
+                )
+            }
+
+            fn label() -> Option<&'static str> {
+                Some($label)
+            }
+        }
+    };
+}
+pub(super) use impl_albedo_only_bind_group;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,4 +1005,130 @@ mod tests {
         assert_eq!(ext.blend_sharpness, 8.0);
         assert_eq!(ext.material_properties.len(), 4);
     }
+
+    #[test]
+    fn test_height_flag_and_key() {
+        let ext = TriplanarExtension::default();
+        assert_eq!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_HAS_HEIGHT,
+            0
+        );
+        assert!(!ext.bind_group_data().displacement_in_shadows);
+
+        let ext = ext
+            .with_height(Handle::default())
+            .with_displacement_in_shadows(true);
+        assert_ne!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_HAS_HEIGHT,
+            0
+        );
+        assert!(ext.bind_group_data().displacement_in_shadows);
+    }
+
+    #[test]
+    fn test_toon_shading_settings() {
+        let ext = TriplanarExtension::default();
+        assert_eq!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_TOON_SHADING,
+            0
+        );
+
+        let settings = ext
+            .with_shading_mode(ShadingMode::Toon { steps: 0, rim: 0.5 })
+            .build_settings();
+        assert_ne!(settings.flags & TriplanarSettings::FLAG_TOON_SHADING, 0);
+        assert_eq!(settings.toon_params, Vec4::new(1.0, 0.5, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_color_lut_flag() {
+        let ext = TriplanarExtension::default();
+        assert_eq!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_HAS_COLOR_LUT,
+            0
+        );
+
+        let settings = ext.with_color_lut(Handle::default(), 2.0).build_settings();
+        assert_ne!(settings.flags & TriplanarSettings::FLAG_HAS_COLOR_LUT, 0);
+        assert_eq!(settings.color_lut_strength, 1.0);
+    }
+
+    #[test]
+    fn test_dissolve_settings() {
+        let ext = TriplanarExtension::default();
+        assert_eq!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_DISSOLVE,
+            0
+        );
+
+        let settings = ext
+            .with_reveal(-0.5)
+            .with_dissolve(DissolveStyle {
+                noise_scale: 2.0,
+                ..default()
+            })
+            .build_settings();
+        assert_ne!(settings.flags & TriplanarSettings::FLAG_DISSOLVE, 0);
+        assert_eq!(settings.reveal_params, Vec4::new(0.0, 0.08, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_tint_mask_rect() {
+        let ext = TriplanarExtension::default()
+            .with_tint_mask(Handle::default(), Rect::new(100.0, -50.0, 300.0, 50.0));
+        let settings = ext.build_settings();
+
+        assert_ne!(settings.flags & TriplanarSettings::FLAG_HAS_TINT_MASK, 0);
+        assert_eq!(
+            settings.tint_mask_rect,
+            Vec4::new(100.0, -50.0, 0.005, 0.01)
+        );
+    }
+
+    #[test]
+    fn test_seam_dither_flag() {
+        let ext = TriplanarExtension::default();
+        assert_eq!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_SEAM_DITHER,
+            0
+        );
+
+        let ext = ext.with_seam_dither(true);
+        assert_ne!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_SEAM_DITHER,
+            0
+        );
+    }
+
+    #[test]
+    fn test_normalize_weights_flag() {
+        let ext = TriplanarExtension::default();
+        assert_ne!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_NORMALIZE_WEIGHTS,
+            0
+        );
+
+        let ext = ext.with_normalize_weights(false);
+        assert_eq!(
+            ext.build_settings().flags & TriplanarSettings::FLAG_NORMALIZE_WEIGHTS,
+            0
+        );
+    }
+
+    #[test]
+    fn test_alpha_cutout_flags_and_key() {
+        let ext = TriplanarExtension::default().with_alpha_to_coverage(true);
+        let settings = ext.build_settings();
+        assert_eq!(settings.flags & TriplanarSettings::FLAG_ALPHA_CUTOUT, 0);
+        assert!(!ext.bind_group_data().alpha_cutout);
+        // Alpha-to-coverage needs a cutout to act on
+        assert!(!ext.bind_group_data().alpha_to_coverage);
+
+        let ext = ext.with_alpha_cutoff(0.4);
+        let settings = ext.build_settings();
+        assert_ne!(settings.flags & TriplanarSettings::FLAG_ALPHA_CUTOUT, 0);
+        assert_eq!(settings.alpha_cutoff, 0.4);
+        assert!(ext.bind_group_data().alpha_cutout);
+        assert!(ext.bind_group_data().alpha_to_coverage);
+    }
 }
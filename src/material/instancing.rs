@@ -0,0 +1,147 @@
+//! Per-instance material overrides for instanced triplanar props.
+//!
+//! Small props (rocks, ore nodes) are usually spawned thousands of times with
+//! the same mesh and material. Bevy batches these into a single instanced draw
+//! as long as mesh and material handles match, so per-prop material variation
+//! has to travel through per-instance data instead of separate assets.
+//!
+//! [`InstanceMaterialOverride`] is packed into Bevy's [`MeshTag`], which lives
+//! in the per-instance mesh uniform and is read by the vertex shader to remap
+//! material IDs before palette lookup.
+
+use bevy::mesh::MeshTag;
+use bevy::prelude::*;
+
+/// Per-instance material ID remapping.
+///
+/// Each entry maps a material ID authored into the mesh to the palette ID
+/// that should be used for this instance. Up to two remaps are supported
+/// per instance; an entry mapping an ID to itself is a no-op, so the default
+/// value leaves the mesh untouched.
+///
+/// Adding this component also adds a [`MeshTag`], which is kept in sync by
+/// [`sync_instance_material_overrides`]. Avoid using [`MeshTag`] for other
+/// purposes on the same entity.
+///
+/// # Example
+/// ```ignore
+/// // Rock mesh authored with material 0 (stone); this instance is iron ore.
+/// commands.spawn((
+///     Mesh3d(rock_mesh.clone()),
+///     MeshMaterial3d(triplanar_material.clone()),
+///     InstanceMaterialOverride::new().with_remap(0, IRON_ORE),
+///     Transform::from_translation(position),
+/// ));
+/// ```
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[require(MeshTag)]
+pub struct InstanceMaterialOverride {
+    /// `(from, to)` material ID pairs. Checked in order; the first match wins.
+    pub remap: [(u8, u8); 2],
+}
+
+impl InstanceMaterialOverride {
+    /// Maximum number of remaps per instance.
+    pub const MAX_REMAPS: usize = 2;
+
+    /// Create an override that leaves all material IDs unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an override that replaces a single material ID.
+    pub fn single(from: u8, to: u8) -> Self {
+        Self::new().with_remap(from, to)
+    }
+
+    /// Add a remap from `from` to `to`.
+    ///
+    /// Fills the first unused slot. If both slots are used, the last one
+    /// is replaced.
+    pub fn with_remap(mut self, from: u8, to: u8) -> Self {
+        let slot = self
+            .remap
+            .iter()
+            .position(|&(f, t)| f == t)
+            .unwrap_or(Self::MAX_REMAPS - 1);
+        self.remap[slot] = (from, to);
+        self
+    }
+
+    /// Resolve the material ID used for this instance.
+    ///
+    /// Mirrors `apply_instance_override` in the shader.
+    #[inline]
+    pub fn apply(&self, material_id: u8) -> u8 {
+        self.remap
+            .iter()
+            .find(|&&(from, to)| from != to && from == material_id)
+            .map(|&(_, to)| to)
+            .unwrap_or(material_id)
+    }
+
+    /// Pack into the `u32` stored in [`MeshTag`].
+    ///
+    /// Layout: `from0 | to0 << 8 | from1 << 16 | to1 << 24`.
+    #[inline]
+    pub const fn pack(&self) -> u32 {
+        (self.remap[0].0 as u32)
+            | ((self.remap[0].1 as u32) << 8)
+            | ((self.remap[1].0 as u32) << 16)
+            | ((self.remap[1].1 as u32) << 24)
+    }
+
+    /// Unpack from a [`MeshTag`] value.
+    #[inline]
+    pub const fn unpack(packed: u32) -> Self {
+        Self {
+            remap: [
+                ((packed & 0xFF) as u8, ((packed >> 8) & 0xFF) as u8),
+                (((packed >> 16) & 0xFF) as u8, ((packed >> 24) & 0xFF) as u8),
+            ],
+        }
+    }
+}
+
+/// System that writes changed [`InstanceMaterialOverride`]s into [`MeshTag`].
+pub fn sync_instance_material_overrides(
+    mut query: Query<(&InstanceMaterialOverride, &mut MeshTag), Changed<InstanceMaterialOverride>>,
+) {
+    for (material_override, mut tag) in &mut query {
+        let packed = material_override.pack();
+        if tag.0 != packed {
+            tag.0 = packed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_identity() {
+        let o = InstanceMaterialOverride::default();
+        assert_eq!(o.pack(), 0);
+        for id in 0..=255u8 {
+            assert_eq!(o.apply(id), id);
+        }
+    }
+
+    #[test]
+    fn test_remap() {
+        let o = InstanceMaterialOverride::new()
+            .with_remap(0, 7)
+            .with_remap(3, 9);
+
+        assert_eq!(o.apply(0), 7);
+        assert_eq!(o.apply(3), 9);
+        assert_eq!(o.apply(1), 1);
+    }
+
+    #[test]
+    fn test_pack_unpack_roundtrip() {
+        let o = InstanceMaterialOverride::single(2, 40).with_remap(5, 6);
+        assert_eq!(InstanceMaterialOverride::unpack(o.pack()), o);
+    }
+}
@@ -1,11 +1,112 @@
 //! Material extension for triplanar voxel rendering.
 use bevy::prelude::*;
+mod entity_features;
 mod extension;
+mod instancing;
+mod overrides;
+mod quality;
+mod shells;
+mod unlit;
 
-pub use extension::{TriplanarExtension, TriplanarSettings, TriplanarVoxelMaterial};
+pub use entity_features::{
+    DisableNormalMaps, ForceBiplanar, MaterialReveal, SimplifiedMaterials,
+    TriplanarMaterialVariant, TriplanarMaterialVariants, apply_entity_feature_overrides,
+    sync_material_variants,
+};
+pub use extension::{
+    DissolveStyle, GrassShells, ShadingMode, TriplanarExtension, TriplanarExtensionKey,
+    TriplanarSettings, TriplanarVoxelMaterial,
+};
+pub use instancing::{InstanceMaterialOverride, sync_instance_material_overrides};
+pub use overrides::{
+    GlobalTriplanarOverrides, TriplanarDebugMode, apply_global_triplanar_overrides,
+    apply_triplanar_debug_mode,
+};
+pub use quality::{
+    TriplanarQualityKey, TriplanarQualitySettings, TriplanarQualityTier, apply_triplanar_quality,
+};
+pub use shells::{
+    GrassShellLayer, RenderGrassShells, TriplanarShellMaterial, TriplanarShellVariants,
+    sync_grass_shells,
+};
+pub use unlit::{
+    RenderUnlit, TriplanarUnlitBase, TriplanarUnlitMaterial, TriplanarUnlitVariants,
+    apply_render_unlit, sync_unlit_variants,
+};
+
+/// Keeps the user's shader hooks module loaded.
+#[derive(Resource)]
+struct TriplanarShaderHooks(#[allow(dead_code)] Handle<bevy::shader::Shader>);
+
+/// Main shader loaded from the plugin's `shader_override`, copied over the
+/// embedded main shader whenever it loads or changes.
+#[derive(Resource)]
+struct TriplanarShaderOverride {
+    source: Handle<bevy::shader::Shader>,
+    embedded: Handle<bevy::shader::Shader>,
+}
 
 /// Register embedded shader assets for the material module.
-pub(crate) fn register_embedded_assets(app: &mut App) {
-bevy::asset::
-    embedded_asset!(app, "shaders/triplanar_extension.wgsl");
+///
+/// `shader_hooks` is an asset path replacing the default
+/// `bevy_painter::user_hooks` module, and `shader_override` one replacing
+/// the main shader.
+pub(crate) fn register_embedded_assets(
+    app: &mut App,
+    shader_hooks: Option<&str>,
+    shader_override: Option<&str>,
+) {
+    bevy::asset::embedded_asset!(app, "shaders/triplanar_extension.wgsl");
+    bevy::asset::embedded_asset!(app, "shaders/triplanar_prepass.wgsl");
+    bevy::asset::embedded_asset!(app, "shaders/triplanar_unlit.wgsl");
+    bevy::asset::embedded_asset!(app, "shaders/triplanar_shells.wgsl");
+    // Import-only modules, loaded up front so `#import` can resolve them
+    bevy::shader::load_shader_library!(app, "shaders/triplanar_common.wgsl");
+    bevy::shader::load_shader_library!(app, "shaders/triplanar_sampling.wgsl");
+    match shader_hooks {
+        Some(path) => {
+            let hooks = app.world().resource::<AssetServer>().load(path.to_owned());
+            app.insert_resource(TriplanarShaderHooks(hooks));
+        }
+        None => {
+            bevy::shader::load_shader_library!(app, "shaders/triplanar_hooks.wgsl");
+        }
+    }
+    if let Some(path) = shader_override {
+        let asset_server = app.world().resource::<AssetServer>();
+        let shader_override = TriplanarShaderOverride {
+            source: asset_server.load(path.to_owned()),
+            embedded: asset_server.load(extension::TRIPLANAR_SHADER_PATH),
+        };
+        app.insert_resource(shader_override)
+            .add_systems(Update, apply_shader_override);
+    }
+}
+
+/// Copies the override shader over the embedded main shader, so every
+/// triplanar pipeline picks it up and file edits hot-reload.
+fn apply_shader_override(
+    mut events: MessageReader<AssetEvent<bevy::shader::Shader>>,
+    shader_override: Res<TriplanarShaderOverride>,
+    mut shaders: ResMut<Assets<bevy::shader::Shader>>,
+) {
+    // Copying emits `Modified` for the embedded shader, so only its initial
+    // load triggers a copy
+    let source = shader_override.source.id();
+    let changed = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } => {
+            *id == source || *id == shader_override.embedded.id()
+        }
+        AssetEvent::Modified { id } => *id == source,
+        _ => false,
+    });
+    if !changed {
+        return;
+    }
+    let Some(shader) = shaders.get(source).cloned() else {
+        return;
+    };
+    if let Some(embedded) = shaders.get_mut(&shader_override.embedded) {
+        *embedded = shader;
+    }
 }
@@ -0,0 +1,248 @@
+//! Global runtime overrides applied to every triplanar material.
+//!
+//! [`GlobalTriplanarOverrides`] is extracted to the render world and folded
+//! into each material's [`TriplanarSettings`] when its bind group is prepared.
+//! Changing the resource re-prepares all [`TriplanarVoxelMaterial`] assets, so
+//! global art tweaks and settings menus don't need to touch individual assets.
+
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+
+use super::extension::{TriplanarSettings, TriplanarVoxelMaterial};
+
+/// Global multipliers and debug flags applied on top of every material.
+///
+/// # Example
+/// ```ignore
+/// fn texture_detail_slider(mut overrides: ResMut<GlobalTriplanarOverrides>, value: f32) {
+///     overrides.texture_scale_multiplier = value;
+/// }
+///
+/// fn toggle_weight_debug(mut overrides: ResMut<GlobalTriplanarOverrides>) {
+///     overrides.debug_flags ^= TriplanarSettings::FLAG_DEBUG_MATERIAL_WEIGHTS;
+/// }
+///
+/// ```
+///
+/// The voxel grid and chunk boundary overlays are driven by
+/// [`TriplanarDebugMode`] instead.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct GlobalTriplanarOverrides {
+    /// Multiplies each material's texture scale.
+    pub texture_scale_multiplier: f32,
+    /// Multiplies each material's triplanar blend sharpness.
+    pub blend_sharpness_multiplier: f32,
+    /// `TriplanarSettings::FLAG_DEBUG_*` bits OR'd into every material's flags.
+    pub debug_flags: u32,
+    /// World-space size of one voxel, for the voxel grid overlay.
+    ///
+    /// The overlay is aligned to the world origin, so it lines up with chunks
+    /// placed at multiples of the chunk size without rotation or scale.
+    pub debug_grid_cell_size: f32,
+    /// World-space size of one chunk, for the chunk boundary overlay.
+    pub debug_grid_chunk_size: f32,
+}
+
+impl Default for GlobalTriplanarOverrides {
+    fn default() -> Self {
+        Self {
+            texture_scale_multiplier: 1.0,
+            blend_sharpness_multiplier: 1.0,
+            debug_flags: 0,
+            debug_grid_cell_size: 1.0,
+            // Matches `FIELD_SIZE` with the default one-unit voxels
+            debug_grid_chunk_size: 32.0,
+        }
+    }
+}
+
+impl GlobalTriplanarOverrides {
+    /// Fold these overrides into a material's settings.
+    pub fn apply(&self, settings: TriplanarSettings) -> TriplanarSettings {
+        TriplanarSettings {
+            texture_scale: settings.texture_scale * self.texture_scale_multiplier,
+            blend_sharpness: settings.blend_sharpness * self.blend_sharpness_multiplier,
+            flags: settings.flags | (self.debug_flags & TriplanarSettings::DEBUG_FLAGS_MASK),
+            debug_grid_cell_size: self.debug_grid_cell_size,
+            debug_grid_chunk_size: self.debug_grid_chunk_size,
+            ..settings
+        }
+    }
+}
+
+/// Debug overlays drawn on top of every triplanar material's blended result.
+///
+/// Sets the overlay bits of [`GlobalTriplanarOverrides::debug_flags`], sized
+/// by its `debug_grid_cell_size` and `debug_grid_chunk_size`.
+///
+/// # Example
+/// ```ignore
+/// fn toggle_debug_overlay(keys: Res<ButtonInput<KeyCode>>, mut mode: ResMut<TriplanarDebugMode>) {
+///     if keys.just_pressed(KeyCode::F3) {
+///         *mode = mode.next();
+///     }
+/// }
+/// ```
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub enum TriplanarDebugMode {
+    /// No overlay.
+    #[default]
+    Off,
+    /// Voxel grid lines.
+    VoxelGrid,
+    /// Chunk boundary highlights.
+    ChunkBounds,
+    /// Voxel grid lines and chunk boundary highlights.
+    VoxelGridAndChunkBounds,
+}
+
+impl TriplanarDebugMode {
+    /// Overlay bits this mode sets.
+    pub const OVERLAY_FLAGS: u32 =
+        TriplanarSettings::FLAG_DEBUG_VOXEL_GRID | TriplanarSettings::FLAG_DEBUG_CHUNK_BOUNDS;
+
+    /// `TriplanarSettings::FLAG_DEBUG_*` overlay bits for this mode.
+    pub fn flags(self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::VoxelGrid => TriplanarSettings::FLAG_DEBUG_VOXEL_GRID,
+            Self::ChunkBounds => TriplanarSettings::FLAG_DEBUG_CHUNK_BOUNDS,
+            Self::VoxelGridAndChunkBounds => Self::OVERLAY_FLAGS,
+        }
+    }
+
+    /// The next mode, cycling back to [`Off`](Self::Off), for a debug hotkey.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::VoxelGrid,
+            Self::VoxelGrid => Self::ChunkBounds,
+            Self::ChunkBounds => Self::VoxelGridAndChunkBounds,
+            Self::VoxelGridAndChunkBounds => Self::Off,
+        }
+    }
+}
+
+/// System copying [`TriplanarDebugMode`] into the overlay bits of
+/// [`GlobalTriplanarOverrides`] when it changes.
+pub fn apply_triplanar_debug_mode(
+    mode: Res<TriplanarDebugMode>,
+    mut overrides: ResMut<GlobalTriplanarOverrides>,
+) {
+    if !mode.is_changed() {
+        return;
+    }
+    let debug_flags = (overrides.debug_flags & !TriplanarDebugMode::OVERLAY_FLAGS) | mode.flags();
+    if overrides.debug_flags != debug_flags {
+        overrides.debug_flags = debug_flags;
+    }
+}
+
+/// System that re-prepares all triplanar materials when the overrides change.
+pub fn apply_global_triplanar_overrides(
+    overrides: Res<GlobalTriplanarOverrides>,
+    mut materials: ResMut<Assets<TriplanarVoxelMaterial>>,
+) {
+    if !overrides.is_changed() || overrides.is_added() {
+        return;
+    }
+    // Mutable iteration queues a modified event for each asset
+    for _ in materials.iter_mut() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_identity() {
+        let settings = TriplanarSettings {
+            texture_scale: 2.0,
+            blend_sharpness: 4.0,
+            flags: TriplanarSettings::FLAG_USE_BIPLANAR,
+            material_count: 3,
+            ..default()
+        };
+        let applied = GlobalTriplanarOverrides::default().apply(settings);
+
+        assert_eq!(applied.texture_scale, 2.0);
+        assert_eq!(applied.blend_sharpness, 4.0);
+        assert_eq!(applied.flags, settings.flags);
+        assert_eq!(applied.material_count, 3);
+    }
+
+    #[test]
+    fn test_multipliers_and_debug_flags() {
+        let overrides = GlobalTriplanarOverrides {
+            texture_scale_multiplier: 0.5,
+            blend_sharpness_multiplier: 2.0,
+            // Non-debug bits are ignored
+            debug_flags: TriplanarSettings::FLAG_DEBUG_NORMALS
+                | TriplanarSettings::FLAG_ENABLE_NORMALS,
+            ..default()
+        };
+        let applied = overrides.apply(TriplanarSettings {
+            texture_scale: 2.0,
+            blend_sharpness: 4.0,
+            flags: 0,
+            material_count: 1,
+            ..default()
+        });
+
+        assert_eq!(applied.texture_scale, 1.0);
+        assert_eq!(applied.blend_sharpness, 8.0);
+        assert_eq!(applied.flags, TriplanarSettings::FLAG_DEBUG_NORMALS);
+    }
+
+    #[test]
+    fn test_grid_overlay_is_not_a_debug_view() {
+        let overrides = GlobalTriplanarOverrides {
+            debug_flags: TriplanarSettings::FLAG_DEBUG_VOXEL_GRID,
+            debug_grid_cell_size: 0.5,
+            debug_grid_chunk_size: 16.0,
+            ..default()
+        };
+        let applied = overrides.apply(TriplanarSettings::default());
+
+        assert_eq!(applied.flags, TriplanarSettings::FLAG_DEBUG_VOXEL_GRID);
+        assert_eq!(applied.flags & TriplanarSettings::DEBUG_VIEW_MASK, 0);
+        assert_eq!(applied.debug_grid_cell_size, 0.5);
+        assert_eq!(applied.debug_grid_chunk_size, 16.0);
+    }
+
+    #[test]
+    fn test_debug_mode_sets_overlay_flags() {
+        let mut app = App::new();
+        app.init_resource::<TriplanarDebugMode>()
+            .insert_resource(GlobalTriplanarOverrides {
+                debug_flags: TriplanarSettings::FLAG_DEBUG_NORMALS,
+                ..default()
+            })
+            .add_systems(Update, apply_triplanar_debug_mode);
+        let flags = |app: &App| {
+            app.world()
+                .resource::<GlobalTriplanarOverrides>()
+                .debug_flags
+        };
+
+        app.update();
+        assert_eq!(flags(&app), TriplanarSettings::FLAG_DEBUG_NORMALS);
+
+        *app.world_mut().resource_mut::<TriplanarDebugMode>() = TriplanarDebugMode::VoxelGrid;
+        app.update();
+        assert_eq!(
+            flags(&app),
+            TriplanarSettings::FLAG_DEBUG_NORMALS | TriplanarSettings::FLAG_DEBUG_VOXEL_GRID
+        );
+
+        // Cycling replaces the overlay but keeps other debug views
+        let next = TriplanarDebugMode::VoxelGrid.next();
+        *app.world_mut().resource_mut::<TriplanarDebugMode>() = next;
+        app.update();
+        assert_eq!(
+            flags(&app),
+            TriplanarSettings::FLAG_DEBUG_NORMALS | TriplanarSettings::FLAG_DEBUG_CHUNK_BOUNDS
+        );
+    }
+}
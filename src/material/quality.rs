@@ -0,0 +1,222 @@
+//! Quality tiers for scaling triplanar shader cost.
+//!
+//! [`TriplanarQualitySettings`] maps a single graphics option to concrete
+//! shader toggles. The settings are copied into every
+//! [`TriplanarVoxelMaterial`] and become part of its pipeline key, so changing
+//! the resource respecializes pipelines with a different set of shader defs.
+
+use bevy::prelude::*;
+use bevy::shader::ShaderDefVal;
+
+use super::extension::TriplanarVoxelMaterial;
+
+/// Coarse quality preset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum TriplanarQualityTier {
+    /// Biplanar albedo, no normal or ARM maps, 2 materials per pixel.
+    Low,
+    /// Biplanar albedo with normal maps, 3 materials per pixel.
+    Medium,
+    /// Normal and ARM maps, 4 materials per pixel.
+    #[default]
+    High,
+    /// Everything in `High`, plus stochastic tiling. Albedo is only biplanar
+    /// for materials that opt into it.
+    Ultra,
+}
+
+/// Shader feature toggles for all triplanar materials.
+///
+/// Toggles cap what a material asks for: a material without a normal map
+/// never samples normals, regardless of `normal_maps`. `stochastic_tiling`
+/// is the only toggle that adds cost.
+///
+/// # Example
+/// ```ignore
+/// fn apply_graphics_option(mut quality: ResMut<TriplanarQualitySettings>) {
+///     *quality = TriplanarQualitySettings::from_tier(TriplanarQualityTier::Low);
+/// }
+/// ```
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Resource)]
+pub struct TriplanarQualitySettings {
+    /// The preset these toggles were derived from.
+    pub tier: TriplanarQualityTier,
+    /// Force biplanar albedo (two dominant projections) on all materials.
+    /// When off, each material's `use_biplanar_color` decides.
+    pub biplanar_color: bool,
+    /// Sample normal maps.
+    pub normal_maps: bool,
+    /// Sample ARM (AO/Roughness/Metallic) maps.
+    pub arm_maps: bool,
+    /// Hide texture repetition by blending randomly offset samples.
+    pub stochastic_tiling: bool,
+    /// Maximum materials blended per pixel (1-4).
+    pub max_blend_materials: u8,
+}
+
+impl Default for TriplanarQualitySettings {
+    fn default() -> Self {
+        Self::from_tier(TriplanarQualityTier::default())
+    }
+}
+
+impl TriplanarQualitySettings {
+    /// Create settings from a quality preset.
+    pub fn from_tier(tier: TriplanarQualityTier) -> Self {
+        let (biplanar_color, normal_maps, arm_maps, stochastic_tiling, max_blend_materials) =
+            match tier {
+                TriplanarQualityTier::Low => (true, false, false, false, 2),
+                TriplanarQualityTier::Medium => (true, true, false, false, 3),
+                TriplanarQualityTier::High => (true, true, true, false, 4),
+                TriplanarQualityTier::Ultra => (false, true, true, true, 4),
+            };
+        Self {
+            tier,
+            biplanar_color,
+            normal_maps,
+            arm_maps,
+            stochastic_tiling,
+            max_blend_materials,
+        }
+    }
+
+    /// Pipeline key for these settings.
+    pub fn key(&self) -> TriplanarQualityKey {
+        let mut bits = 0u8;
+        if self.biplanar_color {
+            bits |= TriplanarQualityKey::BIPLANAR_COLOR;
+        }
+        if self.normal_maps {
+            bits |= TriplanarQualityKey::NORMAL_MAPS;
+        }
+        if self.arm_maps {
+            bits |= TriplanarQualityKey::ARM_MAPS;
+        }
+        if self.stochastic_tiling {
+            bits |= TriplanarQualityKey::STOCHASTIC_TILING;
+        }
+        TriplanarQualityKey {
+            bits,
+            max_blend_materials: self.max_blend_materials.clamp(1, 4),
+        }
+    }
+}
+
+/// Compact pipeline key derived from [`TriplanarQualitySettings`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TriplanarQualityKey {
+    bits: u8,
+    max_blend_materials: u8,
+}
+
+impl TriplanarQualityKey {
+    const BIPLANAR_COLOR: u8 = 1 << 0;
+    const NORMAL_MAPS: u8 = 1 << 1;
+    const ARM_MAPS: u8 = 1 << 2;
+    const STOCHASTIC_TILING: u8 = 1 << 3;
+
+    /// Shader defs for this key.
+    pub fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        let mut defs = vec![ShaderDefVal::UInt(
+            "MAX_BLEND_MATERIALS".into(),
+            self.max_blend_materials.clamp(1, 4) as u32,
+        )];
+        for (bit, name) in [
+            (Self::BIPLANAR_COLOR, "QUALITY_BIPLANAR_COLOR"),
+            (Self::NORMAL_MAPS, "QUALITY_NORMAL_MAPS"),
+            (Self::ARM_MAPS, "QUALITY_ARM_MAPS"),
+            (Self::STOCHASTIC_TILING, "QUALITY_STOCHASTIC_TILING"),
+        ] {
+            if self.bits & bit != 0 {
+                defs.push(name.into());
+            }
+        }
+        defs
+    }
+}
+
+/// System that copies [`TriplanarQualitySettings`] into every material.
+///
+/// Runs for new materials and whenever the resource changes. Only materials
+/// whose settings differ are touched, which re-prepares them with a new
+/// pipeline key.
+pub fn apply_triplanar_quality(
+    quality: Res<TriplanarQualitySettings>,
+    mut events: MessageReader<AssetEvent<TriplanarVoxelMaterial>>,
+    mut materials: ResMut<Assets<TriplanarVoxelMaterial>>,
+) {
+    let added: Vec<_> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    if quality.is_changed() {
+        let ids: Vec<_> = materials.ids().collect();
+        for id in ids {
+            update_material_quality(&mut materials, id, &quality);
+        }
+    } else {
+        for id in added {
+            update_material_quality(&mut materials, id, &quality);
+        }
+    }
+}
+
+fn update_material_quality(
+    materials: &mut Assets<TriplanarVoxelMaterial>,
+    id: AssetId<TriplanarVoxelMaterial>,
+    quality: &TriplanarQualitySettings,
+) {
+    let needs_update = materials
+        .get(id)
+        .is_some_and(|m| m.extension.quality != *quality);
+    if needs_update && let Some(material) = materials.get_mut(id) {
+        material.extension.quality = *quality;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiers_scale_cost() {
+        let low = TriplanarQualitySettings::from_tier(TriplanarQualityTier::Low);
+        let ultra = TriplanarQualitySettings::from_tier(TriplanarQualityTier::Ultra);
+
+        assert!(low.biplanar_color && !ultra.biplanar_color);
+        assert!(!low.normal_maps && ultra.normal_maps);
+        assert!(!low.arm_maps && ultra.arm_maps);
+        assert!(!low.stochastic_tiling && ultra.stochastic_tiling);
+        assert!(low.max_blend_materials < ultra.max_blend_materials);
+    }
+
+    #[test]
+    fn test_key_shader_defs() {
+        let defs = TriplanarQualitySettings::from_tier(TriplanarQualityTier::Low)
+            .key()
+            .shader_defs();
+
+        assert!(defs.contains(&ShaderDefVal::UInt("MAX_BLEND_MATERIALS".into(), 2)));
+        assert!(defs.contains(&"QUALITY_BIPLANAR_COLOR".into()));
+        assert!(!defs.contains(&"QUALITY_NORMAL_MAPS".into()));
+    }
+
+    #[test]
+    fn test_key_clamps_blend_materials() {
+        let zero = TriplanarQualitySettings {
+            max_blend_materials: 0,
+            ..default()
+        };
+        let one = TriplanarQualitySettings {
+            max_blend_materials: 1,
+            ..default()
+        };
+
+        assert_eq!(zero.key(), one.key());
+    }
+}
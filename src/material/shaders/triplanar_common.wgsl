@@ -0,0 +1,249 @@
+#define_import_path bevy_painter::triplanar_common
+
+// Shared between the triplanar main pass and prepass shaders: GPU types,
+// material bindings and the per-vertex helpers both vertex stages need.
+
+// GPU settings - must match TriplanarSettings in extension.rs
+struct TriplanarSettings {
+    texture_scale: f32,
+    blend_sharpness: f32,
+    flags: u32,
+    material_count: u32,
+    tint_mask_rect: vec4<f32>,
+    alpha_cutoff: f32,
+    debug_grid_cell_size: f32,
+    debug_grid_chunk_size: f32,
+    color_lut_strength: f32,
+    // x: band count, y: rim strength
+    toon_params: vec4<f32>,
+    // x: shell layers, y: shell height, z: blades per world unit
+    shell_params: vec4<f32>,
+    // x: reveal, y: edge width, z: noise frequency
+    reveal_params: vec4<f32>,
+    // rgb: emissive edge color
+    reveal_edge_color: vec4<f32>,
+    // x: texture mip bias
+    lod_params: vec4<f32>,
+}
+
+// Per-material properties - must match MaterialPropertiesGpu in properties.rs,
+// or MaterialPropertiesExtendedGpu with EXTENDED_MATERIAL_PROPERTIES
+struct MaterialProperties {
+    texture_scale: f32,
+    blend_sharpness: f32,
+    roughness_override: f32,
+    metallic_override: f32,
+    normal_strength: f32,
+    roughness_scale: f32,
+    metallic_scale: f32,
+    ao_strength: f32,
+    translucency: f32,
+    translucency_distortion: f32,
+    translucency_power: f32,
+    translucency_ambient: f32,
+    flags: u32,
+    bombing_cell_size: f32,
+    bombing_blend: f32,
+    displacement_strength: f32,
+    far_texture_scale: f32,
+    far_blend_start: f32,
+    far_blend_end: f32,
+    hue_variation: f32,
+    fog_multiplier: f32,
+#ifdef EXTENDED_MATERIAL_PROPERTIES
+    specular: f32,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    _padding: f32,
+#endif
+}
+
+// One material's sample, or a blend of several
+struct MaterialSample {
+    albedo: vec4<f32>,
+    normal: vec3<f32>,
+    roughness: f32,
+    metallic: f32,
+    ao: f32,
+    specular: f32,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    translucency: vec4<f32>,
+    // Distance fog strength, from `fog_multiplier`
+    fog: f32,
+}
+
+// Bindings - must match extension.rs bind_group_layout_entries
+// Use #{MATERIAL_BIND_GROUP} placeholder - Bevy replaces this at runtime
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> settings: TriplanarSettings;
+@group(#{MATERIAL_BIND_GROUP}) @binding(101) var albedo_array: texture_2d_array<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(102) var albedo_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(103) var<storage, read> material_props: array<MaterialProperties>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(104) var normal_array: texture_2d_array<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(105) var normal_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(106) var arm_array: texture_2d_array<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(107) var arm_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(108) var height_array: texture_2d_array<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(109) var height_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(110) var tint_mask: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(111) var tint_mask_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(112) var color_lut: texture_3d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(113) var color_lut_sampler: sampler;
+
+// Flags - must match TriplanarSettings constants
+const FLAG_USE_BIPLANAR: u32 = 1u;
+const FLAG_ENABLE_NORMALS: u32 = 2u;
+const FLAG_HAS_ARM: u32 = 4u;
+const FLAG_SEAM_DITHER: u32 = 8u;
+const FLAG_HAS_HEIGHT: u32 = 16u;
+const FLAG_HAS_TINT_MASK: u32 = 32u;
+const FLAG_ALPHA_CUTOUT: u32 = 64u;
+const FLAG_NORMALIZE_WEIGHTS: u32 = 256u;
+const FLAG_TOON_SHADING: u32 = 512u;
+const FLAG_HAS_COLOR_LUT: u32 = 1024u;
+const FLAG_DISSOLVE: u32 = 2048u;
+const FLAG_DEBUG_MATERIAL_IDS: u32 = 65536u;
+const FLAG_DEBUG_MATERIAL_WEIGHTS: u32 = 131072u;
+const FLAG_DEBUG_TRIPLANAR_WEIGHTS: u32 = 262144u;
+const FLAG_DEBUG_NORMALS: u32 = 524288u;
+const FLAG_DEBUG_VOXEL_GRID: u32 = 1048576u;
+const FLAG_DEBUG_CHUNK_BOUNDS: u32 = 2097152u;
+const DEBUG_FLAGS_MASK: u32 = 0xFFFF0000u;
+const DEBUG_VIEW_MASK: u32 = 0x000F0000u;
+
+// Per-material flags - must match MaterialPropertiesGpu constants
+const MATERIAL_FLAG_TEXTURE_BOMBING: u32 = 1u;
+const MATERIAL_FLAG_HARD_EDGES: u32 = 2u;
+const MATERIAL_FLAG_GRASS_SHELLS: u32 = 4u;
+
+// Custom vertex input with material attributes
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) material_ids: u32,
+    @location(3) material_weights: u32,
+#ifdef VERTEX_MATERIAL_PARAMS
+    // Wetness, burn, moss, reserved
+    @location(4) material_params: vec4<f32>,
+#endif
+#ifdef LIGHTMAP
+    // Lightmap UVs, from Mesh::ATTRIBUTE_UV_1
+    @location(5) uv_b: vec2<f32>,
+#endif
+}
+
+// ============================================================================
+// Utility functions
+// ============================================================================
+
+fn unpack_material_ids(packed: u32) -> vec4<u32> {
+    return vec4<u32>(
+        packed & 0xFFu,
+        (packed >> 8u) & 0xFFu,
+        (packed >> 16u) & 0xFFu,
+        (packed >> 24u) & 0xFFu,
+    );
+}
+
+// Remap material IDs using the per-instance override packed into MeshTag.
+// Layout must match InstanceMaterialOverride::pack in instancing.rs:
+// from0 | to0 << 8 | from1 << 16 | to1 << 24. A pair with from == to is unused.
+fn apply_instance_override(packed_ids: u32, tag: u32) -> u32 {
+    if tag == 0u {
+        return packed_ids;
+    }
+
+    let from0 = tag & 0xFFu;
+    let to0 = (tag >> 8u) & 0xFFu;
+    let from1 = (tag >> 16u) & 0xFFu;
+    let to1 = (tag >> 24u) & 0xFFu;
+
+    var result = 0u;
+    for (var i = 0u; i < 4u; i++) {
+        var id = (packed_ids >> (i * 8u)) & 0xFFu;
+        if from0 != to0 && id == from0 {
+            id = to0;
+        } else if from1 != to1 && id == from1 {
+            id = to1;
+        }
+        result |= id << (i * 8u);
+    }
+    return result;
+}
+
+fn unpack_material_weights(packed: u32) -> vec4<f32> {
+    let raw = vec4<f32>(
+        f32(packed & 0xFFu),
+        f32((packed >> 8u) & 0xFFu),
+        f32((packed >> 16u) & 0xFFu),
+        f32((packed >> 24u) & 0xFFu),
+    );
+    if (settings.flags & FLAG_NORMALIZE_WEIGHTS) == 0u {
+        return raw / 255.0;
+    }
+    // Must match VertexMaterialData::normalized_weights
+    let sum = raw.x + raw.y + raw.z + raw.w;
+    if sum > 0.0 {
+        return raw / sum;
+    }
+    return vec4<f32>(1.0, 0.0, 0.0, 0.0);
+}
+
+// Number of leading weight slots that can be non-zero. Weights are flat per
+// triangle, so this is uniform across a primitive: vertices blending only 1-2
+// materials (the common case) never enter the third and fourth sample chains.
+fn active_material_slots(packed_weights: u32) -> u32 {
+    if (packed_weights & 0xFF000000u) != 0u {
+        return 4u;
+    }
+    if (packed_weights & 0x00FF0000u) != 0u {
+        return 3u;
+    }
+    if (packed_weights & 0x0000FF00u) != 0u {
+        return 2u;
+    }
+    return 1u;
+}
+
+// ============================================================================
+// Displacement
+// ============================================================================
+
+// World-space offset along the normal from the height array, blended across
+// the vertex's materials by weight. Sampled at LOD 0 with the same projections
+// and texture scale as the fragment shader, without dithering or bombing.
+fn displacement_offset(
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    packed_ids: u32,
+    packed_weights: u32,
+) -> vec3<f32> {
+    if (settings.flags & FLAG_HAS_HEIGHT) == 0u {
+        return vec3<f32>(0.0);
+    }
+
+    let n = normalize(world_normal);
+    var projection = pow(abs(n), vec3<f32>(settings.blend_sharpness));
+    projection /= max(projection.x + projection.y + projection.z, 0.0001);
+
+    let ids = unpack_material_ids(packed_ids);
+    let weights = unpack_material_weights(packed_weights);
+
+    var height = 0.0;
+    for (var i = 0u; i < active_material_slots(packed_weights); i++) {
+        let id = min(ids[i], max(settings.material_count, 1u) - 1u);
+        let props = material_props[id];
+        if props.displacement_strength == 0.0 {
+            continue;
+        }
+
+        let p = world_position * settings.texture_scale * props.texture_scale;
+        let h = textureSampleLevel(height_array, height_sampler, p.yz, id, 0.0).r * projection.x
+            + textureSampleLevel(height_array, height_sampler, p.xz, id, 0.0).r * projection.y
+            + textureSampleLevel(height_array, height_sampler, p.xy, id, 0.0).r * projection.z;
+        height += h * props.displacement_strength * weights[i];
+    }
+
+    return n * height;
+}
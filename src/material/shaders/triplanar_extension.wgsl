@@ -7,59 +7,45 @@
     pbr_functions::alpha_discard,
     mesh_functions,
     view_transformations::position_world_to_clip,
+    pbr_types::{PbrInput, pbr_input_new, STANDARD_MATERIAL_FLAGS_FOG_ENABLED_BIT},
+    pbr_functions as fns,
+    mesh_view_bindings::{view, lights},
 }
 
+#ifdef LIGHTMAP
+#import bevy_pbr::{lightmap::lightmap, pbr_bindings}
+#endif
+
 #ifdef PREPASS_PIPELINE
 #import bevy_pbr::{
     prepass_io::{FragmentOutput},
-    pbr_deferred_functions::deferred_output,
+    pbr_deferred_functions::deferred_gbuffer_from_pbr_input,
+    pbr_prepass_functions::calculate_motion_vector,
 }
 #else
 #import bevy_pbr::{
     forward_io::{FragmentOutput},
     pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+    mesh_bindings::mesh,
+    mesh_types::MESH_FLAGS_SHADOW_RECEIVER_BIT,
+    mesh_view_types::DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT,
+    shadows::fetch_directional_shadow,
 }
 #endif
 
-// GPU settings - must match TriplanarSettings in extension.rs
-struct TriplanarSettings {
-    texture_scale: f32,
-    blend_sharpness: f32,
-    flags: u32,
-    material_count: u32,
+#import bevy_painter::triplanar_common::{
+    Vertex, settings,
+    FLAG_SEAM_DITHER, FLAG_ALPHA_CUTOUT, FLAG_TOON_SHADING, FLAG_DISSOLVE,
+    FLAG_DEBUG_MATERIAL_IDS, FLAG_DEBUG_MATERIAL_WEIGHTS,
+    FLAG_DEBUG_TRIPLANAR_WEIGHTS, FLAG_DEBUG_NORMALS,
+    DEBUG_VIEW_MASK, FLAG_DEBUG_VOXEL_GRID, FLAG_DEBUG_CHUNK_BOUNDS,
+    unpack_material_ids, unpack_material_weights, apply_instance_override,
+    active_material_slots, displacement_offset,
 }
-
-// Per-material properties - must match MaterialPropertiesGpu in properties.rs
-struct MaterialProperties {
-    texture_scale: f32,
-    blend_sharpness: f32,
-    roughness_override: f32,
-    metallic_override: f32,
-}
-
-// Bindings - must match extension.rs bind_group_layout_entries
-// Use #{MATERIAL_BIND_GROUP} placeholder - Bevy replaces this at runtime
-@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> settings: TriplanarSettings;
-@group(#{MATERIAL_BIND_GROUP}) @binding(101) var albedo_array: texture_2d_array<f32>;
-@group(#{MATERIAL_BIND_GROUP}) @binding(102) var albedo_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(103) var<storage, read> material_props: array<MaterialProperties>;
-@group(#{MATERIAL_BIND_GROUP}) @binding(104) var normal_array: texture_2d_array<f32>;
-@group(#{MATERIAL_BIND_GROUP}) @binding(105) var normal_sampler: sampler;
-@group(#{MATERIAL_BIND_GROUP}) @binding(106) var arm_array: texture_2d_array<f32>;
-@group(#{MATERIAL_BIND_GROUP}) @binding(107) var arm_sampler: sampler;
-
-// Flags - must match TriplanarSettings constants
-const FLAG_USE_BIPLANAR: u32 = 1u;
-const FLAG_ENABLE_NORMALS: u32 = 2u;
-const FLAG_HAS_ARM: u32 = 4u;
-
-// Custom vertex input with material attributes
-struct Vertex {
-    @builtin(instance_index) instance_index: u32,
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>,
-    @location(2) material_ids: u32,
-    @location(3) material_weights: u32,
+#import bevy_painter::triplanar_sampling::{
+    MAX_BLEND_MATERIALS, enable_seam_dither, compute_triplanar_weights, sharpen_hard_edges,
+    apply_weathering, sample_blended_material, apply_surface_overrides, apply_color_grading,
+    apply_material_fog,
 }
 
 // Custom vertex output matching what fragment shader expects
@@ -70,6 +56,12 @@ struct VertexOutput {
     @location(2) @interpolate(flat) material_ids: u32,
     @location(3) @interpolate(flat) material_weights: u32,
     @location(4) instance_index: u32,
+#ifdef VERTEX_MATERIAL_PARAMS
+    @location(5) material_params: vec4<f32>,
+#endif
+#ifdef LIGHTMAP
+    @location(6) uv_b: vec2<f32>,
+#endif
 }
 
 @vertex
@@ -77,162 +69,215 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     var out: VertexOutput;
 
     let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
-    let world_position = mesh_functions::mesh_position_local_to_world(
+    var world_position = mesh_functions::mesh_position_local_to_world(
         world_from_local,
         vec4<f32>(vertex.position, 1.0)
     );
 
-    out.position = position_world_to_clip(world_position.xyz);
-    out.world_position = world_position;
     out.world_normal = mesh_functions::mesh_normal_local_to_world(
         vertex.normal,
         vertex.instance_index
     );
-    out.material_ids = vertex.material_ids;
+    out.material_ids = apply_instance_override(
+        vertex.material_ids,
+        mesh_functions::get_tag(vertex.instance_index),
+    );
     out.material_weights = vertex.material_weights;
+#ifdef VERTEX_MATERIAL_PARAMS
+    out.material_params = vertex.material_params;
+#endif
+#ifdef LIGHTMAP
+    out.uv_b = vertex.uv_b;
+#endif
+
+    world_position += vec4<f32>(displacement_offset(
+        world_position.xyz,
+        out.world_normal,
+        out.material_ids,
+        out.material_weights,
+    ), 0.0);
+    out.position = position_world_to_clip(world_position.xyz);
+    out.world_position = world_position;
     out.instance_index = vertex.instance_index;
 
     return out;
 }
 
 // ============================================================================
-// Utility functions
+// Debug views
 // ============================================================================
 
-fn unpack_material_ids(packed: u32) -> vec4<u32> {
-    return vec4<u32>(
-        packed & 0xFFu,
-        (packed >> 8u) & 0xFFu,
-        (packed >> 16u) & 0xFFu,
-        (packed >> 24u) & 0xFFu,
-    );
+// Stable, well-separated color per material ID
+fn material_id_color(id: u32) -> vec3<f32> {
+    let h = f32(id) * 0.618034;
+    return 0.5 + 0.5 * cos(6.283185 * (fract(h) + vec3<f32>(0.0, 0.333, 0.667)));
 }
 
-fn unpack_material_weights(packed: u32) -> vec4<f32> {
-    let raw = vec4<f32>(
-        f32(packed & 0xFFu),
-        f32((packed >> 8u) & 0xFFu),
-        f32((packed >> 16u) & 0xFFu),
-        f32((packed >> 24u) & 0xFFu),
-    );
-    let sum = raw.x + raw.y + raw.z + raw.w;
-    if sum > 0.0 {
-        return raw / sum;
+fn debug_color(
+    world_normal: vec3<f32>,
+    mat_ids: vec4<u32>,
+    mat_weights: vec4<f32>,
+) -> vec4<f32> {
+    if (settings.flags & FLAG_DEBUG_MATERIAL_IDS) != 0u {
+        return vec4<f32>(material_id_color(mat_ids.x), 1.0);
     }
-    return vec4<f32>(1.0, 0.0, 0.0, 0.0);
-}
-
-fn compute_triplanar_weights(world_normal: vec3<f32>, sharpness: f32) -> vec3<f32> {
-    var weights = abs(world_normal);
-    weights = pow(weights, vec3<f32>(sharpness));
-    let sum = weights.x + weights.y + weights.z;
-    if sum > 0.0001 {
-        return weights / sum;
+    if (settings.flags & FLAG_DEBUG_MATERIAL_WEIGHTS) != 0u {
+        return vec4<f32>(mat_weights.xyz, 1.0);
+    }
+    if (settings.flags & FLAG_DEBUG_TRIPLANAR_WEIGHTS) != 0u {
+        return vec4<f32>(compute_triplanar_weights(world_normal, settings.blend_sharpness), 1.0);
     }
-    return vec3<f32>(0.333, 0.333, 0.334);
+    return vec4<f32>(world_normal * 0.5 + 0.5, 1.0);
 }
 
-// ============================================================================
-// Triplanar sampling
-// ============================================================================
-
-fn sample_albedo_triplanar(
-    world_pos: vec3<f32>,
+// Antialiased coverage of lines every `cell` world units, `width` pixels wide.
+// The axis along the surface normal is ignored, otherwise a face lying on a
+// grid plane would be covered entirely. Fades out once cells get too small
+// on screen to read.
+fn grid_line_coverage(
+    world_position: vec3<f32>,
     world_normal: vec3<f32>,
-    material_id: u32,
-    tex_scale: f32,
-    sharpness: f32,
-) -> vec4<f32> {
-    let weights = compute_triplanar_weights(world_normal, sharpness);
-
-    let uv_x = world_pos.yz * tex_scale;
-    let uv_y = world_pos.xz * tex_scale;
-    let uv_z = world_pos.xy * tex_scale;
-
-    let col_x = textureSample(albedo_array, albedo_sampler, uv_x, material_id);
-    let col_y = textureSample(albedo_array, albedo_sampler, uv_y, material_id);
-    let col_z = textureSample(albedo_array, albedo_sampler, uv_z, material_id);
-
-    return col_x * weights.x + col_y * weights.y + col_z * weights.z;
+    cell: f32,
+    width: f32,
+) -> f32 {
+    let coord = world_position / max(cell, 1e-4);
+    let footprint = max(fwidth(coord), vec3<f32>(1e-6));
+    let pixels = abs(fract(coord - 0.5) - 0.5) / footprint;
+    let masked = select(pixels, vec3<f32>(1e6), abs(world_normal) > vec3<f32>(0.9));
+    let nearest = min(masked.x, min(masked.y, masked.z));
+    let fade = saturate(1.5 - 4.0 * max(footprint.x, max(footprint.y, footprint.z)));
+    return saturate(width * 0.5 + 0.5 - nearest) * fade;
 }
 
-fn sample_arm_triplanar(
-    world_pos: vec3<f32>,
+// Voxel grid and chunk boundary overlays, drawn over the shaded color
+fn apply_grid_overlay(
+    color: vec4<f32>,
+    world_position: vec3<f32>,
     world_normal: vec3<f32>,
-    material_id: u32,
-    tex_scale: f32,
-    sharpness: f32,
-) -> vec3<f32> {
-    let weights = compute_triplanar_weights(world_normal, sharpness);
+) -> vec4<f32> {
+    var rgb = color.rgb;
+    // Scale line colors with the surface so they read at any exposure
+    let brightness = max(max(rgb.r, rgb.g), max(rgb.b, 0.05));
+    if (settings.flags & FLAG_DEBUG_VOXEL_GRID) != 0u {
+        let line = grid_line_coverage(world_position, world_normal, settings.debug_grid_cell_size, 1.0);
+        rgb = mix(rgb, vec3<f32>(0.0), line * 0.6);
+    }
+    if (settings.flags & FLAG_DEBUG_CHUNK_BOUNDS) != 0u {
+        let line = grid_line_coverage(world_position, world_normal, settings.debug_grid_chunk_size, 3.0);
+        rgb = mix(rgb, vec3<f32>(1.0, 0.55, 0.1) * brightness, line);
+    }
+    return vec4<f32>(rgb, color.a);
+}
 
-    let uv_x = world_pos.yz * tex_scale;
-    let uv_y = world_pos.xz * tex_scale;
-    let uv_z = world_pos.xy * tex_scale;
+// ============================================================================
+// Translucency
+// ============================================================================
 
-    let arm_x = textureSample(arm_array, arm_sampler, uv_x, material_id).rgb;
-    let arm_y = textureSample(arm_array, arm_sampler, uv_y, material_id).rgb;
-    let arm_z = textureSample(arm_array, arm_sampler, uv_z, material_id).rgb;
+// Cheap backlight term for snow, ice and jade: light from directional lights
+// bleeds through towards the viewer (distorted by the surface normal), plus a
+// constant ambient glow. Params are (strength, distortion, power, ambient).
+// Returned light is pre-exposure, like Bevy's direct lighting.
+fn translucency_light(
+    albedo: vec3<f32>,
+    N: vec3<f32>,
+    V: vec3<f32>,
+    params: vec4<f32>,
+) -> vec3<f32> {
+    if params.x <= 0.0 {
+        return vec3<f32>(0.0);
+    }
 
-    return arm_x * weights.x + arm_y * weights.y + arm_z * weights.z;
+    var light = vec3<f32>(0.0);
+    for (var i = 0u; i < lights.n_directional_lights; i++) {
+        let directional = lights.directional_lights[i];
+        let L = directional.direction_to_light;
+        let H = normalize(L + N * params.y);
+        let scatter = pow(saturate(dot(V, -H)), params.z) + params.w;
+        light += directional.color.rgb * scatter;
+    }
+    return albedo * light * params.x;
 }
 
 // ============================================================================
-// Material sampling
+// Dissolve
 // ============================================================================
 
-struct MaterialSample {
-    albedo: vec4<f32>,
-    roughness: f32,
-    metallic: f32,
-    ao: f32,
+fn hash13(p: vec3<f32>) -> f32 {
+    var q = fract(p * 0.1031);
+    q += dot(q, q.zyx + 31.32);
+    return fract((q.x + q.y) * q.z);
 }
 
-fn sample_material(
-    world_pos: vec3<f32>,
-    world_normal: vec3<f32>,
-    material_id: u32,
-) -> MaterialSample {
-    var result: MaterialSample;
-    
-    let id = min(material_id, max(settings.material_count, 1u) - 1u);
-    let props = material_props[id];
-    
-    let tex_scale = settings.texture_scale * props.texture_scale;
-    let sharpness = settings.blend_sharpness * props.blend_sharpness;
+fn value_noise3(p: vec3<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let x00 = mix(hash13(i), hash13(i + vec3<f32>(1.0, 0.0, 0.0)), u.x);
+    let x10 = mix(hash13(i + vec3<f32>(0.0, 1.0, 0.0)), hash13(i + vec3<f32>(1.0, 1.0, 0.0)), u.x);
+    let x01 = mix(hash13(i + vec3<f32>(0.0, 0.0, 1.0)), hash13(i + vec3<f32>(1.0, 0.0, 1.0)), u.x);
+    let x11 = mix(hash13(i + vec3<f32>(0.0, 1.0, 1.0)), hash13(i + vec3<f32>(1.0, 1.0, 1.0)), u.x);
+    return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z);
+}
 
-    result.albedo = sample_albedo_triplanar(world_pos, world_normal, id, tex_scale, sharpness);
-    
-    if (settings.flags & FLAG_HAS_ARM) != 0u {
-        let arm = sample_arm_triplanar(world_pos, world_normal, id, tex_scale, sharpness);
-        result.ao = arm.r;
-        result.roughness = arm.g;
-        result.metallic = arm.b;
-    } else {
-        result.ao = 1.0;
-        result.roughness = 0.5;
-        result.metallic = 0.0;
+// Discards pixels whose world-space noise is at or above the reveal value,
+// so reveal 0 hides everything and 1 shows everything. Returns the strength
+// of the glow band just inside the dissolve front.
+fn apply_dissolve(world_position: vec3<f32>) -> f32 {
+    let reveal = settings.reveal_params.x;
+    let edge_width = settings.reveal_params.y;
+    let p = world_position * settings.reveal_params.z;
+    // Two octaves, remapped to roughly fill 0-1
+    let noise = saturate((value_noise3(p) * 0.67 + value_noise3(p * 2.7) * 0.33 - 0.15) / 0.7);
+    if noise >= reveal {
+        discard;
     }
-    
-    if props.roughness_override >= 0.0 {
-        result.roughness = props.roughness_override;
+    if edge_width <= 0.0 {
+        return 0.0;
     }
-    if props.metallic_override >= 0.0 {
-        result.metallic = props.metallic_override;
+    // Fade the band out as reveal reaches 1 so finishing doesn't pop
+    let band = 1.0 - saturate((reveal - noise) / edge_width);
+    return band * saturate((1.0 - reveal) / edge_width);
+}
+
+#ifndef PREPASS_PIPELINE
+// Banded ambient + directional lighting with a hard rim light.
+// params.x is the band count, params.y the rim strength.
+fn toon_lighting(in: PbrInput, params: vec4<f32>, mesh_flags: u32) -> vec4<f32> {
+    let albedo = in.material.base_color.rgb;
+    let steps = max(params.x, 1.0);
+    let view_z = dot(vec4<f32>(
+        view.view_from_world[0].z,
+        view.view_from_world[1].z,
+        view.view_from_world[2].z,
+        view.view_from_world[3].z,
+    ), in.world_position);
+
+    var light = lights.ambient_color.rgb * in.diffuse_occlusion;
+    var rim_light = vec3<f32>(0.0);
+    for (var i = 0u; i < lights.n_directional_lights; i++) {
+        let directional = lights.directional_lights[i];
+        var intensity = saturate(dot(in.N, directional.direction_to_light));
+        if (directional.flags & DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u
+            && (mesh_flags & MESH_FLAGS_SHADOW_RECEIVER_BIT) != 0u {
+            intensity *= fetch_directional_shadow(i, in.world_position, in.world_normal, view_z);
+        }
+        light += directional.color.rgb * ceil(intensity * steps) / steps;
+        rim_light += directional.color.rgb * step(0.0, intensity - 0.5 / steps);
     }
-    
-    return result;
+
+    // Hard fresnel band on the lit side
+    let fresnel = 1.0 - saturate(dot(in.N, in.V));
+    let rim = smoothstep(0.6, 0.65, fresnel) * params.y;
+
+    let color = albedo * light + albedo * rim_light * rim + in.material.emissive.rgb;
+    return vec4<f32>(color * view.exposure, in.material.base_color.a);
 }
+#endif
 
 // ============================================================================
 // Fragment shader - manually construct PbrInput since we have custom VertexOutput
 // ============================================================================
 
-#import bevy_pbr::{
-    pbr_types::{PbrInput, pbr_input_new},
-    pbr_functions as fns,
-    mesh_view_bindings::view,
-}
-
 @fragment
 fn fragment(
     in: VertexOutput,
@@ -241,50 +286,73 @@ fn fragment(
     let world_position = in.world_position.xyz;
     let world_normal = normalize(in.world_normal);
 
-    // Unpack material data
-    let mat_ids = unpack_material_ids(in.material_ids);
-    let mat_weights = unpack_material_weights(in.material_weights);
-
-    // Blend materials
-    var blended_albedo = vec4<f32>(0.0);
-    var blended_roughness = 0.0;
-    var blended_metallic = 0.0;
-    var blended_ao = 0.0;
-
-    if mat_weights.x > 0.001 {
-        let sample = sample_material(world_position, world_normal, mat_ids.x);
-        blended_albedo += sample.albedo * mat_weights.x;
-        blended_roughness += sample.roughness * mat_weights.x;
-        blended_metallic += sample.metallic * mat_weights.x;
-        blended_ao += sample.ao * mat_weights.x;
+    // Dissolve first so hidden pixels skip texture sampling
+    var dissolve_glow = 0.0;
+    if (settings.flags & FLAG_DISSOLVE) != 0u {
+        dissolve_glow = apply_dissolve(world_position);
     }
-    if mat_weights.y > 0.001 {
-        let sample = sample_material(world_position, world_normal, mat_ids.y);
-        blended_albedo += sample.albedo * mat_weights.y;
-        blended_roughness += sample.roughness * mat_weights.y;
-        blended_metallic += sample.metallic * mat_weights.y;
-        blended_ao += sample.ao * mat_weights.y;
+
+    if (settings.flags & FLAG_SEAM_DITHER) != 0u {
+        enable_seam_dither(in.position.xy);
     }
-    if mat_weights.z > 0.001 {
-        let sample = sample_material(world_position, world_normal, mat_ids.z);
-        blended_albedo += sample.albedo * mat_weights.z;
-        blended_roughness += sample.roughness * mat_weights.z;
-        blended_metallic += sample.metallic * mat_weights.z;
-        blended_ao += sample.ao * mat_weights.z;
+
+    // Unpack material data
+    let mat_ids = unpack_material_ids(in.material_ids);
+    let slot_count = min(MAX_BLEND_MATERIALS, active_material_slots(in.material_weights));
+    let mat_weights = sharpen_hard_edges(
+        mat_ids,
+        unpack_material_weights(in.material_weights),
+        slot_count,
+    );
+
+    var surface = sample_blended_material(
+        world_position,
+        world_normal,
+        mat_ids,
+        mat_weights,
+        slot_count,
+        in.instance_index,
+    );
+
+#ifdef VERTEX_MATERIAL_PARAMS
+    let weathered = apply_weathering(surface.albedo.rgb, surface.roughness, in.material_params);
+    surface.albedo = vec4<f32>(weathered.rgb, surface.albedo.a);
+    surface.roughness = weathered.a;
+#endif
+
+    surface = apply_surface_overrides(surface, world_position, world_normal, in.instance_index);
+    var blended_albedo = surface.albedo;
+
+    // Cutout. Alpha-to-coverage turns alpha into a one-pixel ramp around the
+    // cutoff so MSAA coverage antialiases the edge instead of discarding.
+    if (settings.flags & FLAG_ALPHA_CUTOUT) != 0u {
+#ifdef TRIPLANAR_ALPHA_TO_COVERAGE
+        let edge_width = max(fwidth(blended_albedo.a), 1e-4);
+        blended_albedo.a = saturate((blended_albedo.a - settings.alpha_cutoff) / edge_width + 0.5);
+#else
+        if blended_albedo.a < settings.alpha_cutoff {
+            discard;
+        }
+        blended_albedo.a = 1.0;
+#endif
     }
-    if mat_weights.w > 0.001 {
-        let sample = sample_material(world_position, world_normal, mat_ids.w);
-        blended_albedo += sample.albedo * mat_weights.w;
-        blended_roughness += sample.roughness * mat_weights.w;
-        blended_metallic += sample.metallic * mat_weights.w;
-        blended_ao += sample.ao * mat_weights.w;
+
+    if (settings.flags & DEBUG_VIEW_MASK) != 0u {
+        blended_albedo = debug_color(world_normal, mat_ids, mat_weights);
     }
 
     // Build PbrInput manually (following array_texture.wgsl pattern)
     var pbr_input: PbrInput = pbr_input_new();
     
-    // Set material base color
+    // Set material properties
     pbr_input.material.base_color = blended_albedo;
+    pbr_input.material.perceptual_roughness = surface.roughness;
+    pbr_input.material.metallic = surface.metallic;
+    pbr_input.diffuse_occlusion = vec3<f32>(surface.ao);
+    pbr_input.material.reflectance = vec3<f32>(surface.specular);
+    pbr_input.material.clearcoat = surface.clearcoat;
+    pbr_input.material.clearcoat_perceptual_roughness = surface.clearcoat_roughness;
+    pbr_input.material.emissive = vec4<f32>(settings.reveal_edge_color.rgb * dissolve_glow, 1.0);
     
     // Geometry setup
     pbr_input.frag_coord = in.position;
@@ -295,15 +363,65 @@ fn fragment(
         is_front,
     );
     pbr_input.is_orthographic = view.clip_from_view[3].w == 1.0;
-    pbr_input.N = normalize(pbr_input.world_normal);
+    pbr_input.N = fns::prepare_world_normal(surface.normal, false, is_front);
+    // Clearcoat sits on top of the normal-mapped surface, using the geometric normal
+    pbr_input.clearcoat_N = pbr_input.world_normal;
     pbr_input.V = fns::calculate_view(in.world_position, pbr_input.is_orthographic);
+#ifdef LIGHTMAP
+    // Baked indirect light, scaled by the base material's `lightmap_exposure`
+    pbr_input.lightmap_light = lightmap(
+        in.uv_b,
+        pbr_bindings::material.lightmap_exposure,
+        in.instance_index,
+    );
+#endif
 
 #ifdef PREPASS_PIPELINE
-    let out = deferred_output(in, pbr_input);
+    // The deferred lighting pass fogs all or nothing
+    if surface.fog >= 0.5 && (settings.flags & DEBUG_VIEW_MASK) == 0u {
+        pbr_input.material.flags |= STANDARD_MATERIAL_FLAGS_FOG_ENABLED_BIT;
+    }
+    // `deferred_output` expects Bevy's prepass VertexOutput, so fill the
+    // gbuffer directly
+    var out: FragmentOutput;
+    out.deferred = deferred_gbuffer_from_pbr_input(pbr_input);
+    out.deferred_lighting_pass_id = pbr_input.material.deferred_lighting_pass_id;
+#ifdef NORMAL_PREPASS
+    out.normal = vec4<f32>(pbr_input.N * 0.5 + vec3<f32>(0.5), 1.0);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    // Terrain is static, so only camera motion contributes
+    out.motion_vector = calculate_motion_vector(in.world_position, in.world_position);
+#endif
 #else
     var out: FragmentOutput;
-    out.color = apply_pbr_lighting(pbr_input);
-    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    if (settings.flags & DEBUG_VIEW_MASK) != 0u {
+        // Debug views are unlit
+        out.color = blended_albedo;
+    } else {
+        var lit: vec4<f32>;
+        if (settings.flags & FLAG_TOON_SHADING) != 0u {
+            lit = toon_lighting(pbr_input, settings.toon_params, mesh[in.instance_index].flags);
+        } else {
+            lit = apply_pbr_lighting(pbr_input);
+            lit = vec4<f32>(
+                lit.rgb + translucency_light(
+                    blended_albedo.rgb,
+                    pbr_input.N,
+                    pbr_input.V,
+                    surface.translucency,
+                ) * view.exposure,
+                lit.a,
+            );
+        }
+        lit = apply_color_grading(lit);
+        // Fog here instead of in post-processing, scaled per material
+        lit = apply_material_fog(lit, world_position, surface.fog);
+        out.color = main_pass_post_lighting_processing(pbr_input, lit);
+    }
+    if (settings.flags & (FLAG_DEBUG_VOXEL_GRID | FLAG_DEBUG_CHUNK_BOUNDS)) != 0u {
+        out.color = apply_grid_overlay(out.color, world_position, world_normal);
+    }
 #endif
 
     return out;
@@ -0,0 +1,35 @@
+#define_import_path bevy_painter::user_hooks
+
+// Default user hooks: both leave their input unchanged.
+//
+// To inject custom logic, copy this file into your assets, keep the import
+// path and both function signatures, and point
+// `TriplanarVoxelPlugin::shader_hooks` at it. Hooks may import
+// `bevy_painter::triplanar_common` and Bevy's own modules, but not
+// `bevy_painter::triplanar_sampling`, which imports them.
+
+#import bevy_painter::triplanar_common::MaterialSample
+
+// Called on each material's sample before blending, e.g. to recolor one
+// material by team. `instance_index` reads per-instance data such as
+// `mesh_functions::get_tag`.
+fn material_hook(
+    sample: MaterialSample,
+    material_id: u32,
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    instance_index: u32,
+) -> MaterialSample {
+    return sample;
+}
+
+// Called on the blended, weathered surface before alpha cutout and lighting,
+// e.g. for dissolve effects that lower alpha or `discard`.
+fn surface_hook(
+    surface: MaterialSample,
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    instance_index: u32,
+) -> MaterialSample {
+    return surface;
+}
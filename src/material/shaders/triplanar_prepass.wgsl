@@ -0,0 +1,185 @@
+// Prepass and shadow shader for the triplanar voxel material
+// Reads the custom vertex layout and writes the prepass outputs Bevy expects.
+// The fragment stage also applies the alpha cutout, so cut pixels are missing
+// from depth and shadow maps like they are from the main pass.
+
+#import bevy_pbr::{
+    mesh_functions,
+    prepass_io::FragmentOutput,
+    pbr_functions,
+    pbr_prepass_functions::calculate_motion_vector,
+    view_transformations::position_world_to_clip,
+}
+#import bevy_painter::triplanar_common::{
+    Vertex, settings, FLAG_SEAM_DITHER, FLAG_ALPHA_CUTOUT,
+    apply_instance_override, displacement_offset, unpack_material_ids, unpack_material_weights,
+    active_material_slots,
+}
+#import bevy_painter::triplanar_sampling::{
+    MAX_BLEND_MATERIALS, enable_seam_dither, sharpen_hard_edges, sample_blended_material,
+    apply_surface_overrides,
+}
+
+// Bevy's prepass VertexOutput plus the material data the cutout needs
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) @interpolate(flat) material_ids: u32,
+    @location(3) @interpolate(flat) material_weights: u32,
+    @location(4) @interpolate(flat) instance_index: u32,
+#ifdef MOTION_VECTOR_PREPASS
+    @location(5) previous_world_position: vec4<f32>,
+#endif
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    @location(6) unclipped_depth: f32,
+#endif
+#ifdef VISIBILITY_RANGE_DITHER
+    @location(7) @interpolate(flat) visibility_range_dither: i32,
+#endif
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    let world_position = mesh_functions::mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(vertex.position, 1.0)
+    );
+    let world_normal = mesh_functions::mesh_normal_local_to_world(
+        vertex.normal,
+        vertex.instance_index
+    );
+
+    let material_ids = apply_instance_override(
+        vertex.material_ids,
+        mesh_functions::get_tag(vertex.instance_index),
+    );
+
+    // Must match the main pass, or depth prepass and shading disagree.
+    // TRIPLANAR_NO_DISPLACEMENT is only set for directional shadow maps.
+    var offset = vec3<f32>(0.0);
+#ifndef TRIPLANAR_NO_DISPLACEMENT
+    offset = displacement_offset(
+        world_position.xyz,
+        world_normal,
+        material_ids,
+        vertex.material_weights,
+    );
+#endif
+
+    out.world_position = world_position + vec4<f32>(offset, 0.0);
+    out.position = position_world_to_clip(out.world_position.xyz);
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.unclipped_depth = out.position.z;
+    out.position.z = min(out.position.z, 1.0); // Clamp depth to avoid clipping
+#endif
+
+    out.world_normal = world_normal;
+    out.material_ids = material_ids;
+    out.material_weights = vertex.material_weights;
+    out.instance_index = vertex.instance_index;
+
+#ifdef MOTION_VECTOR_PREPASS
+    // Same as Bevy's prepass: last frame's transform gives the previous
+    // position, which the prepass fragment shader projects with last frame's
+    // view to write the motion vector. Displacement is sampled in world
+    // space, so a moving chunk's offset is re-evaluated where it was.
+    let previous_world_from_local = mesh_functions::get_previous_world_from_local(
+        vertex.instance_index
+    );
+    var previous_world_position = mesh_functions::mesh_position_local_to_world(
+        previous_world_from_local,
+        vec4<f32>(vertex.position, 1.0)
+    );
+#ifndef TRIPLANAR_NO_DISPLACEMENT
+    // Exact for uniform scale; there is no previous normal matrix
+    let previous_world_normal = normalize(
+        (previous_world_from_local * vec4<f32>(vertex.normal, 0.0)).xyz
+    );
+    previous_world_position += vec4<f32>(
+        displacement_offset(
+            previous_world_position.xyz,
+            previous_world_normal,
+            material_ids,
+            vertex.material_weights,
+        ),
+        0.0,
+    );
+#endif
+    out.previous_world_position = previous_world_position;
+#endif
+
+#ifdef VISIBILITY_RANGE_DITHER
+    out.visibility_range_dither = mesh_functions::get_visibility_range_dither_level(
+        vertex.instance_index,
+        world_from_local[3],
+    );
+#endif
+
+    return out;
+}
+
+// Drops pixels the main pass cuts out. Must match the main pass cutout.
+fn alpha_cutout(in: VertexOutput) {
+    if (settings.flags & FLAG_ALPHA_CUTOUT) == 0u {
+        return;
+    }
+    if (settings.flags & FLAG_SEAM_DITHER) != 0u {
+        enable_seam_dither(in.position.xy);
+    }
+    let world_position = in.world_position.xyz;
+    let world_normal = normalize(in.world_normal);
+    let mat_ids = unpack_material_ids(in.material_ids);
+    let slot_count = min(MAX_BLEND_MATERIALS, active_material_slots(in.material_weights));
+    let mat_weights = sharpen_hard_edges(
+        mat_ids,
+        unpack_material_weights(in.material_weights),
+        slot_count,
+    );
+    var surface = sample_blended_material(
+        world_position,
+        world_normal,
+        mat_ids,
+        mat_weights,
+        slot_count,
+        in.instance_index,
+    );
+    surface = apply_surface_overrides(surface, world_position, world_normal, in.instance_index);
+    if surface.albedo.a < settings.alpha_cutoff {
+        discard;
+    }
+}
+
+#ifdef PREPASS_FRAGMENT
+@fragment
+fn fragment(in: VertexOutput) -> FragmentOutput {
+#ifdef VISIBILITY_RANGE_DITHER
+    pbr_functions::visibility_range_dither(in.position, in.visibility_range_dither);
+#endif
+    alpha_cutout(in);
+
+    var out: FragmentOutput;
+#ifdef NORMAL_PREPASS
+    out.normal = vec4<f32>(normalize(in.world_normal) * 0.5 + vec3<f32>(0.5), 1.0);
+#endif
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.frag_depth = in.unclipped_depth;
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    out.motion_vector = calculate_motion_vector(in.world_position, in.previous_world_position);
+#endif
+    return out;
+}
+#else
+// Depth-only passes, including shadow maps, only run this for cutouts
+@fragment
+fn fragment(in: VertexOutput) {
+#ifdef VISIBILITY_RANGE_DITHER
+    pbr_functions::visibility_range_dither(in.position, in.visibility_range_dither);
+#endif
+    alpha_cutout(in);
+}
+#endif
@@ -0,0 +1,661 @@
+#define_import_path bevy_painter::triplanar_sampling
+
+// Texture sampling and material blending of the triplanar main pass, for
+// custom shaders that reuse the blend. Per-material and per-surface logic is
+// injected through `bevy_painter::user_hooks`.
+
+#import bevy_pbr::mesh_view_bindings::view
+#ifdef DISTANCE_FOG
+#import bevy_pbr::{mesh_view_bindings::fog, pbr_functions::apply_fog}
+#endif
+
+#import bevy_painter::triplanar_common::{
+    MaterialProperties, MaterialSample,
+    settings, albedo_array, albedo_sampler, material_props,
+    normal_array, normal_sampler, arm_array, arm_sampler, tint_mask, tint_mask_sampler,
+    color_lut, color_lut_sampler,
+    FLAG_USE_BIPLANAR, FLAG_ENABLE_NORMALS, FLAG_HAS_ARM, FLAG_HAS_TINT_MASK, FLAG_HAS_COLOR_LUT,
+    FLAG_NORMALIZE_WEIGHTS, MATERIAL_FLAG_TEXTURE_BOMBING, MATERIAL_FLAG_HARD_EDGES,
+    unpack_material_ids, unpack_material_weights, active_material_slots,
+}
+#import bevy_painter::user_hooks
+
+// Quality shader defs - set by TriplanarQualityKey::shader_defs
+const MAX_BLEND_MATERIALS: u32 = #{MAX_BLEND_MATERIALS}u;
+
+// ============================================================================
+// Utility functions
+// ============================================================================
+
+// Per-pixel offset added to projection weights, set by `enable_seam_dither`
+// when FLAG_SEAM_DITHER is enabled. Zero otherwise.
+var<private> projection_jitter: vec3<f32> = vec3<f32>(0.0);
+
+// Interleaved gradient noise (Jimenez 2014): cheap, blue-noise-like per pixel
+fn interleaved_gradient_noise(pixel: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(pixel, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+fn seam_dither_jitter(pixel: vec2<f32>) -> vec3<f32> {
+    let noise = vec3<f32>(
+        interleaved_gradient_noise(pixel),
+        interleaved_gradient_noise(pixel + vec2<f32>(17.0, 59.0)),
+        interleaved_gradient_noise(pixel + vec2<f32>(113.0, 31.0)),
+    );
+    return (noise - 0.5) * 0.15;
+}
+
+// Dither projection weights for the rest of this invocation
+fn enable_seam_dither(pixel: vec2<f32>) {
+    projection_jitter = seam_dither_jitter(pixel);
+}
+
+fn compute_triplanar_weights(world_normal: vec3<f32>, sharpness: f32) -> vec3<f32> {
+    var weights = abs(world_normal);
+    weights = pow(weights, vec3<f32>(sharpness));
+    var sum = weights.x + weights.y + weights.z;
+    if sum <= 0.0001 {
+        return vec3<f32>(0.333, 0.333, 0.334);
+    }
+    weights /= sum;
+
+    // Turn the band at projection transitions into noise. Saturating keeps
+    // fully dominant projections stable away from transitions.
+    weights = saturate(weights + projection_jitter);
+    sum = weights.x + weights.y + weights.z;
+    return weights / max(sum, 0.0001);
+}
+
+// ============================================================================
+// Texture sampling
+// ============================================================================
+
+fn hash12(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = hash12(i);
+    let b = hash12(i + vec2<f32>(1.0, 0.0));
+    let c = hash12(i + vec2<f32>(0.0, 1.0));
+    let d = hash12(i + vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// Screen-space UV derivatives, widened by the material's mip bias
+fn biased_dpdx(uv: vec2<f32>) -> vec2<f32> {
+    return dpdx(uv) * exp2(settings.lod_params.x);
+}
+
+fn biased_dpdy(uv: vec2<f32>) -> vec2<f32> {
+    return dpdy(uv) * exp2(settings.lod_params.x);
+}
+
+// Sample a texture array layer, optionally hiding tiling repetition by blending
+// two randomly offset samples chosen from low-frequency noise (Quilez,
+// "texture repetition", technique 3 with procedural noise).
+fn sample_layer_stochastic(
+    tex: texture_2d_array<f32>,
+    samp: sampler,
+    uv: vec2<f32>,
+    layer: u32,
+) -> vec4<f32> {
+#ifdef QUALITY_STOCHASTIC_TILING
+    let duv_dx = biased_dpdx(uv);
+    let duv_dy = biased_dpdy(uv);
+    let k = value_noise(uv * 0.25) * 8.0;
+    let i = floor(k);
+    let f = fract(k);
+    let offset_a = sin(vec2<f32>(3.0, 7.0) * i);
+    let offset_b = sin(vec2<f32>(3.0, 7.0) * (i + 1.0));
+    let a = textureSampleGrad(tex, samp, uv + offset_a, layer, duv_dx, duv_dy);
+    let b = textureSampleGrad(tex, samp, uv + offset_b, layer, duv_dx, duv_dy);
+    let d = a.rgb - b.rgb;
+    return mix(a, b, smoothstep(0.2, 0.8, f - 0.1 * (d.x + d.y + d.z)));
+#else
+    return textureSampleBias(tex, samp, uv, layer, settings.lod_params.x);
+#endif
+}
+
+// Random 90 degree rotation and optional mirror for a bombing cell
+fn bombing_transform(cell: vec2<f32>) -> mat2x2<f32> {
+    let r = u32(hash12(cell) * 8.0) & 7u;
+    var m = mat2x2<f32>(1.0, 0.0, 0.0, 1.0);
+    switch r & 3u {
+        case 1u: { m = mat2x2<f32>(0.0, 1.0, -1.0, 0.0); }
+        case 2u: { m = mat2x2<f32>(-1.0, 0.0, 0.0, -1.0); }
+        case 3u: { m = mat2x2<f32>(0.0, -1.0, 1.0, 0.0); }
+        default: {}
+    }
+    if (r & 4u) != 0u {
+        m[0] = -m[0];
+    }
+    return m;
+}
+
+// Sample one bombing cell with its random transform. Tangent-space normals
+// are rotated back so lighting stays consistent with the unrotated surface.
+fn sample_bombing_cell(
+    tex: texture_2d_array<f32>,
+    samp: sampler,
+    uv: vec2<f32>,
+    layer: u32,
+    cell: vec2<f32>,
+    cell_size: f32,
+    duv_dx: vec2<f32>,
+    duv_dy: vec2<f32>,
+    is_normal: bool,
+) -> vec4<f32> {
+    let m = bombing_transform(cell);
+    let center = (cell + 0.5) * cell_size;
+    let cell_uv = m * (uv - center) + center;
+    var texel = textureSampleGrad(tex, samp, cell_uv, layer, m * duv_dx, m * duv_dy);
+    if is_normal {
+        let xy = transpose(m) * (texel.xy * 2.0 - 1.0);
+        texel = vec4<f32>(xy * 0.5 + 0.5, texel.zw);
+    }
+    return texel;
+}
+
+// Texture bombing: each world-space cell gets a random rotation/mirror.
+// Near cell borders the neighbouring cell is cross-faded in over `blend`
+// (fraction of a cell) to hide the seam. bombing = (cell_size, blend).
+fn sample_layer_bombed(
+    tex: texture_2d_array<f32>,
+    samp: sampler,
+    uv: vec2<f32>,
+    layer: u32,
+    bombing: vec2<f32>,
+    is_normal: bool,
+) -> vec4<f32> {
+    let cell_size = bombing.x;
+    let blend = bombing.y;
+    let duv_dx = biased_dpdx(uv);
+    let duv_dy = biased_dpdy(uv);
+    let cell_uv = uv / cell_size;
+    let cell = floor(cell_uv);
+    let f = fract(cell_uv);
+
+    var result = sample_bombing_cell(tex, samp, uv, layer, cell, cell_size, duv_dx, duv_dy, is_normal);
+    if blend <= 0.0 {
+        return result;
+    }
+
+    // Weight of the nearest neighbour per axis: 0.5 on the border, 0 at `blend`
+    let edge = min(f, 1.0 - f);
+    let neighbour = 0.5 * (1.0 - smoothstep(vec2<f32>(0.0), vec2<f32>(blend), edge));
+    let step = select(vec2<f32>(-1.0), vec2<f32>(1.0), f > vec2<f32>(0.5));
+
+    if neighbour.x > 0.0 {
+        let other = sample_bombing_cell(
+            tex, samp, uv, layer, cell + vec2<f32>(step.x, 0.0), cell_size, duv_dx, duv_dy, is_normal,
+        );
+        result = mix(result, other, neighbour.x);
+    }
+    if neighbour.y > 0.0 {
+        let other = sample_bombing_cell(
+            tex, samp, uv, layer, cell + vec2<f32>(0.0, step.y), cell_size, duv_dx, duv_dy, is_normal,
+        );
+        result = mix(result, other, neighbour.y);
+    }
+    return result;
+}
+
+// Sample a texture array layer, bombed if the material opts in
+// (bombing.x > 0), otherwise stochastic or plain depending on quality.
+fn sample_layer(
+    tex: texture_2d_array<f32>,
+    samp: sampler,
+    uv: vec2<f32>,
+    layer: u32,
+    bombing: vec2<f32>,
+    is_normal: bool,
+) -> vec4<f32> {
+    if bombing.x > 0.0 {
+        return sample_layer_bombed(tex, samp, uv, layer, bombing, is_normal);
+    }
+    return sample_layer_stochastic(tex, samp, uv, layer);
+}
+
+// Keep only the two dominant projections
+fn biplanar_weights(weights: vec3<f32>) -> vec3<f32> {
+    var w = weights;
+    if w.x <= w.y && w.x <= w.z {
+        w.x = 0.0;
+    } else if w.y <= w.z {
+        w.y = 0.0;
+    } else {
+        w.z = 0.0;
+    }
+    return w / max(w.x + w.y + w.z, 0.0001);
+}
+
+fn use_biplanar_color() -> bool {
+#ifdef QUALITY_BIPLANAR_COLOR
+    return true;
+#else
+    return (settings.flags & FLAG_USE_BIPLANAR) != 0u;
+#endif
+}
+
+// ============================================================================
+// Triplanar sampling
+// ============================================================================
+
+fn sample_albedo_triplanar(
+    world_pos: vec3<f32>,
+    world_normal: vec3<f32>,
+    material_id: u32,
+    tex_scale: f32,
+    sharpness: f32,
+    bombing: vec2<f32>,
+) -> vec4<f32> {
+    var weights = compute_triplanar_weights(world_normal, sharpness);
+    if use_biplanar_color() {
+        weights = biplanar_weights(weights);
+    }
+
+    let uv_x = world_pos.yz * tex_scale;
+    let uv_y = world_pos.xz * tex_scale;
+    let uv_z = world_pos.xy * tex_scale;
+
+    let col_x = sample_layer(albedo_array, albedo_sampler, uv_x, material_id, bombing, false);
+    let col_y = sample_layer(albedo_array, albedo_sampler, uv_y, material_id, bombing, false);
+    let col_z = sample_layer(albedo_array, albedo_sampler, uv_z, material_id, bombing, false);
+
+    return col_x * weights.x + col_y * weights.y + col_z * weights.z;
+}
+
+fn sample_arm_triplanar(
+    world_pos: vec3<f32>,
+    world_normal: vec3<f32>,
+    material_id: u32,
+    tex_scale: f32,
+    sharpness: f32,
+    bombing: vec2<f32>,
+) -> vec3<f32> {
+    let weights = compute_triplanar_weights(world_normal, sharpness);
+
+    let uv_x = world_pos.yz * tex_scale;
+    let uv_y = world_pos.xz * tex_scale;
+    let uv_z = world_pos.xy * tex_scale;
+
+    let arm_x = sample_layer(arm_array, arm_sampler, uv_x, material_id, bombing, false).rgb;
+    let arm_y = sample_layer(arm_array, arm_sampler, uv_y, material_id, bombing, false).rgb;
+    let arm_z = sample_layer(arm_array, arm_sampler, uv_z, material_id, bombing, false).rgb;
+
+    return arm_x * weights.x + arm_y * weights.y + arm_z * weights.z;
+}
+
+// Reconstruct a tangent-space normal, scaling XY by the material's strength
+fn unpack_tangent_normal(encoded: vec4<f32>, strength: f32) -> vec3<f32> {
+    let xy = encoded.xy * 2.0 - 1.0;
+    let z = sqrt(max(1.0 - dot(xy, xy), 0.0));
+    return normalize(vec3<f32>(xy * strength, z));
+}
+
+// Triplanar normal mapping with whiteout blending. Tangent axes follow the
+// albedo projections: X uses (y, z), Y uses (x, z), Z uses (x, y).
+fn sample_normal_triplanar(
+    world_pos: vec3<f32>,
+    world_normal: vec3<f32>,
+    material_id: u32,
+    tex_scale: f32,
+    sharpness: f32,
+    strength: f32,
+    bombing: vec2<f32>,
+) -> vec3<f32> {
+    let weights = compute_triplanar_weights(world_normal, sharpness);
+
+    let uv_x = world_pos.yz * tex_scale;
+    let uv_y = world_pos.xz * tex_scale;
+    let uv_z = world_pos.xy * tex_scale;
+
+    var tn_x = unpack_tangent_normal(
+        sample_layer(normal_array, normal_sampler, uv_x, material_id, bombing, true),
+        strength,
+    );
+    var tn_y = unpack_tangent_normal(
+        sample_layer(normal_array, normal_sampler, uv_y, material_id, bombing, true),
+        strength,
+    );
+    var tn_z = unpack_tangent_normal(
+        sample_layer(normal_array, normal_sampler, uv_z, material_id, bombing, true),
+        strength,
+    );
+
+    tn_x = vec3<f32>(tn_x.xy + world_normal.yz, abs(tn_x.z) * world_normal.x);
+    tn_y = vec3<f32>(tn_y.xy + world_normal.xz, abs(tn_y.z) * world_normal.y);
+    tn_z = vec3<f32>(tn_z.xy + world_normal.xy, abs(tn_z.z) * world_normal.z);
+
+    return normalize(tn_x.zxy * weights.x + tn_y.xzy * weights.y + tn_z.xyz * weights.z);
+}
+
+// ============================================================================
+// Biome tint
+// ============================================================================
+
+// Rotate hue around the grey axis (Rodrigues), by `turns` of the color wheel
+fn rotate_hue(color: vec3<f32>, turns: f32) -> vec3<f32> {
+    let k = vec3<f32>(0.57735027);
+    let angle = turns * 6.2831853;
+    let c = cos(angle);
+    return color * c + cross(k, color) * sin(angle) + k * dot(k, color) * (1.0 - c);
+}
+
+// Recolor by the world-space tint mask. R shifts hue by up to a quarter turn
+// and G scales saturation up to 2x; 0.5 is neutral for both.
+fn apply_biome_tint(color: vec3<f32>, world_pos: vec3<f32>, strength: f32) -> vec3<f32> {
+    if strength <= 0.0 || (settings.flags & FLAG_HAS_TINT_MASK) == 0u {
+        return color;
+    }
+
+    let uv = saturate((world_pos.xz - settings.tint_mask_rect.xy) * settings.tint_mask_rect.zw);
+    let mask = textureSampleLevel(tint_mask, tint_mask_sampler, uv, 0.0).rg * 2.0 - 1.0;
+
+    let shifted = max(rotate_hue(color, mask.r * 0.25 * strength), vec3<f32>(0.0));
+    let luma = dot(shifted, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return max(mix(vec3<f32>(luma), shifted, 1.0 + mask.g * strength), vec3<f32>(0.0));
+}
+
+// ============================================================================
+// Material sampling
+// ============================================================================
+
+// Texture-driven terms of a material at one texture scale
+fn sample_material_textures(
+    world_pos: vec3<f32>,
+    world_normal: vec3<f32>,
+    id: u32,
+    props: MaterialProperties,
+    tex_scale: f32,
+) -> MaterialSample {
+    var result: MaterialSample;
+
+    let sharpness = settings.blend_sharpness * props.blend_sharpness;
+
+    // Texture bombing: (cell size in UV units, edge blend), zero when disabled
+    var bombing = vec2<f32>(0.0);
+    if (props.flags & MATERIAL_FLAG_TEXTURE_BOMBING) != 0u {
+        bombing = vec2<f32>(max(props.bombing_cell_size, 0.001), props.bombing_blend);
+    }
+
+    result.albedo = sample_albedo_triplanar(world_pos, world_normal, id, tex_scale, sharpness, bombing);
+
+    result.normal = world_normal;
+#ifdef QUALITY_NORMAL_MAPS
+    if (settings.flags & FLAG_ENABLE_NORMALS) != 0u {
+        result.normal = sample_normal_triplanar(
+            world_pos,
+            world_normal,
+            id,
+            tex_scale,
+            sharpness,
+            props.normal_strength,
+            bombing,
+        );
+    }
+#endif
+
+    result.ao = 1.0;
+    result.roughness = 0.5;
+    result.metallic = 0.0;
+#ifdef QUALITY_ARM_MAPS
+    if (settings.flags & FLAG_HAS_ARM) != 0u {
+        let arm = sample_arm_triplanar(world_pos, world_normal, id, tex_scale, sharpness, bombing);
+        result.ao = mix(1.0, arm.r, props.ao_strength);
+        result.roughness = clamp(arm.g * props.roughness_scale, 0.0, 1.0);
+        result.metallic = clamp(arm.b * props.metallic_scale, 0.0, 1.0);
+    }
+#endif
+
+    return result;
+}
+
+fn sample_material(
+    world_pos: vec3<f32>,
+    world_normal: vec3<f32>,
+    material_id: u32,
+) -> MaterialSample {
+    var result: MaterialSample;
+    
+    let id = min(material_id, max(settings.material_count, 1u) - 1u);
+    let props = material_props[id];
+
+    // Near/far cross-fade by camera distance. Outside the fade band only one
+    // scale is sampled, so the second sample is only paid for inside it.
+    var far = 0.0;
+    if props.far_texture_scale > 0.0 {
+        let distance = length(view.world_position - world_pos);
+        far = smoothstep(props.far_blend_start, props.far_blend_end, distance);
+    }
+
+    if far < 1.0 {
+        let tex_scale = settings.texture_scale * props.texture_scale;
+        result = sample_material_textures(world_pos, world_normal, id, props, tex_scale);
+    }
+    if far > 0.0 {
+        let tex_scale = settings.texture_scale * props.far_texture_scale;
+        let far_sample = sample_material_textures(world_pos, world_normal, id, props, tex_scale);
+        if far >= 1.0 {
+            result = far_sample;
+        } else {
+            result.albedo = mix(result.albedo, far_sample.albedo, far);
+            result.normal = normalize(mix(result.normal, far_sample.normal, far));
+            result.ao = mix(result.ao, far_sample.ao, far);
+            result.roughness = mix(result.roughness, far_sample.roughness, far);
+            result.metallic = mix(result.metallic, far_sample.metallic, far);
+        }
+    }
+    
+    result.albedo = vec4<f32>(
+        apply_biome_tint(result.albedo.rgb, world_pos, props.hue_variation),
+        result.albedo.a,
+    );
+
+    if props.roughness_override >= 0.0 {
+        result.roughness = props.roughness_override;
+    }
+    if props.metallic_override >= 0.0 {
+        result.metallic = props.metallic_override;
+    }
+
+    result.translucency = vec4<f32>(
+        props.translucency,
+        props.translucency_distortion,
+        props.translucency_power,
+        props.translucency_ambient,
+    );
+    result.fog = props.fog_multiplier;
+
+    result.specular = 0.5;
+    result.clearcoat = 0.0;
+    result.clearcoat_roughness = 0.0;
+#ifdef EXTENDED_MATERIAL_PROPERTIES
+    if props.specular >= 0.0 {
+        result.specular = props.specular;
+    }
+    result.clearcoat = props.clearcoat;
+    result.clearcoat_roughness = props.clearcoat_roughness;
+#endif
+    
+    return result;
+}
+
+// ============================================================================
+// Weight shaping and weathering
+// ============================================================================
+
+// Exponent applied to blend weights around hard-edged materials
+const HARD_EDGE_EXPONENT: f32 = 16.0;
+
+// Sharpen the cross-material weight curve when any blended material has hard
+// edges, so the transition collapses to a thin seam. Keeps the total weight.
+fn sharpen_hard_edges(ids: vec4<u32>, weights: vec4<f32>, slot_count: u32) -> vec4<f32> {
+    var hard = false;
+    for (var i = 0u; i < slot_count; i++) {
+        if weights[i] > 0.001 && (material_props[ids[i]].flags & MATERIAL_FLAG_HARD_EDGES) != 0u {
+            hard = true;
+        }
+    }
+    if !hard {
+        return weights;
+    }
+    let sharpened = pow(weights, vec4<f32>(HARD_EDGE_EXPONENT));
+    let total = dot(sharpened, vec4<f32>(1.0));
+    if total <= 0.0 {
+        return weights;
+    }
+    return sharpened * (dot(weights, vec4<f32>(1.0)) / total);
+}
+
+const SOOT_COLOR: vec3<f32> = vec3<f32>(0.03, 0.025, 0.02);
+const MOSS_COLOR: vec3<f32> = vec3<f32>(0.16, 0.26, 0.07);
+
+// Weathering from ATTRIBUTE_MATERIAL_PARAMS (x wetness, y burn, z moss).
+// Returns the weathered albedo in xyz and roughness in w.
+fn apply_weathering(albedo: vec3<f32>, roughness: f32, params: vec4<f32>) -> vec4<f32> {
+    // Moss grows on top, burning chars whatever is there
+    var color = mix(albedo, MOSS_COLOR, params.z);
+    var rough = mix(roughness, 0.85, params.z);
+    color = mix(color, SOOT_COLOR, params.y);
+    rough = mix(rough, 0.95, params.y);
+    // Water darkens porous surfaces and leaves a glossy film
+    color *= mix(1.0, 0.55, params.x);
+    rough = mix(rough, 0.08, params.x);
+    return vec4<f32>(color, rough);
+}
+
+// ============================================================================
+// Blending and hooks
+// ============================================================================
+
+// One material's sample, after the user's `material_hook`
+fn apply_material_overrides(
+    sample: MaterialSample,
+    material_id: u32,
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    instance_index: u32,
+) -> MaterialSample {
+    return user_hooks::material_hook(sample, material_id, world_position, world_normal, instance_index);
+}
+
+// The blended surface, after the user's `surface_hook`
+fn apply_surface_overrides(
+    surface: MaterialSample,
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    instance_index: u32,
+) -> MaterialSample {
+    return user_hooks::surface_hook(surface, world_position, world_normal, instance_index);
+}
+
+// Distance fog scaled by the blended `fog_multiplier`. Only for forward
+// output; deferred fog is applied by Bevy's lighting pass.
+fn apply_material_fog(color: vec4<f32>, world_position: vec3<f32>, fog_multiplier: f32) -> vec4<f32> {
+#ifdef DISTANCE_FOG
+    let fogged = apply_fog(fog, color, world_position, view.world_position.xyz);
+    return mix(color, fogged, saturate(fog_multiplier));
+#else
+    return color;
+#endif
+}
+
+// Grade the final color with the terrain LUT. The LUT covers linear 0-1;
+// anything above passes through ungraded.
+fn apply_color_grading(color: vec4<f32>) -> vec4<f32> {
+    if (settings.flags & FLAG_HAS_COLOR_LUT) == 0u {
+        return color;
+    }
+    let size = vec3<f32>(textureDimensions(color_lut));
+    let clamped = saturate(color.rgb);
+    // Sample texel centers so 0 and 1 hit the first and last entries
+    let uvw = clamped * ((size - 1.0) / size) + 0.5 / size;
+    let graded = textureSampleLevel(color_lut, color_lut_sampler, uvw, 0.0).rgb
+        + (color.rgb - clamped);
+    return vec4<f32>(mix(color.rgb, graded, settings.color_lut_strength), color.a);
+}
+
+// Blend up to the quality tier's limit of materials. Slots are sorted by
+// weight, so dropped slots are always the least significant. `weights` should
+// already be sharpened with `sharpen_hard_edges`.
+fn sample_blended_material(
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    ids: vec4<u32>,
+    weights: vec4<f32>,
+    slot_count: u32,
+    instance_index: u32,
+) -> MaterialSample {
+    var blended: MaterialSample;
+    var total_weight = 0.0;
+
+    for (var i = 0u; i < min(slot_count, MAX_BLEND_MATERIALS); i++) {
+        let weight = weights[i];
+        if weight > 0.001 {
+            let sample = apply_material_overrides(
+                sample_material(world_position, world_normal, ids[i]),
+                ids[i],
+                world_position,
+                world_normal,
+                instance_index,
+            );
+            blended.albedo += sample.albedo * weight;
+            blended.normal += sample.normal * weight;
+            blended.roughness += sample.roughness * weight;
+            blended.metallic += sample.metallic * weight;
+            blended.ao += sample.ao * weight;
+            blended.specular += sample.specular * weight;
+            blended.clearcoat += sample.clearcoat * weight;
+            blended.clearcoat_roughness += sample.clearcoat_roughness * weight;
+            blended.translucency += sample.translucency * weight;
+            blended.fog += sample.fog * weight;
+            total_weight += weight;
+        }
+    }
+
+    // Renormalize when slots were dropped
+    if total_weight > 0.0 && (settings.flags & FLAG_NORMALIZE_WEIGHTS) != 0u {
+        let inv = 1.0 / total_weight;
+        blended.albedo *= inv;
+        blended.roughness *= inv;
+        blended.metallic *= inv;
+        blended.ao *= inv;
+        blended.specular *= inv;
+        blended.clearcoat *= inv;
+        blended.clearcoat_roughness *= inv;
+        blended.translucency *= inv;
+        blended.fog *= inv;
+    }
+    if dot(blended.normal, blended.normal) > 1e-8 {
+        blended.normal = normalize(blended.normal);
+    } else {
+        blended.normal = world_normal;
+    }
+    return blended;
+}
+
+// Blended albedo straight from the packed vertex attributes
+fn sample_blended_albedo(
+    world_position: vec3<f32>,
+    world_normal: vec3<f32>,
+    packed_ids: u32,
+    packed_weights: u32,
+    instance_index: u32,
+) -> vec4<f32> {
+    let ids = unpack_material_ids(packed_ids);
+    let slot_count = active_material_slots(packed_weights);
+    let weights = sharpen_hard_edges(ids, unpack_material_weights(packed_weights), slot_count);
+    return sample_blended_material(
+        world_position,
+        world_normal,
+        ids,
+        weights,
+        slot_count,
+        instance_index,
+    ).albedo;
+}
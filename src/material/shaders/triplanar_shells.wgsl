@@ -0,0 +1,148 @@
+// Grass shells over triplanar voxel terrain
+// Each draw is one shell layer, its index carried in the MeshTag. Vertices
+// are pushed out along the normal; fragments keep only the part of each
+// blade tall enough to reach this layer, on materials flagged for shells
+
+#import bevy_pbr::{
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+    pbr_types::{PbrInput, pbr_input_new},
+    pbr_functions as fns,
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+    mesh_view_bindings::view,
+}
+
+#import bevy_painter::triplanar_common::{
+    Vertex, settings, material_props, MATERIAL_FLAG_GRASS_SHELLS,
+    unpack_material_ids, unpack_material_weights, active_material_slots, displacement_offset,
+}
+#import bevy_painter::triplanar_sampling::{
+    MAX_BLEND_MATERIALS, hash12, sample_blended_material, apply_surface_overrides,
+    apply_color_grading, apply_material_fog,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) @interpolate(flat) material_ids: u32,
+    @location(3) @interpolate(flat) material_weights: u32,
+    @location(4) instance_index: u32,
+    // Position on the unextruded surface, so blades stay put across layers
+    @location(5) surface_position: vec3<f32>,
+    @location(6) @interpolate(flat) layer: u32,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    var world_position = mesh_functions::mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(vertex.position, 1.0)
+    );
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        vertex.normal,
+        vertex.instance_index
+    );
+    out.material_ids = vertex.material_ids;
+    out.material_weights = vertex.material_weights;
+
+    world_position += vec4<f32>(displacement_offset(
+        world_position.xyz,
+        out.world_normal,
+        out.material_ids,
+        out.material_weights,
+    ), 0.0);
+    out.surface_position = world_position.xyz;
+
+    out.layer = mesh_functions::get_tag(vertex.instance_index);
+    let layers = max(settings.shell_params.x, 1.0);
+    let height = settings.shell_params.y * f32(out.layer) / layers;
+    world_position += vec4<f32>(normalize(out.world_normal) * height, 0.0);
+
+    out.position = position_world_to_clip(world_position.xyz);
+    out.world_position = world_position;
+    out.instance_index = vertex.instance_index;
+    return out;
+}
+
+// Summed weight of the shell-flagged materials
+fn grass_coverage(ids: vec4<u32>, weights: vec4<f32>, slot_count: u32) -> f32 {
+    var coverage = 0.0;
+    for (var i = 0u; i < slot_count; i++) {
+        let id = min(ids[i], max(settings.material_count, 1u) - 1u);
+        if (material_props[id].flags & MATERIAL_FLAG_GRASS_SHELLS) != 0u {
+            coverage += weights[i];
+        }
+    }
+    return saturate(coverage);
+}
+
+// Surface coordinates on the plane facing the dominant normal axis
+fn blade_plane(p: vec3<f32>, n: vec3<f32>) -> vec2<f32> {
+    let a = abs(n);
+    if a.y >= a.x && a.y >= a.z {
+        return p.xz;
+    }
+    if a.x >= a.z {
+        return p.yz;
+    }
+    return p.xy;
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> @location(0) vec4<f32> {
+    let world_normal = normalize(in.world_normal);
+    let mat_ids = unpack_material_ids(in.material_ids);
+    let slot_count = min(MAX_BLEND_MATERIALS, active_material_slots(in.material_weights));
+    let mat_weights = unpack_material_weights(in.material_weights);
+
+    let coverage = grass_coverage(mat_ids, mat_weights, slot_count);
+    if coverage <= 0.0 {
+        discard;
+    }
+
+    // One blade per cell, with a random height scaled by grass coverage so
+    // blades shorten towards other materials, tapering to a point
+    let uv = blade_plane(in.surface_position, world_normal) * settings.shell_params.z;
+    let blade_height = hash12(floor(uv)) * coverage;
+    let h = f32(in.layer) / max(settings.shell_params.x, 1.0);
+    let radius = 0.5 * (1.0 - h / max(blade_height, 1e-4));
+    if h > blade_height || length(fract(uv) - 0.5) > radius {
+        discard;
+    }
+
+    var surface = sample_blended_material(
+        in.surface_position,
+        world_normal,
+        mat_ids,
+        mat_weights,
+        slot_count,
+        in.instance_index,
+    );
+    surface = apply_surface_overrides(surface, in.world_position.xyz, world_normal, in.instance_index);
+
+    var pbr_input: PbrInput = pbr_input_new();
+    // Fake self-shadowing towards the roots
+    pbr_input.material.base_color = vec4<f32>(surface.albedo.rgb * mix(0.4, 1.0, h), 1.0);
+    pbr_input.material.perceptual_roughness = surface.roughness;
+    pbr_input.material.metallic = 0.0;
+    pbr_input.diffuse_occlusion = vec3<f32>(surface.ao);
+    pbr_input.material.reflectance = vec3<f32>(surface.specular);
+    pbr_input.frag_coord = in.position;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = fns::prepare_world_normal(world_normal, true, is_front);
+    pbr_input.is_orthographic = view.clip_from_view[3].w == 1.0;
+    pbr_input.N = pbr_input.world_normal;
+    pbr_input.V = fns::calculate_view(in.world_position, pbr_input.is_orthographic);
+
+    var lit = apply_pbr_lighting(pbr_input);
+    lit = apply_color_grading(lit);
+    lit = apply_material_fog(lit, in.world_position.xyz, surface.fog);
+    return main_pass_post_lighting_processing(pbr_input, lit);
+}
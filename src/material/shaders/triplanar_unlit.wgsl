@@ -0,0 +1,115 @@
+// Unlit triplanar voxel material
+// Same palette bindings and blending as the lit material, output without
+// PBR lighting for stylized games and cheap far LODs
+
+#import bevy_pbr::{
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+}
+
+#import bevy_painter::triplanar_common::{
+    Vertex, settings, FLAG_SEAM_DITHER, FLAG_ALPHA_CUTOUT,
+    unpack_material_ids, unpack_material_weights, apply_instance_override,
+    active_material_slots, displacement_offset,
+}
+#import bevy_painter::triplanar_sampling::{
+    MAX_BLEND_MATERIALS, enable_seam_dither, sharpen_hard_edges, apply_weathering,
+    sample_blended_material, apply_surface_overrides, apply_color_grading, apply_material_fog,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) @interpolate(flat) material_ids: u32,
+    @location(3) @interpolate(flat) material_weights: u32,
+    @location(4) instance_index: u32,
+#ifdef VERTEX_MATERIAL_PARAMS
+    @location(5) material_params: vec4<f32>,
+#endif
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    var world_position = mesh_functions::mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(vertex.position, 1.0)
+    );
+
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(
+        vertex.normal,
+        vertex.instance_index
+    );
+    out.material_ids = apply_instance_override(
+        vertex.material_ids,
+        mesh_functions::get_tag(vertex.instance_index),
+    );
+    out.material_weights = vertex.material_weights;
+#ifdef VERTEX_MATERIAL_PARAMS
+    out.material_params = vertex.material_params;
+#endif
+
+    world_position += vec4<f32>(displacement_offset(
+        world_position.xyz,
+        out.world_normal,
+        out.material_ids,
+        out.material_weights,
+    ), 0.0);
+    out.position = position_world_to_clip(world_position.xyz);
+    out.world_position = world_position;
+    out.instance_index = vertex.instance_index;
+
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world_position = in.world_position.xyz;
+    let world_normal = normalize(in.world_normal);
+
+    if (settings.flags & FLAG_SEAM_DITHER) != 0u {
+        enable_seam_dither(in.position.xy);
+    }
+
+    let mat_ids = unpack_material_ids(in.material_ids);
+    let slot_count = min(MAX_BLEND_MATERIALS, active_material_slots(in.material_weights));
+    let mat_weights = sharpen_hard_edges(
+        mat_ids,
+        unpack_material_weights(in.material_weights),
+        slot_count,
+    );
+
+    var surface = sample_blended_material(
+        world_position,
+        world_normal,
+        mat_ids,
+        mat_weights,
+        slot_count,
+        in.instance_index,
+    );
+
+#ifdef VERTEX_MATERIAL_PARAMS
+    let weathered = apply_weathering(surface.albedo.rgb, surface.roughness, in.material_params);
+    surface.albedo = vec4<f32>(weathered.rgb, surface.albedo.a);
+#endif
+
+    surface = apply_surface_overrides(surface, world_position, world_normal, in.instance_index);
+    var color = surface.albedo;
+
+    if (settings.flags & FLAG_ALPHA_CUTOUT) != 0u {
+#ifdef TRIPLANAR_ALPHA_TO_COVERAGE
+        let edge_width = max(fwidth(color.a), 1e-4);
+        color.a = saturate((color.a - settings.alpha_cutoff) / edge_width + 0.5);
+#else
+        if color.a < settings.alpha_cutoff {
+            discard;
+        }
+        color.a = 1.0;
+#endif
+    }
+
+    return apply_material_fog(apply_color_grading(color), world_position, surface.fog);
+}
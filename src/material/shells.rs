@@ -0,0 +1,248 @@
+//! Shell-textured grass over flagged palette materials.
+//!
+//! [`RenderGrassShells`] gives a chunk a few extra draws of its mesh, each
+//! pushed further out along the normal by the vertex shader and cut away
+//! by blade noise in the fragment shader, so materials with
+//! [`PaletteMaterial::grass_shells`](crate::palette::PaletteMaterial::grass_shells)
+//! look fuzzy up close. The layers are child entities sharing the chunk's
+//! mesh and one [`TriplanarShellMaterial`], told apart by their
+//! [`MeshTag`], so they batch into a single instanced draw.
+//!
+//! Shells are lit but don't write the depth prepass or cast shadows.
+
+use bevy::mesh::{MeshTag, MeshVertexBufferLayoutRef};
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{RenderPipelineDescriptor, SpecializedMeshPipelineError};
+use bevy::shader::ShaderRef;
+
+use super::extension::{
+    TriplanarExtension, TriplanarVoxelMaterial, impl_albedo_only_bind_group, specialize_triplanar,
+};
+
+/// Shell shader asset path (embedded).
+const TRIPLANAR_SHELLS_SHADER_PATH: &str =
+    "embedded://bevy_painter/material/shaders/triplanar_shells.wgsl";
+
+/// Grass shell layers of a [`TriplanarVoxelMaterial`].
+///
+/// Reads layer count, height and blade density from
+/// [`TriplanarExtension::grass_shells`], and the shell index of each draw
+/// from its [`MeshTag`], starting at 1 for the lowest shell.
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct TriplanarShellMaterial {
+    /// Palette textures, properties and settings, as on the lit material.
+    pub triplanar: TriplanarExtension,
+}
+
+impl TriplanarShellMaterial {
+    pub fn new(triplanar: TriplanarExtension) -> Self {
+        Self { triplanar }
+    }
+}
+
+impl From<&TriplanarVoxelMaterial> for TriplanarShellMaterial {
+    fn from(lit: &TriplanarVoxelMaterial) -> Self {
+        Self::new(lit.extension.clone())
+    }
+}
+
+// Blades are too thin for normal or ARM detail to show
+impl_albedo_only_bind_group!(TriplanarShellMaterial, "triplanar_shells");
+
+impl Material for TriplanarShellMaterial {
+    fn vertex_shader() -> ShaderRef {
+        TRIPLANAR_SHELLS_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        TRIPLANAR_SHELLS_SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_triplanar(descriptor, layout, key.mesh_key, key.bind_group_data)
+    }
+}
+
+/// Draw grass shells over this entity's [`TriplanarVoxelMaterial`].
+///
+/// Needs [`TriplanarExtension::grass_shells`] set on the material; only
+/// palette materials flagged with `grass_shells` grow any grass.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct RenderGrassShells;
+
+/// One shell layer, spawned as a child of a [`RenderGrassShells`] entity.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct GrassShellLayer;
+
+/// Shared shell materials, keyed by lit material.
+#[derive(Resource, Default)]
+pub struct TriplanarShellVariants {
+    variants: HashMap<AssetId<TriplanarVoxelMaterial>, Handle<TriplanarShellMaterial>>,
+}
+
+impl TriplanarShellVariants {
+    /// Number of shell materials currently alive.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Whether no shell materials exist.
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+}
+
+/// System that respawns shell layers when a [`RenderGrassShells`] entity
+/// is added, changes mesh or material, or its material is edited, and
+/// despawns them when the component is removed.
+#[allow(clippy::type_complexity)]
+pub fn sync_grass_shells(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<TriplanarVoxelMaterial>>,
+    chunks: Query<(
+        Entity,
+        Ref<Mesh3d>,
+        Ref<MeshMaterial3d<TriplanarVoxelMaterial>>,
+        Ref<RenderGrassShells>,
+        Option<&Children>,
+    )>,
+    layers: Query<(), With<GrassShellLayer>>,
+    children: Query<&Children>,
+    mut removed: RemovedComponents<RenderGrassShells>,
+    lit_materials: Res<Assets<TriplanarVoxelMaterial>>,
+    mut shell_materials: ResMut<Assets<TriplanarShellMaterial>>,
+    mut variants: ResMut<TriplanarShellVariants>,
+) {
+    let mut modified = Vec::new();
+    for event in events.read() {
+        match *event {
+            AssetEvent::Modified { id } => {
+                if let (Some(base), Some(variant)) =
+                    (lit_materials.get(id), variants.variants.get(&id))
+                    && let Some(target) = shell_materials.get_mut(variant)
+                {
+                    *target = TriplanarShellMaterial::from(base);
+                }
+                modified.push(id);
+            }
+            AssetEvent::Unused { id } | AssetEvent::Removed { id } => {
+                variants.variants.remove(&id);
+            }
+            _ => {}
+        }
+    }
+
+    let despawn_layers = |commands: &mut Commands, children: Option<&Children>| {
+        for &child in children.into_iter().flatten() {
+            if layers.contains(child) {
+                commands.entity(child).despawn();
+            }
+        }
+    };
+
+    for entity in removed.read() {
+        if !chunks.contains(entity) {
+            despawn_layers(&mut commands, children.get(entity).ok());
+        }
+    }
+
+    for (entity, mesh, material, shells, entity_children) in &chunks {
+        let stale = shells.is_changed()
+            || mesh.is_changed()
+            || material.is_changed()
+            || modified.contains(&material.id());
+        if !stale {
+            continue;
+        }
+        let Some(base) = lit_materials.get(&material.0) else {
+            continue;
+        };
+        despawn_layers(&mut commands, entity_children);
+        let Some(settings) = base.extension.grass_shells else {
+            continue;
+        };
+
+        let variant = variants
+            .variants
+            .entry(material.id())
+            .or_insert_with(|| shell_materials.add(TriplanarShellMaterial::from(base)))
+            .clone();
+        commands.entity(entity).with_children(|parent| {
+            for layer in 1..=settings.layers {
+                parent.spawn((
+                    Mesh3d(mesh.0.clone()),
+                    MeshMaterial3d(variant.clone()),
+                    MeshTag(layer),
+                    GrassShellLayer,
+                ));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::GrassShells;
+
+    #[test]
+    fn test_shell_layers_follow_component() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .init_asset::<Mesh>()
+            .init_asset::<TriplanarVoxelMaterial>()
+            .init_asset::<TriplanarShellMaterial>()
+            .init_resource::<TriplanarShellVariants>()
+            .add_systems(Update, sync_grass_shells);
+
+        let material = app
+            .world_mut()
+            .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+            .add(TriplanarVoxelMaterial {
+                base: StandardMaterial::default(),
+                extension: TriplanarExtension::default().with_grass_shells(GrassShells {
+                    layers: 4,
+                    ..default()
+                }),
+            });
+        let chunk = app
+            .world_mut()
+            .spawn((
+                Mesh3d::default(),
+                MeshMaterial3d(material),
+                RenderGrassShells,
+            ))
+            .id();
+        app.update();
+
+        let mut tags: Vec<u32> = app
+            .world_mut()
+            .query_filtered::<&MeshTag, With<GrassShellLayer>>()
+            .iter(app.world())
+            .map(|tag| tag.0)
+            .collect();
+        tags.sort_unstable();
+        assert_eq!(tags, vec![1, 2, 3, 4]);
+        assert_eq!(app.world().resource::<TriplanarShellVariants>().len(), 1);
+
+        app.world_mut()
+            .entity_mut(chunk)
+            .remove::<RenderGrassShells>();
+        app.update();
+        let remaining = app
+            .world_mut()
+            .query_filtered::<(), With<GrassShellLayer>>()
+            .iter(app.world())
+            .count();
+        assert_eq!(remaining, 0);
+    }
+}
@@ -53,8 +53,13 @@ pub fn validate_palettes(
             .as_ref()
             .map(|h| images.contains(h))
             .unwrap_or(true);
+        let height_loaded = palette
+            .height
+            .as_ref()
+            .map(|h| images.contains(h))
+            .unwrap_or(true);
 
-        if !albedo_loaded || !normal_loaded || !arm_loaded {
+        if !albedo_loaded || !normal_loaded || !arm_loaded || !height_loaded {
             // Not all assets loaded yet, skip validation
             continue;
         }
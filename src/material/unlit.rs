@@ -0,0 +1,338 @@
+//! Unlit triplanar material for stylized games and far LODs.
+//!
+//! [`TriplanarUnlitMaterial`] is a plain [`Material`] sharing the palette
+//! bindings and blending of [`TriplanarVoxelMaterial`], without the
+//! `StandardMaterial` PBR pipeline. Normal and ARM maps are never sampled.
+//!
+//! Entities pick it either by using `MeshMaterial3d<TriplanarUnlitMaterial>`
+//! directly, or by adding [`RenderUnlit`] to an entity with a lit material;
+//! the plugin then swaps in a shared unlit copy and swaps back when the
+//! component is removed.
+
+use bevy::mesh::MeshVertexBufferLayoutRef;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{RenderPipelineDescriptor, SpecializedMeshPipelineError};
+use bevy::shader::ShaderRef;
+
+use super::extension::{
+    TRIPLANAR_PREPASS_SHADER_PATH, TriplanarExtension, TriplanarVoxelMaterial,
+    impl_albedo_only_bind_group, specialize_triplanar,
+};
+
+/// Unlit shader asset path (embedded).
+const TRIPLANAR_UNLIT_SHADER_PATH: &str =
+    "embedded://bevy_painter/material/shaders/triplanar_unlit.wgsl";
+
+/// Triplanar material that outputs blended albedo without lighting.
+///
+/// Weathering params, instance overrides, displacement, alpha cutout and
+/// the `surface_hook` shader hook still apply.
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct TriplanarUnlitMaterial {
+    /// Palette textures, properties and settings, as on the lit material.
+    pub triplanar: TriplanarExtension,
+}
+
+impl TriplanarUnlitMaterial {
+    pub fn new(triplanar: TriplanarExtension) -> Self {
+        Self { triplanar }
+    }
+}
+
+impl From<&TriplanarVoxelMaterial> for TriplanarUnlitMaterial {
+    fn from(lit: &TriplanarVoxelMaterial) -> Self {
+        Self::new(lit.extension.clone())
+    }
+}
+
+// Unlit output never uses normal or ARM maps
+impl_albedo_only_bind_group!(TriplanarUnlitMaterial, "triplanar_unlit");
+
+impl Material for TriplanarUnlitMaterial {
+    fn vertex_shader() -> ShaderRef {
+        TRIPLANAR_UNLIT_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        TRIPLANAR_UNLIT_SHADER_PATH.into()
+    }
+
+    fn prepass_vertex_shader() -> ShaderRef {
+        TRIPLANAR_PREPASS_SHADER_PATH.into()
+    }
+
+    fn prepass_fragment_shader() -> ShaderRef {
+        TRIPLANAR_PREPASS_SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        specialize_triplanar(descriptor, layout, key.mesh_key, key.bind_group_data)
+    }
+}
+
+/// Render this entity with a [`TriplanarUnlitMaterial`] copy of its
+/// [`TriplanarVoxelMaterial`].
+///
+/// Entities sharing a lit material share its unlit copy, which follows
+/// edits to the lit material.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct RenderUnlit;
+
+/// The lit material of an entity currently rendered unlit.
+#[derive(Component, Clone, Debug)]
+pub struct TriplanarUnlitBase {
+    /// Material restored when [`RenderUnlit`] is removed.
+    pub base: Handle<TriplanarVoxelMaterial>,
+}
+
+/// Shared unlit copies, keyed by lit material.
+#[derive(Resource, Default)]
+pub struct TriplanarUnlitVariants {
+    variants: HashMap<AssetId<TriplanarVoxelMaterial>, Handle<TriplanarUnlitMaterial>>,
+}
+
+impl TriplanarUnlitVariants {
+    /// Number of unlit copies currently alive.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Whether no unlit copies exist.
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+}
+
+/// System that swaps [`RenderUnlit`] entities between lit and unlit materials,
+/// retrying ones whose lit material hasn't loaded yet.
+#[allow(clippy::type_complexity)]
+pub fn apply_render_unlit(
+    mut commands: Commands,
+    changed: Query<
+        Entity,
+        (
+            With<RenderUnlit>,
+            Or<(
+                Changed<MeshMaterial3d<TriplanarVoxelMaterial>>,
+                Added<RenderUnlit>,
+            )>,
+        ),
+    >,
+    lit: Query<&MeshMaterial3d<TriplanarVoxelMaterial>, With<RenderUnlit>>,
+    unlit: Query<&TriplanarUnlitBase, Without<RenderUnlit>>,
+    mut removed: RemovedComponents<RenderUnlit>,
+    mut pending: Local<Vec<Entity>>,
+    lit_materials: Res<Assets<TriplanarVoxelMaterial>>,
+    mut unlit_materials: ResMut<Assets<TriplanarUnlitMaterial>>,
+    mut variants: ResMut<TriplanarUnlitVariants>,
+) {
+    let mut queued: Vec<Entity> = pending.drain(..).collect();
+    queued.extend(changed.iter());
+    queued.sort_unstable();
+    queued.dedup();
+
+    for entity in queued {
+        // Despawned, swapped already or no longer unlit
+        let Ok(material) = lit.get(entity) else {
+            continue;
+        };
+        let base = material.0.clone();
+        let variant = match variants.variants.get(&base.id()) {
+            Some(variant) => variant.clone(),
+            None => {
+                let Some(base_material) = lit_materials.get(&base) else {
+                    pending.push(entity);
+                    continue;
+                };
+                let variant = unlit_materials.add(TriplanarUnlitMaterial::from(base_material));
+                variants.variants.insert(base.id(), variant.clone());
+                variant
+            }
+        };
+
+        // Both material components would render the mesh twice
+        commands
+            .entity(entity)
+            .remove::<MeshMaterial3d<TriplanarVoxelMaterial>>()
+            .insert((MeshMaterial3d(variant), TriplanarUnlitBase { base }));
+    }
+
+    for entity in removed.read() {
+        let Ok(current) = unlit.get(entity) else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .remove::<(MeshMaterial3d<TriplanarUnlitMaterial>, TriplanarUnlitBase)>()
+            .insert(MeshMaterial3d(current.base.clone()));
+    }
+}
+
+/// System that keeps unlit copies in sync with their lit materials.
+pub fn sync_unlit_variants(
+    mut events: MessageReader<AssetEvent<TriplanarVoxelMaterial>>,
+    lit_materials: Res<Assets<TriplanarVoxelMaterial>>,
+    mut unlit_materials: ResMut<Assets<TriplanarUnlitMaterial>>,
+    mut variants: ResMut<TriplanarUnlitVariants>,
+) {
+    for event in events.read() {
+        match *event {
+            AssetEvent::Modified { id } => {
+                let (Some(base), Some(variant)) =
+                    (lit_materials.get(id), variants.variants.get(&id))
+                else {
+                    continue;
+                };
+                if let Some(target) = unlit_materials.get_mut(variant) {
+                    *target = TriplanarUnlitMaterial::from(base);
+                }
+            }
+            AssetEvent::Unused { id } | AssetEvent::Removed { id } => {
+                variants.variants.remove(&id);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .init_asset::<TriplanarVoxelMaterial>()
+            .init_asset::<TriplanarUnlitMaterial>()
+            .init_resource::<TriplanarUnlitVariants>()
+            .add_systems(Update, apply_render_unlit);
+        app
+    }
+
+    #[test]
+    fn test_unlit_swap_and_restore() {
+        let mut app = app();
+        let base = app
+            .world_mut()
+            .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+            .add(TriplanarVoxelMaterial {
+                base: StandardMaterial::default(),
+                extension: TriplanarExtension::default(),
+            });
+        let a = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), RenderUnlit))
+            .id();
+        let b = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), RenderUnlit))
+            .id();
+        app.update();
+
+        let world = app.world();
+        assert!(
+            world
+                .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(a)
+                .is_none()
+        );
+        let unlit_a = &world
+            .get::<MeshMaterial3d<TriplanarUnlitMaterial>>(a)
+            .unwrap()
+            .0;
+        let unlit_b = &world
+            .get::<MeshMaterial3d<TriplanarUnlitMaterial>>(b)
+            .unwrap()
+            .0;
+        assert_eq!(unlit_a, unlit_b);
+        assert_eq!(world.resource::<TriplanarUnlitVariants>().len(), 1);
+
+        app.world_mut().entity_mut(a).remove::<RenderUnlit>();
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world
+                .get::<MeshMaterial3d<TriplanarVoxelMaterial>>(a)
+                .unwrap()
+                .0,
+            base
+        );
+        assert!(
+            world
+                .get::<MeshMaterial3d<TriplanarUnlitMaterial>>(a)
+                .is_none()
+        );
+        assert!(world.get::<TriplanarUnlitBase>(a).is_none());
+    }
+
+    #[test]
+    fn test_unlit_added_to_existing_entity() {
+        let mut app = app();
+        let base = app
+            .world_mut()
+            .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+            .add(TriplanarVoxelMaterial {
+                base: StandardMaterial::default(),
+                extension: TriplanarExtension::default(),
+            });
+        let entity = app.world_mut().spawn(MeshMaterial3d(base.clone())).id();
+        app.update();
+
+        app.world_mut().entity_mut(entity).insert(RenderUnlit);
+        app.update();
+
+        let world = app.world();
+        assert!(
+            world
+                .get::<MeshMaterial3d<TriplanarUnlitMaterial>>(entity)
+                .is_some()
+        );
+        assert_eq!(world.get::<TriplanarUnlitBase>(entity).unwrap().base, base);
+    }
+
+    #[test]
+    fn test_unlit_waits_for_lit_material() {
+        let mut app = app();
+        let base = app
+            .world_mut()
+            .resource::<Assets<TriplanarVoxelMaterial>>()
+            .reserve_handle();
+        let entity = app
+            .world_mut()
+            .spawn((MeshMaterial3d(base.clone()), RenderUnlit))
+            .id();
+        app.update();
+        assert!(
+            app.world()
+                .get::<MeshMaterial3d<TriplanarUnlitMaterial>>(entity)
+                .is_none()
+        );
+
+        app.world_mut()
+            .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+            .insert(
+                &base,
+                TriplanarVoxelMaterial {
+                    base: StandardMaterial::default(),
+                    extension: TriplanarExtension::default(),
+                },
+            )
+            .unwrap();
+        app.update();
+
+        assert!(
+            app.world()
+                .get::<MeshMaterial3d<TriplanarUnlitMaterial>>(entity)
+                .is_some()
+        );
+    }
+}
@@ -1,13 +1,14 @@
 //! Material blending logic based on density values.
 
+use bevy::mesh::VertexAttributeValues;
 use bevy::prelude::*;
-use bevy_sculpter::{
-    field::Field,
-    prelude::{DensityField, NeighborDensityFields},
-};
+use bevy_sculpter::prelude::{DensityField, NeighborDensityFields};
 
+use super::density;
+use super::storage;
 use super::{MaterialField, NeighborMaterialFields};
-use crate::mesh::VertexMaterialData;
+use crate::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS, VertexMaterialData};
+use crate::palette::{MaterialGroup, TexturePalette};
 
 /// Settings for material blending at vertices.
 #[derive(Resource, Clone, Debug)]
@@ -21,6 +22,27 @@ pub struct MaterialBlendSettings {
     /// Materials below this weight are excluded.
     /// Default: 0.01
     pub weight_threshold: f32,
+
+    /// Maximum materials kept per vertex (1-4).
+    /// Setting this to 2 lets the shader skip the third and fourth sample
+    /// chains on every pixel, which is much cheaper on terrain-heavy scenes.
+    /// Default: 4
+    pub max_materials: usize,
+
+    /// Per-material blending behavior, indexed by material ID.
+    /// Materials without an entry use the defaults.
+    /// Default: empty
+    pub materials: Vec<MaterialBlendInfo>,
+
+    /// Which material groups may blend with each other.
+    /// Default: [`GroupBlendRules::default`]
+    pub group_rules: GroupBlendRules,
+
+    /// Crease angle in degrees above which a dual contouring vertex counts
+    /// as a sharp feature when the mesher doesn't flag it.
+    /// `None` disables inference.
+    /// Default: None
+    pub sharp_feature_angle: Option<f32>,
 }
 
 impl Default for MaterialBlendSettings {
@@ -28,10 +50,131 @@ impl Default for MaterialBlendSettings {
         Self {
             density_influence: 2.0,
             weight_threshold: 0.01,
+            max_materials: 4,
+            materials: Vec::new(),
+            group_rules: GroupBlendRules::default(),
+            sharp_feature_angle: None,
+        }
+    }
+}
+
+/// Blending behavior of one material, taken from its
+/// [`PaletteMaterial`](crate::palette::PaletteMaterial).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MaterialBlendInfo {
+    /// See [`PaletteMaterial::blend_priority`](crate::palette::PaletteMaterial::blend_priority).
+    pub blend_priority: f32,
+    /// See [`PaletteMaterial::hard_edges`](crate::palette::PaletteMaterial::hard_edges).
+    pub hard_edges: bool,
+    /// See [`PaletteMaterial::group`](crate::palette::PaletteMaterial::group).
+    pub group: MaterialGroup,
+}
+
+/// Which [`MaterialGroup`]s may blend with each other at a vertex.
+///
+/// Materials that may not blend with a stronger material at the same vertex
+/// are dropped, so water never smears into brick. By default liquids only
+/// blend with liquids and artificial materials only with their own group.
+///
+/// # Example
+/// ```ignore
+/// let rules = GroupBlendRules::none()
+///     .isolate(MaterialGroup::Liquid)
+///     .deny(MaterialGroup::Custom(0), MaterialGroup::Artificial);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupBlendRules {
+    /// Groups that only blend with materials of the same group.
+    pub isolated: Vec<MaterialGroup>,
+    /// Pairs of groups that never blend, in either order.
+    pub denied: Vec<(MaterialGroup, MaterialGroup)>,
+}
+
+impl Default for GroupBlendRules {
+    fn default() -> Self {
+        Self {
+            isolated: vec![MaterialGroup::Liquid, MaterialGroup::Artificial],
+            denied: Vec::new(),
         }
     }
 }
 
+impl GroupBlendRules {
+    /// Rules letting every group blend with every other.
+    pub fn none() -> Self {
+        Self {
+            isolated: Vec::new(),
+            denied: Vec::new(),
+        }
+    }
+
+    /// Only let `group` blend within itself.
+    pub fn isolate(mut self, group: MaterialGroup) -> Self {
+        self.isolated.push(group);
+        self
+    }
+
+    /// Never blend groups `a` and `b`.
+    pub fn deny(mut self, a: MaterialGroup, b: MaterialGroup) -> Self {
+        self.denied.push((a, b));
+        self
+    }
+
+    /// Whether materials of groups `a` and `b` may blend.
+    pub fn allows(&self, a: MaterialGroup, b: MaterialGroup) -> bool {
+        if a == b {
+            return true;
+        }
+        !self.isolated.contains(&a)
+            && !self.isolated.contains(&b)
+            && !self.denied.contains(&(a, b))
+            && !self.denied.contains(&(b, a))
+    }
+}
+
+impl MaterialBlendSettings {
+    /// Settings that keep only the top 2 materials per vertex.
+    pub fn top_two() -> Self {
+        Self {
+            max_materials: 2,
+            ..default()
+        }
+    }
+
+    /// Infers sharp features at creases steeper than `degrees`.
+    pub fn with_sharp_feature_angle(mut self, degrees: f32) -> Self {
+        self.sharp_feature_angle = Some(degrees);
+        self
+    }
+
+    /// Takes per-material blending behavior from a palette.
+    pub fn with_palette(mut self, palette: &TexturePalette) -> Self {
+        self.materials = palette
+            .materials
+            .iter()
+            .map(|material| MaterialBlendInfo {
+                blend_priority: material.blend_priority,
+                hard_edges: material.hard_edges,
+                group: material.group,
+            })
+            .collect();
+        self
+    }
+
+    /// Blending behavior of a material.
+    pub fn material(&self, material_id: u8) -> MaterialBlendInfo {
+        self.materials
+            .get(material_id as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Weight multiplier from a material's blend priority.
+    pub(crate) fn priority_scale(&self, material_id: u8) -> f32 {
+        self.material(material_id).blend_priority.exp2()
+    }
+}
+
 /// Offsets to the 8 corners of a voxel cube.
 const CORNER_OFFSETS: [IVec3; 8] = [
     IVec3::new(0, 0, 0),
@@ -51,78 +194,455 @@ const CORNER_OFFSETS: [IVec3; 8] = [
 ///
 /// Only contributes voxels where BOTH density and material data are available,
 /// preventing incorrect material 0 blending at chunk boundaries.
-pub fn compute_vertex_materials(
+///
+/// Adapter over [`VertexMaterialComputer`]; prefer the computer when
+/// processing many vertices of the same chunk.
+pub fn compute_vertex_materials<M, D>(
     world_pos: Vec3,
     mesh_size: Vec3,
-    density_field: &DensityField,
-    material_field: &MaterialField,
+    density_field: &D,
+    material_field: &M,
     neighbor_densities: Option<&NeighborDensityFields>,
     neighbor_materials: Option<&NeighborMaterialFields>,
     settings: &MaterialBlendSettings,
-) -> VertexMaterialData {
-    let field_size = DensityField::SIZE;
-    let scale = field_size.as_vec3() / mesh_size;
-    let grid_pos = world_pos * scale;
-    let base = grid_pos.floor().as_ivec3();
+) -> VertexMaterialData
+where
+    M: storage::MaterialStorage + ?Sized,
+    D: density::DensitySource + ?Sized,
+{
+    VertexMaterialComputer::new(density_field, material_field, mesh_size, settings)
+        .with_neighbors(neighbor_densities, neighbor_materials)
+        .compute(world_pos)
+}
 
-    // Collect materials and their weights from 8 surrounding voxels
-    let mut contributions: Vec<(u8, f32)> = Vec::with_capacity(8);
+/// Computes vertex material data for one chunk.
+///
+/// Bundles the chunk's fields, neighbor data and blend settings so every
+/// CPU meshing path shares one blending implementation.
+///
+/// # Example
+/// ```ignore
+/// let computer = VertexMaterialComputer::new(&density, &materials, mesh_size, &settings)
+///     .with_neighbors(Some(&neighbor_density), Some(&neighbor_materials));
+/// let (ids, weights) = computer.compute_packed(&positions);
+/// mesh.insert_attribute(ATTRIBUTE_MATERIAL_IDS, ids);
+/// mesh.insert_attribute(ATTRIBUTE_MATERIAL_WEIGHTS, weights);
+/// ```
+///
+/// Materials come from any [`MaterialStorage`](super::MaterialStorage) covering the density
+/// field's grid; neighbor data stays in chunk format.
+pub struct VertexMaterialComputer<'a, M = MaterialField, D = DensityField>
+where
+    M: storage::MaterialStorage + ?Sized,
+    D: density::DensitySource + ?Sized,
+{
+    density_field: &'a D,
+    material_field: &'a M,
+    neighbor_densities: Option<&'a NeighborDensityFields>,
+    neighbor_materials: Option<&'a NeighborMaterialFields>,
+    settings: &'a MaterialBlendSettings,
+    /// Field cells per world unit.
+    scale: Vec3,
+    /// Material of a uniform chunk, which every interior vertex takes.
+    uniform: Option<u8>,
+}
 
-    // Track if we got any valid samples for fallback
-    let mut any_valid_sample = false;
-    let mut fallback_material: u8 = 0;
+// Manual impls: derives would require `M: Clone`
+impl<M, D> Clone for VertexMaterialComputer<'_, M, D>
+where
+    M: storage::MaterialStorage + ?Sized,
+    D: density::DensitySource + ?Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
 
-    for offset in &CORNER_OFFSETS {
-        let voxel = base + *offset;
+impl<M, D> Copy for VertexMaterialComputer<'_, M, D>
+where
+    M: storage::MaterialStorage + ?Sized,
+    D: density::DensitySource + ?Sized,
+{
+}
 
-        // Only contribute if we have BOTH valid density AND material
-        let Some((density, material)) = sample_voxel(
-            voxel,
+impl<'a, M, D> VertexMaterialComputer<'a, M, D>
+where
+    M: storage::MaterialStorage + ?Sized,
+    D: density::DensitySource + ?Sized,
+{
+    /// Creates a computer for a chunk mesh spanning `mesh_size` world units.
+    pub fn new(
+        density_field: &'a D,
+        material_field: &'a M,
+        mesh_size: Vec3,
+        settings: &'a MaterialBlendSettings,
+    ) -> Self {
+        Self {
             density_field,
             material_field,
-            neighbor_densities,
-            neighbor_materials,
-        ) else {
-            continue;
+            neighbor_densities: None,
+            neighbor_materials: None,
+            settings,
+            scale: density_field.size().as_vec3() / mesh_size,
+            uniform: material_field.uniform_region(UVec3::ZERO, density_field.size()),
+        }
+    }
+
+    /// Samples neighbor chunks across boundaries for seamless blending.
+    pub fn with_neighbors(
+        mut self,
+        densities: Option<&'a NeighborDensityFields>,
+        materials: Option<&'a NeighborMaterialFields>,
+    ) -> Self {
+        self.neighbor_densities = densities;
+        self.neighbor_materials = materials;
+        self
+    }
+
+    /// Material data for a single vertex in mesh-local space.
+    pub fn compute(&self, world_pos: Vec3) -> VertexMaterialData {
+        let grid_pos = world_pos * self.scale;
+        let base = grid_pos.floor().as_ivec3();
+
+        // Uniform chunks: all 8 corners hold the same material unless they
+        // reach into a neighbor
+        if let Some(material) = self.uniform
+            && base.cmpge(IVec3::ZERO).all()
+            && (base + IVec3::ONE)
+                .cmplt(self.density_field.size().as_ivec3())
+                .all()
+        {
+            return VertexMaterialData::single(material);
+        }
+
+        self.blend_voxels(CORNER_OFFSETS.iter().map(|&offset| base + offset), grid_pos)
+    }
+
+    /// Material data for a marching cubes vertex in mesh-local space.
+    ///
+    /// Marching cubes places vertices on grid edges, often exactly on a
+    /// cell face where [`compute`](Self::compute)'s `floor(pos)` cell is an
+    /// arbitrary pick and drags in voxels off the edge. This blends only
+    /// the edge's two endpoint voxels, found from the axis the vertex lies
+    /// furthest from a grid plane on.
+    pub fn compute_on_edge(&self, world_pos: Vec3) -> VertexMaterialData {
+        let grid_pos = world_pos * self.scale;
+        let offset = (grid_pos - grid_pos.round()).abs();
+        let axis = if offset.x >= offset.y && offset.x >= offset.z {
+            IVec3::X
+        } else if offset.y >= offset.z {
+            IVec3::Y
+        } else {
+            IVec3::Z
         };
+        // Round onto the edge's line, floor along it
+        let start = IVec3::select(
+            axis.cmpeq(IVec3::ONE),
+            grid_pos.floor().as_ivec3(),
+            grid_pos.round().as_ivec3(),
+        );
+        self.blend_voxels([start, start + axis].into_iter(), grid_pos)
+    }
 
-        // Track for fallback
-        if !any_valid_sample {
-            any_valid_sample = true;
-            fallback_material = material;
+    /// Material data for a dual contouring vertex in mesh-local space.
+    ///
+    /// Sharp feature vertices take the single material of the solid voxel
+    /// on the face the vertex belongs to, found by stepping inward along
+    /// `normal`, instead of averaging across the crease. Flat-shaded meshes
+    /// that split vertices per face keep each face's material this way, so
+    /// carved steps and walls stay crisp. `feature` is the mesher's flag;
+    /// `None` infers it with [`is_sharp_feature`](Self::is_sharp_feature).
+    /// Other vertices blend like [`compute`](Self::compute).
+    pub fn compute_dual_contouring(
+        &self,
+        world_pos: Vec3,
+        normal: Vec3,
+        feature: Option<bool>,
+    ) -> VertexMaterialData {
+        let feature = feature.unwrap_or_else(|| self.is_sharp_feature(world_pos));
+        if !feature {
+            return self.compute(world_pos);
         }
 
-        // Convert density to weight: more negative = more "inside" = higher weight
-        // Only interior voxels (negative density) contribute
-        if density < 0.0 {
-            let weight = (-density * settings.density_influence).clamp(0.0, 1.0);
-            if weight > settings.weight_threshold {
-                contributions.push((material, weight));
+        let grid_pos = world_pos * self.scale;
+        let base = grid_pos.floor().as_ivec3();
+        let target = grid_pos - (normal * self.scale).normalize_or_zero() * 0.5;
+        CORNER_OFFSETS
+            .iter()
+            .filter_map(|&offset| {
+                let voxel = base + offset;
+                let (density, material) = sample_voxel(
+                    voxel,
+                    self.density_field,
+                    self.material_field,
+                    self.neighbor_densities,
+                    self.neighbor_materials,
+                )?;
+                (density < 0.0).then(|| (voxel.as_vec3().distance_squared(target), material))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map_or_else(
+                || self.compute(world_pos),
+                |(_, material)| VertexMaterialData::single(material),
+            )
+    }
+
+    /// Single-material data for a vertex in mesh-local space, for
+    /// [`SimplifiedMaterials`](crate::material::SimplifiedMaterials) chunks.
+    ///
+    /// Takes the material of the most solid of the cell's corner voxels,
+    /// skipping weights, group rules and allocation, so it is much cheaper
+    /// than [`compute`](Self::compute) and the shader samples one material.
+    pub fn compute_dominant(&self, world_pos: Vec3) -> VertexMaterialData {
+        let base = (world_pos * self.scale).floor().as_ivec3();
+        CORNER_OFFSETS
+            .iter()
+            .filter_map(|&offset| {
+                sample_voxel(
+                    base + offset,
+                    self.density_field,
+                    self.material_field,
+                    self.neighbor_densities,
+                    self.neighbor_materials,
+                )
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map_or_else(
+                || self.compute(world_pos),
+                |(_, material)| VertexMaterialData::single(material),
+            )
+    }
+
+    /// Whether the surface creases sharply in the cell around `world_pos`.
+    ///
+    /// Compares density gradients at the cell's corners against
+    /// [`MaterialBlendSettings::sharp_feature_angle`]; always false when
+    /// that's unset.
+    pub fn is_sharp_feature(&self, world_pos: Vec3) -> bool {
+        let Some(angle) = self.settings.sharp_feature_angle else {
+            return false;
+        };
+        let base = (world_pos * self.scale).floor().as_ivec3();
+        let gradients: Vec<Vec3> = CORNER_OFFSETS
+            .iter()
+            .filter_map(|&offset| self.gradient(base + offset))
+            .collect();
+        let min_cos = angle.to_radians().cos();
+        gradients
+            .iter()
+            .enumerate()
+            .any(|(i, a)| gradients[i + 1..].iter().any(|b| a.dot(*b) < min_cos))
+    }
+
+    /// Normalized density gradient at a voxel, where it's defined.
+    fn gradient(&self, voxel: IVec3) -> Option<Vec3> {
+        let sample = |axis: IVec3| {
+            Some(
+                self.density_field.density(voxel + axis)?
+                    - self.density_field.density(voxel - axis)?,
+            )
+        };
+        vec3(sample(IVec3::X)?, sample(IVec3::Y)?, sample(IVec3::Z)?).try_normalize()
+    }
+
+    /// Blends the materials of `voxels` by how far inside each one is.
+    fn blend_voxels(
+        &self,
+        voxels: impl Iterator<Item = IVec3>,
+        grid_pos: Vec3,
+    ) -> VertexMaterialData {
+        let settings = self.settings;
+
+        // Collect materials and their weights from the sampled voxels
+        let mut contributions: Vec<(u8, f32)> = Vec::with_capacity(8);
+
+        // Track if we got any valid samples for fallback
+        let mut any_valid_sample = false;
+        let mut fallback_material: u8 = 0;
+
+        for voxel in voxels {
+            // Only contribute if we have BOTH valid density AND material
+            let Some((density, material)) = sample_voxel(
+                voxel,
+                self.density_field,
+                self.material_field,
+                self.neighbor_densities,
+                self.neighbor_materials,
+            ) else {
+                continue;
+            };
+
+            // Track for fallback
+            if !any_valid_sample {
+                any_valid_sample = true;
+                fallback_material = material;
+            }
+
+            // Convert density to weight: more negative = more "inside" = higher weight
+            // Only interior voxels (negative density) contribute
+            if density < 0.0 {
+                let weight = (-density * settings.density_influence).clamp(0.0, 1.0);
+                if weight > settings.weight_threshold {
+                    // Two-material voxels split their weight; neighbor data
+                    // holds primaries only
+                    let secondary = voxel
+                        .cmpge(IVec3::ZERO)
+                        .all()
+                        .then(|| self.material_field.secondary(voxel.as_uvec3()))
+                        .flatten();
+                    let share = secondary.map_or(0.0, |(_, share)| share);
+                    contributions.push((
+                        material,
+                        weight * (1.0 - share) * settings.priority_scale(material),
+                    ));
+                    if let Some((secondary, share)) = secondary {
+                        contributions.push((
+                            secondary,
+                            weight * share * settings.priority_scale(secondary),
+                        ));
+                    }
+                }
+            }
+        }
+
+        // If no interior voxels contributed, use fallback
+        if contributions.is_empty() {
+            if any_valid_sample {
+                return VertexMaterialData::single(fallback_material);
             }
+
+            // Absolute fallback: sample nearest in-bounds voxel
+            let field_size_i = self.density_field.size().as_ivec3();
+            let clamped = grid_pos
+                .round()
+                .as_ivec3()
+                .clamp(IVec3::ZERO, field_size_i - IVec3::ONE);
+            let material = self.material_field.get(clamped.as_uvec3());
+            return VertexMaterialData::single(material);
         }
+
+        // Merge duplicate materials and normalize weights
+        merge_and_normalize_materials(&mut contributions);
+        apply_group_rules(&mut contributions, settings);
+
+        // Hard-edged materials never blend: snap to the dominant material
+        let hard_edges = contributions
+            .iter()
+            .any(|&(material, _)| settings.material(material).hard_edges);
+        let max_materials = if hard_edges {
+            1
+        } else {
+            settings.max_materials
+        };
+        limit_materials(&mut contributions, max_materials);
+
+        // Convert to VertexMaterialData (up to 4 materials)
+        contributions_to_vertex_data(&contributions)
     }
 
-    // If no interior voxels contributed, use fallback
-    if contributions.is_empty() {
-        if any_valid_sample {
-            return VertexMaterialData::single(fallback_material);
+    /// Packed material IDs and weights for every position, ready for
+    /// [`ATTRIBUTE_MATERIAL_IDS`](crate::mesh::ATTRIBUTE_MATERIAL_IDS) and
+    /// [`ATTRIBUTE_MATERIAL_WEIGHTS`](crate::mesh::ATTRIBUTE_MATERIAL_WEIGHTS).
+    pub fn compute_packed(&self, positions: &[[f32; 3]]) -> (Vec<u32>, Vec<u32>) {
+        positions
+            .iter()
+            .map(|&pos| {
+                let data = self.compute(Vec3::from_array(pos));
+                (data.pack_ids(), data.pack_weights())
+            })
+            .unzip()
+    }
+
+    /// [`compute_packed`](Self::compute_packed) into reused buffers, which
+    /// are cleared first.
+    ///
+    /// Pair with
+    /// [`MeshTriplanarExt::set_triplanar_attributes`](crate::mesh::MeshTriplanarExt::set_triplanar_attributes)
+    /// to repaint a mesh without allocating.
+    pub fn compute_packed_into(
+        &self,
+        positions: &[[f32; 3]],
+        ids: &mut Vec<u32>,
+        weights: &mut Vec<u32>,
+    ) {
+        ids.clear();
+        weights.clear();
+        for &pos in positions {
+            let data = self.compute(Vec3::from_array(pos));
+            ids.push(data.pack_ids());
+            weights.push(data.pack_weights());
         }
+    }
 
-        // Absolute fallback: sample nearest in-bounds voxel
-        let field_size_i = field_size.as_ivec3();
-        let clamped = grid_pos
-            .round()
-            .as_ivec3()
-            .clamp(IVec3::ZERO, field_size_i - IVec3::ONE);
-        let material = material_field.get(clamped.x as u32, clamped.y as u32, clamped.z as u32);
-        return VertexMaterialData::single(material);
+    /// [`compute_packed_into`](Self::compute_packed_into) with
+    /// [`compute_dominant`](Self::compute_dominant).
+    pub fn compute_packed_dominant_into(
+        &self,
+        positions: &[[f32; 3]],
+        ids: &mut Vec<u32>,
+        weights: &mut Vec<u32>,
+    ) {
+        ids.clear();
+        weights.clear();
+        for &pos in positions {
+            let data = self.compute_dominant(Vec3::from_array(pos));
+            ids.push(data.pack_ids());
+            weights.push(data.pack_weights());
+        }
     }
 
-    // Merge duplicate materials and normalize weights
-    merge_and_normalize_materials(&mut contributions);
+    /// [`compute_packed`](Self::compute_packed) with
+    /// [`compute_dominant`](Self::compute_dominant).
+    pub fn compute_packed_dominant(&self, positions: &[[f32; 3]]) -> (Vec<u32>, Vec<u32>) {
+        positions
+            .iter()
+            .map(|&pos| {
+                let data = self.compute_dominant(Vec3::from_array(pos));
+                (data.pack_ids(), data.pack_weights())
+            })
+            .unzip()
+    }
 
-    // Convert to VertexMaterialData (up to 4 materials)
-    contributions_to_vertex_data(&contributions)
+    /// [`compute_packed`](Self::compute_packed) for marching cubes meshes,
+    /// using [`compute_on_edge`](Self::compute_on_edge).
+    pub fn compute_packed_on_edges(&self, positions: &[[f32; 3]]) -> (Vec<u32>, Vec<u32>) {
+        positions
+            .iter()
+            .map(|&pos| {
+                let data = self.compute_on_edge(Vec3::from_array(pos));
+                (data.pack_ids(), data.pack_weights())
+            })
+            .unzip()
+    }
+}
+
+/// Adds material attributes to a marching cubes mesh spanning `mesh_size`
+/// world units, with default blend settings.
+///
+/// Each vertex takes its material from the grid edge it was placed on
+/// rather than the cell below it; see
+/// [`VertexMaterialComputer::compute_on_edge`]. Use the computer directly
+/// for custom settings or neighbor data. Does nothing if the mesh lacks
+/// float positions.
+pub fn add_material_attributes_marching_cubes<D, M>(
+    mesh: &mut Mesh,
+    sampler: &D,
+    materials: &M,
+    mesh_size: Vec3,
+) where
+    D: density::DensitySource + ?Sized,
+    M: storage::MaterialStorage + ?Sized,
+{
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let settings = MaterialBlendSettings::default();
+    let (ids, weights) = VertexMaterialComputer::new(sampler, materials, mesh_size, &settings)
+        .compute_packed_on_edges(positions);
+    mesh.insert_attribute(ATTRIBUTE_MATERIAL_IDS, ids);
+    mesh.insert_attribute(ATTRIBUTE_MATERIAL_WEIGHTS, weights);
 }
 
 /// Samples both density and material at a voxel coordinate.
@@ -130,23 +650,30 @@ pub fn compute_vertex_materials(
 ///
 /// This ensures consistency - we only blend voxels where we have complete information.
 #[inline]
-fn sample_voxel(
+pub(crate) fn sample_voxel<M, D>(
     voxel: IVec3,
-    density_field: &DensityField,
-    material_field: &MaterialField,
+    density_field: &D,
+    material_field: &M,
     neighbor_densities: Option<&NeighborDensityFields>,
     neighbor_materials: Option<&NeighborMaterialFields>,
-) -> Option<(f32, u8)> {
+) -> Option<(f32, u8)>
+where
+    M: storage::MaterialStorage + ?Sized,
+    D: density::DensitySource + ?Sized,
+{
     // Try local fields first
     if let (Some(density), Some(material)) = (
-        density_field.get_ivec3(voxel),
+        density_field.density(voxel),
         material_field.get_ivec3(voxel),
     ) {
         return Some((density, material));
     }
 
-    // Out of bounds - need BOTH neighbor fields to have data
-    let density = neighbor_densities?.sample_for::<DensityField>(voxel)?;
+    // Out of bounds - need BOTH density and neighbor material data. The
+    // source may sample past its edge itself, else neighbor fields do
+    let density = density_field
+        .neighbor_density(voxel)
+        .or_else(|| neighbor_densities?.sample_for::<DensityField>(voxel))?;
     let material = neighbor_materials?.sample_for::<MaterialField>(voxel)?;
 
     Some((density, material))
@@ -183,6 +710,54 @@ fn merge_and_normalize_materials(contributions: &mut Vec<(u8, f32)>) {
     *contributions = merged;
 }
 
+/// Drops materials whose group may not blend with a stronger material at the
+/// same vertex, then renormalizes.
+///
+/// Expects contributions sorted by weight descending.
+fn apply_group_rules(contributions: &mut Vec<(u8, f32)>, settings: &MaterialBlendSettings) {
+    let mut kept: Vec<(u8, f32)> = Vec::with_capacity(contributions.len());
+    for &(material, weight) in contributions.iter() {
+        let group = settings.material(material).group;
+        let allowed = kept.iter().all(|&(stronger, _)| {
+            settings
+                .group_rules
+                .allows(settings.material(stronger).group, group)
+        });
+        if allowed {
+            kept.push((material, weight));
+        }
+    }
+    if kept.len() == contributions.len() {
+        return;
+    }
+
+    let sum: f32 = kept.iter().map(|(_, w)| w).sum();
+    if sum > 0.0 {
+        for (_, weight) in &mut kept {
+            *weight /= sum;
+        }
+    }
+    *contributions = kept;
+}
+
+/// Keeps the `max` highest-weighted materials and renormalizes.
+///
+/// Expects contributions sorted by weight descending.
+fn limit_materials(contributions: &mut Vec<(u8, f32)>, max: usize) {
+    let max = max.clamp(1, 4);
+    if contributions.len() <= max {
+        return;
+    }
+    contributions.truncate(max);
+
+    let sum: f32 = contributions.iter().map(|(_, w)| w).sum();
+    if sum > 0.0 {
+        for (_, weight) in contributions.iter_mut() {
+            *weight /= sum;
+        }
+    }
+}
+
 /// Converts material contributions to VertexMaterialData.
 fn contributions_to_vertex_data(contributions: &[(u8, f32)]) -> VertexMaterialData {
     match contributions.len() {
@@ -218,7 +793,10 @@ fn contributions_to_vertex_data(contributions: &[(u8, f32)]) -> VertexMaterialDa
 
 #[cfg(test)]
 mod tests {
+    use bevy_sculpter::field::Field;
+
     use super::*;
+    use crate::material_field::{DensitySource, MaterialSlice, MaterialSliceExt, NeighborFace};
 
     #[test]
     fn test_merge_materials() {
@@ -231,6 +809,127 @@ mod tests {
         assert!((contributions[0].1 - 0.7).abs() < 0.01);
     }
 
+    #[test]
+    fn test_blend_priority_wins_ties() {
+        let mut palette = TexturePalette::default();
+        palette.materials = vec![
+            crate::palette::PaletteMaterial::new("dirt").with_blend_priority(-1.0),
+            crate::palette::PaletteMaterial::new("road").with_blend_priority(1.0),
+        ];
+        let settings = MaterialBlendSettings::default().with_palette(&palette);
+        assert_eq!(settings.material(1).blend_priority, 1.0);
+        assert_eq!(settings.material(7), MaterialBlendInfo::default());
+
+        // Half dirt, half road, all corners equally inside
+        let mut density = DensityField::new();
+        let mut materials = MaterialField::new();
+        for offset in CORNER_OFFSETS {
+            let voxel = (ivec3(15, 8, 8) + offset).as_uvec3();
+            density.set(voxel.x, voxel.y, voxel.z, -1.0);
+            materials.set(voxel.x, voxel.y, voxel.z, (voxel.x >= 16) as u8);
+        }
+
+        let data = VertexMaterialComputer::new(&density, &materials, Vec3::splat(32.0), &settings)
+            .compute(vec3(15.5, 8.5, 8.5));
+        assert_eq!(data.ids[0], 1);
+        // 4:1 after priorities of +1 and -1
+        assert!((data.weights[0] as f32 / 255.0 - 0.8).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_group_rules() {
+        let rules = GroupBlendRules::default();
+        assert!(rules.allows(MaterialGroup::Natural, MaterialGroup::Natural));
+        assert!(rules.allows(MaterialGroup::Liquid, MaterialGroup::Liquid));
+        assert!(!rules.allows(MaterialGroup::Liquid, MaterialGroup::Natural));
+        assert!(!rules.allows(MaterialGroup::Natural, MaterialGroup::Artificial));
+        assert!(rules.allows(MaterialGroup::Natural, MaterialGroup::Custom(0)));
+
+        let rules = GroupBlendRules::none().deny(MaterialGroup::Custom(0), MaterialGroup::Natural);
+        assert!(rules.allows(MaterialGroup::Liquid, MaterialGroup::Artificial));
+        assert!(!rules.allows(MaterialGroup::Natural, MaterialGroup::Custom(0)));
+    }
+
+    #[test]
+    fn test_liquid_does_not_blend_into_brick() {
+        let mut palette = TexturePalette::default();
+        palette.materials = vec![
+            crate::palette::PaletteMaterial::new("water").with_group(MaterialGroup::Liquid),
+            crate::palette::PaletteMaterial::new("brick").with_group(MaterialGroup::Artificial),
+            crate::palette::PaletteMaterial::new("concrete").with_group(MaterialGroup::Artificial),
+        ];
+        let settings = MaterialBlendSettings::default().with_palette(&palette);
+
+        // Mostly brick, some concrete and water
+        let mut density = DensityField::new();
+        let mut materials = MaterialField::new();
+        for (i, offset) in CORNER_OFFSETS.into_iter().enumerate() {
+            let voxel = (ivec3(15, 8, 8) + offset).as_uvec3();
+            density.set(voxel.x, voxel.y, voxel.z, -1.0);
+            let material = match i {
+                0 | 1 => 0,
+                2 => 2,
+                _ => 1,
+            };
+            materials.set(voxel.x, voxel.y, voxel.z, material);
+        }
+
+        let data = VertexMaterialComputer::new(&density, &materials, Vec3::splat(32.0), &settings)
+            .compute(vec3(15.5, 8.5, 8.5));
+        assert_eq!(data.ids[0], 1);
+        assert_eq!(data.ids[1], 2);
+        assert_eq!(data.weights[2], 0);
+        // Brick and concrete split 5:1
+        assert!((data.weights[0] as f32 / 255.0 - 5.0 / 6.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_hard_edges_snap_to_dominant() {
+        let mut palette = TexturePalette::default();
+        palette.materials = vec![
+            crate::palette::PaletteMaterial::new("grass"),
+            crate::palette::PaletteMaterial::new("tiles").with_hard_edges(),
+        ];
+        let settings = MaterialBlendSettings::default().with_palette(&palette);
+
+        // Three corners of tiles, five of grass
+        let mut density = DensityField::new();
+        let mut materials = MaterialField::new();
+        for (i, offset) in CORNER_OFFSETS.into_iter().enumerate() {
+            let voxel = (ivec3(15, 8, 8) + offset).as_uvec3();
+            density.set(voxel.x, voxel.y, voxel.z, -1.0);
+            materials.set(voxel.x, voxel.y, voxel.z, (i < 3) as u8);
+        }
+
+        let data = VertexMaterialComputer::new(&density, &materials, Vec3::splat(32.0), &settings)
+            .compute(vec3(15.5, 8.5, 8.5));
+        assert_eq!(data, VertexMaterialData::single(0));
+
+        // Without hard edges the tiles blend in
+        let data = VertexMaterialComputer::new(
+            &density,
+            &materials,
+            Vec3::splat(32.0),
+            &MaterialBlendSettings::default(),
+        )
+        .compute(vec3(15.5, 8.5, 8.5));
+        assert_eq!(data.ids[1], 1);
+    }
+
+    #[test]
+    fn test_limit_materials() {
+        let mut contributions = vec![(1, 0.4), (2, 0.3), (3, 0.2), (4, 0.1)];
+        limit_materials(&mut contributions, 2);
+
+        assert_eq!(contributions.len(), 2);
+        assert!((contributions[0].1 - 4.0 / 7.0).abs() < 0.001);
+        assert!((contributions[1].1 - 3.0 / 7.0).abs() < 0.001);
+
+        let data = contributions_to_vertex_data(&contributions);
+        assert_eq!(data.weights[2], 0);
+        assert_eq!(data.weights[3], 0);
+    }
+
     #[test]
     fn test_contributions_to_vertex_data() {
         let data = contributions_to_vertex_data(&[(5, 1.0)]);
@@ -242,6 +941,215 @@ mod tests {
         assert_eq!(data.ids[1], 2);
     }
 
+    #[test]
+    fn test_computer_matches_adapter() {
+        let mut density_field = DensityField::new();
+        let mut material_field = MaterialField::new();
+        density_field.set(4, 4, 4, -0.5);
+        material_field.set(4, 4, 4, 3);
+        density_field.set(5, 4, 4, -0.25);
+        material_field.set(5, 4, 4, 7);
+
+        let settings = MaterialBlendSettings::default();
+        let mesh_size = DensityField::SIZE.as_vec3();
+        let computer =
+            VertexMaterialComputer::new(&density_field, &material_field, mesh_size, &settings);
+        let positions = [[4.5, 4.0, 4.0], [4.0, 4.0, 4.0], [20.0, 20.0, 20.0]];
+        let (ids, weights) = computer.compute_packed(&positions);
+
+        for (i, &pos) in positions.iter().enumerate() {
+            let expected = compute_vertex_materials(
+                Vec3::from_array(pos),
+                mesh_size,
+                &density_field,
+                &material_field,
+                None,
+                None,
+                &settings,
+            );
+            assert_eq!(ids[i], expected.pack_ids());
+            assert_eq!(weights[i], expected.pack_weights());
+        }
+        assert_eq!(ids[0] & 0xFFFF, 3 | (7 << 8));
+    }
+
+    #[test]
+    fn test_uniform_fast_path_matches_full_blend() {
+        let mut density_field = DensityField::new();
+        for (i, d) in density_field.data_mut().iter_mut().enumerate() {
+            *d = (i % 7) as f32 * 0.3 - 1.0;
+        }
+        let material_field = MaterialField::filled(6);
+        let settings = MaterialBlendSettings::default();
+        let mesh_size = DensityField::SIZE.as_vec3();
+        let fast =
+            VertexMaterialComputer::new(&density_field, &material_field, mesh_size, &settings);
+        assert_eq!(fast.uniform, Some(6));
+
+        let mut slow = fast;
+        slow.uniform = None;
+        // Last one reaches past the chunk edge and takes the full path
+        for pos in [
+            vec3(3.2, 9.7, 1.5),
+            vec3(0.0, 0.0, 0.0),
+            vec3(30.9, 30.5, 12.0),
+            vec3(31.5, 4.0, 4.0),
+        ] {
+            assert_eq!(fast.compute(pos), slow.compute(pos));
+        }
+    }
+
+    #[test]
+    fn test_custom_density_source() {
+        /// Solid below grid height 8.
+        struct Floor;
+
+        impl DensitySource for Floor {
+            fn size(&self) -> UVec3 {
+                UVec3::splat(32)
+            }
+
+            fn density(&self, pos: IVec3) -> Option<f32> {
+                let inside = pos.cmpge(IVec3::ZERO).all() && pos.cmplt(IVec3::splat(32)).all();
+                inside.then_some(pos.y as f32 - 7.5)
+            }
+        }
+
+        let mut material_field = MaterialField::filled(1);
+        material_field.paint_with(|pos| if pos.x < 16 { 1 } else { 2 });
+        let settings = MaterialBlendSettings::default();
+        let computer =
+            VertexMaterialComputer::new(&Floor, &material_field, Vec3::splat(32.0), &settings);
+
+        assert_eq!(computer.compute(vec3(4.0, 7.5, 4.0)).ids[0], 1);
+        assert_eq!(computer.compute(vec3(20.0, 7.5, 4.0)).ids[0], 2);
+    }
+
+    #[test]
+    fn test_dominant_takes_most_solid_voxel() {
+        // Ground below grid height 7.5, slightly more solid towards +x
+        let mut density_field = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    density_field.set(x, y, z, y as f32 - 7.5 - x as f32 * 0.01);
+                }
+            }
+        }
+        let mut material_field = MaterialField::filled(1);
+        material_field.paint_with(|pos| if pos.x < 16 { 1 } else { 2 });
+        let settings = MaterialBlendSettings::default();
+        let computer = VertexMaterialComputer::new(
+            &density_field,
+            &material_field,
+            DensityField::SIZE.as_vec3(),
+            &settings,
+        );
+
+        let pos = vec3(15.5, 7.5, 4.0);
+        assert_ne!(computer.compute(pos).weights[1], 0);
+        assert_eq!(
+            computer.compute_dominant(pos),
+            VertexMaterialData::single(2)
+        );
+        let (ids, weights) = computer.compute_packed_dominant(&[[4.0, 7.5, 4.0]]);
+        assert_eq!(ids[0], VertexMaterialData::single(1).pack_ids());
+        assert_eq!(weights[0], VertexMaterialData::single(1).pack_weights());
+    }
+
+    #[test]
+    fn test_dual_contouring_features_stay_crisp() {
+        const STONE: u8 = 1;
+        const GRASS: u8 = 2;
+        // Grass floor below y = 16 meeting a stone wall below x = 16
+        let mut density_field = DensityField::new();
+        let mut material_field = MaterialField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let solid = x < 16 || y < 16;
+                    density_field.set(x, y, z, if solid { -1.0 } else { 1.0 });
+                    material_field.set(x, y, z, if x < 16 { STONE } else { GRASS });
+                }
+            }
+        }
+
+        let settings = MaterialBlendSettings::default().with_sharp_feature_angle(45.0);
+        let mesh_size = DensityField::SIZE.as_vec3();
+        let computer =
+            VertexMaterialComputer::new(&density_field, &material_field, mesh_size, &settings);
+
+        // The crease vertex, split per face
+        let crease = vec3(15.8, 15.8, 8.5);
+        assert!(computer.is_sharp_feature(crease));
+        assert_eq!(computer.compute(crease).ids[..2], [STONE, GRASS]);
+        assert_eq!(
+            computer.compute_dual_contouring(crease, Vec3::Y, None),
+            VertexMaterialData::single(GRASS)
+        );
+        assert_eq!(
+            computer.compute_dual_contouring(crease, Vec3::X, None),
+            VertexMaterialData::single(STONE)
+        );
+
+        // Flat floor away from the wall blends as usual
+        let floor = vec3(20.5, 15.8, 8.5);
+        assert!(!computer.is_sharp_feature(floor));
+        assert_eq!(
+            computer.compute_dual_contouring(floor, Vec3::Y, None),
+            computer.compute(floor)
+        );
+        // An explicit flag overrides inference
+        assert_eq!(
+            computer.compute_dual_contouring(crease, Vec3::X, Some(false)),
+            computer.compute(crease)
+        );
+    }
+
+    #[test]
+    fn test_marching_cubes_vertex_uses_its_edge() {
+        // Solid voxel (4,4,4) of material 3 and its diagonal neighbor
+        // (4,5,5) of material 7, empty elsewhere
+        let mut density_field = DensityField::new();
+        density_field.data_mut().fill(1.0);
+        let mut material_field = MaterialField::new();
+        density_field.set(4, 4, 4, -1.0);
+        material_field.set(4, 4, 4, 3);
+        density_field.set(4, 5, 5, -1.0);
+        material_field.set(4, 5, 5, 7);
+
+        let settings = MaterialBlendSettings::default();
+        let mesh_size = DensityField::SIZE.as_vec3();
+        let computer =
+            VertexMaterialComputer::new(&density_field, &material_field, mesh_size, &settings);
+
+        // On the edge (4,4,4)-(5,4,4), exactly on the y = 4 / z = 4 faces:
+        // floor(pos) drags in (4,5,5) but the edge only touches (4,4,4)
+        let vertex = vec3(4.5, 4.0, 4.0);
+        assert_eq!(computer.compute(vertex).ids[..2], [3, 7]);
+        assert_eq!(
+            computer.compute_on_edge(vertex),
+            VertexMaterialData::single(3)
+        );
+
+        let mut mesh = Mesh::new(
+            bevy::mesh::PrimitiveTopology::TriangleList,
+            bevy::asset::RenderAssetUsages::all(),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![vertex.to_array()]);
+        add_material_attributes_marching_cubes(
+            &mut mesh,
+            &density_field,
+            &material_field,
+            mesh_size,
+        );
+        let Some(VertexAttributeValues::Uint32(ids)) = mesh.attribute(ATTRIBUTE_MATERIAL_IDS)
+        else {
+            panic!("missing material ids");
+        };
+        assert_eq!(ids[0], VertexMaterialData::single(3).pack_ids());
+    }
+
     #[test]
     fn test_sample_voxel_in_bounds() {
         let mut density_field = DensityField::new();
@@ -277,4 +1185,91 @@ mod tests {
 
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn test_sample_voxel_density_source_past_edge() {
+        /// Ground below y = 8 that extends past the chunk.
+        struct Ground;
+
+        impl DensitySource for Ground {
+            fn size(&self) -> UVec3 {
+                UVec3::splat(32)
+            }
+
+            fn density(&self, pos: IVec3) -> Option<f32> {
+                let inside = pos.cmpge(IVec3::ZERO).all() && pos.cmplt(IVec3::splat(32)).all();
+                inside.then(|| pos.y as f32 - 8.0)
+            }
+
+            fn neighbor_density(&self, pos: IVec3) -> Option<f32> {
+                Some(pos.y as f32 - 8.0)
+            }
+        }
+
+        let face = NeighborFace::ALL
+            .into_iter()
+            .find(|face| face.offset() == IVec3::NEG_X)
+            .unwrap();
+        let mut neighbor_materials = NeighborMaterialFields::default();
+        neighbor_materials.neighbors[face as usize] = Some(MaterialSlice::from_material_field(
+            &MaterialField::filled(3),
+            face,
+        ));
+
+        // Density comes from the source, material from the neighbor slice
+        let result = sample_voxel(
+            IVec3::new(-1, 5, 5),
+            &Ground,
+            &MaterialField::new(),
+            None,
+            Some(&neighbor_materials),
+        );
+        assert_eq!(result, Some((-3.0, 3)));
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn contributions() -> impl Strategy<Value = Vec<(u8, f32)>> {
+            prop::collection::vec((0u8..6, 0.011f32..1.0), 1..8)
+        }
+
+        proptest! {
+            #[test]
+            fn merge_keeps_every_material(input in contributions()) {
+                let mut merged = input.clone();
+                merge_and_normalize_materials(&mut merged);
+
+                let total: f32 = merged.iter().map(|(_, w)| w).sum();
+                prop_assert!((total - 1.0).abs() < 1e-4);
+                for (id, _) in &input {
+                    prop_assert_eq!(merged.iter().filter(|(m, _)| m == id).count(), 1);
+                }
+                // Descending weight, ties by ascending ID
+                for pair in merged.windows(2) {
+                    let (a, b) = (pair[0], pair[1]);
+                    prop_assert!(a.1 > b.1 || a.1 == b.1 && a.0 < b.0);
+                }
+            }
+
+            #[test]
+            fn vertex_data_sums_to_255(input in contributions(), max in 1usize..=4) {
+                let mut merged = input;
+                merge_and_normalize_materials(&mut merged);
+                limit_materials(&mut merged, max);
+                let data = contributions_to_vertex_data(&merged);
+
+                let sum: u32 = data.weights.iter().map(|&w| w as u32).sum();
+                prop_assert_eq!(sum, 255);
+                prop_assert!(merged.len() <= max);
+                for (slot, &(id, weight)) in merged.iter().enumerate() {
+                    prop_assert_eq!(data.ids[slot], id);
+                    if weight * 255.0 >= 1.0 {
+                        prop_assert!(data.weights[slot] > 0);
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,423 @@
+//! One-call area effects for gameplay code.
+//!
+//! Explosions and spells usually want several strokes at once: repaint the
+//! core, char the surroundings, dry out the ground. [`AreaEffects`] writes
+//! the matching [`PaintCommand`] and [`ParamPaintCommand`]s from a world
+//! position and radius, or a path for roads, so callers never deal with
+//! grid coordinates or neighbor margins; the plugin applies them and marks
+//! chunks dirty.
+
+use std::sync::Arc;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use super::{
+    BrushFilter, BrushShape, BrushVoxel, MaterialMask, NearSurface, PaintCommand,
+    ParamPaintCommand, segment_distance_squared, voxel_noise,
+};
+use crate::mesh::MaterialParam;
+
+/// How [`AreaEffects::scorch_area`] marks the ground.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScorchConfig {
+    /// Material painted over the core of the area, e.g. ash; `None` keeps
+    /// the existing materials.
+    /// Default: None
+    pub material_id: Option<u8>,
+    /// Fraction of the radius repainted with `material_id`.
+    /// Default: 0.5
+    pub core_fraction: f32,
+    /// Burn added at the center, fading to nothing at the edge.
+    /// Default: 1.0
+    pub burn: f32,
+    /// Wetness removed at the center, fading to nothing at the edge.
+    /// Default: 1.0
+    pub dry: f32,
+    /// Materials the effect may touch; `None` affects any.
+    /// Default: None
+    pub mask: Option<MaterialMask>,
+    /// Only affect voxels within this distance of the surface, so buried
+    /// voxels don't show scorch marks after digging. Needs a
+    /// [`DensityField`](bevy_sculpter::prelude::DensityField) on the chunk;
+    /// chunks without one are skipped.
+    /// Default: Some(1.5)
+    pub surface_depth: Option<f32>,
+}
+
+impl Default for ScorchConfig {
+    fn default() -> Self {
+        Self {
+            material_id: None,
+            core_fraction: 0.5,
+            burn: 1.0,
+            dry: 1.0,
+            mask: None,
+            surface_depth: Some(1.5),
+        }
+    }
+}
+
+impl ScorchConfig {
+    /// Repaint the core of the area with `material_id`.
+    pub fn with_material(mut self, material_id: u8) -> Self {
+        self.material_id = Some(material_id);
+        self
+    }
+
+    /// Set the fraction of the radius repainted.
+    pub fn with_core_fraction(mut self, fraction: f32) -> Self {
+        self.core_fraction = fraction;
+        self
+    }
+
+    /// Set the burn added at the center.
+    pub fn with_burn(mut self, burn: f32) -> Self {
+        self.burn = burn;
+        self
+    }
+
+    /// Set the wetness removed at the center.
+    pub fn with_dry(mut self, dry: f32) -> Self {
+        self.dry = dry;
+        self
+    }
+
+    /// Only affect materials allowed by `mask`.
+    pub fn with_mask(mut self, mask: MaterialMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Set the surface depth limit; `None` affects buried voxels too.
+    pub fn with_surface_depth(mut self, depth: Option<f32>) -> Self {
+        self.surface_depth = depth;
+        self
+    }
+}
+
+/// How [`AreaEffects::paint_spline`] lays a path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SplineConfig {
+    /// Extra distance beyond the edge over which the path frays out, with
+    /// voxels dithered in less often the further out they are.
+    /// Default: 0.0
+    pub falloff: f32,
+    /// Material banding both sides of the path, e.g. gravel along a road;
+    /// `None` paints no band.
+    /// Default: None
+    pub edge_material: Option<u8>,
+    /// Width of each edge band in world units.
+    /// Default: 1.0
+    pub edge_width: f32,
+    /// Materials the path may replace; `None` replaces any.
+    /// Default: None
+    pub mask: Option<MaterialMask>,
+    /// Only paint voxels within this distance of the surface. Needs a
+    /// [`DensityField`](bevy_sculpter::prelude::DensityField) on the chunk;
+    /// chunks without one are skipped.
+    /// Default: Some(1.5)
+    pub surface_depth: Option<f32>,
+}
+
+impl Default for SplineConfig {
+    fn default() -> Self {
+        Self {
+            falloff: 0.0,
+            edge_material: None,
+            edge_width: 1.0,
+            mask: None,
+            surface_depth: Some(1.5),
+        }
+    }
+}
+
+impl SplineConfig {
+    /// Set the distance over which the path frays out.
+    pub fn with_falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Band both sides of the path with `material_id`, `width` wide.
+    pub fn with_edge(mut self, material_id: u8, width: f32) -> Self {
+        self.edge_material = Some(material_id);
+        self.edge_width = width;
+        self
+    }
+
+    /// Only replace materials allowed by `mask`.
+    pub fn with_mask(mut self, mask: MaterialMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Set the surface depth limit; `None` paints buried voxels too.
+    pub fn with_surface_depth(mut self, depth: Option<f32>) -> Self {
+        self.surface_depth = depth;
+        self
+    }
+}
+
+/// Writes world-space area effects as paint commands.
+///
+/// # Example
+/// ```ignore
+/// fn explode(mut blasts: MessageReader<Explosion>, mut effects: AreaEffects) {
+///     for blast in blasts.read() {
+///         effects.scorch_area(blast.position, blast.radius, ScorchConfig::default().with_material(ASH));
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct AreaEffects<'w> {
+    paint: MessageWriter<'w, PaintCommand>,
+    params: MessageWriter<'w, ParamPaintCommand>,
+}
+
+impl AreaEffects<'_> {
+    /// Scorch a sphere of `radius` around `world_pos`: repaint its core,
+    /// add burn and dry out the ground.
+    pub fn scorch_area(&mut self, world_pos: Vec3, radius: f32, config: ScorchConfig) {
+        let sphere = |radius| BrushShape::Sphere {
+            center: world_pos,
+            radius,
+        };
+
+        if let Some(material_id) = config.material_id {
+            let radius = radius * config.core_fraction.clamp(0.0, 1.0);
+            let mut command = PaintCommand::new(sphere(radius), material_id);
+            if let Some(mask) = config.mask {
+                command = command.with_mask(mask);
+            }
+            if let Some(max_distance) = config.surface_depth {
+                command = command.with_filter(NearSurface { max_distance });
+            }
+            self.paint.write(command);
+        }
+
+        for (param, amount) in [
+            (MaterialParam::Burn, config.burn),
+            (MaterialParam::Wetness, -config.dry),
+        ] {
+            if amount != 0.0 {
+                self.add_param(sphere(radius), param, amount, &config);
+            }
+        }
+    }
+
+    /// Repaint a sphere of `radius` around `world_pos` with `material_id`.
+    pub fn repaint_area(&mut self, world_pos: Vec3, radius: f32, material_id: u8) {
+        self.paint.write(PaintCommand::new(
+            BrushShape::Sphere {
+                center: world_pos,
+                radius,
+            },
+            material_id,
+        ));
+    }
+
+    /// Paint a path `width` wide along the polyline `spline`, e.g. a road
+    /// across many chunks.
+    ///
+    /// Points are world positions on the ground; sample curves such as a
+    /// [`CubicCurve`](bevy::math::cubic_splines::CubicCurve) into points
+    /// first, a few per voxel of bend. The path covers everything within
+    /// half the width of the polyline in 3D, so
+    /// [`SplineConfig::surface_depth`] keeps it to the terrain's skin. Edge
+    /// bands are painted first and the path over them.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let points: Vec<Vec3> = curve.iter_positions(64).collect();
+    /// effects.paint_spline(&points, 4.0, ROAD, SplineConfig::default().with_falloff(1.0).with_edge(GRAVEL, 1.5));
+    /// ```
+    pub fn paint_spline(
+        &mut self,
+        spline: &[Vec3],
+        width: f32,
+        material_id: u8,
+        config: SplineConfig,
+    ) {
+        if spline.is_empty() {
+            return;
+        }
+        let points: Arc<[Vec3]> = spline.into();
+        let half_width = width.max(0.0) * 0.5;
+        if let Some(edge_material) = config.edge_material {
+            let radius = half_width + config.edge_width.max(0.0);
+            self.paint_path(&points, radius, edge_material, &config);
+        }
+        self.paint_path(&points, half_width, material_id, &config);
+    }
+
+    /// Writes one capsule command per segment of `points`, painting within
+    /// `radius` of the whole polyline plus the dithered falloff.
+    fn paint_path(
+        &mut self,
+        points: &Arc<[Vec3]>,
+        radius: f32,
+        material_id: u8,
+        config: &SplineConfig,
+    ) {
+        let falloff = config.falloff.max(0.0);
+        let mask = config.mask;
+        let near_surface = config
+            .surface_depth
+            .map(|max_distance| NearSurface { max_distance });
+        let path = points.clone();
+        let filter = move |voxel: &BrushVoxel| {
+            if mask.is_some_and(|mask| !mask.allows(voxel.material_id))
+                || near_surface.is_some_and(|near| !near.allows(voxel))
+            {
+                return false;
+            }
+            let distance = polyline_distance(&path, voxel.world_pos);
+            let fray = voxel_noise(voxel.world_pos.round().as_ivec3().as_uvec3());
+            distance <= radius + falloff * (1.0 - fray)
+        };
+        let filter: Arc<dyn BrushFilter> = Arc::new(filter);
+
+        // A single point paints a sphere
+        let segments = points.windows(2).map(|pair| (pair[0], pair[1]));
+        let segments = segments.chain((points.len() == 1).then_some((points[0], points[0])));
+        for (start, end) in segments {
+            let mut command = PaintCommand::new(
+                BrushShape::Capsule {
+                    start,
+                    end,
+                    radius: radius + falloff,
+                },
+                material_id,
+            );
+            command.filter = Some(filter.clone());
+            self.paint.write(command);
+        }
+    }
+
+    fn add_param(
+        &mut self,
+        shape: BrushShape,
+        param: MaterialParam,
+        amount: f32,
+        config: &ScorchConfig,
+    ) {
+        let mut command = ParamPaintCommand::new(shape, param, amount);
+        if let Some(mask) = config.mask {
+            command = command.with_mask(mask);
+        }
+        if let Some(max_distance) = config.surface_depth {
+            command = command.with_filter(NearSurface { max_distance });
+        }
+        self.params.write(command);
+    }
+}
+
+/// Distance from `point` to the nearest segment of `points`.
+fn polyline_distance(points: &[Vec3], point: Vec3) -> f32 {
+    let nearest = match points {
+        [single] => point.distance_squared(*single),
+        _ => points
+            .windows(2)
+            .map(|pair| segment_distance_squared(point, pair[0], pair[1]))
+            .fold(f32::INFINITY, f32::min),
+    };
+    nearest.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy_sculpter::field::Field;
+
+    use super::*;
+    use crate::material_field::{
+        MaterialChanged, MaterialField, MaterialFieldDirty, MaterialParamsField,
+        apply_paint_commands, apply_param_paint_commands,
+    };
+
+    #[test]
+    fn test_scorch_area() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<PaintCommand>()
+            .add_message::<ParamPaintCommand>()
+            .add_message::<MaterialChanged>()
+            .add_systems(Update, (apply_paint_commands, apply_param_paint_commands));
+
+        let mut params = MaterialParamsField::new();
+        params.set_param(uvec3(16, 16, 16), MaterialParam::Wetness, 1.0);
+        let chunk = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(1), params))
+            .id();
+
+        app.world_mut()
+            .run_system_once(|mut effects: AreaEffects| {
+                effects.scorch_area(
+                    vec3(16.0, 16.0, 16.0),
+                    8.0,
+                    ScorchConfig::default()
+                        .with_material(5)
+                        .with_surface_depth(None),
+                );
+            })
+            .unwrap();
+        app.update();
+
+        let entity = app.world().entity(chunk);
+        let field = entity.get::<MaterialField>().unwrap();
+        assert_eq!(field.get(16, 16, 16), 5);
+        assert_eq!(field.get(16, 16, 22), 1);
+
+        let params = entity.get::<MaterialParamsField>().unwrap();
+        assert_eq!(params.param(uvec3(16, 16, 16), MaterialParam::Burn), 1.0);
+        assert_eq!(params.param(uvec3(16, 16, 16), MaterialParam::Wetness), 0.0);
+        assert!(params.param(uvec3(16, 16, 22), MaterialParam::Burn) > 0.0);
+        assert_eq!(params.param(uvec3(16, 16, 26), MaterialParam::Burn), 0.0);
+        assert!(entity.contains::<MaterialFieldDirty>());
+    }
+
+    #[test]
+    fn test_spline_paints_path_and_edges_across_chunks() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<PaintCommand>()
+            .add_message::<ParamPaintCommand>()
+            .add_message::<MaterialChanged>()
+            .add_systems(Update, apply_paint_commands);
+
+        let chunks = [0.0, 32.0].map(|x| {
+            app.world_mut()
+                .spawn((
+                    GlobalTransform::from_xyz(x, 0.0, 0.0),
+                    MaterialField::filled(1),
+                ))
+                .id()
+        });
+
+        app.world_mut()
+            .run_system_once(|mut effects: AreaEffects| {
+                let spline = [
+                    vec3(4.0, 16.0, 16.0),
+                    vec3(30.0, 16.0, 16.0),
+                    vec3(48.0, 16.0, 6.0),
+                ];
+                let config = SplineConfig::default()
+                    .with_edge(6, 2.0)
+                    .with_surface_depth(None);
+                effects.paint_spline(&spline, 4.0, 5, config);
+            })
+            .unwrap();
+        app.update();
+
+        let field = |index: usize| app.world().get::<MaterialField>(chunks[index]).unwrap();
+        // Path, edge band and untouched ground across the first segment
+        assert_eq!(field(0).get(10, 16, 16), 5);
+        assert_eq!(field(0).get(10, 16, 19), 6);
+        assert_eq!(field(0).get(10, 16, 21), 1);
+        // The path continues into the next chunk
+        assert_eq!(field(1).get(7, 16, 11), 5);
+        assert_eq!(field(1).get(20, 16, 8), 1);
+    }
+}
@@ -0,0 +1,101 @@
+//! Per-voxel coverage for soft, airbrush-style painting.
+
+use bevy::prelude::*;
+
+use crate::material_field::{FIELD_SIZE, FIELD_VOLUME};
+
+/// Coverage of a target material that repeated strokes build up.
+///
+/// Build-up strokes ([`BrushShape::build_up`](super::BrushShape::build_up),
+/// or [`PaintCommand::with_build_up`](super::PaintCommand::with_build_up))
+/// add coverage each pass instead of replacing voxels outright, strongest at
+/// the brush center. Coverage is written as the target material's share of
+/// the voxel through [`MaterialStorage::set_mix`], so storages holding two
+/// materials per voxel like [`MaterialFieldDual`] blend it in gradually,
+/// while single-material storages switch once the target covers more than
+/// half the voxel. Building up a different material on a voxel starts over
+/// from zero.
+///
+/// [`MaterialStorage::set_mix`]: crate::material_field::MaterialStorage::set_mix
+/// [`MaterialFieldDual`]: crate::material_field::MaterialFieldDual
+#[derive(Component, Clone, Debug)]
+pub struct PaintBuildUp {
+    /// Material each voxel is building towards.
+    target: Vec<u8>,
+    /// Material each voxel held before building started.
+    base: Vec<u8>,
+    coverage: Vec<u16>,
+}
+
+impl Default for PaintBuildUp {
+    fn default() -> Self {
+        Self {
+            target: vec![0; FIELD_VOLUME],
+            base: vec![0; FIELD_VOLUME],
+            coverage: vec![0; FIELD_VOLUME],
+        }
+    }
+}
+
+impl PaintBuildUp {
+    /// Creates an empty build-up with no coverage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coverage (0.0 to 1.0) of `material_id` at a voxel.
+    pub fn coverage(&self, pos: UVec3, material_id: u8) -> f32 {
+        let Some(index) = Self::index(pos) else {
+            return 0.0;
+        };
+        if self.target[index] != material_id {
+            return 0.0;
+        }
+        self.coverage[index] as f32 / u16::MAX as f32
+    }
+
+    /// Drops all coverage, e.g. when a stroke ends.
+    pub fn clear(&mut self) {
+        self.target.fill(0);
+        self.base.fill(0);
+        self.coverage.fill(0);
+    }
+
+    /// Material a voxel is building up `material_id` over, given its
+    /// `current` primary material and the `share` of `material_id` already
+    /// mixed into it.
+    ///
+    /// Keeps the voxel's progress while it still holds the base or target
+    /// material, and starts over from `current` otherwise.
+    pub(super) fn base(&mut self, pos: UVec3, material_id: u8, current: u8, share: f32) -> u8 {
+        let Some(index) = Self::index(pos) else {
+            return current;
+        };
+        let building = self.target[index] == material_id
+            && self.base[index] != material_id
+            && (current == self.base[index] || current == material_id);
+        if !building {
+            self.target[index] = material_id;
+            self.base[index] = current;
+            self.coverage[index] = (share.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+        }
+        self.base[index]
+    }
+
+    /// Adds `amount` of the target material at a voxel, returning its new
+    /// coverage (0.0 to 1.0).
+    pub(super) fn add(&mut self, pos: UVec3, amount: f32) -> f32 {
+        let Some(index) = Self::index(pos) else {
+            return 0.0;
+        };
+        let amount = (amount.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+        self.coverage[index] = self.coverage[index].saturating_add(amount);
+        self.coverage[index] as f32 / u16::MAX as f32
+    }
+
+    fn index(pos: UVec3) -> Option<usize> {
+        pos.cmplt(FIELD_SIZE)
+            .all()
+            .then(|| (pos.x + pos.y * FIELD_SIZE.x + pos.z * FIELD_SIZE.x * FIELD_SIZE.y) as usize)
+    }
+}
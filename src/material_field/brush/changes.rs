@@ -0,0 +1,131 @@
+//! Per-voxel change reports for gameplay reactions to painting.
+
+use bevy::prelude::*;
+
+use crate::material_field::MaterialStorage;
+
+/// A voxel whose material changed during a paint stroke.
+///
+/// [`apply_paint_commands`](super::apply_paint_commands) writes one per
+/// changed voxel, all changes of a command in a single batch, so gameplay
+/// can react to painting without diffing fields.
+///
+/// # Example
+/// ```ignore
+/// fn dirt_particles(mut changed: MessageReader<MaterialChanged>, mut commands: Commands) {
+///     for change in changed.read() {
+///         if change.old == GRASS && change.new != GRASS {
+///             commands.trigger(SpawnDirt { chunk: change.chunk, voxel: change.voxel });
+///         }
+///     }
+/// }
+/// ```
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaterialChanged {
+    /// The painted chunk, or [`Entity::PLACEHOLDER`] for changes recorded
+    /// outside the ECS.
+    pub chunk: Entity,
+    /// Grid coordinates of the voxel in the chunk.
+    pub voxel: UVec3,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// Wraps a [`MaterialStorage`], recording every material change made
+/// through it.
+///
+/// Lets the free brush functions report what they painted.
+///
+/// # Example
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::{MaterialField, RecordChanges, brush};
+///
+/// let mut field = MaterialField::filled(1);
+/// let mut recorder = RecordChanges::new(&mut field);
+/// brush::paint_sphere(&mut recorder, vec3(16.0, 16.0, 16.0), 1.0, 2, None);
+///
+/// let changes = recorder.into_changes(Entity::PLACEHOLDER);
+/// assert_eq!(changes.len(), 7);
+/// assert!(changes.iter().all(|change| change.old == 1 && change.new == 2));
+/// ```
+pub struct RecordChanges<'a, S: MaterialStorage + ?Sized> {
+    storage: &'a mut S,
+    changes: Vec<(UVec3, u8, u8)>,
+}
+
+impl<'a, S: MaterialStorage + ?Sized> RecordChanges<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        Self {
+            storage,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Number of changes recorded so far.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Whether nothing changed yet.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The recorded changes, attributed to `chunk`, in the order they were
+    /// made.
+    pub fn into_changes(self, chunk: Entity) -> Vec<MaterialChanged> {
+        self.changes
+            .into_iter()
+            .map(|(voxel, old, new)| MaterialChanged {
+                chunk,
+                voxel,
+                old,
+                new,
+            })
+            .collect()
+    }
+}
+
+impl<S: MaterialStorage + ?Sized> MaterialStorage for RecordChanges<'_, S> {
+    fn size(&self) -> UVec3 {
+        self.storage.size()
+    }
+
+    #[inline]
+    fn get(&self, pos: UVec3) -> u8 {
+        self.storage.get(pos)
+    }
+
+    fn set(&mut self, pos: UVec3, material_id: u8) {
+        if pos.cmpge(self.storage.size()).any() {
+            return;
+        }
+        let old = self.storage.get(pos);
+        if old == material_id {
+            return;
+        }
+        self.storage.set(pos, material_id);
+        self.changes.push((pos, old, material_id));
+    }
+
+    fn secondary(&self, pos: UVec3) -> Option<(u8, f32)> {
+        self.storage.secondary(pos)
+    }
+
+    fn set_mix(&mut self, pos: UVec3, material_a: u8, material_b: u8, share: f32) {
+        if pos.cmpge(self.storage.size()).any() {
+            return;
+        }
+        let old = self.storage.get(pos);
+        self.storage.set_mix(pos, material_a, material_b, share);
+        let new = self.storage.get(pos);
+        if old != new {
+            self.changes.push((pos, old, new));
+        }
+    }
+
+    fn uniform_region(&self, min: UVec3, max: UVec3) -> Option<u8> {
+        self.storage.uniform_region(min, max)
+    }
+}
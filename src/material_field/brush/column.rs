@@ -0,0 +1,170 @@
+//! Column brushes for top-down and 2.5D games.
+//!
+//! When the terrain is only ever painted from above, a cursor position on
+//! the ground plane picks a column directly, so these brushes work on grid
+//! XZ columns instead of 3D shapes and skip raycasting altogether. Use
+//! [`coords`](crate::material_field::coords) to find the chunk and grid
+//! column under a world XZ position.
+
+use bevy::prelude::*;
+
+use crate::material_field::density::DensitySource;
+use crate::material_field::storage::MaterialStorage;
+
+/// Paints voxels `from_y..=to_y` of the column at grid `x`, `z`.
+///
+/// The range is clamped to the field, and may be given in either order.
+/// Returns whether any voxel changed.
+///
+/// # Example
+/// ```
+/// use bevy_painter::material_field::{MaterialField, brush};
+///
+/// let mut field = MaterialField::new();
+/// assert!(brush::paint_column(&mut field, 4, 7, 10, 12, 3));
+/// assert_eq!(field.get(4, 11, 7), 3);
+/// assert_eq!(field.get(4, 13, 7), 0);
+/// ```
+pub fn paint_column(
+    field: &mut (impl MaterialStorage + ?Sized),
+    x: u32,
+    z: u32,
+    from_y: u32,
+    to_y: u32,
+    material_id: u8,
+) -> bool {
+    let size = field.size();
+    if x >= size.x || z >= size.z || size.y == 0 {
+        return false;
+    }
+    let (low, high) = (from_y.min(to_y), from_y.max(to_y).min(size.y - 1));
+    let mut changed = false;
+    for y in low..=high {
+        let pos = uvec3(x, y, z);
+        if field.get(pos) != material_id {
+            field.set(pos, material_id);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Height of the topmost solid voxel of the column at grid `x`, `z` that
+/// has open space above it within the chunk.
+///
+/// `None` for columns that are empty, or solid up to the chunk's top, in
+/// which case the surface belongs to the chunk above.
+pub fn surface_height(density: &(impl DensitySource + ?Sized), x: u32, z: u32) -> Option<u32> {
+    let size = density.size();
+    let solid = |y: u32| {
+        density
+            .density(ivec3(x as i32, y as i32, z as i32))
+            .is_some_and(|value| value < 0.0)
+    };
+    (0..size.y.saturating_sub(1))
+        .rev()
+        .find(|&y| solid(y) && !solid(y + 1))
+}
+
+/// Paints the top `depth` voxels of every column within `radius` of the
+/// grid XZ position `center`, as seen from above.
+///
+/// Each column is painted down from its [`surface_height`], so overhangs
+/// and caves below the visible surface are left alone. Columns whose
+/// surface isn't in this chunk are skipped. Returns whether any voxel
+/// changed.
+///
+/// # Example
+/// ```ignore
+/// // Cursor on the ground plane, in chunk grid coordinates
+/// brush::paint_disc_topdown(&mut field, &density, cursor.xz(), 4.0, 2, MUD);
+/// ```
+pub fn paint_disc_topdown(
+    field: &mut (impl MaterialStorage + ?Sized),
+    density: &(impl DensitySource + ?Sized),
+    center: Vec2,
+    radius: f32,
+    depth: u32,
+    material_id: u8,
+) -> bool {
+    if depth == 0 {
+        return false;
+    }
+    let size = field.size().min(density.size());
+    let min = (center - radius).floor().max(Vec2::ZERO).as_uvec2();
+    let max = (center + radius)
+        .ceil()
+        .min(size.xz().as_vec2() - 1.0)
+        .as_ivec2();
+    if max.cmplt(IVec2::ZERO).any() {
+        return false;
+    }
+
+    let mut changed = false;
+    for z in min.y..=max.y as u32 {
+        for x in min.x..=max.x as u32 {
+            if vec2(x as f32, z as f32).distance_squared(center) > radius * radius {
+                continue;
+            }
+            let Some(top) = surface_height(density, x, z) else {
+                continue;
+            };
+            let bottom = (top + 1).saturating_sub(depth);
+            changed |= paint_column(field, x, z, bottom, top, material_id);
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_sculpter::field::Field;
+    use bevy_sculpter::prelude::DensityField;
+
+    use super::*;
+    use crate::material_field::MaterialField;
+
+    #[test]
+    fn test_disc_paints_only_the_visible_surface() {
+        // Ground up to height 10, and a floating ledge at 20..=21 over x < 8
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let ledge = x < 8 && (20..=21).contains(&y);
+                    let value = if ledge { -1.0 } else { y as f32 - 10.5 };
+                    density.set(x, y, z, value);
+                }
+            }
+        }
+        assert_eq!(surface_height(&density, 16, 16), Some(10));
+        assert_eq!(surface_height(&density, 4, 16), Some(21));
+
+        let mut field = MaterialField::new();
+        assert!(paint_disc_topdown(
+            &mut field,
+            &density,
+            vec2(8.0, 16.0),
+            3.0,
+            2,
+            5
+        ));
+        assert_eq!(field.get(10, 10, 16), 5);
+        assert_eq!(field.get(10, 9, 16), 5);
+        assert_eq!(field.get(10, 8, 16), 0);
+        // The ledge hides the ground below it
+        assert_eq!(field.get(6, 21, 16), 5);
+        assert_eq!(field.get(6, 10, 16), 0);
+        // Outside the disc
+        assert_eq!(field.get(12, 10, 16), 0);
+
+        assert!(!paint_disc_topdown(
+            &mut field,
+            &density,
+            vec2(-10.0, 16.0),
+            3.0,
+            2,
+            5
+        ));
+    }
+}
@@ -0,0 +1,287 @@
+//! Per-voxel predicates that restrict where a brush paints.
+
+use super::MaterialMask;
+use crate::material_field::DensitySource;
+use bevy::prelude::*;
+
+/// A voxel a brush is about to paint.
+#[derive(Clone, Copy)]
+pub struct BrushVoxel<'a> {
+    /// Grid coordinates within the chunk.
+    pub grid_pos: UVec3,
+    /// World-space position of the voxel.
+    pub world_pos: Vec3,
+    /// Material currently stored in the voxel.
+    pub material_id: u8,
+    pub(super) density: Option<&'a dyn DensitySource>,
+    pub(super) grid_to_world: Mat3,
+}
+
+impl BrushVoxel<'_> {
+    /// Density at the voxel, if the chunk has a [`DensitySource`].
+    ///
+    /// Negative values are solid.
+    pub fn density(&self) -> Option<f32> {
+        self.density?.density(self.grid_pos.as_ivec3())
+    }
+
+    /// World-space outward surface normal from the density gradient.
+    ///
+    /// `None` without a [`DensitySource`] or where the density is flat, e.g.
+    /// deep inside solid ground.
+    pub fn surface_normal(&self) -> Option<Vec3> {
+        let density = self.density?;
+        let p = self.grid_pos.as_ivec3();
+        // Central differences, one-sided at the field border
+        let sample = |offset: IVec3| {
+            let q = (p + offset).clamp(IVec3::ZERO, density.size().as_ivec3() - IVec3::ONE);
+            density.density(q).unwrap_or_default()
+        };
+        let gradient = vec3(
+            sample(IVec3::X) - sample(IVec3::NEG_X),
+            sample(IVec3::Y) - sample(IVec3::NEG_Y),
+            sample(IVec3::Z) - sample(IVec3::NEG_Z),
+        );
+        // Normals transform with the inverse transpose
+        (self.grid_to_world.inverse().transpose() * gradient).try_normalize()
+    }
+}
+
+/// A predicate deciding whether a brush may paint a voxel.
+///
+/// Filters compose with [`and`](BrushFilter::and), [`or`](BrushFilter::or)
+/// and [`not`](BrushFilter::not). Closures taking a [`BrushVoxel`] are
+/// filters too.
+///
+/// # Example
+/// ```ignore
+/// // Snow on flat ground above the tree line, never under overhangs
+/// let snow = MaxSlope::degrees(35.0).and(HeightRange::above(120.0));
+/// ```
+pub trait BrushFilter: Send + Sync {
+    /// Whether the brush may paint `voxel`.
+    fn allows(&self, voxel: &BrushVoxel) -> bool;
+
+    /// Passes voxels both filters allow.
+    fn and<F: BrushFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Passes voxels either filter allows.
+    fn or<F: BrushFilter>(self, other: F) -> Or<Self, F>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Passes voxels this filter rejects.
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+impl<F> BrushFilter for F
+where
+    F: Fn(&BrushVoxel) -> bool + Send + Sync,
+{
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        self(voxel)
+    }
+}
+
+impl BrushFilter for MaterialMask {
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        MaterialMask::allows(self, voxel.material_id)
+    }
+}
+
+/// Filter passing voxels both `A` and `B` allow.
+#[derive(Clone, Copy, Debug)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: BrushFilter, B: BrushFilter> BrushFilter for And<A, B> {
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        self.0.allows(voxel) && self.1.allows(voxel)
+    }
+}
+
+/// Filter passing voxels either `A` or `B` allows.
+#[derive(Clone, Copy, Debug)]
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A: BrushFilter, B: BrushFilter> BrushFilter for Or<A, B> {
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        self.0.allows(voxel) || self.1.allows(voxel)
+    }
+}
+
+/// Filter passing voxels `F` rejects.
+#[derive(Clone, Copy, Debug)]
+pub struct Not<F>(pub F);
+
+impl<F: BrushFilter> BrushFilter for Not<F> {
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        !self.0.allows(voxel)
+    }
+}
+
+/// Only paint surfaces flatter than a maximum slope.
+///
+/// Overhangs and ceilings always fail, so a snow brush using this never
+/// paints underneath them. Needs a [`DensityField`] on the chunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MaxSlope {
+    /// Minimum Y component of the world-space surface normal.
+    pub min_normal_y: f32,
+}
+
+impl MaxSlope {
+    /// Slope limit in degrees from horizontal.
+    pub fn degrees(degrees: f32) -> Self {
+        Self {
+            min_normal_y: degrees.to_radians().cos(),
+        }
+    }
+}
+
+impl BrushFilter for MaxSlope {
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        voxel
+            .surface_normal()
+            .is_some_and(|normal| normal.y >= self.min_normal_y)
+    }
+}
+
+/// Only paint voxels within a world-space height range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeightRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl HeightRange {
+    /// Voxels at or above world height `y`.
+    pub fn above(y: f32) -> Self {
+        Self {
+            min: y,
+            max: f32::INFINITY,
+        }
+    }
+
+    /// Voxels at or below world height `y`.
+    pub fn below(y: f32) -> Self {
+        Self {
+            min: f32::NEG_INFINITY,
+            max: y,
+        }
+    }
+}
+
+impl BrushFilter for HeightRange {
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        (self.min..=self.max).contains(&voxel.world_pos.y)
+    }
+}
+
+/// Only paint voxels close to the isosurface.
+///
+/// Keeps strokes from recoloring voxels buried deep inside terrain, which
+/// would show up later when sculpting exposes them. Needs a
+/// [`DensityField`] on the chunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NearSurface {
+    /// Maximum absolute density, in the field's distance units.
+    pub max_distance: f32,
+}
+
+impl BrushFilter for NearSurface {
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        voxel
+            .density()
+            .is_some_and(|density| density.abs() <= self.max_distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Affine3A;
+    use bevy_sculpter::field::Field;
+    use bevy_sculpter::prelude::DensityField;
+
+    use super::super::{BrushShape, BrushSpace, MaterialField};
+    use super::*;
+
+    /// Ground below grid height 16, with a solid ceiling above height 24.
+    fn ground_and_ceiling() -> DensityField {
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let floor = y as f32 - 16.0;
+                    let ceiling = 24.0 - y as f32;
+                    density.set(x, y, z, floor.min(ceiling));
+                }
+            }
+        }
+        density
+    }
+
+    fn paint(density: &DensityField, filter: &dyn BrushFilter) -> MaterialField {
+        let mut field = MaterialField::new();
+        let space = BrushSpace {
+            density: Some(density),
+            grid_to_world: Affine3A::from_translation(vec3(0.0, 100.0, 0.0)),
+        };
+        BrushShape::Box {
+            min: Vec3::ZERO,
+            max: Vec3::splat(31.0),
+        }
+        .paint(&mut field, &space, 1, filter);
+        field
+    }
+
+    #[test]
+    fn test_slope_rejects_overhangs() {
+        let density = ground_and_ceiling();
+        let field = paint(
+            &density,
+            &MaxSlope::degrees(30.0).and(NearSurface { max_distance: 1.0 }),
+        );
+
+        // Top of the ground is painted, the underside of the ceiling isn't
+        assert_eq!(field.get(8, 16, 8), 1);
+        assert_eq!(field.get(8, 24, 8), 0);
+        // Deep inside the ground is too far from the surface
+        assert_eq!(field.get(8, 4, 8), 0);
+    }
+
+    #[test]
+    fn test_height_range_uses_world_space() {
+        let density = ground_and_ceiling();
+        let field = paint(
+            &density,
+            &HeightRange::above(110.0).or(HeightRange::below(101.0)),
+        );
+
+        assert_eq!(field.get(0, 1, 0), 1);
+        assert_eq!(field.get(0, 5, 0), 0);
+        assert_eq!(field.get(0, 10, 0), 1);
+    }
+
+    #[test]
+    fn test_not_and_closures() {
+        let density = ground_and_ceiling();
+        let filter = (|voxel: &BrushVoxel| voxel.grid_pos.x < 4).not();
+        let field = paint(&density, &filter);
+
+        assert_eq!(field.get(3, 0, 0), 0);
+        assert_eq!(field.get(4, 0, 0), 1);
+    }
+}
@@ -0,0 +1,988 @@
+//! Brushes for painting materials into a [`MaterialField`].
+//!
+//! The free functions work in grid coordinates on a single field, or any
+//! other [`MaterialStorage`](super::MaterialStorage) backend;
+//! [`paint_column`] and [`paint_disc_topdown`] paint by XZ column for
+//! top-down games, and [`paint_shorelines`] bands shores around water. A
+//! [`PaintCommand`] describes a stroke in world space; the plugin applies it
+//! to every chunk it touches and marks them [`MaterialFieldDirty`], once per
+//! stroke inside a [`PaintStroke`].
+//! [`BrushFilter`]s restrict strokes further, e.g. to flat ground.
+//! [`ParamPaintCommand`]s paint wetness, burn or moss instead of materials.
+//! Painted voxels are reported as [`MaterialChanged`] messages.
+//! [`AreaEffects`] writes both kinds of command for explosions and spells,
+//! and roads along splines.
+
+mod area;
+mod build_up;
+mod changes;
+mod column;
+mod filter;
+mod params;
+mod shore;
+mod stroke;
+
+use std::fmt;
+use std::sync::Arc;
+
+use bevy::log::info_span;
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy_sculpter::neighbor::NEIGHBOR_DEPTH;
+use bevy_sculpter::prelude::{DensityField, DensityFieldMeshSize};
+
+use super::density::DensitySource;
+use super::field::{FIELD_SIZE, MaterialField, MaterialFieldDirty};
+use super::storage;
+
+pub use area::{AreaEffects, ScorchConfig, SplineConfig};
+pub use build_up::PaintBuildUp;
+pub use changes::{MaterialChanged, RecordChanges};
+pub use column::{paint_column, paint_disc_topdown, surface_height};
+pub use filter::{And, BrushFilter, BrushVoxel, HeightRange, MaxSlope, NearSurface, Not, Or};
+pub use params::{ParamPaintCommand, apply_param_paint_commands};
+pub use shore::paint_shorelines;
+pub use stroke::{PaintStroke, StrokePreview, flush_paint_stroke};
+
+/// Restricts which existing materials a brush may replace.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::{MaterialField, MaterialMask, brush};
+///
+/// const GRASS: u8 = 1;
+/// const DIRT: u8 = 2;
+///
+/// let mut field = MaterialField::filled(GRASS);
+/// field.set(16, 16, 16, 5);
+///
+/// // Dirt patch strictly on grass
+/// let mask = MaterialMask::only(&[GRASS]);
+/// brush::paint_sphere(&mut field, vec3(16.0, 16.0, 16.0), 3.0, DIRT, Some(&mask));
+///
+/// assert_eq!(field.get(16, 16, 17), DIRT);
+/// assert_eq!(field.get(16, 16, 16), 5);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaterialMask {
+    /// One bit per material ID; set bits may be painted over.
+    allowed: [u64; 4],
+}
+
+impl MaterialMask {
+    /// Only replace the listed materials.
+    pub fn only(material_ids: &[u8]) -> Self {
+        let mut mask = Self { allowed: [0; 4] };
+        for &id in material_ids {
+            mask.allowed[id as usize / 64] |= 1 << (id % 64);
+        }
+        mask
+    }
+
+    /// Replace every material except the listed ones.
+    pub fn except(material_ids: &[u8]) -> Self {
+        let only = Self::only(material_ids);
+        Self {
+            allowed: only.allowed.map(|bits| !bits),
+        }
+    }
+
+    /// Also allow `material_id`.
+    pub fn with(mut self, material_id: u8) -> Self {
+        self.allowed[material_id as usize / 64] |= 1 << (material_id % 64);
+        self
+    }
+
+    /// Whether a voxel currently holding `material_id` may be painted.
+    #[inline]
+    pub fn allows(&self, material_id: u8) -> bool {
+        self.allowed[material_id as usize / 64] & (1 << (material_id % 64)) != 0
+    }
+}
+
+/// Where a brush paints: the chunk's density, if any, and its placement.
+#[derive(Clone, Copy)]
+pub struct BrushSpace<'a> {
+    /// Density of the painted chunk, for surface-aware filters.
+    pub density: Option<&'a dyn DensitySource>,
+    /// Maps grid coordinates to world space.
+    pub grid_to_world: Affine3A,
+}
+
+impl<'a> BrushSpace<'a> {
+    /// The voxel at `grid_pos`, currently holding `material_id`.
+    fn voxel(&self, grid_pos: UVec3, material_id: u8) -> BrushVoxel<'a> {
+        BrushVoxel {
+            grid_pos,
+            world_pos: self.grid_to_world.transform_point3(grid_pos.as_vec3()),
+            material_id,
+            density: self.density,
+            grid_to_world: self.grid_to_world.matrix3.into(),
+        }
+    }
+}
+
+impl Default for BrushSpace<'_> {
+    /// No density, one world unit per voxel.
+    fn default() -> Self {
+        Self {
+            density: None,
+            grid_to_world: Affine3A::IDENTITY,
+        }
+    }
+}
+
+/// Sets voxels inside `shape` (clamped to the field) that aren't already
+/// `material_id` and pass the filter to `material_id`. Returns whether any
+/// voxel changed.
+fn paint_region<S: storage::MaterialStorage + ?Sized>(
+    field: &mut S,
+    space: &BrushSpace,
+    shape: &BrushShape,
+    material_id: u8,
+    filter: &dyn BrushFilter,
+) -> bool {
+    let mut changed = false;
+    for grid_pos in shape.field_voxels(field.size()) {
+        let current = field.get(grid_pos);
+        if current == material_id || shape.falloff(grid_pos.as_vec3()).is_none() {
+            continue;
+        }
+        if !filter.allows(&space.voxel(grid_pos, current)) {
+            continue;
+        }
+        field.set(grid_pos, material_id);
+        changed = true;
+    }
+    changed
+}
+
+/// Filter from an optional material mask.
+fn mask_filter(mask: Option<&MaterialMask>) -> impl BrushFilter {
+    let mask = mask.copied();
+    move |voxel: &BrushVoxel| mask.is_none_or(|mask| mask.allows(voxel.material_id))
+}
+
+/// Paints voxels within `radius` of `center`, in grid coordinates.
+///
+/// Only voxels whose current material passes `mask` are replaced. Returns
+/// whether any voxel changed.
+pub fn paint_sphere(
+    field: &mut (impl storage::MaterialStorage + ?Sized),
+    center: Vec3,
+    radius: f32,
+    material_id: u8,
+    mask: Option<&MaterialMask>,
+) -> bool {
+    BrushShape::Sphere { center, radius }.paint(
+        field,
+        &BrushSpace::default(),
+        material_id,
+        &mask_filter(mask),
+    )
+}
+
+/// Paints voxels within `radius` of the segment from `start` to `end`, in
+/// grid coordinates.
+///
+/// Sweeping from the previous brush position to the current one keeps
+/// strokes continuous however far the cursor moved between frames. Only
+/// voxels whose current material passes `mask` are replaced. Returns whether
+/// any voxel changed.
+pub fn paint_capsule(
+    field: &mut (impl storage::MaterialStorage + ?Sized),
+    start: Vec3,
+    end: Vec3,
+    radius: f32,
+    material_id: u8,
+    mask: Option<&MaterialMask>,
+) -> bool {
+    BrushShape::Capsule { start, end, radius }.paint(
+        field,
+        &BrushSpace::default(),
+        material_id,
+        &mask_filter(mask),
+    )
+}
+
+/// Builds up `material_id` within `radius` of `center`, in grid coordinates.
+///
+/// Adds `strength` coverage at the center, fading to nothing at the edge,
+/// and mixes it into the voxels. See [`PaintBuildUp`]. Only voxels whose
+/// material before the build-up passes `mask` are affected. Returns whether
+/// any voxel's materials or mix changed.
+pub fn build_up_sphere(
+    field: &mut (impl storage::MaterialStorage + ?Sized),
+    build_up: &mut PaintBuildUp,
+    center: Vec3,
+    radius: f32,
+    material_id: u8,
+    strength: f32,
+    mask: Option<&MaterialMask>,
+) -> bool {
+    BrushShape::Sphere { center, radius }.build_up(
+        field,
+        build_up,
+        &BrushSpace::default(),
+        material_id,
+        strength,
+        &mask_filter(mask),
+    )
+}
+
+/// Paints voxels in `min..=max`, in grid coordinates.
+///
+/// Only voxels whose current material passes `mask` are replaced. Returns
+/// whether any voxel changed.
+pub fn paint_box(
+    field: &mut (impl storage::MaterialStorage + ?Sized),
+    min: IVec3,
+    max: IVec3,
+    material_id: u8,
+    mask: Option<&MaterialMask>,
+) -> bool {
+    BrushShape::Box {
+        min: min.as_vec3(),
+        max: max.as_vec3(),
+    }
+    .paint(
+        field,
+        &BrushSpace::default(),
+        material_id,
+        &mask_filter(mask),
+    )
+}
+
+/// Paints a dithered ramp from `material_a` at `from` to `material_b` at
+/// `to`, in grid coordinates.
+///
+/// Voxels between the planes through `from` and `to` (perpendicular to the
+/// axis between them) take `material_b` with a probability that grows along
+/// the axis, e.g. beach sand fading into grass in one stroke. `dither` in
+/// `0.0..=1.0` sets how noisy the transition is: 0 cuts sharply at the
+/// midpoint, 1 ramps linearly over the whole distance. The pattern is
+/// deterministic per voxel, so repainting gives the same result.
+///
+/// Only voxels whose current material passes `mask` are replaced. Returns
+/// whether any voxel changed.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::{MaterialField, brush};
+///
+/// const SAND: u8 = 3;
+/// const GRASS: u8 = 1;
+///
+/// let mut field = MaterialField::new();
+/// let (from, to) = (vec3(0.0, 0.0, 0.0), vec3(31.0, 0.0, 0.0));
+/// brush::paint_gradient(&mut field, from, to, SAND, GRASS, 1.0, None);
+///
+/// assert_eq!(field.get(0, 8, 8), SAND);
+/// assert_eq!(field.get(31, 8, 8), GRASS);
+/// ```
+pub fn paint_gradient(
+    field: &mut (impl storage::MaterialStorage + ?Sized),
+    from: Vec3,
+    to: Vec3,
+    material_a: u8,
+    material_b: u8,
+    dither: f32,
+    mask: Option<&MaterialMask>,
+) -> bool {
+    let axis = to - from;
+    let length_sq = axis.length_squared();
+    if length_sq <= f32::EPSILON {
+        return false;
+    }
+    let dither = dither.clamp(0.0, 1.0);
+
+    let size = field.size();
+    let mut changed = false;
+    let positions = (0..size.z)
+        .flat_map(|z| (0..size.y).flat_map(move |y| (0..size.x).map(move |x| uvec3(x, y, z))));
+    for pos in positions {
+        let material = field.get(pos);
+        let t = (pos.as_vec3() - from).dot(axis) / length_sq;
+        if !(0.0..=1.0).contains(&t) {
+            continue;
+        }
+        let threshold = 0.5 + (voxel_noise(pos) - 0.5) * dither;
+        let target = if t > threshold {
+            material_b
+        } else {
+            material_a
+        };
+        if material == target || mask.is_some_and(|mask| !mask.allows(material)) {
+            continue;
+        }
+        field.set(pos, target);
+        changed = true;
+    }
+    changed
+}
+
+/// Deterministic per-voxel noise in `0.0..1.0`.
+pub(crate) fn voxel_noise(pos: UVec3) -> f32 {
+    let mut h = pos.x.wrapping_mul(0x8da6_b343)
+        ^ pos.y.wrapping_mul(0xd816_3841)
+        ^ pos.z.wrapping_mul(0xcb1a_b31f);
+    // Final avalanche from the lowbias32 integer hash
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// Volume covered by a [`PaintCommand`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BrushShape {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    /// Axis-aligned box between two corners.
+    Box {
+        min: Vec3,
+        max: Vec3,
+    },
+    /// A sphere swept from `start` to `end`.
+    ///
+    /// Connects consecutive brush positions so fast strokes stay continuous.
+    Capsule {
+        start: Vec3,
+        end: Vec3,
+        radius: f32,
+    },
+}
+
+impl BrushShape {
+    /// Maps the shape through an affine transform without rotation.
+    fn transformed(&self, transform: Affine3A) -> Self {
+        match *self {
+            Self::Sphere { center, radius } => Self::Sphere {
+                center: transform.transform_point3(center),
+                radius: transform.transform_vector3(Vec3::X * radius).length(),
+            },
+            Self::Box { min, max } => {
+                let (a, b) = (
+                    transform.transform_point3(min),
+                    transform.transform_point3(max),
+                );
+                Self::Box {
+                    min: a.min(b),
+                    max: a.max(b),
+                }
+            }
+            Self::Capsule { start, end, radius } => Self::Capsule {
+                start: transform.transform_point3(start),
+                end: transform.transform_point3(end),
+                radius: transform.transform_vector3(Vec3::X * radius).length(),
+            },
+        }
+    }
+
+    /// Axis-aligned bounds of the shape.
+    fn bounds(&self) -> (Vec3, Vec3) {
+        match *self {
+            Self::Sphere { center, radius } => (center - radius, center + radius),
+            Self::Box { min, max } => (min, max),
+            Self::Capsule { start, end, radius } => {
+                (start.min(end) - radius, start.max(end) + radius)
+            }
+        }
+    }
+
+    /// Grid cells that may lie inside the shape.
+    fn voxel_bounds(&self) -> (IVec3, IVec3) {
+        let (min, max) = self.bounds();
+        match self {
+            // Box corners are inclusive
+            Self::Box { .. } => (min.ceil().as_ivec3(), max.floor().as_ivec3()),
+            _ => (min.floor().as_ivec3(), max.ceil().as_ivec3()),
+        }
+    }
+
+    /// Grid positions within a field of `size` that may lie inside the shape.
+    fn field_voxels(&self, size: UVec3) -> impl Iterator<Item = UVec3> + use<> {
+        let (min, max) = self.voxel_bounds();
+        let min = min.max(IVec3::ZERO);
+        let max = max.min(size.as_ivec3() - IVec3::ONE);
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| {
+                (min.x..=max.x).map(move |x| uvec3(x as u32, y as u32, z as u32))
+            })
+        })
+    }
+
+    /// Brush strength at `pos`: 1 at the center falling to 0 at the edge,
+    /// or `None` outside. Boxes are uniform.
+    fn falloff(&self, pos: Vec3) -> Option<f32> {
+        let (distance_sq, radius) = match *self {
+            Self::Sphere { center, radius } => (pos.distance_squared(center), radius),
+            Self::Box { min, max } => {
+                return (pos.cmpge(min).all() && pos.cmple(max).all()).then_some(1.0);
+            }
+            Self::Capsule { start, end, radius } => {
+                (segment_distance_squared(pos, start, end), radius)
+            }
+        };
+        (distance_sq <= radius * radius)
+            .then(|| (1.0 - distance_sq.sqrt() / radius.max(f32::EPSILON)).max(0.0))
+    }
+
+    /// Paints the shape, given in grid coordinates, into `field`.
+    ///
+    /// Only voxels passing `filter` are replaced. Returns whether any voxel
+    /// changed.
+    pub fn paint(
+        &self,
+        field: &mut (impl storage::MaterialStorage + ?Sized),
+        space: &BrushSpace,
+        material_id: u8,
+        filter: &dyn BrushFilter,
+    ) -> bool {
+        paint_region(field, space, self, material_id, filter)
+    }
+
+    /// Builds up `material_id` inside the shape, given in grid coordinates.
+    ///
+    /// Each call adds `strength` (scaled by the brush falloff) to the
+    /// coverage stored in `build_up` and mixes that share of `material_id`
+    /// into the voxel. Only voxels whose material before the build-up
+    /// passes `filter` are affected. Returns whether any voxel's materials
+    /// or mix changed.
+    pub fn build_up(
+        &self,
+        field: &mut (impl storage::MaterialStorage + ?Sized),
+        build_up: &mut PaintBuildUp,
+        space: &BrushSpace,
+        material_id: u8,
+        strength: f32,
+        filter: &dyn BrushFilter,
+    ) -> bool {
+        let mut changed = false;
+        for grid_pos in self.field_voxels(field.size()) {
+            let Some(falloff) = self.falloff(grid_pos.as_vec3()) else {
+                continue;
+            };
+            let current = field.get(grid_pos);
+            let secondary = field.secondary(grid_pos);
+            let share = secondary
+                .filter(|&(id, _)| id == material_id)
+                .map_or(0.0, |(_, share)| share);
+            let base = build_up.base(grid_pos, material_id, current, share);
+            if base == material_id || !filter.allows(&space.voxel(grid_pos, base)) {
+                continue;
+            }
+            let coverage = build_up.add(grid_pos, strength * falloff);
+            field.set_mix(grid_pos, base, material_id, coverage);
+            changed |= field.get(grid_pos) != current || field.secondary(grid_pos) != secondary;
+        }
+        changed
+    }
+}
+
+/// Squared distance from `point` to the segment `start..end`.
+fn segment_distance_squared(point: Vec3, start: Vec3, end: Vec3) -> f32 {
+    let axis = end - start;
+    let length_sq = axis.length_squared();
+    let t = if length_sq > 0.0 {
+        ((point - start).dot(axis) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance_squared(start + axis * t)
+}
+
+/// A world-space paint stroke, applied to every chunk it touches.
+///
+/// Chunks are entities with a [`MaterialField`] and a [`GlobalTransform`]
+/// whose mesh spans [`DensityFieldMeshSize`] in local space (one world unit
+/// per voxel without that resource). Surface-aware filters read the chunk's
+/// [`DensityField`] when present. Painted chunks, and neighbors whose
+/// boundary blending reads the painted voxels, get [`MaterialFieldDirty`].
+///
+/// # Example
+/// ```ignore
+/// fn paint_snow(mut paint: MessageWriter<PaintCommand>, hit: Vec3) {
+///     paint.write(
+///         PaintCommand::new(BrushShape::Sphere { center: hit, radius: 4.0 }, SNOW)
+///             .with_mask(MaterialMask::except(&[ROAD]))
+///             .with_filter(MaxSlope::degrees(35.0).and(NearSurface { max_distance: 1.5 })),
+///     );
+/// }
+/// ```
+#[derive(Message, Clone)]
+pub struct PaintCommand {
+    pub shape: BrushShape,
+    pub material_id: u8,
+    /// Existing materials the stroke may replace; `None` replaces any.
+    pub mask: Option<MaterialMask>,
+    /// Additional per-voxel constraint; `None` paints everywhere.
+    pub filter: Option<Arc<dyn BrushFilter>>,
+    /// Coverage added per command for soft build-up painting; `None`
+    /// replaces voxels outright. See [`PaintBuildUp`].
+    pub build_up: Option<f32>,
+}
+
+impl PaintCommand {
+    /// Paints `material_id` over everything inside `shape`.
+    pub fn new(shape: BrushShape, material_id: u8) -> Self {
+        Self {
+            shape,
+            material_id,
+            mask: None,
+            filter: None,
+            build_up: None,
+        }
+    }
+
+    /// Only replace materials allowed by `mask`.
+    pub fn with_mask(mut self, mask: MaterialMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Only paint voxels passing `filter`.
+    pub fn with_filter(mut self, filter: impl BrushFilter + 'static) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Build up the material gradually, adding `strength` (0.0 to 1.0)
+    /// coverage at the brush center per command.
+    ///
+    /// Send one command per frame while the stroke is held for an
+    /// airbrush-like effect.
+    pub fn with_build_up(mut self, strength: f32) -> Self {
+        self.build_up = Some(strength);
+        self
+    }
+
+    /// Whether the mask and filter allow painting `voxel`.
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        self.mask.is_none_or(|mask| mask.allows(voxel.material_id))
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.allows(voxel))
+    }
+}
+
+impl fmt::Debug for PaintCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PaintCommand")
+            .field("shape", &self.shape)
+            .field("material_id", &self.material_id)
+            .field("mask", &self.mask)
+            .field("filter", &self.filter.as_ref().map(|_| ".."))
+            .field("build_up", &self.build_up)
+            .finish()
+    }
+}
+
+/// System that applies [`PaintCommand`]s to chunk material fields.
+///
+/// Build-up commands use the chunk's [`PaintBuildUp`], adding one if it's
+/// missing. Writes a [`MaterialChanged`] batch per command. Touched chunks
+/// are collected by an open [`PaintStroke`] instead of being marked dirty
+/// right away.
+#[allow(clippy::type_complexity)]
+pub fn apply_paint_commands(
+    mut commands: Commands,
+    mut paint: MessageReader<PaintCommand>,
+    mut changed_voxels: MessageWriter<MaterialChanged>,
+    mut chunks: Query<(
+        Entity,
+        &GlobalTransform,
+        &mut MaterialField,
+        Option<&DensityField>,
+        Option<&mut PaintBuildUp>,
+    )>,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+    mut stroke: Option<ResMut<PaintStroke>>,
+) {
+    let grid_scale = grid_scale(mesh_size.as_deref());
+    let mut touched = Vec::new();
+    let mut stroke_changes = Vec::new();
+
+    for command in paint.read() {
+        let _span = info_span!("paint_command", material = command.material_id).entered();
+        let mut painted = false;
+        touched.clear();
+
+        for (entity, transform, mut field, density, build_up) in &mut chunks {
+            let Some((shape, grid_to_world)) =
+                shape_in_chunk(&command.shape, transform, grid_scale)
+            else {
+                continue;
+            };
+            touched.push(entity);
+
+            let space = BrushSpace {
+                density: density.map(|density| density as &dyn DensitySource),
+                grid_to_world,
+            };
+            let filter = |voxel: &BrushVoxel| command.allows(voxel);
+            // Fully masked strokes shouldn't trigger change detection
+            let mut recorder = RecordChanges::new(field.bypass_change_detection());
+            let field_data = &mut recorder;
+            let changed = match (command.build_up, build_up) {
+                (None, _) => shape.paint(field_data, &space, command.material_id, &filter),
+                (Some(strength), Some(mut build_up)) => shape.build_up(
+                    field_data,
+                    &mut build_up,
+                    &space,
+                    command.material_id,
+                    strength,
+                    &filter,
+                ),
+                (Some(strength), None) => {
+                    let mut build_up = PaintBuildUp::new();
+                    let changed = shape.build_up(
+                        field_data,
+                        &mut build_up,
+                        &space,
+                        command.material_id,
+                        strength,
+                        &filter,
+                    );
+                    commands.entity(entity).insert(build_up);
+                    changed
+                }
+            };
+            stroke_changes.extend(recorder.into_changes(entity));
+            if changed {
+                field.set_changed();
+                painted = true;
+            }
+        }
+
+        if painted {
+            let deferred = stroke
+                .as_mut()
+                .is_some_and(|stroke| stroke.defer(touched.iter().copied()));
+            if !deferred {
+                for &entity in &touched {
+                    commands.entity(entity).insert(MaterialFieldDirty);
+                }
+            }
+            changed_voxels.write_batch(stroke_changes.drain(..));
+        }
+    }
+}
+
+/// Field cells per world unit along each axis.
+pub(crate) fn grid_scale(mesh_size: Option<&DensityFieldMeshSize>) -> Vec3 {
+    FIELD_SIZE.as_vec3() / mesh_size.map_or(FIELD_SIZE.as_vec3(), |size| size.0)
+}
+
+/// A world-space shape in a chunk's grid coordinates, with the chunk's
+/// grid-to-world transform. `None` when the shape misses the chunk and the
+/// neighbor margin its boundary blending reads.
+fn shape_in_chunk(
+    shape: &BrushShape,
+    transform: &GlobalTransform,
+    grid_scale: Vec3,
+) -> Option<(BrushShape, Affine3A)> {
+    let to_grid = Affine3A::from_scale(grid_scale) * transform.affine().inverse();
+    let shape = shape.transformed(to_grid);
+
+    let margin = NEIGHBOR_DEPTH as f32;
+    let (min, max) = shape.bounds();
+    if max.cmplt(Vec3::splat(-margin)).any() || min.cmpgt(FIELD_SIZE.as_vec3() - 1.0 + margin).any()
+    {
+        return None;
+    }
+    Some((shape, to_grid.inverse()))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_sculpter::field::Field;
+
+    use super::*;
+    use crate::material_field::{
+        MaterialBlendSettings, MaterialFieldDual, SvoMaterialField, VertexMaterialComputer,
+    };
+
+    #[test]
+    fn test_brushes_paint_other_storage() {
+        let mut region = SvoMaterialField::new(64, 1);
+        assert!(paint_sphere(
+            &mut region,
+            vec3(40.0, 20.0, 20.0),
+            10.0,
+            2,
+            None
+        ));
+        assert_eq!(storage::MaterialStorage::get(&region, uvec3(49, 20, 20)), 2);
+        assert_eq!(storage::MaterialStorage::get(&region, uvec3(51, 20, 20)), 1);
+
+        paint_gradient(
+            &mut region,
+            Vec3::ZERO,
+            vec3(63.0, 0.0, 0.0),
+            3,
+            4,
+            0.0,
+            None,
+        );
+        assert_eq!(storage::MaterialStorage::get(&region, uvec3(5, 50, 5)), 3);
+        assert_eq!(storage::MaterialStorage::get(&region, uvec3(60, 50, 5)), 4);
+
+        // Blending reads the octree directly
+        let chunk = SvoMaterialField::new(32, 4);
+        let settings = MaterialBlendSettings::default();
+        let density = DensityField::new();
+        let computer =
+            VertexMaterialComputer::new(&density, &chunk, FIELD_SIZE.as_vec3(), &settings);
+        assert_eq!(computer.compute(vec3(8.0, 8.0, 8.0)).ids[0], 4);
+    }
+
+    #[test]
+    fn test_mask_only_and_except() {
+        let only = MaterialMask::only(&[1, 200]);
+        assert!(only.allows(1));
+        assert!(only.allows(200));
+        assert!(!only.allows(0));
+        assert!(!only.allows(64));
+
+        let except = MaterialMask::except(&[1, 200]);
+        assert!(!except.allows(1));
+        assert!(!except.allows(200));
+        assert!(except.allows(0));
+        assert!(except.allows(255));
+    }
+
+    #[test]
+    fn test_masked_sphere_only_replaces_allowed() {
+        let mut field = MaterialField::filled(1);
+        field.paint_with(|pos| if pos.x < 16 { 1 } else { 3 });
+
+        let changed = paint_sphere(
+            &mut field,
+            vec3(16.0, 16.0, 16.0),
+            4.0,
+            2,
+            Some(&MaterialMask::only(&[1])),
+        );
+
+        assert!(changed);
+        assert_eq!(field.get(15, 16, 16), 2);
+        assert_eq!(field.get(16, 16, 16), 3);
+        assert_eq!(field.get(10, 16, 16), 1);
+    }
+
+    #[test]
+    fn test_masked_out_brush_reports_no_change() {
+        let mut field = MaterialField::filled(4);
+        let changed = paint_box(
+            &mut field,
+            IVec3::ZERO,
+            IVec3::splat(40),
+            2,
+            Some(&MaterialMask::except(&[4])),
+        );
+        assert!(!changed);
+        assert!(!field.contains(2));
+    }
+
+    #[test]
+    fn test_capsule_fills_gap_between_dabs() {
+        let mut field = MaterialField::new();
+        let (start, end) = (vec3(4.0, 16.0, 16.0), vec3(28.0, 16.0, 16.0));
+        assert!(paint_capsule(&mut field, start, end, 1.5, 3, None));
+
+        assert!((4..=28).all(|x| field.get(x, 16, 16) == 3));
+        assert_eq!(field.get(16, 17, 17), 3);
+        assert_eq!(field.get(16, 18, 16), 0);
+        // Rounded caps
+        assert_eq!(field.get(2, 16, 16), 0);
+        assert_eq!(field.get(3, 16, 16), 3);
+        assert_eq!(field.get(3, 17, 17), 0);
+    }
+
+    #[test]
+    fn test_build_up_grows_from_center() {
+        let mut field = MaterialField::new();
+        let mut build_up = PaintBuildUp::new();
+        let center = vec3(16.0, 16.0, 16.0);
+        let mut stroke = |field: &mut MaterialField| {
+            build_up_sphere(field, &mut build_up, center, 4.0, 2, 0.3, None)
+        };
+
+        // Single-material storage switches once more than half covered
+        assert!(!stroke(&mut field));
+        assert!(stroke(&mut field));
+        assert_eq!(field.get(16, 16, 16), 2);
+        assert_eq!(field.get(18, 16, 16), 0);
+
+        for _ in 0..2 {
+            stroke(&mut field);
+        }
+        assert_eq!(field.get(18, 16, 16), 2);
+        assert_eq!(field.get(20, 16, 16), 0);
+    }
+
+    #[test]
+    fn test_build_up_blends_weight_progressively() {
+        let mut field = MaterialFieldDual::filled(1);
+        let mut build_up = PaintBuildUp::new();
+        let center = vec3(16.0, 16.0, 16.0);
+        let pos = uvec3(16, 16, 16);
+
+        let mut shares = Vec::new();
+        for _ in 0..4 {
+            assert!(build_up_sphere(
+                &mut field,
+                &mut build_up,
+                center,
+                4.0,
+                2,
+                0.3,
+                None
+            ));
+            let (primary, secondary, share) = field.mix(pos);
+            shares.push(if primary == 2 { 1.0 - share } else { share });
+            assert!(primary == 2 || secondary == 2);
+        }
+        assert!(shares.windows(2).all(|pair| pair[1] > pair[0]));
+        assert!((shares[0] - 0.3).abs() < 0.01);
+        assert_eq!(storage::MaterialStorage::get(&field, pos), 2);
+        assert_eq!(storage::MaterialStorage::secondary(&field, pos), None);
+    }
+
+    #[test]
+    fn test_build_up_resets_on_new_material() {
+        let mut build_up = PaintBuildUp::new();
+        let pos = uvec3(1, 2, 3);
+        assert_eq!(build_up.base(pos, 4, 1, 0.0), 1);
+        build_up.add(pos, 0.6);
+        assert!((build_up.coverage(pos, 4) - 0.6).abs() < 1e-3);
+
+        assert_eq!(build_up.base(pos, 5, 4, 0.0), 4);
+        assert_eq!(build_up.coverage(pos, 4), 0.0);
+        assert!((build_up.add(pos, 0.6) - 0.6).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_build_up_clear_resets_all_state() {
+        let mut build_up = PaintBuildUp::new();
+        let pos = uvec3(1, 2, 3);
+        build_up.base(pos, 4, 1, 0.0);
+        build_up.add(pos, 0.6);
+
+        build_up.clear();
+        assert_eq!(build_up.coverage(pos, 4), 0.0);
+        // A voxel that became the target material isn't built on again
+        assert_eq!(build_up.base(pos, 4, 4, 0.0), 4);
+    }
+
+    #[test]
+    fn test_gradient_ramps_between_materials() {
+        let mut field = MaterialField::new();
+        let from = vec3(4.0, 0.0, 0.0);
+        let to = vec3(28.0, 0.0, 0.0);
+        assert!(paint_gradient(&mut field, from, to, 1, 2, 1.0, None));
+
+        // Outside the slab is untouched
+        assert_eq!(field.get(2, 5, 5), 0);
+        assert_eq!(field.get(30, 5, 5), 0);
+
+        // Share of material 2 grows along the axis
+        let share = |x: u32| {
+            let count = (0..32 * 32)
+                .filter(|i| field.get(x, i % 32, i / 32) == 2)
+                .count();
+            count as f32 / 1024.0
+        };
+        assert_eq!(share(4), 0.0);
+        assert!((share(10) - 0.25).abs() < 0.1);
+        assert!((share(22) - 0.75).abs() < 0.1);
+        assert_eq!(share(28), 1.0);
+    }
+
+    #[test]
+    fn test_gradient_without_dither_is_sharp() {
+        let mut field = MaterialField::new();
+        paint_gradient(
+            &mut field,
+            Vec3::ZERO,
+            vec3(0.0, 0.0, 30.0),
+            1,
+            2,
+            0.0,
+            Some(&MaterialMask::only(&[0])),
+        );
+        assert!((0..=30).all(|z| field.get(3, 3, z) == if z > 15 { 2 } else { 1 }));
+        assert_eq!(field.get(3, 3, 31), 0);
+    }
+
+    #[test]
+    fn test_paint_command_marks_touched_chunks() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<PaintCommand>()
+            .add_message::<MaterialChanged>()
+            .add_systems(Update, apply_paint_commands);
+
+        // Two chunks side by side along X, 32 world units each
+        let left = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::new()))
+            .id();
+        let right = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(32.0, 0.0, 0.0)),
+                MaterialField::new(),
+            ))
+            .id();
+        let far = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(320.0, 0.0, 0.0)),
+                MaterialField::new(),
+            ))
+            .id();
+
+        app.world_mut().write_message(PaintCommand::new(
+            BrushShape::Sphere {
+                center: vec3(31.0, 8.0, 8.0),
+                radius: 2.0,
+            },
+            5,
+        ));
+        app.update();
+
+        let world = app.world();
+        assert_eq!(world.get::<MaterialField>(left).unwrap().get(31, 8, 8), 5);
+        assert_eq!(world.get::<MaterialField>(right).unwrap().get(1, 8, 8), 5);
+        assert!(world.get::<MaterialFieldDirty>(left).is_some());
+        assert!(world.get::<MaterialFieldDirty>(right).is_some());
+        assert!(world.get::<MaterialFieldDirty>(far).is_none());
+
+        let changes = world.resource::<Messages<MaterialChanged>>();
+        let mut reader = changes.get_cursor();
+        let changes: Vec<_> = reader.read(changes).copied().collect();
+        assert!(
+            changes
+                .iter()
+                .all(|change| change.old == 0 && change.new == 5)
+        );
+        assert!(changes.contains(&MaterialChanged {
+            chunk: right,
+            voxel: uvec3(1, 8, 8),
+            old: 0,
+            new: 5,
+        }));
+        assert!(changes.iter().any(|change| change.chunk == left));
+    }
+}
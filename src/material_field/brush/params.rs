@@ -0,0 +1,278 @@
+//! Brushes that paint auxiliary [`MaterialParam`]s instead of materials.
+
+use std::fmt;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_sculpter::field::Field;
+use bevy_sculpter::prelude::{DensityField, DensityFieldMeshSize};
+
+use super::{BrushFilter, BrushShape, BrushSpace, BrushVoxel, MaterialMask, PaintStroke};
+use crate::material_field::{
+    DensitySource, FIELD_SIZE, MaterialField, MaterialFieldDirty, MaterialParamsField,
+};
+use crate::mesh::MaterialParam;
+
+impl BrushShape {
+    /// Adds `amount` of `param` inside the shape, given in grid coordinates.
+    ///
+    /// The amount is scaled by the brush falloff and may be negative, e.g.
+    /// to dry out wet ground. `materials` only feeds the filter; material
+    /// IDs are left alone. Returns whether any value changed.
+    pub fn paint_param(
+        &self,
+        params: &mut MaterialParamsField,
+        materials: &MaterialField,
+        space: &BrushSpace,
+        param: MaterialParam,
+        amount: f32,
+        filter: &dyn BrushFilter,
+    ) -> bool {
+        let mut changed = false;
+        for grid_pos in self.field_voxels(FIELD_SIZE) {
+            let Some(falloff) = self.falloff(grid_pos.as_vec3()) else {
+                continue;
+            };
+            let material_id = materials.get(grid_pos.x, grid_pos.y, grid_pos.z);
+            if !filter.allows(&space.voxel(grid_pos, material_id)) {
+                continue;
+            }
+
+            let mut values = params.get(grid_pos.x, grid_pos.y, grid_pos.z);
+            let value = &mut values[param.index()];
+            let painted = (*value as f32 + amount * falloff * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+            if painted == *value {
+                continue;
+            }
+            *value = painted;
+            params.set(grid_pos.x, grid_pos.y, grid_pos.z, values);
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// A world-space stroke adding wetness, burn or moss to every chunk it
+/// touches.
+///
+/// Chunks are placed like for [`PaintCommand`](super::PaintCommand). Chunks
+/// without a [`MaterialParamsField`] get one. Painted chunks are marked
+/// [`MaterialFieldDirty`] so their meshes pick up the new
+/// [`ATTRIBUTE_MATERIAL_PARAMS`](crate::mesh::ATTRIBUTE_MATERIAL_PARAMS).
+///
+/// # Example
+/// ```ignore
+/// fn scorch(mut paint: MessageWriter<ParamPaintCommand>, blast: Vec3) {
+///     paint.write(ParamPaintCommand::new(
+///         BrushShape::Sphere { center: blast, radius: 6.0 },
+///         MaterialParam::Burn,
+///         1.0,
+///     ));
+/// }
+/// ```
+#[derive(Message, Clone)]
+pub struct ParamPaintCommand {
+    pub shape: BrushShape,
+    pub param: MaterialParam,
+    /// Added at the brush center, fading to nothing at the edge. Negative
+    /// values remove the param.
+    pub amount: f32,
+    /// Materials the stroke may affect; `None` affects any.
+    pub mask: Option<MaterialMask>,
+    /// Additional per-voxel constraint; `None` paints everywhere.
+    pub filter: Option<Arc<dyn BrushFilter>>,
+}
+
+impl ParamPaintCommand {
+    /// Adds `amount` of `param` inside `shape`.
+    pub fn new(shape: BrushShape, param: MaterialParam, amount: f32) -> Self {
+        Self {
+            shape,
+            param,
+            amount,
+            mask: None,
+            filter: None,
+        }
+    }
+
+    /// Only affect materials allowed by `mask`.
+    pub fn with_mask(mut self, mask: MaterialMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Only paint voxels passing `filter`.
+    pub fn with_filter(mut self, filter: impl BrushFilter + 'static) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Whether the mask and filter allow painting `voxel`.
+    fn allows(&self, voxel: &BrushVoxel) -> bool {
+        self.mask.is_none_or(|mask| mask.allows(voxel.material_id))
+            && self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.allows(voxel))
+    }
+}
+
+impl fmt::Debug for ParamPaintCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParamPaintCommand")
+            .field("shape", &self.shape)
+            .field("param", &self.param)
+            .field("amount", &self.amount)
+            .field("mask", &self.mask)
+            .field("filter", &self.filter.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// System that applies [`ParamPaintCommand`]s to chunk param fields.
+///
+/// Painted chunks are collected by an open [`PaintStroke`] instead of
+/// being marked dirty right away.
+#[allow(clippy::type_complexity)]
+pub fn apply_param_paint_commands(
+    mut commands: Commands,
+    mut paint: MessageReader<ParamPaintCommand>,
+    mut chunks: Query<(
+        Entity,
+        &GlobalTransform,
+        &MaterialField,
+        Option<&DensityField>,
+        Option<&mut MaterialParamsField>,
+    )>,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+    mut stroke: Option<ResMut<PaintStroke>>,
+) {
+    let grid_scale = super::grid_scale(mesh_size.as_deref());
+
+    for command in paint.read() {
+        for (entity, transform, materials, density, params) in &mut chunks {
+            let Some((shape, grid_to_world)) =
+                super::shape_in_chunk(&command.shape, transform, grid_scale)
+            else {
+                continue;
+            };
+
+            let space = BrushSpace {
+                density: density.map(|density| density as &dyn DensitySource),
+                grid_to_world,
+            };
+            let filter = |voxel: &BrushVoxel| command.allows(voxel);
+            let changed = match params {
+                Some(mut params) => {
+                    // Strokes that change nothing shouldn't trigger change detection
+                    let changed = shape.paint_param(
+                        params.bypass_change_detection(),
+                        materials,
+                        &space,
+                        command.param,
+                        command.amount,
+                        &filter,
+                    );
+                    if changed {
+                        params.set_changed();
+                    }
+                    changed
+                }
+                None => {
+                    let mut params = MaterialParamsField::new();
+                    let changed = shape.paint_param(
+                        &mut params,
+                        materials,
+                        &space,
+                        command.param,
+                        command.amount,
+                        &filter,
+                    );
+                    if changed {
+                        commands.entity(entity).insert(params);
+                    }
+                    changed
+                }
+            };
+            if changed && !stroke.as_mut().is_some_and(|stroke| stroke.defer([entity])) {
+                commands.entity(entity).insert(MaterialFieldDirty);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param_brush_falls_off_and_clamps() {
+        let materials = MaterialField::new();
+        let mut params = MaterialParamsField::new();
+        let shape = BrushShape::Sphere {
+            center: vec3(16.0, 16.0, 16.0),
+            radius: 4.0,
+        };
+        let space = BrushSpace::default();
+        let everywhere = |_: &BrushVoxel| true;
+
+        for _ in 0..3 {
+            shape.paint_param(
+                &mut params,
+                &materials,
+                &space,
+                MaterialParam::Wetness,
+                0.5,
+                &everywhere,
+            );
+        }
+        assert_eq!(params.param(uvec3(16, 16, 16), MaterialParam::Wetness), 1.0);
+        let edge = params.param(uvec3(18, 16, 16), MaterialParam::Wetness);
+        assert!((edge - 0.75).abs() < 0.01);
+        assert_eq!(params.param(uvec3(16, 16, 16), MaterialParam::Burn), 0.0);
+
+        // Negative amounts dry it out again
+        shape.paint_param(
+            &mut params,
+            &materials,
+            &space,
+            MaterialParam::Wetness,
+            -1.0,
+            &everywhere,
+        );
+        assert_eq!(params.param(uvec3(16, 16, 16), MaterialParam::Wetness), 0.0);
+    }
+
+    #[test]
+    fn test_param_command_adds_field() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_message::<ParamPaintCommand>()
+            .add_systems(Update, apply_param_paint_commands);
+
+        let chunk = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(2)))
+            .id();
+
+        app.world_mut().write_message(
+            ParamPaintCommand::new(
+                BrushShape::Sphere {
+                    center: vec3(8.0, 8.0, 8.0),
+                    radius: 2.0,
+                },
+                MaterialParam::Moss,
+                1.0,
+            )
+            .with_mask(MaterialMask::only(&[2])),
+        );
+        app.update();
+
+        let world = app.world();
+        let params = world.get::<MaterialParamsField>(chunk).unwrap();
+        assert_eq!(params.param(uvec3(8, 8, 8), MaterialParam::Moss), 1.0);
+        assert!(world.get::<MaterialFieldDirty>(chunk).is_some());
+    }
+}
@@ -0,0 +1,141 @@
+//! Shoreline banding around a water plane.
+//!
+//! Lakes and oceans look painted on when the grass runs straight into the
+//! water. [`paint_shorelines`] bands the terrain's surface by height above
+//! the water instead, e.g. wet sand at the waterline, dry sand above and
+//! grass beyond, in one call per chunk.
+
+use bevy::prelude::*;
+
+use super::MaterialMask;
+use crate::material_field::density::DensitySource;
+use crate::material_field::storage::MaterialStorage;
+
+/// Face neighbor offsets checked for open space.
+const FACES: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// Paints surface voxels in bands by height above `water_level`, in grid
+/// coordinates.
+///
+/// `bands` are `(max_height, material_id)` pairs from the water up: each
+/// surface voxel takes the first band whose `max_height` above the water
+/// is above its own, so surfaces below the water take the first band and
+/// surfaces higher than the last band are left alone. Surface voxels are
+/// solid ones with an open face neighbor within the chunk. Convert a world
+/// water height with [`coords`](crate::material_field::coords), as
+/// `(water_y - chunk_origin.y) / voxel_size.y`.
+///
+/// Only voxels whose current material passes `mask` are replaced. Returns
+/// whether any voxel changed.
+///
+/// # Example
+/// ```ignore
+/// let bands = [(0.5, WET_SAND), (2.5, SAND), (4.0, DRY_GRASS)];
+/// brush::paint_shorelines(&mut field, &density, sea_level, &bands, Some(&MaterialMask::except(&[ROAD])));
+/// ```
+pub fn paint_shorelines(
+    field: &mut (impl MaterialStorage + ?Sized),
+    density: &(impl DensitySource + ?Sized),
+    water_level: f32,
+    bands: &[(f32, u8)],
+    mask: Option<&MaterialMask>,
+) -> bool {
+    let Some(&(top, _)) = bands.last() else {
+        return false;
+    };
+    let size = field.size().min(density.size());
+    let solid = |pos: IVec3| density.density(pos).is_some_and(|value| value < 0.0);
+    let open = |pos: IVec3| density.density(pos).is_some_and(|value| value >= 0.0);
+
+    // Rows above the highest band can't change
+    let max_y = ((water_level + top).ceil().max(0.0) as u32).min(size.y);
+    let mut changed = false;
+    for z in 0..size.z {
+        for y in 0..max_y {
+            for x in 0..size.x {
+                let pos = ivec3(x as i32, y as i32, z as i32);
+                if !solid(pos) || !FACES.iter().any(|&face| open(pos + face)) {
+                    continue;
+                }
+                let height = y as f32 - water_level;
+                let Some(&(_, material_id)) = bands.iter().find(|(max, _)| height < *max) else {
+                    continue;
+                };
+                let pos = pos.as_uvec3();
+                let current = field.get(pos);
+                if current == material_id || mask.is_some_and(|mask| !mask.allows(current)) {
+                    continue;
+                }
+                field.set(pos, material_id);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_sculpter::field::Field;
+    use bevy_sculpter::prelude::DensityField;
+
+    use super::*;
+    use crate::material_field::MaterialField;
+
+    const GRASS: u8 = 1;
+    const WET_SAND: u8 = 2;
+    const SAND: u8 = 3;
+    const ROAD: u8 = 4;
+
+    #[test]
+    fn test_shore_bands_follow_height_above_water() {
+        // Ground rising one voxel every four along X, surface at 4..=11
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    density.set(x, y, z, y as f32 - (4 + x / 4) as f32 - 0.5);
+                }
+            }
+        }
+        let mut field = MaterialField::filled(GRASS);
+        field.set(9, 6, 5, ROAD);
+
+        let bands = [(1.0, WET_SAND), (3.0, SAND)];
+        let mask = MaterialMask::only(&[GRASS]);
+        assert!(paint_shorelines(
+            &mut field,
+            &density,
+            6.0,
+            &bands,
+            Some(&mask)
+        ));
+
+        // Underwater and at the waterline
+        assert_eq!(field.get(0, 4, 5), WET_SAND);
+        assert_eq!(field.get(8, 6, 5), WET_SAND);
+        assert_eq!(field.get(9, 6, 5), ROAD);
+        // Above it, then untouched grass
+        assert_eq!(field.get(12, 7, 5), SAND);
+        assert_eq!(field.get(16, 8, 5), SAND);
+        assert_eq!(field.get(20, 9, 5), GRASS);
+        // Buried voxels aren't surface
+        assert_eq!(field.get(12, 3, 5), GRASS);
+
+        // Nothing left to change
+        assert!(!paint_shorelines(
+            &mut field,
+            &density,
+            6.0,
+            &bands,
+            Some(&mask)
+        ));
+    }
+}
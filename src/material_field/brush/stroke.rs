@@ -0,0 +1,214 @@
+//! Batching the remeshes of a painting stroke.
+
+use std::time::Duration;
+
+use bevy::ecs::entity::EntityHashSet;
+use bevy::prelude::*;
+
+use crate::material_field::MaterialFieldDirty;
+
+/// Groups the paint commands of one stroke into a single dirty pass.
+///
+/// While a stroke is open, [`apply_paint_commands`](super::apply_paint_commands)
+/// and [`apply_param_paint_commands`](super::apply_param_paint_commands)
+/// still write every voxel immediately, but collect the chunks they touch,
+/// including neighbors whose boundary blending reads the edit, instead of
+/// marking them [`MaterialFieldDirty`]. [`end_stroke`](Self::end_stroke)
+/// marks all of them at once, so a chunk dragged over for a hundred frames
+/// is remeshed once. With a [`flush_interval`](Self::flush_interval), the
+/// collected chunks are also marked while the stroke is open.
+///
+/// A [`preview_interval`](Self::preview_interval) instead remeshes them as
+/// a [`StrokePreview`] that often, keeping only the top 2 materials per
+/// vertex so big brushes stay responsive, and gives every previewed chunk
+/// a full-quality remesh when the stroke ends.
+///
+/// Without the resource, or outside a stroke, chunks are marked as soon as
+/// they are painted.
+///
+/// # Example
+/// ```ignore
+/// fn paint_tool(mouse: Res<ButtonInput<MouseButton>>, mut stroke: ResMut<PaintStroke>) {
+///     if mouse.just_pressed(MouseButton::Left) {
+///         stroke.begin_stroke();
+///     }
+///     if mouse.just_released(MouseButton::Left) {
+///         stroke.end_stroke();
+///     }
+/// }
+/// ```
+#[derive(Resource, Clone, Debug, Default)]
+pub struct PaintStroke {
+    /// Also mark collected chunks dirty this often during a stroke.
+    /// Default: None
+    pub flush_interval: Option<Duration>,
+    /// Remesh collected chunks as a cheaper [`StrokePreview`] this often
+    /// during a stroke.
+    /// Default: None
+    pub preview_interval: Option<Duration>,
+    active: bool,
+    pending: EntityHashSet,
+    /// Chunks remeshed as previews since the last full flush
+    previewed: EntityHashSet,
+    since_flush: Duration,
+}
+
+/// Marks chunks remeshed as a preview during a [`PaintStroke`].
+///
+/// [`remesh_dirty_chunks`](crate::material_field::remesh_dirty_chunks)
+/// keeps at most 2 materials per vertex for these chunks. Removed when the
+/// chunk is queued for its full-quality remesh.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct StrokePreview;
+
+/// Chunks [`PaintStroke`] marks dirty this frame.
+#[derive(Debug, PartialEq)]
+struct DueChunks {
+    chunks: EntityHashSet,
+    preview: bool,
+}
+
+impl PaintStroke {
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    pub fn with_preview_interval(mut self, interval: Duration) -> Self {
+        self.preview_interval = Some(interval);
+        self
+    }
+
+    /// Start collecting painted chunks.
+    pub fn begin_stroke(&mut self) {
+        self.active = true;
+        self.since_flush = Duration::ZERO;
+    }
+
+    /// Mark every chunk painted during the stroke dirty on the next flush.
+    pub fn end_stroke(&mut self) {
+        self.active = false;
+    }
+
+    /// Whether a stroke is open.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Chunks painted since the last preview or flush.
+    pub fn pending_chunks(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.pending.iter().copied()
+    }
+
+    /// Collect `chunks` if a stroke is open. Returns whether they were
+    /// collected rather than left for the caller to mark.
+    pub(crate) fn defer(&mut self, chunks: impl IntoIterator<Item = Entity>) -> bool {
+        if self.active {
+            self.pending.extend(chunks);
+        }
+        self.active
+    }
+
+    /// Chunks due to be marked dirty after `delta` more time.
+    fn take_due(&mut self, delta: Duration) -> Option<DueChunks> {
+        self.since_flush += delta;
+        let elapsed = |interval: Option<Duration>| {
+            interval.is_some_and(|interval| self.since_flush >= interval)
+        };
+        let preview = if !self.active || elapsed(self.flush_interval) {
+            false
+        } else if elapsed(self.preview_interval) {
+            true
+        } else {
+            return None;
+        };
+        if self.pending.is_empty() && (preview || self.previewed.is_empty()) {
+            return None;
+        }
+
+        self.since_flush = Duration::ZERO;
+        let mut chunks = std::mem::take(&mut self.pending);
+        if preview {
+            self.previewed.extend(chunks.iter().copied());
+        } else {
+            chunks.extend(self.previewed.drain());
+        }
+        Some(DueChunks { chunks, preview })
+    }
+}
+
+/// System marking the chunks collected by [`PaintStroke`] dirty when the
+/// stroke ends or one of its intervals passes.
+pub fn flush_paint_stroke(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut stroke: ResMut<PaintStroke>,
+) {
+    let Some(due) = stroke.take_due(time.delta()) else {
+        return;
+    };
+    for entity in due.chunks {
+        let Ok(mut entity) = commands.get_entity(entity) else {
+            continue;
+        };
+        entity.insert(MaterialFieldDirty);
+        if due.preview {
+            entity.insert(StrokePreview);
+        } else {
+            entity.remove::<StrokePreview>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stroke_flushes_on_end_and_interval() {
+        let mut world = World::new();
+        let chunk = world.spawn_empty().id();
+
+        let mut stroke = PaintStroke::default();
+        assert!(!stroke.defer([chunk]));
+        stroke.begin_stroke();
+        assert!(stroke.defer([chunk]));
+        assert_eq!(stroke.take_due(Duration::from_secs(10)), None);
+        stroke.end_stroke();
+        assert_eq!(
+            stroke.take_due(Duration::ZERO),
+            Some(DueChunks {
+                chunks: EntityHashSet::from_iter([chunk]),
+                preview: false,
+            })
+        );
+        assert_eq!(stroke.take_due(Duration::ZERO), None);
+
+        let mut stroke = PaintStroke::default().with_flush_interval(Duration::from_millis(100));
+        stroke.begin_stroke();
+        stroke.defer([chunk]);
+        assert_eq!(stroke.take_due(Duration::from_millis(60)), None);
+        assert!(stroke.take_due(Duration::from_millis(60)).is_some());
+        assert!(stroke.is_active());
+    }
+
+    #[test]
+    fn test_previewed_chunks_get_full_pass_on_end() {
+        let mut world = World::new();
+        let chunk = world.spawn_empty().id();
+
+        let mut stroke = PaintStroke::default().with_preview_interval(Duration::from_millis(50));
+        stroke.begin_stroke();
+        stroke.defer([chunk]);
+        let due = stroke.take_due(Duration::from_millis(50)).unwrap();
+        assert!(due.preview);
+        assert_eq!(stroke.pending_chunks().count(), 0);
+        // Nothing painted since the preview
+        assert_eq!(stroke.take_due(Duration::from_millis(50)), None);
+
+        stroke.end_stroke();
+        let due = stroke.take_due(Duration::ZERO).unwrap();
+        assert!(!due.preview);
+        assert_eq!(due.chunks, EntityHashSet::from_iter([chunk]));
+    }
+}
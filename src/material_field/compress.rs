@@ -0,0 +1,257 @@
+//! Run-length compressed material fields for inactive chunks.
+
+use bevy::prelude::*;
+use bevy_sculpter::prelude::DensityFieldMeshSize;
+
+use super::brush::grid_scale;
+use super::field::{FIELD_SIZE, FIELD_VOLUME, MaterialField};
+use super::pool::FieldPool;
+use super::views::{ActiveCameras, nearest_view_distance};
+
+/// A run of equal material IDs in storage order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct MaterialRun {
+    /// Storage index one past the run's last voxel.
+    end: u16,
+    material: u8,
+}
+
+/// Run-length encoded [`MaterialField`].
+///
+/// Terrain is mostly long stretches of one material along X, so this is
+/// typically 10-50x smaller than the 32KB field, and a single run for
+/// uniform chunks. Reads are still possible through [`get`](Self::get)
+/// without decompressing.
+///
+/// # Example
+/// ```
+/// use bevy_painter::material_field::MaterialField;
+///
+/// let mut field = MaterialField::filled(1);
+/// field.paint_height_layers(&[(12, 4), (32, 1)]);
+///
+/// let compressed = field.compress();
+/// // Two runs per Z slice
+/// assert_eq!(compressed.run_count(), 64);
+/// assert_eq!(compressed.decompress().as_slice(), field.as_slice());
+/// ```
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct CompressedMaterialField {
+    runs: Vec<MaterialRun>,
+}
+
+impl MaterialField {
+    /// Run-length encodes the field.
+    pub fn compress(&self) -> CompressedMaterialField {
+        let mut runs: Vec<MaterialRun> = Vec::new();
+        for (index, &material) in self.0.iter().enumerate() {
+            match runs.last_mut() {
+                Some(run) if run.material == material => run.end = index as u16 + 1,
+                _ => runs.push(MaterialRun {
+                    end: index as u16 + 1,
+                    material,
+                }),
+            }
+        }
+        runs.shrink_to_fit();
+        CompressedMaterialField { runs }
+    }
+}
+
+impl CompressedMaterialField {
+    /// Expands back into a full field.
+    pub fn decompress(&self) -> MaterialField {
+        let mut field = MaterialField(Vec::with_capacity(FIELD_VOLUME));
+        self.decompress_into(&mut field);
+        field
+    }
+
+    /// Expands into an existing field, reusing its allocation.
+    pub fn decompress_into(&self, field: &mut MaterialField) {
+        field.0.clear();
+        for run in &self.runs {
+            field.0.resize(run.end as usize, run.material);
+        }
+        field.0.resize(FIELD_VOLUME, 0);
+    }
+
+    /// Material at grid coordinates, or 0 out of bounds.
+    pub fn get(&self, pos: UVec3) -> u8 {
+        if pos.cmpge(FIELD_SIZE).any() {
+            return 0;
+        }
+        let index = (pos.x + pos.y * FIELD_SIZE.x + pos.z * FIELD_SIZE.x * FIELD_SIZE.y) as usize;
+        let run = self.runs.partition_point(|run| run.end as usize <= index);
+        self.runs.get(run).map_or(0, |run| run.material)
+    }
+
+    /// Returns the material if the whole field is one run.
+    pub fn is_uniform(&self) -> Option<u8> {
+        match self.runs.as_slice() {
+            [run] => Some(run.material),
+            _ => None,
+        }
+    }
+
+    /// Number of runs stored.
+    pub fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Heap bytes used by the runs.
+    pub fn memory_bytes(&self) -> usize {
+        self.runs.capacity() * size_of::<MaterialRun>()
+    }
+}
+
+/// Compresses the [`MaterialField`]s of chunks far from every active camera.
+///
+/// Chunks beyond `compress_distance` swap their field for a
+/// [`CompressedMaterialField`]; they get it back once a camera comes
+/// within `decompress_distance`. Keep the gap between the two wide enough
+/// that a camera hovering near the border doesn't flip chunks every frame.
+/// Compressed chunks keep their mesh but can't be painted or remeshed.
+///
+/// # Example
+/// ```ignore
+/// commands.insert_resource(FieldCompression::new(256.0));
+/// ```
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct FieldCompression {
+    /// Camera distance to a chunk's center beyond which it is compressed.
+    pub compress_distance: f32,
+    /// Camera distance within which a compressed chunk is expanded again.
+    pub decompress_distance: f32,
+}
+
+impl FieldCompression {
+    /// Compresses beyond `distance`, expanding again at 80% of it.
+    pub fn new(distance: f32) -> Self {
+        Self {
+            compress_distance: distance,
+            decompress_distance: distance * 0.8,
+        }
+    }
+
+    /// Set the distance within which chunks are expanded again.
+    pub fn with_decompress_distance(mut self, distance: f32) -> Self {
+        self.decompress_distance = distance;
+        self
+    }
+}
+
+/// System applying [`FieldCompression`].
+///
+/// Freed fields go to the [`FieldPool`] and expanded ones come from it.
+#[allow(clippy::type_complexity)]
+pub fn update_field_compression(
+    mut commands: Commands,
+    compression: Res<FieldCompression>,
+    cameras: ActiveCameras,
+    mut expanded: Query<(Entity, &GlobalTransform, &mut MaterialField)>,
+    compressed: Query<(Entity, &GlobalTransform, &CompressedMaterialField)>,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+    mut pool: Option<ResMut<FieldPool>>,
+) {
+    let views = cameras.positions();
+    if views.is_empty() {
+        return;
+    }
+    let half_extent = FIELD_SIZE.as_vec3() * 0.5 / grid_scale(mesh_size.as_deref());
+    let distance = |transform: &GlobalTransform| {
+        nearest_view_distance(&views, transform.transform_point(half_extent))
+    };
+
+    for (entity, transform, mut field) in &mut expanded {
+        if distance(transform) > compression.compress_distance {
+            let packed = field.compress();
+            if let Some(pool) = pool.as_mut() {
+                pool.recycle_field(MaterialField(std::mem::take(&mut field.0)));
+            }
+            commands
+                .entity(entity)
+                .remove::<MaterialField>()
+                .insert(packed);
+        }
+    }
+
+    for (entity, transform, packed) in &compressed {
+        if distance(transform) < compression.decompress_distance {
+            let mut field = match pool.as_mut() {
+                Some(pool) => pool.take_field(),
+                None => MaterialField::new(),
+            };
+            packed.decompress_into(&mut field);
+            commands
+                .entity(entity)
+                .remove::<CompressedMaterialField>()
+                .insert(field);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_sculpter::field::Field;
+
+    use super::*;
+
+    #[test]
+    fn test_compress_roundtrip_and_random_access() {
+        let mut field = MaterialField::filled(2);
+        field.paint_sphere(ivec3(10, 12, 14), 5, 7);
+        field.set(31, 31, 31, 9);
+
+        let compressed = field.compress();
+        assert_eq!(compressed.decompress().as_slice(), field.as_slice());
+        for (pos, material) in field.enumerate_coords() {
+            assert_eq!(compressed.get(pos), material);
+        }
+        assert!(compressed.memory_bytes() * 10 < FIELD_VOLUME);
+
+        let uniform = MaterialField::filled(4).compress();
+        assert_eq!(uniform.is_uniform(), Some(4));
+        let mut reused = MaterialField::filled(1);
+        uniform.decompress_into(&mut reused);
+        assert_eq!(reused.coverage(4), 1.0);
+    }
+
+    #[test]
+    fn test_far_chunks_compress_and_expand() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(FieldCompression::new(100.0))
+            .init_resource::<FieldPool>()
+            .add_systems(Update, update_field_compression);
+
+        let camera = app
+            .world_mut()
+            .spawn((Camera::default(), GlobalTransform::IDENTITY))
+            .id();
+        let near = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(3)))
+            .id();
+        let far = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(500.0, 0.0, 0.0)),
+                MaterialField::filled(3),
+            ))
+            .id();
+
+        app.update();
+        assert!(app.world().get::<MaterialField>(near).is_some());
+        assert!(app.world().get::<MaterialField>(far).is_none());
+        assert!(app.world().get::<CompressedMaterialField>(far).is_some());
+        assert_eq!(app.world().resource::<FieldPool>().field_count(), 1);
+
+        *app.world_mut().get_mut::<GlobalTransform>(camera).unwrap() =
+            GlobalTransform::from_translation(vec3(500.0, 0.0, 0.0));
+        app.update();
+        let world = app.world();
+        assert_eq!(world.get::<MaterialField>(far).unwrap().coverage(3), 1.0);
+        assert!(world.get::<CompressedMaterialField>(far).is_none());
+        assert!(world.get::<CompressedMaterialField>(near).is_some());
+    }
+}
@@ -0,0 +1,116 @@
+//! Conversions between world space, chunks and voxels.
+//!
+//! Chunks sit on a regular grid, `chunk_size` world units apart (the
+//! [`DensityFieldMeshSize`](bevy_sculpter::prelude::DensityFieldMeshSize)),
+//! each holding [`FIELD_SIZE`] voxels per axis. Voxels are samples at
+//! integer grid positions, so voxel `v` of chunk `c` sits exactly at
+//! `chunk_origin(c) + v * voxel_size`, not half a voxel in. World voxel
+//! coordinates count voxels from the world origin across chunks: chunk `c`
+//! holds world voxels `c * FIELD_SIZE` up to the next chunk's first.
+//!
+//! # Example
+//! ```
+//! use bevy::prelude::*;
+//! use bevy_painter::material_field::coords;
+//!
+//! let chunk_size = Vec3::splat(16.0);
+//! let voxel = coords::world_to_voxel(vec3(20.0, 1.0, -0.2), chunk_size);
+//! assert_eq!(voxel, ivec3(40, 2, 0));
+//! assert_eq!(coords::voxel_to_chunk(voxel), (ivec3(1, 0, 0), uvec3(8, 2, 0)));
+//! ```
+
+use bevy::prelude::*;
+use bevy_sculpter::neighbor::NEIGHBOR_DEPTH;
+
+use super::field::FIELD_SIZE;
+
+/// World size of one voxel along each axis.
+pub fn voxel_size(chunk_size: Vec3) -> Vec3 {
+    chunk_size / FIELD_SIZE.as_vec3()
+}
+
+/// Chunk whose cell contains `world_pos`.
+pub fn world_to_chunk(world_pos: Vec3, chunk_size: Vec3) -> IVec3 {
+    (world_pos / chunk_size).floor().as_ivec3()
+}
+
+/// World position of the corner of chunk `chunk`, where its voxel 0 sits.
+pub fn chunk_origin(chunk: IVec3, chunk_size: Vec3) -> Vec3 {
+    chunk.as_vec3() * chunk_size
+}
+
+/// World voxel nearest `world_pos`.
+pub fn world_to_voxel(world_pos: Vec3, chunk_size: Vec3) -> IVec3 {
+    (world_pos / voxel_size(chunk_size)).round().as_ivec3()
+}
+
+/// World position of the world voxel `voxel`.
+pub fn voxel_to_world_center(voxel: IVec3, chunk_size: Vec3) -> Vec3 {
+    voxel.as_vec3() * voxel_size(chunk_size)
+}
+
+/// Chunk holding world voxel `voxel`, and the voxel within that chunk.
+pub fn voxel_to_chunk(voxel: IVec3) -> (IVec3, UVec3) {
+    let size = FIELD_SIZE.as_ivec3();
+    (voxel.div_euclid(size), voxel.rem_euclid(size).as_uvec3())
+}
+
+/// World voxel of voxel `local` in chunk `chunk`.
+pub fn chunk_to_voxel(chunk: IVec3, local: UVec3) -> IVec3 {
+    chunk * FIELD_SIZE.as_ivec3() + local.as_ivec3()
+}
+
+/// Inclusive range of chunks affected by an edit within the world-space
+/// box `min..=max`.
+///
+/// Includes chunks that only reach the edit through the
+/// [`NEIGHBOR_DEPTH`] margin their boundary blending reads, matching the
+/// chunks a [`PaintCommand`](super::PaintCommand) marks dirty.
+pub fn chunk_range(min: Vec3, max: Vec3, chunk_size: Vec3) -> (IVec3, IVec3) {
+    let voxel = voxel_size(chunk_size);
+    let voxels = FIELD_SIZE.as_vec3();
+    let margin = NEIGHBOR_DEPTH as f32;
+    let first = ((min / voxel - (voxels - 1.0) - margin) / voxels).ceil();
+    let last = ((max / voxel + margin) / voxels).floor();
+    (first.as_ivec3(), last.as_ivec3())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_voxel_round_trips() {
+        let chunk_size = Vec3::splat(8.0);
+        for voxel in [ivec3(0, 0, 0), ivec3(-1, 31, 32), ivec3(-33, 64, 7)] {
+            let world = voxel_to_world_center(voxel, chunk_size);
+            assert_eq!(world_to_voxel(world, chunk_size), voxel);
+            let (chunk, local) = voxel_to_chunk(voxel);
+            assert_eq!(chunk_to_voxel(chunk, local), voxel);
+            assert_eq!(world_to_chunk(world, chunk_size), chunk);
+        }
+        assert_eq!(
+            voxel_to_chunk(ivec3(-1, 32, 31)),
+            (ivec3(-1, 1, 0), uvec3(31, 0, 31))
+        );
+        assert_eq!(
+            chunk_origin(ivec3(-2, 0, 1), chunk_size),
+            vec3(-16.0, 0.0, 8.0)
+        );
+    }
+
+    #[test]
+    fn test_chunk_range_includes_blend_margin() {
+        let chunk_size = FIELD_SIZE.as_vec3();
+        let (first, last) = chunk_range(vec3(10.0, 10.0, 10.0), vec3(12.0, 12.0, 12.0), chunk_size);
+        assert_eq!((first, last), (IVec3::ZERO, IVec3::ZERO));
+
+        // Near the +X face, the next chunk's margin reaches back
+        let (first, last) = chunk_range(vec3(30.0, 10.0, 10.0), vec3(31.0, 12.0, 12.0), chunk_size);
+        assert_eq!((first, last), (IVec3::ZERO, ivec3(1, 0, 0)));
+
+        // Just inside the -X face, the previous chunk reads it too
+        let (first, _) = chunk_range(vec3(0.0, 10.0, 10.0), vec3(1.0, 12.0, 12.0), chunk_size);
+        assert_eq!(first, ivec3(-1, 0, 0));
+    }
+}
@@ -0,0 +1,327 @@
+//! Keeps materials consistent with fluids when density changes.
+
+use bevy::prelude::*;
+use bevy_sculpter::field::Field;
+use bevy_sculpter::prelude::DensityField;
+
+use super::brush::MaterialMask;
+use super::field::{FIELD_SIZE, FIELD_VOLUME, MaterialField, MaterialFieldDirty};
+use crate::palette::{MaterialGroup, TexturePalette};
+
+/// Fluid materials and the rules that repaint terrain around them.
+///
+/// When a chunk's [`DensityField`] changes, every rule runs on its voxels,
+/// e.g. turning the bed of a lake into wet sand where sculpting just
+/// exposed it. Insert the resource to enable
+/// [`apply_fluid_coupling`]; chunks get a [`FluidCouplingState`] to
+/// remember what was solid before.
+///
+/// # Example
+/// ```ignore
+/// commands.insert_resource(
+///     FluidCoupling::from_palette(&palette).with_rule(WetRevealed {
+///         onto: MaterialMask::except(&[ROAD]),
+///         into: WET_SAND,
+///     }),
+/// );
+/// ```
+#[derive(Resource)]
+pub struct FluidCoupling {
+    /// Materials treated as fluids.
+    pub fluids: MaterialMask,
+    /// Rules tried in order on each voxel; the first that returns a
+    /// material wins.
+    pub rules: Vec<Box<dyn CouplingRule>>,
+}
+
+impl Default for FluidCoupling {
+    fn default() -> Self {
+        Self {
+            fluids: MaterialMask::only(&[]),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl FluidCoupling {
+    /// Treats the palette's [`MaterialGroup::Liquid`] materials as fluids.
+    pub fn from_palette(palette: &TexturePalette) -> Self {
+        let fluids: Vec<u8> = palette
+            .materials
+            .iter()
+            .enumerate()
+            .filter(|(_, material)| material.group == MaterialGroup::Liquid)
+            .map(|(id, _)| id as u8)
+            .collect();
+        Self {
+            fluids: MaterialMask::only(&fluids),
+            ..default()
+        }
+    }
+
+    /// Marks `material_id` as a fluid.
+    pub fn with_fluid(mut self, material_id: u8) -> Self {
+        self.fluids = self.fluids.with(material_id);
+        self
+    }
+
+    /// Appends a rule.
+    pub fn with_rule(mut self, rule: impl CouplingRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+}
+
+/// Which voxels of a chunk were solid when coupling last ran.
+#[derive(Component, Clone, Debug)]
+pub struct FluidCouplingState {
+    solid: Vec<u64>,
+}
+
+impl FluidCouplingState {
+    fn capture(density: &DensityField) -> Self {
+        let mut solid = vec![0u64; FIELD_VOLUME.div_ceil(64)];
+        for (index, &d) in density.data().iter().enumerate() {
+            if d < 0.0 {
+                solid[index / 64] |= 1 << (index % 64);
+            }
+        }
+        Self { solid }
+    }
+
+    fn is_solid(&self, pos: IVec3) -> bool {
+        if pos.cmplt(IVec3::ZERO).any() || pos.cmpge(FIELD_SIZE.as_ivec3()).any() {
+            return false;
+        }
+        let pos = pos.as_uvec3();
+        let index = (pos.x + pos.y * FIELD_SIZE.x + pos.z * FIELD_SIZE.x * FIELD_SIZE.y) as usize;
+        self.solid[index / 64] & (1 << (index % 64)) != 0
+    }
+}
+
+/// Face-adjacent offsets.
+const FACE_NEIGHBORS: [IVec3; 6] = [
+    IVec3::NEG_X,
+    IVec3::X,
+    IVec3::NEG_Y,
+    IVec3::Y,
+    IVec3::NEG_Z,
+    IVec3::Z,
+];
+
+/// A voxel of a chunk whose density just changed.
+#[derive(Clone, Copy)]
+pub struct CouplingCell<'a> {
+    /// Grid coordinates within the chunk.
+    pub grid_pos: UVec3,
+    /// Material currently stored in the voxel.
+    pub material_id: u8,
+    materials: &'a MaterialField,
+    density: &'a DensityField,
+    previous: &'a FluidCouplingState,
+    fluids: &'a MaterialMask,
+}
+
+impl CouplingCell<'_> {
+    /// Whether a material is registered as a fluid.
+    pub fn is_fluid(&self, material_id: u8) -> bool {
+        self.fluids.allows(material_id)
+    }
+
+    /// Whether the voxel is solid now.
+    pub fn is_solid(&self) -> bool {
+        let p = self.grid_pos;
+        self.density.get(p.x, p.y, p.z) < 0.0
+    }
+
+    /// Whether the voxel is solid and borders air on at least one face.
+    pub fn is_surface(&self) -> bool {
+        let p = self.grid_pos.as_ivec3();
+        self.is_solid()
+            && FACE_NEIGHBORS
+                .iter()
+                .any(|&offset| self.density.get_ivec3(p + offset).is_some_and(|d| d >= 0.0))
+    }
+
+    /// Whether the voxel was a surface voxel before the density changed.
+    pub fn was_surface(&self) -> bool {
+        let p = self.grid_pos.as_ivec3();
+        self.previous.is_solid(p)
+            && FACE_NEIGHBORS.iter().any(|&offset| {
+                let q = p + offset;
+                q.cmpge(IVec3::ZERO).all()
+                    && q.cmplt(FIELD_SIZE.as_ivec3()).all()
+                    && !self.previous.is_solid(q)
+            })
+    }
+
+    /// Whether the density change just exposed this voxel to air.
+    pub fn revealed(&self) -> bool {
+        self.is_surface() && !self.was_surface()
+    }
+
+    /// Whether any voxel above within the chunk holds a fluid.
+    pub fn under_fluid(&self) -> bool {
+        let p = self.grid_pos;
+        (p.y + 1..FIELD_SIZE.y).any(|y| self.is_fluid(self.materials.get(p.x, y, p.z)))
+    }
+
+    /// Whether any face-adjacent voxel holds a fluid.
+    pub fn touches_fluid(&self) -> bool {
+        let p = self.grid_pos.as_ivec3();
+        FACE_NEIGHBORS.iter().any(|&offset| {
+            self.materials
+                .get_ivec3(p + offset)
+                .is_some_and(|material| self.is_fluid(material))
+        })
+    }
+}
+
+/// Decides what a voxel becomes after its chunk's density changed.
+///
+/// Closures taking a [`CouplingCell`] are rules too.
+pub trait CouplingRule: Send + Sync {
+    /// New material for the voxel, or `None` to leave it to later rules.
+    fn apply(&self, cell: &CouplingCell) -> Option<u8>;
+}
+
+impl<F> CouplingRule for F
+where
+    F: Fn(&CouplingCell) -> Option<u8> + Send + Sync,
+{
+    fn apply(&self, cell: &CouplingCell) -> Option<u8> {
+        self(cell)
+    }
+}
+
+/// Surfaces newly exposed underneath or beside a fluid turn into `into`,
+/// like a lake bed becoming wet sand.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WetRevealed {
+    /// Materials that may be repainted.
+    pub onto: MaterialMask,
+    pub into: u8,
+}
+
+impl CouplingRule for WetRevealed {
+    fn apply(&self, cell: &CouplingCell) -> Option<u8> {
+        (self.onto.allows(cell.material_id)
+            && !cell.is_fluid(cell.material_id)
+            && cell.revealed()
+            && (cell.under_fluid() || cell.touches_fluid()))
+        .then_some(self.into)
+    }
+}
+
+/// System running [`FluidCoupling`] rules on chunks whose density changed.
+///
+/// The first change a chunk sees only records its [`FluidCouplingState`].
+#[allow(clippy::type_complexity)]
+pub fn apply_fluid_coupling(
+    mut commands: Commands,
+    coupling: Res<FluidCoupling>,
+    mut chunks: Query<
+        (
+            Entity,
+            &DensityField,
+            &mut MaterialField,
+            Option<&mut FluidCouplingState>,
+        ),
+        Changed<DensityField>,
+    >,
+) {
+    for (entity, density, mut materials, state) in &mut chunks {
+        let current = FluidCouplingState::capture(density);
+        let Some(mut state) = state else {
+            commands.entity(entity).insert(current);
+            continue;
+        };
+
+        let mut changes = Vec::new();
+        for (grid_pos, material_id) in materials.enumerate_coords() {
+            let cell = CouplingCell {
+                grid_pos,
+                material_id,
+                materials: &materials,
+                density,
+                previous: &state,
+                fluids: &coupling.fluids,
+            };
+            if let Some(material_id) = coupling
+                .rules
+                .iter()
+                .find_map(|rule| rule.apply(&cell))
+                .filter(|&material_id| material_id != cell.material_id)
+            {
+                changes.push((cell.grid_pos, material_id));
+            }
+        }
+
+        if !changes.is_empty() {
+            for (pos, material_id) in changes {
+                materials.set(pos.x, pos.y, pos.z, material_id);
+            }
+            commands.entity(entity).insert(MaterialFieldDirty);
+        }
+        *state = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROCK: u8 = 1;
+    const WATER: u8 = 2;
+    const WET_SAND: u8 = 3;
+
+    /// Rock below grid height 16 under a water column.
+    fn lake() -> (DensityField, MaterialField) {
+        let mut density = DensityField::new();
+        let mut materials = MaterialField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    density.set(x, y, z, y as f32 - 15.5);
+                    materials.set(x, y, z, if y < 16 { ROCK } else { WATER });
+                }
+            }
+        }
+        (density, materials)
+    }
+
+    #[test]
+    fn test_sculpting_under_water_wets_revealed_rock() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(
+                FluidCoupling::default()
+                    .with_fluid(WATER)
+                    .with_rule(WetRevealed {
+                        onto: MaterialMask::only(&[ROCK]),
+                        into: WET_SAND,
+                    }),
+            )
+            .add_systems(Update, apply_fluid_coupling);
+
+        let (density, materials) = lake();
+        let chunk = app.world_mut().spawn((density, materials)).id();
+        app.update();
+        assert!(app.world().get::<FluidCouplingState>(chunk).is_some());
+
+        // Dig a pit under the lake
+        let mut density = app.world_mut().get_mut::<DensityField>(chunk).unwrap();
+        density.set(8, 15, 8, 1.0);
+        density.set(8, 14, 8, 1.0);
+        app.update();
+
+        let world = app.world();
+        let materials = world.get::<MaterialField>(chunk).unwrap();
+        assert_eq!(materials.get(8, 13, 8), WET_SAND);
+        assert_eq!(materials.get(7, 14, 8), WET_SAND);
+        // Already exposed before digging
+        assert_eq!(materials.get(7, 15, 8), ROCK);
+        assert_eq!(materials.get(20, 15, 20), ROCK);
+        assert!(world.get::<MaterialFieldDirty>(chunk).is_some());
+    }
+}
@@ -0,0 +1,120 @@
+//! Test patterns for checking a mesher integration.
+//!
+//! When material attributes come out wrong, it is hard to tell from
+//! painted terrain whether voxels map to the wrong vertices, an axis is
+//! flipped or neighbor data is read from the wrong chunk. Filling chunks
+//! with a [`PatternKind`] makes each of those mistakes visible at a glance.
+
+use bevy::prelude::*;
+
+use super::coords;
+use super::storage::MaterialStorage;
+
+/// Pattern painted by [`fill_debug_pattern`].
+///
+/// Patterns use consecutive materials starting at `first`, so point them
+/// at palette layers with contrasting colours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PatternKind {
+    /// Alternates `first` and `first + 1` every voxel along all three axes,
+    /// in world space. Blurred or shifted squares show a voxel-to-vertex
+    /// mapping error; a break at a chunk border shows wrong neighbor data.
+    Checker3D { first: u8 },
+    /// `bands` equal stripes across the chunk along `axis` (0 for X, 1 for
+    /// Y, 2 for Z), from `first` at the low end upwards, showing both the
+    /// axis and its direction.
+    AxisStripes { axis: usize, bands: u8, first: u8 },
+    /// Fills each chunk with one of eight materials from `first`, picked by
+    /// the parity of its position, so any two face neighbors differ and a
+    /// chunk placed at the wrong position stands out.
+    ChunkId { first: u8 },
+}
+
+/// Fills the field of the chunk at `chunk_pos` with a debug pattern.
+///
+/// # Example
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::{MaterialField, PatternKind, fill_debug_pattern};
+///
+/// let mut field = MaterialField::new();
+/// fill_debug_pattern(&mut field, IVec3::ZERO, PatternKind::Checker3D { first: 1 });
+/// assert_eq!(field.get(0, 0, 0), 1);
+/// assert_eq!(field.get(1, 0, 0), 2);
+/// assert_eq!(field.get(1, 1, 0), 1);
+/// ```
+pub fn fill_debug_pattern(
+    field: &mut (impl MaterialStorage + ?Sized),
+    chunk_pos: IVec3,
+    kind: PatternKind,
+) {
+    let size = field.size();
+    let chunk_parity = chunk_pos.rem_euclid(IVec3::splat(2));
+    for z in 0..size.z {
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let pos = uvec3(x, y, z);
+                let material_id = match kind {
+                    PatternKind::Checker3D { first } => {
+                        let voxel = coords::chunk_to_voxel(chunk_pos, pos);
+                        first.wrapping_add(voxel.element_sum().rem_euclid(2) as u8)
+                    }
+                    PatternKind::AxisStripes { axis, bands, first } => {
+                        let axis = axis.min(2);
+                        let band = pos[axis] * bands.max(1) as u32 / size[axis];
+                        first.wrapping_add(band as u8)
+                    }
+                    PatternKind::ChunkId { first } => first.wrapping_add(
+                        (chunk_parity.x + 2 * chunk_parity.y + 4 * chunk_parity.z) as u8,
+                    ),
+                };
+                field.set(pos, material_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_sculpter::field::Field;
+
+    use super::*;
+    use crate::material_field::MaterialField;
+
+    #[test]
+    fn test_debug_patterns() {
+        // The checker continues across the border with the -X neighbor
+        let mut left = MaterialField::new();
+        let mut right = MaterialField::new();
+        fill_debug_pattern(
+            &mut left,
+            ivec3(-1, 0, 0),
+            PatternKind::Checker3D { first: 3 },
+        );
+        fill_debug_pattern(&mut right, IVec3::ZERO, PatternKind::Checker3D { first: 3 });
+        assert_ne!(left.get(31, 5, 5), right.get(0, 5, 5));
+
+        let mut field = MaterialField::new();
+        let stripes = PatternKind::AxisStripes {
+            axis: 1,
+            bands: 4,
+            first: 2,
+        };
+        fill_debug_pattern(&mut field, IVec3::ZERO, stripes);
+        assert_eq!(field.get(20, 0, 3), 2);
+        assert_eq!(field.get(20, 8, 3), 3);
+        assert_eq!(field.get(0, 31, 30), 5);
+
+        // Face neighbors along every axis get different materials
+        let chunk_id = |chunk_pos: IVec3| {
+            let mut field = MaterialField::new();
+            fill_debug_pattern(&mut field, chunk_pos, PatternKind::ChunkId { first: 1 });
+            assert!(field.iter().all(|&id| id == field.get(0, 0, 0)));
+            field.get(0, 0, 0)
+        };
+        let center = chunk_id(ivec3(-3, 0, 2));
+        for face in [IVec3::X, IVec3::NEG_Y, IVec3::Z] {
+            assert_ne!(chunk_id(ivec3(-3, 0, 2) + face), center);
+        }
+    }
+}
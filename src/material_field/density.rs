@@ -0,0 +1,69 @@
+//! Storage-agnostic access to chunk density.
+
+use bevy::prelude::*;
+#[cfg(feature = "bevy-sculpter")]
+use bevy_sculpter::{field::Field, prelude::DensityField};
+
+/// Signed density on a chunk's voxel grid, negative inside solid ground.
+///
+/// Blending and surface-aware brush filters read density through this
+/// trait, so an SDF, heightmap or other representation can drive them
+/// instead of a bevy_sculpter `DensityField`. The trait itself doesn't
+/// depend on bevy_sculpter.
+///
+/// Samples past the chunk edge come from
+/// [`neighbor_density`](Self::neighbor_density) when the source can
+/// evaluate them itself, and otherwise from the neighbor data passed to
+/// [`VertexMaterialComputer::with_neighbors`](super::VertexMaterialComputer::with_neighbors).
+///
+/// # Example
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::DensitySource;
+///
+/// /// Flat ground at a fixed height.
+/// struct Plane(f32);
+///
+/// impl DensitySource for Plane {
+///     fn size(&self) -> UVec3 {
+///         UVec3::splat(32)
+///     }
+///
+///     fn density(&self, pos: IVec3) -> Option<f32> {
+///         let inside = pos.cmpge(IVec3::ZERO).all() && pos.cmplt(IVec3::splat(32)).all();
+///         inside.then(|| pos.y as f32 - self.0)
+///     }
+/// }
+///
+/// assert_eq!(Plane(8.0).density(ivec3(0, 4, 0)), Some(-4.0));
+/// ```
+pub trait DensitySource {
+    /// Voxels along each axis.
+    fn size(&self) -> UVec3;
+
+    /// Density at grid coordinates, `None` outside the chunk.
+    fn density(&self, pos: IVec3) -> Option<f32>;
+
+    /// Density at grid coordinates outside the chunk, `None` where the
+    /// source has no data.
+    ///
+    /// Sources defined everywhere, like an analytic SDF, override this so
+    /// blending stays seamless across chunk edges without neighbor fields.
+    /// The default has no data outside the chunk.
+    fn neighbor_density(&self, pos: IVec3) -> Option<f32> {
+        let _ = pos;
+        None
+    }
+}
+
+#[cfg(feature = "bevy-sculpter")]
+impl DensitySource for DensityField {
+    fn size(&self) -> UVec3 {
+        DensityField::SIZE
+    }
+
+    #[inline]
+    fn density(&self, pos: IVec3) -> Option<f32> {
+        self.get_ivec3(pos)
+    }
+}
@@ -0,0 +1,84 @@
+//! Painter cost in Bevy's diagnostics.
+//!
+//! Remeshing, neighbor slicing, paint strokes and triplanar bind group
+//! preparation always run inside `tracing` spans, which show up in Tracy
+//! with Bevy's `trace_tracy` feature. [`PainterDiagnosticsPlugin`] adds
+//! per-frame totals as [`Diagnostic`]s, e.g. for `LogDiagnosticsPlugin`
+//! or an on-screen overlay.
+
+use std::time::Duration;
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+
+/// Painter work done so far this frame, reset when reported.
+///
+/// Filled while the resource exists, which [`PainterDiagnosticsPlugin`]
+/// ensures.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PainterFrameStats {
+    /// Chunks marked dirty when attributes were remeshed.
+    pub dirty_chunks: usize,
+    /// Chunks whose attributes were recomputed on the CPU.
+    pub remeshed_chunks: usize,
+    /// Vertices whose material attributes were recomputed on the CPU.
+    pub vertices_processed: usize,
+    /// Time spent in CPU attribute remeshing.
+    pub remesh_time: Duration,
+}
+
+/// Plugin reporting [`PainterFrameStats`] as [`Diagnostic`]s.
+///
+/// # Example
+/// ```ignore
+/// app.add_plugins((
+///     PainterDiagnosticsPlugin,
+///     LogDiagnosticsPlugin::filtered(vec![PainterDiagnosticsPlugin::REMESH_TIME]),
+/// ));
+/// ```
+pub struct PainterDiagnosticsPlugin;
+
+impl PainterDiagnosticsPlugin {
+    /// Chunks waiting for remeshing per frame.
+    pub const DIRTY_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("painter/dirty_chunks");
+    /// Chunks remeshed on the CPU per frame.
+    pub const REMESHED_CHUNKS: DiagnosticPath =
+        DiagnosticPath::const_new("painter/remeshed_chunks");
+    /// Vertices given new material attributes per frame.
+    pub const VERTICES_PROCESSED: DiagnosticPath =
+        DiagnosticPath::const_new("painter/vertices_processed");
+    /// Milliseconds of CPU attribute remeshing per frame.
+    pub const REMESH_TIME: DiagnosticPath = DiagnosticPath::const_new("painter/remesh_time");
+}
+
+impl Plugin for PainterDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PainterFrameStats>()
+            .register_diagnostic(Diagnostic::new(Self::DIRTY_CHUNKS))
+            .register_diagnostic(Diagnostic::new(Self::REMESHED_CHUNKS))
+            .register_diagnostic(Diagnostic::new(Self::VERTICES_PROCESSED))
+            .register_diagnostic(Diagnostic::new(Self::REMESH_TIME).with_suffix("ms"))
+            .add_systems(Last, report_painter_diagnostics);
+    }
+}
+
+/// System adding this frame's [`PainterFrameStats`] to the diagnostics and
+/// resetting them.
+pub fn report_painter_diagnostics(
+    mut diagnostics: Diagnostics,
+    mut stats: ResMut<PainterFrameStats>,
+) {
+    let frame = std::mem::take(&mut *stats);
+    diagnostics.add_measurement(&PainterDiagnosticsPlugin::DIRTY_CHUNKS, || {
+        frame.dirty_chunks as f64
+    });
+    diagnostics.add_measurement(&PainterDiagnosticsPlugin::REMESHED_CHUNKS, || {
+        frame.remeshed_chunks as f64
+    });
+    diagnostics.add_measurement(&PainterDiagnosticsPlugin::VERTICES_PROCESSED, || {
+        frame.vertices_processed as f64
+    });
+    diagnostics.add_measurement(&PainterDiagnosticsPlugin::REMESH_TIME, || {
+        frame.remesh_time.as_secs_f64() * 1000.0
+    });
+}
@@ -0,0 +1,216 @@
+//! Two-material voxel storage for gradual strata.
+
+use bevy::prelude::*;
+
+use super::field::{FIELD_SIZE, FIELD_VOLUME, MaterialField};
+use super::storage::MaterialStorage;
+
+/// Material field storing two materials and a blend per voxel.
+///
+/// A [`MaterialField`] voxel holds one material, so blending can only
+/// fade between voxels, over about one voxel. Here each voxel mixes a
+/// primary and a secondary material, so an ore vein can thin out into the
+/// surrounding stone over many voxels. [`VertexMaterialComputer`](super::VertexMaterialComputer)
+/// splits each voxel's weight between its two materials.
+///
+/// Through [`MaterialStorage`], a voxel reads as its primary material,
+/// which always has at least half the voxel, and writing one replaces the
+/// mix. Brushes therefore paint solid materials over mixed voxels.
+/// Automatic remeshing reads [`MaterialField`]s only, so compute
+/// attributes for chunks with this storage in your own meshing system.
+///
+/// # Example
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::{MaterialFieldDual, MaterialStorage};
+///
+/// const STONE: u8 = 1;
+/// const ORE: u8 = 5;
+///
+/// let mut field = MaterialFieldDual::filled(STONE);
+/// // Vein fading out over five voxels
+/// for (x, share) in [(10, 1.0), (11, 0.8), (12, 0.6), (13, 0.4), (14, 0.2)] {
+///     field.set_mix(uvec3(x, 16, 16), STONE, ORE, share);
+/// }
+///
+/// assert_eq!(field.get(uvec3(11, 16, 16)), ORE);
+/// assert_eq!(field.mix(uvec3(14, 16, 16)), (STONE, ORE, 0.2));
+/// ```
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct MaterialFieldDual {
+    /// Primary material, secondary material and secondary share (0-255)
+    /// per voxel, in X-Y-Z order.
+    voxels: Vec<[u8; 3]>,
+}
+
+impl Default for MaterialFieldDual {
+    fn default() -> Self {
+        Self::filled(0)
+    }
+}
+
+impl MaterialFieldDual {
+    /// Creates a field with all voxels set to material 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a field with all voxels set to `material_id`.
+    pub fn filled(material_id: u8) -> Self {
+        Self {
+            voxels: vec![[material_id, material_id, 0]; FIELD_VOLUME],
+        }
+    }
+
+    #[inline]
+    fn index(pos: UVec3) -> Option<usize> {
+        pos.cmplt(FIELD_SIZE)
+            .all()
+            .then(|| (pos.x + pos.y * FIELD_SIZE.x + pos.z * FIELD_SIZE.x * FIELD_SIZE.y) as usize)
+    }
+
+    /// Mixes `material_b` into `material_a` at `pos`, `share` being
+    /// `material_b`'s part of the voxel in `0.0..=1.0`.
+    ///
+    /// Stored with the larger part as primary, quantized to 1/255.
+    /// Out-of-bounds positions are ignored.
+    pub fn set_mix(&mut self, pos: UVec3, material_a: u8, material_b: u8, share: f32) {
+        let Some(index) = Self::index(pos) else {
+            return;
+        };
+        let share = share.clamp(0.0, 1.0);
+        let (primary, secondary, share) = if share > 0.5 {
+            (material_b, material_a, 1.0 - share)
+        } else {
+            (material_a, material_b, share)
+        };
+        let blend = if primary == secondary {
+            0
+        } else {
+            (share * 255.0).round() as u8
+        };
+        self.voxels[index] = [primary, secondary, blend];
+    }
+
+    /// Primary material, secondary material and the secondary's share of
+    /// the voxel at `pos`, or material 0 out of bounds.
+    pub fn mix(&self, pos: UVec3) -> (u8, u8, f32) {
+        let Some(index) = Self::index(pos) else {
+            return (0, 0, 0.0);
+        };
+        let [primary, secondary, blend] = self.voxels[index];
+        (primary, secondary, blend as f32 / 255.0)
+    }
+
+    /// Dense single-material copy holding each voxel's primary material.
+    pub fn to_material_field(&self) -> MaterialField {
+        MaterialField(self.voxels.iter().map(|[primary, ..]| *primary).collect())
+    }
+}
+
+impl From<&MaterialField> for MaterialFieldDual {
+    fn from(field: &MaterialField) -> Self {
+        Self {
+            voxels: field.0.iter().map(|&id| [id, id, 0]).collect(),
+        }
+    }
+}
+
+impl MaterialStorage for MaterialFieldDual {
+    fn size(&self) -> UVec3 {
+        FIELD_SIZE
+    }
+
+    #[inline]
+    fn get(&self, pos: UVec3) -> u8 {
+        Self::index(pos).map_or(0, |index| self.voxels[index][0])
+    }
+
+    #[inline]
+    fn set(&mut self, pos: UVec3, material_id: u8) {
+        if let Some(index) = Self::index(pos) {
+            self.voxels[index] = [material_id, material_id, 0];
+        }
+    }
+
+    #[inline]
+    fn secondary(&self, pos: UVec3) -> Option<(u8, f32)> {
+        let [_, secondary, blend] = self.voxels[Self::index(pos)?];
+        (blend > 0).then(|| (secondary, blend as f32 / 255.0))
+    }
+
+    #[inline]
+    fn set_mix(&mut self, pos: UVec3, material_a: u8, material_b: u8, share: f32) {
+        MaterialFieldDual::set_mix(self, pos, material_a, material_b, share);
+    }
+
+    fn uniform_region(&self, min: UVec3, max: UVec3) -> Option<u8> {
+        if min.cmpge(max).any() || max.cmpgt(FIELD_SIZE).any() {
+            return None;
+        }
+        let first = self.get(min);
+        for z in min.z..max.z {
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let [primary, _, blend] = self.voxels[Self::index(uvec3(x, y, z))?];
+                    if primary != first || blend > 0 {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_sculpter::field::Field;
+    use bevy_sculpter::prelude::DensityField;
+
+    use super::*;
+    use crate::material_field::{MaterialBlendSettings, VertexMaterialComputer};
+
+    #[test]
+    fn test_mix_keeps_larger_part_primary() {
+        let mut field = MaterialFieldDual::filled(1);
+        let pos = uvec3(3, 4, 5);
+        field.set_mix(pos, 1, 7, 0.8);
+        let (primary, secondary, share) = field.mix(pos);
+        assert_eq!((primary, secondary), (7, 1));
+        assert!((share - 0.2).abs() < 1.0 / 255.0);
+        assert_eq!(field.uniform_region(UVec3::ZERO, FIELD_SIZE), None);
+
+        field.set(pos, 2);
+        assert_eq!(field.mix(pos), (2, 2, 0.0));
+        assert_eq!(field.secondary(pos), None);
+        assert_eq!(field.to_material_field().get(3, 4, 5), 2);
+    }
+
+    #[test]
+    fn test_vertices_blend_both_materials_of_a_voxel() {
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    density.set(x, y, z, -1.0);
+                }
+            }
+        }
+        let mut field = MaterialFieldDual::filled(1);
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    field.set_mix(uvec3(x, y, z), 1, 5, 0.4);
+                }
+            }
+        }
+
+        let settings = MaterialBlendSettings::default();
+        let data = VertexMaterialComputer::new(&density, &field, FIELD_SIZE.as_vec3(), &settings)
+            .compute(vec3(16.5, 16.5, 16.5));
+        assert_eq!(&data.ids[..2], &[1, 5]);
+        assert!(data.weights[0].abs_diff(153) <= 1);
+        assert!(data.weights[1].abs_diff(102) <= 1);
+    }
+}
@@ -67,6 +67,94 @@ impl MaterialField {
         Self(vec![material_id; FIELD_VOLUME])
     }
 
+    /// Sets every voxel to the given material ID.
+    pub fn fill(&mut self, material_id: u8) {
+        self.0.fill(material_id);
+    }
+
+    /// Returns the material if every voxel holds the same one.
+    ///
+    /// Solid underground chunks are almost always uniform, and blending
+    /// skips its per-vertex lookups for them. The storage is public, so
+    /// this scans instead of trusting a cached flag; mixed fields usually
+    /// bail out within the first few voxels.
+    pub fn is_uniform(&self) -> Option<u8> {
+        let (&first, rest) = self.0.split_first()?;
+        rest.iter().all(|&m| m == first).then_some(first)
+    }
+
+    // =========================================================================
+    // Bulk access
+    // =========================================================================
+
+    /// Returns the raw material IDs in X-Y-Z order (X varies fastest).
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the raw material IDs mutably in X-Y-Z order (X varies fastest).
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+
+    /// Iterates over all material IDs in storage order.
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, u8> {
+        self.0.iter()
+    }
+
+    /// Iterates mutably over all material IDs in storage order.
+    #[inline]
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, u8> {
+        self.0.iter_mut()
+    }
+
+    /// Iterates over `(coordinate, material)` pairs without per-voxel bounds checks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_painter::material_field::MaterialField;
+    ///
+    /// let mut field = MaterialField::new();
+    /// field.set(1, 2, 3, 7);
+    ///
+    /// let painted: Vec<UVec3> = field
+    ///     .enumerate_coords()
+    ///     .filter(|(_, material)| *material == 7)
+    ///     .map(|(pos, _)| pos)
+    ///     .collect();
+    /// assert_eq!(painted, vec![uvec3(1, 2, 3)]);
+    /// ```
+    pub fn enumerate_coords(&self) -> impl Iterator<Item = (UVec3, u8)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, &material)| (Self::coord_of(index), material))
+    }
+
+    /// Iterates over `(coordinate, &mut material)` pairs without per-voxel bounds checks.
+    pub fn enumerate_coords_mut(&mut self) -> impl Iterator<Item = (UVec3, &mut u8)> + '_ {
+        self.0
+            .iter_mut()
+            .enumerate()
+            .map(|(index, material)| (Self::coord_of(index), material))
+    }
+
+    /// Converts a storage index back to grid coordinates.
+    #[inline]
+    fn coord_of(index: usize) -> UVec3 {
+        let index = index as u32;
+        uvec3(
+            index % FIELD_SIZE.x,
+            (index / FIELD_SIZE.x) % FIELD_SIZE.y,
+            index / (FIELD_SIZE.x * FIELD_SIZE.y),
+        )
+    }
+
     // =========================================================================
     // Material-specific convenience methods
     // =========================================================================
@@ -94,13 +182,12 @@ impl MaterialField {
     /// # Arguments
     /// * `layers` - Slice of (max_height, material_id) pairs, processed bottom to top
     pub fn paint_height_layers(&mut self, layers: &[(u32, u8)]) {
-        for pos in Self::positions() {
-            let material = layers
+        for (pos, material) in self.enumerate_coords_mut() {
+            *material = layers
                 .iter()
                 .find(|(max_y, _)| pos.y < *max_y)
                 .map(|(_, mat)| *mat)
                 .unwrap_or(0);
-            self.set(pos.x, pos.y, pos.z, material);
         }
     }
 
@@ -112,10 +199,230 @@ impl MaterialField {
     where
         F: Fn(UVec3) -> u8,
     {
-        for pos in Self::positions() {
-            self.set(pos.x, pos.y, pos.z, sampler(pos));
+        for (pos, material) in self.enumerate_coords_mut() {
+            *material = sampler(pos);
         }
     }
+
+    // =========================================================================
+    // Resolution changes
+    // =========================================================================
+
+    /// Downsamples the field by `factor` using a majority vote per block.
+    ///
+    /// The coarse data fills the low corner of the result (`FIELD_SIZE / factor`
+    /// voxels per axis); the rest is material 0. Use
+    /// [`MaterialField::downsample_into`] to place it elsewhere, e.g. when
+    /// merging 2×2×2 chunks into one far-LOD chunk.
+    ///
+    /// # Panics
+    /// Panics if `factor` is zero or doesn't divide [`FIELD_SIZE`].
+    pub fn downsample(&self, factor: u32) -> MaterialField {
+        let mut coarse = MaterialField::new();
+        self.downsample_into(factor, &mut coarse, UVec3::ZERO);
+        coarse
+    }
+
+    /// Downsamples the field by `factor` and writes the result into `target`
+    /// starting at `offset`.
+    ///
+    /// Each output voxel takes the most common material of its
+    /// `factor³` source block; ties resolve to the lowest ID. Voxels that
+    /// land outside `target` are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_painter::material_field::MaterialField;
+    ///
+    /// // Merge eight children into one parent at half resolution
+    /// let children = [(); 8].map(|_| MaterialField::filled(2));
+    /// let mut parent = MaterialField::new();
+    /// for (i, child) in children.iter().enumerate() {
+    ///     let octant = uvec3(i as u32 & 1, (i as u32 >> 1) & 1, i as u32 >> 2);
+    ///     child.downsample_into(2, &mut parent, octant * 16);
+    /// }
+    /// assert_eq!(parent.get(31, 31, 31), 2);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `factor` is zero or doesn't divide [`FIELD_SIZE`].
+    pub fn downsample_into(&self, factor: u32, target: &mut MaterialField, offset: UVec3) {
+        let coarse = Self::coarse_size(factor);
+        let mut counts = [0u32; 256];
+        let mut block = Vec::with_capacity((factor * factor * factor) as usize);
+
+        for z in 0..coarse.z {
+            for y in 0..coarse.y {
+                for x in 0..coarse.x {
+                    let base = uvec3(x, y, z) * factor;
+                    block.clear();
+                    for dz in 0..factor {
+                        for dy in 0..factor {
+                            for dx in 0..factor {
+                                let pos = base + uvec3(dx, dy, dz);
+                                block.push(self.0[Self::index_of(pos)]);
+                            }
+                        }
+                    }
+
+                    let out = offset + uvec3(x, y, z);
+                    target.set(out.x, out.y, out.z, majority(&block, &mut counts));
+                }
+            }
+        }
+    }
+
+    /// Upsamples the low-corner region by `factor`, inverting
+    /// [`MaterialField::downsample`].
+    ///
+    /// # Panics
+    /// Panics if `factor` is zero or doesn't divide [`FIELD_SIZE`].
+    pub fn upsample(&self, factor: u32) -> MaterialField {
+        self.upsample_region(factor, UVec3::ZERO)
+    }
+
+    /// Upsamples the `FIELD_SIZE / factor` region starting at `offset` to a
+    /// full-resolution field, e.g. when splitting a far-LOD chunk back into
+    /// its children.
+    ///
+    /// Each source voxel is repeated as a `factor³` block. Source voxels
+    /// outside the field read as material 0.
+    ///
+    /// # Panics
+    /// Panics if `factor` is zero or doesn't divide [`FIELD_SIZE`].
+    pub fn upsample_region(&self, factor: u32, offset: UVec3) -> MaterialField {
+        Self::coarse_size(factor);
+        let mut fine = MaterialField::new();
+        for (pos, material) in fine.enumerate_coords_mut() {
+            let source = offset + pos / factor;
+            *material = self.get(source.x, source.y, source.z);
+        }
+        fine
+    }
+
+    /// Coarse grid size for a resampling factor.
+    fn coarse_size(factor: u32) -> UVec3 {
+        assert!(
+            factor > 0 && (FIELD_SIZE % factor) == UVec3::ZERO,
+            "Resampling factor ({}) must divide the field size ({})",
+            factor,
+            FIELD_SIZE
+        );
+        FIELD_SIZE / factor
+    }
+
+    /// Storage index of in-bounds grid coordinates.
+    #[inline]
+    fn index_of(pos: UVec3) -> usize {
+        (pos.x + pos.y * FIELD_SIZE.x + pos.z * FIELD_SIZE.x * FIELD_SIZE.y) as usize
+    }
+
+    // =========================================================================
+    // Statistics
+    // =========================================================================
+
+    /// Counts how many voxels use each material ID.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy_painter::material_field::{FIELD_VOLUME, MaterialField};
+    ///
+    /// let field = MaterialField::filled(3);
+    /// assert_eq!(field.histogram()[3] as usize, FIELD_VOLUME);
+    /// ```
+    pub fn histogram(&self) -> [u32; 256] {
+        let mut counts = [0u32; 256];
+        for &material in &self.0 {
+            counts[material as usize] += 1;
+        }
+        counts
+    }
+
+    /// Returns the most common material ID.
+    ///
+    /// Ties resolve to the lowest ID.
+    pub fn dominant_material(&self) -> u8 {
+        let counts = self.histogram();
+        let mut dominant = 0;
+        for (material, &count) in counts.iter().enumerate() {
+            if count > counts[dominant] {
+                dominant = material;
+            }
+        }
+        dominant as u8
+    }
+
+    /// Returns true if any voxel uses the given material ID.
+    pub fn contains(&self, material_id: u8) -> bool {
+        self.0.contains(&material_id)
+    }
+
+    /// Returns the fraction of voxels (0.0 to 1.0) that use the given material ID.
+    ///
+    /// Useful for gameplay rules such as "chunk is 80% corrupted".
+    pub fn coverage(&self, material_id: u8) -> f32 {
+        if self.0.is_empty() {
+            return 0.0;
+        }
+        let count = self.0.iter().filter(|&&m| m == material_id).count();
+        count as f32 / self.0.len() as f32
+    }
+}
+
+/// Most common value in `block`, lowest ID on ties.
+///
+/// `counts` must be all zero on entry and is left all zero on return.
+fn majority(block: &[u8], counts: &mut [u32; 256]) -> u8 {
+    for &material in block {
+        counts[material as usize] += 1;
+    }
+
+    let mut best = 0u8;
+    let mut best_count = 0;
+    for &material in block {
+        let count = counts[material as usize];
+        if count > best_count || (count == best_count && material < best) {
+            best = material;
+            best_count = count;
+        }
+    }
+
+    for &material in block {
+        counts[material as usize] = 0;
+    }
+    best
+}
+
+#[cfg(feature = "rayon")]
+impl MaterialField {
+    /// Parallel iterator over all material IDs in storage order.
+    pub fn par_iter(&self) -> rayon::slice::Iter<'_, u8> {
+        use rayon::prelude::*;
+        self.0.par_iter()
+    }
+
+    /// Parallel mutable iterator over all material IDs in storage order.
+    pub fn par_iter_mut(&mut self) -> rayon::slice::IterMut<'_, u8> {
+        use rayon::prelude::*;
+        self.0.par_iter_mut()
+    }
+
+    /// Paints materials in parallel from a sampling function.
+    ///
+    /// Parallel counterpart of [`MaterialField::paint_with`].
+    pub fn par_paint_with<F>(&mut self, sampler: F)
+    where
+        F: Fn(UVec3) -> u8 + Sync,
+    {
+        use rayon::prelude::*;
+        self.0
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, material)| *material = sampler(Self::coord_of(index)));
+    }
 }
 
 /// Marker component indicating this chunk's material field needs processing.
@@ -140,6 +447,16 @@ mod tests {
         assert!(field.0.iter().all(|&m| m == 5));
     }
 
+    #[test]
+    fn test_is_uniform() {
+        let mut field = MaterialField::filled(5);
+        assert_eq!(field.is_uniform(), Some(5));
+        field.set(31, 31, 31, 2);
+        assert_eq!(field.is_uniform(), None);
+        field.fill(7);
+        assert_eq!(field.is_uniform(), Some(7));
+    }
+
     #[test]
     fn test_get_set() {
         let mut field = MaterialField::new();
@@ -166,6 +483,104 @@ mod tests {
         assert_eq!(field.get(16, 16, 20), 0);
     }
 
+    #[test]
+    fn test_statistics() {
+        let mut field = MaterialField::filled(1);
+        field.paint_with(|pos| if pos.x < 8 { 4 } else { 1 });
+
+        let counts = field.histogram();
+        assert_eq!(counts[4] as usize, FIELD_VOLUME / 4);
+        assert_eq!(counts[1] as usize, FIELD_VOLUME * 3 / 4);
+        assert_eq!(field.dominant_material(), 1);
+        assert!(field.contains(4));
+        assert!(!field.contains(2));
+        assert_eq!(field.coverage(4), 0.25);
+    }
+
+    #[test]
+    fn test_dominant_material_tie() {
+        let mut field = MaterialField::new();
+        field.paint_with(|pos| if pos.x < 16 { 9 } else { 2 });
+        assert_eq!(field.dominant_material(), 2);
+    }
+
+    #[test]
+    fn test_enumerate_coords_matches_get() {
+        let mut field = MaterialField::new();
+        field.paint_with(|pos| (pos.x + pos.y * 3 + pos.z * 7) as u8);
+
+        for (pos, material) in field.enumerate_coords() {
+            assert_eq!(field.get(pos.x, pos.y, pos.z), material);
+        }
+        assert_eq!(field.enumerate_coords().count(), FIELD_VOLUME);
+    }
+
+    #[test]
+    fn test_enumerate_coords_mut() {
+        let mut field = MaterialField::new();
+        for (pos, material) in field.enumerate_coords_mut() {
+            if pos.y == 31 {
+                *material = 3;
+            }
+        }
+        assert_eq!(field.get(5, 31, 9), 3);
+        assert_eq!(field.get(5, 30, 9), 0);
+
+        field.iter_mut().for_each(|m| *m += 1);
+        assert_eq!(field.as_slice()[0], 1);
+    }
+
+    #[test]
+    fn test_downsample_majority() {
+        let mut field = MaterialField::new();
+        // In each 2x2x2 block, 5 of 8 voxels are material 3
+        field.paint_with(|pos| {
+            if pos.x % 2 == 0 || (pos.y % 2 == 0 && pos.z % 2 == 0) {
+                3
+            } else {
+                1
+            }
+        });
+
+        let coarse = field.downsample(2);
+        assert_eq!(coarse.get(0, 0, 0), 3);
+        assert_eq!(coarse.get(15, 15, 15), 3);
+        assert_eq!(coarse.get(16, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_majority_tie_lowest() {
+        let mut counts = [0u32; 256];
+        assert_eq!(majority(&[9, 4, 9, 4], &mut counts), 4);
+        assert!(counts.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_downsample_upsample_roundtrip() {
+        let mut field = MaterialField::new();
+        field.paint_with(|pos| (pos.x / 4 + pos.z / 4) as u8);
+
+        let roundtrip = field.downsample(4).upsample(4);
+        assert_eq!(roundtrip.as_slice(), field.as_slice());
+    }
+
+    #[test]
+    fn test_upsample_region_splits_octant() {
+        let mut parent = MaterialField::new();
+        parent.set(16, 0, 0, 6);
+
+        let child = parent.upsample_region(2, uvec3(16, 0, 0));
+        assert_eq!(child.get(0, 0, 0), 6);
+        assert_eq!(child.get(1, 1, 1), 6);
+        assert_eq!(child.get(2, 0, 0), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_factor() {
+        MaterialField::new().downsample(3);
+    }
+
     #[test]
     fn test_iter() {
         let field = MaterialField::new();
@@ -0,0 +1,207 @@
+//! Material generation off the main thread.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, futures::check_ready};
+use chunky_bevy::prelude::ChunkPos;
+
+use super::compress::CompressedMaterialField;
+use super::field::{MaterialField, MaterialFieldDirty};
+
+/// Material for a voxel, given its chunk position and grid coordinates.
+pub type MaterialGenerator = Arc<dyn Fn(IVec3, UVec3) -> u8 + Send + Sync>;
+
+impl MaterialField {
+    /// Builds a field from `sampler` on the [`AsyncComputeTaskPool`].
+    ///
+    /// Same as [`MaterialField::paint_with`] on a new field, without
+    /// blocking the caller. Poll the task with
+    /// [`check_ready`](bevy::tasks::futures::check_ready).
+    pub fn generate_async<F>(sampler: F) -> Task<MaterialField>
+    where
+        F: Fn(UVec3) -> u8 + Send + 'static,
+    {
+        AsyncComputeTaskPool::get().spawn(async move {
+            let mut field = MaterialField::new();
+            field.paint_with(sampler);
+            field
+        })
+    }
+}
+
+/// Generates materials for new chunks in the background.
+///
+/// With this resource present, every entity that gets a [`ChunkPos`]
+/// without a [`MaterialField`] has its field generated on the
+/// [`AsyncComputeTaskPool`]. The field is inserted with
+/// [`MaterialFieldDirty`] once ready. Chunks that need materials on the
+/// frame they spawn, like the ones around the camera, can insert their
+/// field directly and skip the queue.
+///
+/// # Example
+/// ```ignore
+/// commands.insert_resource(MaterialGeneration::new(|chunk_pos, local| {
+///     let height = chunk_pos.y * 32 + local.y as i32;
+///     if height < 12 { STONE } else { DIRT }
+/// }));
+/// ```
+#[derive(Resource, Clone)]
+pub struct MaterialGeneration {
+    pub generator: MaterialGenerator,
+    /// Tasks started per frame; the rest wait for later frames.
+    /// Default: 16
+    pub max_spawns_per_frame: usize,
+}
+
+impl MaterialGeneration {
+    /// Generates fields with `generator`.
+    pub fn new(generator: impl Fn(IVec3, UVec3) -> u8 + Send + Sync + 'static) -> Self {
+        Self {
+            generator: Arc::new(generator),
+            max_spawns_per_frame: 16,
+        }
+    }
+
+    /// Set the number of tasks started per frame.
+    pub fn with_max_spawns_per_frame(mut self, max_spawns_per_frame: usize) -> Self {
+        self.max_spawns_per_frame = max_spawns_per_frame;
+        self
+    }
+}
+
+/// In-flight [`MaterialGeneration`] task for a chunk.
+///
+/// Removing the component or despawning the chunk cancels the task.
+#[derive(Component)]
+pub struct PendingMaterialField(pub Task<MaterialField>);
+
+/// System starting [`MaterialGeneration`] tasks for chunks without a
+/// [`MaterialField`].
+///
+/// Chunks holding a [`CompressedMaterialField`] already have materials and
+/// are skipped.
+#[allow(clippy::type_complexity)]
+pub fn spawn_material_generation(
+    mut commands: Commands,
+    generation: Res<MaterialGeneration>,
+    chunks: Query<
+        (Entity, &ChunkPos),
+        (
+            Without<MaterialField>,
+            Without<CompressedMaterialField>,
+            Without<PendingMaterialField>,
+        ),
+    >,
+) {
+    for (entity, chunk_pos) in chunks.iter().take(generation.max_spawns_per_frame) {
+        let generator = generation.generator.clone();
+        let chunk_pos = chunk_pos.0;
+        let task = MaterialField::generate_async(move |local| generator(chunk_pos, local));
+        commands.entity(entity).insert(PendingMaterialField(task));
+    }
+}
+
+/// System inserting finished [`MaterialGeneration`] fields.
+pub fn apply_generated_materials(
+    mut commands: Commands,
+    mut pending: Query<(Entity, &mut PendingMaterialField)>,
+) {
+    for (entity, mut task) in &mut pending {
+        if let Some(field) = check_ready(&mut task.0) {
+            commands
+                .entity(entity)
+                .remove::<PendingMaterialField>()
+                .insert((field, MaterialFieldDirty));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::block_on;
+
+    use super::*;
+    use crate::material_field::compress::{FieldCompression, update_field_compression};
+
+    #[test]
+    fn test_generate_async_matches_paint_with() {
+        AsyncComputeTaskPool::get_or_init(Default::default);
+        let sampler = |pos: UVec3| (pos.x + pos.y) as u8 % 4;
+        let mut expected = MaterialField::new();
+        expected.paint_with(sampler);
+        let field = block_on(MaterialField::generate_async(sampler));
+        assert_eq!(field.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_generation_fills_new_chunks() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(
+                MaterialGeneration::new(|chunk_pos, _| chunk_pos.x as u8)
+                    .with_max_spawns_per_frame(1),
+            )
+            .add_systems(
+                Update,
+                (spawn_material_generation, apply_generated_materials).chain(),
+            );
+        let first = app.world_mut().spawn(ChunkPos(ivec3(3, 0, 0))).id();
+        let second = app.world_mut().spawn(ChunkPos(ivec3(5, 0, 0))).id();
+
+        // One task per frame; results land on a later frame
+        for _ in 0..100 {
+            app.update();
+            let world = app.world();
+            if world.get::<MaterialField>(first).is_some()
+                && world.get::<MaterialField>(second).is_some()
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let world = app.world();
+        assert_eq!(world.get::<MaterialField>(first).unwrap().coverage(3), 1.0);
+        assert_eq!(world.get::<MaterialField>(second).unwrap().coverage(5), 1.0);
+        assert!(world.get::<MaterialFieldDirty>(first).is_some());
+        assert!(world.get::<PendingMaterialField>(second).is_none());
+    }
+
+    #[test]
+    fn test_generation_skips_compressed_chunks() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(FieldCompression::new(100.0))
+            .insert_resource(MaterialGeneration::new(|_, _| 1))
+            .add_systems(
+                Update,
+                (
+                    update_field_compression,
+                    spawn_material_generation,
+                    apply_generated_materials,
+                )
+                    .chain(),
+            );
+        app.world_mut()
+            .spawn((Camera::default(), GlobalTransform::IDENTITY));
+        let far = app
+            .world_mut()
+            .spawn((
+                ChunkPos(ivec3(20, 0, 0)),
+                GlobalTransform::from_translation(vec3(500.0, 0.0, 0.0)),
+                MaterialField::filled(3),
+            ))
+            .id();
+
+        // Compress, then give generation a few frames to pick the chunk up
+        for _ in 0..3 {
+            app.update();
+        }
+
+        let world = app.world();
+        assert!(world.get::<CompressedMaterialField>(far).is_some());
+        assert!(world.get::<PendingMaterialField>(far).is_none());
+        assert!(world.get::<MaterialField>(far).is_none());
+    }
+}
@@ -0,0 +1,79 @@
+//! Chunk lifecycle events for coordinating dependent systems.
+
+use bevy::prelude::*;
+
+use super::compress::CompressedMaterialField;
+use super::field::MaterialField;
+
+/// Triggered when a chunk's mesh has up-to-date material attributes.
+///
+/// Fired by [`remesh_dirty_chunks`](super::remesh_dirty_chunks) after
+/// blending, and by GPU meshing when a chunk is handed to the GPU, whose
+/// mesh follows on the next render. Use it to rebuild navmeshes or
+/// scatter grass once painting is visible.
+///
+/// # Example
+/// ```ignore
+/// app.add_observer(|ready: On<OnChunkMaterialReady>, mut navmesh: ResMut<NavMesh>| {
+///     navmesh.mark_dirty(ready.entity);
+/// });
+/// ```
+#[derive(EntityEvent, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OnChunkMaterialReady {
+    pub entity: Entity,
+}
+
+/// Triggered when a chunk with a [`MaterialField`] or
+/// [`CompressedMaterialField`] is despawned.
+///
+/// The chunk is already gone when observers run, so only its entity ID is
+/// available.
+#[derive(EntityEvent, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OnChunkMaterialUnloaded {
+    pub entity: Entity,
+}
+
+/// Observer triggering [`OnChunkMaterialUnloaded`] for despawned chunks.
+pub fn notify_unloaded_chunks(
+    despawn: On<Despawn, (MaterialField, CompressedMaterialField)>,
+    mut commands: Commands,
+) {
+    commands.trigger(OnChunkMaterialUnloaded {
+        entity: despawn.entity,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Unloaded(Vec<Entity>);
+
+    #[test]
+    fn test_despawned_chunks_notify_once() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Unloaded>()
+            .add_observer(notify_unloaded_chunks)
+            .add_observer(
+                |unloaded: On<OnChunkMaterialUnloaded>, mut log: ResMut<Unloaded>| {
+                    log.0.push(unloaded.entity);
+                },
+            );
+
+        let chunk = app.world_mut().spawn(MaterialField::filled(1)).id();
+        let compressed = app
+            .world_mut()
+            .spawn(MaterialField::filled(2).compress())
+            .id();
+        app.world_mut().despawn(chunk);
+        app.world_mut().despawn(compressed);
+        app.world_mut().flush();
+
+        assert_eq!(
+            app.world().resource::<Unloaded>().0,
+            vec![chunk, compressed]
+        );
+    }
+}
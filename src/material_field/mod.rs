@@ -2,17 +2,112 @@
 //!
 //! This module provides:
 //! - [`MaterialField`]: Per-voxel material ID storage
-//! - [`NeighborMaterialFields`]: Cached neighbor data for seamless boundaries
-//! - Material blending logic for vertex attribute computation
+//! - [`MaterialParamsField`]: Per-voxel wetness, burn and moss
+//! - [`MaterialStorage`]: Backend-agnostic material access, with the sparse [`SvoMaterialField`]
+//!   and the two-materials-per-voxel [`MaterialFieldDual`] for gradual strata
+//! - [`DensitySource`]: Backend-agnostic density for blending and brush filters
+//! - [`brush`]: Masked and filtered paint brushes, and world-space [`PaintCommand`]s
+//!   reporting [`MaterialChanged`] voxels, batched into one remesh per [`PaintStroke`] with optional previews,
+//!   and [`AreaEffects`] for gameplay and roads
+//! - [`coords`]: World, chunk and voxel coordinate conversions
+//! - [`fill_debug_pattern`]: Checker, stripe and chunk ID patterns for checking a mesher integration
+//! - [`FluidCoupling`]: Rules repainting terrain around fluids as density changes
+//! - [`MaterialTemplate`]: Prefab material blocks stamped with [`PlaceTemplate`]
+//! - [`MaterialGeneration`]: Background material generation for new chunks
+//! - [`FieldPool`]: Recycled field and mesh buffer allocations
+//! - [`GlobalRepaintTask`]: Time-sliced material remapping of every loaded chunk
+//! - [`remesh_dirty_chunks`]: Automatic attribute updates for dirty chunks,
+//!   visible chunks first under [`RemeshScheduling`]
+//! - [`MaterialTransition`]: Cross-fades from the old attributes when remeshed
+//! - [`PainterDiagnosticsPlugin`]: Remesh cost as Bevy diagnostics, next to `tracing` spans
+//! - [`OnChunkMaterialReady`] and [`OnChunkMaterialUnloaded`]: Chunk lifecycle events
+//! - [`CompressedMaterialField`]: Run-length encoded fields for distant chunks
+//! - [`MaterialSimplification`]: Single-material attributes and blurrier mips for far chunks
+//! - [`MaterialFieldQuery`]: World-space material reads, world voxel sampling and voxel
+//!   raycasts across chunks, through the shared [`MaterialChunkIndex`], also
+//!   cast from any camera's viewport with [`CameraVoxelRaycast`]
+//! - [`SurfaceTagQuery`]: Weighted per-material audio tags around a point
+//! - [`sample_particle_color`]: Debris colours matching the ground
+//! - [`NeighborMaterialFields`]: Cached neighbor data for seamless boundaries,
+//!   refreshed per changed face by [`NeighborMaterialSync`]
+//! - Material blending logic for vertex attribute computation, including
+//!   edge-based attributes for marching cubes meshes
 
 mod blending;
+pub mod brush;
+mod compress;
+pub mod coords;
+mod coupling;
+mod debug_pattern;
+mod density;
+mod diagnostics;
+mod dual;
 mod field;
+mod generate;
+mod lifecycle;
+mod neighbors;
+mod params;
+mod particles;
+mod pool;
+mod raycast;
+mod remesh;
+mod repaint;
+mod simplify;
+mod storage;
+mod surface;
+mod svo;
+mod template;
+mod transition;
+mod views;
 
+use bevy::math::UVec3;
 // Import Field trait so it's available for the MaterialSliceExt impl
 use bevy_sculpter::field::Field;
 
-pub use blending::{MaterialBlendSettings, compute_vertex_materials};
+pub use blending::{
+    GroupBlendRules, MaterialBlendInfo, MaterialBlendSettings, VertexMaterialComputer,
+    add_material_attributes_marching_cubes, compute_vertex_materials,
+};
+#[cfg(feature = "gpu_meshing")]
+pub(crate) use blending::sample_voxel;
+pub use brush::{
+    AreaEffects, BrushFilter, BrushShape, MaterialChanged, MaterialMask, PaintBuildUp,
+    PaintCommand, PaintStroke, ParamPaintCommand, RecordChanges, ScorchConfig, SplineConfig,
+    StrokePreview, apply_paint_commands, apply_param_paint_commands, flush_paint_stroke,
+};
+pub use compress::{CompressedMaterialField, FieldCompression, update_field_compression};
+pub use coupling::{
+    CouplingCell, CouplingRule, FluidCoupling, FluidCouplingState, WetRevealed,
+    apply_fluid_coupling,
+};
+pub use debug_pattern::{PatternKind, fill_debug_pattern};
+pub use density::DensitySource;
+pub use diagnostics::{PainterDiagnosticsPlugin, PainterFrameStats, report_painter_diagnostics};
+pub use dual::MaterialFieldDual;
 pub use field::{FIELD_SIZE, FIELD_VOLUME, MaterialField, MaterialFieldDirty};
+pub use generate::{
+    MaterialGeneration, MaterialGenerator, PendingMaterialField, apply_generated_materials,
+    spawn_material_generation,
+};
+pub use lifecycle::{OnChunkMaterialReady, OnChunkMaterialUnloaded, notify_unloaded_chunks};
+pub use neighbors::{NeighborMaterialSync, sync_neighbor_materials};
+pub use params::MaterialParamsField;
+pub use particles::{ParticlePalette, sample_particle_color};
+pub use pool::{FieldPool, recycle_despawned_fields};
+pub use raycast::{CameraVoxelRaycast, VoxelRayHit};
+#[cfg(feature = "gpu_meshing")]
+pub use remesh::mark_dirty_chunks_gpu_meshed;
+pub use remesh::{AttributeBackend, RemeshScheduling, remesh_dirty_chunks};
+pub use repaint::{GlobalRepaintTask, apply_global_repaint};
+pub use simplify::{MaterialSimplification, update_material_simplification};
+pub use storage::MaterialStorage;
+pub use surface::{
+    MaterialChunkIndex, MaterialFieldQuery, SurfaceTag, SurfaceTagMix, SurfaceTagQuery,
+    SurfaceTags, update_material_chunk_index,
+};
+pub use svo::SvoMaterialField;
+pub use template::{DensityStamp, MaterialTemplate, PlaceTemplate, place_templates};
+pub use transition::{MaterialTransition, animate_material_transitions};
 
 // Re-export neighbor types from bevy_sculpter with material-specific aliases
 pub use bevy_sculpter::neighbor::{NEIGHBOR_DEPTH, NeighborFace, NeighborFields, NeighborSlice};
@@ -27,15 +122,56 @@ pub type NeighborMaterialFields = NeighborFields<u8>;
 pub trait MaterialSliceExt {
     /// Creates a material slice from a neighbor chunk's boundary planes.
     ///
+    /// Allocates a new slice; use [`fill_from_material_field`](Self::fill_from_material_field)
+    /// to refresh a slice that already exists.
+    ///
     /// # Arguments
     /// * `field` - The neighbor's material field
     /// * `face` - Which face of the neighbor to sample
     fn from_material_field(field: &MaterialField, face: NeighborFace) -> Self;
+
+    /// Overwrites this slice with a neighbor chunk's boundary planes without
+    /// allocating.
+    ///
+    /// Produces the same samples as [`from_material_field`](Self::from_material_field):
+    /// the [`NEIGHBOR_DEPTH`] planes of the neighbor closest to the shared
+    /// face, indexed by `(depth, v, u)` over the two remaining axes.
+    fn fill_from_material_field(&mut self, field: &MaterialField, face: NeighborFace);
 }
 
 impl MaterialSliceExt for MaterialSlice {
     fn from_material_field(field: &MaterialField, face: NeighborFace) -> Self {
+        let _span = bevy::log::info_span!("material_neighbor_slice").entered();
         // Now we can use NeighborSlice::from_field since Field trait is in scope
         Self::from_field(field, face)
     }
+
+    fn fill_from_material_field(&mut self, field: &MaterialField, face: NeighborFace) {
+        let _span = bevy::log::info_span!("material_neighbor_slice_fill").entered();
+        let offset = face.offset();
+        let axis = offset.abs().max_position();
+        let (u_axis, v_axis) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        let (width, height) = (FIELD_SIZE[u_axis], FIELD_SIZE[v_axis]);
+
+        let mut pos = UVec3::ZERO;
+        for depth in 0..NEIGHBOR_DEPTH as u32 {
+            pos[axis] = if offset[axis] > 0 {
+                depth
+            } else {
+                FIELD_SIZE[axis] - 1 - depth
+            };
+            for v in 0..height {
+                pos[v_axis] = v;
+                for u in 0..width {
+                    pos[u_axis] = u;
+                    let index = ((depth * height + v) * width + u) as usize;
+                    self.data[index] = Field::get(field, pos.x, pos.y, pos.z);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,159 @@
+//! Keeping chunk neighbor material data up to date.
+
+use bevy::ecs::component::Tick;
+use bevy::prelude::*;
+
+use super::field::{MaterialField, MaterialFieldDirty};
+use super::surface::MaterialChunkIndex;
+use super::{MaterialSlice, MaterialSliceExt, NeighborFace, NeighborMaterialFields};
+
+/// Keeps this chunk's [`NeighborMaterialFields`] in sync with the chunks
+/// around it in the [`MaterialChunkIndex`].
+///
+/// Whenever the chunk is [`MaterialFieldDirty`], [`sync_neighbor_materials`]
+/// recopies only the faces whose neighbor entity or [`MaterialField`]
+/// changed since they were last copied. Faces of untouched neighbors keep
+/// their slices, so steady-state painting copies only for the neighbors a
+/// stroke actually reached, refilling their existing slices in place.
+#[derive(Component, Clone, Debug, Default)]
+pub struct NeighborMaterialSync {
+    /// Neighbor and field change tick each face was copied from.
+    copied: [Option<(Entity, Tick)>; 6],
+}
+
+/// System refreshing the [`NeighborMaterialFields`] of dirty
+/// [`NeighborMaterialSync`] chunks.
+#[allow(clippy::type_complexity)]
+pub fn sync_neighbor_materials(
+    mut commands: Commands,
+    mut chunks: Query<
+        (
+            Entity,
+            &mut NeighborMaterialSync,
+            Option<&mut NeighborMaterialFields>,
+        ),
+        With<MaterialFieldDirty>,
+    >,
+    fields: Query<Ref<MaterialField>>,
+    index: Res<MaterialChunkIndex>,
+) {
+    for (entity, mut sync, mut existing) in &mut chunks {
+        let Some(position) = index.position(entity) else {
+            continue;
+        };
+
+        let mut inserted = None;
+        let neighbors = match existing.as_mut() {
+            Some(neighbors) => neighbors.bypass_change_detection(),
+            None => inserted.insert(NeighborMaterialFields::default()),
+        };
+        let mut changed = false;
+        for face in NeighborFace::ALL {
+            let slot = face as usize;
+            let source = index
+                .get(position + face.offset())
+                .and_then(|neighbor| Some((neighbor, fields.get(neighbor).ok()?)));
+            let copied = source
+                .as_ref()
+                .map(|(neighbor, field)| (*neighbor, field.last_changed()));
+            if sync.copied[slot] == copied {
+                continue;
+            }
+            sync.copied[slot] = copied;
+            changed = true;
+
+            let slice = &mut neighbors.neighbors[slot];
+            match (source, slice.as_mut()) {
+                // Reuse the slice's buffer when the face already has one
+                (Some((_, field)), Some(slice)) => slice.fill_from_material_field(&field, face),
+                (Some((_, field)), None) => {
+                    *slice = Some(MaterialSlice::from_material_field(&field, face));
+                }
+                (None, _) => *slice = None,
+            }
+        }
+        if !changed {
+            continue;
+        }
+        match (existing, inserted) {
+            (Some(mut existing), _) => existing.set_changed(),
+            (None, Some(neighbors)) => {
+                commands.entity(entity).insert(neighbors);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material_field::update_material_chunk_index;
+
+    #[test]
+    fn test_only_changed_faces_are_recopied() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MaterialChunkIndex>()
+            .add_systems(
+                Update,
+                (update_material_chunk_index, sync_neighbor_materials).chain(),
+            );
+
+        let chunk = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::IDENTITY,
+                MaterialField::filled(1),
+                NeighborMaterialSync::default(),
+                MaterialFieldDirty,
+            ))
+            .id();
+        let neighbor = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(32.0, 0.0, 0.0)),
+                MaterialField::filled(2),
+            ))
+            .id();
+        app.update();
+
+        let copied = |app: &App| {
+            app.world()
+                .get::<NeighborMaterialSync>(chunk)
+                .unwrap()
+                .copied
+        };
+        let first = copied(&app);
+        assert_eq!(first.iter().flatten().count(), 1);
+        let neighbors = app.world().get::<NeighborMaterialFields>(chunk).unwrap();
+        assert_eq!(neighbors.neighbors.iter().flatten().count(), 1);
+
+        // Neighbor unchanged: nothing is recopied
+        app.update();
+        assert_eq!(copied(&app), first);
+
+        app.world_mut()
+            .get_mut::<MaterialField>(neighbor)
+            .unwrap()
+            .set(0, 0, 0, 3);
+        app.update();
+        assert_ne!(copied(&app), first);
+    }
+
+    #[test]
+    fn test_fill_matches_from_material_field() {
+        let mut field = MaterialField::new();
+        field.paint_with(|pos| (pos.x + 2 * pos.y + 3 * pos.z) as u8);
+
+        let mut slice =
+            MaterialSlice::from_material_field(&MaterialField::new(), NeighborFace::ALL[0]);
+        for face in NeighborFace::ALL {
+            slice.fill_from_material_field(&field, face);
+            assert_eq!(
+                slice.data,
+                MaterialSlice::from_material_field(&field, face).data
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Per-voxel auxiliary scalars for weathering and damage.
+
+use bevy::prelude::*;
+use bevy_sculpter::field::Field;
+
+use super::field::{FIELD_SIZE, FIELD_VOLUME};
+use crate::mesh::MaterialParam;
+
+/// Per-voxel [`MaterialParam`] values (wetness, burn, moss) as normalized
+/// bytes.
+///
+/// Independent of the material IDs: wet grass stays grass. Param brushes
+/// ([`BrushShape::paint_param`](super::BrushShape::paint_param), or
+/// [`ParamPaintCommand`](super::ParamPaintCommand)) write into it, and
+/// meshing bakes it into
+/// [`ATTRIBUTE_MATERIAL_PARAMS`](crate::mesh::ATTRIBUTE_MATERIAL_PARAMS).
+///
+/// # Example
+/// ```ignore
+/// let params = params_field.vertex_params(&positions, mesh_size);
+/// mesh = mesh.with_material_params(params);
+/// ```
+#[derive(Component, Clone, Debug)]
+pub struct MaterialParamsField(pub Vec<[u8; 4]>);
+
+impl Default for MaterialParamsField {
+    fn default() -> Self {
+        Self(vec![[0; 4]; FIELD_VOLUME])
+    }
+}
+
+impl Field<[u8; 4]> for MaterialParamsField {
+    const SIZE: UVec3 = FIELD_SIZE;
+    const DEFAULT: [u8; 4] = [0; 4];
+
+    #[inline]
+    fn data(&self) -> &[[u8; 4]] {
+        &self.0
+    }
+
+    #[inline]
+    fn data_mut(&mut self) -> &mut [[u8; 4]] {
+        &mut self.0
+    }
+}
+
+impl MaterialParamsField {
+    /// Creates a field with every param at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Value (0.0 to 1.0) of `param` at a voxel.
+    pub fn param(&self, pos: UVec3, param: MaterialParam) -> f32 {
+        self.get(pos.x, pos.y, pos.z)[param.index()] as f32 / 255.0
+    }
+
+    /// Sets `param` at a voxel, clamped to 0.0 to 1.0.
+    pub fn set_param(&mut self, pos: UVec3, param: MaterialParam, value: f32) {
+        let mut params = self.get(pos.x, pos.y, pos.z);
+        params[param.index()] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        self.set(pos.x, pos.y, pos.z, params);
+    }
+
+    /// Trilinearly interpolated params at a grid position, clamped to the
+    /// field.
+    pub fn sample(&self, grid_pos: Vec3) -> [u8; 4] {
+        let max = (FIELD_SIZE - UVec3::ONE).as_vec3();
+        let grid_pos = grid_pos.clamp(Vec3::ZERO, max);
+        let base = grid_pos.floor().min(max - Vec3::ONE);
+        let t = grid_pos - base;
+        let base = base.as_uvec3();
+
+        let mut sum = Vec4::ZERO;
+        for corner in 0..8u32 {
+            let offset = uvec3(corner & 1, (corner >> 1) & 1, corner >> 2);
+            let weight =
+                Vec3::select(offset.cmpgt(UVec3::ZERO), t, Vec3::ONE - t).element_product();
+            let p = base + offset;
+            sum += Vec4::from_array(self.get(p.x, p.y, p.z).map(f32::from)) * weight;
+        }
+        sum.round().to_array().map(|value| value as u8)
+    }
+
+    /// Packed params for every mesh-local position, ready for
+    /// [`ATTRIBUTE_MATERIAL_PARAMS`](crate::mesh::ATTRIBUTE_MATERIAL_PARAMS).
+    pub fn vertex_params(&self, positions: &[[f32; 3]], mesh_size: Vec3) -> Vec<[u8; 4]> {
+        let scale = FIELD_SIZE.as_vec3() / mesh_size;
+        positions
+            .iter()
+            .map(|&pos| self.sample(Vec3::from_array(pos) * scale))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_read_param() {
+        let mut field = MaterialParamsField::new();
+        field.set_param(uvec3(4, 5, 6), MaterialParam::Burn, 0.5);
+
+        assert!((field.param(uvec3(4, 5, 6), MaterialParam::Burn) - 0.5).abs() < 0.01);
+        assert_eq!(field.param(uvec3(4, 5, 6), MaterialParam::Wetness), 0.0);
+    }
+
+    #[test]
+    fn test_sample_interpolates() {
+        let mut field = MaterialParamsField::new();
+        field.set_param(uvec3(11, 8, 8), MaterialParam::Wetness, 1.0);
+
+        assert_eq!(field.sample(vec3(11.0, 8.0, 8.0))[0], 255);
+        assert_eq!(field.sample(vec3(10.5, 8.0, 8.0))[0], 128);
+        assert_eq!(field.sample(vec3(10.0, 8.0, 8.0))[0], 0);
+        // Clamped at the far edge
+        assert_eq!(field.sample(vec3(40.0, 8.0, 8.0)), [0; 4]);
+    }
+}
@@ -0,0 +1,232 @@
+//! Particle colours matching the ground they came from.
+//!
+//! Digging and destruction effects look wrong when their debris doesn't
+//! match the terrain. [`sample_particle_color`] picks the dominant material
+//! around a point and tints its average albedo with the same world-space
+//! biome variation the shader applies.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::surface::MaterialFieldQuery;
+use crate::palette::TexturePalette;
+
+/// Per-material particle colours, with the biome tint of the terrain.
+///
+/// Build once from a loaded palette and keep it around, e.g. in a resource;
+/// averaging the albedo array reads every texel of its top mip.
+///
+/// # Example
+/// ```ignore
+/// let particles = ParticlePalette::from_palette(&palette, &images)
+///     .unwrap()
+///     .with_tint_mask(images.get(&tint_mask).unwrap(), mask_rect);
+/// commands.insert_resource(DebrisColors(particles));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParticlePalette {
+    colors: Vec<Color>,
+    hue_variation: Vec<f32>,
+    tint_mask: Option<Image>,
+    tint_mask_rect: Rect,
+}
+
+impl ParticlePalette {
+    /// Particle colours by material ID, without biome variation.
+    pub fn new(colors: Vec<Color>) -> Self {
+        Self {
+            colors,
+            ..default()
+        }
+    }
+
+    /// Average colours and `hue_variation` of every palette material.
+    ///
+    /// Returns `None` if the albedo array isn't loaded or can't be read, see
+    /// [`TexturePalette::average_colors`].
+    pub fn from_palette(palette: &TexturePalette, images: &Assets<Image>) -> Option<Self> {
+        Some(Self {
+            colors: palette.average_colors(images)?,
+            hue_variation: palette
+                .materials
+                .iter()
+                .map(|material| material.hue_variation)
+                .collect(),
+            ..default()
+        })
+    }
+
+    /// Set how strongly the tint mask recolours `material_id`.
+    pub fn with_hue_variation(mut self, material_id: u8, strength: f32) -> Self {
+        let index = material_id as usize;
+        if self.hue_variation.len() <= index {
+            self.hue_variation.resize(index + 1, 0.0);
+        }
+        self.hue_variation[index] = strength;
+        self
+    }
+
+    /// Tint by a CPU copy of the material's
+    /// [`tint_mask`](crate::material::TriplanarExtension::tint_mask), covering
+    /// the world XZ area `world_rect`.
+    pub fn with_tint_mask(mut self, mask: &Image, world_rect: Rect) -> Self {
+        self.tint_mask = Some(mask.clone());
+        self.tint_mask_rect = world_rect;
+        self
+    }
+
+    /// Colour of `material_id` at `world_pos`, or white for unknown IDs.
+    pub fn color(&self, material_id: u8, world_pos: Vec3) -> Color {
+        let Some(&base) = self.colors.get(material_id as usize) else {
+            return Color::WHITE;
+        };
+        let strength = self
+            .hue_variation
+            .get(material_id as usize)
+            .copied()
+            .unwrap_or(0.0);
+        let Some(mask) = self.tint_mask_at(world_pos).filter(|_| strength > 0.0) else {
+            return base;
+        };
+
+        let linear = base.to_linear();
+        let tinted = apply_biome_tint(linear.to_vec3(), mask, strength);
+        Color::linear_rgba(tinted.x, tinted.y, tinted.z, linear.alpha)
+    }
+
+    /// Signed tint mask value at `world_pos`, 0 being neutral.
+    fn tint_mask_at(&self, world_pos: Vec3) -> Option<Vec2> {
+        let mask = self.tint_mask.as_ref()?;
+        let size = self.tint_mask_rect.size().max(Vec2::splat(f32::EPSILON));
+        let uv = ((world_pos.xz() - self.tint_mask_rect.min) / size).clamp(Vec2::ZERO, Vec2::ONE);
+        let texel = (uv * mask.size().as_vec2())
+            .floor()
+            .as_uvec2()
+            .min(mask.size().saturating_sub(UVec2::ONE));
+        let value = mask.get_color_at(texel.x, texel.y).ok()?.to_linear();
+        Some(vec2(value.red, value.green) * 2.0 - 1.0)
+    }
+}
+
+/// CPU copy of the shader's `apply_biome_tint`: R shifts hue by up to a
+/// quarter turn and G scales saturation up to 2x.
+fn apply_biome_tint(color: Vec3, mask: Vec2, strength: f32) -> Vec3 {
+    // Rotate hue around the grey axis (Rodrigues)
+    let k = Vec3::splat(0.577_350_27);
+    let (sin, cos) = (mask.x * 0.25 * strength * TAU).sin_cos();
+    let shifted =
+        (color * cos + k.cross(color) * sin + k * k.dot(color) * (1.0 - cos)).max(Vec3::ZERO);
+    let luma = shifted.dot(vec3(0.2126, 0.7152, 0.0722));
+    Vec3::splat(luma)
+        .lerp(shifted, 1.0 + mask.y * strength)
+        .max(Vec3::ZERO)
+}
+
+/// Colour for particles spawned at `world_pos`.
+///
+/// Uses the dominant solid material within one voxel, falling back to the
+/// nearest voxel's material so debris sampled after a dig still matches.
+/// White if no chunk covers `world_pos`.
+///
+/// # Example
+/// ```ignore
+/// fn dig_debris(mut digs: MessageReader<Dig>, debris: Res<DebrisColors>, fields: MaterialFieldQuery) {
+///     for dig in digs.read() {
+///         let color = sample_particle_color(dig.position, &debris.0, &fields);
+///         spawn_debris(dig.position, color);
+///     }
+/// }
+/// ```
+pub fn sample_particle_color(
+    world_pos: Vec3,
+    palette: &ParticlePalette,
+    fields: &MaterialFieldQuery,
+) -> Color {
+    let radius = fields.voxel_size().max_element();
+    fields
+        .dominant_material(world_pos, radius)
+        .or_else(|| fields.material_at(world_pos))
+        .map_or(Color::WHITE, |material| palette.color(material, world_pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    use super::*;
+    use crate::material_field::{MaterialChunkIndex, MaterialField, update_material_chunk_index};
+
+    fn mask(r: u8, g: u8) -> Image {
+        Image::new_fill(
+            Extent3d::default(),
+            TextureDimension::D2,
+            &[r, g, 0, 255],
+            TextureFormat::Rgba8Unorm,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    #[test]
+    fn test_particle_color_matches_ground() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MaterialChunkIndex>()
+            .add_systems(Update, update_material_chunk_index);
+        app.world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(1)));
+        app.update();
+
+        let red = Color::linear_rgb(1.0, 0.0, 0.0);
+        let green = Color::linear_rgb(0.0, 1.0, 0.0);
+        let palette = ParticlePalette::new(vec![red, green]);
+        let color = app
+            .world_mut()
+            .run_system_once(move |fields: MaterialFieldQuery| {
+                sample_particle_color(vec3(8.0, 8.0, 8.0), &palette, &fields)
+            })
+            .unwrap();
+        assert_eq!(color, green);
+
+        let outside = app
+            .world_mut()
+            .run_system_once(|fields: MaterialFieldQuery| {
+                sample_particle_color(vec3(-100.0, 8.0, 8.0), &ParticlePalette::default(), &fields)
+            })
+            .unwrap();
+        assert_eq!(outside, Color::WHITE);
+    }
+
+    #[test]
+    fn test_biome_tint() {
+        let red = Color::linear_rgb(1.0, 0.0, 0.0);
+        let rect = Rect::new(-10.0, -10.0, 10.0, 10.0);
+
+        // A neutral mask, or no variation, leaves the colour alone
+        let neutral = ParticlePalette::new(vec![red])
+            .with_hue_variation(0, 1.0)
+            .with_tint_mask(&mask(128, 128), rect);
+        let color = neutral.color(0, Vec3::ZERO).to_linear();
+        assert!((color.red - 1.0).abs() < 0.05 && color.green < 0.05 && color.blue < 0.05);
+        let untinted = ParticlePalette::new(vec![red]).with_tint_mask(&mask(255, 128), rect);
+        assert_eq!(untinted.color(0, Vec3::ZERO), red);
+
+        // Full R turns red a quarter of the way round the colour wheel
+        let shifted = ParticlePalette::new(vec![red])
+            .with_hue_variation(0, 1.0)
+            .with_tint_mask(&mask(255, 128), rect)
+            .color(0, Vec3::ZERO)
+            .to_linear();
+        assert!(shifted.green > 0.5 && shifted.red < 0.5);
+
+        // Zero G desaturates to grey
+        let grey = ParticlePalette::new(vec![red])
+            .with_hue_variation(0, 1.0)
+            .with_tint_mask(&mask(128, 0), rect)
+            .color(0, Vec3::ZERO)
+            .to_linear();
+        assert!((grey.red - grey.green).abs() < 0.05 && (grey.red - grey.blue).abs() < 0.05);
+    }
+}
@@ -0,0 +1,167 @@
+//! Recycling of chunk allocations in streaming worlds.
+
+use bevy::prelude::*;
+
+use super::field::{FIELD_VOLUME, MaterialField};
+use crate::mesh::MeshBuffers;
+
+/// Spare [`MaterialField`] and mesh buffer allocations.
+///
+/// Streaming worlds spawn and despawn chunks constantly; taking fields
+/// and mesh buffers from the pool instead of allocating keeps that churn
+/// away from the allocator. The plugin returns the fields of despawned
+/// chunks here automatically.
+///
+/// # Example
+/// ```ignore
+/// fn spawn_chunk(mut commands: Commands, mut pool: ResMut<FieldPool>) {
+///     commands.spawn((ChunkPos(pos), pool.take_filled(STONE)));
+/// }
+///
+/// fn remesh(mut pool: ResMut<FieldPool>, old_mesh: &mut Mesh) {
+///     pool.recycle_mesh(old_mesh);
+///     let builder = TriplanarMeshBuilder::from_buffers(pool.take_mesh_buffers());
+///     // ...
+/// }
+/// ```
+#[derive(Resource, Debug)]
+pub struct FieldPool {
+    fields: Vec<Vec<u8>>,
+    mesh_buffers: Vec<MeshBuffers>,
+    /// Spare fields kept; extras are freed.
+    /// Default: 64
+    pub max_fields: usize,
+    /// Spare mesh buffer sets kept; extras are freed.
+    /// Default: 16
+    pub max_mesh_buffers: usize,
+}
+
+impl Default for FieldPool {
+    fn default() -> Self {
+        Self {
+            fields: Vec::new(),
+            mesh_buffers: Vec::new(),
+            max_fields: 64,
+            max_mesh_buffers: 16,
+        }
+    }
+}
+
+impl FieldPool {
+    /// Set the number of spare fields kept.
+    pub fn with_max_fields(mut self, max_fields: usize) -> Self {
+        self.max_fields = max_fields;
+        self
+    }
+
+    /// Set the number of spare mesh buffer sets kept.
+    pub fn with_max_mesh_buffers(mut self, max_mesh_buffers: usize) -> Self {
+        self.max_mesh_buffers = max_mesh_buffers;
+        self
+    }
+
+    /// A field with every voxel set to material 0.
+    pub fn take_field(&mut self) -> MaterialField {
+        self.take_filled(0)
+    }
+
+    /// A field with every voxel set to `material_id`, reusing a spare
+    /// allocation when there is one.
+    pub fn take_filled(&mut self, material_id: u8) -> MaterialField {
+        match self.fields.pop() {
+            Some(mut data) => {
+                data.clear();
+                data.resize(FIELD_VOLUME, material_id);
+                MaterialField(data)
+            }
+            None => MaterialField::filled(material_id),
+        }
+    }
+
+    /// Returns a field's allocation to the pool.
+    pub fn recycle_field(&mut self, field: MaterialField) {
+        if self.fields.len() < self.max_fields && field.0.capacity() >= FIELD_VOLUME {
+            self.fields.push(field.0);
+        }
+    }
+
+    /// Empty mesh buffers, reusing a spare set when there is one.
+    pub fn take_mesh_buffers(&mut self) -> MeshBuffers {
+        self.mesh_buffers.pop().unwrap_or_default()
+    }
+
+    /// Returns mesh buffers to the pool.
+    pub fn recycle_mesh_buffers(&mut self, mut buffers: MeshBuffers) {
+        if self.mesh_buffers.len() < self.max_mesh_buffers {
+            buffers.clear();
+            self.mesh_buffers.push(buffers);
+        }
+    }
+
+    /// Reclaims the buffers of a mesh that is being replaced.
+    pub fn recycle_mesh(&mut self, mesh: &mut Mesh) {
+        if self.mesh_buffers.len() < self.max_mesh_buffers {
+            self.recycle_mesh_buffers(MeshBuffers::reclaim(mesh));
+        }
+    }
+
+    /// Number of spare fields.
+    pub fn field_count(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Number of spare mesh buffer sets.
+    pub fn mesh_buffer_count(&self) -> usize {
+        self.mesh_buffers.len()
+    }
+}
+
+/// Observer returning the [`MaterialField`] of a despawned chunk to the
+/// [`FieldPool`].
+pub fn recycle_despawned_fields(
+    despawn: On<Despawn, MaterialField>,
+    mut fields: Query<&mut MaterialField>,
+    pool: Option<ResMut<FieldPool>>,
+) {
+    let (Some(mut pool), Ok(mut field)) = (pool, fields.get_mut(despawn.entity)) else {
+        return;
+    };
+    if pool.field_count() < pool.max_fields {
+        pool.recycle_field(MaterialField(std::mem::take(&mut field.0)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_reuses_allocations() {
+        let mut pool = FieldPool::default().with_max_fields(1);
+        let field = MaterialField::filled(3);
+        let ptr = field.0.as_ptr();
+        pool.recycle_field(field);
+        pool.recycle_field(MaterialField::new());
+        assert_eq!(pool.field_count(), 1);
+
+        let reused = pool.take_filled(5);
+        assert_eq!(reused.0.as_ptr(), ptr);
+        assert_eq!(reused.coverage(5), 1.0);
+        assert_eq!(pool.take_field().0.len(), FIELD_VOLUME);
+    }
+
+    #[test]
+    fn test_despawned_chunks_return_fields() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<FieldPool>()
+            .add_observer(recycle_despawned_fields);
+
+        let chunk = app.world_mut().spawn(MaterialField::filled(2)).id();
+        let kept = app.world_mut().spawn(MaterialField::filled(2)).id();
+        app.world_mut().entity_mut(kept).remove::<MaterialField>();
+        app.world_mut().despawn(chunk);
+
+        assert_eq!(app.world().resource::<FieldPool>().field_count(), 1);
+    }
+}
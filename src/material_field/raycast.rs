@@ -0,0 +1,349 @@
+//! Voxel raycasts against chunk material fields.
+//!
+//! [`MaterialFieldQuery::raycast`] walks the voxel grid along a ray with a
+//! 3D DDA (Amanatides & Woo), visiting every voxel the ray passes through
+//! once, so tools and gameplay code can find the painted voxel under a ray
+//! without mesh colliders. [`CameraVoxelRaycast`] casts those rays from a
+//! given camera's viewport, so each view of a split screen paints what it
+//! shows.
+//!
+//! Rays come from `Camera::viewport_to_world`, so perspective,
+//! orthographic and custom projections all work: an orthographic
+//! top-down editor casts parallel rays starting on its near plane, often
+//! exactly on voxel boundaries, which the DDA resolves to the voxel the ray
+//! moves into. Screen-space brush sizes differ per projection, so convert
+//! them with [`CameraVoxelRaycast::world_units_per_pixel`].
+
+use bevy::camera::NormalizedRenderTarget;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::brush::grid_scale;
+use super::density::DensitySource;
+use super::field::FIELD_SIZE;
+use super::storage::MaterialStorage;
+use super::surface::MaterialFieldQuery;
+
+/// The first solid voxel along a ray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoxelRayHit {
+    /// Chunk entity holding the voxel.
+    pub chunk: Entity,
+    /// Voxel coordinates within the chunk.
+    pub voxel: UVec3,
+    /// Material of the voxel.
+    pub material_id: u8,
+    /// World position where the ray entered the voxel.
+    pub position: Vec3,
+    /// World-space surface normal, from the density gradient where
+    /// available, else the face the ray entered through.
+    pub normal: Vec3,
+    /// World distance from the ray origin to `position`.
+    pub distance: f32,
+}
+
+/// Voxel raycasts from a camera's viewport.
+///
+/// Takes the camera entity, so brushes work with any number of cameras:
+/// pass the camera of the view the cursor is in, or the one belonging to
+/// the player painting.
+///
+/// # Example
+/// ```ignore
+/// fn paint_from_view(
+///     views: Query<Entity, With<PlayerView>>,
+///     raycast: CameraVoxelRaycast,
+///     mut paint: MessageWriter<PaintCommand>,
+/// ) {
+///     for camera in &views {
+///         if let Some(hit) = raycast.under_cursor(camera, 200.0) {
+///             paint.write(PaintCommand::new(
+///                 BrushShape::Sphere { center: hit.position, radius: 2.0 },
+///                 1,
+///             ));
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct CameraVoxelRaycast<'w, 's> {
+    /// Field reads the rays are cast through.
+    pub fields: MaterialFieldQuery<'w, 's>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    windows: Query<'w, 's, &'static Window>,
+    primary: Query<'w, 's, Entity, With<PrimaryWindow>>,
+}
+
+impl CameraVoxelRaycast<'_, '_> {
+    /// The first solid voxel under the logical `viewport_position` of
+    /// `camera`, measured from the viewport's top-left corner.
+    pub fn from_viewport(
+        &self,
+        camera: Entity,
+        viewport_position: Vec2,
+        max_distance: f32,
+    ) -> Option<VoxelRayHit> {
+        let (camera, transform) = self.cameras.get(camera).ok()?;
+        let ray = camera
+            .viewport_to_world(transform, viewport_position)
+            .ok()?;
+        self.fields.raycast(ray, max_distance)
+    }
+
+    /// The first solid voxel under the cursor, if it is inside `camera`'s
+    /// viewport of its window.
+    pub fn under_cursor(&self, camera: Entity, max_distance: f32) -> Option<VoxelRayHit> {
+        let (view, _) = self.cameras.get(camera).ok()?;
+        let NormalizedRenderTarget::Window(window) =
+            view.target.normalize(self.primary.single().ok())?
+        else {
+            return None;
+        };
+        let cursor = self.windows.get(window.entity()).ok()?.cursor_position()?;
+        let viewport = view.logical_viewport_rect()?;
+        if !viewport.contains(cursor) {
+            return None;
+        }
+        self.from_viewport(camera, cursor - viewport.min, max_distance)
+    }
+
+    /// World size of one viewport pixel of `camera` at `point`, measured
+    /// across the view.
+    ///
+    /// Constant for orthographic cameras and growing with distance for
+    /// perspective ones, so multiply a brush radius in pixels by it to
+    /// paint the size the cursor shows.
+    pub fn world_units_per_pixel(&self, camera: Entity, point: Vec3) -> Option<f32> {
+        let (camera, transform) = self.cameras.get(camera).ok()?;
+        let pixel = camera.world_to_viewport(transform, point).ok()?;
+        let next = camera.viewport_to_world(transform, pixel + Vec2::X).ok()?;
+        spacing_at(point, transform.forward(), next)
+    }
+}
+
+/// Distance from `point` to where `ray` crosses the plane through `point`
+/// facing along `forward`.
+fn spacing_at(point: Vec3, forward: Dir3, ray: Ray3d) -> Option<f32> {
+    let distance = ray.intersect_plane(point, InfinitePlane3d::new(forward))?;
+    Some(ray.get_point(distance).distance(point))
+}
+
+impl MaterialFieldQuery<'_, '_> {
+    /// The first solid voxel hit by `ray` within `max_distance`.
+    ///
+    /// Voxels are solid where the chunk's
+    /// [`DensityField`](bevy_sculpter::prelude::DensityField) is negative;
+    /// chunks without one are solid throughout. The hit is on the voxel
+    /// grid, so up to half a voxel off the smooth meshed surface.
+    pub fn raycast(&self, ray: Ray3d, max_distance: f32) -> Option<VoxelRayHit> {
+        let grid_scale = grid_scale(self.mesh_size.as_deref());
+        // Voxels sit on grid points, so cell `k` spans `k - 0.5..k + 0.5`
+        let origin = ray.origin * grid_scale + 0.5;
+        // Grid units per world unit along the ray, so `t` stays in world units
+        let direction = *ray.direction * grid_scale;
+
+        let mut cell = origin.floor().as_ivec3();
+        let mut step = IVec3::ZERO;
+        let mut t_max = Vec3::INFINITY;
+        let mut t_delta = Vec3::INFINITY;
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                continue;
+            }
+            let forward = direction[axis] > 0.0;
+            // Starting on a boundary while moving down, the ray is in the
+            // lower cell; common for orthographic rays on grid-aligned views
+            if !forward && origin[axis] == cell[axis] as f32 {
+                cell[axis] -= 1;
+            }
+            step[axis] = if forward { 1 } else { -1 };
+            let boundary = cell[axis] as f32 + if forward { 1.0 } else { 0.0 };
+            t_max[axis] = (boundary - origin[axis]) / direction[axis];
+            t_delta[axis] = 1.0 / direction[axis].abs();
+        }
+
+        let mut t = 0.0;
+        let mut entered = -*ray.direction;
+        while t <= max_distance {
+            if let Some(mut hit) = self.solid_voxel(cell, grid_scale) {
+                hit.position = ray.origin + *ray.direction * t;
+                hit.distance = t;
+                if hit.normal == Vec3::ZERO {
+                    hit.normal = entered;
+                }
+                return Some(hit);
+            }
+
+            let axis = if t_max.x < t_max.y {
+                if t_max.x < t_max.z { 0 } else { 2 }
+            } else if t_max.y < t_max.z {
+                1
+            } else {
+                2
+            };
+            t = t_max[axis];
+            t_max[axis] += t_delta[axis];
+            cell[axis] += step[axis];
+            entered = Vec3::ZERO;
+            entered[axis] = -step[axis] as f32;
+        }
+        None
+    }
+
+    /// The voxel at global grid coordinates `cell` if it is solid, with a
+    /// zero normal where density gives none.
+    fn solid_voxel(&self, cell: IVec3, grid_scale: Vec3) -> Option<VoxelRayHit> {
+        let size = FIELD_SIZE.as_ivec3();
+        let chunk = self.index.get(cell.div_euclid(size))?;
+        let (_, field, compressed, density) = self.chunks.get(chunk).ok()?;
+        let local = cell.rem_euclid(size);
+        if density.is_some_and(|density| density.density(local).is_none_or(|value| value >= 0.0)) {
+            return None;
+        }
+        let voxel = local.as_uvec3();
+        let material_id = match (field, compressed) {
+            (Some(field), _) => MaterialStorage::get(field, voxel),
+            (None, Some(compressed)) => compressed.get(voxel),
+            (None, None) => return None,
+        };
+
+        // Density rises towards the outside; one-sided at chunk edges
+        let normal = density.map_or(Vec3::ZERO, |density| {
+            let sample = |pos: IVec3| density.density(pos);
+            let center = sample(local).unwrap_or_default();
+            let mut gradient = Vec3::ZERO;
+            for axis in 0..3 {
+                let mut offset = IVec3::ZERO;
+                offset[axis] = 1;
+                gradient[axis] = match (sample(local + offset), sample(local - offset)) {
+                    (Some(up), Some(down)) => (up - down) * 0.5,
+                    (Some(up), None) => up - center,
+                    (None, Some(down)) => center - down,
+                    (None, None) => 0.0,
+                };
+            }
+            (gradient * grid_scale).normalize_or_zero()
+        });
+
+        Some(VoxelRayHit {
+            chunk,
+            voxel,
+            material_id,
+            position: Vec3::ZERO,
+            normal,
+            distance: 0.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy_sculpter::field::Field;
+    use bevy_sculpter::prelude::DensityField;
+
+    use super::*;
+    use crate::material_field::{MaterialChunkIndex, MaterialField, update_material_chunk_index};
+
+    #[test]
+    fn test_raycast_hits_ground() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MaterialChunkIndex>()
+            .add_systems(Update, update_material_chunk_index);
+
+        // Ground below grid height 10 in the second chunk along x
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    density.set(x, y, z, y as f32 - 10.0);
+                }
+            }
+        }
+        let chunk = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(32.0, 0.0, 0.0)),
+                MaterialField::filled(4),
+                density,
+            ))
+            .id();
+        app.update();
+
+        let hit = app
+            .world_mut()
+            .run_system_once(|fields: MaterialFieldQuery| {
+                fields.raycast(Ray3d::new(vec3(40.3, 20.0, 5.2), Dir3::NEG_Y), 100.0)
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.chunk, chunk);
+        assert_eq!(hit.material_id, 4);
+        assert_eq!(hit.voxel, uvec3(8, 9, 5));
+        assert!((hit.position.y - 9.5).abs() < 1e-4);
+        assert!((hit.distance - 10.5).abs() < 1e-4);
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-4));
+
+        let miss = app
+            .world_mut()
+            .run_system_once(|fields: MaterialFieldQuery| {
+                fields.raycast(Ray3d::new(vec3(8.0, 20.0, 5.0), Dir3::NEG_Y), 100.0)
+            })
+            .unwrap();
+        assert_eq!(miss, None);
+    }
+
+    #[test]
+    fn test_orthographic_rays_start_in_the_cell_they_enter() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MaterialChunkIndex>()
+            .add_systems(Update, update_material_chunk_index);
+
+        // Ground below grid height 10 under a ceiling from height 12
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let value = if y >= 12 { -1.0 } else { y as f32 - 10.0 };
+                    density.set(x, y, z, value);
+                }
+            }
+        }
+        app.world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(2), density));
+        app.update();
+
+        // Parallel top-down rays starting on the ceiling's bottom face, as
+        // an orthographic camera between the two would cast them
+        let hits = app
+            .world_mut()
+            .run_system_once(|fields: MaterialFieldQuery| {
+                [3.5, 8.0, 20.25]
+                    .map(|x| fields.raycast(Ray3d::new(vec3(x, 11.5, 5.5), Dir3::NEG_Y), 100.0))
+            })
+            .unwrap();
+        for hit in hits {
+            let hit = hit.unwrap();
+            assert_eq!(hit.voxel.y, 9);
+            assert!((hit.distance - 2.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_pixel_spacing_per_projection() {
+        let point = vec3(0.0, 0.0, -10.0);
+        // Orthographic: the next pixel's ray is parallel, a fixed step aside
+        let ortho = Ray3d::new(vec3(0.5, 0.0, 0.0), Dir3::NEG_Z);
+        assert_eq!(spacing_at(point, Dir3::NEG_Z, ortho), Some(0.5));
+
+        // Perspective: the spacing grows with distance from the camera
+        let perspective = Ray3d::new(Vec3::ZERO, Dir3::new(vec3(0.01, 0.0, -1.0)).unwrap());
+        let near = spacing_at(point, Dir3::NEG_Z, perspective).unwrap();
+        let far = spacing_at(point * 2.0, Dir3::NEG_Z, perspective).unwrap();
+        assert!((near - 0.1).abs() < 1e-4);
+        assert!((far - 0.2).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,380 @@
+//! Automatic material attribute updates for dirty chunks.
+
+use bevy::camera::primitives::Aabb;
+use bevy::ecs::entity::EntityHashSet;
+use bevy::log::info_span;
+use bevy::mesh::VertexAttributeValues;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use bevy_sculpter::prelude::{DensityField, DensityFieldMeshSize, NeighborDensityFields};
+
+use super::NeighborMaterialFields;
+use super::blending::{MaterialBlendSettings, VertexMaterialComputer};
+use super::brush::{StrokePreview, grid_scale};
+use super::diagnostics::PainterFrameStats;
+use super::field::{FIELD_SIZE, MaterialField, MaterialFieldDirty};
+use super::lifecycle::OnChunkMaterialReady;
+use super::params::MaterialParamsField;
+use super::transition::MaterialTransition;
+use super::views::{ActiveCameras, nearest_view_distance};
+use crate::material::SimplifiedMaterials;
+use crate::mesh::{ATTRIBUTE_MATERIAL_PARAMS, MeshTriplanarExt};
+
+/// Where chunk material attributes are computed when
+/// [`TriplanarVoxelPlugin::auto_remesh`](crate::TriplanarVoxelPlugin::auto_remesh)
+/// is on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AttributeBackend {
+    /// Blend on the CPU into the chunk's existing [`Mesh3d`].
+    #[default]
+    Cpu,
+    /// Mesh and blend on the GPU; dirty chunks get a
+    /// [`GpuMeshedChunk`](crate::gpu_meshing::GpuMeshedChunk).
+    #[cfg(feature = "gpu_meshing")]
+    Gpu,
+}
+
+/// Limits and orders CPU remeshing when many chunks are dirty at once,
+/// such as while a world loads.
+///
+/// With this resource present, [`remesh_dirty_chunks`] remeshes chunks
+/// whose [`ViewVisibility`] is set first, nearest the closest active
+/// camera first, and at most
+/// [`max_chunks_per_frame`](Self::max_chunks_per_frame) chunks a frame.
+/// Culled chunks stay [`MaterialFieldDirty`] until they come into view,
+/// so their attributes are never computed while nobody can see them.
+/// Visibility is as of Bevy's last visibility check, which includes
+/// shadow-casting views, and every active camera counts, so each half of
+/// a split screen gets its nearby chunks early.
+///
+/// # Example
+/// ```ignore
+/// app.insert_resource(RemeshScheduling::default().with_max_chunks_per_frame(8));
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct RemeshScheduling {
+    /// Most chunks remeshed per frame, visible ones first.
+    /// Default: 32
+    pub max_chunks_per_frame: usize,
+    /// Keep culled chunks dirty until they are visible. When off, they are
+    /// remeshed with whatever budget the visible chunks leave over.
+    /// Default: true
+    pub defer_hidden: bool,
+}
+
+impl Default for RemeshScheduling {
+    fn default() -> Self {
+        Self {
+            max_chunks_per_frame: 32,
+            defer_hidden: true,
+        }
+    }
+}
+
+impl RemeshScheduling {
+    pub fn with_max_chunks_per_frame(mut self, max_chunks_per_frame: usize) -> Self {
+        self.max_chunks_per_frame = max_chunks_per_frame;
+        self
+    }
+
+    pub fn with_defer_hidden(mut self, defer_hidden: bool) -> Self {
+        self.defer_hidden = defer_hidden;
+        self
+    }
+
+    /// Chunks to remesh this frame out of dirty `(entity, visible, distance)`
+    /// triples, `distance` being to the nearest active camera.
+    fn select(&self, chunks: impl Iterator<Item = (Entity, bool, f32)>) -> EntityHashSet {
+        let mut chunks: Vec<_> = chunks
+            .filter(|&(_, visible, _)| visible || !self.defer_hidden)
+            .collect();
+        chunks.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.total_cmp(&b.2)));
+        chunks
+            .into_iter()
+            .map(|(entity, ..)| entity)
+            .take(self.max_chunks_per_frame)
+            .collect()
+    }
+}
+
+/// System recomputing material attributes of [`MaterialFieldDirty`] chunks.
+///
+/// Updates the chunk's [`Mesh3d`] in place with [`MaterialBlendSettings`]:
+/// only the material ID and weight buffers are rewritten, reusing their
+/// allocations, and the mesh handle is kept. Also adds [`ATTRIBUTE_MATERIAL_PARAMS`] for chunks with a
+/// [`MaterialParamsField`], then clears the marker and triggers
+/// [`OnChunkMaterialReady`]. Chunks with a [`MaterialTransition`] fade to
+/// the new materials instead. Chunks whose mesh isn't loaded yet stay dirty.
+/// [`SimplifiedMaterials`] chunks get one dominant material per vertex, and
+/// [`StrokePreview`] chunks at most two.
+/// The chunk's [`Aabb`] is refreshed from the mesh when it is stale or
+/// missing, since Bevy only computes it when the [`Mesh3d`] handle changes.
+/// A [`RemeshScheduling`] resource limits and prioritizes the chunks
+/// remeshed each frame. Work done is added to [`PainterFrameStats`] when
+/// present.
+#[allow(clippy::type_complexity)]
+pub fn remesh_dirty_chunks(
+    mut commands: Commands,
+    mut chunks: Query<
+        (
+            Entity,
+            &Mesh3d,
+            &DensityField,
+            &MaterialField,
+            Option<&NeighborDensityFields>,
+            Option<&NeighborMaterialFields>,
+            Option<&MaterialParamsField>,
+            Option<&mut MaterialTransition>,
+            Has<SimplifiedMaterials>,
+            Has<StrokePreview>,
+            Option<&ViewVisibility>,
+            Option<&GlobalTransform>,
+            Option<&Aabb>,
+        ),
+        With<MaterialFieldDirty>,
+    >,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<MaterialBlendSettings>,
+    scheduling: Option<Res<RemeshScheduling>>,
+    cameras: ActiveCameras,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+    stats: Option<ResMut<PainterFrameStats>>,
+    mut scratch: Local<(Vec<u32>, Vec<u32>)>,
+) {
+    let start = Instant::now();
+    let (ids, weights) = &mut *scratch;
+    let mut preview_settings = None;
+    let mut frame = PainterFrameStats::default();
+    let mesh_size = FIELD_SIZE.as_vec3() / grid_scale(mesh_size.as_deref());
+    let scheduled = scheduling.map(|scheduling| {
+        let views = cameras.positions();
+        scheduling.select(chunks.iter().map(|chunk| {
+            let (entity, .., visibility, transform, _) = chunk;
+            let center = transform.map_or(Vec3::ZERO, |transform| {
+                transform.transform_point(mesh_size * 0.5)
+            });
+            (
+                entity,
+                visibility.is_some_and(|visibility| visibility.get()),
+                nearest_view_distance(&views, center),
+            )
+        }))
+    });
+    for (
+        entity,
+        mesh,
+        density,
+        materials,
+        neighbor_densities,
+        neighbor_materials,
+        params,
+        transition,
+        simplified,
+        preview,
+        _,
+        _,
+        aabb,
+    ) in &mut chunks
+    {
+        frame.dirty_chunks += 1;
+        if scheduled
+            .as_ref()
+            .is_some_and(|scheduled| !scheduled.contains(&entity))
+        {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {
+            continue;
+        };
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            commands.entity(entity).remove::<MaterialFieldDirty>();
+            continue;
+        };
+
+        let _span = info_span!("remesh_chunk_attributes", vertices = positions.len()).entered();
+        frame.remeshed_chunks += 1;
+        frame.vertices_processed += positions.len();
+        let settings = if preview {
+            &*preview_settings.get_or_insert_with(|| MaterialBlendSettings {
+                max_materials: settings.max_materials.min(2),
+                ..settings.clone()
+            })
+        } else {
+            &*settings
+        };
+        let computer = VertexMaterialComputer::new(density, materials, mesh_size, settings)
+            .with_neighbors(neighbor_densities, neighbor_materials);
+        if simplified {
+            computer.compute_packed_dominant_into(positions, ids, weights);
+        } else {
+            computer.compute_packed_into(positions, ids, weights);
+        }
+        let params = params.map(|params| params.vertex_params(positions, mesh_size));
+        if let Some(bounds) = mesh.compute_aabb()
+            && aabb != Some(&bounds)
+        {
+            commands.entity(entity).insert(bounds);
+        }
+        // Fading chunks keep their current attributes until animated
+        let fading = transition.is_some_and(|mut transition| transition.start(mesh, ids, weights));
+        if !fading {
+            mesh.set_triplanar_attributes(ids, weights);
+        }
+        if let Some(params) = params {
+            mesh.insert_attribute(
+                ATTRIBUTE_MATERIAL_PARAMS,
+                VertexAttributeValues::Unorm8x4(params),
+            );
+        }
+        commands.entity(entity).remove::<MaterialFieldDirty>();
+        commands.trigger(OnChunkMaterialReady { entity });
+    }
+
+    if let Some(mut stats) = stats {
+        stats.dirty_chunks += frame.dirty_chunks;
+        stats.remeshed_chunks += frame.remeshed_chunks;
+        stats.vertices_processed += frame.vertices_processed;
+        stats.remesh_time += start.elapsed();
+    }
+}
+
+/// System handing [`MaterialFieldDirty`] chunks to GPU meshing.
+///
+/// GPU meshed chunks remesh whenever their fields change, so this only
+/// adds the [`GpuMeshedChunk`](crate::gpu_meshing::GpuMeshedChunk) marker
+/// once, clears the dirty marker and triggers [`OnChunkMaterialReady`].
+#[cfg(feature = "gpu_meshing")]
+#[allow(clippy::type_complexity)]
+pub fn mark_dirty_chunks_gpu_meshed(
+    mut commands: Commands,
+    chunks: Query<
+        (Entity, Has<crate::gpu_meshing::GpuMeshedChunk>),
+        (With<MaterialFieldDirty>, With<DensityField>),
+    >,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+    stats: Option<ResMut<PainterFrameStats>>,
+) {
+    if let Some(mut stats) = stats {
+        stats.dirty_chunks += chunks.iter().count();
+    }
+    let mesh_size = FIELD_SIZE.as_vec3() / grid_scale(mesh_size.as_deref());
+    for (entity, gpu_meshed) in &chunks {
+        commands.trigger(OnChunkMaterialReady { entity });
+        let mut entity = commands.entity(entity);
+        entity.remove::<MaterialFieldDirty>();
+        if !gpu_meshed {
+            entity.insert(crate::gpu_meshing::GpuMeshedChunk { mesh_size });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::PrimitiveTopology;
+    use bevy_sculpter::field::Field;
+
+    use super::*;
+    use crate::mesh::{ATTRIBUTE_MATERIAL_IDS, VertexMaterialData};
+
+    #[derive(Resource, Default)]
+    struct ReadyChunks(Vec<Entity>);
+
+    #[test]
+    fn test_dirty_chunk_gets_attributes() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_resource::<MaterialBlendSettings>()
+            .init_resource::<ReadyChunks>()
+            .init_resource::<PainterFrameStats>()
+            .add_observer(
+                |ready: On<OnChunkMaterialReady>, mut log: ResMut<ReadyChunks>| {
+                    log.0.push(ready.entity);
+                },
+            )
+            .add_systems(Update, remesh_dirty_chunks);
+
+        let mut density = DensityField::new();
+        density.data_mut().fill(-1.0);
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[4.5, 4.5, 4.5]]);
+        let mesh = app.world_mut().resource_mut::<Assets<Mesh>>().add(mesh);
+        let chunk = app
+            .world_mut()
+            .spawn((
+                Mesh3d(mesh.clone()),
+                density,
+                MaterialField::filled(3),
+                MaterialFieldDirty,
+            ))
+            .id();
+        app.update();
+
+        let world = app.world();
+        assert!(world.get::<MaterialFieldDirty>(chunk).is_none());
+        assert_eq!(world.resource::<ReadyChunks>().0, vec![chunk]);
+        let stats = world.resource::<PainterFrameStats>();
+        assert_eq!((stats.dirty_chunks, stats.remeshed_chunks), (1, 1));
+        assert_eq!(stats.vertices_processed, 1);
+        let mesh = world.resource::<Assets<Mesh>>().get(&mesh).unwrap();
+        let Some(VertexAttributeValues::Uint32(ids)) = mesh.attribute(ATTRIBUTE_MATERIAL_IDS)
+        else {
+            panic!("missing material ids");
+        };
+        assert_eq!(ids[0], VertexMaterialData::single(3).pack_ids());
+        assert_eq!(world.get::<Aabb>(chunk), mesh.compute_aabb().as_ref());
+    }
+
+    #[test]
+    fn test_scheduling_prefers_visible_chunks() {
+        let mut world = World::new();
+        let chunks: Vec<Entity> = (0..4).map(|_| world.spawn_empty().id()).collect();
+        let dirty = || {
+            chunks
+                .iter()
+                .enumerate()
+                .map(|(i, &e)| (e, i % 2 == 1, 0.0))
+        };
+
+        let scheduling = RemeshScheduling::default().with_max_chunks_per_frame(1);
+        assert_eq!(
+            scheduling.select(dirty()),
+            EntityHashSet::from_iter([chunks[1]])
+        );
+
+        let scheduling = scheduling.with_max_chunks_per_frame(8);
+        assert_eq!(
+            scheduling.select(dirty()),
+            EntityHashSet::from_iter([chunks[1], chunks[3]])
+        );
+        assert_eq!(scheduling.with_defer_hidden(false).select(dirty()).len(), 4);
+    }
+
+    #[test]
+    fn test_scheduling_prefers_chunks_near_any_camera() {
+        let mut world = World::new();
+        let chunks: Vec<Entity> = (0..3).map(|_| world.spawn_empty().id()).collect();
+        // Distances to the nearest of two split-screen cameras
+        let dirty = [
+            (chunks[0], true, 40.0),
+            (chunks[1], true, 5.0),
+            (chunks[2], false, 1.0),
+        ];
+
+        let scheduling = RemeshScheduling::default().with_max_chunks_per_frame(1);
+        assert_eq!(
+            scheduling.select(dirty.into_iter()),
+            EntityHashSet::from_iter([chunks[1]])
+        );
+        let scheduling = scheduling.with_max_chunks_per_frame(2);
+        assert_eq!(
+            scheduling
+                .with_defer_hidden(false)
+                .select(dirty.into_iter()),
+            EntityHashSet::from_iter([chunks[0], chunks[1]])
+        );
+    }
+}
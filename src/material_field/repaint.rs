@@ -0,0 +1,259 @@
+//! Time-sliced material remapping across every loaded chunk.
+//!
+//! A season change that turns all grass to snow touches every voxel in the
+//! world. [`GlobalRepaintTask`] spreads that work over frames with a voxel
+//! budget, so thousands of chunks repaint without a hitch.
+
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+
+use super::compress::CompressedMaterialField;
+use super::field::{FIELD_VOLUME, MaterialField, MaterialFieldDirty};
+use super::surface::MaterialChunkIndex;
+
+/// Remaps the materials of every loaded chunk over several frames.
+///
+/// Insert the resource to start; the chunks loaded on the first frame are
+/// repainted in entity order, [`voxel_budget`](Self::voxel_budget) voxels
+/// per frame. Chunks loaded later are left alone, so have generation
+/// produce the new materials too. Compressed chunks are expanded, remapped
+/// and compressed again. Finished chunks and their finished neighbors are
+/// marked [`MaterialFieldDirty`], so boundaries blend with the new
+/// materials on both sides. The resource stays once done; poll
+/// [`is_finished`](Self::is_finished) and remove it yourself.
+///
+/// # Example
+/// ```ignore
+/// commands.insert_resource(GlobalRepaintTask::new(|id| match id {
+///     GRASS => SNOW,
+///     WATER => ICE,
+///     other => other,
+/// }));
+///
+/// fn loading_bar(task: Res<GlobalRepaintTask>, mut bar: Single<&mut Node, With<Progress>>) {
+///     bar.width = percent(task.progress() * 100.0);
+/// }
+/// ```
+#[derive(Resource, Clone, Debug)]
+pub struct GlobalRepaintTask {
+    /// New material for each material ID.
+    table: [u8; 256],
+    /// Voxels remapped per frame, across all chunks.
+    /// Default: 65536
+    pub voxel_budget: usize,
+    /// Chunks to repaint, collected on the first frame.
+    chunks: Vec<Entity>,
+    started: bool,
+    /// Index into `chunks` of the chunk being repainted.
+    chunk: usize,
+    /// Next voxel of the current chunk.
+    voxel: usize,
+    /// Whether the current chunk changed so far.
+    chunk_changed: bool,
+    repainted: HashSet<Entity>,
+}
+
+impl GlobalRepaintTask {
+    /// Replaces every material ID by `remap(id)`.
+    pub fn new(remap: impl Fn(u8) -> u8) -> Self {
+        Self {
+            table: std::array::from_fn(|id| remap(id as u8)),
+            voxel_budget: 65536,
+            chunks: Vec::new(),
+            started: false,
+            chunk: 0,
+            voxel: 0,
+            chunk_changed: false,
+            repainted: HashSet::default(),
+        }
+    }
+
+    /// Set the number of voxels remapped per frame.
+    pub fn with_voxel_budget(mut self, voxel_budget: usize) -> Self {
+        self.voxel_budget = voxel_budget;
+        self
+    }
+
+    /// New material for `material_id`.
+    pub fn remap(&self, material_id: u8) -> u8 {
+        self.table[material_id as usize]
+    }
+
+    /// Fraction of voxels repainted, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if !self.started {
+            return 0.0;
+        }
+        if self.chunks.is_empty() {
+            return 1.0;
+        }
+        let done = self.chunk * FIELD_VOLUME + self.voxel;
+        (done as f64 / (self.chunks.len() * FIELD_VOLUME) as f64) as f32
+    }
+
+    /// Number of chunks fully repainted, and the total.
+    pub fn chunks_done(&self) -> (usize, usize) {
+        (self.chunk.min(self.chunks.len()), self.chunks.len())
+    }
+
+    /// Whether every chunk has been repainted.
+    pub fn is_finished(&self) -> bool {
+        self.started && self.chunk >= self.chunks.len()
+    }
+
+    /// Remaps `materials` in place, returning whether anything changed.
+    fn remap_slice(&self, materials: &mut [u8]) -> bool {
+        let mut changed = false;
+        for material in materials {
+            let remapped = self.table[*material as usize];
+            changed |= remapped != *material;
+            *material = remapped;
+        }
+        changed
+    }
+}
+
+/// System advancing the [`GlobalRepaintTask`] by one frame's budget.
+#[allow(clippy::type_complexity)]
+pub fn apply_global_repaint(
+    mut commands: Commands,
+    mut task: ResMut<GlobalRepaintTask>,
+    mut fields: Query<&mut MaterialField>,
+    mut compressed: Query<&mut CompressedMaterialField, Without<MaterialField>>,
+    chunks: Query<Entity, Or<(With<MaterialField>, With<CompressedMaterialField>)>>,
+    index: Res<MaterialChunkIndex>,
+) {
+    if task.is_finished() {
+        return;
+    }
+    let task = &mut *task;
+    if !task.started {
+        task.chunks = chunks.iter().collect();
+        task.chunks.sort_unstable();
+        task.started = true;
+    }
+
+    let mut remaining = task.voxel_budget.max(1);
+    while remaining > 0 && task.chunk < task.chunks.len() {
+        let entity = task.chunks[task.chunk];
+        let start = task.voxel;
+
+        if let Ok(mut field) = fields.get_mut(entity) {
+            let end = (start + remaining).min(FIELD_VOLUME);
+            // Untouched slices shouldn't trigger change detection
+            if task.remap_slice(&mut field.bypass_change_detection().0[start..end]) {
+                field.set_changed();
+                task.chunk_changed = true;
+            }
+            remaining -= end - start;
+            task.voxel = end;
+        } else if let Ok(mut packed) = compressed.get_mut(entity) {
+            let mut field = packed.decompress();
+            if task.remap_slice(&mut field.0[start..]) {
+                *packed = field.compress();
+                task.chunk_changed = true;
+            }
+            remaining = remaining.saturating_sub(FIELD_VOLUME - start);
+            task.voxel = FIELD_VOLUME;
+        } else {
+            // Despawned since the task started
+            task.voxel = FIELD_VOLUME;
+        }
+
+        if task.voxel < FIELD_VOLUME {
+            break;
+        }
+        if task.chunk_changed {
+            commands.entity(entity).insert(MaterialFieldDirty);
+            // Neighbors remeshed earlier blended against the old materials
+            if let Some(position) = index.position(entity) {
+                for z in -1..=1 {
+                    for y in -1..=1 {
+                        for x in -1..=1 {
+                            if let Some(neighbor) = index.get(position + ivec3(x, y, z))
+                                && neighbor != entity
+                                && task.repainted.contains(&neighbor)
+                            {
+                                commands.entity(neighbor).insert(MaterialFieldDirty);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        task.repainted.insert(entity);
+        task.chunk += 1;
+        task.voxel = 0;
+        task.chunk_changed = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material_field::update_material_chunk_index;
+
+    const GRASS: u8 = 1;
+    const SNOW: u8 = 2;
+
+    #[test]
+    fn test_repaint_over_frames() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MaterialChunkIndex>()
+            .add_systems(
+                Update,
+                (
+                    update_material_chunk_index,
+                    apply_global_repaint.run_if(resource_exists::<GlobalRepaintTask>),
+                )
+                    .chain(),
+            );
+
+        let first = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(GRASS)))
+            .id();
+        let second = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(32.0, 0.0, 0.0)),
+                MaterialField::filled(GRASS).compress(),
+            ))
+            .id();
+        app.insert_resource(
+            GlobalRepaintTask::new(|id| if id == GRASS { SNOW } else { id })
+                .with_voxel_budget(FIELD_VOLUME / 2),
+        );
+
+        app.update();
+        let task = app.world().resource::<GlobalRepaintTask>();
+        assert_eq!(task.progress(), 0.25);
+        assert_eq!(task.chunks_done(), (0, 2));
+        let field = app.world().get::<MaterialField>(first).unwrap();
+        assert_eq!(field.coverage(SNOW), 0.5);
+        assert!(app.world().get::<MaterialFieldDirty>(first).is_none());
+
+        app.update();
+        assert!(app.world().get::<MaterialFieldDirty>(first).is_some());
+        app.world_mut()
+            .entity_mut(first)
+            .remove::<MaterialFieldDirty>();
+
+        app.update();
+        let world = app.world();
+        let task = world.resource::<GlobalRepaintTask>();
+        assert!(task.is_finished());
+        assert_eq!(task.progress(), 1.0);
+        assert_eq!(
+            world
+                .get::<CompressedMaterialField>(second)
+                .unwrap()
+                .is_uniform(),
+            Some(SNOW)
+        );
+        // The finished neighbor is remeshed again against the new boundary
+        assert!(world.get::<MaterialFieldDirty>(first).is_some());
+        assert!(world.get::<MaterialFieldDirty>(second).is_some());
+    }
+}
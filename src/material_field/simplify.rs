@@ -0,0 +1,124 @@
+//! Cheap single-material rendering for far chunks.
+
+use bevy::prelude::*;
+use bevy_sculpter::prelude::DensityFieldMeshSize;
+
+use super::brush::grid_scale;
+use super::field::{FIELD_SIZE, MaterialField, MaterialFieldDirty};
+use super::views::{ActiveCameras, nearest_view_distance};
+use crate::material::SimplifiedMaterials;
+
+/// Switches chunks far from every active camera to [`SimplifiedMaterials`].
+///
+/// Chunks beyond `simplify_distance` are remeshed with one dominant
+/// material per vertex and render with blurrier mips; they blend fully
+/// again once a camera comes within `restore_distance`. Both switches
+/// mark the chunk [`MaterialFieldDirty`], so keep the gap between the two
+/// distances wide enough that a camera near the border doesn't remesh
+/// chunks every frame.
+///
+/// # Example
+/// ```ignore
+/// commands.insert_resource(MaterialSimplification::new(192.0));
+/// ```
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct MaterialSimplification {
+    /// Camera distance to a chunk's center beyond which it is simplified.
+    pub simplify_distance: f32,
+    /// Camera distance within which a simplified chunk blends again.
+    pub restore_distance: f32,
+}
+
+impl MaterialSimplification {
+    /// Simplifies beyond `distance`, restoring again at 80% of it.
+    pub fn new(distance: f32) -> Self {
+        Self {
+            simplify_distance: distance,
+            restore_distance: distance * 0.8,
+        }
+    }
+
+    /// Set the distance within which chunks blend again.
+    pub fn with_restore_distance(mut self, distance: f32) -> Self {
+        self.restore_distance = distance;
+        self
+    }
+}
+
+/// System applying [`MaterialSimplification`].
+pub fn update_material_simplification(
+    mut commands: Commands,
+    simplification: Res<MaterialSimplification>,
+    cameras: ActiveCameras,
+    chunks: Query<(Entity, &GlobalTransform, Has<SimplifiedMaterials>), With<MaterialField>>,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+) {
+    let views = cameras.positions();
+    if views.is_empty() {
+        return;
+    }
+    let half_extent = FIELD_SIZE.as_vec3() * 0.5 / grid_scale(mesh_size.as_deref());
+
+    for (entity, transform, simplified) in &chunks {
+        let distance = nearest_view_distance(&views, transform.transform_point(half_extent));
+
+        if !simplified && distance > simplification.simplify_distance {
+            commands
+                .entity(entity)
+                .insert((SimplifiedMaterials, MaterialFieldDirty));
+        } else if simplified && distance < simplification.restore_distance {
+            commands
+                .entity(entity)
+                .remove::<SimplifiedMaterials>()
+                .insert(MaterialFieldDirty);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_far_chunks_simplify_and_restore() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(MaterialSimplification::new(100.0))
+            .add_systems(Update, update_material_simplification);
+
+        let camera = app
+            .world_mut()
+            .spawn((Camera::default(), GlobalTransform::IDENTITY))
+            .id();
+        let near = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(3)))
+            .id();
+        let far = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(500.0, 0.0, 0.0)),
+                MaterialField::filled(3),
+            ))
+            .id();
+        app.update();
+
+        let world = app.world();
+        assert!(world.get::<SimplifiedMaterials>(near).is_none());
+        assert!(world.get::<MaterialFieldDirty>(near).is_none());
+        assert!(world.get::<SimplifiedMaterials>(far).is_some());
+        assert!(world.get::<MaterialFieldDirty>(far).is_some());
+
+        app.world_mut()
+            .entity_mut(far)
+            .remove::<MaterialFieldDirty>();
+        *app.world_mut().get_mut::<GlobalTransform>(camera).unwrap() =
+            GlobalTransform::from_translation(vec3(480.0, 0.0, 0.0));
+        app.update();
+
+        let world = app.world();
+        assert!(world.get::<SimplifiedMaterials>(far).is_none());
+        assert!(world.get::<MaterialFieldDirty>(far).is_some());
+        assert!(world.get::<SimplifiedMaterials>(near).is_some());
+    }
+}
@@ -0,0 +1,103 @@
+//! Storage-agnostic access to per-voxel material IDs.
+
+use bevy::prelude::*;
+use bevy_sculpter::field::Field;
+
+use super::field::{FIELD_SIZE, MaterialField};
+
+/// Read/write access to a 3D grid of material IDs.
+///
+/// Implemented by the dense [`MaterialField`], the sparse
+/// [`SvoMaterialField`](super::SvoMaterialField) and the two-material
+/// [`MaterialFieldDual`](super::MaterialFieldDual), so code written against
+/// the trait works with either backend, or a custom one.
+///
+/// [`MaterialField`] also implements the sculpter `Field` trait, whose
+/// `get`/`set` take separate coordinates. Where both traits are in
+/// scope, call these as `MaterialStorage::get(&field, pos)`.
+pub trait MaterialStorage {
+    /// Voxels along each axis.
+    fn size(&self) -> UVec3;
+
+    /// Material at `pos`, or 0 out of bounds.
+    fn get(&self, pos: UVec3) -> u8;
+
+    /// Sets the material at `pos`; out-of-bounds positions are ignored.
+    fn set(&mut self, pos: UVec3, material_id: u8);
+
+    /// Second material mixed into the voxel at `pos`, and its share of
+    /// the voxel in `0.0..=0.5`, for storages holding two materials per
+    /// voxel like [`MaterialFieldDual`](super::MaterialFieldDual).
+    /// [`get`](Self::get) returns the larger part.
+    fn secondary(&self, pos: UVec3) -> Option<(u8, f32)> {
+        let _ = pos;
+        None
+    }
+
+    /// Mixes `material_b` into `material_a` at `pos`, `share` being
+    /// `material_b`'s part of the voxel in `0.0..=1.0`.
+    ///
+    /// Storages holding one material per voxel keep the larger part.
+    fn set_mix(&mut self, pos: UVec3, material_a: u8, material_b: u8, share: f32) {
+        self.set(pos, if share > 0.5 { material_b } else { material_a });
+    }
+
+    /// Material at signed coordinates, `None` out of bounds.
+    fn get_ivec3(&self, pos: IVec3) -> Option<u8> {
+        (pos.cmpge(IVec3::ZERO).all() && pos.cmplt(self.size().as_ivec3()).all())
+            .then(|| self.get(pos.as_uvec3()))
+    }
+
+    /// Returns the material if every voxel in `min..max` holds the same
+    /// one. Empty or out-of-bounds regions return `None`.
+    fn uniform_region(&self, min: UVec3, max: UVec3) -> Option<u8> {
+        if min.cmpge(max).any() || max.cmpgt(self.size()).any() {
+            return None;
+        }
+        let first = self.get(min);
+        for z in min.z..max.z {
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    if self.get(uvec3(x, y, z)) != first {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(first)
+    }
+}
+
+impl MaterialStorage for MaterialField {
+    fn size(&self) -> UVec3 {
+        FIELD_SIZE
+    }
+
+    #[inline]
+    fn get(&self, pos: UVec3) -> u8 {
+        Field::get(self, pos.x, pos.y, pos.z)
+    }
+
+    #[inline]
+    fn set(&mut self, pos: UVec3, material_id: u8) {
+        Field::set(self, pos.x, pos.y, pos.z, material_id);
+    }
+
+    fn uniform_region(&self, min: UVec3, max: UVec3) -> Option<u8> {
+        if min == UVec3::ZERO && max == FIELD_SIZE {
+            return self.is_uniform();
+        }
+        if min.cmpge(max).any() || max.cmpgt(FIELD_SIZE).any() {
+            return None;
+        }
+        let first = MaterialStorage::get(self, min);
+        let row = (max.x - min.x) as usize;
+        (min.z..max.z)
+            .flat_map(|z| (min.y..max.y).map(move |y| (y, z)))
+            .all(|(y, z)| {
+                let start = (min.x + y * FIELD_SIZE.x + z * FIELD_SIZE.x * FIELD_SIZE.y) as usize;
+                self.0[start..start + row].iter().all(|&m| m == first)
+            })
+            .then_some(first)
+    }
+}
@@ -0,0 +1,565 @@
+//! Per-material surface tags sampled around world positions.
+//!
+//! Audio systems can pick footstep sounds and reverb from the materials
+//! around the listener without raycasting meshes.
+//! [`SurfaceTagQuery::sample_surface_tags`] reads chunk material fields
+//! directly through a [`MaterialFieldQuery`], which finds chunks in the
+//! [`MaterialChunkIndex`].
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::Affine3A;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_sculpter::prelude::{DensityField, DensityFieldMeshSize};
+use chunky_bevy::prelude::ChunkPos;
+
+use super::brush::grid_scale;
+use super::compress::CompressedMaterialField;
+use super::coords;
+use super::density::DensitySource;
+use super::field::{FIELD_SIZE, MaterialField};
+use super::storage::MaterialStorage;
+
+/// Acoustic description of a palette material.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct SurfaceTag {
+    /// Application-defined category, e.g. a footstep sound set.
+    pub id: u16,
+    /// How much the material blocks sound, from 0 to 1.
+    pub occlusion: f32,
+    /// How much the material reflects sound, from 0 to 1.
+    pub reverb: f32,
+}
+
+impl SurfaceTag {
+    /// Tag `id` with medium occlusion and reverb.
+    pub fn new(id: u16) -> Self {
+        Self {
+            id,
+            occlusion: 0.5,
+            reverb: 0.5,
+        }
+    }
+
+    /// Set how much the material blocks sound.
+    pub fn with_occlusion(mut self, occlusion: f32) -> Self {
+        self.occlusion = occlusion;
+        self
+    }
+
+    /// Set how much the material reflects sound.
+    pub fn with_reverb(mut self, reverb: f32) -> Self {
+        self.reverb = reverb;
+        self
+    }
+}
+
+/// [`SurfaceTag`]s by material ID.
+///
+/// Untagged materials are ignored when sampling.
+///
+/// # Example
+/// ```ignore
+/// commands.insert_resource(
+///     SurfaceTags::default()
+///         .with_tag(STONE, SurfaceTag::new(FOOTSTEP_STONE).with_reverb(0.9))
+///         .with_tag(MOSS, SurfaceTag::new(FOOTSTEP_SOFT).with_reverb(0.1)),
+/// );
+/// ```
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SurfaceTags {
+    tags: HashMap<u8, SurfaceTag>,
+}
+
+impl SurfaceTags {
+    /// Tag `material_id`, replacing any previous tag.
+    pub fn with_tag(mut self, material_id: u8, tag: SurfaceTag) -> Self {
+        self.set(material_id, tag);
+        self
+    }
+
+    /// Tag `material_id`, replacing any previous tag.
+    pub fn set(&mut self, material_id: u8, tag: SurfaceTag) {
+        self.tags.insert(material_id, tag);
+    }
+
+    /// The tag of `material_id`, if any.
+    pub fn get(&self, material_id: u8) -> Option<&SurfaceTag> {
+        self.tags.get(&material_id)
+    }
+}
+
+/// Weighted mix of the surface tags around a point.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SurfaceTagMix {
+    /// `(tag id, weight)` pairs, weights summing to 1, heaviest first.
+    pub tags: Vec<(u16, f32)>,
+    /// Weighted average occlusion.
+    pub occlusion: f32,
+    /// Weighted average reverb.
+    pub reverb: f32,
+}
+
+impl SurfaceTagMix {
+    /// The heaviest tag, if any tagged material was found.
+    pub fn dominant(&self) -> Option<u16> {
+        self.tags.first().map(|&(id, _)| id)
+    }
+
+    /// Weight of `id` in the mix, 0.0 if absent.
+    pub fn weight(&self, id: u16) -> f32 {
+        self.tags
+            .iter()
+            .find(|&&(tag, _)| tag == id)
+            .map_or(0.0, |&(_, weight)| weight)
+    }
+
+    /// Whether no tagged material was found.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+/// Chunk entities by chunk grid position, shared by every world-space
+/// material read.
+///
+/// A chunk's position is its [`ChunkPos`], or without one its translation
+/// divided by the chunk's world size ([`DensityFieldMeshSize`], or one
+/// world unit per voxel without it). Assumes unrotated, unscaled chunks on
+/// a regular grid.
+#[derive(Resource, Default)]
+pub struct MaterialChunkIndex {
+    chunks: HashMap<IVec3, Entity>,
+    positions: HashMap<Entity, IVec3>,
+}
+
+impl MaterialChunkIndex {
+    /// The chunk at grid position `chunk`.
+    pub fn get(&self, chunk: IVec3) -> Option<Entity> {
+        self.chunks.get(&chunk).copied()
+    }
+
+    /// Grid position of `entity`, if indexed.
+    pub fn position(&self, entity: Entity) -> Option<IVec3> {
+        self.positions.get(&entity).copied()
+    }
+
+    /// Number of indexed chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Whether no chunks are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    fn insert(&mut self, entity: Entity, chunk: IVec3) {
+        if let Some(old) = self.positions.insert(entity, chunk)
+            && self.chunks.get(&old) == Some(&entity)
+        {
+            self.chunks.remove(&old);
+        }
+        self.chunks.insert(chunk, entity);
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        if let Some(old) = self.positions.remove(&entity)
+            && self.chunks.get(&old) == Some(&entity)
+        {
+            self.chunks.remove(&old);
+        }
+    }
+}
+
+/// World size of one chunk along each axis.
+fn chunk_world_size(mesh_size: Option<&DensityFieldMeshSize>) -> Vec3 {
+    FIELD_SIZE.as_vec3() / grid_scale(mesh_size)
+}
+
+/// System keeping the [`MaterialChunkIndex`] in sync with chunk entities.
+///
+/// Chunks are entities with a [`GlobalTransform`] and a [`MaterialField`]
+/// or [`CompressedMaterialField`], placed by their [`ChunkPos`] when they
+/// have one. Rebuilds the whole index when the
+/// resource is added or [`DensityFieldMeshSize`] changes.
+#[allow(clippy::type_complexity)]
+pub fn update_material_chunk_index(
+    mut index: ResMut<MaterialChunkIndex>,
+    chunks: Query<
+        (Entity, Ref<GlobalTransform>, Option<Ref<ChunkPos>>),
+        Or<(With<MaterialField>, With<CompressedMaterialField>)>,
+    >,
+    mut removed_fields: RemovedComponents<MaterialField>,
+    mut removed_compressed: RemovedComponents<CompressedMaterialField>,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+) {
+    let chunk_size = chunk_world_size(mesh_size.as_deref());
+    let rebuild = index.is_added() || mesh_size.as_ref().is_some_and(|size| size.is_changed());
+
+    // Compression swaps one field component for the other, so only drop
+    // entities that have neither
+    for entity in removed_fields.read().chain(removed_compressed.read()) {
+        if !chunks.contains(entity) {
+            index.remove(entity);
+        }
+    }
+
+    for (entity, transform, chunk_pos) in &chunks {
+        let moved = match &chunk_pos {
+            Some(chunk_pos) => chunk_pos.is_changed(),
+            None => transform.is_changed(),
+        };
+        if rebuild || moved || !index.positions.contains_key(&entity) {
+            let chunk = chunk_pos.map_or_else(
+                || (transform.translation() / chunk_size).round().as_ivec3(),
+                |chunk_pos| chunk_pos.0,
+            );
+            index.insert(entity, chunk);
+        }
+    }
+}
+
+/// World-space reads of chunk material fields.
+///
+/// Finds chunks through the [`MaterialChunkIndex`], so the same grid
+/// assumptions apply.
+///
+/// # Example
+/// ```ignore
+/// fn report_ground(player: Single<&Transform, With<Player>>, fields: MaterialFieldQuery) {
+///     if let Some(material) = fields.dominant_material(player.translation, 1.0) {
+///         info!("standing on material {material}");
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct MaterialFieldQuery<'w, 's> {
+    pub(super) index: Res<'w, MaterialChunkIndex>,
+    pub(super) chunks: Query<
+        'w,
+        's,
+        (
+            &'static GlobalTransform,
+            Option<&'static MaterialField>,
+            Option<&'static CompressedMaterialField>,
+            Option<&'static DensityField>,
+        ),
+    >,
+    pub(super) mesh_size: Option<Res<'w, DensityFieldMeshSize>>,
+}
+
+impl MaterialFieldQuery<'_, '_> {
+    /// World size of one voxel along each axis.
+    pub fn voxel_size(&self) -> Vec3 {
+        1.0 / grid_scale(self.mesh_size.as_deref())
+    }
+
+    /// Material of the voxel at `world_voxel`, solid or not.
+    ///
+    /// World voxel coordinates count voxels from the world origin across
+    /// chunk boundaries: chunk `c` holds voxels `c * FIELD_SIZE` up to the
+    /// next chunk's first. `None` where no chunk is loaded.
+    pub fn sample(&self, world_voxel: IVec3) -> Option<u8> {
+        let ((_, field, compressed, _), voxel) = self.chunk_voxel(world_voxel)?;
+        match (field, compressed) {
+            (Some(field), _) => Some(MaterialStorage::get(field, voxel)),
+            (None, Some(compressed)) => Some(compressed.get(voxel)),
+            (None, None) => None,
+        }
+    }
+
+    /// Density of the voxel at `world_voxel`, see [`sample`](Self::sample).
+    /// `None` where the chunk has no [`DensityField`].
+    pub fn sample_density(&self, world_voxel: IVec3) -> Option<f32> {
+        let ((_, _, _, density), voxel) = self.chunk_voxel(world_voxel)?;
+        density?.density(voxel.as_ivec3())
+    }
+
+    /// Components of the chunk holding `world_voxel`, and the voxel within it.
+    #[allow(clippy::type_complexity)]
+    fn chunk_voxel(
+        &self,
+        world_voxel: IVec3,
+    ) -> Option<(
+        (
+            &GlobalTransform,
+            Option<&MaterialField>,
+            Option<&CompressedMaterialField>,
+            Option<&DensityField>,
+        ),
+        UVec3,
+    )> {
+        let (chunk, voxel) = coords::voxel_to_chunk(world_voxel);
+        let chunk = self
+            .index
+            .get(chunk)
+            .and_then(|entity| self.chunks.get(entity).ok())?;
+        Some((chunk, voxel))
+    }
+
+    /// Material of the voxel nearest `world_pos`, solid or not.
+    pub fn material_at(&self, world_pos: Vec3) -> Option<u8> {
+        let grid_scale = grid_scale(self.mesh_size.as_deref());
+        let chunk = coords::world_to_chunk(world_pos, FIELD_SIZE.as_vec3() / grid_scale);
+        let (transform, field, compressed, _) = self
+            .index
+            .get(chunk)
+            .and_then(|entity| self.chunks.get(entity).ok())?;
+
+        let to_grid = Affine3A::from_scale(grid_scale) * transform.affine().inverse();
+        let voxel = to_grid
+            .transform_point3(world_pos)
+            .round()
+            .clamp(Vec3::ZERO, FIELD_SIZE.as_vec3() - 1.0)
+            .as_uvec3();
+        match (field, compressed) {
+            (Some(field), _) => Some(MaterialStorage::get(field, voxel)),
+            (None, Some(compressed)) => Some(compressed.get(voxel)),
+            (None, None) => None,
+        }
+    }
+
+    /// Material with the most weight among solid voxels within `radius` of
+    /// `world_pos`, weighted as in [`for_each_solid_voxel`](Self::for_each_solid_voxel).
+    /// Ties go to the lower ID.
+    pub fn dominant_material(&self, world_pos: Vec3, radius: f32) -> Option<u8> {
+        let mut totals: Vec<(u8, f32)> = Vec::new();
+        self.for_each_solid_voxel(world_pos, radius, |material, weight| {
+            match totals.iter_mut().find(|(id, _)| *id == material) {
+                Some((_, total)) => *total += weight,
+                None => totals.push((material, weight)),
+            }
+        });
+        totals
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(material, _)| material)
+    }
+
+    /// Call `visit` with the material and weight of every solid voxel
+    /// within `radius` of `world_pos`.
+    ///
+    /// Voxels weigh more the closer they are, falling off linearly to zero
+    /// at `radius`. Chunks without a [`DensityField`] count every voxel as
+    /// solid.
+    pub fn for_each_solid_voxel(
+        &self,
+        world_pos: Vec3,
+        radius: f32,
+        mut visit: impl FnMut(u8, f32),
+    ) {
+        let grid_scale = grid_scale(self.mesh_size.as_deref());
+        let chunk_size = FIELD_SIZE.as_vec3() / grid_scale;
+        let radius = radius.max(f32::EPSILON);
+
+        let min_chunk = coords::world_to_chunk(world_pos - radius, chunk_size);
+        let max_chunk = coords::world_to_chunk(world_pos + radius, chunk_size);
+        for z in min_chunk.z..=max_chunk.z {
+            for y in min_chunk.y..=max_chunk.y {
+                for x in min_chunk.x..=max_chunk.x {
+                    let Some((transform, field, compressed, density)) = self
+                        .index
+                        .get(ivec3(x, y, z))
+                        .and_then(|entity| self.chunks.get(entity).ok())
+                    else {
+                        continue;
+                    };
+                    let material = |pos: UVec3| match (field, compressed) {
+                        (Some(field), _) => Some(MaterialStorage::get(field, pos)),
+                        (None, Some(compressed)) => Some(compressed.get(pos)),
+                        (None, None) => None,
+                    };
+
+                    let to_grid = Affine3A::from_scale(grid_scale) * transform.affine().inverse();
+                    let center = to_grid.transform_point3(world_pos);
+                    let extent = radius * grid_scale;
+                    let min = (center - extent).ceil().max(Vec3::ZERO).as_uvec3();
+                    let max = (center + extent)
+                        .floor()
+                        .min(FIELD_SIZE.as_vec3() - 1.0)
+                        .as_ivec3();
+                    if max.cmplt(IVec3::ZERO).any() {
+                        continue;
+                    }
+                    let max = max.as_uvec3();
+
+                    for vz in min.z..=max.z {
+                        for vy in min.y..=max.y {
+                            for vx in min.x..=max.x {
+                                let voxel = uvec3(vx, vy, vz);
+                                let distance = ((voxel.as_vec3() - center) / grid_scale).length();
+                                let weight = 1.0 - distance / radius;
+                                if weight <= 0.0 {
+                                    continue;
+                                }
+                                if density.is_some_and(|density| {
+                                    density
+                                        .density(voxel.as_ivec3())
+                                        .is_none_or(|value| value >= 0.0)
+                                }) {
+                                    continue;
+                                }
+                                if let Some(id) = material(voxel) {
+                                    visit(id, weight);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Samples [`SurfaceTags`] from chunk material fields.
+///
+/// # Example
+/// ```ignore
+/// fn footsteps(player: Single<&Transform, With<Player>>, surfaces: SurfaceTagQuery) {
+///     let mix = surfaces.sample_surface_tags(player.translation, 1.5);
+///     if let Some(tag) = mix.dominant() {
+///         play_footstep(tag);
+///     }
+///     set_reverb(mix.reverb);
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct SurfaceTagQuery<'w, 's> {
+    fields: MaterialFieldQuery<'w, 's>,
+    tags: Res<'w, SurfaceTags>,
+}
+
+impl SurfaceTagQuery<'_, '_> {
+    /// Weighted mix of the tags of solid voxels within `radius` of
+    /// `world_pos`.
+    ///
+    /// Voxels count more the closer they are, falling off linearly to zero
+    /// at `radius`. Chunks without a [`DensityField`] count every voxel as
+    /// solid.
+    pub fn sample_surface_tags(&self, world_pos: Vec3, radius: f32) -> SurfaceTagMix {
+        let mut totals: Vec<(u16, f32)> = Vec::new();
+        let mut occlusion = 0.0;
+        let mut reverb = 0.0;
+        let mut total_weight = 0.0;
+
+        self.fields
+            .for_each_solid_voxel(world_pos, radius, |material, weight| {
+                let Some(tag) = self.tags.get(material) else {
+                    return;
+                };
+                match totals.iter_mut().find(|(id, _)| *id == tag.id) {
+                    Some((_, total)) => *total += weight,
+                    None => totals.push((tag.id, weight)),
+                }
+                occlusion += tag.occlusion * weight;
+                reverb += tag.reverb * weight;
+                total_weight += weight;
+            });
+
+        if total_weight <= 0.0 {
+            return SurfaceTagMix::default();
+        }
+        for (_, weight) in &mut totals {
+            *weight /= total_weight;
+        }
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        SurfaceTagMix {
+            tags: totals,
+            occlusion: occlusion / total_weight,
+            reverb: reverb / total_weight,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn test_sample_surface_tags_across_chunks() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MaterialChunkIndex>()
+            .insert_resource(
+                SurfaceTags::default()
+                    .with_tag(1, SurfaceTag::new(10).with_reverb(1.0))
+                    .with_tag(2, SurfaceTag::new(20).with_reverb(0.0)),
+            )
+            .add_systems(Update, update_material_chunk_index);
+
+        let stone = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(1)))
+            .id();
+        app.world_mut().spawn((
+            GlobalTransform::from_translation(vec3(32.0, 0.0, 0.0)),
+            MaterialField::filled(2).compress(),
+        ));
+        app.update();
+        assert_eq!(app.world().resource::<MaterialChunkIndex>().len(), 2);
+
+        let mix = app
+            .world_mut()
+            .run_system_once(|surfaces: SurfaceTagQuery| {
+                surfaces.sample_surface_tags(vec3(31.5, 16.0, 16.0), 3.0)
+            })
+            .unwrap();
+        assert!((mix.weight(10) - 0.5).abs() < 0.01);
+        assert!((mix.weight(20) - 0.5).abs() < 0.01);
+        assert!((mix.reverb - 0.5).abs() < 0.01);
+
+        let mix = app
+            .world_mut()
+            .run_system_once(|surfaces: SurfaceTagQuery| {
+                surfaces.sample_surface_tags(vec3(8.0, 16.0, 16.0), 2.0)
+            })
+            .unwrap();
+        assert_eq!(mix.dominant(), Some(10));
+        assert_eq!(mix.tags.len(), 1);
+
+        let samples = app
+            .world_mut()
+            .run_system_once(|fields: MaterialFieldQuery| {
+                [
+                    fields.sample(ivec3(31, 4, 4)),
+                    fields.sample(ivec3(32, 4, 4)),
+                    fields.sample(ivec3(-1, 4, 4)),
+                ]
+            })
+            .unwrap();
+        assert_eq!(samples, [Some(1), Some(2), None]);
+
+        app.world_mut().entity_mut(stone).despawn();
+        app.update();
+        assert_eq!(app.world().resource::<MaterialChunkIndex>().len(), 1);
+    }
+
+    #[test]
+    fn test_index_prefers_chunk_pos() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<MaterialChunkIndex>()
+            .add_systems(Update, update_material_chunk_index);
+
+        let chunk = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::IDENTITY,
+                ChunkPos(ivec3(4, -1, 2)),
+                MaterialField::filled(1),
+            ))
+            .id();
+        app.update();
+        let index = app.world().resource::<MaterialChunkIndex>();
+        assert_eq!(index.position(chunk), Some(ivec3(4, -1, 2)));
+
+        app.world_mut().get_mut::<ChunkPos>(chunk).unwrap().0 = ivec3(5, -1, 2);
+        app.update();
+        let index = app.world().resource::<MaterialChunkIndex>();
+        assert_eq!(index.get(ivec3(5, -1, 2)), Some(chunk));
+        assert_eq!(index.get(ivec3(4, -1, 2)), None);
+    }
+}
@@ -0,0 +1,271 @@
+//! Sparse voxel octree material storage for large regions.
+
+use bevy::prelude::*;
+
+use super::field::{FIELD_SIZE, MaterialField};
+use super::storage::MaterialStorage;
+
+/// Octree node: a uniform cube or eight children in X-Y-Z order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SvoNode {
+    Leaf(u8),
+    Branch(Box<[SvoNode; 8]>),
+}
+
+impl SvoNode {
+    /// Child index and child-local position for `pos` in a node of `half` * 2.
+    #[inline]
+    fn child(pos: UVec3, half: u32) -> (usize, UVec3) {
+        let upper = pos.cmpge(UVec3::splat(half));
+        let index = upper.x as usize | (upper.y as usize) << 1 | (upper.z as usize) << 2;
+        (
+            index,
+            pos - UVec3::select(upper, UVec3::splat(half), UVec3::ZERO),
+        )
+    }
+
+    fn get(&self, pos: UVec3, size: u32) -> u8 {
+        match self {
+            Self::Leaf(material) => *material,
+            Self::Branch(children) => {
+                let half = size / 2;
+                let (index, local) = Self::child(pos, half);
+                children[index].get(local, half)
+            }
+        }
+    }
+
+    /// Paints the node's `min..max` part, collapsing uniform branches.
+    fn fill(&mut self, min: UVec3, max: UVec3, size: u32, material_id: u8) {
+        if min == UVec3::ZERO && max == UVec3::splat(size) {
+            *self = Self::Leaf(material_id);
+            return;
+        }
+        if let Self::Leaf(material) = *self {
+            if material == material_id {
+                return;
+            }
+            *self = Self::Branch(Box::new(std::array::from_fn(|_| Self::Leaf(material))));
+        }
+        let Self::Branch(children) = self else {
+            unreachable!()
+        };
+
+        let half = size / 2;
+        for (index, child) in children.iter_mut().enumerate() {
+            let offset = uvec3(index as u32 & 1, (index as u32 >> 1) & 1, index as u32 >> 2) * half;
+            let child_min = min.max(offset);
+            let child_max = max.min(offset + half);
+            if child_min.cmplt(child_max).all() {
+                child.fill(child_min - offset, child_max - offset, half, material_id);
+            }
+        }
+        self.collapse();
+    }
+
+    /// Replaces a branch of identical leaves with one leaf.
+    fn collapse(&mut self) {
+        if let Self::Branch(children) = self
+            && let Self::Leaf(first) = children[0]
+            && children.iter().all(|child| *child == Self::Leaf(first))
+        {
+            *self = Self::Leaf(first);
+        }
+    }
+
+    fn uniform(&self, min: UVec3, max: UVec3, size: u32) -> Option<u8> {
+        match self {
+            Self::Leaf(material) => Some(*material),
+            Self::Branch(children) => {
+                let half = size / 2;
+                let mut result = None;
+                for (index, child) in children.iter().enumerate() {
+                    let offset =
+                        uvec3(index as u32 & 1, (index as u32 >> 1) & 1, index as u32 >> 2) * half;
+                    let child_min = min.max(offset);
+                    let child_max = max.min(offset + half);
+                    if child_min.cmpge(child_max).any() {
+                        continue;
+                    }
+                    let material = child.uniform(child_min - offset, child_max - offset, half)?;
+                    if result.is_some_and(|result| result != material) {
+                        return None;
+                    }
+                    result = Some(material);
+                }
+                result
+            }
+        }
+    }
+
+    fn count(&self) -> usize {
+        match self {
+            Self::Leaf(_) => 1,
+            Self::Branch(children) => 1 + children.iter().map(Self::count).sum::<usize>(),
+        }
+    }
+}
+
+/// Material IDs in a sparse voxel octree.
+///
+/// Uniform regions collapse into single nodes, so one entity can cover a
+/// cube far larger than a chunk, e.g. a whole server-side region of
+/// mostly solid stone, and "is this area all one material" queries are
+/// answered without visiting voxels. Use [`extract_chunk`](Self::extract_chunk)
+/// to get a dense [`MaterialField`] for meshing.
+///
+/// # Example
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::{MaterialStorage, SvoMaterialField};
+///
+/// const STONE: u8 = 1;
+/// const ORE: u8 = 5;
+///
+/// let mut region = SvoMaterialField::new(1024, STONE);
+/// region.set(uvec3(500, 20, 700), ORE);
+///
+/// assert_eq!(region.get(uvec3(500, 20, 700)), ORE);
+/// assert_eq!(region.uniform_region(UVec3::ZERO, uvec3(256, 256, 256)), Some(STONE));
+/// ```
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct SvoMaterialField {
+    root: SvoNode,
+    size: u32,
+}
+
+impl SvoMaterialField {
+    /// Creates a cube of `size` voxels per axis filled with `material_id`.
+    ///
+    /// # Panics
+    /// Panics if `size` isn't a power of two.
+    pub fn new(size: u32, material_id: u8) -> Self {
+        assert!(size.is_power_of_two(), "octree size must be a power of two");
+        Self {
+            root: SvoNode::Leaf(material_id),
+            size,
+        }
+    }
+
+    /// Paints the box `min..max` (exclusive), clipped to the region.
+    pub fn fill_box(&mut self, min: UVec3, max: UVec3, material_id: u8) {
+        let max = max.min(UVec3::splat(self.size));
+        if min.cmplt(max).all() {
+            self.root.fill(min, max, self.size, material_id);
+        }
+    }
+
+    /// Copies a dense field into the region with its origin at `offset`.
+    pub fn insert_chunk(&mut self, offset: UVec3, field: &MaterialField) {
+        if let Some(material) = field.is_uniform() {
+            self.fill_box(offset, offset + FIELD_SIZE, material);
+            return;
+        }
+        for (pos, material) in field.enumerate_coords() {
+            self.set(offset + pos, material);
+        }
+    }
+
+    /// Dense field of the chunk-sized block with its origin at `offset`.
+    /// Voxels past the region's edge are material 0.
+    pub fn extract_chunk(&self, offset: UVec3) -> MaterialField {
+        if let Some(material) = self.uniform_region(offset, offset + FIELD_SIZE) {
+            return MaterialField::filled(material);
+        }
+        let mut field = MaterialField::new();
+        for (pos, material) in field.enumerate_coords_mut() {
+            *material = self.get(offset + pos);
+        }
+        field
+    }
+
+    /// Number of octree nodes, a measure of memory use.
+    pub fn node_count(&self) -> usize {
+        self.root.count()
+    }
+}
+
+impl MaterialStorage for SvoMaterialField {
+    fn size(&self) -> UVec3 {
+        UVec3::splat(self.size)
+    }
+
+    fn get(&self, pos: UVec3) -> u8 {
+        if pos.cmpge(UVec3::splat(self.size)).any() {
+            return 0;
+        }
+        self.root.get(pos, self.size)
+    }
+
+    fn set(&mut self, pos: UVec3, material_id: u8) {
+        self.fill_box(pos, pos + UVec3::ONE, material_id);
+    }
+
+    fn uniform_region(&self, min: UVec3, max: UVec3) -> Option<u8> {
+        if min.cmpge(max).any() || max.cmpgt(UVec3::splat(self.size)).any() {
+            return None;
+        }
+        self.root.uniform(min, max, self.size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_splits_and_collapses() {
+        let mut region = SvoMaterialField::new(64, 1);
+        region.set(uvec3(10, 20, 30), 4);
+        assert_eq!(region.get(uvec3(10, 20, 30)), 4);
+        assert_eq!(region.get(uvec3(11, 20, 30)), 1);
+        // One branch per level down to the voxel
+        assert_eq!(region.node_count(), 6 * 8 + 1);
+
+        region.set(uvec3(10, 20, 30), 1);
+        assert_eq!(region.node_count(), 1);
+        assert_eq!(region.get(uvec3(64, 0, 0)), 0);
+    }
+
+    #[test]
+    fn test_uniform_region_queries() {
+        let mut region = SvoMaterialField::new(128, 2);
+        region.fill_box(uvec3(0, 0, 0), uvec3(128, 40, 128), 7);
+
+        assert_eq!(
+            region.uniform_region(UVec3::ZERO, uvec3(128, 40, 128)),
+            Some(7)
+        );
+        assert_eq!(
+            region.uniform_region(uvec3(0, 40, 0), uvec3(128, 128, 128)),
+            Some(2)
+        );
+        assert_eq!(
+            region.uniform_region(uvec3(0, 39, 0), uvec3(8, 41, 8)),
+            None
+        );
+        assert_eq!(region.uniform_region(UVec3::ZERO, uvec3(129, 1, 1)), None);
+    }
+
+    #[test]
+    fn test_chunk_roundtrip() {
+        let mut field = MaterialField::filled(3);
+        field.paint_sphere(ivec3(16, 16, 16), 6, 9);
+
+        let mut region = SvoMaterialField::new(256, 0);
+        region.insert_chunk(uvec3(32, 64, 96), &field);
+        assert_eq!(
+            region.extract_chunk(uvec3(32, 64, 96)).as_slice(),
+            field.as_slice()
+        );
+        assert_eq!(region.extract_chunk(uvec3(128, 0, 0)).is_uniform(), Some(0));
+
+        // The dense backend agrees on region queries
+        assert_eq!(field.uniform_region(UVec3::ZERO, uvec3(8, 8, 8)), Some(3));
+        assert_eq!(
+            field.uniform_region(uvec3(10, 10, 10), uvec3(20, 20, 20)),
+            None
+        );
+        assert_eq!(MaterialStorage::get(&field, uvec3(16, 16, 16)), 9);
+    }
+}
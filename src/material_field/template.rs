@@ -0,0 +1,318 @@
+//! Prefab material templates stamped into chunks at world positions.
+
+use std::fmt;
+use std::sync::Arc;
+
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy_sculpter::field::Field;
+use bevy_sculpter::neighbor::NEIGHBOR_DEPTH;
+use bevy_sculpter::prelude::{DensityField, DensityFieldMeshSize};
+
+use super::brush::grid_scale;
+use super::field::{FIELD_SIZE, MaterialField, MaterialFieldDirty};
+
+/// A small 3D block of material IDs, like a building or dungeon room, that
+/// gameplay code can stamp into terrain.
+///
+/// One template cell covers one voxel. Empty cells leave the terrain
+/// untouched. The anchor cell lands on the placement position.
+///
+/// # Example
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_painter::material_field::{MaterialField, MaterialTemplate};
+///
+/// const STONE: u8 = 4;
+///
+/// // 3x1x3 floor slab anchored at its center
+/// let mut slab = MaterialTemplate::new(uvec3(3, 1, 3)).with_anchor(uvec3(1, 0, 1));
+/// slab.fill(STONE);
+///
+/// let mut field = MaterialField::new();
+/// slab.stamp(&mut field, ivec3(16, 8, 16), |_, _| {});
+/// assert_eq!(field.get(15, 8, 17), STONE);
+/// ```
+#[derive(Asset, TypePath, Clone, Debug, PartialEq)]
+pub struct MaterialTemplate {
+    /// Cells along each axis.
+    pub size: UVec3,
+    /// Material per cell in X-Y-Z order (X varies fastest); `None` is empty.
+    pub cells: Vec<Option<u8>>,
+    /// Cell placed at the target position.
+    pub anchor: UVec3,
+}
+
+impl MaterialTemplate {
+    /// Creates an empty template anchored at its origin corner.
+    pub fn new(size: UVec3) -> Self {
+        Self {
+            size,
+            cells: vec![None; (size.x * size.y * size.z) as usize],
+            anchor: UVec3::ZERO,
+        }
+    }
+
+    /// Set the cell placed at the target position.
+    pub fn with_anchor(mut self, anchor: UVec3) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Material at a cell, `None` if empty or out of bounds.
+    pub fn get(&self, pos: UVec3) -> Option<u8> {
+        self.index(pos).and_then(|index| self.cells[index])
+    }
+
+    /// Sets a cell; out-of-bounds positions are ignored.
+    pub fn set(&mut self, pos: UVec3, material_id: Option<u8>) {
+        if let Some(index) = self.index(pos) {
+            self.cells[index] = material_id;
+        }
+    }
+
+    /// Sets every cell to `material_id`.
+    pub fn fill(&mut self, material_id: u8) {
+        self.cells.fill(Some(material_id));
+    }
+
+    fn index(&self, pos: UVec3) -> Option<usize> {
+        pos.cmplt(self.size)
+            .all()
+            .then(|| (pos.x + pos.y * self.size.x + pos.z * self.size.x * self.size.y) as usize)
+    }
+
+    /// Non-empty cells with their offset from the anchor.
+    fn offsets(&self) -> impl Iterator<Item = (IVec3, u8)> + '_ {
+        let (size, anchor) = (self.size, self.anchor.as_ivec3());
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, cell)| {
+                let index = index as u32;
+                let pos = uvec3(
+                    index % size.x,
+                    index / size.x % size.y,
+                    index / (size.x * size.y),
+                );
+                cell.map(|material_id| (pos.as_ivec3() - anchor, material_id))
+            })
+    }
+
+    /// Writes the template into `field` with the anchor at grid position
+    /// `at`, clipped to the field.
+    ///
+    /// `density` is called with the grid position and material of every
+    /// stamped voxel, so the caller can carve or fill the matching density
+    /// with their sculpting tools. Returns whether any voxel was stamped.
+    pub fn stamp(
+        &self,
+        field: &mut MaterialField,
+        at: IVec3,
+        mut density: impl FnMut(UVec3, u8),
+    ) -> bool {
+        let mut stamped = false;
+        for (offset, material_id) in self.offsets() {
+            let pos = at + offset;
+            if pos.cmplt(IVec3::ZERO).any() || pos.cmpge(FIELD_SIZE.as_ivec3()).any() {
+                continue;
+            }
+            let pos = pos.as_uvec3();
+            field.set(pos.x, pos.y, pos.z, material_id);
+            density(pos, material_id);
+            stamped = true;
+        }
+        stamped
+    }
+}
+
+/// Callback stamping density for one voxel of a [`PlaceTemplate`]: the
+/// chunk's density, the grid position and the template material there.
+pub type DensityStamp = Arc<dyn Fn(&mut DensityField, UVec3, u8) + Send + Sync>;
+
+/// Stamps a [`MaterialTemplate`] at a world position across every chunk it
+/// overlaps.
+///
+/// Chunks are placed like for [`PaintCommand`](super::PaintCommand). Placements
+/// wait until the template asset has loaded. Stamped chunks, and neighbors
+/// whose boundary blending reads them, get [`MaterialFieldDirty`].
+///
+/// # Example
+/// ```ignore
+/// fn spawn_ruin(mut place: MessageWriter<PlaceTemplate>, ruin: Res<RuinTemplate>, at: Vec3) {
+///     // Solid wherever the ruin has stone
+///     place.write(PlaceTemplate::new(ruin.0.clone(), at).with_density(|density, pos, _| {
+///         density.set(pos.x, pos.y, pos.z, -1.0);
+///     }));
+/// }
+/// ```
+#[derive(Message, Clone)]
+pub struct PlaceTemplate {
+    pub template: Handle<MaterialTemplate>,
+    /// World position of the template's anchor.
+    pub position: Vec3,
+    /// Density callback; `None` stamps materials only.
+    pub density: Option<DensityStamp>,
+}
+
+impl PlaceTemplate {
+    /// Stamps `template` with its anchor at `position`, materials only.
+    pub fn new(template: Handle<MaterialTemplate>, position: Vec3) -> Self {
+        Self {
+            template,
+            position,
+            density: None,
+        }
+    }
+
+    /// Also stamp density through `stamp` on chunks with a [`DensityField`].
+    pub fn with_density(
+        mut self,
+        stamp: impl Fn(&mut DensityField, UVec3, u8) + Send + Sync + 'static,
+    ) -> Self {
+        self.density = Some(Arc::new(stamp));
+        self
+    }
+}
+
+impl fmt::Debug for PlaceTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlaceTemplate")
+            .field("template", &self.template)
+            .field("position", &self.position)
+            .field("density", &self.density.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// System applying [`PlaceTemplate`] messages, retrying ones whose
+/// template hasn't loaded yet.
+#[allow(clippy::type_complexity)]
+pub fn place_templates(
+    mut commands: Commands,
+    mut place: MessageReader<PlaceTemplate>,
+    mut pending: Local<Vec<PlaceTemplate>>,
+    templates: Res<Assets<MaterialTemplate>>,
+    mut chunks: Query<(
+        Entity,
+        &GlobalTransform,
+        &mut MaterialField,
+        Option<&mut DensityField>,
+    )>,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+) {
+    let grid_scale = grid_scale(mesh_size.as_deref());
+    let margin = NEIGHBOR_DEPTH as i32;
+    let mut queued: Vec<PlaceTemplate> = pending.drain(..).collect();
+    queued.extend(place.read().cloned());
+    let mut touched = Vec::new();
+
+    for placement in queued {
+        let Some(template) = templates.get(&placement.template) else {
+            pending.push(placement);
+            continue;
+        };
+        let mut stamped = false;
+        touched.clear();
+
+        for (entity, transform, mut materials, mut density) in &mut chunks {
+            let to_grid = Affine3A::from_scale(grid_scale) * transform.affine().inverse();
+            let at = to_grid
+                .transform_point3(placement.position)
+                .round()
+                .as_ivec3();
+            let min = at - template.anchor.as_ivec3();
+            let max = min + template.size.as_ivec3() - IVec3::ONE;
+            if max.cmplt(IVec3::splat(-margin)).any()
+                || min.cmpgt(FIELD_SIZE.as_ivec3() - IVec3::ONE + margin).any()
+            {
+                continue;
+            }
+            touched.push(entity);
+
+            let changed = template.stamp(&mut materials, at, |pos, material_id| {
+                if let (Some(stamp), Some(density)) = (&placement.density, density.as_mut()) {
+                    stamp(density, pos, material_id);
+                }
+            });
+            stamped |= changed;
+        }
+
+        if stamped {
+            for &entity in &touched {
+                commands.entity(entity).insert(MaterialFieldDirty);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp_clips_and_skips_empty_cells() {
+        let mut template = MaterialTemplate::new(uvec3(2, 2, 2)).with_anchor(uvec3(1, 0, 0));
+        template.fill(3);
+        template.set(uvec3(1, 1, 1), None);
+
+        let mut field = MaterialField::new();
+        let mut density_calls = 0;
+        assert!(template.stamp(&mut field, ivec3(0, 4, 4), |_, _| density_calls += 1));
+
+        // The x = -1 half falls outside the field
+        assert_eq!(density_calls, 3);
+        assert_eq!(field.get(0, 4, 4), 3);
+        assert_eq!(field.get(0, 5, 5), 0);
+        assert!(!template.stamp(&mut field, ivec3(40, 4, 4), |_, _| {}));
+    }
+
+    #[test]
+    fn test_place_template_spans_chunks() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<MaterialTemplate>()
+            .add_message::<PlaceTemplate>()
+            .add_systems(Update, place_templates);
+
+        let mut template = MaterialTemplate::new(uvec3(4, 1, 1));
+        template.fill(7);
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<MaterialTemplate>>()
+            .add(template);
+
+        let left = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::IDENTITY,
+                MaterialField::new(),
+                DensityField::new(),
+            ))
+            .id();
+        let right = app
+            .world_mut()
+            .spawn((
+                GlobalTransform::from_translation(vec3(32.0, 0.0, 0.0)),
+                MaterialField::new(),
+            ))
+            .id();
+
+        app.world_mut().write_message(
+            PlaceTemplate::new(handle, vec3(30.0, 8.0, 8.0)).with_density(|density, pos, _| {
+                density.set(pos.x, pos.y, pos.z, -1.0);
+            }),
+        );
+        app.update();
+
+        let world = app.world();
+        let left_field = world.get::<MaterialField>(left).unwrap();
+        assert_eq!(left_field.get(30, 8, 8), 7);
+        assert_eq!(left_field.get(31, 8, 8), 7);
+        assert_eq!(world.get::<DensityField>(left).unwrap().get(31, 8, 8), -1.0);
+        let right_field = world.get::<MaterialField>(right).unwrap();
+        assert_eq!(right_field.get(0, 8, 8), 7);
+        assert_eq!(right_field.get(1, 8, 8), 7);
+        assert!(world.get::<MaterialFieldDirty>(right).is_some());
+    }
+}
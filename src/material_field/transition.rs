@@ -0,0 +1,209 @@
+//! Cross-fading repainted chunks instead of popping.
+//!
+//! [`remesh_dirty_chunks`](super::remesh_dirty_chunks) hands the new
+//! attributes of chunks with a [`MaterialTransition`] to the component
+//! instead of writing them straight into the mesh. Each frame,
+//! [`animate_material_transitions`] then blends every vertex from the
+//! attributes it had before towards the new ones with
+//! [`VertexMaterialData::lerp`], all vertices at the same progress.
+
+use bevy::prelude::*;
+
+use crate::mesh::{MeshTriplanarExt, VertexMaterialData, material_attributes, unpack_vertex};
+
+/// Fade this chunk's material attributes over time when it is remeshed.
+///
+/// Only CPU remeshing fades; chunks whose vertex count changed (sculpted
+/// rather than repainted) switch immediately. The mesh is re-uploaded every
+/// frame while a fade runs.
+///
+/// # Example
+/// ```ignore
+/// commands.entity(chunk).insert(MaterialTransition::new(0.75));
+/// ```
+#[derive(Component, Clone, Debug)]
+pub struct MaterialTransition {
+    /// Seconds a fade takes.
+    /// Default: 0.5
+    pub duration: f32,
+    elapsed: f32,
+    /// Unpacked attributes at the start of the fade.
+    before: Vec<VertexMaterialData>,
+    /// Unpacked attributes to fade to.
+    after: Vec<VertexMaterialData>,
+}
+
+impl Default for MaterialTransition {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl MaterialTransition {
+    /// Fade over `duration` seconds.
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            elapsed: 0.0,
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Whether a fade is running.
+    pub fn is_active(&self) -> bool {
+        !self.after.is_empty()
+    }
+
+    /// Progress of the running fade, from 0 to 1; 1 when idle.
+    pub fn progress(&self) -> f32 {
+        if !self.is_active() || self.duration <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.duration).min(1.0)
+    }
+
+    /// Start fading `mesh` from its current attributes to `ids` and
+    /// `weights`.
+    ///
+    /// Returns `false`, leaving the transition idle, if the mesh has no
+    /// attributes yet or a different vertex count.
+    pub(crate) fn start(&mut self, mesh: &Mesh, ids: &[u32], weights: &[u32]) -> bool {
+        let Some((old_ids, old_weights)) = material_attributes(mesh) else {
+            return false;
+        };
+        if old_ids.len() != ids.len() || self.duration <= 0.0 {
+            return false;
+        }
+        // Restarting mid-fade starts from what is on screen
+        self.before = old_ids
+            .iter()
+            .zip(old_weights)
+            .map(|(&ids, &weights)| unpack_vertex(ids, weights))
+            .collect();
+        self.after = ids
+            .iter()
+            .zip(weights)
+            .map(|(&ids, &weights)| unpack_vertex(ids, weights))
+            .collect();
+        self.elapsed = 0.0;
+        true
+    }
+}
+
+/// System advancing [`MaterialTransition`]s and writing the blended
+/// attributes into their chunk meshes.
+pub fn animate_material_transitions(
+    time: Res<Time>,
+    mut chunks: Query<(&Mesh3d, &mut MaterialTransition)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut scratch: Local<(Vec<u32>, Vec<u32>)>,
+) {
+    let (ids, weights) = &mut *scratch;
+    for (mesh, mut transition) in &mut chunks {
+        if !transition.is_active() {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {
+            continue;
+        };
+        if mesh.count_vertices() != transition.after.len() {
+            // Remeshed with new geometry since the fade started
+            transition.before.clear();
+            transition.after.clear();
+            continue;
+        }
+
+        transition.elapsed += time.delta_secs();
+        let t = transition.progress();
+        ids.clear();
+        weights.clear();
+        for (before, after) in transition.before.iter().zip(&transition.after) {
+            let blended = before.lerp(after, t);
+            ids.push(blended.pack_ids());
+            weights.push(blended.pack_weights());
+        }
+        mesh.set_triplanar_attributes(ids, weights);
+
+        if t >= 1.0 {
+            transition.before.clear();
+            transition.after.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::PrimitiveTopology;
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS, MeshMaterialQueryExt};
+
+    #[test]
+    fn test_transition_fades_to_new_attributes() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                250,
+            )))
+            .add_systems(Update, animate_material_transitions);
+
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; 3]);
+        mesh.insert_attribute(ATTRIBUTE_MATERIAL_IDS, vec![1u32; 3]);
+        mesh.insert_attribute(ATTRIBUTE_MATERIAL_WEIGHTS, vec![255u32; 3]);
+
+        let mut transition = MaterialTransition::new(1.0);
+        assert!(!transition.start(&mesh, &[2; 2], &[255; 2]));
+        assert!(transition.start(&mesh, &[2; 3], &[255; 3]));
+        let mesh = app.world_mut().resource_mut::<Assets<Mesh>>().add(mesh);
+        let chunk = app
+            .world_mut()
+            .spawn((Mesh3d(mesh.clone()), transition))
+            .id();
+
+        // At most halfway, so the old material is still at least as heavy
+        app.update();
+        app.update();
+        let (ids, _) = app
+            .world()
+            .resource::<Assets<Mesh>>()
+            .get(&mesh)
+            .unwrap()
+            .material_at_triangle(0)
+            .unwrap();
+        assert_eq!(ids[0], 1);
+        assert!(
+            app.world()
+                .get::<MaterialTransition>(chunk)
+                .unwrap()
+                .is_active()
+        );
+
+        for _ in 0..4 {
+            app.update();
+        }
+        let (ids, weights) = app
+            .world()
+            .resource::<Assets<Mesh>>()
+            .get(&mesh)
+            .unwrap()
+            .material_at_triangle(0)
+            .unwrap();
+        assert_eq!((ids[0], weights[0]), (2, 255));
+        assert!(
+            !app.world()
+                .get::<MaterialTransition>(chunk)
+                .unwrap()
+                .is_active()
+        );
+    }
+}
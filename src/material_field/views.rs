@@ -0,0 +1,70 @@
+//! Camera distances for view-dependent chunk updates.
+
+use bevy::camera::RenderTarget;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Positions of every active camera, so split-screen and picture-in-picture
+/// views each keep the chunks around them at full quality.
+///
+/// Inactive cameras are ignored, and so are cameras rendering to an image:
+/// the crate's own picking and impostor bake cameras render offscreen and
+/// shouldn't keep chunks at full quality.
+#[derive(SystemParam)]
+pub(crate) struct ActiveCameras<'w, 's> {
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl ActiveCameras<'_, '_> {
+    /// World positions of the active cameras.
+    pub(crate) fn positions(&self) -> Vec<Vec3> {
+        self.cameras
+            .iter()
+            .filter(|(camera, _)| {
+                camera.is_active && !matches!(camera.target, RenderTarget::Image(_))
+            })
+            .map(|(_, transform)| transform.translation())
+            .collect()
+    }
+}
+
+/// Distance from `point` to the nearest of `views`, infinite without any.
+pub(crate) fn nearest_view_distance(views: &[Vec3], point: Vec3) -> f32 {
+    views
+        .iter()
+        .map(|view| view.distance(point))
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn test_offscreen_cameras_are_ignored() {
+        let mut world = World::new();
+        world.spawn((
+            Camera::default(),
+            GlobalTransform::from_translation(Vec3::X),
+        ));
+        world.spawn((
+            Camera {
+                is_active: false,
+                ..default()
+            },
+            GlobalTransform::from_translation(Vec3::Y),
+        ));
+        world.spawn((
+            Camera {
+                target: RenderTarget::Image(Handle::<Image>::default().into()),
+                ..default()
+            },
+            GlobalTransform::from_translation(Vec3::Z),
+        ));
+
+        let mut state = SystemState::<ActiveCameras>::new(&mut world);
+        assert_eq!(state.get(&world).positions(), vec![Vec3::X]);
+    }
+}
@@ -36,3 +36,40 @@ pub const ATTRIBUTE_MATERIAL_IDS: MeshVertexAttribute =
 /// ```
 pub const ATTRIBUTE_MATERIAL_WEIGHTS: MeshVertexAttribute =
     MeshVertexAttribute::new("MaterialWeights", 988540921, VertexFormat::Uint32);
+
+/// Optional vertex attribute with per-vertex auxiliary scalars as
+/// normalized `[u8; 4]`, indexed by [`MaterialParam`].
+///
+/// Unlike material IDs and weights, these interpolate smoothly across
+/// triangles. Meshes without the attribute render unweathered.
+///
+/// # Shader Location
+/// This attribute is bound to location 4 in the vertex shader when present.
+/// (After position=0, normal=1, material_ids=2, material_weights=3)
+///
+/// # Example
+/// ```ignore
+/// // Soaked and slightly scorched
+/// let mesh = mesh.with_material_params(vec![[255, 40, 0, 0]; vertex_count]);
+/// ```
+pub const ATTRIBUTE_MATERIAL_PARAMS: MeshVertexAttribute =
+    MeshVertexAttribute::new("MaterialParams", 988540922, VertexFormat::Unorm8x4);
+
+/// Channels of [`ATTRIBUTE_MATERIAL_PARAMS`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MaterialParam {
+    /// Darkens albedo and smooths roughness, e.g. after rain.
+    Wetness = 0,
+    /// Chars albedo towards black soot.
+    Burn = 1,
+    /// Overgrows albedo with green moss.
+    Moss = 2,
+}
+
+impl MaterialParam {
+    /// Channel index within the packed attribute.
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
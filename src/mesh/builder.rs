@@ -1,13 +1,27 @@
 //! Mesh builder for triplanar voxel meshes.
 
 use bevy::asset::RenderAssetUsages;
-use bevy::mesh::{Indices, Mesh, PrimitiveTopology};
+use bevy::camera::primitives::Aabb;
+use bevy::math::Vec3;
+use bevy::mesh::{Indices, Mesh, MeshVertexAttribute, PrimitiveTopology, VertexAttributeValues};
+use thiserror::Error;
 
 use super::{
-    attributes::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS},
+    attributes::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_PARAMS, ATTRIBUTE_MATERIAL_WEIGHTS},
+    usage::MaterialUsage,
     vertex_data::VertexMaterialData,
 };
 
+/// Problems found by [`TriplanarMeshBuilder::validate`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshValidationError {
+    #[error("Material ID {id} exceeds maximum {max} at vertex {vertex}")]
+    MaterialIdOutOfRange { vertex: usize, id: u8, max: u8 },
+
+    #[error("Index {index} is out of range for {vertex_count} vertices")]
+    IndexOutOfRange { index: u32, vertex_count: usize },
+}
+
 /// Builder for creating meshes with triplanar material attributes.
 ///
 /// This builder collects vertex data (positions, normals, material data)
@@ -36,6 +50,8 @@ pub struct TriplanarMeshBuilder {
     material_weights: Vec<u32>,
     indices: Option<Vec<u32>>,
     max_material_id: Option<u8>,
+    validate_vertices: bool,
+    usage: MaterialUsage,
 }
 
 impl TriplanarMeshBuilder {
@@ -56,13 +72,33 @@ impl TriplanarMeshBuilder {
             material_weights: Vec::with_capacity(vertex_count),
             indices: Some(Vec::with_capacity(index_count)),
             max_material_id: None,
+            validate_vertices: false,
+            usage: MaterialUsage::default(),
+        }
+    }
+
+    /// Create a builder writing into recycled buffers.
+    ///
+    /// The buffers are cleared but keep their capacity, so remeshing a
+    /// chunk of similar size doesn't reallocate.
+    pub fn from_buffers(mut buffers: MeshBuffers) -> Self {
+        buffers.clear();
+        Self {
+            positions: buffers.positions,
+            normals: buffers.normals,
+            material_ids: buffers.material_ids,
+            material_weights: buffers.material_weights,
+            indices: Some(buffers.indices),
+            max_material_id: None,
+            validate_vertices: false,
+            usage: MaterialUsage::default(),
         }
     }
 
     /// Set the maximum valid material ID for validation.
     ///
-    /// When set, debug builds will panic if any vertex uses a material ID
-    /// greater than this value.
+    /// Checked by [`validate`](Self::validate), and per vertex in debug
+    /// builds with [`with_validation`](Self::with_validation).
     ///
     /// This is typically set to `palette.materials.len() - 1`.
     pub fn with_max_material_id(mut self, max_id: u8) -> Self {
@@ -70,6 +106,17 @@ impl TriplanarMeshBuilder {
         self
     }
 
+    /// Check every pushed vertex against the maximum material ID, panicking
+    /// on the first invalid one in debug builds.
+    ///
+    /// Off by default: the per-vertex loop makes debug-profile meshing of
+    /// large worlds very slow. Prefer a single [`validate`](Self::validate)
+    /// call before [`build`](Self::build).
+    pub fn with_validation(mut self, enable: bool) -> Self {
+        self.validate_vertices = enable;
+        self
+    }
+
     /// Add a vertex with a single material.
     ///
     /// Convenience method equivalent to:
@@ -109,7 +156,9 @@ impl TriplanarMeshBuilder {
         material_data: VertexMaterialData,
     ) {
         #[cfg(debug_assertions)]
-        if let Some(max_id) = self.max_material_id {
+        if self.validate_vertices
+            && let Some(max_id) = self.max_material_id
+        {
             for (i, &id) in material_data.ids.iter().enumerate() {
                 if material_data.weights[i] > 0 {
                     debug_assert!(
@@ -123,6 +172,7 @@ impl TriplanarMeshBuilder {
             }
         }
 
+        self.usage.insert_vertex(&material_data);
         self.positions.push(position);
         self.normals.push(normal);
         self.material_ids.push(material_data.pack_ids());
@@ -159,6 +209,61 @@ impl TriplanarMeshBuilder {
         self.indices.as_ref().map(|i| i.len()).unwrap_or(0)
     }
 
+    /// Materials used by the vertices added so far.
+    ///
+    /// Tracked as vertices are pushed, so this is free to call before
+    /// [`build`](Self::build) consumes the builder.
+    pub fn material_usage(&self) -> MaterialUsage {
+        self.usage
+    }
+
+    /// Bounds of the vertices added so far, or `None` without vertices.
+    ///
+    /// Insert it next to the built mesh's `Mesh3d` so the chunk is frustum
+    /// culled correctly from its first frame.
+    pub fn aabb(&self) -> Option<Aabb> {
+        Aabb::enclosing(self.positions.iter().map(|&p| Vec3::from_array(p)))
+    }
+
+    /// Check the vertices and indices added so far.
+    ///
+    /// Material IDs are checked against
+    /// [`with_max_material_id`](Self::with_max_material_id) through the
+    /// tracked [`material_usage`](Self::material_usage), so only meshes with
+    /// an invalid ID are scanned to find its vertex. Works in every build
+    /// profile.
+    pub fn validate(&self) -> Result<(), MeshValidationError> {
+        if let Some(max) = self.max_material_id
+            && self.usage.iter().any(|id| id > max)
+        {
+            let invalid = self
+                .material_ids
+                .iter()
+                .zip(&self.material_weights)
+                .enumerate()
+                .find_map(|(vertex, (&ids, &weights))| {
+                    let (ids, weights) = (ids.to_le_bytes(), weights.to_le_bytes());
+                    (0..4)
+                        .find(|&i| weights[i] > 0 && ids[i] > max)
+                        .map(|i| (vertex, ids[i]))
+                });
+            if let Some((vertex, id)) = invalid {
+                return Err(MeshValidationError::MaterialIdOutOfRange { vertex, id, max });
+            }
+        }
+
+        let vertex_count = self.positions.len();
+        if let Some(&index) = self
+            .indices
+            .iter()
+            .flatten()
+            .find(|&&index| index as usize >= vertex_count)
+        {
+            return Err(MeshValidationError::IndexOutOfRange { index, vertex_count });
+        }
+        Ok(())
+    }
+
     /// Build the final mesh.
     ///
     /// Returns `None` if there are no vertices or indices.
@@ -195,6 +300,71 @@ impl TriplanarMeshBuilder {
     }
 }
 
+/// Vertex and index vectors kept around between remeshes.
+///
+/// Reclaim them from a mesh that is being replaced and hand them to
+/// [`TriplanarMeshBuilder::from_buffers`] to reuse their capacity.
+#[derive(Default, Debug)]
+pub struct MeshBuffers {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub material_ids: Vec<u32>,
+    pub material_weights: Vec<u32>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshBuffers {
+    /// Takes the position, normal, material and index buffers out of
+    /// `mesh`. Attributes in an unexpected format are left in place and
+    /// their buffer starts empty.
+    pub fn reclaim(mesh: &mut Mesh) -> Self {
+        let mut buffers = Self::default();
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.remove_attribute(Mesh::ATTRIBUTE_POSITION)
+        {
+            buffers.positions = positions;
+        }
+        if let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.remove_attribute(Mesh::ATTRIBUTE_NORMAL)
+        {
+            buffers.normals = normals;
+        }
+        if let Some(VertexAttributeValues::Uint32(ids)) =
+            mesh.remove_attribute(ATTRIBUTE_MATERIAL_IDS)
+        {
+            buffers.material_ids = ids;
+        }
+        if let Some(VertexAttributeValues::Uint32(weights)) =
+            mesh.remove_attribute(ATTRIBUTE_MATERIAL_WEIGHTS)
+        {
+            buffers.material_weights = weights;
+        }
+        if let Some(Indices::U32(indices)) = mesh.remove_indices() {
+            buffers.indices = indices;
+        }
+        buffers
+    }
+
+    /// Empties every buffer, keeping capacity.
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.normals.clear();
+        self.material_ids.clear();
+        self.material_weights.clear();
+        self.indices.clear();
+    }
+
+    /// Total bytes of capacity held.
+    pub fn capacity_bytes(&self) -> usize {
+        self.positions.capacity() * size_of::<[f32; 3]>()
+            + self.normals.capacity() * size_of::<[f32; 3]>()
+            + (self.material_ids.capacity()
+                + self.material_weights.capacity()
+                + self.indices.capacity())
+                * size_of::<u32>()
+    }
+}
+
 /// Extension trait for adding triplanar material data to existing meshes.
 pub trait MeshTriplanarExt {
     /// Add material attributes to an existing mesh.
@@ -205,8 +375,34 @@ pub trait MeshTriplanarExt {
     /// Panics if `material_data.len()` doesn't match the vertex count.
     fn with_triplanar_materials(self, material_data: &[VertexMaterialData]) -> Self;
 
+    /// Overwrite the packed material ID and weight attributes in place.
+    ///
+    /// Existing `Uint32` buffers are reused, so repainting a mesh copies
+    /// into them instead of allocating new attributes or swapping the
+    /// mesh handle; positions, normals and indices are untouched. Missing
+    /// attributes are inserted.
+    ///
+    /// # Panics
+    /// Panics if either slice's length doesn't match the vertex count.
+    fn set_triplanar_attributes(&mut self, ids: &[u32], weights: &[u32]);
+
     /// Add uniform material to all vertices.
     fn with_uniform_material(self, material_id: u8) -> Self;
+
+    /// Add per-vertex [`ATTRIBUTE_MATERIAL_PARAMS`] (wetness, burn, moss).
+    ///
+    /// # Panics
+    /// Panics if `params.len()` doesn't match the vertex count.
+    fn with_material_params(self, params: Vec<[u8; 4]>) -> Self;
+
+    /// Add lightmap UVs ([`Mesh::ATTRIBUTE_UV_1`]) for Bevy's
+    /// [`Lightmap`](bevy::pbr::Lightmap) component.
+    ///
+    /// Triplanar texturing ignores them; only baked lighting uses them.
+    ///
+    /// # Panics
+    /// Panics if `uvs.len()` doesn't match the vertex count.
+    fn with_lightmap_uvs(self, uvs: Vec<[f32; 2]>) -> Self;
 }
 
 impl MeshTriplanarExt for Mesh {
@@ -233,6 +429,20 @@ impl MeshTriplanarExt for Mesh {
         self
     }
 
+    fn set_triplanar_attributes(&mut self, ids: &[u32], weights: &[u32]) {
+        let vertex_count = self.count_vertices();
+        assert!(
+            ids.len() == vertex_count && weights.len() == vertex_count,
+            "Material attribute lengths ({}, {}) must match vertex count ({})",
+            ids.len(),
+            weights.len(),
+            vertex_count
+        );
+
+        write_u32_attribute(self, ATTRIBUTE_MATERIAL_IDS, ids);
+        write_u32_attribute(self, ATTRIBUTE_MATERIAL_WEIGHTS, weights);
+    }
+
     fn with_uniform_material(self, material_id: u8) -> Self {
         let vertex_count = self
             .attribute(Mesh::ATTRIBUTE_POSITION)
@@ -242,12 +452,84 @@ impl MeshTriplanarExt for Mesh {
         let data = vec![VertexMaterialData::single(material_id); vertex_count];
         self.with_triplanar_materials(&data)
     }
+
+    fn with_material_params(mut self, params: Vec<[u8; 4]>) -> Self {
+        let vertex_count = self
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .map(|a| a.len())
+            .unwrap_or(0);
+
+        assert_eq!(
+            params.len(),
+            vertex_count,
+            "Material params length ({}) must match vertex count ({})",
+            params.len(),
+            vertex_count
+        );
+
+        self.insert_attribute(
+            ATTRIBUTE_MATERIAL_PARAMS,
+            VertexAttributeValues::Unorm8x4(params),
+        );
+        self
+    }
+
+    fn with_lightmap_uvs(mut self, uvs: Vec<[f32; 2]>) -> Self {
+        let vertex_count = self
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .map(|a| a.len())
+            .unwrap_or(0);
+
+        assert_eq!(
+            uvs.len(),
+            vertex_count,
+            "Lightmap UV length ({}) must match vertex count ({})",
+            uvs.len(),
+            vertex_count
+        );
+
+        self.insert_attribute(Mesh::ATTRIBUTE_UV_1, uvs);
+        self
+    }
+}
+
+/// Copies `values` into `mesh`'s existing `Uint32` attribute, keeping its
+/// allocation, or inserts it.
+fn write_u32_attribute(mesh: &mut Mesh, attribute: MeshVertexAttribute, values: &[u32]) {
+    if let Some(VertexAttributeValues::Uint32(existing)) = mesh.attribute_mut(attribute) {
+        existing.clear();
+        existing.extend_from_slice(values);
+    } else {
+        mesh.insert_attribute(attribute, values.to_vec());
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_reclaimed_buffers_keep_capacity() {
+        let mut builder = TriplanarMeshBuilder::with_capacity(300, 900);
+        for i in 0..300 {
+            builder.push_vertex([i as f32, 0.0, 0.0], [0.0, 1.0, 0.0], VertexMaterialData::single(1));
+        }
+        builder.push_indices(&[0; 900]);
+        let mut mesh = builder.build_unwrap();
+
+        let buffers = MeshBuffers::reclaim(&mut mesh);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_POSITION).is_none());
+        assert!(buffers.positions.capacity() >= 300);
+        assert!(buffers.indices.capacity() >= 900);
+
+        let mut builder = TriplanarMeshBuilder::from_buffers(buffers);
+        assert_eq!(builder.vertex_count(), 0);
+        builder.push_vertex([0.0; 3], [0.0, 1.0, 0.0], VertexMaterialData::single(2));
+        builder.push_triangle(0, 0, 0);
+        assert!(builder.positions.capacity() >= 300);
+        assert!(builder.build().is_some());
+    }
+
     #[test]
     fn test_builder_basic() {
         let mesh = TriplanarMeshBuilder::new()
@@ -268,6 +550,72 @@ mod tests {
         assert!(mesh.attribute(Mesh::ATTRIBUTE_UV_0).is_none());
     }
 
+    #[test]
+    fn test_validate() {
+        let mut builder = TriplanarMeshBuilder::new()
+            .with_max_material_id(3)
+            .with_vertex_single([0.0, 0.0, 0.0], [0.0, 1.0, 0.0], 1)
+            .with_vertex_single([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], 3)
+            .with_vertex([0.5, 0.0, 1.0], [0.0, 1.0, 0.0], VertexMaterialData::single(2))
+            .with_indices(vec![0, 1, 2]);
+        assert_eq!(builder.validate(), Ok(()));
+
+        // Without `with_validation`, pushing doesn't panic even in debug builds
+        builder.push_vertex([0.0; 3], [0.0, 1.0, 0.0], VertexMaterialData::blend2_half(2, 7));
+        assert_eq!(
+            builder.validate(),
+            Err(MeshValidationError::MaterialIdOutOfRange { vertex: 3, id: 7, max: 3 })
+        );
+
+        let builder = TriplanarMeshBuilder::new()
+            .with_vertex_single([0.0, 0.0, 0.0], [0.0, 1.0, 0.0], 1)
+            .with_indices(vec![0, 0, 1]);
+        assert_eq!(
+            builder.validate(),
+            Err(MeshValidationError::IndexOutOfRange { index: 1, vertex_count: 1 })
+        );
+    }
+
+    #[test]
+    fn test_set_triplanar_attributes_reuses_buffers() {
+        let mut mesh = TriplanarMeshBuilder::new()
+            .with_vertex_single([0.0, 0.0, 0.0], [0.0, 1.0, 0.0], 1)
+            .with_vertex_single([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], 1)
+            .with_vertex_single([0.5, 0.0, 1.0], [0.0, 1.0, 0.0], 1)
+            .with_indices(vec![0, 1, 2])
+            .build_unwrap();
+        let ids_ptr = |mesh: &Mesh| match mesh.attribute(ATTRIBUTE_MATERIAL_IDS) {
+            Some(VertexAttributeValues::Uint32(ids)) => ids.as_ptr(),
+            _ => panic!("missing material ids"),
+        };
+        let before = ids_ptr(&mesh);
+
+        let data = VertexMaterialData::blend2_half(2, 5);
+        mesh.set_triplanar_attributes(&[data.pack_ids(); 3], &[data.pack_weights(); 3]);
+
+        assert_eq!(ids_ptr(&mesh), before);
+        let Some(VertexAttributeValues::Uint32(weights)) =
+            mesh.attribute(ATTRIBUTE_MATERIAL_WEIGHTS)
+        else {
+            panic!("missing material weights");
+        };
+        assert_eq!(weights, &vec![data.pack_weights(); 3]);
+    }
+
+    #[test]
+    fn test_builder_aabb() {
+        assert_eq!(TriplanarMeshBuilder::new().aabb(), None);
+
+        let builder = TriplanarMeshBuilder::new()
+            .with_vertex_single([0.0, -1.0, 0.0], [0.0, 1.0, 0.0], 1)
+            .with_vertex_single([2.0, 0.0, 0.0], [0.0, 1.0, 0.0], 1)
+            .with_vertex_single([0.5, 0.0, 4.0], [0.0, 1.0, 0.0], 1);
+        assert_eq!(
+            builder.aabb(),
+            Some(Aabb::from_min_max(Vec3::new(0.0, -1.0, 0.0), Vec3::new(2.0, 0.0, 4.0)))
+        );
+    }
+
     #[test]
     fn test_builder_empty_returns_none() {
         assert!(TriplanarMeshBuilder::new().build().is_none());
@@ -278,6 +626,15 @@ mod tests {
             .is_none()); // No indices
     }
 
+    #[test]
+    fn test_builder_tracks_usage() {
+        let builder = TriplanarMeshBuilder::new()
+            .with_vertex_single([0.0, 0.0, 0.0], [0.0, 1.0, 0.0], 4)
+            .with_vertex([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], VertexMaterialData::blend2_half(4, 9));
+
+        assert_eq!(builder.material_usage().iter().collect::<Vec<_>>(), vec![4, 9]);
+    }
+
     #[test]
     fn test_mesh_extension() {
         let mut mesh = Mesh::new(
@@ -298,4 +655,40 @@ mod tests {
         assert!(mesh.attribute(ATTRIBUTE_MATERIAL_IDS).is_some());
         assert!(mesh.attribute(ATTRIBUTE_MATERIAL_WEIGHTS).is_some());
     }
+
+    #[test]
+    fn test_mesh_lightmap_uvs() {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.5, 1.0, 0.0]],
+        );
+
+        let mesh = mesh.with_lightmap_uvs(vec![[0.0, 0.0], [1.0, 0.0], [0.5, 1.0]]);
+
+        assert_eq!(mesh.attribute(Mesh::ATTRIBUTE_UV_1).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_mesh_material_params() {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.5, 1.0, 0.0]],
+        );
+
+        let mesh = mesh.with_material_params(vec![[255, 0, 0, 0]; 3]);
+
+        let Some(VertexAttributeValues::Unorm8x4(params)) = mesh.attribute(ATTRIBUTE_MATERIAL_PARAMS)
+        else {
+            panic!("missing material params");
+        };
+        assert_eq!(params, &vec![[255, 0, 0, 0]; 3]);
+    }
 }
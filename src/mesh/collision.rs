@@ -0,0 +1,166 @@
+//! Material-aware collision mesh generation.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, Mesh, PrimitiveTopology, VertexAttributeValues};
+
+use super::query::{dominant_triangle_material, material_attributes, triangles};
+
+/// Split a triplanar mesh into one collision mesh per dominant material.
+///
+/// Each triangle is assigned to the material with the highest summed weight
+/// across its three vertices. Vertices are re-indexed per submesh, so each
+/// returned mesh only contains the positions (and normals, if present) it
+/// references.
+///
+/// This lets physics engines assign per-material properties (ice friction,
+/// lava damage) to regions of a single render mesh.
+///
+/// Returned meshes use [`RenderAssetUsages::MAIN_WORLD`] since they are not
+/// meant to be rendered. Results are sorted by material ID.
+///
+/// Returns an empty `Vec` if the mesh has no positions or no material attributes.
+///
+/// # Example
+/// ```ignore
+/// for (material_id, collider_mesh) in generate_collision_submeshes(&mesh) {
+///     commands.spawn((
+///         Collider::from_mesh(&collider_mesh),
+///         Friction(friction_for(material_id)),
+///     ));
+/// }
+/// ```
+pub fn generate_collision_submeshes(mesh: &Mesh) -> Vec<(u8, Mesh)> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return Vec::new();
+    };
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) if normals.len() == positions.len() => {
+            Some(normals)
+        }
+        _ => None,
+    };
+    let Some((ids, weights)) = material_attributes(mesh) else {
+        return Vec::new();
+    };
+
+    // Bucket triangles by dominant material, indexed by material ID
+    let mut buckets: Vec<Vec<[usize; 3]>> = vec![Vec::new(); 256];
+    for triangle in triangles(mesh) {
+        if triangle.iter().any(|&v| v >= positions.len()) {
+            continue;
+        }
+        if let Some(material) = dominant_triangle_material(triangle, ids, weights) {
+            buckets[material as usize].push(triangle);
+        }
+    }
+
+    let mut remap = vec![u32::MAX; positions.len()];
+    let mut result = Vec::new();
+
+    for (material, bucket) in buckets.iter().enumerate() {
+        if bucket.is_empty() {
+            continue;
+        }
+
+        let mut sub_positions = Vec::new();
+        let mut sub_normals = Vec::new();
+        let mut sub_indices = Vec::with_capacity(bucket.len() * 3);
+        let mut touched = Vec::new();
+
+        for triangle in bucket {
+            for &v in triangle {
+                if remap[v] == u32::MAX {
+                    remap[v] = sub_positions.len() as u32;
+                    sub_positions.push(positions[v]);
+                    if let Some(normals) = normals {
+                        sub_normals.push(normals[v]);
+                    }
+                    touched.push(v);
+                }
+                sub_indices.push(remap[v]);
+            }
+        }
+
+        // Reset only the entries we touched
+        for v in touched {
+            remap[v] = u32::MAX;
+        }
+
+        let mut sub_mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::MAIN_WORLD,
+        );
+        sub_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, sub_positions);
+        if normals.is_some() {
+            sub_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, sub_normals);
+        }
+        sub_mesh.insert_indices(Indices::U32(sub_indices));
+
+        result.push((material as u8, sub_mesh));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{TriplanarMeshBuilder, VertexMaterialData};
+
+    #[test]
+    fn test_split_by_dominant_material() {
+        let up = [0.0, 1.0, 0.0];
+        let mesh = TriplanarMeshBuilder::new()
+            // Triangle 0: all material 1
+            .with_vertex_single([0.0, 0.0, 0.0], up, 1)
+            .with_vertex_single([1.0, 0.0, 0.0], up, 1)
+            .with_vertex_single([0.0, 0.0, 1.0], up, 1)
+            // Triangle 1: mostly material 3
+            .with_vertex_single([2.0, 0.0, 0.0], up, 3)
+            .with_vertex_single([3.0, 0.0, 0.0], up, 3)
+            .with_vertex([2.0, 0.0, 1.0], up, VertexMaterialData::blend2(3, 1, 0.5))
+            .with_indices(vec![0, 1, 2, 3, 4, 5])
+            .build_unwrap();
+
+        let submeshes = generate_collision_submeshes(&mesh);
+
+        assert_eq!(submeshes.len(), 2);
+        assert_eq!(submeshes[0].0, 1);
+        assert_eq!(submeshes[1].0, 3);
+
+        for (_, sub_mesh) in &submeshes {
+            assert_eq!(sub_mesh.count_vertices(), 3);
+            assert_eq!(sub_mesh.indices().map(|i| i.len()), Some(3));
+            assert!(sub_mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+        }
+    }
+
+    #[test]
+    fn test_shared_vertices_are_reindexed() {
+        let up = [0.0, 1.0, 0.0];
+        let mesh = TriplanarMeshBuilder::new()
+            .with_vertex_single([0.0, 0.0, 0.0], up, 2)
+            .with_vertex_single([1.0, 0.0, 0.0], up, 2)
+            .with_vertex_single([0.0, 0.0, 1.0], up, 2)
+            .with_vertex_single([1.0, 0.0, 1.0], up, 2)
+            .with_indices(vec![0, 1, 2, 2, 1, 3])
+            .build_unwrap();
+
+        let submeshes = generate_collision_submeshes(&mesh);
+
+        assert_eq!(submeshes.len(), 1);
+        assert_eq!(submeshes[0].1.count_vertices(), 4);
+        assert_eq!(submeshes[0].1.indices().map(|i| i.len()), Some(6));
+    }
+
+    #[test]
+    fn test_missing_attributes_returns_empty() {
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        assert!(generate_collision_submeshes(&mesh).is_empty());
+    }
+}
@@ -11,12 +11,25 @@ use bevy::prelude::*;
 
 mod attributes;
 mod builder;
+mod collision;
+mod query;
+mod swap;
+mod usage;
 mod vertex_data;
 
-pub use attributes::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS};
-pub use builder::{MeshTriplanarExt, TriplanarMeshBuilder};
+pub use attributes::{
+    ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_PARAMS, ATTRIBUTE_MATERIAL_WEIGHTS, MaterialParam,
+};
+pub use builder::{MeshBuffers, MeshTriplanarExt, MeshValidationError, TriplanarMeshBuilder};
+pub use collision::generate_collision_submeshes;
+pub use query::MeshMaterialQueryExt;
+pub use swap::{DoubleBufferedMesh, PendingMesh};
+pub use usage::MaterialUsage;
 pub use vertex_data::VertexMaterialData;
 
+pub(crate) use query::{dominant_triangle_material, material_attributes, triangles, unpack_vertex};
+pub(crate) use swap::build_mesh_swapping;
+
 /// Packs material data into a vertex color value.
 /// 
 /// Material IDs go into the R channel, weights into G channel.
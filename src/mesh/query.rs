@@ -0,0 +1,260 @@
+//! Reading triplanar material attributes back out of a mesh.
+//!
+//! Useful for converting physics raycast hits into material information
+//! without re-sampling the voxel fields.
+
+use bevy::math::Vec3;
+use bevy::mesh::{Mesh, VertexAttributeValues};
+
+use super::{
+    attributes::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS},
+    vertex_data::{VertexMaterialData, quantize_weights},
+};
+
+/// Borrow the packed material ID and weight attributes of a mesh.
+///
+/// Returns `None` if either attribute is missing or has an unexpected format.
+pub(crate) fn material_attributes(mesh: &Mesh) -> Option<(&[u32], &[u32])> {
+    let Some(VertexAttributeValues::Uint32(ids)) = mesh.attribute(ATTRIBUTE_MATERIAL_IDS) else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Uint32(weights)) = mesh.attribute(ATTRIBUTE_MATERIAL_WEIGHTS)
+    else {
+        return None;
+    };
+    Some((ids, weights))
+}
+
+/// Collect the triangle vertex indices of a mesh.
+///
+/// Non-indexed meshes are treated as a plain triangle list.
+pub(crate) fn triangles(mesh: &Mesh) -> Vec<[usize; 3]> {
+    match mesh.indices() {
+        Some(indices) => {
+            let flat: Vec<usize> = indices.iter().collect();
+            flat.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect()
+        }
+        None => {
+            let count = mesh.count_vertices();
+            (0..count / 3)
+                .map(|t| [t * 3, t * 3 + 1, t * 3 + 2])
+                .collect()
+        }
+    }
+}
+
+/// Vertex indices of a single triangle, or `None` if out of range.
+pub(crate) fn triangle(mesh: &Mesh, triangle_index: usize) -> Option<[usize; 3]> {
+    let start = triangle_index.checked_mul(3)?;
+    match mesh.indices() {
+        Some(indices) => {
+            let mut iter = indices.iter().skip(start);
+            Some([iter.next()?, iter.next()?, iter.next()?])
+        }
+        None => (start + 3 <= mesh.count_vertices()).then_some([start, start + 1, start + 2]),
+    }
+}
+
+/// Unpack a vertex's material data from packed attribute values.
+#[inline]
+pub(crate) fn unpack_vertex(packed_ids: u32, packed_weights: u32) -> VertexMaterialData {
+    VertexMaterialData {
+        ids: packed_ids.to_le_bytes(),
+        weights: packed_weights.to_le_bytes(),
+    }
+}
+
+/// Accumulate material weights of several vertices, summing weights per material.
+///
+/// Returns `(material_id, total_weight)` pairs sorted by weight descending,
+/// with ties broken by lower material ID for determinism.
+pub(crate) fn accumulate_weights(
+    vertices: impl IntoIterator<Item = VertexMaterialData>,
+) -> Vec<(u8, u32)> {
+    let mut totals: Vec<(u8, u32)> = Vec::with_capacity(8);
+    for data in vertices {
+        for (&id, &weight) in data.ids.iter().zip(data.weights.iter()) {
+            if weight == 0 {
+                continue;
+            }
+            match totals.iter_mut().find(|(m, _)| *m == id) {
+                Some((_, total)) => *total += weight as u32,
+                None => totals.push((id, weight as u32)),
+            }
+        }
+    }
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    totals
+}
+
+/// Dominant material of a triangle, by summed vertex weight.
+pub(crate) fn dominant_triangle_material(
+    triangle: [usize; 3],
+    ids: &[u32],
+    weights: &[u32],
+) -> Option<u8> {
+    let vertices = triangle
+        .iter()
+        .filter(|&&v| v < ids.len() && v < weights.len())
+        .map(|&v| unpack_vertex(ids[v], weights[v]));
+    accumulate_weights(vertices).first().map(|&(id, _)| id)
+}
+
+/// Quantize up to four `(material_id, weight)` pairs into packed-attribute form.
+///
+/// Weights are normalized to sum to 255 like [`VertexMaterialData::blend4`].
+/// Unused slots have ID 0 and weight 0.
+fn quantize(materials: &[(u8, f32)]) -> ([u8; 4], [u8; 4]) {
+    let mut ids = [0u8; 4];
+    let mut relative = [0.0; 4];
+    for (i, &(id, weight)) in materials.iter().take(4).enumerate() {
+        ids[i] = id;
+        relative[i] = weight;
+    }
+    match quantize_weights(relative) {
+        Some(weights) => (ids, weights),
+        None => ([0; 4], [0; 4]),
+    }
+}
+
+/// Extension trait for looking up material data on triplanar meshes.
+///
+/// # Example
+/// ```ignore
+/// // After a physics raycast reports a triangle index
+/// if let Some(id) = mesh.dominant_material_at_triangle(hit.triangle_index) {
+///     if id == LAVA {
+///         info!("you hit mostly lava");
+///     }
+/// }
+/// ```
+pub trait MeshMaterialQueryExt {
+    /// Blend the material data of a triangle's three vertices equally.
+    ///
+    /// Returns the top four `(ids, weights)` in packed-attribute form, sorted by
+    /// weight descending, or `None` if the triangle index is out of range or
+    /// the mesh has no material attributes.
+    fn material_at_triangle(&self, triangle_index: usize) -> Option<([u8; 4], [u8; 4])>;
+
+    /// Blend the material data of a triangle's vertices using barycentric coordinates.
+    ///
+    /// Use this with the barycentric hit coordinates reported by a raycast
+    /// for an exact per-point result.
+    fn material_at_barycentric(
+        &self,
+        triangle_index: usize,
+        barycentric: Vec3,
+    ) -> Option<([u8; 4], [u8; 4])>;
+
+    /// The material with the highest summed weight on a triangle.
+    fn dominant_material_at_triangle(&self, triangle_index: usize) -> Option<u8>;
+}
+
+impl MeshMaterialQueryExt for Mesh {
+    fn material_at_triangle(&self, triangle_index: usize) -> Option<([u8; 4], [u8; 4])> {
+        self.material_at_barycentric(triangle_index, Vec3::splat(1.0 / 3.0))
+    }
+
+    fn material_at_barycentric(
+        &self,
+        triangle_index: usize,
+        barycentric: Vec3,
+    ) -> Option<([u8; 4], [u8; 4])> {
+        let (ids, weights) = material_attributes(self)?;
+        let tri = triangle(self, triangle_index)?;
+        if tri.iter().any(|&v| v >= ids.len() || v >= weights.len()) {
+            return None;
+        }
+
+        let mut totals: Vec<(u8, f32)> = Vec::with_capacity(12);
+        for (corner, &v) in tri.iter().enumerate() {
+            let data = unpack_vertex(ids[v], weights[v]);
+            let factor = barycentric[corner].max(0.0);
+            for (&id, &weight) in data.ids.iter().zip(data.weights.iter()) {
+                if weight == 0 {
+                    continue;
+                }
+                let contribution = weight as f32 * factor;
+                match totals.iter_mut().find(|(m, _)| *m == id) {
+                    Some((_, total)) => *total += contribution,
+                    None => totals.push((id, contribution)),
+                }
+            }
+        }
+        totals.retain(|(_, w)| *w > 0.0);
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        Some(quantize(&totals))
+    }
+
+    fn dominant_material_at_triangle(&self, triangle_index: usize) -> Option<u8> {
+        let (ids, weights) = material_attributes(self)?;
+        dominant_triangle_material(triangle(self, triangle_index)?, ids, weights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::TriplanarMeshBuilder;
+
+    fn two_material_triangle() -> Mesh {
+        let up = [0.0, 1.0, 0.0];
+        TriplanarMeshBuilder::new()
+            .with_vertex_single([0.0, 0.0, 0.0], up, 4)
+            .with_vertex_single([1.0, 0.0, 0.0], up, 4)
+            .with_vertex_single([0.0, 0.0, 1.0], up, 9)
+            .with_indices(vec![0, 1, 2])
+            .build_unwrap()
+    }
+
+    #[test]
+    fn test_material_at_triangle() {
+        let mesh = two_material_triangle();
+        let (ids, weights) = mesh.material_at_triangle(0).unwrap();
+
+        assert_eq!(ids[..2], [4, 9]);
+        assert_eq!(weights[0] as u16 + weights[1] as u16, 255);
+        assert!(weights[0] > weights[1]);
+        assert_eq!(weights[2..], [0, 0]);
+        assert_eq!(mesh.dominant_material_at_triangle(0), Some(4));
+    }
+
+    #[test]
+    fn test_material_at_barycentric_vertex() {
+        let mesh = two_material_triangle();
+        let (ids, weights) = mesh
+            .material_at_barycentric(0, Vec3::new(0.0, 0.0, 1.0))
+            .unwrap();
+
+        assert_eq!(ids[0], 9);
+        assert_eq!(weights, [255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_out_of_range_triangle() {
+        let mesh = two_material_triangle();
+        assert!(mesh.material_at_triangle(1).is_none());
+        assert!(mesh.dominant_material_at_triangle(1).is_none());
+    }
+
+    #[test]
+    fn test_accumulate_weights() {
+        let totals = accumulate_weights([
+            VertexMaterialData::single(2),
+            VertexMaterialData::blend2(2, 5, 0.5),
+            VertexMaterialData::single(5),
+        ]);
+
+        assert_eq!(totals.len(), 2);
+        // 255 + 127 for material 2, 128 + 255 for material 5
+        assert_eq!(totals[0].0, 5);
+        assert_eq!(totals[1].0, 2);
+    }
+
+    #[test]
+    fn test_unpack_vertex_matches_pack() {
+        let data = VertexMaterialData::blend4([1, 2, 3, 4], [0.4, 0.3, 0.2, 0.1]);
+        assert_eq!(unpack_vertex(data.pack_ids(), data.pack_weights()), data);
+    }
+}
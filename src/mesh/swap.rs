@@ -0,0 +1,190 @@
+//! Double-buffered mesh swaps.
+//!
+//! Pointing a [`Mesh3d`] at a new mesh hides the entity until the render
+//! world has prepared that mesh, usually for one frame, which shows as
+//! flicker when chunks are remeshed. Entities marked [`DoubleBufferedMesh`]
+//! keep showing their previous mesh instead: a changed [`Mesh3d`] is put
+//! back and the new handle parked in a [`PendingMesh`]. The render world
+//! reports which pending meshes are in `RenderAssets<RenderMesh>`, and the
+//! next frame swaps them in.
+//!
+//! Systems that replace meshes themselves can insert [`PendingMesh`]
+//! directly, with or without the marker.
+
+use std::sync::{Arc, Mutex};
+
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy::render::{
+    Extract, ExtractSchedule, Render, RenderApp, RenderSystems, mesh::RenderMesh,
+    render_asset::RenderAssets,
+};
+
+/// Keep showing the current mesh until a replacement is ready on the GPU.
+///
+/// Changes are deferred in `Last`, so attributes written into the new mesh
+/// the frame it is assigned are kept. Attributes written through [`Mesh3d`]
+/// while it is pending land on the shown mesh instead.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct DoubleBufferedMesh;
+
+/// Mesh to show on this entity once the render world has prepared it.
+///
+/// Replaces the entity's [`Mesh3d`] and removes itself when ready. Meshes
+/// whose [`RenderAssetUsages`](bevy::asset::RenderAssetUsages) lack
+/// `RENDER_WORLD` are never prepared and stay pending.
+#[derive(Component, Clone, Debug)]
+pub struct PendingMesh(pub Handle<Mesh>);
+
+/// The mesh a [`DoubleBufferedMesh`] entity currently shows.
+#[derive(Component, Clone, Debug)]
+struct ShownMesh(Handle<Mesh>);
+
+/// Pending meshes the render world has prepared, shared between worlds.
+///
+/// Only present when rendering; without a render world, loaded meshes
+/// count as ready.
+#[derive(Resource, Clone, Default)]
+struct PreparedPendingMeshes(Arc<Mutex<HashSet<AssetId<Mesh>>>>);
+
+/// Pending mesh ids extracted this frame.
+#[derive(Resource, Default)]
+struct ExtractedPendingMeshes(Vec<AssetId<Mesh>>);
+
+pub(crate) fn build_mesh_swapping(app: &mut App) {
+    app.register_type::<DoubleBufferedMesh>()
+        .add_systems(Last, (defer_mesh_swaps, swap_prepared_meshes).chain());
+
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+        return;
+    };
+    let prepared = PreparedPendingMeshes::default();
+    render_app
+        .insert_resource(prepared.clone())
+        .init_resource::<ExtractedPendingMeshes>()
+        .add_systems(ExtractSchedule, extract_pending_meshes)
+        .add_systems(
+            Render,
+            report_prepared_meshes.in_set(RenderSystems::PrepareResources),
+        );
+    app.insert_resource(prepared);
+}
+
+/// Puts back the shown mesh of [`DoubleBufferedMesh`] entities whose
+/// [`Mesh3d`] changed, parking the new one in a [`PendingMesh`].
+///
+/// Runs in `Last`, after anything this frame wrote attributes into the new
+/// mesh.
+#[allow(clippy::type_complexity)]
+fn defer_mesh_swaps(
+    mut commands: Commands,
+    mut entities: Query<
+        (Entity, &mut Mesh3d, Option<&ShownMesh>),
+        (With<DoubleBufferedMesh>, Changed<Mesh3d>),
+    >,
+) {
+    for (entity, mut mesh, shown) in &mut entities {
+        match shown {
+            Some(ShownMesh(shown)) if *shown != mesh.0 => {
+                let new = std::mem::replace(&mut mesh.0, shown.clone());
+                commands.entity(entity).insert(PendingMesh(new));
+            }
+            Some(_) => {}
+            // First mesh: nothing to keep showing
+            None => {
+                commands.entity(entity).insert(ShownMesh(mesh.0.clone()));
+            }
+        }
+    }
+}
+
+/// Swaps [`PendingMesh`]es the render world has prepared into [`Mesh3d`].
+fn swap_prepared_meshes(
+    mut commands: Commands,
+    pending: Query<(Entity, &PendingMesh, Has<DoubleBufferedMesh>)>,
+    prepared: Option<Res<PreparedPendingMeshes>>,
+    meshes: Option<Res<Assets<Mesh>>>,
+) {
+    let prepared = prepared.map(|prepared| prepared.0.lock().unwrap().clone());
+    for (entity, PendingMesh(mesh), double_buffered) in &pending {
+        let ready = match &prepared {
+            Some(prepared) => prepared.contains(&mesh.id()),
+            None => meshes.as_ref().is_some_and(|meshes| meshes.contains(mesh)),
+        };
+        if !ready {
+            continue;
+        }
+        let mut entity = commands.entity(entity);
+        entity.insert(Mesh3d(mesh.clone())).remove::<PendingMesh>();
+        if double_buffered {
+            entity.insert(ShownMesh(mesh.clone()));
+        }
+    }
+}
+
+fn extract_pending_meshes(
+    mut extracted: ResMut<ExtractedPendingMeshes>,
+    pending: Extract<Query<&PendingMesh>>,
+) {
+    extracted.0.clear();
+    extracted
+        .0
+        .extend(pending.iter().map(|PendingMesh(mesh)| mesh.id()));
+}
+
+fn report_prepared_meshes(
+    extracted: Res<ExtractedPendingMeshes>,
+    render_meshes: Res<RenderAssets<RenderMesh>>,
+    prepared: Res<PreparedPendingMeshes>,
+) {
+    let mut prepared = prepared.0.lock().unwrap();
+    prepared.clear();
+    prepared.extend(
+        extracted
+            .0
+            .iter()
+            .copied()
+            .filter(|&mesh| render_meshes.get(mesh).is_some()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_mesh_waits_for_render_world() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_resource::<PreparedPendingMeshes>()
+            .add_systems(Last, (defer_mesh_swaps, swap_prepared_meshes).chain());
+
+        let mut meshes = app.world_mut().resource_mut::<Assets<Mesh>>();
+        let old = meshes.add(Cuboid::default());
+        let new = meshes.add(Sphere::default());
+        let chunk = app
+            .world_mut()
+            .spawn((Mesh3d(old.clone()), DoubleBufferedMesh))
+            .id();
+        app.update();
+
+        app.world_mut().get_mut::<Mesh3d>(chunk).unwrap().0 = new.clone();
+        app.update();
+        let world = app.world();
+        assert_eq!(world.get::<Mesh3d>(chunk).unwrap().0, old);
+        assert_eq!(world.get::<PendingMesh>(chunk).unwrap().0, new);
+
+        let prepared = app.world().resource::<PreparedPendingMeshes>().clone();
+        prepared.0.lock().unwrap().insert(new.id());
+        app.update();
+        let world = app.world();
+        assert_eq!(world.get::<Mesh3d>(chunk).unwrap().0, new);
+        assert!(world.get::<PendingMesh>(chunk).is_none());
+
+        // Swapping in doesn't count as another change
+        app.update();
+        assert_eq!(app.world().get::<Mesh3d>(chunk).unwrap().0, new);
+    }
+}
@@ -0,0 +1,142 @@
+//! Which material IDs a mesh actually uses.
+//!
+//! [`MaterialUsage`] is a 256-bit set filled while vertex attributes are
+//! generated, so streaming and analytics can tell which palette layers a
+//! chunk needs without scanning its mesh again.
+
+use bevy::prelude::*;
+
+use super::query::{material_attributes, unpack_vertex};
+use super::vertex_data::VertexMaterialData;
+
+/// Set of material IDs with non-zero weight on at least one vertex.
+///
+/// Insert it on chunk entities (for example from
+/// [`TriplanarMeshBuilder::material_usage`](super::TriplanarMeshBuilder::material_usage))
+/// to feed palette streaming.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub struct MaterialUsage(pub [u64; 4]);
+
+impl MaterialUsage {
+    /// Mark a material ID as used.
+    #[inline]
+    pub fn insert(&mut self, material_id: u8) {
+        self.0[(material_id >> 6) as usize] |= 1 << (material_id & 63);
+    }
+
+    /// Whether a material ID is used.
+    #[inline]
+    pub fn contains(&self, material_id: u8) -> bool {
+        self.0[(material_id >> 6) as usize] & (1 << (material_id & 63)) != 0
+    }
+
+    /// Mark every material with non-zero weight in `data` as used.
+    #[inline]
+    pub fn insert_vertex(&mut self, data: &VertexMaterialData) {
+        for (id, weight) in data.ids.iter().zip(data.weights) {
+            if weight > 0 {
+                self.insert(*id);
+            }
+        }
+    }
+
+    /// Add all materials used by `other`.
+    pub fn union_with(&mut self, other: &MaterialUsage) {
+        for (word, other) in self.0.iter_mut().zip(other.0) {
+            *word |= other;
+        }
+    }
+
+    /// Materials in `self` that are not in `other`.
+    pub fn difference(&self, other: &MaterialUsage) -> MaterialUsage {
+        let mut result = *self;
+        for (word, other) in result.0.iter_mut().zip(other.0) {
+            *word &= !other;
+        }
+        result
+    }
+
+    /// Whether no material is used.
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    /// Number of distinct materials used.
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Used material IDs in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|id| self.contains(*id))
+    }
+
+    /// Collect usage from a mesh's material attributes.
+    ///
+    /// Returns an empty set if the mesh has no material attributes.
+    pub fn from_mesh(mesh: &Mesh) -> Self {
+        let mut usage = Self::default();
+        if let Some((ids, weights)) = material_attributes(mesh) {
+            for (&ids, &weights) in ids.iter().zip(weights) {
+                usage.insert_vertex(&unpack_vertex(ids, weights));
+            }
+        }
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::MeshTriplanarExt;
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::PrimitiveTopology;
+
+    #[test]
+    fn test_insert_and_iter() {
+        let mut usage = MaterialUsage::default();
+        usage.insert_vertex(&VertexMaterialData::blend2(3, 200, 0.5));
+        usage.insert(64);
+
+        assert_eq!(usage.iter().collect::<Vec<_>>(), vec![3, 64, 200]);
+        assert_eq!(usage.len(), 3);
+        assert!(!usage.contains(4));
+    }
+
+    #[test]
+    fn test_zero_weight_ignored() {
+        let mut usage = MaterialUsage::default();
+        usage.insert_vertex(&VertexMaterialData::single(7));
+
+        assert_eq!(usage.iter().collect::<Vec<_>>(), vec![7]);
+    }
+
+    #[test]
+    fn test_difference_and_union() {
+        let mut a = MaterialUsage::default();
+        a.insert(1);
+        a.insert(2);
+        let mut b = MaterialUsage::default();
+        b.insert(2);
+
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), vec![1]);
+        b.union_with(&a);
+        assert_eq!(b, a);
+    }
+
+    #[test]
+    fn test_from_mesh() {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0f32; 3]; 3]);
+        let mesh = mesh.with_uniform_material(9);
+
+        assert_eq!(
+            MaterialUsage::from_mesh(&mesh).iter().collect::<Vec<_>>(),
+            vec![9]
+        );
+    }
+}
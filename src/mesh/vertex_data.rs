@@ -94,20 +94,12 @@ impl VertexMaterialData {
     /// let data = VertexMaterialData::blend3(0, 1, 2, 1.0, 1.0, 1.0);
     /// ```
     pub fn blend3(id0: u8, id1: u8, id2: u8, w0: f32, w1: f32, w2: f32) -> Self {
-        let sum = w0 + w1 + w2;
-        if sum < 0.0001 {
-            return Self::single(id0);
-        }
-
-        let scale = 255.0 / sum;
-        let w0_u8 = (w0 * scale).round() as u8;
-        let w1_u8 = (w1 * scale).round() as u8;
-        // Last weight absorbs rounding error
-        let w2_u8 = 255u8.saturating_sub(w0_u8).saturating_sub(w1_u8);
-
-        Self {
-            ids: [id0, id1, id2, 0],
-            weights: [w0_u8, w1_u8, w2_u8, 0],
+        match quantize_weights([w0, w1, w2, 0.0]) {
+            Some(weights) => Self {
+                ids: [id0, id1, id2, 0],
+                weights,
+            },
+            None => Self::single(id0),
         }
     }
 
@@ -129,25 +121,9 @@ impl VertexMaterialData {
     /// );
     /// ```
     pub fn blend4(ids: [u8; 4], weights: [f32; 4]) -> Self {
-        let sum: f32 = weights.iter().sum();
-        if sum < 0.0001 {
-            return Self::single(ids[0]);
-        }
-
-        let scale = 255.0 / sum;
-        let mut result = [0u8; 4];
-        let mut running = 0u8;
-
-        for i in 0..3 {
-            result[i] = (weights[i] * scale).round() as u8;
-            running = running.saturating_add(result[i]);
-        }
-        // Last weight absorbs rounding error
-        result[3] = 255u8.saturating_sub(running);
-
-        Self {
-            ids,
-            weights: result,
+        match quantize_weights(weights) {
+            Some(weights) => Self { ids, weights },
+            None => Self::single(ids[0]),
         }
     }
 
@@ -177,6 +153,63 @@ impl VertexMaterialData {
         Self { ids, weights }
     }
 
+    /// Weights as fractions summing to 1, as the shader sees them.
+    ///
+    /// Divides by the actual sum rather than 255, so quantization error from
+    /// [`raw`](Self::raw) or filtered blending doesn't shift brightness.
+    /// All-zero weights select the first material.
+    pub fn normalized_weights(&self) -> [f32; 4] {
+        let sum: u32 = self.weights.iter().map(|&w| w as u32).sum();
+        if sum == 0 {
+            return [1.0, 0.0, 0.0, 0.0];
+        }
+        self.weights.map(|w| w as f32 / sum as f32)
+    }
+
+    /// Cross-fade towards `other` by `t` (0.0 is `self`, 1.0 is `other`).
+    ///
+    /// Weights of materials in both are summed; when the fade passes
+    /// through more than four materials, the four heaviest are kept.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_painter::mesh::VertexMaterialData;
+    ///
+    /// let grass = VertexMaterialData::single(1);
+    /// let snow = VertexMaterialData::single(2);
+    /// let halfway = grass.lerp(&snow, 0.5);
+    /// assert_eq!(halfway.ids[..2], [1, 2]);
+    /// assert_eq!(halfway.weights[0] + halfway.weights[1], 255);
+    /// ```
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mut totals: Vec<(u8, f32)> = Vec::with_capacity(8);
+        for (data, scale) in [(self, 1.0 - t), (other, t)] {
+            for (&id, &weight) in data.ids.iter().zip(data.weights.iter()) {
+                if weight == 0 || scale <= 0.0 {
+                    continue;
+                }
+                let weight = weight as f32 * scale;
+                match totals.iter_mut().find(|(m, _)| *m == id) {
+                    Some((_, total)) => *total += weight,
+                    None => totals.push((id, weight)),
+                }
+            }
+        }
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut ids = [0; 4];
+        let mut weights = [0.0; 4];
+        for (slot, &(id, weight)) in totals.iter().take(4).enumerate() {
+            ids[slot] = id;
+            weights[slot] = weight;
+        }
+        match quantize_weights(weights) {
+            Some(weights) => Self { ids, weights },
+            None => *other,
+        }
+    }
+
     /// Pack material IDs into a u32 for the vertex attribute.
     #[inline]
     pub const fn pack_ids(&self) -> u32 {
@@ -196,6 +229,40 @@ impl VertexMaterialData {
     }
 }
 
+/// Quantize relative weights to bytes summing to exactly 255.
+///
+/// Uses largest remainders: a heavier weight never quantizes below a lighter
+/// one, ties favour the earlier slot, and any weight worth at least one step
+/// keeps a non-zero byte. Negative and non-finite weights count as zero.
+/// Returns `None` if no weight is left.
+pub(crate) fn quantize_weights(weights: [f32; 4]) -> Option<[u8; 4]> {
+    let weights = weights.map(|w| if w.is_finite() && w > 0.0 { w } else { 0.0 });
+    let sum: f32 = weights.iter().sum();
+    if sum < 0.0001 || !sum.is_finite() {
+        return None;
+    }
+
+    let scaled = weights.map(|w| (w / sum * 255.0).min(255.0));
+    let mut result = scaled.map(|w| w.floor() as u8);
+    let assigned: u32 = result.iter().map(|&w| w as u32).sum();
+
+    // Hand out the remaining steps by descending remainder, then slot
+    let mut order = [0, 1, 2, 3];
+    order.sort_by(|&a, &b| {
+        let (ra, rb) = (scaled[a] - scaled[a].floor(), scaled[b] - scaled[b].floor());
+        rb.total_cmp(&ra).then(a.cmp(&b))
+    });
+    let mut remaining = 255u32.saturating_sub(assigned);
+    for &slot in order.iter().cycle().filter(|&&slot| weights[slot] > 0.0) {
+        if remaining == 0 {
+            break;
+        }
+        result[slot] += 1;
+        remaining -= 1;
+    }
+    Some(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,6 +307,21 @@ mod tests {
         assert_eq!(sum, 255);
     }
 
+    #[test]
+    fn test_lerp_endpoints_and_overflow() {
+        let before = VertexMaterialData::blend4([0, 1, 2, 3], [1.0; 4]);
+        let after = VertexMaterialData::blend2(4, 5, 0.5);
+
+        assert_eq!(before.lerp(&after, 0.0), before);
+        assert_eq!(before.lerp(&after, 1.0), after);
+
+        // Six materials in flight, the two new ones heaviest
+        let fading = before.lerp(&after, 0.6);
+        assert_eq!(fading.ids[..2], [4, 5]);
+        let sum: u16 = fading.weights.iter().map(|&w| w as u16).sum();
+        assert_eq!(sum, 255);
+    }
+
     #[test]
     fn test_pack_ids() {
         let data = VertexMaterialData {
@@ -267,4 +349,112 @@ mod tests {
     fn test_raw_panics_on_bad_weights() {
         VertexMaterialData::raw([0, 0, 0, 0], [100, 100, 0, 0]); // Sum = 200
     }
+
+    #[test]
+    fn test_normalized_weights_ignore_quantization() {
+        // Same proportions, sums of 254, 255 and 256
+        let under = VertexMaterialData {
+            ids: [0, 1, 0, 0],
+            weights: [127, 127, 0, 0],
+        };
+        let exact = VertexMaterialData::blend2_half(0, 1);
+        let over = VertexMaterialData {
+            ids: [0, 1, 0, 0],
+            weights: [128, 128, 0, 0],
+        };
+
+        for data in [under, exact, over] {
+            let weights = data.normalized_weights();
+            assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+            assert!((weights[0] - 0.5).abs() < 0.01);
+        }
+        assert_eq!(under.normalized_weights(), over.normalized_weights());
+        assert_eq!(
+            VertexMaterialData::default().normalized_weights(),
+            [1.0, 0.0, 0.0, 0.0]
+        );
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn sum(data: &VertexMaterialData) -> u32 {
+            data.weights.iter().map(|&w| w as u32).sum()
+        }
+
+        /// Relative weights with plenty of zeros, ties and tiny values.
+        fn weight() -> impl Strategy<Value = f32> {
+            prop_oneof![Just(0.0), Just(1.0), 1e-6f32..1e-3, 0.0f32..10.0,]
+        }
+
+        /// Every weight worth at least one step keeps a non-zero byte, and
+        /// a heavier weight never quantizes below a lighter one.
+        fn check_quantized(relative: &[f32], data: &VertexMaterialData) {
+            let total: f32 = relative.iter().sum();
+            for (i, &w) in relative.iter().enumerate() {
+                if w / total * 255.0 >= 1.0 {
+                    assert!(data.weights[i] > 0, "{relative:?} -> {:?}", data.weights);
+                }
+                for (j, &v) in relative.iter().enumerate() {
+                    if w >= v {
+                        assert!(
+                            data.weights[i] >= data.weights[j] || w == v && i > j,
+                            "{relative:?} -> {:?}",
+                            data.weights
+                        );
+                    }
+                }
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn blend2_sums_to_255(ratio in any::<f32>()) {
+                let data = VertexMaterialData::blend2(3, 7, ratio);
+                prop_assert_eq!(sum(&data), 255);
+                prop_assert_eq!(data.ids, [3, 7, 0, 0]);
+            }
+
+            #[test]
+            fn blend3_sums_to_255(w0 in weight(), w1 in weight(), w2 in weight()) {
+                let data = VertexMaterialData::blend3(1, 2, 3, w0, w1, w2);
+                prop_assert_eq!(sum(&data), 255);
+                if w0 + w1 + w2 >= 0.0001 {
+                    prop_assert_eq!(data.ids, [1, 2, 3, 0]);
+                    check_quantized(&[w0, w1, w2], &data);
+                    let padded = VertexMaterialData::blend4([1, 2, 3, 0], [w0, w1, w2, 0.0]);
+                    prop_assert_eq!(data, padded);
+                }
+            }
+
+            #[test]
+            fn blend4_sums_to_255(weights in [weight(), weight(), weight(), weight()]) {
+                let data = VertexMaterialData::blend4([4, 5, 6, 7], weights);
+                prop_assert_eq!(sum(&data), 255);
+                if weights.iter().sum::<f32>() >= 0.0001 {
+                    check_quantized(&weights, &data);
+                }
+            }
+
+            #[test]
+            fn blend4_all_equal_is_ordered(w in 1e-3f32..100.0) {
+                let data = VertexMaterialData::blend4([0, 1, 2, 3], [w; 4]);
+                prop_assert_eq!(data.weights, [64, 64, 64, 63]);
+            }
+
+            #[test]
+            fn raw_round_trips(ids in any::<[u8; 4]>(), mut cuts in any::<[u8; 3]>()) {
+                // Split 255 into four parts
+                cuts.sort();
+                let weights = [cuts[0], cuts[1] - cuts[0], cuts[2] - cuts[1], 255 - cuts[2]];
+                let data = VertexMaterialData::raw(ids, weights);
+
+                prop_assert_eq!(data.pack_ids().to_le_bytes(), ids);
+                prop_assert_eq!(data.pack_weights().to_le_bytes(), weights);
+                let normalized: f32 = data.normalized_weights().iter().sum();
+                prop_assert!((normalized - 1.0).abs() < 1e-5);
+            }
+        }
+    }
 }
@@ -0,0 +1,790 @@
+//! Seamless noise keyed by world voxel coordinates.
+//!
+//! Every function here takes world-space voxel coordinates
+//! (`chunk_pos * FIELD_SIZE + local`, see [`world_voxel`]) rather than
+//! chunk-local ones, so neighbouring chunks evaluate the same lattice and
+//! material patterns line up across chunk borders. Generators like
+//! [`fill_noise`], [`fill_strata`], [`fill_voronoi_patches`], [`scatter`]
+//! and [`scatter_veins`] build on that.
+//!
+//! # Example
+//! ```ignore
+//! let rock_veins = WorldNoise::simplex(7).with_frequency(1.0 / 24.0).with_octaves(3);
+//! for (chunk_pos, mut field, density) in &mut chunks {
+//!     noise::fill_noise(&mut field, chunk_pos.0, &rock_veins, &[(0.45, STONE), (0.55, ORE), (1.0, STONE)]);
+//!     noise::scatter(&mut field, chunk_pos.0, 11, 0.01, GEM, Some(&MaterialMask::only(&[STONE])));
+//!     noise::scatter_veins(&mut *field, chunk_pos.0, density, IRON, 5, &VeinConfig::default());
+//! }
+//! ```
+
+use bevy::prelude::*;
+
+use crate::material_field::{
+    DensitySource, FIELD_SIZE, MaterialField, MaterialMask, MaterialStorage,
+};
+
+/// World-space voxel coordinate of `local` in the chunk at `chunk_pos`.
+#[inline]
+pub fn world_voxel(chunk_pos: IVec3, local: UVec3) -> IVec3 {
+    chunk_pos * FIELD_SIZE.as_ivec3() + local.as_ivec3()
+}
+
+/// Noise basis used by [`WorldNoise`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NoiseKind {
+    /// Interpolated random values per lattice point. Cheapest, blocky.
+    Value,
+    /// Classic gradient noise.
+    Perlin,
+    /// Simplex gradient noise, fewer axis-aligned artifacts.
+    #[default]
+    Simplex,
+}
+
+/// Fractal noise over world voxel coordinates, in `0.0..=1.0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldNoise {
+    pub seed: u32,
+    pub kind: NoiseKind,
+    /// Lattice cells per voxel for the first octave.
+    /// Default: 1/16
+    pub frequency: f32,
+    /// Octaves summed, each at twice the frequency and half the amplitude.
+    /// Default: 1
+    pub octaves: u32,
+}
+
+impl WorldNoise {
+    /// Single-octave noise of the given kind.
+    pub fn new(kind: NoiseKind, seed: u32) -> Self {
+        Self {
+            seed,
+            kind,
+            frequency: 1.0 / 16.0,
+            octaves: 1,
+        }
+    }
+
+    /// Value noise.
+    pub fn value(seed: u32) -> Self {
+        Self::new(NoiseKind::Value, seed)
+    }
+
+    /// Perlin noise.
+    pub fn perlin(seed: u32) -> Self {
+        Self::new(NoiseKind::Perlin, seed)
+    }
+
+    /// Simplex noise.
+    pub fn simplex(seed: u32) -> Self {
+        Self::new(NoiseKind::Simplex, seed)
+    }
+
+    /// Set the first octave's frequency in lattice cells per voxel.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Set the number of octaves.
+    pub fn with_octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    /// Noise at a world voxel coordinate.
+    pub fn sample(&self, world_voxel: IVec3) -> f32 {
+        self.sample_at(world_voxel.as_vec3())
+    }
+
+    /// Noise at a continuous position in world voxel units.
+    pub fn sample_at(&self, pos: Vec3) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut frequency = self.frequency;
+        for octave in 0..self.octaves.max(1) {
+            let seed = self.seed.wrapping_add(octave.wrapping_mul(0x9e37_79b9));
+            let p = pos * frequency;
+            let value = match self.kind {
+                NoiseKind::Value => value3(seed, p),
+                NoiseKind::Perlin => perlin3(seed, p) * 0.5 + 0.5,
+                NoiseKind::Simplex => simplex3(seed, p) * 0.5 + 0.5,
+            };
+            sum += value * amplitude;
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        (sum / total).clamp(0.0, 1.0)
+    }
+}
+
+/// Integer hash of a lattice point.
+#[inline]
+fn hash3(seed: u32, p: IVec3) -> u32 {
+    let mut h = seed
+        ^ (p.x as u32).wrapping_mul(0x8da6_b343)
+        ^ (p.y as u32).wrapping_mul(0xd816_3841)
+        ^ (p.z as u32).wrapping_mul(0xcb1a_b31f);
+    // Final avalanche from the lowbias32 integer hash
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    h
+}
+
+/// Uniform random value in `0.0..1.0` for a lattice point.
+#[inline]
+fn hash_unit(seed: u32, p: IVec3) -> f32 {
+    (hash3(seed, p) >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// The 12 cube edge directions used as gradients.
+const GRADIENTS: [Vec3; 12] = [
+    vec3(1.0, 1.0, 0.0),
+    vec3(-1.0, 1.0, 0.0),
+    vec3(1.0, -1.0, 0.0),
+    vec3(-1.0, -1.0, 0.0),
+    vec3(1.0, 0.0, 1.0),
+    vec3(-1.0, 0.0, 1.0),
+    vec3(1.0, 0.0, -1.0),
+    vec3(-1.0, 0.0, -1.0),
+    vec3(0.0, 1.0, 1.0),
+    vec3(0.0, -1.0, 1.0),
+    vec3(0.0, 1.0, -1.0),
+    vec3(0.0, -1.0, -1.0),
+];
+
+#[inline]
+fn gradient(seed: u32, p: IVec3) -> Vec3 {
+    GRADIENTS[(hash3(seed, p) % 12) as usize]
+}
+
+/// Quintic fade curve with zero first and second derivatives at 0 and 1.
+#[inline]
+fn fade(t: Vec3) -> Vec3 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Trilinear blend of eight corner values, indexed x + 2y + 4z.
+#[inline]
+fn trilerp(corners: [f32; 8], t: Vec3) -> f32 {
+    let x00 = corners[0].lerp(corners[1], t.x);
+    let x10 = corners[2].lerp(corners[3], t.x);
+    let x01 = corners[4].lerp(corners[5], t.x);
+    let x11 = corners[6].lerp(corners[7], t.x);
+    x00.lerp(x10, t.y).lerp(x01.lerp(x11, t.y), t.z)
+}
+
+/// Corner offsets indexed x + 2y + 4z.
+fn corner(index: usize) -> IVec3 {
+    ivec3(index as i32 & 1, (index as i32 >> 1) & 1, index as i32 >> 2)
+}
+
+/// Value noise in `0.0..=1.0`.
+pub fn value3(seed: u32, p: Vec3) -> f32 {
+    let cell = p.floor();
+    let base = cell.as_ivec3();
+    let corners = std::array::from_fn(|i| hash_unit(seed, base + corner(i)));
+    trilerp(corners, fade(p - cell))
+}
+
+/// Perlin gradient noise in roughly `-1.0..=1.0`, zero on lattice points.
+pub fn perlin3(seed: u32, p: Vec3) -> f32 {
+    let cell = p.floor();
+    let base = cell.as_ivec3();
+    let f = p - cell;
+    let corners = std::array::from_fn(|i| {
+        let offset = corner(i);
+        gradient(seed, base + offset).dot(f - offset.as_vec3())
+    });
+    trilerp(corners, fade(f)).clamp(-1.0, 1.0)
+}
+
+/// Simplex gradient noise in roughly `-1.0..=1.0`.
+pub fn simplex3(seed: u32, p: Vec3) -> f32 {
+    const F3: f32 = 1.0 / 3.0;
+    const G3: f32 = 1.0 / 6.0;
+
+    // Skew into the simplex lattice to find the containing cell
+    let skewed = (p + (p.x + p.y + p.z) * F3).floor();
+    let base = skewed.as_ivec3();
+    let d0 = p - (skewed - (skewed.x + skewed.y + skewed.z) * G3);
+
+    // Walk the simplex corners in order of the largest offset axis
+    let (first, second) = if d0.x >= d0.y {
+        if d0.y >= d0.z {
+            (IVec3::X, ivec3(1, 1, 0))
+        } else if d0.x >= d0.z {
+            (IVec3::X, ivec3(1, 0, 1))
+        } else {
+            (IVec3::Z, ivec3(1, 0, 1))
+        }
+    } else if d0.y < d0.z {
+        (IVec3::Z, ivec3(0, 1, 1))
+    } else if d0.x < d0.z {
+        (IVec3::Y, ivec3(0, 1, 1))
+    } else {
+        (IVec3::Y, ivec3(1, 1, 0))
+    };
+
+    let contribution = |offset: IVec3, d: Vec3| {
+        let t = 0.6 - d.length_squared();
+        if t <= 0.0 {
+            0.0
+        } else {
+            t * t * t * t * gradient(seed, base + offset).dot(d)
+        }
+    };
+    let sum = contribution(IVec3::ZERO, d0)
+        + contribution(first, d0 - first.as_vec3() + G3)
+        + contribution(second, d0 - second.as_vec3() + 2.0 * G3)
+        + contribution(IVec3::ONE, d0 - 1.0 + 3.0 * G3);
+    (32.0 * sum).clamp(-1.0, 1.0)
+}
+
+/// Fills a chunk's field from noise bands.
+///
+/// Each voxel takes the material of the first `(max, material_id)` band
+/// whose `max` is above its noise value; values past the last band keep
+/// the last band's material.
+pub fn fill_noise(
+    field: &mut MaterialField,
+    chunk_pos: IVec3,
+    noise: &WorldNoise,
+    bands: &[(f32, u8)],
+) {
+    let Some(&(_, last)) = bands.last() else {
+        return;
+    };
+    for (pos, material) in field.enumerate_coords_mut() {
+        let value = noise.sample(world_voxel(chunk_pos, pos));
+        *material = bands
+            .iter()
+            .find(|(max, _)| value < *max)
+            .map_or(last, |&(_, material_id)| material_id);
+    }
+}
+
+/// Fills a chunk's field with tilted, warped rock strata.
+///
+/// `layers` are `(thickness, material_id)` bands in voxels, bottom to top,
+/// repeating every total thickness so bands never run out however far the
+/// planes are tilted. `tilt_degrees` dips the bands along X and Z: at
+/// 30 degrees on X, a band rises by `tan(30°)` voxels per voxel towards
+/// -X. `warp` bends the bands up and down by up to its amplitude in
+/// voxels, following the noise, for folded rather than planar layers.
+/// Bands are evaluated in world space, so they line up across chunks.
+///
+/// Unlike [`MaterialField::paint_height_layers`], which stacks flat
+/// layers within a single chunk, this looks like sedimentary rock cut by
+/// the terrain. Does nothing if the layers have no total thickness.
+///
+/// # Example
+/// ```ignore
+/// let folds = WorldNoise::simplex(3).with_frequency(1.0 / 64.0).with_octaves(2);
+/// let layers = [(3.0, SANDSTONE), (1.5, SHALE), (4.0, LIMESTONE), (1.0, SHALE)];
+/// noise::fill_strata(&mut field, chunk_pos.0, &layers, vec2(20.0, 5.0), Some((&folds, 6.0)));
+/// ```
+pub fn fill_strata(
+    field: &mut MaterialField,
+    chunk_pos: IVec3,
+    layers: &[(f32, u8)],
+    tilt_degrees: Vec2,
+    warp: Option<(&WorldNoise, f32)>,
+) {
+    let period: f32 = layers.iter().map(|(thickness, _)| thickness.max(0.0)).sum();
+    if period <= 0.0 {
+        return;
+    }
+    let slope = vec2(
+        tilt_degrees.x.to_radians().tan(),
+        tilt_degrees.y.to_radians().tan(),
+    );
+    for (pos, material) in field.enumerate_coords_mut() {
+        let p = world_voxel(chunk_pos, pos).as_vec3();
+        let offset = warp.map_or(0.0, |(noise, amplitude)| {
+            (noise.sample_at(p) * 2.0 - 1.0) * amplitude
+        });
+        let mut depth = (p.y + p.x * slope.x + p.z * slope.y + offset).rem_euclid(period);
+        for &(thickness, material_id) in layers {
+            *material = material_id;
+            depth -= thickness.max(0.0);
+            if depth < 0.0 {
+                break;
+            }
+        }
+    }
+}
+
+/// Fills a chunk's field with patches of the nearest seed's material, e.g.
+/// meadows of mixed grasses, lichen on rock or camouflage.
+///
+/// `seeds` are `(position, material_id)` pairs in world voxel coordinates;
+/// each voxel takes the material of the nearest one. `jitter` displaces
+/// the lookup by up to its amplitude in voxels along each axis, following
+/// the noise, so patch borders wander instead of running straight. Only
+/// voxels whose current material passes `mask` are replaced. Returns
+/// whether any voxel changed.
+///
+/// # Example
+/// ```ignore
+/// let seeds = [(vec3(10.0, 20.0, 4.0), MOSS), (vec3(40.0, 18.0, 30.0), LICHEN), (vec3(-5.0, 22.0, 50.0), MOSS)];
+/// let wobble = WorldNoise::simplex(4).with_frequency(1.0 / 12.0);
+/// noise::fill_voronoi_patches(&mut field, chunk_pos.0, &seeds, Some((&wobble, 5.0)), Some(&MaterialMask::only(&[ROCK])));
+/// ```
+pub fn fill_voronoi_patches(
+    field: &mut MaterialField,
+    chunk_pos: IVec3,
+    seeds: &[(Vec3, u8)],
+    jitter: Option<(&WorldNoise, f32)>,
+    mask: Option<&MaterialMask>,
+) -> bool {
+    // Offsets decorrelating the noise sampled for each axis
+    const AXIS_OFFSETS: [Vec3; 3] = [
+        Vec3::ZERO,
+        vec3(173.0, -311.0, 97.0),
+        vec3(-241.0, 59.0, 389.0),
+    ];
+
+    let mut changed = false;
+    for (pos, material) in field.enumerate_coords_mut() {
+        if mask.is_some_and(|mask| !mask.allows(*material)) {
+            continue;
+        }
+        let mut p = world_voxel(chunk_pos, pos).as_vec3();
+        if let Some((noise, amplitude)) = jitter {
+            let offset = Vec3::from_array(AXIS_OFFSETS.map(|axis| noise.sample_at(p + axis)));
+            p += (offset * 2.0 - 1.0) * amplitude;
+        }
+        let Some(&(_, material_id)) = seeds
+            .iter()
+            .min_by(|(a, _), (b, _)| a.distance_squared(p).total_cmp(&b.distance_squared(p)))
+        else {
+            return false;
+        };
+        if *material != material_id {
+            *material = material_id;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Sprinkles `material_id` over a chunk's field with probability `chance`
+/// per voxel, e.g. ore or gems in rock.
+///
+/// Which voxels are picked depends only on `seed` and the world
+/// coordinate. Only voxels whose current material passes `mask` are
+/// replaced. Returns whether any voxel changed.
+pub fn scatter(
+    field: &mut MaterialField,
+    chunk_pos: IVec3,
+    seed: u32,
+    chance: f32,
+    material_id: u8,
+    mask: Option<&MaterialMask>,
+) -> bool {
+    let mut changed = false;
+    for (pos, material) in field.enumerate_coords_mut() {
+        if *material == material_id
+            || mask.is_some_and(|mask| !mask.allows(*material))
+            || hash_unit(seed, world_voxel(chunk_pos, pos)) >= chance
+        {
+            continue;
+        }
+        *material = material_id;
+        changed = true;
+    }
+    changed
+}
+
+/// Shape and frequency of the veins [`scatter_veins`] generates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VeinConfig {
+    /// Edge of the world-space cells veins are seeded in, in voxels.
+    /// Default: 32
+    pub cell_size: u32,
+    /// Veins started in each cell.
+    /// Default: 2
+    pub veins_per_cell: u32,
+    /// Steps of each vein's random walk.
+    /// Default: 24
+    pub length: u32,
+    /// Voxels advanced per step.
+    /// Default: 1.0
+    pub step: f32,
+    /// Vein radius in voxels.
+    /// Default: 1.2
+    pub radius: f32,
+    /// How sharply veins turn each step; 0 is straight.
+    /// Default: 0.4
+    pub turn: f32,
+    /// Only replace materials passing this mask, e.g. the host rock.
+    /// Default: None
+    pub host: Option<MaterialMask>,
+}
+
+impl Default for VeinConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 32,
+            veins_per_cell: 2,
+            length: 24,
+            step: 1.0,
+            radius: 1.2,
+            turn: 0.4,
+            host: None,
+        }
+    }
+}
+
+impl VeinConfig {
+    /// Set the number of veins started per cell.
+    pub fn with_veins_per_cell(mut self, veins: u32) -> Self {
+        self.veins_per_cell = veins;
+        self
+    }
+
+    /// Set the number of random walk steps per vein.
+    pub fn with_length(mut self, length: u32) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Set the vein radius in voxels.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Set how sharply veins turn each step.
+    pub fn with_turn(mut self, turn: f32) -> Self {
+        self.turn = turn;
+        self
+    }
+
+    /// Only replace materials passing `mask`.
+    pub fn with_host(mut self, mask: MaterialMask) -> Self {
+        self.host = Some(mask);
+        self
+    }
+}
+
+/// Paints connected, worm-like veins of `material_id` through the solid
+/// part of a chunk, e.g. ore in rock.
+///
+/// Veins are random walks seeded per world-space cell from `seed`, so they
+/// don't depend on which chunk is generated first: a vein crossing a chunk
+/// border continues in the neighbour. Every chunk a vein can reach walks
+/// it and paints its own part, only where `density` is solid. A vein
+/// leaving the rock doesn't bend back in; it resumes wherever its path
+/// re-enters solid ground. `field` and `density` may be any storage
+/// covering the same voxels, with voxel 0 at `chunk_pos * FIELD_SIZE`.
+/// Returns whether any voxel changed.
+///
+/// # Example
+/// ```ignore
+/// let iron = VeinConfig::default().with_host(MaterialMask::only(&[STONE]));
+/// noise::scatter_veins(&mut field, chunk_pos.0, &density, IRON, world_seed, &iron);
+/// ```
+pub fn scatter_veins(
+    field: &mut (impl MaterialStorage + ?Sized),
+    chunk_pos: IVec3,
+    density: &(impl DensitySource + ?Sized),
+    material_id: u8,
+    seed: u32,
+    config: &VeinConfig,
+) -> bool {
+    let origin = world_voxel(chunk_pos, UVec3::ZERO);
+    let size = field.size().min(density.size()).as_ivec3();
+    let cell_size = config.cell_size.max(1) as i32;
+    // Cells whose veins can reach this chunk
+    let reach = (config.length as f32 * config.step.abs() + config.radius).ceil() as i32;
+    let first_cell = (origin - reach).div_euclid(IVec3::splat(cell_size));
+    let last_cell = (origin + size - 1 + reach).div_euclid(IVec3::splat(cell_size));
+
+    let mut changed = false;
+    for z in first_cell.z..=last_cell.z {
+        for y in first_cell.y..=last_cell.y {
+            for x in first_cell.x..=last_cell.x {
+                let cell = ivec3(x, y, z);
+                for vein in 0..config.veins_per_cell {
+                    let vein_seed = hash3(seed.wrapping_add(vein.wrapping_mul(0x9e37_79b9)), cell);
+                    let start =
+                        (cell * cell_size).as_vec3() + random_unit(vein_seed, 0) * cell_size as f32;
+                    for point in vein_path(vein_seed, start, config) {
+                        changed |= paint_vein_point(
+                            field,
+                            density,
+                            point - origin.as_vec3(),
+                            size,
+                            material_id,
+                            config,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    changed
+}
+
+/// Three uniform values in `0.0..1.0` for step `index` of a walk.
+#[inline]
+fn random_unit(seed: u32, index: i32) -> Vec3 {
+    vec3(
+        hash_unit(seed, ivec3(index, 0, 0)),
+        hash_unit(seed, ivec3(index, 1, 0)),
+        hash_unit(seed, ivec3(index, 2, 0)),
+    )
+}
+
+/// Points along a vein's random walk from `start`, in world voxels.
+fn vein_path(seed: u32, start: Vec3, config: &VeinConfig) -> impl Iterator<Item = Vec3> + use<> {
+    let random_direction =
+        move |index: i32| (random_unit(seed, index) * 2.0 - 1.0).normalize_or(Vec3::X);
+    let (step, turn) = (config.step, config.turn);
+    let mut position = start;
+    let mut direction = random_direction(1);
+    (0..config.length as i32).map(move |index| {
+        let point = position;
+        direction = (direction + random_direction(index + 2) * turn).normalize_or(direction);
+        position += direction * step;
+        point
+    })
+}
+
+/// Paints the vein's sphere around `center`, in field coordinates.
+fn paint_vein_point(
+    field: &mut (impl MaterialStorage + ?Sized),
+    density: &(impl DensitySource + ?Sized),
+    center: Vec3,
+    size: IVec3,
+    material_id: u8,
+    config: &VeinConfig,
+) -> bool {
+    let radius = config.radius;
+    let min = (center - radius).ceil().as_ivec3().max(IVec3::ZERO);
+    let max = (center + radius).floor().as_ivec3().min(size - 1);
+    let mut changed = false;
+    for z in min.z..=max.z {
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let pos = ivec3(x, y, z);
+                if pos.as_vec3().distance_squared(center) > radius * radius
+                    || density.density(pos).is_none_or(|value| value >= 0.0)
+                {
+                    continue;
+                }
+                let pos = pos.as_uvec3();
+                let current = field.get(pos);
+                if current == material_id || config.host.is_some_and(|host| !host.allows(current)) {
+                    continue;
+                }
+                field.set(pos, material_id);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_sculpter::field::Field;
+
+    use super::*;
+    use crate::material_field::SvoMaterialField;
+
+    #[test]
+    fn test_noise_ranges_and_determinism() {
+        for kind in [NoiseKind::Value, NoiseKind::Perlin, NoiseKind::Simplex] {
+            let noise = WorldNoise::new(kind, 3).with_octaves(3);
+            for i in 0..500 {
+                let p = ivec3(i * 7 - 1000, i * 3, -i * 5);
+                let value = noise.sample(p);
+                assert!((0.0..=1.0).contains(&value), "{kind:?} {value}");
+                assert_eq!(value, noise.sample(p));
+            }
+        }
+        assert_eq!(perlin3(1, vec3(4.0, -2.0, 7.0)), 0.0);
+    }
+
+    #[test]
+    fn test_noise_is_continuous() {
+        let noise = WorldNoise::simplex(5).with_frequency(1.0 / 32.0);
+        for i in 0..200 {
+            let p = vec3(i as f32 * 0.37, 11.0, -3.0);
+            let step = (noise.sample_at(p) - noise.sample_at(p + Vec3::X * 0.01)).abs();
+            assert!(step < 0.05, "{step}");
+        }
+    }
+
+    #[test]
+    fn test_fill_matches_across_chunk_border() {
+        let noise = WorldNoise::perlin(9).with_frequency(1.0 / 8.0);
+        let bands = [(0.5, 1), (1.0, 2)];
+        let mut left = MaterialField::new();
+        let mut right = MaterialField::new();
+        fill_noise(&mut left, ivec3(-1, 0, 0), &noise, &bands);
+        fill_noise(&mut right, IVec3::ZERO, &noise, &bands);
+
+        // Both chunks sample the same world lattice
+        assert_eq!(
+            world_voxel(ivec3(-1, 0, 0), uvec3(31, 0, 0)),
+            ivec3(-1, 0, 0)
+        );
+        for z in 0..32 {
+            let expected = |x: i32| {
+                if noise.sample(ivec3(x, 4, z)) < 0.5 {
+                    1
+                } else {
+                    2
+                }
+            };
+            assert_eq!(left.get(31, 4, z as u32), expected(-1));
+            assert_eq!(right.get(0, 4, z as u32), expected(0));
+        }
+
+        let mut field = MaterialField::filled(1);
+        assert!(scatter(
+            &mut field,
+            IVec3::ZERO,
+            4,
+            0.1,
+            9,
+            Some(&MaterialMask::only(&[1]))
+        ));
+        assert!((field.coverage(9) - 0.1).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_strata_tilt_across_chunk_border() {
+        let layers = [(4.0, 1), (4.0, 2)];
+        let mut left = MaterialField::new();
+        let mut right = MaterialField::new();
+        fill_strata(&mut left, IVec3::ZERO, &layers, vec2(45.0, 0.0), None);
+        fill_strata(&mut right, IVec3::X, &layers, vec2(45.0, 0.0), None);
+
+        // Bands follow x + y, checked mid-band on both sides of the border
+        for x in 24..40u32 {
+            let field = if x < 32 { &left } else { &right };
+            for y in 0..16u32 {
+                let expected = match (x + y) % 8 {
+                    2 => 1,
+                    6 => 2,
+                    _ => continue,
+                };
+                assert_eq!(field.get(x % 32, y, 7), expected, "{x} {y}");
+            }
+        }
+
+        // Warping bends the bands without changing their materials
+        let noise = WorldNoise::simplex(2).with_frequency(1.0 / 16.0);
+        let mut flat = MaterialField::new();
+        let mut warped = MaterialField::new();
+        fill_strata(&mut flat, IVec3::ZERO, &layers, Vec2::ZERO, None);
+        fill_strata(
+            &mut warped,
+            IVec3::ZERO,
+            &layers,
+            Vec2::ZERO,
+            Some((&noise, 3.0)),
+        );
+        assert_ne!(warped.0, flat.0);
+        assert!(warped.iter().all(|&id| id == 1 || id == 2));
+    }
+
+    #[test]
+    fn test_voronoi_patches_split_between_seeds() {
+        let seeds = [(vec3(-8.0, 16.0, 16.0), 1), (vec3(8.0, 16.0, 16.0), 2)];
+        let mut left = MaterialField::new();
+        let mut right = MaterialField::filled(3);
+        assert!(fill_voronoi_patches(
+            &mut left,
+            ivec3(-1, 0, 0),
+            &seeds,
+            None,
+            None
+        ));
+        right.set(20, 4, 4, 7);
+        fill_voronoi_patches(
+            &mut right,
+            IVec3::ZERO,
+            &seeds,
+            None,
+            Some(&MaterialMask::only(&[3])),
+        );
+        assert!(left.iter().all(|&id| id == 1));
+        assert_eq!(right.get(1, 30, 2), 2);
+        assert_eq!(right.get(20, 4, 4), 7);
+
+        // Jitter moves the border near x = 0 but not far from it
+        let noise = WorldNoise::simplex(8).with_frequency(1.0 / 6.0);
+        let mut jittered = MaterialField::new();
+        fill_voronoi_patches(
+            &mut jittered,
+            ivec3(-1, 0, 0),
+            &seeds,
+            Some((&noise, 3.0)),
+            None,
+        );
+        assert!(jittered.iter().any(|&id| id == 2));
+        assert!((0..32).all(|i| jittered.get(20, i, 31 - i) == 1));
+    }
+
+    /// Solid ground of any size.
+    struct Solid(UVec3);
+
+    impl DensitySource for Solid {
+        fn size(&self) -> UVec3 {
+            self.0
+        }
+
+        fn density(&self, pos: IVec3) -> Option<f32> {
+            (pos.cmpge(IVec3::ZERO).all() && pos.cmplt(self.0.as_ivec3()).all()).then_some(-1.0)
+        }
+    }
+
+    #[test]
+    fn test_veins_continue_across_chunk_border() {
+        let config = VeinConfig::default().with_veins_per_cell(6);
+        let mut left = MaterialField::filled(1);
+        let mut right = MaterialField::filled(1);
+        assert!(scatter_veins(
+            &mut left,
+            IVec3::ZERO,
+            &Solid(FIELD_SIZE),
+            7,
+            3,
+            &config
+        ));
+        scatter_veins(&mut right, IVec3::X, &Solid(FIELD_SIZE), 7, 3, &config);
+
+        // One field spanning both chunks paints the same voxels
+        let span = uvec3(64, 32, 32);
+        let mut both = SvoMaterialField::new(64, 1);
+        scatter_veins(&mut both, IVec3::ZERO, &Solid(span), 7, 3, &config);
+        for (pos, material) in left.enumerate_coords() {
+            assert_eq!(MaterialStorage::get(&both, pos), material);
+        }
+        for (pos, material) in right.enumerate_coords() {
+            assert_eq!(MaterialStorage::get(&both, pos + UVec3::X * 32), material);
+        }
+
+        // Veins stay out of empty space
+        let mut air = MaterialField::filled(1);
+        assert!(!scatter_veins(
+            &mut air,
+            IVec3::ZERO,
+            &Solid(UVec3::ZERO),
+            7,
+            3,
+            &config
+        ));
+    }
+}
@@ -4,6 +4,7 @@ use bevy::asset::Assets;
 use bevy::image::Image;
 use bevy::prelude::*;
 
+use super::layers::LayerReplacement;
 use super::properties::PaletteMaterial;
 use super::validation::{self, PaletteValidationError};
 
@@ -22,24 +23,26 @@ use super::validation::{self, PaletteValidationError};
 /// - Albedo: sRGB format (e.g., `Rgba8UnormSrgb`, `Bc7RgbaUnormSrgb`)
 /// - Normal/ARM: Linear format (e.g., `Rgba8Unorm`, `Bc5RgUnorm`)
 ///
+/// Fields may be added in minor releases, so palettes are created with
+/// [`PaletteBuilder`](super::PaletteBuilder) or from [`Default`] rather
+/// than with a struct literal.
+///
 /// # Example
 ///
 /// ```ignore
-/// use bevy_painter::palette::{TexturePalette, PaletteMaterial};
+/// use bevy_painter::palette::{PaletteBuilder, PaletteMaterial};
 ///
-/// let palette = TexturePalette {
-///     albedo: asset_server.load("terrain/albedo.ktx2"),
-///     normal: Some(asset_server.load("terrain/normal.ktx2")),
-///     arm: Some(asset_server.load("terrain/arm.ktx2")),
-///     materials: vec![
-///         PaletteMaterial::new("grass").with_texture_scale(1.0),
-///         PaletteMaterial::new("stone").with_texture_scale(0.5),
-///         PaletteMaterial::new("dirt").with_texture_scale(1.0),
-///     ],
-///     generate_mipmaps: false, // KTX2 should have mipmaps baked in
-/// };
+/// let palette = PaletteBuilder::new()
+///     .with_albedo(asset_server.load("terrain/albedo.ktx2"))
+///     .with_normal(asset_server.load("terrain/normal.ktx2"))
+///     .with_arm(asset_server.load("terrain/arm.ktx2"))
+///     .add_material(PaletteMaterial::new("grass").with_texture_scale(1.0))
+///     .add_material(PaletteMaterial::new("stone").with_texture_scale(0.5))
+///     .add_material(PaletteMaterial::new("dirt").with_texture_scale(1.0))
+///     .build();
 /// ```
 #[derive(Asset, TypePath, Clone, Debug)]
+#[non_exhaustive]
 pub struct TexturePalette {
     /// Albedo (base color) texture array.
     ///
@@ -58,6 +61,14 @@ pub struct TexturePalette {
     /// Channel layout: R = AO, G = Roughness, B = Metallic
     pub arm: Option<Handle<Image>>,
 
+    /// Height texture array.
+    ///
+    /// Optional. Must be linear format, or single-channel `R8Unorm`; only
+    /// the R channel is read.
+    /// Drives per-material vertex displacement (see
+    /// [`PaletteMaterial::displacement_strength`]).
+    pub height: Option<Handle<Image>>,
+
     /// Per-layer material properties.
     ///
     /// The length of this vector should match the layer count of the textures.
@@ -69,6 +80,11 @@ pub struct TexturePalette {
     /// When using pre-mipmapped KTX2 textures (recommended), set this to `false`.
     /// Default: `false`
     pub generate_mipmaps: bool,
+
+    /// Layer replacements waiting to be copied to the GPU.
+    ///
+    /// Filled by [`TexturePalette::replace_layer`] and drained by the plugin.
+    pub layer_replacements: Vec<LayerReplacement>,
 }
 
 impl Default for TexturePalette {
@@ -77,8 +93,10 @@ impl Default for TexturePalette {
             albedo: Handle::default(),
             normal: None,
             arm: None,
+            height: None,
             materials: Vec::new(),
             generate_mipmaps: false,
+            layer_replacements: Vec::new(),
         }
     }
 }
@@ -99,6 +117,11 @@ impl TexturePalette {
         self.arm.is_some()
     }
 
+    /// Check if this palette has a height array.
+    pub fn has_height(&self) -> bool {
+        self.height.is_some()
+    }
+
     /// Validate the palette against loaded image assets.
     ///
     /// This checks that:
@@ -136,6 +159,13 @@ impl TexturePalette {
             }
         }
 
+        // Validate height (optional)
+        if let Some(ref height_handle) = self.height {
+            if let Some(height_image) = images.get(height_handle) {
+                validation::validate_linear_texture(height_image, albedo_image, "height")?;
+            }
+        }
+
         // Validate material count
         validation::validate_material_count(self.materials.len(), layer_count)?;
 
@@ -162,5 +192,6 @@ mod tests {
         assert_eq!(palette.material_count(), 0);
         assert!(!palette.has_normal_maps());
         assert!(!palette.has_arm());
+        assert!(!palette.has_height());
     }
 }
@@ -28,6 +28,7 @@ pub struct PaletteBuilder {
     albedo: Option<Handle<Image>>,
     normal: Option<Handle<Image>>,
     arm: Option<Handle<Image>>,
+    height: Option<Handle<Image>>,
     materials: Vec<PaletteMaterial>,
     generate_mipmaps: bool,
 }
@@ -62,6 +63,14 @@ impl PaletteBuilder {
         self
     }
 
+    /// Set the height texture array.
+    ///
+    /// Optional. Enables per-material vertex displacement when provided.
+    pub fn with_height(mut self, height: Handle<Image>) -> Self {
+        self.height = Some(height);
+        self
+    }
+
     /// Add a material to the palette.
     ///
     /// Materials are added in order, corresponding to texture array layers.
@@ -99,8 +108,10 @@ impl PaletteBuilder {
             albedo: self.albedo.expect("Albedo texture is required"),
             normal: self.normal,
             arm: self.arm,
+            height: self.height,
             materials: self.materials,
             generate_mipmaps: self.generate_mipmaps,
+            layer_replacements: Vec::new(),
         }
     }
 
@@ -112,8 +123,10 @@ impl PaletteBuilder {
             albedo: self.albedo?,
             normal: self.normal,
             arm: self.arm,
+            height: self.height,
             materials: self.materials,
             generate_mipmaps: self.generate_mipmaps,
+            layer_replacements: Vec::new(),
         })
     }
 }
@@ -132,8 +145,10 @@ pub trait QuickPalette {
             albedo,
             normal: None,
             arm: None,
+            height: None,
             materials,
             generate_mipmaps: false,
+            layer_replacements: Vec::new(),
         }
     }
 }
@@ -163,12 +178,14 @@ mod tests {
             .with_albedo(Handle::default())
             .with_normal(Handle::default())
             .with_arm(Handle::default())
+            .with_height(Handle::default())
             .add_material(PaletteMaterial::new("grass").with_texture_scale(2.0))
             .with_generate_mipmaps(true)
             .build();
 
         assert!(palette.has_normal_maps());
         assert!(palette.has_arm());
+        assert!(palette.has_height());
         assert!(palette.generate_mipmaps);
         assert_eq!(palette.materials[0].texture_scale, 2.0);
     }
@@ -0,0 +1,122 @@
+//! Average colours of palette texture layers.
+//!
+//! Used wherever a single colour per material is needed instead of a texture,
+//! such as matching imported voxel colours or baking vertex colours.
+
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+
+use super::asset::TexturePalette;
+use super::streaming::layer_mip_range;
+
+impl TexturePalette {
+    /// Average albedo colour of each material layer.
+    ///
+    /// Returns `None` if the albedo array isn't loaded or can't be read, see
+    /// [`material_average_colors`].
+    pub fn average_colors(&self, images: &Assets<Image>) -> Option<Vec<Color>> {
+        material_average_colors(images.get(&self.albedo)?)
+    }
+}
+
+/// Average colour of each layer of an albedo texture array.
+///
+/// Reads the top mip of every layer. Texels of `*Srgb` formats are decoded
+/// as sRGB, the others as linear. Returns `None` if the image has no CPU
+/// data or isn't an uncompressed 8-bit RGBA/BGRA format.
+pub fn material_average_colors(albedo: &Image) -> Option<Vec<Color>> {
+    let data = albedo.data.as_ref()?;
+    let descriptor = &albedo.texture_descriptor;
+    let (bgra, srgb) = match descriptor.format {
+        TextureFormat::Rgba8Unorm => (false, false),
+        TextureFormat::Rgba8UnormSrgb => (false, true),
+        TextureFormat::Bgra8Unorm => (true, false),
+        TextureFormat::Bgra8UnormSrgb => (true, true),
+        _ => return None,
+    };
+
+    let layers = descriptor.size.depth_or_array_layers;
+    let colors = (0..layers)
+        .map(|layer| {
+            let range = layer_mip_range(
+                descriptor.size,
+                descriptor.format,
+                descriptor.mip_level_count,
+                albedo.data_order,
+                layer,
+                0,
+            );
+            let texels = data.get(range)?;
+            let mut sum = Vec3::ZERO;
+            for texel in texels.chunks_exact(4) {
+                let (r, b) = if bgra {
+                    (texel[2], texel[0])
+                } else {
+                    (texel[0], texel[2])
+                };
+                // Average in linear space
+                let color = if srgb {
+                    LinearRgba::from(Srgba::rgb_u8(r, texel[1], b))
+                } else {
+                    LinearRgba::rgb(r as f32 / 255.0, texel[1] as f32 / 255.0, b as f32 / 255.0)
+                };
+                sum += color.to_vec3();
+            }
+            let count = (texels.len() / 4).max(1) as f32;
+            let mean = sum / count;
+            Some(Color::linear_rgb(mean.x, mean.y, mean.z))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{Extent3d, TextureDimension};
+
+    #[test]
+    fn test_average_colors() {
+        let red = [255u8, 0, 0, 255];
+        let blue = [0u8, 0, 255, 255];
+        let data: Vec<u8> = [red; 4].into_iter().chain([blue; 4]).flatten().collect();
+        let image = Image::new(
+            Extent3d {
+                width: 2,
+                height: 2,
+                depth_or_array_layers: 2,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+
+        let colors = material_average_colors(&image).unwrap();
+        assert_eq!(colors.len(), 2);
+        assert_eq!(colors[0].to_srgba().to_u8_array(), red);
+        assert_eq!(colors[1].to_srgba().to_u8_array(), blue);
+    }
+
+    #[test]
+    fn test_average_colors_linear_format() {
+        let gray = [128u8, 128, 128, 255];
+        let image = Image::new(
+            Extent3d {
+                width: 2,
+                height: 2,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            gray.repeat(4),
+            TextureFormat::Rgba8Unorm,
+            RenderAssetUsages::default(),
+        );
+
+        let colors = material_average_colors(&image).unwrap();
+        let linear = colors[0].to_linear();
+        assert!((linear.red - 128.0 / 255.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,259 @@
+//! Runtime replacement of individual palette texture layers.
+//!
+//! [`TexturePalette::replace_layer`] queues a copy of a single 2D image into
+//! one layer of an existing texture array. The copy happens on the GPU, so
+//! bind groups stay valid and no material has to be recreated or re-prepared,
+//! which makes swapping texture packs at runtime cheap.
+//!
+//! The CPU-side array [`Image`] is not modified. If the array image asset is
+//! changed later, its next upload overwrites replaced layers.
+
+use bevy::prelude::*;
+use bevy::render::{
+    Extract, ExtractSchedule, Render, RenderApp, RenderSystems,
+    render_asset::RenderAssets,
+    render_resource::{
+        CommandEncoderDescriptor, Origin3d, TexelCopyTextureInfo, TextureAspect, TextureDimension,
+    },
+    renderer::{RenderDevice, RenderQueue},
+    texture::GpuImage,
+};
+
+use super::asset::TexturePalette;
+
+/// Which texture array of a palette a layer belongs to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum PaletteTexture {
+    #[default]
+    Albedo,
+    Normal,
+    Arm,
+    Height,
+}
+
+/// A pending copy of `image` into layer `layer` of a palette texture array.
+#[derive(Clone, Debug)]
+pub struct LayerReplacement {
+    pub target: PaletteTexture,
+    pub layer: u32,
+    /// A single-layer 2D image with the same size, format and mip count as
+    /// the target array.
+    pub image: Handle<Image>,
+}
+
+impl TexturePalette {
+    /// Replace an albedo layer at runtime.
+    ///
+    /// See [`TexturePalette::replace_layer_in`].
+    pub fn replace_layer(&mut self, index: u32, image: Handle<Image>) {
+        self.replace_layer_in(PaletteTexture::Albedo, index, image);
+    }
+
+    /// Replace a layer of one of the palette's texture arrays at runtime.
+    ///
+    /// The copy is done on the GPU once `image` is loaded. `image` must match
+    /// the array's per-layer size and format; mismatches are logged and
+    /// skipped.
+    pub fn replace_layer_in(&mut self, target: PaletteTexture, index: u32, image: Handle<Image>) {
+        self.layer_replacements.push(LayerReplacement {
+            target,
+            layer: index,
+            image,
+        });
+    }
+
+    /// The texture array handle for `target`, if the palette has one.
+    pub fn texture(&self, target: PaletteTexture) -> Option<&Handle<Image>> {
+        match target {
+            PaletteTexture::Albedo => Some(&self.albedo),
+            PaletteTexture::Normal => self.normal.as_ref(),
+            PaletteTexture::Arm => self.arm.as_ref(),
+            PaletteTexture::Height => self.height.as_ref(),
+        }
+    }
+}
+
+/// A layer copy resolved to concrete images.
+#[derive(Clone, Debug)]
+struct LayerCopy {
+    source: Handle<Image>,
+    target: AssetId<Image>,
+    layer: u32,
+}
+
+/// Copies queued this frame, read by the render world.
+#[derive(Resource, Default)]
+struct QueuedLayerCopies(Vec<LayerCopy>);
+
+/// Render-world copies waiting for their images to be uploaded.
+#[derive(Resource, Default)]
+struct PendingLayerCopies(Vec<LayerCopy>);
+
+pub(crate) fn build_layer_replacement(app: &mut App) {
+    app.init_resource::<QueuedLayerCopies>()
+        .add_systems(PostUpdate, queue_layer_replacements);
+
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+        return;
+    };
+
+    render_app
+        .init_resource::<PendingLayerCopies>()
+        .add_systems(ExtractSchedule, extract_layer_copies)
+        .add_systems(
+            Render,
+            copy_palette_layers.in_set(RenderSystems::PrepareResources),
+        );
+}
+
+/// Moves replacements out of palettes into the queue for the render world.
+fn queue_layer_replacements(
+    mut palettes: ResMut<Assets<TexturePalette>>,
+    mut queued: ResMut<QueuedLayerCopies>,
+) {
+    if !queued.0.is_empty() {
+        queued.0.clear();
+    }
+
+    let ids: Vec<_> = palettes
+        .iter()
+        .filter(|(_, palette)| !palette.layer_replacements.is_empty())
+        .map(|(id, _)| id)
+        .collect();
+
+    for id in ids {
+        // Untracked: the palette's handles are unchanged, so there's
+        // nothing to revalidate or re-prepare
+        let Some(palette) = palettes.get_mut_untracked(id) else {
+            continue;
+        };
+        for replacement in std::mem::take(&mut palette.layer_replacements) {
+            let Some(target) = palette.texture(replacement.target) else {
+                warn!(
+                    "Palette has no {:?} array; layer {} replacement skipped",
+                    replacement.target, replacement.layer
+                );
+                continue;
+            };
+            queued.0.push(LayerCopy {
+                source: replacement.image,
+                target: target.id(),
+                layer: replacement.layer,
+            });
+        }
+    }
+}
+
+fn extract_layer_copies(
+    mut pending: ResMut<PendingLayerCopies>,
+    queued: Extract<Res<QueuedLayerCopies>>,
+) {
+    if queued.is_changed() {
+        pending.0.extend(queued.0.iter().cloned());
+    }
+}
+
+fn copy_palette_layers(
+    mut pending: ResMut<PendingLayerCopies>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    if pending.0.is_empty() {
+        return;
+    }
+
+    let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("palette_layer_copy"),
+    });
+    let mut copied = false;
+
+    pending.0.retain(|copy| {
+        // Keep waiting until both images are on the GPU
+        let (Some(source), Some(target)) =
+            (gpu_images.get(&copy.source), gpu_images.get(copy.target))
+        else {
+            return true;
+        };
+
+        if let Err(reason) = check_layer_copy(source, target, copy.layer) {
+            warn!(
+                "Palette layer {} replacement skipped: {}",
+                copy.layer, reason
+            );
+            return false;
+        }
+
+        for mip in 0..source.mip_level_count.min(target.mip_level_count) {
+            let size = source
+                .size
+                .mip_level_size(mip, TextureDimension::D2)
+                .physical_size(source.texture_format);
+            encoder.copy_texture_to_texture(
+                TexelCopyTextureInfo {
+                    texture: &source.texture,
+                    mip_level: mip,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                TexelCopyTextureInfo {
+                    texture: &target.texture,
+                    mip_level: mip,
+                    origin: Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: copy.layer,
+                    },
+                    aspect: TextureAspect::All,
+                },
+                size,
+            );
+        }
+        copied = true;
+        false
+    });
+
+    if copied {
+        render_queue.submit([encoder.finish()]);
+    }
+}
+
+fn check_layer_copy(source: &GpuImage, target: &GpuImage, layer: u32) -> Result<(), String> {
+    if layer >= target.size.depth_or_array_layers {
+        return Err(format!(
+            "array has only {} layers",
+            target.size.depth_or_array_layers
+        ));
+    }
+    if source.texture_format != target.texture_format {
+        return Err(format!(
+            "format {:?} doesn't match array format {:?}",
+            source.texture_format, target.texture_format
+        ));
+    }
+    if source.size.width != target.size.width || source.size.height != target.size.height {
+        return Err(format!(
+            "size {}x{} doesn't match array size {}x{}",
+            source.size.width, source.size.height, target.size.width, target.size.height
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_layer_queues() {
+        let mut palette = TexturePalette::default();
+        palette.replace_layer(2, Handle::default());
+        palette.replace_layer_in(PaletteTexture::Normal, 3, Handle::default());
+
+        assert_eq!(palette.layer_replacements.len(), 2);
+        assert_eq!(palette.layer_replacements[0].target, PaletteTexture::Albedo);
+        assert_eq!(palette.layer_replacements[1].layer, 3);
+        assert!(palette.texture(PaletteTexture::Albedo).is_some());
+        assert!(palette.texture(PaletteTexture::Normal).is_none());
+    }
+}
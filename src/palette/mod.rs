@@ -6,10 +6,22 @@
 
 mod asset;
 mod builder;
+mod colors;
+mod layers;
 mod properties;
+mod streaming;
 mod validation;
 
 pub use asset::TexturePalette;
 pub use builder::PaletteBuilder;
-pub use properties::{MAX_MATERIALS, MaterialPropertiesGpu, PaletteMaterial};
+pub use colors::material_average_colors;
+pub use layers::{LayerReplacement, PaletteTexture};
+pub use properties::{
+    MAX_MATERIALS, MaterialGroup, MaterialPropertiesExtendedGpu, MaterialPropertiesGpu,
+    PaletteMaterial,
+};
+pub use streaming::{PaletteStreaming, VisibleMaterialUsage};
 pub use validation::PaletteValidationError;
+
+pub(crate) use layers::build_layer_replacement;
+pub(crate) use streaming::{build_palette_streaming, layer_mip_range};
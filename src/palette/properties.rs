@@ -41,6 +41,186 @@ pub struct PaletteMaterial {
     /// If `Some`, this value overrides the metallic from the ARM texture.
     /// If `None`, the ARM texture value is used.
     pub metallic_override: Option<f32>,
+
+    /// Normal map strength.
+    ///
+    /// Scales the tangent-space normal's XY components, so overly bumpy
+    /// source textures can be toned down without editing assets.
+    /// 0.0 = flat, 1.0 = unchanged, >1.0 = exaggerated.
+    ///
+    /// Default: 1.0
+    pub normal_strength: f32,
+
+    /// Multiplier applied to ARM texture roughness.
+    ///
+    /// Ignored when `roughness_override` is set.
+    ///
+    /// Default: 1.0
+    pub roughness_scale: f32,
+
+    /// Multiplier applied to ARM texture metallic.
+    ///
+    /// Ignored when `metallic_override` is set.
+    ///
+    /// Default: 1.0
+    pub metallic_scale: f32,
+
+    /// Ambient occlusion strength.
+    ///
+    /// 0.0 = no occlusion, 1.0 = ARM texture AO as authored.
+    ///
+    /// Default: 1.0
+    pub ao_strength: f32,
+
+    /// Strength of the cheap translucency (backlight) term.
+    ///
+    /// Lets light bleed through thin edges and towards the viewer when
+    /// looking at the sun, for snow, ice and jade. 0.0 disables it.
+    ///
+    /// Default: 0.0
+    pub translucency: f32,
+
+    /// How much the surface normal bends the light direction for translucency.
+    ///
+    /// Default: 0.2
+    pub translucency_distortion: f32,
+
+    /// Falloff exponent of the translucency term. Higher = tighter glow.
+    ///
+    /// Default: 4.0
+    pub translucency_power: f32,
+
+    /// View-independent translucency, so shaded sides never go fully dark.
+    ///
+    /// Default: 0.0
+    pub translucency_ambient: f32,
+
+    /// Randomly rotate and mirror the texture per world-space cell.
+    ///
+    /// Hides repetition on large flat areas at the cost of some extra shader
+    /// math, and extra samples near cell borders when `bombing_blend > 0`.
+    ///
+    /// Default: false
+    pub texture_bombing: bool,
+
+    /// Bombing cell size, in texture repeats.
+    ///
+    /// Default: 1.0
+    pub bombing_cell_size: f32,
+
+    /// Fraction of a cell over which neighbouring cells are cross-faded.
+    /// 0.0 gives hard cell borders.
+    ///
+    /// Default: 0.15
+    pub bombing_blend: f32,
+
+    /// Vertex displacement along the normal, in world units, at full height.
+    ///
+    /// Reads the palette's height array, so it has no effect without one.
+    /// A height of 0.0 leaves the surface in place. Useful for chunky gravel
+    /// or snow depth; keep it small, since collision uses the undisplaced mesh.
+    ///
+    /// Default: 0.0
+    pub displacement_strength: f32,
+
+    /// Optional texture scale used far from the camera.
+    ///
+    /// When set, the material is sampled at `texture_scale` up close and at
+    /// this scale in the distance, cross-faded between `far_blend_start` and
+    /// `far_blend_end`. Avoids picking one scale that tiles visibly far away
+    /// but looks stretched up close. Costs a second sample inside the band.
+    pub far_texture_scale: Option<f32>,
+
+    /// Camera distance where the cross-fade to `far_texture_scale` begins.
+    ///
+    /// Default: 20.0
+    pub far_blend_start: f32,
+
+    /// Camera distance where only `far_texture_scale` is sampled.
+    ///
+    /// Default: 60.0
+    pub far_blend_end: f32,
+
+    /// How strongly the extension's biome tint mask recolors this material.
+    ///
+    /// Meant for vegetation, so grass can shift from lush to dry across the
+    /// world without extra texture layers. 0.0 ignores the mask.
+    ///
+    /// Default: 0.0
+    pub hue_variation: f32,
+
+    /// How strongly distance fog covers this material.
+    ///
+    /// 1.0 fogs like the rest of the scene, 0.0 ignores fog entirely, so
+    /// glowing materials like lava stay visible through haze. Deferred
+    /// rendering can only fog fully or not at all, cutting at 0.5.
+    ///
+    /// Default: 1.0
+    pub fog_multiplier: f32,
+
+    /// Optional specular intensity (Bevy's `reflectance`).
+    ///
+    /// If `None`, Bevy's default of 0.5 is used.
+    pub specular: Option<f32>,
+
+    /// Clearcoat layer strength, for wet lava crust, ice or polished stone.
+    ///
+    /// 0.0 disables the clearcoat term.
+    ///
+    /// Default: 0.0
+    pub clearcoat: f32,
+
+    /// Perceptual roughness of the clearcoat layer.
+    ///
+    /// Default: 0.5
+    pub clearcoat_roughness: f32,
+
+    /// Pull of this material in CPU vertex blending.
+    ///
+    /// Each step of 1.0 doubles the material's weight against its
+    /// neighbours, so high-priority materials (roads, lava) win ties and keep
+    /// crisp edges while low-priority filler (dirt) yields. Copied into the
+    /// blend settings by `MaterialBlendSettings::with_palette`.
+    ///
+    /// Default: 0.0
+    pub blend_priority: f32,
+
+    /// Never blend this material softly with its neighbours.
+    ///
+    /// For tile floors or metal plating. Vertices touching the material
+    /// snap to their dominant material in CPU vertex blending, and the shader
+    /// sharpens the weight curve between materials wherever it's present.
+    ///
+    /// Default: false
+    pub hard_edges: bool,
+
+    /// Grow shell-textured grass fur where this material dominates.
+    ///
+    /// Only drawn on entities with `RenderGrassShells`; shell count and
+    /// height come from the material's `TriplanarExtension::grass_shells`.
+    ///
+    /// Default: false
+    pub grass_shells: bool,
+
+    /// Category used by blending rules, so e.g. water never smears into
+    /// brick. See `MaterialBlendSettings::group_rules`.
+    ///
+    /// Default: [`MaterialGroup::Natural`]
+    pub group: MaterialGroup,
+}
+
+/// Broad category of a material, for group blending rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum MaterialGroup {
+    /// Rock, soil, grass, sand.
+    #[default]
+    Natural,
+    /// Water, lava, mud puddles.
+    Liquid,
+    /// Brick, tiles, concrete, metal.
+    Artificial,
+    /// Application-defined category.
+    Custom(u8),
 }
 
 impl Default for PaletteMaterial {
@@ -51,6 +231,30 @@ impl Default for PaletteMaterial {
             blend_sharpness: 4.0,
             roughness_override: None,
             metallic_override: None,
+            normal_strength: 1.0,
+            roughness_scale: 1.0,
+            metallic_scale: 1.0,
+            ao_strength: 1.0,
+            translucency: 0.0,
+            translucency_distortion: 0.2,
+            translucency_power: 4.0,
+            translucency_ambient: 0.0,
+            texture_bombing: false,
+            bombing_cell_size: 1.0,
+            bombing_blend: 0.15,
+            displacement_strength: 0.0,
+            far_texture_scale: None,
+            far_blend_start: 20.0,
+            far_blend_end: 60.0,
+            hue_variation: 0.0,
+            fog_multiplier: 1.0,
+            specular: None,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.5,
+            blend_priority: 0.0,
+            hard_edges: false,
+            grass_shells: false,
+            group: MaterialGroup::Natural,
         }
     }
 }
@@ -87,12 +291,126 @@ impl PaletteMaterial {
         self.metallic_override = Some(metallic);
         self
     }
+
+    /// Set the normal map strength.
+    pub fn with_normal_strength(mut self, strength: f32) -> Self {
+        self.normal_strength = strength;
+        self
+    }
+
+    /// Set the roughness multiplier.
+    pub fn with_roughness_scale(mut self, scale: f32) -> Self {
+        self.roughness_scale = scale;
+        self
+    }
+
+    /// Set the metallic multiplier.
+    pub fn with_metallic_scale(mut self, scale: f32) -> Self {
+        self.metallic_scale = scale;
+        self
+    }
+
+    /// Set the ambient occlusion strength.
+    pub fn with_ao_strength(mut self, strength: f32) -> Self {
+        self.ao_strength = strength;
+        self
+    }
+
+    /// Enable translucency with the given strength.
+    pub fn with_translucency(mut self, strength: f32) -> Self {
+        self.translucency = strength;
+        self
+    }
+
+    /// Set the translucency shape parameters.
+    pub fn with_translucency_shape(mut self, distortion: f32, power: f32, ambient: f32) -> Self {
+        self.translucency_distortion = distortion;
+        self.translucency_power = power;
+        self.translucency_ambient = ambient;
+        self
+    }
+
+    /// Enable texture bombing with the given cell size and border blend.
+    pub fn with_texture_bombing(mut self, cell_size: f32, blend: f32) -> Self {
+        self.texture_bombing = true;
+        self.bombing_cell_size = cell_size;
+        self.bombing_blend = blend;
+        self
+    }
+
+    /// Set the vertex displacement strength.
+    pub fn with_displacement(mut self, strength: f32) -> Self {
+        self.displacement_strength = strength;
+        self
+    }
+
+    /// Cross-fade to a second texture scale between two camera distances.
+    pub fn with_far_texture_scale(mut self, scale: f32, blend_start: f32, blend_end: f32) -> Self {
+        self.far_texture_scale = Some(scale);
+        self.far_blend_start = blend_start;
+        self.far_blend_end = blend_end;
+        self
+    }
+
+    /// Let the biome tint mask shift this material's hue and saturation.
+    pub fn with_hue_variation(mut self, strength: f32) -> Self {
+        self.hue_variation = strength;
+        self
+    }
+
+    /// Set how strongly distance fog covers this material.
+    pub fn with_fog_multiplier(mut self, multiplier: f32) -> Self {
+        self.fog_multiplier = multiplier;
+        self
+    }
+
+    /// Set the specular intensity.
+    pub fn with_specular(mut self, specular: f32) -> Self {
+        self.specular = Some(specular);
+        self
+    }
+
+    /// Enable a clearcoat layer with the given strength and roughness.
+    pub fn with_clearcoat(mut self, clearcoat: f32, roughness: f32) -> Self {
+        self.clearcoat = clearcoat;
+        self.clearcoat_roughness = roughness;
+        self
+    }
+
+    /// Set the blending priority.
+    pub fn with_blend_priority(mut self, priority: f32) -> Self {
+        self.blend_priority = priority;
+        self
+    }
+
+    /// Disable soft blending with neighbouring materials.
+    pub fn with_hard_edges(mut self) -> Self {
+        self.hard_edges = true;
+        self
+    }
+
+    /// Grow shell-textured grass where this material dominates.
+    pub fn with_grass_shells(mut self) -> Self {
+        self.grass_shells = true;
+        self
+    }
+
+    /// Set the material's blending group.
+    pub fn with_group(mut self, group: MaterialGroup) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Whether this material needs [`MaterialPropertiesExtendedGpu`].
+    pub fn uses_extended_properties(&self) -> bool {
+        self.specular.is_some() || self.clearcoat > 0.0
+    }
 }
 
 /// GPU-side representation of material properties.
 ///
 /// This is stored in a uniform buffer and indexed by material ID in the shader.
-#[derive(Clone, Copy, Debug, Default, ShaderType, Pod, Zeroable)]
+#[derive(Clone, Copy, Debug, ShaderType, Pod, Zeroable)]
 #[repr(C)]
 pub struct MaterialPropertiesGpu {
     /// Texture scale (world units per repeat).
@@ -106,6 +424,101 @@ pub struct MaterialPropertiesGpu {
 
     /// Metallic override. Negative value means "use texture".
     pub metallic_override: f32,
+
+    /// Tangent-space normal XY scale.
+    pub normal_strength: f32,
+
+    /// ARM roughness multiplier.
+    pub roughness_scale: f32,
+
+    /// ARM metallic multiplier.
+    pub metallic_scale: f32,
+
+    /// ARM ambient occlusion strength.
+    pub ao_strength: f32,
+
+    /// Translucency strength. 0.0 disables the term.
+    pub translucency: f32,
+
+    /// Translucency normal distortion.
+    pub translucency_distortion: f32,
+
+    /// Translucency falloff exponent.
+    pub translucency_power: f32,
+
+    /// View-independent translucency.
+    pub translucency_ambient: f32,
+
+    /// `MaterialPropertiesGpu::FLAG_*` bits.
+    pub flags: u32,
+
+    /// Texture bombing cell size, in texture repeats.
+    pub bombing_cell_size: f32,
+
+    /// Texture bombing border blend.
+    pub bombing_blend: f32,
+
+    /// Height-map displacement along the normal. 0.0 disables it.
+    pub displacement_strength: f32,
+
+    /// Far texture scale. 0.0 disables the near/far cross-fade.
+    pub far_texture_scale: f32,
+
+    /// Camera distance where the far cross-fade begins.
+    pub far_blend_start: f32,
+
+    /// Camera distance where the far cross-fade ends.
+    pub far_blend_end: f32,
+
+    /// Biome tint mask strength. 0.0 ignores the mask.
+    pub hue_variation: f32,
+
+    /// Distance fog strength. 0.0 ignores fog.
+    pub fog_multiplier: f32,
+}
+
+impl MaterialPropertiesGpu {
+    /// Randomly rotate/mirror the texture per world-space cell.
+    pub const FLAG_TEXTURE_BOMBING: u32 = 1 << 0;
+    /// Sharpen blending towards this material to a hard edge.
+    pub const FLAG_HARD_EDGES: u32 = 1 << 1;
+    /// Draw grass shells over this material.
+    pub const FLAG_GRASS_SHELLS: u32 = 1 << 2;
+}
+
+/// Neutral properties: the material's texture is sampled as-is at the
+/// material's global scale and sharpness.
+///
+/// This used to be the derived all-zero value, which sampled at a texture
+/// scale of 0 and forced roughness and metallic to 0, e.g. for materials
+/// added with [`TriplanarExtension::with_materials`](crate::material::TriplanarExtension::with_materials).
+/// Use [`Zeroable::zeroed`] where the old value is needed.
+impl Default for MaterialPropertiesGpu {
+    fn default() -> Self {
+        Self {
+            texture_scale: 1.0,
+            blend_sharpness: 1.0,
+            roughness_override: -1.0,
+            metallic_override: -1.0,
+            normal_strength: 1.0,
+            roughness_scale: 1.0,
+            metallic_scale: 1.0,
+            ao_strength: 1.0,
+            translucency: 0.0,
+            translucency_distortion: 0.2,
+            translucency_power: 4.0,
+            translucency_ambient: 0.0,
+            flags: 0,
+            bombing_cell_size: 1.0,
+            bombing_blend: 0.15,
+            displacement_strength: 0.0,
+            far_texture_scale: 0.0,
+            far_blend_start: 20.0,
+            far_blend_end: 60.0,
+            hue_variation: 0.0,
+            fog_multiplier: 1.0,
+        }
+    }
 }
 
 impl From<&PaletteMaterial> for MaterialPropertiesGpu {
@@ -115,6 +528,83 @@ impl From<&PaletteMaterial> for MaterialPropertiesGpu {
             blend_sharpness: mat.blend_sharpness,
             roughness_override: mat.roughness_override.unwrap_or(-1.0),
             metallic_override: mat.metallic_override.unwrap_or(-1.0),
+            normal_strength: mat.normal_strength,
+            roughness_scale: mat.roughness_scale,
+            metallic_scale: mat.metallic_scale,
+            ao_strength: mat.ao_strength,
+            translucency: mat.translucency,
+            translucency_distortion: mat.translucency_distortion,
+            translucency_power: mat.translucency_power,
+            translucency_ambient: mat.translucency_ambient,
+            flags: (mat.texture_bombing as u32 * Self::FLAG_TEXTURE_BOMBING)
+                | (mat.hard_edges as u32 * Self::FLAG_HARD_EDGES)
+                | (mat.grass_shells as u32 * Self::FLAG_GRASS_SHELLS),
+            bombing_cell_size: mat.bombing_cell_size,
+            bombing_blend: mat.bombing_blend,
+            displacement_strength: mat.displacement_strength,
+            far_texture_scale: mat.far_texture_scale.unwrap_or(0.0),
+            far_blend_start: mat.far_blend_start,
+            far_blend_end: mat.far_blend_end,
+            hue_variation: mat.hue_variation,
+            fog_multiplier: mat.fog_multiplier,
+        }
+    }
+}
+
+/// GPU-side material properties with specular and clearcoat terms.
+///
+/// A superset of [`MaterialPropertiesGpu`]. Only uploaded when at least one
+/// material needs it; the shader picks the matching struct layout through the
+/// `EXTENDED_MATERIAL_PROPERTIES` shader def, so materials that don't use
+/// these terms keep the smaller stride.
+#[derive(Clone, Copy, Debug, ShaderType, Pod, Zeroable)]
+#[repr(C)]
+pub struct MaterialPropertiesExtendedGpu {
+    /// Base properties.
+    pub base: MaterialPropertiesGpu,
+
+    /// Specular intensity. Negative value means "use default".
+    pub specular: f32,
+
+    /// Clearcoat strength. 0.0 disables the clearcoat term.
+    pub clearcoat: f32,
+
+    /// Clearcoat perceptual roughness.
+    pub clearcoat_roughness: f32,
+
+    /// Padding to a 16-byte multiple.
+    pub _padding: f32,
+}
+
+impl Default for MaterialPropertiesExtendedGpu {
+    fn default() -> Self {
+        Self {
+            base: MaterialPropertiesGpu::default(),
+            specular: -1.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.5,
+            _padding: 0.0,
+        }
+    }
+}
+
+impl From<MaterialPropertiesGpu> for MaterialPropertiesExtendedGpu {
+    fn from(base: MaterialPropertiesGpu) -> Self {
+        Self {
+            base,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&PaletteMaterial> for MaterialPropertiesExtendedGpu {
+    fn from(mat: &PaletteMaterial) -> Self {
+        Self {
+            base: mat.into(),
+            specular: mat.specular.unwrap_or(-1.0),
+            clearcoat: mat.clearcoat,
+            clearcoat_roughness: mat.clearcoat_roughness,
+            _padding: 0.0,
         }
     }
 }
@@ -183,6 +673,141 @@ mod tests {
         assert_eq!(gpu.texture_scale, 1.0);
         assert_eq!(gpu.roughness_override, 0.5);
         assert!(gpu.metallic_override < 0.0); // Indicates "use texture"
+        assert_eq!(gpu.normal_strength, 1.0);
+    }
+
+    #[test]
+    fn test_fog_multiplier() {
+        let gpu: MaterialPropertiesGpu = (&PaletteMaterial::new("dirt")).into();
+        assert_eq!(gpu.fog_multiplier, 1.0);
+
+        let lava = PaletteMaterial::new("lava").with_fog_multiplier(0.1);
+        let gpu: MaterialPropertiesExtendedGpu = (&lava).into();
+        assert_eq!(gpu.base.fog_multiplier, 0.1);
+    }
+
+    #[test]
+    fn test_normal_strength() {
+        let mat = PaletteMaterial::new("rock").with_normal_strength(0.25);
+        let gpu: MaterialPropertiesGpu = (&mat).into();
+
+        assert_eq!(gpu.normal_strength, 0.25);
+    }
+
+    #[test]
+    fn test_arm_adjusters() {
+        let mat = PaletteMaterial::new("polished")
+            .with_roughness_scale(0.5)
+            .with_metallic_scale(1.5)
+            .with_ao_strength(0.0);
+        let gpu: MaterialPropertiesGpu = (&mat).into();
+
+        assert_eq!(gpu.roughness_scale, 0.5);
+        assert_eq!(gpu.metallic_scale, 1.5);
+        assert_eq!(gpu.ao_strength, 0.0);
+    }
+
+    #[test]
+    fn test_translucency() {
+        let snow = PaletteMaterial::new("snow")
+            .with_translucency(0.6)
+            .with_translucency_shape(0.3, 8.0, 0.1);
+        let gpu: MaterialPropertiesGpu = (&snow).into();
+
+        assert_eq!(gpu.translucency, 0.6);
+        assert_eq!(gpu.translucency_distortion, 0.3);
+        assert_eq!(gpu.translucency_power, 8.0);
+        assert_eq!(gpu.translucency_ambient, 0.1);
+        assert_eq!(MaterialPropertiesGpu::default().translucency, 0.0);
+    }
+
+    #[test]
+    fn test_gpu_default_is_neutral() {
+        let gpu = MaterialPropertiesGpu::default();
+
+        assert_eq!(gpu.texture_scale, 1.0);
+        assert_eq!(gpu.blend_sharpness, 1.0);
+        assert!(gpu.roughness_override < 0.0);
+        assert!(gpu.metallic_override < 0.0);
+        assert_eq!(gpu.normal_strength, 1.0);
+    }
+
+    #[test]
+    fn test_texture_bombing_flag() {
+        let plain: MaterialPropertiesGpu = (&PaletteMaterial::new("floor")).into();
+        assert_eq!(plain.flags & MaterialPropertiesGpu::FLAG_TEXTURE_BOMBING, 0);
+
+        let bombed: MaterialPropertiesGpu =
+            (&PaletteMaterial::new("floor").with_texture_bombing(2.0, 0.1)).into();
+        assert_ne!(
+            bombed.flags & MaterialPropertiesGpu::FLAG_TEXTURE_BOMBING,
+            0
+        );
+        assert_eq!(bombed.bombing_cell_size, 2.0);
+    }
+
+    #[test]
+    fn test_hard_edges_flag() {
+        let tiles: MaterialPropertiesGpu =
+            (&PaletteMaterial::new("tiles").with_hard_edges()).into();
+        assert_eq!(tiles.flags, MaterialPropertiesGpu::FLAG_HARD_EDGES);
+
+        let extended: MaterialPropertiesExtendedGpu = (&PaletteMaterial::new("tiles")
+            .with_hard_edges()
+            .with_specular(0.2))
+            .into();
+        assert_eq!(extended.base.flags, MaterialPropertiesGpu::FLAG_HARD_EDGES);
+
+        let meadow: MaterialPropertiesGpu =
+            (&PaletteMaterial::new("meadow").with_grass_shells()).into();
+        assert_eq!(meadow.flags, MaterialPropertiesGpu::FLAG_GRASS_SHELLS);
+    }
+
+    #[test]
+    fn test_displacement_strength() {
+        let gravel = PaletteMaterial::new("gravel").with_displacement(0.05);
+        let gpu: MaterialPropertiesGpu = (&gravel).into();
+
+        assert_eq!(gpu.displacement_strength, 0.05);
+        assert_eq!(MaterialPropertiesGpu::default().displacement_strength, 0.0);
+    }
+
+    #[test]
+    fn test_far_texture_scale() {
+        let plain: MaterialPropertiesGpu = (&PaletteMaterial::new("grass")).into();
+        assert_eq!(plain.far_texture_scale, 0.0);
+
+        let grass = PaletteMaterial::new("grass").with_far_texture_scale(0.25, 10.0, 40.0);
+        let gpu: MaterialPropertiesGpu = (&grass).into();
+        assert_eq!(gpu.far_texture_scale, 0.25);
+        assert_eq!(gpu.far_blend_start, 10.0);
+        assert_eq!(gpu.far_blend_end, 40.0);
+    }
+
+    #[test]
+    fn test_hue_variation() {
+        let grass = PaletteMaterial::new("grass").with_hue_variation(0.5);
+        let gpu: MaterialPropertiesGpu = (&grass).into();
+
+        assert_eq!(gpu.hue_variation, 0.5);
+        assert_eq!(MaterialPropertiesGpu::default().hue_variation, 0.0);
+    }
+
+    #[test]
+    fn test_extended_properties() {
+        let plain = PaletteMaterial::new("dirt");
+        assert!(!plain.uses_extended_properties());
+
+        let ice = PaletteMaterial::new("ice")
+            .with_specular(0.8)
+            .with_clearcoat(1.0, 0.1);
+        assert!(ice.uses_extended_properties());
+
+        let gpu: MaterialPropertiesExtendedGpu = (&ice).into();
+        assert_eq!(gpu.specular, 0.8);
+        assert_eq!(gpu.clearcoat, 1.0);
+        assert_eq!(gpu.clearcoat_roughness, 0.1);
+        assert_eq!(size_of::<MaterialPropertiesExtendedGpu>() % 16, 0);
     }
 
     #[test]
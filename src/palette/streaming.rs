@@ -0,0 +1,387 @@
+//! Palette texture streaming driven by visible material usage.
+//!
+//! Large palettes spend most of their memory and upload time on full
+//! resolution mips nobody is close enough to see. With
+//! [`PaletteStreaming::enabled`], palette arrays are rebuilt with only their
+//! small mips, from [`PaletteStreaming::resident_mip`] down, and the full
+//! mip chain is restored once a layer shows up in [`VisibleMaterialUsage`].
+//!
+//! Usage comes from [`MaterialUsage`] components on chunk entities, see
+//! [`TriplanarMeshBuilder::material_usage`](crate::mesh::TriplanarMeshBuilder::material_usage).
+//!
+//! Limitations:
+//! - Every layer of a texture array has the same size, so an array is
+//!   restored as a whole once a visible chunk uses any of its layers.
+//! - Arrays are never downgraded once restored, so chunks moving in and out
+//!   of view don't recreate textures.
+//! - Until restored, surfaces sample the small mips and look blurry up
+//!   close.
+//! - Only images that become loaded while streaming is enabled are streamed.
+
+use std::ops::Range;
+
+use bevy::camera::visibility::VisibilitySystems;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDataOrder, TextureDimension, TextureFormat};
+
+use super::asset::TexturePalette;
+use super::layers::PaletteTexture;
+use crate::material::TriplanarVoxelMaterial;
+use crate::mesh::MaterialUsage;
+
+/// Settings for usage-driven palette streaming.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct PaletteStreaming {
+    /// Enable streaming. Off by default.
+    pub enabled: bool,
+    /// First mip kept for arrays no visible chunk uses yet. With a 1024px
+    /// array, mip 4 is 64px per layer.
+    pub resident_mip: u32,
+}
+
+impl Default for PaletteStreaming {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            resident_mip: 4,
+        }
+    }
+}
+
+/// Union of [`MaterialUsage`] over all visible entities.
+///
+/// Updated every frame after visibility checks; only marked changed when
+/// the set of materials actually changes.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VisibleMaterialUsage(pub MaterialUsage);
+
+/// Palette arrays seen by the streamer, with the full image of those still
+/// holding only their small mips. `None` once restored.
+#[derive(Resource, Default)]
+struct StreamedPaletteImages(HashMap<AssetId<Image>, Option<Image>>);
+
+pub(crate) fn build_palette_streaming(app: &mut App) {
+    app.init_resource::<PaletteStreaming>()
+        .init_resource::<VisibleMaterialUsage>()
+        .init_resource::<StreamedPaletteImages>()
+        .register_type::<PaletteStreaming>()
+        .register_type::<MaterialUsage>()
+        .add_systems(
+            PostUpdate,
+            (update_visible_material_usage, stream_palette_textures)
+                .chain()
+                .after(VisibilitySystems::CheckVisibility),
+        );
+}
+
+fn update_visible_material_usage(
+    streaming: Res<PaletteStreaming>,
+    chunks: Query<(&MaterialUsage, &ViewVisibility)>,
+    mut visible: ResMut<VisibleMaterialUsage>,
+) {
+    if !streaming.enabled {
+        return;
+    }
+
+    let mut usage = MaterialUsage::default();
+    for (chunk_usage, visibility) in &chunks {
+        if visibility.get() {
+            usage.union_with(chunk_usage);
+        }
+    }
+    visible.set_if_neq(VisibleMaterialUsage(usage));
+}
+
+fn stream_palette_textures(
+    streaming: Res<PaletteStreaming>,
+    visible: Res<VisibleMaterialUsage>,
+    palettes: Res<Assets<TexturePalette>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<TriplanarVoxelMaterial>>,
+    mut streamed: ResMut<StreamedPaletteImages>,
+) {
+    if !streaming.enabled {
+        return;
+    }
+
+    // Drop arrays whose image asset is gone
+    streamed.0.retain(|id, _| images.contains(*id));
+
+    let mut rebuilt = Vec::new();
+    for (_, palette) in palettes.iter() {
+        for target in [
+            PaletteTexture::Albedo,
+            PaletteTexture::Normal,
+            PaletteTexture::Arm,
+            PaletteTexture::Height,
+        ] {
+            let Some(id) = palette.texture(target).map(Handle::id) else {
+                continue;
+            };
+            if streamed.0.contains_key(&id) {
+                continue;
+            }
+            let Some(reduced) = images
+                .get(id)
+                .and_then(|image| reduced_image(image, streaming.resident_mip))
+            else {
+                continue;
+            };
+            let Some(image) = images.get_mut(id) else {
+                continue;
+            };
+            streamed
+                .0
+                .insert(id, Some(std::mem::replace(image, reduced)));
+            rebuilt.push(id);
+        }
+    }
+
+    for (&id, full) in streamed.0.iter_mut() {
+        let Some(layers) = full
+            .as_ref()
+            .map(|full| full.texture_descriptor.size.depth_or_array_layers)
+        else {
+            continue;
+        };
+        if !visible.0.iter().any(|material| (material as u32) < layers) {
+            continue;
+        }
+        if let Some(image) = images.get_mut(id)
+            && let Some(full) = full.take()
+        {
+            *image = full;
+            rebuilt.push(id);
+        }
+    }
+
+    if !rebuilt.is_empty() {
+        refresh_materials_using(&rebuilt, &mut materials);
+    }
+}
+
+/// Marks materials sampling any of `images` as changed, so their bind
+/// groups pick up the recreated textures.
+fn refresh_materials_using(
+    images: &[AssetId<Image>],
+    materials: &mut Assets<TriplanarVoxelMaterial>,
+) {
+    let users: Vec<_> = materials
+        .iter()
+        .filter(|(_, material)| {
+            let extension = &material.extension;
+            [
+                Some(&extension.albedo),
+                extension.normal.as_ref(),
+                extension.arm.as_ref(),
+                extension.height.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|texture| images.contains(&texture.id()))
+        })
+        .map(|(id, _)| id)
+        .collect();
+    for id in users {
+        materials.get_mut(id);
+    }
+}
+
+/// Copy of a palette array holding only mips `resident_mip` and below, or
+/// `None` when there is nothing to drop.
+///
+/// Compressed arrays keep larger mips if the requested one isn't a whole
+/// number of blocks.
+fn reduced_image(image: &Image, resident_mip: u32) -> Option<Image> {
+    let data = image.data.as_ref()?;
+    let descriptor = &image.texture_descriptor;
+    let format = descriptor.format;
+    let (block_width, block_height) = format.block_dimensions();
+    let first_mip = (1..descriptor.mip_level_count.min(resident_mip + 1))
+        .rev()
+        .find(|&mip| {
+            let size = descriptor.size.mip_level_size(mip, TextureDimension::D2);
+            size.width % block_width == 0 && size.height % block_height == 0
+        })?;
+
+    let layers = descriptor.size.depth_or_array_layers;
+    let mips = first_mip..descriptor.mip_level_count;
+    let range = |layer, mip| {
+        layer_mip_range(
+            descriptor.size,
+            format,
+            descriptor.mip_level_count,
+            image.data_order,
+            layer,
+            mip,
+        )
+    };
+    let ranges: Vec<Range<usize>> = match image.data_order {
+        TextureDataOrder::LayerMajor => (0..layers)
+            .flat_map(|layer| mips.clone().map(move |mip| (layer, mip)))
+            .map(|(layer, mip)| range(layer, mip))
+            .collect(),
+        TextureDataOrder::MipMajor => mips
+            .clone()
+            .flat_map(|mip| (0..layers).map(move |layer| (layer, mip)))
+            .map(|(layer, mip)| range(layer, mip))
+            .collect(),
+    };
+    if ranges.iter().any(|range| range.end > data.len()) {
+        return None;
+    }
+
+    let mut reduced = Image::new_uninit(
+        descriptor
+            .size
+            .mip_level_size(first_mip, TextureDimension::D2),
+        descriptor.dimension,
+        format,
+        image.asset_usage,
+    );
+    reduced.texture_descriptor.mip_level_count = mips.len() as u32;
+    reduced.texture_descriptor.usage = descriptor.usage;
+    reduced.texture_descriptor.view_formats = descriptor.view_formats;
+    reduced.texture_view_descriptor = image.texture_view_descriptor.clone();
+    reduced.sampler = image.sampler.clone();
+    reduced.data_order = image.data_order;
+    reduced.data = Some(
+        ranges
+            .into_iter()
+            .flat_map(|range| &data[range])
+            .copied()
+            .collect(),
+    );
+    Some(reduced)
+}
+
+/// Byte size of one layer of one mip.
+fn mip_bytes(size: Extent3d, format: TextureFormat, mip: u32) -> usize {
+    let mip_size = size
+        .mip_level_size(mip, TextureDimension::D2)
+        .physical_size(format);
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(0);
+    ((mip_size.width / block_width) * (mip_size.height / block_height) * block_size) as usize
+}
+
+/// Byte range of `layer` at `mip` in an array's packed data.
+pub(crate) fn layer_mip_range(
+    size: Extent3d,
+    format: TextureFormat,
+    mip_level_count: u32,
+    data_order: TextureDataOrder,
+    layer: u32,
+    mip: u32,
+) -> Range<usize> {
+    let layers = size.depth_or_array_layers as usize;
+    let layer_bytes = |mip| mip_bytes(size, format, mip);
+
+    let start = match data_order {
+        TextureDataOrder::LayerMajor => {
+            let chain: usize = (0..mip_level_count).map(layer_bytes).sum();
+            layer as usize * chain + (0..mip).map(layer_bytes).sum::<usize>()
+        }
+        TextureDataOrder::MipMajor => {
+            (0..mip).map(|m| layer_bytes(m) * layers).sum::<usize>()
+                + layer as usize * layer_bytes(mip)
+        }
+    };
+    start..start + layer_bytes(mip)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+
+    use super::*;
+
+    const SIZE: Extent3d = Extent3d {
+        width: 4,
+        height: 4,
+        depth_or_array_layers: 3,
+    };
+
+    #[test]
+    fn test_layer_major_ranges() {
+        let format = TextureFormat::Rgba8Unorm;
+        // Chain per layer: 64 + 16 + 4 bytes
+        let order = TextureDataOrder::LayerMajor;
+
+        assert_eq!(layer_mip_range(SIZE, format, 3, order, 0, 0), 0..64);
+        assert_eq!(layer_mip_range(SIZE, format, 3, order, 0, 2), 80..84);
+        assert_eq!(layer_mip_range(SIZE, format, 3, order, 1, 1), 148..164);
+    }
+
+    #[test]
+    fn test_mip_major_ranges() {
+        let format = TextureFormat::Rgba8Unorm;
+        let order = TextureDataOrder::MipMajor;
+
+        assert_eq!(layer_mip_range(SIZE, format, 3, order, 2, 0), 128..192);
+        assert_eq!(layer_mip_range(SIZE, format, 3, order, 1, 1), 208..224);
+        assert_eq!(layer_mip_range(SIZE, format, 3, order, 0, 2), 240..244);
+    }
+
+    #[test]
+    fn test_compressed_mip_bytes() {
+        // 4x4 blocks of 16 bytes; mips below one block round up to a block
+        let format = TextureFormat::Bc7RgbaUnorm;
+
+        assert_eq!(mip_bytes(SIZE, format, 0), 16);
+        assert_eq!(mip_bytes(SIZE, format, 2), 16);
+    }
+
+    fn array(size: Extent3d, format: TextureFormat, mips: u32, bytes: usize) -> Image {
+        let mut image = Image::new_uninit(
+            size,
+            TextureDimension::D2,
+            format,
+            RenderAssetUsages::default(),
+        );
+        image.texture_descriptor.mip_level_count = mips;
+        image.data = Some((0..bytes).map(|i| i as u8).collect());
+        image
+    }
+
+    #[test]
+    fn test_reduced_image_keeps_small_mips() {
+        let image = array(SIZE, TextureFormat::Rgba8Unorm, 3, 252);
+        let reduced = reduced_image(&image, 1).unwrap();
+
+        assert_eq!(reduced.width(), 2);
+        assert_eq!(reduced.texture_descriptor.size.depth_or_array_layers, 3);
+        assert_eq!(reduced.texture_descriptor.mip_level_count, 2);
+        let data = image.data.as_ref().unwrap();
+        let expected: Vec<u8> = [64..84, 148..168, 232..252]
+            .into_iter()
+            .flat_map(|range| data[range].iter().copied())
+            .collect();
+        assert_eq!(reduced.data.unwrap(), expected);
+
+        assert!(reduced_image(&image, 0).is_none());
+    }
+
+    #[test]
+    fn test_reduced_compressed_image_stays_block_aligned() {
+        let size = Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        };
+        // Mips of 8, 4, 2 and 1 pixels; only the first two are whole blocks
+        let image = array(size, TextureFormat::Bc7RgbaUnorm, 4, 112);
+        let reduced = reduced_image(&image, 3).unwrap();
+
+        assert_eq!(reduced.width(), 4);
+        assert_eq!(reduced.texture_descriptor.mip_level_count, 3);
+        assert_eq!(reduced.data.unwrap().len(), 48);
+    }
+
+    #[test]
+    fn test_default_disabled() {
+        assert!(!PaletteStreaming::default().enabled);
+    }
+}
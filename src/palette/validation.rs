@@ -24,6 +24,9 @@ pub enum PaletteValidationError {
     #[error("ARM texture has invalid format: expected linear format, got {found:?}")]
     InvalidArmFormat { found: TextureFormat },
 
+    #[error("Height texture has invalid format: expected linear format, got {found:?}")]
+    InvalidHeightFormat { found: TextureFormat },
+
     #[error("Texture '{name}' is not a 2D array: dimension is {found:?}")]
     NotTextureArray {
         name: &'static str,
@@ -108,6 +111,14 @@ pub fn is_valid_linear_format(format: TextureFormat) -> bool {
     ) || matches!(format, TextureFormat::Astc { channel, .. } if channel == bevy::render::render_resource::AstcChannel::Unorm)
 }
 
+/// Check if a texture format is valid for height textures.
+///
+/// Only the R channel is read, so single-channel formats are allowed on top
+/// of [`is_valid_linear_format`].
+pub fn is_valid_height_format(format: TextureFormat) -> bool {
+    format == TextureFormat::R8Unorm || is_valid_linear_format(format)
+}
+
 /// Validate an albedo texture.
 pub fn validate_albedo(image: &Image) -> Result<(), PaletteValidationError> {
     // Check dimension
@@ -147,7 +158,7 @@ pub fn validate_albedo(image: &Image) -> Result<(), PaletteValidationError> {
     Ok(())
 }
 
-/// Validate a linear texture (normal or ARM) against the albedo texture.
+/// Validate a linear texture (normal, ARM or height) against the albedo texture.
 pub fn validate_linear_texture(
     image: &Image,
     albedo: &Image,
@@ -162,16 +173,18 @@ pub fn validate_linear_texture(
     }
 
     // Check format
-    if !is_valid_linear_format(image.texture_descriptor.format) {
-        if name == "normal" {
-            return Err(PaletteValidationError::InvalidNormalFormat {
-                found: image.texture_descriptor.format,
-            });
-        } else {
-            return Err(PaletteValidationError::InvalidArmFormat {
-                found: image.texture_descriptor.format,
-            });
-        }
+    let valid_format = if name == "height" {
+        is_valid_height_format(image.texture_descriptor.format)
+    } else {
+        is_valid_linear_format(image.texture_descriptor.format)
+    };
+    if !valid_format {
+        let found = image.texture_descriptor.format;
+        return Err(match name {
+            "normal" => PaletteValidationError::InvalidNormalFormat { found },
+            "height" => PaletteValidationError::InvalidHeightFormat { found },
+            _ => PaletteValidationError::InvalidArmFormat { found },
+        });
     }
 
     // Check layer count matches albedo
@@ -240,4 +253,11 @@ mod tests {
         assert!(is_valid_linear_format(TextureFormat::Bc5RgUnorm));
         assert!(!is_valid_linear_format(TextureFormat::Rgba8UnormSrgb)); // sRGB, not linear
     }
+
+    #[test]
+    fn test_height_formats() {
+        assert!(is_valid_height_format(TextureFormat::R8Unorm));
+        assert!(is_valid_height_format(TextureFormat::Rgba8Unorm));
+        assert!(!is_valid_linear_format(TextureFormat::R8Unorm)); // Single channel, height only
+    }
 }
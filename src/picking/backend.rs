@@ -0,0 +1,171 @@
+//! `bevy_picking` backend for voxel chunks.
+//!
+//! [`ChunkPickingPlugin`] casts every pointer ray through the voxel grid
+//! with [`MaterialFieldQuery::raycast`] and reports the chunk it hits, so
+//! `Pointer<Over>`, `Pointer<Click>`, `Pointer<Drag>` and the other picking
+//! events fire on chunk entities with the hit position and normal. Tools
+//! observe those events instead of querying windows and cameras.
+//! `HitData` has no room for the material, so the full [`VoxelRayHit`] of
+//! each pointer is kept in [`ChunkPointerHits`].
+//!
+//! Bevy's `MeshPickingPlugin` would report the same chunks against their
+//! meshes; use one or the other for chunks.
+
+use bevy::picking::PickingSystems;
+use bevy::picking::backend::ray::RayMap;
+use bevy::picking::backend::{HitData, PointerHits};
+use bevy::picking::pointer::PointerId;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::material_field::{MaterialFieldQuery, VoxelRayHit};
+
+/// Settings of the chunk picking backend.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct ChunkPickingSettings {
+    /// Only pick through cameras marked [`ChunkPickingCamera`].
+    /// Default: false
+    pub require_markers: bool,
+    /// World distance along each ray to search for solid voxels.
+    /// Default: 500.0
+    pub max_distance: f32,
+}
+
+impl Default for ChunkPickingSettings {
+    fn default() -> Self {
+        Self {
+            require_markers: false,
+            max_distance: 500.0,
+        }
+    }
+}
+
+/// Marks cameras the chunk picking backend casts rays from, when
+/// [`ChunkPickingSettings::require_markers`] is set.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ChunkPickingCamera;
+
+/// The voxel hit under each pointer this frame.
+///
+/// # Example
+/// ```ignore
+/// app.add_observer(
+///     |drag: On<Pointer<Drag>>, hits: Res<ChunkPointerHits>, brush: Res<Brush>, mut paint: MessageWriter<PaintCommand>| {
+///         if let Some(hit) = hits.get(drag.pointer_id) {
+///             paint.write(PaintCommand::new(
+///                 BrushShape::Sphere { center: hit.position, radius: brush.radius },
+///                 brush.material_id,
+///             ));
+///         }
+///     },
+/// );
+/// ```
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ChunkPointerHits {
+    /// Hit and order of the camera it was cast from.
+    hits: HashMap<PointerId, (VoxelRayHit, isize)>,
+}
+
+impl ChunkPointerHits {
+    /// The hit under `pointer`, through the highest-order camera when
+    /// several cameras see it.
+    pub fn get(&self, pointer: PointerId) -> Option<&VoxelRayHit> {
+        self.hits.get(&pointer).map(|(hit, _)| hit)
+    }
+
+    /// Material under `pointer`.
+    pub fn material(&self, pointer: PointerId) -> Option<u8> {
+        self.get(pointer).map(|hit| hit.material_id)
+    }
+
+    /// Record `hit` unless a higher-order camera already hit for `pointer`.
+    fn insert(&mut self, pointer: PointerId, hit: VoxelRayHit, order: isize) {
+        match self.hits.get(&pointer) {
+            Some(&(_, existing)) if existing > order => {}
+            _ => {
+                self.hits.insert(pointer, (hit, order));
+            }
+        }
+    }
+}
+
+/// Plugin adding the chunk picking backend.
+///
+/// Needs `bevy_picking` (part of `DefaultPlugins`) and the
+/// [`TriplanarVoxelPlugin`](crate::TriplanarVoxelPlugin).
+pub struct ChunkPickingPlugin;
+
+impl Plugin for ChunkPickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkPickingSettings>()
+            .init_resource::<ChunkPointerHits>()
+            .register_type::<ChunkPickingSettings>()
+            .register_type::<ChunkPickingCamera>()
+            .add_systems(
+                PreUpdate,
+                update_chunk_pointer_hits.in_set(PickingSystems::Backend),
+            );
+    }
+}
+
+/// System raycasting pointer rays against chunks and reporting the hits.
+pub fn update_chunk_pointer_hits(
+    settings: Res<ChunkPickingSettings>,
+    ray_map: Res<RayMap>,
+    cameras: Query<(&Camera, Has<ChunkPickingCamera>)>,
+    fields: MaterialFieldQuery,
+    mut hits: ResMut<ChunkPointerHits>,
+    mut output: MessageWriter<PointerHits>,
+) {
+    hits.hits.clear();
+    for (&ray_id, &ray) in ray_map.iter() {
+        let Ok((camera, marked)) = cameras.get(ray_id.camera) else {
+            continue;
+        };
+        if settings.require_markers && !marked {
+            continue;
+        }
+        let Some(hit) = fields.raycast(ray, settings.max_distance) else {
+            continue;
+        };
+
+        let data = HitData::new(
+            ray_id.camera,
+            hit.distance,
+            Some(hit.position),
+            Some(hit.normal),
+        );
+        output.write(PointerHits::new(
+            ray_id.pointer,
+            vec![(hit.chunk, data)],
+            camera.order as f32,
+        ));
+        hits.insert(ray_id.pointer, hit, camera.order);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highest_order_camera_wins() {
+        let hit = |material_id| VoxelRayHit {
+            chunk: Entity::PLACEHOLDER,
+            voxel: UVec3::ZERO,
+            material_id,
+            position: Vec3::ZERO,
+            normal: Vec3::Y,
+            distance: 1.0,
+        };
+        let mut hits = ChunkPointerHits::default();
+        hits.insert(PointerId::Mouse, hit(1), 1);
+        hits.insert(PointerId::Mouse, hit(2), 0);
+        assert_eq!(hits.material(PointerId::Mouse), Some(1));
+        hits.insert(PointerId::Mouse, hit(3), 2);
+        assert_eq!(hits.material(PointerId::Mouse), Some(3));
+        assert_eq!(hits.get(PointerId::Touch(0)), None);
+    }
+}
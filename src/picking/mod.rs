@@ -0,0 +1,509 @@
+//! Picking chunks and materials under the cursor.
+//!
+//! With the `picking` feature, [`ChunkPickingPlugin`] is a `bevy_picking`
+//! backend raycasting the voxel grid, so pointer events fire on chunks.
+//!
+//! CPU raycasts against material fields resolve whole voxels, not the
+//! material the blend actually put on a pixel. For exact per-pixel picks,
+//! [`MaterialPickingPlugin`] renders the pixel under the cursor a second
+//! time into a 1×1 target, writing the dominant material ID and a chunk
+//! slot instead of a color, and reads it back asynchronously:
+//! - every entity with a [`TriplanarVoxelMaterial`] or
+//!   [`TriplanarUnlitMaterial`] gets a [`MaterialPickingProxy`] child
+//!   sharing its mesh, on a render layer only the picking camera sees
+//! - the picking camera follows the [`MaterialPickingSource`] camera whose
+//!   viewport holds the cursor, cropped to that pixel with a sub-camera view
+//! - [`MaterialPicker::material_under_cursor`] returns the latest readback,
+//!   usually two or three frames behind the cursor
+//!
+//! Only triplanar chunks are drawn, so other meshes don't occlude the pick.
+//! The picked surface is undisplaced and ignores
+//! [`MaterialReveal`](crate::material::MaterialReveal). The picking camera
+//! is a full camera view, shadow cascades included, so turn
+//! [`MaterialPicker::enabled`] off while no tool needs it.
+
+use bevy::asset::embedded_asset;
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::{NormalizedRenderTarget, RenderTarget, SubCameraView};
+use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
+use bevy::mesh::{MeshTag, MeshVertexBufferLayoutRef};
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, SpecializedMeshPipelineError, TextureFormat,
+};
+use bevy::shader::ShaderRef;
+use bevy::window::PrimaryWindow;
+
+#[cfg(feature = "material_field")]
+mod backend;
+
+#[cfg(feature = "material_field")]
+pub use backend::{
+    ChunkPickingCamera, ChunkPickingPlugin, ChunkPickingSettings, ChunkPointerHits,
+    update_chunk_pointer_hits,
+};
+
+use crate::material::{InstanceMaterialOverride, TriplanarUnlitMaterial, TriplanarVoxelMaterial};
+use crate::mesh::{ATTRIBUTE_MATERIAL_IDS, ATTRIBUTE_MATERIAL_WEIGHTS};
+
+/// Picking shader asset path (embedded).
+const MATERIAL_PICKING_SHADER_PATH: &str =
+    "embedded://bevy_painter/picking/shaders/material_picking.wgsl";
+
+/// Number of chunks that can be picked at once; slots are 16 bits.
+pub const MAX_PICKING_SLOTS: usize = 1 << 16;
+
+/// Marks a camera whose view [`MaterialPicker`] picks from.
+///
+/// Several cameras can carry it, e.g. both halves of a split screen; picks
+/// come from the active one whose viewport holds the cursor. Targets must
+/// be windows.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct MaterialPickingSource;
+
+/// Child drawing its parent chunk into the picking target.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct MaterialPickingProxy {
+    /// The chunk this proxy draws.
+    pub chunk: Entity,
+}
+
+/// A material picked under the cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaterialPick {
+    /// Chunk entity the pixel belongs to.
+    pub chunk: Entity,
+    /// Dominant material ID of the pixel, after
+    /// [`InstanceMaterialOverride`] remapping.
+    pub material_id: u8,
+}
+
+/// Picking state and the latest pick.
+///
+/// # Example
+/// ```ignore
+/// fn eyedropper(picker: Res<MaterialPicker>, mouse: Res<ButtonInput<MouseButton>>, mut brush: ResMut<Brush>) {
+///     if mouse.just_pressed(MouseButton::Right)
+///         && let Some(pick) = picker.material_under_cursor()
+///     {
+///         brush.material_id = pick.material_id;
+///     }
+/// }
+/// ```
+#[derive(Resource, Debug)]
+pub struct MaterialPicker {
+    /// Render the picking pass. While off, no pick is reported.
+    /// Default: true
+    pub enabled: bool,
+    latest: Option<MaterialPick>,
+    render_layer: usize,
+    camera: Option<Entity>,
+    material: Handle<MaterialPickingMaterial>,
+    /// Chunk of each slot, `None` for free slots.
+    slots: Vec<Option<Entity>>,
+    free: Vec<u16>,
+    /// Slot and proxy of each chunk.
+    chunks: HashMap<Entity, (u16, Entity)>,
+}
+
+impl MaterialPicker {
+    fn new(render_layer: usize) -> Self {
+        Self {
+            enabled: true,
+            latest: None,
+            render_layer,
+            camera: None,
+            material: Handle::default(),
+            slots: Vec::new(),
+            free: Vec::new(),
+            chunks: HashMap::default(),
+        }
+    }
+
+    /// The material under the cursor, from the latest completed readback.
+    ///
+    /// `None` while disabled, with the cursor outside the source camera's
+    /// viewport, or over a pixel without triplanar chunks.
+    pub fn material_under_cursor(&self) -> Option<MaterialPick> {
+        self.latest
+    }
+
+    /// The picking camera, once spawned.
+    pub fn camera(&self) -> Option<Entity> {
+        self.camera
+    }
+
+    /// Number of chunks with a picking slot.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Reserve a slot for `chunk`, or `None` if all slots are taken.
+    fn allocate(&mut self, chunk: Entity) -> Option<u16> {
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None if self.slots.len() < MAX_PICKING_SLOTS => {
+                self.slots.push(None);
+                (self.slots.len() - 1) as u16
+            }
+            None => return None,
+        };
+        self.slots[slot as usize] = Some(chunk);
+        Some(slot)
+    }
+
+    /// Free the slot of `chunk`, returning its proxy.
+    fn release(&mut self, chunk: Entity) -> Option<Entity> {
+        let (slot, proxy) = self.chunks.remove(&chunk)?;
+        self.slots[slot as usize] = None;
+        self.free.push(slot);
+        Some(proxy)
+    }
+
+    /// Decode a picking pixel: material ID in red, slot in green and blue,
+    /// alpha set wherever a chunk was drawn.
+    fn decode(&self, pixel: [u8; 4]) -> Option<(Entity, u8)> {
+        let [material_id, low, high, coverage] = pixel;
+        if coverage == 0 {
+            return None;
+        }
+        let slot = u16::from_le_bytes([low, high]);
+        let chunk = (*self.slots.get(slot as usize)?)?;
+        Some((chunk, material_id))
+    }
+}
+
+/// Writes the dominant material and picking slot of each pixel.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug, Default)]
+pub struct MaterialPickingMaterial {}
+
+impl Material for MaterialPickingMaterial {
+    fn vertex_shader() -> ShaderRef {
+        MATERIAL_PICKING_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        MATERIAL_PICKING_SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_MATERIAL_IDS.at_shader_location(2),
+            ATTRIBUTE_MATERIAL_WEIGHTS.at_shader_location(3),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        Ok(())
+    }
+}
+
+/// Plugin adding [`MaterialPicker`] GPU picking.
+///
+/// Needs the render plugins; mark the main camera with
+/// [`MaterialPickingSource`].
+#[derive(Clone, Debug)]
+pub struct MaterialPickingPlugin {
+    /// Render layer of the picking proxies, which no other camera should
+    /// render.
+    /// Default: 31
+    pub render_layer: usize,
+}
+
+impl Default for MaterialPickingPlugin {
+    fn default() -> Self {
+        Self { render_layer: 31 }
+    }
+}
+
+impl Plugin for MaterialPickingPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "shaders/material_picking.wgsl");
+
+        app.add_plugins(MaterialPlugin::<MaterialPickingMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        })
+        .insert_resource(MaterialPicker::new(self.render_layer))
+        .register_type::<MaterialPickingSource>()
+        .add_systems(Startup, setup_material_picker)
+        .add_systems(
+            PostUpdate,
+            (
+                sync_picking_proxies,
+                update_picking_camera.after(TransformSystems::Propagate),
+            ),
+        );
+    }
+}
+
+/// System spawning the picking camera and its target.
+pub fn setup_material_picker(
+    mut commands: Commands,
+    mut picker: ResMut<MaterialPicker>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<MaterialPickingMaterial>>,
+) {
+    // The view texture is sRGB, so the shader pre-linearizes each byte and
+    // an sRGB target stores it unchanged
+    let target = images.add(Image::new_target_texture(
+        1,
+        1,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    picker.material = materials.add(MaterialPickingMaterial::default());
+    let camera = commands
+        .spawn((
+            Name::new("Material picking camera"),
+            Camera3d::default(),
+            Camera {
+                is_active: false,
+                order: -1,
+                target: RenderTarget::Image(target.clone().into()),
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+                ..default()
+            },
+            Msaa::Off,
+            Tonemapping::None,
+            DebandDither::Disabled,
+            RenderLayers::layer(picker.render_layer),
+            Readback::texture(target),
+        ))
+        .observe(read_material_pick)
+        .id();
+    picker.camera = Some(camera);
+}
+
+/// System spawning, updating and despawning [`MaterialPickingProxy`]
+/// children of triplanar chunks.
+#[allow(clippy::type_complexity)]
+pub fn sync_picking_proxies(
+    mut commands: Commands,
+    mut picker: ResMut<MaterialPicker>,
+    chunks: Query<
+        (Entity, Ref<Mesh3d>),
+        (
+            Or<(
+                With<MeshMaterial3d<TriplanarVoxelMaterial>>,
+                With<MeshMaterial3d<TriplanarUnlitMaterial>>,
+            )>,
+            Without<MaterialPickingProxy>,
+        ),
+    >,
+    mut proxies: Query<&mut Mesh3d, With<MaterialPickingProxy>>,
+    mut removed_meshes: RemovedComponents<Mesh3d>,
+    mut removed_lit: RemovedComponents<MeshMaterial3d<TriplanarVoxelMaterial>>,
+    mut removed_unlit: RemovedComponents<MeshMaterial3d<TriplanarUnlitMaterial>>,
+) {
+    let removed: Vec<Entity> = removed_meshes
+        .read()
+        .chain(removed_lit.read())
+        .chain(removed_unlit.read())
+        .collect();
+    for chunk in removed {
+        if !chunks.contains(chunk)
+            && let Some(proxy) = picker.release(chunk)
+        {
+            // Gone already if the chunk was despawned
+            commands.entity(proxy).try_despawn();
+        }
+    }
+
+    for (chunk, mesh) in &chunks {
+        if let Some(&(_, proxy)) = picker.chunks.get(&chunk) {
+            if mesh.is_changed()
+                && let Ok(mut proxy_mesh) = proxies.get_mut(proxy)
+            {
+                proxy_mesh.0 = mesh.0.clone();
+            }
+            continue;
+        }
+        let Some(slot) = picker.allocate(chunk) else {
+            warn_once!(
+                "More than {MAX_PICKING_SLOTS} pickable chunks; extra chunks can't be picked"
+            );
+            break;
+        };
+        let proxy = commands
+            .spawn((
+                Mesh3d(mesh.0.clone()),
+                MeshMaterial3d(picker.material.clone()),
+                MeshTag(slot as u32),
+                RenderLayers::layer(picker.render_layer),
+                MaterialPickingProxy { chunk },
+                ChildOf(chunk),
+            ))
+            .id();
+        picker.chunks.insert(chunk, (slot, proxy));
+    }
+}
+
+/// Physical cursor pixel within `camera`'s viewport, and the viewport size.
+fn cursor_pixel(
+    camera: &Camera,
+    windows: &Query<&Window>,
+    primary: &Query<Entity, With<PrimaryWindow>>,
+) -> Option<(Vec2, UVec2)> {
+    let NormalizedRenderTarget::Window(window) = camera.target.normalize(primary.single().ok())?
+    else {
+        return None;
+    };
+    let cursor = windows
+        .get(window.entity())
+        .ok()?
+        .physical_cursor_position()?;
+    let viewport = camera.physical_viewport_rect()?;
+    let position = (cursor - viewport.min.as_vec2()).floor();
+    let size = viewport.size();
+    (position.cmpge(Vec2::ZERO).all() && position.cmplt(size.as_vec2()).all())
+        .then_some((position, size))
+}
+
+/// System pointing the picking camera at the cursor pixel of the
+/// [`MaterialPickingSource`] camera the cursor is over.
+#[allow(clippy::type_complexity)]
+pub fn update_picking_camera(
+    mut picker: ResMut<MaterialPicker>,
+    sources: Query<(&Camera, &GlobalTransform, &Projection), With<MaterialPickingSource>>,
+    mut cameras: Query<
+        (
+            &mut Camera,
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut Projection,
+        ),
+        Without<MaterialPickingSource>,
+    >,
+    windows: Query<&Window>,
+    primary: Query<Entity, With<PrimaryWindow>>,
+) {
+    let Some(camera) = picker.camera else {
+        return;
+    };
+    let Ok((mut camera, mut transform, mut global_transform, mut projection)) =
+        cameras.get_mut(camera)
+    else {
+        return;
+    };
+
+    let source = sources
+        .iter()
+        .filter(|(camera, ..)| picker.enabled && camera.is_active)
+        .find_map(|source| Some((source, cursor_pixel(source.0, &windows, &primary)?)));
+    let Some(((source, source_transform, source_projection), (position, full_size))) = source
+    else {
+        if camera.is_active {
+            camera.is_active = false;
+        }
+        if picker.latest.is_some() {
+            picker.latest = None;
+        }
+        return;
+    };
+
+    camera.is_active = true;
+    // The projection keeps the source aspect ratio from `full_size`
+    camera.sub_camera_view = Some(SubCameraView {
+        full_size,
+        offset: position,
+        size: UVec2::ONE,
+    });
+    *transform = source_transform.compute_transform();
+    *global_transform = *source_transform;
+    *projection = source_projection.clone();
+}
+
+/// Observer turning picking readbacks into [`MaterialPick`]s.
+pub fn read_material_pick(
+    readback: On<ReadbackComplete>,
+    mut picker: ResMut<MaterialPicker>,
+    overrides: Query<&InstanceMaterialOverride>,
+    cameras: Query<&Camera>,
+) {
+    // Late readbacks of a camera switched off since would show a stale pick
+    let active = picker
+        .camera
+        .and_then(|camera| cameras.get(camera).ok())
+        .is_some_and(|camera| camera.is_active);
+    let Some(pixel) = readback.data.first_chunk::<4>() else {
+        return;
+    };
+    picker.latest = active
+        .then(|| picker.decode(*pixel))
+        .flatten()
+        .map(|(chunk, material_id)| MaterialPick {
+            chunk,
+            material_id: overrides
+                .get(chunk)
+                .map_or(material_id, |remap| remap.apply(material_id)),
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_reused_and_decoded() {
+        let mut picker = MaterialPicker::new(31);
+        let mut world = World::new();
+        let [a, b, proxy] = std::array::from_fn(|_| world.spawn_empty().id());
+
+        let slot_a = picker.allocate(a).unwrap();
+        picker.chunks.insert(a, (slot_a, proxy));
+        assert_eq!(picker.decode([7, slot_a as u8, 0, 255]), Some((a, 7)));
+        assert_eq!(picker.decode([7, slot_a as u8, 0, 0]), None);
+
+        assert_eq!(picker.release(a), Some(proxy));
+        assert_eq!(picker.decode([7, slot_a as u8, 0, 255]), None);
+        assert_eq!(picker.allocate(b), Some(slot_a));
+        assert_eq!(picker.decode([3, slot_a as u8, 0, 255]), Some((b, 3)));
+    }
+
+    #[test]
+    fn test_proxies_follow_chunks() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(MaterialPicker::new(5))
+            .add_systems(Update, sync_picking_proxies);
+
+        let chunk = app
+            .world_mut()
+            .spawn((
+                Mesh3d::default(),
+                MeshMaterial3d::<TriplanarVoxelMaterial>::default(),
+            ))
+            .id();
+        app.update();
+
+        let world = app.world_mut();
+        let (proxy, tag, layers) = world
+            .query::<(&MaterialPickingProxy, &MeshTag, &RenderLayers)>()
+            .single(world)
+            .unwrap();
+        assert_eq!(proxy.chunk, chunk);
+        assert_eq!(tag.0, 0);
+        assert_eq!(*layers, RenderLayers::layer(5));
+        assert_eq!(world.resource::<MaterialPicker>().chunk_count(), 1);
+
+        world
+            .entity_mut(chunk)
+            .remove::<MeshMaterial3d<TriplanarVoxelMaterial>>();
+        app.update();
+        let world = app.world_mut();
+        assert_eq!(
+            world.query::<&MaterialPickingProxy>().iter(world).count(),
+            0
+        );
+        assert_eq!(world.resource::<MaterialPicker>().chunk_count(), 0);
+    }
+}
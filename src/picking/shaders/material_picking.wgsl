@@ -0,0 +1,73 @@
+// Material picking pass
+// Writes the dominant material ID of each pixel in red and the chunk's
+// picking slot (from its MeshTag) in green and blue, for readback by
+// MaterialPicker. Alpha marks pixels covered by a chunk.
+
+#import bevy_pbr::{
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+}
+
+// Subset of triplanar_common's Vertex; only IDs and weights are needed
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(2) material_ids: u32,
+    @location(3) material_weights: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) material_ids: u32,
+    @location(1) @interpolate(flat) material_weights: u32,
+    @location(2) @interpolate(flat) slot: u32,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    let world_position = mesh_functions::mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(vertex.position, 1.0)
+    );
+    out.position = position_world_to_clip(world_position.xyz);
+    out.material_ids = vertex.material_ids;
+    out.material_weights = vertex.material_weights;
+    out.slot = mesh_functions::get_tag(vertex.instance_index);
+
+    return out;
+}
+
+// The view target is sRGB, so write the linear value it encodes back to
+// exactly `byte`
+fn encode_byte(byte: u32) -> f32 {
+    let c = f32(byte) / 255.0;
+    if c <= 0.04045 {
+        return c / 12.92;
+    }
+    return pow((c + 0.055) / 1.055, 2.4);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var best = 0u;
+    var best_weight = 0u;
+    for (var i = 0u; i < 4u; i++) {
+        let weight = (in.material_weights >> (i * 8u)) & 0xFFu;
+        if weight > best_weight {
+            best_weight = weight;
+            best = i;
+        }
+    }
+    let material_id = (in.material_ids >> (best * 8u)) & 0xFFu;
+
+    // Alpha isn't sRGB encoded
+    return vec4<f32>(
+        encode_byte(material_id),
+        encode_byte(in.slot & 0xFFu),
+        encode_byte((in.slot >> 8u) & 0xFFu),
+        1.0,
+    );
+}
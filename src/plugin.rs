@@ -1,13 +1,63 @@
 //! Plugin for triplanar voxel materials.
 use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResourcePlugin;
 
-use crate::material::TriplanarVoxelMaterial;
+use crate::bake::{BakeSettings, BakeToStandardMaterial, bake_marked_chunks};
+use crate::material::{
+    DisableNormalMaps, ForceBiplanar, GlobalTriplanarOverrides, InstanceMaterialOverride,
+    MaterialReveal, RenderGrassShells, RenderUnlit, SimplifiedMaterials, TriplanarDebugMode,
+    TriplanarMaterialVariants, TriplanarQualitySettings, TriplanarQualityTier,
+    TriplanarShellMaterial, TriplanarShellVariants, TriplanarUnlitMaterial, TriplanarUnlitVariants,
+    TriplanarVoxelMaterial, apply_entity_feature_overrides, apply_global_triplanar_overrides,
+    apply_render_unlit, apply_triplanar_debug_mode, apply_triplanar_quality, sync_grass_shells,
+    sync_instance_material_overrides, sync_material_variants, sync_unlit_variants,
+};
+use crate::palette::{PaletteTexture, TexturePalette};
 
 /// Plugin that adds triplanar voxel material support to Bevy.
 ///
 /// This plugin registers:
-/// - [`TriplanarVoxelMaterial`] as a material type
-/// - Embedded shader assets
+/// - [`TriplanarVoxelMaterial`] and [`TriplanarUnlitMaterial`] as material types
+/// - [`TexturePalette`] as an asset, with GPU copies for runtime layer replacement
+/// - The [`PaletteStreaming`](crate::palette::PaletteStreaming) resource for usage-driven uploads
+/// - Embedded shader assets, with the main shader and user hooks optionally loaded from the asset folder
+/// - [`InstanceMaterialOverride`] syncing for instanced props
+/// - The [`GlobalTriplanarOverrides`] resource, applied to every material
+/// - The [`TriplanarDebugMode`] resource, toggling voxel grid and chunk boundary overlays
+/// - The [`TriplanarQualitySettings`] resource, which respecializes pipelines on change
+/// - [`DisableNormalMaps`] and [`ForceBiplanar`] per-entity feature opt-outs
+/// - [`MaterialReveal`] per-entity dissolve in and out
+/// - [`SimplifiedMaterials`] per-entity cheap far-terrain rendering
+/// - [`RenderUnlit`] per-entity switching to the unlit material
+/// - [`RenderGrassShells`] shell layers over grass-flagged palette materials
+/// - [`DoubleBufferedMesh`](crate::mesh::DoubleBufferedMesh) mesh swaps, waiting for the render world to prepare new meshes
+/// - [`BakeToStandardMaterial`] baking for single-texture fallback chunks
+/// - [`PaintCommand`](crate::material_field::PaintCommand) strokes, applied to chunk material fields and reported as [`MaterialChanged`](crate::material_field::MaterialChanged)
+/// - [`ParamPaintCommand`](crate::material_field::ParamPaintCommand) strokes, applied to chunk param fields
+/// - [`PaintStroke`](crate::material_field::PaintStroke) batching, marking a stroke's chunks dirty once with optional cheap previews, when the resource is present
+/// - [`PlaceTemplate`](crate::material_field::PlaceTemplate) prefab placement
+/// - [`FluidCoupling`](crate::material_field::FluidCoupling) rules, when the resource is present
+/// - [`MaterialGeneration`](crate::material_field::MaterialGeneration) background tasks, when the resource is present
+/// - The [`FieldPool`](crate::material_field::FieldPool) resource, refilled from despawned chunks
+/// - [`OnChunkMaterialUnloaded`](crate::material_field::OnChunkMaterialUnloaded) for despawned chunks
+/// - [`FieldCompression`](crate::material_field::FieldCompression) of distant chunks, when the resource is present
+/// - [`GlobalRepaintTask`](crate::material_field::GlobalRepaintTask) time-sliced repaints, when the resource is present
+/// - [`MaterialSimplification`](crate::material_field::MaterialSimplification) of far chunks, when the resource is present
+/// - [`NeighborMaterialSync`](crate::material_field::NeighborMaterialSync) neighbor data refreshes for dirty chunks
+/// - The [`MaterialChunkIndex`](crate::material_field::MaterialChunkIndex) for [`MaterialFieldQuery`](crate::material_field::MaterialFieldQuery) world-space reads
+/// - The [`MaterialBlendSettings`](crate::material_field::MaterialBlendSettings) resource, from `default_blend_settings`
+///
+/// With `auto_remesh` on, chunks marked
+/// [`MaterialFieldDirty`](crate::material_field::MaterialFieldDirty) get
+/// their material attributes recomputed by the
+/// [`attribute_backend`](Self::attribute_backend), triggering
+/// [`OnChunkMaterialReady`](crate::material_field::OnChunkMaterialReady).
+/// CPU remeshed chunks with a
+/// [`MaterialTransition`](crate::material_field::MaterialTransition) fade
+/// to their new materials. A
+/// [`RemeshScheduling`](crate::material_field::RemeshScheduling) resource
+/// caps CPU remeshing per frame, nearest any active camera first, and
+/// defers chunks out of view.
 ///
 /// # Example
 /// ```ignore
@@ -16,17 +66,232 @@ use crate::material::TriplanarVoxelMaterial;
 ///
 /// App::new()
 ///     .add_plugins(DefaultPlugins)
-///     .add_plugins(TriplanarVoxelPlugin)
+///     .add_plugins(TriplanarVoxelPlugin {
+///         shadows_enabled: false,
+///         auto_remesh: true,
+///         ..default()
+///     })
 ///     .run();
 /// ```
-pub struct TriplanarVoxelPlugin;
+#[derive(Clone, Debug)]
+pub struct TriplanarVoxelPlugin {
+    /// Whether the material renders in depth and normal prepasses.
+    /// Default: true
+    pub prepass_enabled: bool,
+    /// Whether chunks cast shadows.
+    /// Default: true
+    pub shadows_enabled: bool,
+    /// Asset path to load the main shader from instead of the embedded
+    /// copy, e.g. `"shaders/triplanar_extension.wgsl"`. With Bevy's
+    /// `file_watcher` feature, edits to that file hot-reload. The override
+    /// still `#import`s the embedded common library, so copy
+    /// `triplanar_extension.wgsl` from this crate's sources as a starting point.
+    /// Default: None
+    pub shader_override: Option<String>,
+    /// Asset path of a WGSL module replacing the default
+    /// `bevy_painter::user_hooks`, whose `material_hook` and `surface_hook`
+    /// inject custom per-material logic such as team colors or dissolve
+    /// effects. Copy `triplanar_hooks.wgsl` from this crate's sources as a
+    /// starting point. Texture sampling and blending are importable from
+    /// `bevy_painter::triplanar_sampling` for fully custom shaders.
+    /// Default: None
+    pub shader_hooks: Option<String>,
+    /// Inserted as the [`MaterialBlendSettings`](crate::material_field::MaterialBlendSettings)
+    /// resource unless the app already has one.
+    /// Default: [`MaterialBlendSettings::default`](crate::material_field::MaterialBlendSettings::default)
+    #[cfg(feature = "material_field")]
+    pub default_blend_settings: crate::material_field::MaterialBlendSettings,
+    /// Recompute material attributes of dirty chunks automatically.
+    /// Default: false
+    #[cfg(feature = "material_field")]
+    pub auto_remesh: bool,
+    /// Where automatic remeshing computes attributes. The GPU backend also
+    /// adds the [`GpuMeshingPlugin`](crate::gpu_meshing::GpuMeshingPlugin).
+    /// Default: [`AttributeBackend::Cpu`](crate::material_field::AttributeBackend::Cpu)
+    #[cfg(feature = "material_field")]
+    pub attribute_backend: crate::material_field::AttributeBackend,
+}
+
+impl Default for TriplanarVoxelPlugin {
+    fn default() -> Self {
+        Self {
+            prepass_enabled: true,
+            shadows_enabled: true,
+            shader_override: None,
+            shader_hooks: None,
+            #[cfg(feature = "material_field")]
+            default_blend_settings: default(),
+            #[cfg(feature = "material_field")]
+            auto_remesh: false,
+            #[cfg(feature = "material_field")]
+            attribute_backend: default(),
+        }
+    }
+}
 
 impl Plugin for TriplanarVoxelPlugin {
     fn build(&self, app: &mut App) {
         // Embed the shader into the binary
-        crate::material::register_embedded_assets(app);
+        crate::material::register_embedded_assets(
+            app,
+            self.shader_hooks.as_deref(),
+            self.shader_override.as_deref(),
+        );
+        app.init_asset::<TexturePalette>();
+        crate::palette::build_layer_replacement(app);
+        crate::palette::build_palette_streaming(app);
+        crate::mesh::build_mesh_swapping(app);
+        #[cfg(feature = "vox")]
+        app.init_asset::<crate::vox::VoxFile>()
+            .init_asset_loader::<crate::vox::VoxLoader>();
+        #[cfg(feature = "material_field")]
+        app.init_asset::<crate::material_field::MaterialTemplate>()
+            .init_resource::<crate::material_field::FieldPool>()
+            .init_resource::<crate::material_field::MaterialChunkIndex>()
+            .add_observer(crate::material_field::recycle_despawned_fields)
+            .add_observer(crate::material_field::notify_unloaded_chunks)
+            .add_message::<crate::material_field::PaintCommand>()
+            .add_message::<crate::material_field::MaterialChanged>()
+            .add_message::<crate::material_field::ParamPaintCommand>()
+            .add_message::<crate::material_field::PlaceTemplate>()
+            .add_systems(
+                PostUpdate,
+                (
+                    (
+                        crate::material_field::apply_paint_commands,
+                        crate::material_field::apply_param_paint_commands,
+                        crate::material_field::flush_paint_stroke
+                            .after(crate::material_field::apply_paint_commands)
+                            .after(crate::material_field::apply_param_paint_commands)
+                            .run_if(resource_exists::<crate::material_field::PaintStroke>),
+                        crate::material_field::place_templates,
+                        crate::material_field::apply_fluid_coupling
+                            .run_if(resource_exists::<crate::material_field::FluidCoupling>),
+                        crate::material_field::spawn_material_generation
+                            .run_if(resource_exists::<crate::material_field::MaterialGeneration>),
+                        crate::material_field::apply_generated_materials,
+                        crate::material_field::update_field_compression
+                            .run_if(resource_exists::<crate::material_field::FieldCompression>),
+                        crate::material_field::update_material_chunk_index
+                            .after(crate::material_field::update_field_compression),
+                        crate::material_field::apply_global_repaint
+                            .after(crate::material_field::update_material_chunk_index)
+                            .run_if(resource_exists::<crate::material_field::GlobalRepaintTask>),
+                        crate::material_field::update_material_simplification.run_if(
+                            resource_exists::<crate::material_field::MaterialSimplification>,
+                        ),
+                    )
+                        .in_set(PaintingSystems),
+                    crate::material_field::animate_material_transitions,
+                ),
+            );
+        #[cfg(feature = "material_field")]
+        self.build_material_field(app);
         app
             // Register material (includes shader loading)
-            .add_plugins(MaterialPlugin::<TriplanarVoxelMaterial>::default());
+            .add_plugins(MaterialPlugin::<TriplanarVoxelMaterial> {
+                prepass_enabled: self.prepass_enabled,
+                shadows_enabled: self.shadows_enabled,
+                ..default()
+            })
+            .add_plugins(MaterialPlugin::<TriplanarUnlitMaterial> {
+                prepass_enabled: self.prepass_enabled,
+                shadows_enabled: self.shadows_enabled,
+                ..default()
+            })
+            // Shells are extruded in the vertex shader, which the prepass
+            // and shadow passes don't know about
+            .add_plugins(MaterialPlugin::<TriplanarShellMaterial> {
+                prepass_enabled: false,
+                shadows_enabled: false,
+                ..default()
+            })
+            .add_plugins(ExtractResourcePlugin::<GlobalTriplanarOverrides>::default())
+            .init_resource::<GlobalTriplanarOverrides>()
+            .init_resource::<TriplanarDebugMode>()
+            .init_resource::<TriplanarQualitySettings>()
+            .init_resource::<TriplanarMaterialVariants>()
+            .init_resource::<TriplanarUnlitVariants>()
+            .init_resource::<TriplanarShellVariants>()
+            .register_type::<InstanceMaterialOverride>()
+            .register_type::<GlobalTriplanarOverrides>()
+            .register_type::<TriplanarDebugMode>()
+            .register_type::<TriplanarQualitySettings>()
+            .register_type::<TriplanarQualityTier>()
+            .register_type::<PaletteTexture>()
+            .register_type::<DisableNormalMaps>()
+            .register_type::<ForceBiplanar>()
+            .register_type::<MaterialReveal>()
+            .register_type::<SimplifiedMaterials>()
+            .register_type::<RenderUnlit>()
+            .register_type::<RenderGrassShells>()
+            .register_type::<BakeToStandardMaterial>()
+            .register_type::<BakeSettings>()
+            .add_systems(
+                PostUpdate,
+                (
+                    sync_instance_material_overrides,
+                    apply_global_triplanar_overrides.after(apply_triplanar_debug_mode),
+                    apply_triplanar_debug_mode,
+                    apply_triplanar_quality,
+                    bake_marked_chunks,
+                    (
+                        apply_entity_feature_overrides,
+                        sync_material_variants,
+                        apply_render_unlit,
+                        sync_unlit_variants,
+                        sync_grass_shells,
+                    )
+                        .chain(),
+                ),
+            );
+    }
+}
+
+/// The plugin's `PostUpdate` systems that paint or otherwise mark chunks
+/// [`MaterialFieldDirty`](crate::material_field::MaterialFieldDirty).
+#[cfg(feature = "material_field")]
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+struct PaintingSystems;
+
+#[cfg(feature = "material_field")]
+impl TriplanarVoxelPlugin {
+    /// Blend settings and automatic remeshing.
+    fn build_material_field(&self, app: &mut App) {
+        use crate::material_field::{
+            AttributeBackend, MaterialBlendSettings, animate_material_transitions,
+            remesh_dirty_chunks, sync_neighbor_materials,
+        };
+
+        if !app.world().contains_resource::<MaterialBlendSettings>() {
+            app.insert_resource(self.default_blend_settings.clone());
+        }
+        #[cfg(feature = "gpu_meshing")]
+        if self.attribute_backend == AttributeBackend::Gpu {
+            app.add_plugins(crate::gpu_meshing::GpuMeshingPlugin);
+        }
+        // Sync neighbors and remesh after everything that marks chunks dirty
+        // this frame, which includes updating the chunk index
+        app.add_systems(PostUpdate, sync_neighbor_materials.after(PaintingSystems));
+        if !self.auto_remesh {
+            return;
+        }
+        match self.attribute_backend {
+            AttributeBackend::Cpu => {
+                app.add_systems(
+                    PostUpdate,
+                    remesh_dirty_chunks
+                        .after(sync_neighbor_materials)
+                        .before(animate_material_transitions),
+                );
+            }
+            #[cfg(feature = "gpu_meshing")]
+            AttributeBackend::Gpu => {
+                app.add_systems(
+                    PostUpdate,
+                    crate::material_field::mark_dirty_chunks_gpu_meshed.after(PaintingSystems),
+                );
+            }
+        }
     }
 }
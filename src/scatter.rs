@@ -0,0 +1,392 @@
+//! Grass and detail scattering on painted surfaces.
+//!
+//! With the `scatter` feature, [`SurfaceScatterPlugin`] places detail
+//! meshes on chunk surfaces according to [`ScatterRule`]s: tufts of grass
+//! only where grass is painted, pebbles on gravel paths, no flowers on
+//! cliffs. Rules read the dominant material of each mesh triangle, so
+//! repainting a chunk and remeshing it rescatters on the next frame.
+//!
+//! Every instance of a rule shares one mesh and material, letting Bevy's
+//! automatic batching draw them instanced.
+//!
+//! # Example
+//! ```ignore
+//! app.add_plugins(SurfaceScatterPlugin).insert_resource(
+//!     SurfaceScatter::default().with_rule(
+//!         ScatterRule::new(GRASS, tuft_mesh, tuft_material)
+//!             .with_density(4.0)
+//!             .with_max_slope_degrees(30.0),
+//!     ),
+//! );
+//!
+//! commands.spawn((Mesh3d(chunk_mesh), MeshMaterial3d(terrain), ScatterSurface));
+//! ```
+
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+
+use crate::mesh::{dominant_triangle_material, material_attributes, triangles};
+
+/// Plugin scattering [`SurfaceScatter`] rules on [`ScatterSurface`] meshes.
+pub struct SurfaceScatterPlugin;
+
+impl Plugin for SurfaceScatterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SurfaceScatter>()
+            .register_type::<ScatterSurface>()
+            .add_systems(Update, scatter_surfaces);
+    }
+}
+
+/// Detail placed on triangles whose dominant material is `material_id`.
+#[derive(Clone, Debug)]
+pub struct ScatterRule {
+    pub material_id: u8,
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+    /// Instances per square world unit of matching surface.
+    /// Default: 1.0
+    pub density: f32,
+    /// Steepest surface, in radians from horizontal, that gets instances.
+    /// Default: 45 degrees
+    pub max_slope: f32,
+    /// Uniform scale picked per instance from this range.
+    /// Default: 1.0..=1.0
+    pub scale: (f32, f32),
+    /// Tilt instances to the surface normal instead of standing upright.
+    /// Default: false
+    pub align_to_normal: bool,
+}
+
+impl ScatterRule {
+    /// One upright instance per square unit on surfaces up to 45 degrees.
+    pub fn new(material_id: u8, mesh: Handle<Mesh>, material: Handle<StandardMaterial>) -> Self {
+        Self {
+            material_id,
+            mesh,
+            material,
+            density: 1.0,
+            max_slope: 45f32.to_radians(),
+            scale: (1.0, 1.0),
+            align_to_normal: false,
+        }
+    }
+
+    /// Set the instances per square world unit.
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Set the steepest surface that gets instances.
+    pub fn with_max_slope_degrees(mut self, degrees: f32) -> Self {
+        self.max_slope = degrees.to_radians();
+        self
+    }
+
+    /// Pick each instance's scale between `min` and `max`.
+    pub fn with_scale(mut self, min: f32, max: f32) -> Self {
+        self.scale = (min, max);
+        self
+    }
+
+    /// Tilt instances to the surface normal.
+    pub fn with_align_to_normal(mut self, align: bool) -> Self {
+        self.align_to_normal = align;
+        self
+    }
+}
+
+/// Scatter rules applied to every [`ScatterSurface`].
+///
+/// Changing the resource rescatters all surfaces.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SurfaceScatter {
+    pub rules: Vec<ScatterRule>,
+    /// Varies placement between worlds; the same seed and mesh always give
+    /// the same instances.
+    pub seed: u32,
+}
+
+impl SurfaceScatter {
+    /// Appends a rule.
+    pub fn with_rule(mut self, rule: ScatterRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Set the placement seed.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Instance transforms for `mesh`, in the mesh's local space, paired
+    /// with the index of the rule that placed them.
+    ///
+    /// Slopes are measured against local +Y. Returns nothing for meshes
+    /// without positions or material attributes.
+    pub fn placements(&self, mesh: &Mesh) -> Vec<(usize, Transform)> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return Vec::new();
+        };
+        let Some((ids, weights)) = material_attributes(mesh) else {
+            return Vec::new();
+        };
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+
+        let mut placements = Vec::new();
+        for (index, triangle) in triangles(mesh).into_iter().enumerate() {
+            if triangle.iter().any(|&v| v >= positions.len()) {
+                continue;
+            }
+            let Some(material_id) = dominant_triangle_material(triangle, ids, weights) else {
+                continue;
+            };
+            let [a, b, c] = triangle.map(|v| Vec3::from(positions[v]));
+            let cross = (b - a).cross(c - a);
+            let area = cross.length() * 0.5;
+            let Some(normal) = cross.try_normalize() else {
+                continue;
+            };
+            let slope = normal.y.clamp(-1.0, 1.0).acos();
+
+            for (rule_index, rule) in self.rules.iter().enumerate() {
+                if rule.material_id != material_id || slope > rule.max_slope {
+                    continue;
+                }
+                let mut random = ScatterRandom::new(self.seed, index as u32, rule_index as u32);
+                // Fractional expected counts round up with matching odds
+                let expected = area * rule.density.max(0.0);
+                let count = expected as u32 + u32::from(random.next_unit() < expected.fract());
+
+                for _ in 0..count {
+                    let (mut u, mut v) = (random.next_unit(), random.next_unit());
+                    if u + v > 1.0 {
+                        (u, v) = (1.0 - u, 1.0 - v);
+                    }
+                    let position = a + (b - a) * u + (c - a) * v;
+                    let up = if rule.align_to_normal {
+                        normal
+                    } else {
+                        Vec3::Y
+                    };
+                    let rotation = Quat::from_rotation_arc(Vec3::Y, up)
+                        * Quat::from_rotation_y(random.next_unit() * std::f32::consts::TAU);
+                    let scale = rule.scale.0 + (rule.scale.1 - rule.scale.0) * random.next_unit();
+                    placements.push((
+                        rule_index,
+                        Transform::from_translation(position)
+                            .with_rotation(rotation)
+                            .with_scale(Vec3::splat(scale)),
+                    ));
+                }
+            }
+        }
+        placements
+    }
+}
+
+/// Marks a mesh entity to receive [`SurfaceScatter`] instances.
+///
+/// Instances are spawned as [`ScatterInstance`] children, and replaced
+/// whenever the entity's mesh is modified, e.g. after repainting.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct ScatterSurface;
+
+/// A detail instance spawned on a [`ScatterSurface`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ScatterInstance {
+    /// Index of the placing rule in [`SurfaceScatter::rules`].
+    pub rule: usize,
+}
+
+/// Small deterministic random sequence per triangle and rule.
+struct ScatterRandom(u32);
+
+impl ScatterRandom {
+    fn new(seed: u32, triangle: u32, rule: u32) -> Self {
+        Self(seed ^ triangle.wrapping_mul(0x9e37_79b9) ^ rule.wrapping_mul(0x85eb_ca6b))
+    }
+
+    /// Next value in `0.0..1.0`.
+    fn next_unit(&mut self) -> f32 {
+        // lowbias32 over a Weyl sequence
+        self.0 = self.0.wrapping_add(0x6d2b_79f5);
+        let mut h = self.0;
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x7feb_352d);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x846c_a68b);
+        h ^= h >> 16;
+        (h >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+/// System respawning [`ScatterInstance`]s on new [`ScatterSurface`]s, on
+/// surfaces whose mesh changed, and on all surfaces when
+/// [`SurfaceScatter`] changes.
+#[allow(clippy::type_complexity)]
+pub fn scatter_surfaces(
+    mut commands: Commands,
+    scatter: Res<SurfaceScatter>,
+    mut mesh_events: MessageReader<AssetEvent<Mesh>>,
+    surfaces: Query<(Entity, Ref<Mesh3d>, Ref<ScatterSurface>, Option<&Children>)>,
+    instances: Query<(), With<ScatterInstance>>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    let modified: Vec<AssetId<Mesh>> = mesh_events
+        .read()
+        .filter_map(|event| match *event {
+            AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => Some(id),
+            _ => None,
+        })
+        .collect();
+
+    for (entity, mesh, surface, children) in &surfaces {
+        let stale = scatter.is_changed()
+            || surface.is_added()
+            || mesh.is_changed()
+            || modified.contains(&mesh.id());
+        if !stale {
+            continue;
+        }
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+
+        for child in children.into_iter().flatten() {
+            if instances.contains(*child) {
+                commands.entity(*child).despawn();
+            }
+        }
+        let placements = scatter.placements(mesh);
+        commands.entity(entity).with_children(|parent| {
+            for (rule, transform) in placements {
+                let rule_data = &scatter.rules[rule];
+                parent.spawn((
+                    Mesh3d(rule_data.mesh.clone()),
+                    MeshMaterial3d(rule_data.material.clone()),
+                    transform,
+                    ScatterInstance { rule },
+                ));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::{Indices, PrimitiveTopology};
+
+    use super::*;
+    use crate::mesh::{MeshTriplanarExt, VertexMaterialData};
+
+    const GRASS: u8 = 1;
+    const ROCK: u8 = 2;
+
+    /// A flat 10x10 grass quad next to a vertical 10x10 grass wall, and a
+    /// flat quad of `far` material.
+    fn terrain(far: u8) -> Mesh {
+        let quads = [
+            (
+                [0.0, 0.0, 0.0],
+                [10.0, 0.0, 0.0],
+                [10.0, 0.0, 10.0],
+                [0.0, 0.0, 10.0],
+                GRASS,
+            ),
+            (
+                [0.0, 0.0, 0.0],
+                [0.0, 10.0, 0.0],
+                [10.0, 10.0, 0.0],
+                [10.0, 0.0, 0.0],
+                GRASS,
+            ),
+            (
+                [20.0, 0.0, 0.0],
+                [30.0, 0.0, 0.0],
+                [30.0, 0.0, 10.0],
+                [20.0, 0.0, 10.0],
+                far,
+            ),
+        ];
+        let mut positions = Vec::new();
+        let mut materials = Vec::new();
+        let mut indices = Vec::new();
+        for (a, b, c, d, material) in quads {
+            let base = positions.len() as u32;
+            positions.extend([a, b, c, d]);
+            materials.extend([VertexMaterialData::single(material); 4]);
+            indices.extend([base, base + 2, base + 1, base, base + 3, base + 2]);
+        }
+        Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_indices(Indices::U32(indices))
+            .with_triplanar_materials(&materials)
+    }
+
+    #[test]
+    fn test_placements_follow_material_and_slope() {
+        let scatter = SurfaceScatter::default()
+            .with_rule(ScatterRule::new(GRASS, default(), default()).with_density(2.0));
+        let placements = scatter.placements(&terrain(ROCK));
+
+        // Only the flat grass quad: 100 square units at 2 per unit
+        assert!((180..=220).contains(&placements.len()));
+        for (rule, transform) in &placements {
+            assert_eq!(*rule, 0);
+            let p = transform.translation;
+            assert!(p.y.abs() < 1e-4);
+            assert!((0.0..=10.0).contains(&p.x) && (0.0..=10.0).contains(&p.z));
+        }
+        assert_eq!(scatter.placements(&terrain(ROCK)), placements);
+    }
+
+    #[test]
+    fn test_surface_rescatters_on_mesh_change() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .add_plugins(SurfaceScatterPlugin)
+            .insert_resource(
+                SurfaceScatter::default()
+                    .with_rule(ScatterRule::new(ROCK, default(), default()).with_density(1.0)),
+            );
+        let mesh = app
+            .world_mut()
+            .resource_mut::<Assets<Mesh>>()
+            .add(terrain(ROCK));
+        let chunk = app
+            .world_mut()
+            .spawn((Mesh3d(mesh.clone()), ScatterSurface))
+            .id();
+        app.update();
+
+        let count = |app: &mut App| {
+            app.world_mut()
+                .query::<&ScatterInstance>()
+                .iter(app.world())
+                .count()
+        };
+        let before = count(&mut app);
+        assert!(before > 50);
+        assert_eq!(
+            app.world().get::<Children>(chunk).map(|c| c.len()),
+            Some(before)
+        );
+
+        // Repaint everything to grass; rock instances disappear
+        let mut meshes = app.world_mut().resource_mut::<Assets<Mesh>>();
+        *meshes.get_mut(&mesh).unwrap() = terrain(GRASS);
+        app.update();
+        app.update();
+        assert_eq!(count(&mut app), 0);
+    }
+}
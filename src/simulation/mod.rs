@@ -0,0 +1,335 @@
+//! Runtime terrain aging driven by material rules.
+//!
+//! With the `simulation` feature, [`MaterialSimulationPlugin`] slowly
+//! rewrites chunk [`MaterialField`]s: grass creeping over bare dirt, snow
+//! melting at low altitude, moss growing in sheltered corners. Each frame
+//! visits a fixed budget of voxels round-robin across all chunks, so the
+//! cost stays flat however large the world is. Chunks that changed are
+//! marked [`MaterialFieldDirty`] for remeshing.
+//!
+//! # Example
+//! ```ignore
+//! app.add_plugins(MaterialSimulationPlugin)
+//!     .insert_resource(
+//!         MaterialSimulation::default()
+//!             .with_rule(Spread { from: GRASS, onto: DIRT, chance: 0.05 })
+//!             .with_rule(MeltBelow { material: SNOW, into: DIRT, height: 40.0, chance: 0.1 })
+//!             .with_rule(GrowInShade { onto: ROCK, into: MOSS, min_occlusion: 0.6, chance: 0.02 }),
+//!     );
+//! ```
+
+mod rules;
+
+use bevy::prelude::*;
+use bevy_sculpter::field::Field;
+use bevy_sculpter::prelude::{DensityField, DensityFieldMeshSize};
+
+use crate::material_field::brush::{grid_scale, voxel_noise};
+use crate::material_field::{FIELD_SIZE, FIELD_VOLUME, MaterialField, MaterialFieldDirty};
+
+pub use rules::{GrowInShade, MeltBelow, SimulationRule, Spread};
+
+/// Plugin ticking [`MaterialSimulation`] rules on every chunk with a
+/// [`MaterialField`] and a [`GlobalTransform`].
+pub struct MaterialSimulationPlugin;
+
+impl Plugin for MaterialSimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MaterialSimulation>()
+            .add_systems(Update, tick_material_simulation);
+    }
+}
+
+/// Rules and budget for the material simulation.
+#[derive(Resource)]
+pub struct MaterialSimulation {
+    /// Rules tried in order on each visited voxel; the first that returns a
+    /// material wins.
+    pub rules: Vec<Box<dyn SimulationRule>>,
+    /// Voxels visited per frame, across all chunks.
+    /// Default: 4096
+    pub voxel_budget: usize,
+    /// Stops ticking without removing the plugin.
+    /// Default: false
+    pub paused: bool,
+    /// Next voxel to visit, counting through all chunks in entity order.
+    cursor: usize,
+    /// Frames ticked so far, seeding per-voxel randomness.
+    tick: u32,
+}
+
+impl Default for MaterialSimulation {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            voxel_budget: 4096,
+            paused: false,
+            cursor: 0,
+            tick: 0,
+        }
+    }
+}
+
+impl MaterialSimulation {
+    /// Appends a rule.
+    pub fn with_rule(mut self, rule: impl SimulationRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Set the number of voxels visited per frame.
+    pub fn with_voxel_budget(mut self, voxel_budget: usize) -> Self {
+        self.voxel_budget = voxel_budget;
+        self
+    }
+}
+
+/// A voxel visited by the simulation, with read access to its chunk.
+#[derive(Clone, Copy)]
+pub struct SimulationCell<'a> {
+    /// Grid coordinates within the chunk.
+    pub grid_pos: UVec3,
+    /// World-space position of the voxel.
+    pub world_pos: Vec3,
+    /// Material currently stored in the voxel.
+    pub material_id: u8,
+    materials: &'a MaterialField,
+    density: Option<&'a DensityField>,
+    seed: u32,
+}
+
+/// Face-adjacent offsets.
+const FACE_NEIGHBORS: [IVec3; 6] = [
+    IVec3::NEG_X,
+    IVec3::X,
+    IVec3::NEG_Y,
+    IVec3::Y,
+    IVec3::NEG_Z,
+    IVec3::Z,
+];
+
+impl SimulationCell<'_> {
+    /// Material of the voxel at `offset`, or `None` outside the chunk.
+    pub fn neighbor(&self, offset: IVec3) -> Option<u8> {
+        self.materials.get_ivec3(self.grid_pos.as_ivec3() + offset)
+    }
+
+    /// Whether any face-adjacent voxel holds `material_id`.
+    pub fn touches(&self, material_id: u8) -> bool {
+        FACE_NEIGHBORS
+            .iter()
+            .any(|&offset| self.neighbor(offset) == Some(material_id))
+    }
+
+    /// Density at the voxel, if the chunk has a [`DensityField`].
+    pub fn density(&self) -> Option<f32> {
+        let p = self.grid_pos;
+        self.density.map(|density| density.get(p.x, p.y, p.z))
+    }
+
+    /// Whether the voxel is solid and borders air on at least one face.
+    ///
+    /// Always false without a [`DensityField`].
+    pub fn is_surface(&self) -> bool {
+        let Some(density) = self.density else {
+            return false;
+        };
+        let p = self.grid_pos.as_ivec3();
+        self.density().is_some_and(|d| d < 0.0)
+            && FACE_NEIGHBORS
+                .iter()
+                .any(|&offset| density.get_ivec3(p + offset).is_some_and(|d| d >= 0.0))
+    }
+
+    /// Whether nothing solid sits above the voxel within the chunk.
+    ///
+    /// Always false without a [`DensityField`].
+    pub fn sky_access(&self) -> bool {
+        let Some(density) = self.density else {
+            return false;
+        };
+        let p = self.grid_pos;
+        (p.y + 1..FIELD_SIZE.y).all(|y| density.get(p.x, y, p.z) >= 0.0)
+    }
+
+    /// Fraction (0.0 to 1.0) of the 26 surrounding voxels that are solid,
+    /// a cheap stand-in for ambient occlusion. Voxels outside the chunk
+    /// count as open.
+    ///
+    /// `None` without a [`DensityField`].
+    pub fn occlusion(&self) -> Option<f32> {
+        let density = self.density?;
+        let p = self.grid_pos.as_ivec3();
+        let mut solid = 0;
+        for z in -1..=1 {
+            for y in -1..=1 {
+                for x in -1..=1 {
+                    if (x, y, z) != (0, 0, 0)
+                        && density
+                            .get_ivec3(p + ivec3(x, y, z))
+                            .is_some_and(|d| d < 0.0)
+                    {
+                        solid += 1;
+                    }
+                }
+            }
+        }
+        Some(solid as f32 / 26.0)
+    }
+
+    /// True with probability `chance`, differing per voxel and frame.
+    pub fn chance(&self, chance: f32) -> bool {
+        let p = self.grid_pos;
+        voxel_noise(uvec3(p.x ^ self.seed, p.y, p.z ^ self.seed.rotate_left(16))) < chance
+    }
+}
+
+/// Visits the next [`MaterialSimulation::voxel_budget`] voxels and applies
+/// the first matching rule to each.
+pub fn tick_material_simulation(
+    mut commands: Commands,
+    mut simulation: ResMut<MaterialSimulation>,
+    mut chunks: Query<(
+        Entity,
+        &GlobalTransform,
+        &mut MaterialField,
+        Option<&DensityField>,
+    )>,
+    mesh_size: Option<Res<DensityFieldMeshSize>>,
+) {
+    if simulation.paused || simulation.rules.is_empty() {
+        return;
+    }
+    let mut order: Vec<Entity> = chunks.iter().map(|(entity, ..)| entity).collect();
+    if order.is_empty() {
+        return;
+    }
+    order.sort_unstable();
+
+    let grid_scale = grid_scale(mesh_size.as_deref());
+    let total = order.len() * FIELD_VOLUME;
+    let simulation = &mut *simulation;
+    simulation.tick = simulation.tick.wrapping_add(1);
+    let mut cursor = simulation.cursor % total;
+    let mut remaining = simulation.voxel_budget.min(total);
+    let mut changes = Vec::new();
+
+    while remaining > 0 {
+        let chunk = cursor / FIELD_VOLUME;
+        let start = cursor % FIELD_VOLUME;
+        let end = (start + remaining).min(FIELD_VOLUME);
+        let Ok((entity, transform, mut materials, density)) = chunks.get_mut(order[chunk]) else {
+            break;
+        };
+        let grid_to_world =
+            transform.affine() * bevy::math::Affine3A::from_scale(grid_scale.recip());
+        let seed = simulation.tick.wrapping_mul(0x9e37_79b9) ^ entity.index();
+
+        changes.clear();
+        for index in start..end {
+            let grid_pos = uvec3(
+                index as u32 % FIELD_SIZE.x,
+                index as u32 / FIELD_SIZE.x % FIELD_SIZE.y,
+                index as u32 / (FIELD_SIZE.x * FIELD_SIZE.y),
+            );
+            let cell = SimulationCell {
+                grid_pos,
+                world_pos: grid_to_world.transform_point3(grid_pos.as_vec3()),
+                material_id: materials.get(grid_pos.x, grid_pos.y, grid_pos.z),
+                materials: &materials,
+                density,
+                seed,
+            };
+            if let Some(material_id) = simulation
+                .rules
+                .iter()
+                .find_map(|rule| rule.apply(&cell))
+                .filter(|&material_id| material_id != cell.material_id)
+            {
+                changes.push((grid_pos, material_id));
+            }
+        }
+
+        // Applied after the pass, so growth advances one voxel per visit
+        if !changes.is_empty() {
+            for &(pos, material_id) in &changes {
+                materials.set(pos.x, pos.y, pos.z, material_id);
+            }
+            commands.entity(entity).insert(MaterialFieldDirty);
+        }
+
+        remaining -= end - start;
+        cursor = (cursor + end - start) % total;
+    }
+    simulation.cursor = cursor;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRT: u8 = 1;
+    const GRASS: u8 = 2;
+
+    /// Flat ground below grid height 16.
+    fn flat_ground() -> DensityField {
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    density.set(x, y, z, y as f32 - 15.5);
+                }
+            }
+        }
+        density
+    }
+
+    #[test]
+    fn test_cell_queries() {
+        let density = flat_ground();
+        let materials = MaterialField::filled(DIRT);
+        let cell = |grid_pos: UVec3| SimulationCell {
+            grid_pos,
+            world_pos: grid_pos.as_vec3(),
+            material_id: DIRT,
+            materials: &materials,
+            density: Some(&density),
+            seed: 0,
+        };
+
+        assert!(cell(uvec3(8, 15, 8)).is_surface());
+        assert!(cell(uvec3(8, 15, 8)).sky_access());
+        assert!(!cell(uvec3(8, 10, 8)).is_surface());
+        assert!(!cell(uvec3(8, 10, 8)).sky_access());
+        assert_eq!(cell(uvec3(8, 10, 8)).occlusion(), Some(1.0));
+        assert!((cell(uvec3(8, 15, 8)).occlusion().unwrap() - 17.0 / 26.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_tick_respects_budget_and_dirties() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(MaterialSimulationPlugin);
+        app.insert_resource(
+            MaterialSimulation::default()
+                .with_rule(|cell: &SimulationCell| (cell.material_id == DIRT).then_some(GRASS))
+                .with_voxel_budget(FIELD_VOLUME / 2),
+        );
+
+        let chunk = app
+            .world_mut()
+            .spawn((GlobalTransform::IDENTITY, MaterialField::filled(DIRT)))
+            .id();
+
+        app.update();
+        let world = app.world();
+        let field = world.get::<MaterialField>(chunk).unwrap();
+        assert_eq!(field.coverage(GRASS), 0.5);
+        assert!(world.get::<MaterialFieldDirty>(chunk).is_some());
+
+        // The next frame picks up where the last one stopped
+        app.update();
+        let field = app.world().get::<MaterialField>(chunk).unwrap();
+        assert_eq!(field.coverage(GRASS), 1.0);
+    }
+}
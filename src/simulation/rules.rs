@@ -0,0 +1,200 @@
+//! Built-in material simulation rules.
+
+use super::SimulationCell;
+
+/// Decides what a voxel becomes on a simulation tick.
+///
+/// Closures taking a [`SimulationCell`] are rules too.
+pub trait SimulationRule: Send + Sync {
+    /// New material for the voxel, or `None` to leave it to later rules.
+    fn apply(&self, cell: &SimulationCell) -> Option<u8>;
+}
+
+impl<F> SimulationRule for F
+where
+    F: Fn(&SimulationCell) -> Option<u8> + Send + Sync,
+{
+    fn apply(&self, cell: &SimulationCell) -> Option<u8> {
+        self(cell)
+    }
+}
+
+/// `from` creeps onto adjacent `onto` voxels with open sky above, like
+/// grass reclaiming bare dirt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spread {
+    pub from: u8,
+    pub onto: u8,
+    /// Probability per visit that a bordering voxel is taken over.
+    pub chance: f32,
+}
+
+impl SimulationRule for Spread {
+    fn apply(&self, cell: &SimulationCell) -> Option<u8> {
+        (cell.material_id == self.onto
+            && cell.touches(self.from)
+            && cell.sky_access()
+            && cell.chance(self.chance))
+        .then_some(self.from)
+    }
+}
+
+/// `material` turns into `into` below a world height, like snow melting
+/// in the valleys.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeltBelow {
+    pub material: u8,
+    pub into: u8,
+    /// World-space Y below which the material melts.
+    pub height: f32,
+    /// Probability per visit.
+    pub chance: f32,
+}
+
+impl SimulationRule for MeltBelow {
+    fn apply(&self, cell: &SimulationCell) -> Option<u8> {
+        (cell.material_id == self.material
+            && cell.world_pos.y < self.height
+            && cell.chance(self.chance))
+        .then_some(self.into)
+    }
+}
+
+/// `onto` surface voxels in sheltered spots turn into `into`, like moss
+/// growing in crevices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrowInShade {
+    pub onto: u8,
+    pub into: u8,
+    /// Minimum [`SimulationCell::occlusion`] for growth.
+    pub min_occlusion: f32,
+    /// Probability per visit.
+    pub chance: f32,
+}
+
+impl SimulationRule for GrowInShade {
+    fn apply(&self, cell: &SimulationCell) -> Option<u8> {
+        (cell.material_id == self.onto
+            && cell.is_surface()
+            && cell
+                .occlusion()
+                .is_some_and(|occlusion| occlusion >= self.min_occlusion)
+            && cell.chance(self.chance))
+        .then_some(self.into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_sculpter::field::Field;
+    use bevy_sculpter::prelude::DensityField;
+
+    use super::*;
+    use crate::material_field::MaterialField;
+
+    const DIRT: u8 = 1;
+    const GRASS: u8 = 2;
+    const SNOW: u8 = 3;
+    const MOSS: u8 = 4;
+
+    /// Ground below grid height 16 with a trench along z = 8.
+    fn trenched_ground() -> DensityField {
+        let mut density = DensityField::new();
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let floor = if z == 8 { 11.5 } else { 15.5 };
+                    density.set(x, y, z, y as f32 - floor);
+                }
+            }
+        }
+        density
+    }
+
+    fn cell<'a>(
+        materials: &'a MaterialField,
+        density: &'a DensityField,
+        grid_pos: UVec3,
+    ) -> SimulationCell<'a> {
+        SimulationCell {
+            grid_pos,
+            world_pos: grid_pos.as_vec3(),
+            material_id: materials.get(grid_pos.x, grid_pos.y, grid_pos.z),
+            materials,
+            density: Some(density),
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn test_spread_needs_neighbor_and_sky() {
+        let density = trenched_ground();
+        let mut materials = MaterialField::filled(DIRT);
+        materials.set(4, 15, 4, GRASS);
+        materials.set(4, 14, 4, GRASS);
+        let rule = Spread {
+            from: GRASS,
+            onto: DIRT,
+            chance: 1.0,
+        };
+
+        assert_eq!(
+            rule.apply(&cell(&materials, &density, uvec3(5, 15, 4))),
+            Some(GRASS)
+        );
+        // Buried dirt has no sky
+        assert_eq!(
+            rule.apply(&cell(&materials, &density, uvec3(5, 14, 4))),
+            None
+        );
+        // No grass next to it
+        assert_eq!(
+            rule.apply(&cell(&materials, &density, uvec3(9, 15, 4))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_melt_below_height() {
+        let density = trenched_ground();
+        let materials = MaterialField::filled(SNOW);
+        let rule = MeltBelow {
+            material: SNOW,
+            into: DIRT,
+            height: 12.0,
+            chance: 1.0,
+        };
+
+        assert_eq!(
+            rule.apply(&cell(&materials, &density, uvec3(0, 11, 8))),
+            Some(DIRT)
+        );
+        assert_eq!(
+            rule.apply(&cell(&materials, &density, uvec3(0, 15, 0))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_moss_grows_in_trench() {
+        let density = trenched_ground();
+        let materials = MaterialField::filled(DIRT);
+        let rule = GrowInShade {
+            onto: DIRT,
+            into: MOSS,
+            min_occlusion: 0.7,
+            chance: 1.0,
+        };
+
+        // Trench floor is walled in on two sides, open ground isn't
+        assert_eq!(
+            rule.apply(&cell(&materials, &density, uvec3(4, 11, 8))),
+            Some(MOSS)
+        );
+        assert_eq!(
+            rule.apply(&cell(&materials, &density, uvec3(4, 15, 20))),
+            None
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Mapping MagicaVoxel colour indices to palette materials.
+
+use bevy::color::Oklaba;
+use bevy::prelude::*;
+
+/// Maps each of the 256 `.vox` colour indices to a palette material ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoxPaletteMapping(pub [u8; 256]);
+
+impl Default for VoxPaletteMapping {
+    fn default() -> Self {
+        Self::uniform(0)
+    }
+}
+
+impl VoxPaletteMapping {
+    /// Maps every colour to the same material.
+    pub fn uniform(material_id: u8) -> Self {
+        Self([material_id; 256])
+    }
+
+    /// Builds a mapping from explicit `(colour index, material ID)` pairs.
+    ///
+    /// Colours not listed map to `default_material`.
+    pub fn from_pairs(default_material: u8, pairs: &[(u8, u8)]) -> Self {
+        let mut mapping = Self::uniform(default_material);
+        for &(color_index, material_id) in pairs {
+            mapping.0[color_index as usize] = material_id;
+        }
+        mapping
+    }
+
+    /// Maps each colour to the material with the closest average colour.
+    ///
+    /// Distances are measured in Oklab so the match follows perceived
+    /// colour rather than raw RGB. `material_colors[i]` is the average
+    /// colour of material `i`, e.g. from
+    /// [`TexturePalette::average_colors`](crate::palette::TexturePalette::average_colors).
+    ///
+    /// Without a `.vox` palette the file uses MagicaVoxel's built-in
+    /// default palette, which is matched instead. Without material colours
+    /// every colour maps to material 0.
+    pub fn nearest_color(palette: Option<&[[u8; 4]; 256]>, material_colors: &[Color]) -> Self {
+        let mut mapping = Self::default();
+        if material_colors.is_empty() {
+            return mapping;
+        }
+        let default_palette;
+        let palette = match palette {
+            Some(palette) => palette,
+            None => {
+                default_palette = default_palette();
+                &*default_palette
+            }
+        };
+
+        let materials: Vec<Oklaba> = material_colors.iter().map(|c| Oklaba::from(*c)).collect();
+        for (color_index, rgba) in palette.iter().enumerate() {
+            let color = Oklaba::from(Color::srgb_u8(rgba[0], rgba[1], rgba[2]));
+            let nearest = materials
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    oklab_distance_squared(color, **a)
+                        .total_cmp(&oklab_distance_squared(color, **b))
+                })
+                .map(|(id, _)| id)
+                .unwrap_or(0);
+            mapping.0[color_index] = nearest.min(u8::MAX as usize) as u8;
+        }
+        mapping
+    }
+
+    /// Material ID for a colour index.
+    #[inline]
+    pub fn get(&self, color_index: u8) -> u8 {
+        self.0[color_index as usize]
+    }
+}
+
+/// MagicaVoxel's built-in palette, used by files without an `RGBA` chunk.
+///
+/// Indexed by colour index like [`VoxFile::palette`](super::VoxFile::palette),
+/// stored as `0xAABBGGRR` as in the `.vox` format description.
+#[rustfmt::skip]
+const DEFAULT_PALETTE: [u32; 256] = [
+    0x00000000, 0xffffffff, 0xffccffff, 0xff99ffff, 0xff66ffff, 0xff33ffff, 0xff00ffff, 0xffffccff,
+    0xffccccff, 0xff99ccff, 0xff66ccff, 0xff33ccff, 0xff00ccff, 0xffff99ff, 0xffcc99ff, 0xff9999ff,
+    0xff6699ff, 0xff3399ff, 0xff0099ff, 0xffff66ff, 0xffcc66ff, 0xff9966ff, 0xff6666ff, 0xff3366ff,
+    0xff0066ff, 0xffff33ff, 0xffcc33ff, 0xff9933ff, 0xff6633ff, 0xff3333ff, 0xff0033ff, 0xffff00ff,
+    0xffcc00ff, 0xff9900ff, 0xff6600ff, 0xff3300ff, 0xff0000ff, 0xffffffcc, 0xffccffcc, 0xff99ffcc,
+    0xff66ffcc, 0xff33ffcc, 0xff00ffcc, 0xffffcccc, 0xffcccccc, 0xff99cccc, 0xff66cccc, 0xff33cccc,
+    0xff00cccc, 0xffff99cc, 0xffcc99cc, 0xff9999cc, 0xff6699cc, 0xff3399cc, 0xff0099cc, 0xffff66cc,
+    0xffcc66cc, 0xff9966cc, 0xff6666cc, 0xff3366cc, 0xff0066cc, 0xffff33cc, 0xffcc33cc, 0xff9933cc,
+    0xff6633cc, 0xff3333cc, 0xff0033cc, 0xffff00cc, 0xffcc00cc, 0xff9900cc, 0xff6600cc, 0xff3300cc,
+    0xff0000cc, 0xffffff99, 0xffccff99, 0xff99ff99, 0xff66ff99, 0xff33ff99, 0xff00ff99, 0xffffcc99,
+    0xffcccc99, 0xff99cc99, 0xff66cc99, 0xff33cc99, 0xff00cc99, 0xffff9999, 0xffcc9999, 0xff999999,
+    0xff669999, 0xff339999, 0xff009999, 0xffff6699, 0xffcc6699, 0xff996699, 0xff666699, 0xff336699,
+    0xff006699, 0xffff3399, 0xffcc3399, 0xff993399, 0xff663399, 0xff333399, 0xff003399, 0xffff0099,
+    0xffcc0099, 0xff990099, 0xff660099, 0xff330099, 0xff000099, 0xffffff66, 0xffccff66, 0xff99ff66,
+    0xff66ff66, 0xff33ff66, 0xff00ff66, 0xffffcc66, 0xffcccc66, 0xff99cc66, 0xff66cc66, 0xff33cc66,
+    0xff00cc66, 0xffff9966, 0xffcc9966, 0xff999966, 0xff669966, 0xff339966, 0xff009966, 0xffff6666,
+    0xffcc6666, 0xff996666, 0xff666666, 0xff336666, 0xff006666, 0xffff3366, 0xffcc3366, 0xff993366,
+    0xff663366, 0xff333366, 0xff003366, 0xffff0066, 0xffcc0066, 0xff990066, 0xff660066, 0xff330066,
+    0xff000066, 0xffffff33, 0xffccff33, 0xff99ff33, 0xff66ff33, 0xff33ff33, 0xff00ff33, 0xffffcc33,
+    0xffcccc33, 0xff99cc33, 0xff66cc33, 0xff33cc33, 0xff00cc33, 0xffff9933, 0xffcc9933, 0xff999933,
+    0xff669933, 0xff339933, 0xff009933, 0xffff6633, 0xffcc6633, 0xff996633, 0xff666633, 0xff336633,
+    0xff006633, 0xffff3333, 0xffcc3333, 0xff993333, 0xff663333, 0xff333333, 0xff003333, 0xffff0033,
+    0xffcc0033, 0xff990033, 0xff660033, 0xff330033, 0xff000033, 0xffffff00, 0xffccff00, 0xff99ff00,
+    0xff66ff00, 0xff33ff00, 0xff00ff00, 0xffffcc00, 0xffcccc00, 0xff99cc00, 0xff66cc00, 0xff33cc00,
+    0xff00cc00, 0xffff9900, 0xffcc9900, 0xff999900, 0xff669900, 0xff339900, 0xff009900, 0xffff6600,
+    0xffcc6600, 0xff996600, 0xff666600, 0xff336600, 0xff006600, 0xffff3300, 0xffcc3300, 0xff993300,
+    0xff663300, 0xff333300, 0xff003300, 0xffff0000, 0xffcc0000, 0xff990000, 0xff660000, 0xff330000,
+    0xff0000ee, 0xff0000dd, 0xff0000bb, 0xff0000aa, 0xff000088, 0xff000077, 0xff000055, 0xff000044,
+    0xff000022, 0xff000011, 0xff00ee00, 0xff00dd00, 0xff00bb00, 0xff00aa00, 0xff008800, 0xff007700,
+    0xff005500, 0xff004400, 0xff002200, 0xff001100, 0xffee0000, 0xffdd0000, 0xffbb0000, 0xffaa0000,
+    0xff880000, 0xff770000, 0xff550000, 0xff440000, 0xff220000, 0xff110000, 0xffeeeeee, 0xffdddddd,
+    0xffbbbbbb, 0xffaaaaaa, 0xff888888, 0xff777777, 0xff555555, 0xff444444, 0xff222222, 0xff111111,
+];
+
+/// RGBA colours of MagicaVoxel's built-in palette.
+fn default_palette() -> Box<[[u8; 4]; 256]> {
+    Box::new(DEFAULT_PALETTE.map(u32::to_le_bytes))
+}
+
+fn oklab_distance_squared(a: Oklaba, b: Oklaba) -> f32 {
+    let (dl, da, db) = (a.lightness - b.lightness, a.a - b.a, a.b - b.b);
+    dl * dl + da * da + db * db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_pairs() {
+        let mapping = VoxPaletteMapping::from_pairs(3, &[(1, 7), (200, 9)]);
+        assert_eq!(mapping.get(1), 7);
+        assert_eq!(mapping.get(200), 9);
+        assert_eq!(mapping.get(2), 3);
+    }
+
+    #[test]
+    fn test_nearest_color() {
+        let mut palette = Box::new([[0u8; 4]; 256]);
+        palette[1] = [250, 10, 10, 255];
+        palette[2] = [20, 200, 30, 255];
+        let materials = [Color::srgb(0.0, 0.8, 0.0), Color::srgb(0.9, 0.0, 0.0)];
+
+        let mapping = VoxPaletteMapping::nearest_color(Some(&palette), &materials);
+        assert_eq!(mapping.get(1), 1);
+        assert_eq!(mapping.get(2), 0);
+    }
+
+    #[test]
+    fn test_nearest_color_default_palette() {
+        // Without an RGBA chunk, colours come from MagicaVoxel's defaults:
+        // index 1 is white and 216 starts the pure red ramp
+        let materials = [Color::WHITE, Color::srgb(0.9, 0.0, 0.0)];
+
+        let mapping = VoxPaletteMapping::nearest_color(None, &materials);
+        assert_eq!(mapping.get(1), 0);
+        assert_eq!(mapping.get(216), 1);
+    }
+}
@@ -0,0 +1,196 @@
+//! MagicaVoxel (`.vox`) import.
+//!
+//! This module provides:
+//! - [`VoxLoader`]: Asset loader producing [`VoxFile`] assets
+//! - [`VoxPaletteMapping`]: Colour index to palette material mapping, either
+//!   user-supplied or matched automatically by average colour
+//! - [`VoxModel::to_fields`]: Conversion to a `DensityField` occupancy plus a
+//!   [`MaterialField`] for one chunk
+//!
+//! # Example
+//!
+//! ```ignore
+//! let vox = vox_files.get(&handle).unwrap();
+//! let colors = palette.average_colors(&images).unwrap();
+//! let mapping = VoxPaletteMapping::nearest_color(vox.palette.as_deref(), &colors);
+//!
+//! let (density, materials) = vox.models[0].to_fields(&mapping, UVec3::ZERO);
+//! commands.spawn((density, materials, MaterialFieldDirty));
+//! ```
+
+mod mapping;
+mod parser;
+
+use bevy::asset::{AssetLoader, LoadContext, io::Reader};
+use bevy::prelude::*;
+use bevy_sculpter::field::Field;
+use bevy_sculpter::prelude::DensityField;
+
+use crate::material_field::{FIELD_SIZE, MaterialField};
+
+pub use mapping::VoxPaletteMapping;
+pub use parser::{VoxError, VoxFile, VoxModel, parse_vox};
+
+/// Density written for occupied voxels.
+const SOLID_DENSITY: f32 = -1.0;
+
+/// Density written for empty voxels.
+const EMPTY_DENSITY: f32 = 1.0;
+
+/// Loads `.vox` files as [`VoxFile`] assets.
+#[derive(Default)]
+pub struct VoxLoader;
+
+impl AssetLoader for VoxLoader {
+    type Asset = VoxFile;
+    type Settings = ();
+    type Error = VoxError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<VoxFile, VoxError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_vox(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["vox"]
+    }
+}
+
+impl VoxModel {
+    /// Model size in Y-up coordinates.
+    pub fn size_y_up(&self) -> UVec3 {
+        uvec3(self.size.x, self.size.z, self.size.y)
+    }
+
+    /// Number of chunks needed to cover the model on each axis (Y-up).
+    pub fn chunk_count(&self) -> UVec3 {
+        (self.size_y_up() + FIELD_SIZE - UVec3::ONE) / FIELD_SIZE
+    }
+
+    /// Converts one chunk of the model to density and material fields.
+    ///
+    /// MagicaVoxel is Z-up; voxels are rotated to Bevy's Y-up as
+    /// `(x, z, size.y - 1 - y)` so the model keeps its handedness. `chunk`
+    /// selects which `FIELD_SIZE` block to extract, see
+    /// [`VoxModel::chunk_count`].
+    ///
+    /// Occupied voxels get a density of -1 and their mapped material. Empty
+    /// voxels next to a solid one copy its material, so vertices on the
+    /// surface never fall back to material 0.
+    pub fn to_fields(
+        &self,
+        mapping: &VoxPaletteMapping,
+        chunk: UVec3,
+    ) -> (DensityField, MaterialField) {
+        let origin = chunk * FIELD_SIZE;
+        let mut density = DensityField::new();
+        let mut materials = MaterialField::new();
+        let mut solid = vec![false; materials.as_slice().len()];
+
+        for pos in MaterialField::positions() {
+            density.set(pos.x, pos.y, pos.z, EMPTY_DENSITY);
+        }
+
+        for &(pos, color_index) in &self.voxels {
+            let y_up = uvec3(pos.x, pos.z, self.size.y - 1 - pos.y);
+            let Some(local) = y_up
+                .checked_sub(origin)
+                .filter(|p| p.cmplt(FIELD_SIZE).all())
+            else {
+                continue;
+            };
+            density.set(local.x, local.y, local.z, SOLID_DENSITY);
+            materials.set(local.x, local.y, local.z, mapping.get(color_index));
+            solid[index_of(local)] = true;
+        }
+
+        dilate_materials(&mut materials, &solid);
+        (density, materials)
+    }
+}
+
+impl VoxFile {
+    /// Converts the first model to fields for a single chunk at the origin.
+    ///
+    /// Returns `None` if the file has no models.
+    pub fn first_model_fields(
+        &self,
+        mapping: &VoxPaletteMapping,
+    ) -> Option<(DensityField, MaterialField)> {
+        self.models
+            .first()
+            .map(|model| model.to_fields(mapping, UVec3::ZERO))
+    }
+}
+
+#[inline]
+fn index_of(pos: UVec3) -> usize {
+    (pos.x + pos.y * FIELD_SIZE.x + pos.z * FIELD_SIZE.x * FIELD_SIZE.y) as usize
+}
+
+/// Copies solid materials into face-adjacent empty voxels.
+fn dilate_materials(materials: &mut MaterialField, solid: &[bool]) {
+    let source = materials.clone();
+    let max = FIELD_SIZE.as_ivec3();
+    for (pos, material) in materials.enumerate_coords_mut() {
+        if solid[index_of(pos)] {
+            continue;
+        }
+        let neighbor = [
+            IVec3::X,
+            IVec3::NEG_X,
+            IVec3::Y,
+            IVec3::NEG_Y,
+            IVec3::Z,
+            IVec3::NEG_Z,
+        ]
+        .into_iter()
+        .map(|offset| pos.as_ivec3() + offset)
+        .filter(|n| n.cmpge(IVec3::ZERO).all() && n.cmplt(max).all())
+        .map(|n| n.as_uvec3())
+        .find(|n| solid[index_of(*n)]);
+        if let Some(n) = neighbor {
+            *material = source.as_slice()[index_of(n)];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_fields() {
+        let vox = parse_vox(&parser::tests::sample_vox()).unwrap();
+        let model = &vox.models[0];
+        let mapping = VoxPaletteMapping::from_pairs(0, &[(1, 4), (200, 6)]);
+
+        assert_eq!(model.size_y_up(), uvec3(2, 4, 3));
+        assert_eq!(model.chunk_count(), UVec3::ONE);
+
+        let (density, materials) = model.to_fields(&mapping, UVec3::ZERO);
+        // vox (0, 0, 0) -> (0, 0, 2), vox (1, 2, 3) -> (1, 3, 0)
+        assert_eq!(density.get(0, 0, 2), SOLID_DENSITY);
+        assert_eq!(materials.get(0, 0, 2), 4);
+        assert_eq!(materials.get(1, 3, 0), 6);
+        assert_eq!(density.get(5, 5, 5), EMPTY_DENSITY);
+
+        // Dilated into the empty neighbour above
+        assert_eq!(density.get(0, 1, 2), EMPTY_DENSITY);
+        assert_eq!(materials.get(0, 1, 2), 4);
+        assert_eq!(materials.get(5, 5, 5), 0);
+    }
+
+    #[test]
+    fn test_chunk_outside_model_is_empty() {
+        let vox = parse_vox(&parser::tests::sample_vox()).unwrap();
+        let (density, _) = vox.models[0].to_fields(&VoxPaletteMapping::default(), UVec3::X);
+        assert!(density.iter().all(|&d| d == EMPTY_DENSITY));
+    }
+}
@@ -0,0 +1,210 @@
+//! MagicaVoxel `.vox` chunk parser.
+//!
+//! Only the chunks needed for geometry and colour are read (`SIZE`, `XYZI`,
+//! `RGBA`). Scene graph, material and camera chunks are skipped.
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+/// Errors that can occur when reading a `.vox` file.
+#[derive(Error, Debug)]
+pub enum VoxError {
+    #[error("Failed to read vox file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Not a MagicaVoxel file: missing 'VOX ' header")]
+    InvalidMagic,
+
+    #[error("Missing MAIN chunk")]
+    MissingMain,
+
+    #[error("Unexpected end of data while reading {context}")]
+    UnexpectedEof { context: &'static str },
+
+    #[error("XYZI chunk without a preceding SIZE chunk")]
+    VoxelsWithoutSize,
+
+    #[error("Voxel at ({x}, {y}, {z}) lies outside model size {size}")]
+    VoxelOutOfBounds { x: u8, y: u8, z: u8, size: UVec3 },
+}
+
+/// A single model from a `.vox` file, in MagicaVoxel's Z-up coordinates.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VoxModel {
+    /// Model dimensions (X, Y, Z with Z up).
+    pub size: UVec3,
+    /// Occupied voxels as `(position, colour index)`. Colour index 0 is never used.
+    pub voxels: Vec<(UVec3, u8)>,
+}
+
+/// Parsed contents of a `.vox` file.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct VoxFile {
+    /// All models in file order.
+    pub models: Vec<VoxModel>,
+    /// RGBA colours indexed by voxel colour index (entry 0 is unused).
+    ///
+    /// `None` if the file has no `RGBA` chunk and relies on MagicaVoxel's
+    /// built-in default palette.
+    pub palette: Option<Box<[[u8; 4]; 256]>>,
+}
+
+/// A chunk's id, content and children bytes.
+type RawChunk<'a> = ([u8; 4], &'a [u8], &'a [u8]);
+
+/// Little-endian cursor over the file bytes.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize, context: &'static str) -> Result<&'a [u8], VoxError> {
+        if self.bytes.len() < len {
+            return Err(VoxError::UnexpectedEof { context });
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self, context: &'static str) -> Result<u32, VoxError> {
+        let bytes = self.take(4, context)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn chunk(&mut self) -> Result<RawChunk<'a>, VoxError> {
+        let id = self.take(4, "chunk id")?;
+        let content_len = self.u32("chunk header")? as usize;
+        let children_len = self.u32("chunk header")? as usize;
+        let content = self.take(content_len, "chunk content")?;
+        let children = self.take(children_len, "chunk children")?;
+        Ok(([id[0], id[1], id[2], id[3]], content, children))
+    }
+}
+
+/// Parses a `.vox` file from memory.
+pub fn parse_vox(bytes: &[u8]) -> Result<VoxFile, VoxError> {
+    let mut cursor = Cursor { bytes };
+    if cursor.take(4, "header")? != b"VOX " {
+        return Err(VoxError::InvalidMagic);
+    }
+    // Version (150 or 200); the chunks we read haven't changed between them
+    cursor.u32("version")?;
+
+    let (id, _, children) = cursor.chunk()?;
+    if &id != b"MAIN" {
+        return Err(VoxError::MissingMain);
+    }
+
+    let mut models = Vec::new();
+    let mut palette = None;
+    let mut pending_size = None;
+    let mut cursor = Cursor { bytes: children };
+
+    while !cursor.bytes.is_empty() {
+        let (id, content, _) = cursor.chunk()?;
+        let mut content = Cursor { bytes: content };
+        match &id {
+            b"SIZE" => {
+                let x = content.u32("SIZE")?;
+                let y = content.u32("SIZE")?;
+                let z = content.u32("SIZE")?;
+                pending_size = Some(uvec3(x, y, z));
+            }
+            b"XYZI" => {
+                let size = pending_size.take().ok_or(VoxError::VoxelsWithoutSize)?;
+                let count = content.u32("XYZI")? as usize;
+                let data = content.take(count * 4, "XYZI")?;
+                let mut voxels = Vec::with_capacity(count);
+                for voxel in data.chunks_exact(4) {
+                    let (x, y, z) = (voxel[0], voxel[1], voxel[2]);
+                    let pos = uvec3(x as u32, y as u32, z as u32);
+                    if pos.cmpge(size).any() {
+                        return Err(VoxError::VoxelOutOfBounds { x, y, z, size });
+                    }
+                    voxels.push((pos, voxel[3]));
+                }
+                models.push(VoxModel { size, voxels });
+            }
+            b"RGBA" => {
+                let data = content.take(256 * 4, "RGBA")?;
+                // Colour index i is stored at entry i - 1
+                let mut colors = Box::new([[0u8; 4]; 256]);
+                for (i, rgba) in data.chunks_exact(4).take(255).enumerate() {
+                    colors[i + 1] = [rgba[0], rgba[1], rgba[2], rgba[3]];
+                }
+                palette = Some(colors);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(VoxFile { models, palette })
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], content: &[u8], children: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.extend((content.len() as u32).to_le_bytes());
+        out.extend((children.len() as u32).to_le_bytes());
+        out.extend(content);
+        out.extend(children);
+        out
+    }
+
+    /// A 2x3x4 model with two voxels and a palette where index i is (i, 0, 0).
+    pub(in crate::vox) fn sample_vox() -> Vec<u8> {
+        let mut size = Vec::new();
+        for v in [2u32, 3, 4] {
+            size.extend(v.to_le_bytes());
+        }
+        let mut xyzi = 2u32.to_le_bytes().to_vec();
+        xyzi.extend([0, 0, 0, 1, 1, 2, 3, 200]);
+        let rgba: Vec<u8> = (1..=256u32).flat_map(|i| [i as u8, 0, 0, 255]).collect();
+
+        let mut children = chunk(b"SIZE", &size, &[]);
+        children.extend(chunk(b"XYZI", &xyzi, &[]));
+        children.extend(chunk(b"nTRN", &[1, 2, 3], &[]));
+        children.extend(chunk(b"RGBA", &rgba, &[]));
+
+        let mut file = b"VOX ".to_vec();
+        file.extend(150u32.to_le_bytes());
+        file.extend(chunk(b"MAIN", &[], &children));
+        file
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let vox = parse_vox(&sample_vox()).unwrap();
+
+        assert_eq!(vox.models.len(), 1);
+        assert_eq!(vox.models[0].size, uvec3(2, 3, 4));
+        assert_eq!(
+            vox.models[0].voxels,
+            vec![(uvec3(0, 0, 0), 1), (uvec3(1, 2, 3), 200)]
+        );
+        let palette = vox.palette.unwrap();
+        assert_eq!(palette[1], [1, 0, 0, 255]);
+        assert_eq!(palette[255], [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        assert!(matches!(
+            parse_vox(b"PNG 1234"),
+            Err(VoxError::InvalidMagic)
+        ));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bytes = sample_vox();
+        assert!(matches!(
+            parse_vox(&bytes[..bytes.len() - 10]),
+            Err(VoxError::UnexpectedEof { .. })
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+//! Headless render regression tests.
+//!
+//! Renders small reference scenes to an offscreen target and compares them
+//! with the golden images in `tests/golden/`, so shader refactors can't
+//! silently break triplanar projection or material blending. Needs a GPU
+//! adapter, so the tests only build with the `render_tests` feature:
+//!
+//! ```sh
+//! cargo test --features render_tests --test render_reference
+//! ```
+//!
+//! The fog tests compare renders with each other instead, so they need no
+//! golden images.
+//!
+//! A missing golden image fails its test. To add one, or to replace them
+//! after an intentional visual change, rerun with `BEVY_PAINTER_BLESS=1` and
+//! commit the written PNGs.
+
+#![cfg(feature = "render_tests")]
+
+use std::path::PathBuf;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::RenderTarget;
+use bevy::core_pipeline::prepass::{DeferredPrepass, DepthPrepass};
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
+use bevy::log::LogPlugin;
+use bevy::pbr::ExtendedMaterial;
+use bevy::prelude::*;
+use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use bevy_painter::prelude::*;
+
+/// Output size. 256 RGBA8 texels make rows already 256-byte aligned, so the
+/// readback has no row padding.
+const SIZE: u32 = 256;
+
+/// Frames rendered before capturing, so pipelines and textures are ready.
+const WARMUP_FRAMES: u32 = 30;
+
+/// Give up if no readback arrives within this many frames.
+const MAX_FRAMES: u32 = 600;
+
+/// Per-channel difference above which a pixel counts as changed.
+const CHANNEL_TOLERANCE: u8 = 8;
+
+/// Fraction of changed pixels allowed, absorbing driver differences.
+const MAX_CHANGED_FRACTION: f32 = 0.01;
+
+/// sRGB fog color of [`Scene::fog`].
+const FOG_COLOR: [u8; 3] = [128, 153, 179];
+
+#[derive(Resource, Default)]
+struct Captured(Option<Vec<u8>>);
+
+/// Variations on the default reference scene.
+#[derive(Clone, Copy, Default)]
+struct Scene {
+    /// Add linear distance fog to the camera.
+    fog: bool,
+    /// Render with the deferred pipeline instead of forward.
+    deferred: bool,
+    /// `fog_multiplier` of every palette material, 1.0 when `None`.
+    fog_multiplier: Option<f32>,
+}
+
+/// A 2-layer checker palette: red/white for material 0, blue/black for 1.
+fn palette(images: &mut Assets<Image>) -> Handle<Image> {
+    let layer = 32u32;
+    let colors = [
+        ([220, 40, 40, 255], [255, 255, 255, 255]),
+        ([40, 60, 220, 255], [0, 0, 0, 255]),
+    ];
+    let mut data = Vec::new();
+    for (a, b) in colors {
+        for y in 0..layer {
+            for x in 0..layer {
+                let even = (x / 8 + y / 8) % 2 == 0;
+                data.extend_from_slice(if even { &a } else { &b });
+            }
+        }
+    }
+    images.add(Image::new(
+        Extent3d {
+            width: layer,
+            height: layer,
+            depth_or_array_layers: colors.len() as u32,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
+
+/// Renders the mesh built by `mesh` and returns tightly packed RGBA8 pixels.
+fn render(mesh: fn() -> Mesh) -> Vec<u8> {
+    render_scene(mesh, Scene::default())
+}
+
+/// [`render`] with scene variations.
+fn render_scene(mesh: fn() -> Mesh, scene: Scene) -> Vec<u8> {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .disable::<WinitPlugin>()
+            // Tests run in parallel and only one global logger can be set
+            .disable::<LogPlugin>(),
+    )
+    .add_plugins(TriplanarVoxelPlugin::default())
+    .init_resource::<Captured>();
+
+    let world = app.world_mut();
+    let target = world
+        .resource_mut::<Assets<Image>>()
+        .add(Image::new_target_texture(
+            SIZE,
+            SIZE,
+            TextureFormat::Rgba8UnormSrgb,
+        ));
+    let albedo = palette(&mut world.resource_mut::<Assets<Image>>());
+    let material = world
+        .resource_mut::<Assets<TriplanarVoxelMaterial>>()
+        .add(ExtendedMaterial {
+            base: StandardMaterial {
+                perceptual_roughness: 0.9,
+                ..default()
+            },
+            extension: TriplanarExtension::new(albedo)
+                .with_texture_scale(0.5)
+                .with_material_properties(vec![
+                    MaterialPropertiesGpu {
+                        fog_multiplier: scene.fog_multiplier.unwrap_or(1.0),
+                        ..default()
+                    };
+                    2
+                ]),
+        });
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(mesh());
+
+    world.spawn((
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        Transform::from_rotation(Quat::from_rotation_y(0.6)),
+    ));
+    let mut camera = world.spawn((
+        Camera3d::default(),
+        Camera {
+            target: RenderTarget::Image(target.clone().into()),
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        Msaa::Off,
+        Tonemapping::None,
+        Transform::from_xyz(2.5, 2.0, 3.5).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    if scene.fog {
+        camera.insert(DistanceFog {
+            color: Color::srgb_u8(FOG_COLOR[0], FOG_COLOR[1], FOG_COLOR[2]),
+            falloff: FogFalloff::Linear {
+                start: 2.0,
+                end: 8.0,
+            },
+            ..default()
+        });
+    }
+    if scene.deferred {
+        camera.insert((DepthPrepass, DeferredPrepass));
+    }
+    world.spawn((
+        DirectionalLight {
+            illuminance: 8000.0,
+            shadows_enabled: false,
+            ..default()
+        },
+        Transform::from_xyz(3.0, 5.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+    world.spawn(Readback::texture(target)).observe(
+        |event: On<ReadbackComplete>, mut captured: ResMut<Captured>| {
+            captured.0 = Some(event.data.clone());
+        },
+    );
+
+    app.finish();
+    app.cleanup();
+    for frame in 0..MAX_FRAMES {
+        app.update();
+        if frame < WARMUP_FRAMES {
+            continue;
+        }
+        if let Some(pixels) = app.world_mut().resource_mut::<Captured>().0.take() {
+            return pixels;
+        }
+    }
+    panic!("no readback after {MAX_FRAMES} frames");
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+fn to_image(pixels: Vec<u8>) -> Image {
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::MAIN_WORLD,
+    )
+}
+
+/// Fraction of pixels differing by more than [`CHANNEL_TOLERANCE`].
+fn changed_fraction(a: &[u8], b: &[u8]) -> f32 {
+    let changed = a
+        .chunks_exact(4)
+        .zip(b.chunks_exact(4))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(*b)
+                .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    changed as f32 / (SIZE * SIZE) as f32
+}
+
+/// Mean distance to [`FOG_COLOR`] of `pixels`, over the pixels the mesh
+/// covers in `clear`. The background is cleared to black and never fogged.
+fn mean_fog_distance(clear: &[u8], pixels: &[u8]) -> f32 {
+    let (sum, count) = clear
+        .chunks_exact(4)
+        .zip(pixels.chunks_exact(4))
+        .filter(|(clear, _)| clear[..3] != [0, 0, 0])
+        .fold((0.0, 0u32), |(sum, count), (_, pixel)| {
+            let distance = pixel
+                .iter()
+                .zip(FOG_COLOR)
+                .map(|(&a, b)| (a as f32 - b as f32).powi(2))
+                .sum::<f32>()
+                .sqrt();
+            (sum + distance, count + 1)
+        });
+    assert!(count > 0, "the mesh covers no pixels");
+    sum / count as f32
+}
+
+/// Renders the cube with and without fog and checks fog pulled it toward
+/// [`FOG_COLOR`].
+fn assert_fog_applied(deferred: bool) {
+    let clear = render_scene(
+        cube,
+        Scene {
+            deferred,
+            ..default()
+        },
+    );
+    let fogged = render_scene(
+        cube,
+        Scene {
+            fog: true,
+            deferred,
+            ..default()
+        },
+    );
+    assert!(
+        changed_fraction(&clear, &fogged) > MAX_CHANGED_FRACTION,
+        "fog left the render unchanged (deferred: {deferred})"
+    );
+    assert!(
+        mean_fog_distance(&clear, &fogged) < mean_fog_distance(&clear, &clear),
+        "fog didn't move the mesh toward the fog color (deferred: {deferred})"
+    );
+}
+
+/// Compares `pixels` with the named golden image, or overwrites it when
+/// blessing.
+fn assert_matches_golden(name: &str, pixels: Vec<u8>) {
+    assert_eq!(pixels.len(), (SIZE * SIZE * 4) as usize);
+    let path = golden_path(name);
+
+    if std::env::var_os("BEVY_PAINTER_BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        to_image(pixels)
+            .try_into_dynamic()
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        return;
+    }
+    assert!(
+        path.exists(),
+        "{name}: missing golden image {}, rerun with BEVY_PAINTER_BLESS=1 to create it",
+        path.display()
+    );
+
+    let golden = Image::from_buffer(
+        &std::fs::read(&path).unwrap(),
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::MAIN_WORLD,
+    )
+    .unwrap();
+    let golden = golden.data.expect("golden image has no data");
+    assert_eq!(golden.len(), pixels.len(), "{name}: size changed");
+
+    let fraction = changed_fraction(&pixels, &golden);
+    assert!(
+        fraction <= MAX_CHANGED_FRACTION,
+        "{name}: {:.2}% of pixels differ from {}",
+        fraction * 100.0,
+        path.display()
+    );
+}
+
+fn cube() -> Mesh {
+    Cuboid::new(2.0, 2.0, 2.0)
+        .mesh()
+        .build()
+        .with_uniform_material(0)
+}
+
+#[test]
+fn minimal_cube() {
+    let pixels = render(cube);
+    assert_matches_golden("minimal_cube", pixels);
+}
+
+#[test]
+fn two_material_blend() {
+    let pixels = render(|| {
+        let mesh = Cuboid::new(2.0, 2.0, 2.0).mesh().build();
+        // Fade from material 0 at -X to material 1 at +X
+        let data: Vec<VertexMaterialData> = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|positions| positions.as_float3())
+            .unwrap()
+            .iter()
+            .map(|p| VertexMaterialData::blend2(0, 1, (p[0] + 1.0) / 2.0))
+            .collect();
+        mesh.with_triplanar_materials(&data)
+    });
+    assert_matches_golden("two_material_blend", pixels);
+}
+
+#[test]
+fn distance_fog_forward() {
+    assert_fog_applied(false);
+}
+
+#[test]
+fn distance_fog_deferred() {
+    assert_fog_applied(true);
+}
+
+#[test]
+fn fog_multiplier_zero_ignores_fog() {
+    for deferred in [false, true] {
+        let clear = render_scene(
+            cube,
+            Scene {
+                deferred,
+                ..default()
+            },
+        );
+        let fogged = render_scene(
+            cube,
+            Scene {
+                fog: true,
+                deferred,
+                fog_multiplier: Some(0.0),
+            },
+        );
+        assert!(
+            changed_fraction(&clear, &fogged) <= MAX_CHANGED_FRACTION,
+            "fog covered a material with fog_multiplier 0 (deferred: {deferred})"
+        );
+    }
+}